@@ -152,6 +152,11 @@ static TIMECODE_CAPS: Lazy<gst::Caps> =
 static TIMESTAMP_CAPS: Lazy<gst::Caps> =
     Lazy::new(|| gst::Caps::new_empty_simple("timestamp/x-ndi-timestamp"));
 
+/// Caps feature of GL textures, which the sink elements accept as mapping
+/// them for reading downloads them into system memory
+#[cfg(feature = "sink")]
+const GL_MEMORY_FEATURE: &str = "memory:GLMemory";
+
 gst::plugin_define!(
     ndi,
     env!("CARGO_PKG_DESCRIPTION"),
@@ -6,6 +6,8 @@ use std::ffi;
 use std::fmt;
 use std::mem;
 use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use byte_slice_cast::*;
 
@@ -244,16 +246,41 @@ impl<'a> RecvBuilder<'a> {
             if ptr.is_null() {
                 None
             } else {
-                Some(RecvInstance(ptr::NonNull::new_unchecked(ptr)))
+                Some(RecvInstance(Arc::new(RecvInstancePtr {
+                    ptr: ptr::NonNull::new_unchecked(ptr),
+                    wrapped_video_frames: AtomicUsize::new(0),
+                })))
             }
         }
     }
 }
 
+/// Shared between the receiver and the frames received from it, which
+/// can outlive it when wrapped into buffers
 #[derive(Debug, Clone)]
-pub struct RecvInstance(ptr::NonNull<::std::os::raw::c_void>);
+pub struct RecvInstance(Arc<RecvInstancePtr>);
 
-unsafe impl Send for RecvInstance {}
+/// Maximum number of received video frames wrapped into buffers at once.
+/// The SDK only has a small pool of frames and stops receiving when they
+/// are all held downstream, further frames are copied instead.
+const MAX_WRAPPED_VIDEO_FRAMES: usize = 8;
+
+#[derive(Debug)]
+struct RecvInstancePtr {
+    ptr: ptr::NonNull<::std::os::raw::c_void>,
+    wrapped_video_frames: AtomicUsize,
+}
+
+// SAFETY: the SDK receive functions are thread-safe, and the instance is
+// only destroyed once the receiver and all its frames are gone
+unsafe impl Send for RecvInstancePtr {}
+unsafe impl Sync for RecvInstancePtr {}
+
+impl Drop for RecvInstancePtr {
+    fn drop(&mut self) {
+        unsafe { NDIlib_recv_destroy(self.ptr.as_ptr() as *mut _) }
+    }
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct ReceiveError;
@@ -282,25 +309,40 @@ impl RecvInstance {
         }
     }
 
+    fn as_ptr(&self) -> *mut ::std::os::raw::c_void {
+        self.0.ptr.as_ptr()
+    }
+
+    /// Accounts for one more wrapped video frame, fails if too many
+    /// of them are outstanding already
+    fn reserve_wrapped_video_frame(&self) -> bool {
+        self.0
+            .wrapped_video_frames
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < MAX_WRAPPED_VIDEO_FRAMES).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
     pub fn set_tally(&self, tally: &Tally) -> bool {
-        unsafe { NDIlib_recv_set_tally(self.0.as_ptr(), &tally.0) }
+        unsafe { NDIlib_recv_set_tally(self.as_ptr(), &tally.0) }
     }
 
     pub fn send_metadata(&self, metadata: &MetadataFrame) -> bool {
-        unsafe { NDIlib_recv_send_metadata(self.0.as_ptr(), metadata.as_ptr()) }
+        unsafe { NDIlib_recv_send_metadata(self.as_ptr(), metadata.as_ptr()) }
     }
 
     pub fn get_queue(&self) -> Queue {
         unsafe {
             let mut queue = mem::MaybeUninit::uninit();
-            NDIlib_recv_get_queue(self.0.as_ptr(), queue.as_mut_ptr());
+            NDIlib_recv_get_queue(self.as_ptr(), queue.as_mut_ptr());
             Queue(queue.assume_init())
         }
     }
 
     pub fn capture(&self, timeout_in_ms: u32) -> Result<Option<Frame>, ReceiveError> {
         unsafe {
-            let ptr = self.0.as_ptr();
+            let ptr = self.as_ptr();
 
             let mut video_frame = mem::zeroed();
             let mut audio_frame = mem::zeroed();
@@ -318,9 +360,12 @@ impl RecvInstance {
                 NDIlib_frame_type_e::NDIlib_frame_type_audio => Ok(Some(Frame::Audio(
                     AudioFrame::BorrowedRecv(audio_frame, self),
                 ))),
-                NDIlib_frame_type_e::NDIlib_frame_type_video => Ok(Some(Frame::Video(
-                    VideoFrame::BorrowedRecv(video_frame, self),
-                ))),
+                NDIlib_frame_type_e::NDIlib_frame_type_video => {
+                    Ok(Some(Frame::Video(VideoFrame::Recv(RecvVideoFrame {
+                        frame: video_frame,
+                        recv: self.clone(),
+                    }))))
+                }
                 NDIlib_frame_type_e::NDIlib_frame_type_metadata => Ok(Some(Frame::Metadata(
                     MetadataFrame::Borrowed(metadata_frame, self),
                 ))),
@@ -331,12 +376,6 @@ impl RecvInstance {
     }
 }
 
-impl Drop for RecvInstance {
-    fn drop(&mut self) {
-        unsafe { NDIlib_recv_destroy(self.0.as_ptr() as *mut _) }
-    }
-}
-
 #[derive(Debug)]
 pub struct SendBuilder<'a> {
     ndi_name: &'a str,
@@ -403,6 +442,12 @@ impl SendInstance {
             NDIlib_send_send_audio_v3(self.0.as_ptr(), frame.as_ptr());
         }
     }
+
+    pub fn send_metadata(&mut self, frame: &MetadataFrame) {
+        unsafe {
+            NDIlib_send_send_metadata(self.0.as_ptr(), frame.as_ptr());
+        }
+    }
 }
 
 impl Drop for SendInstance {
@@ -451,7 +496,7 @@ pub enum Frame<'a> {
 #[derive(Debug)]
 pub enum VideoFrame<'a> {
     //Owned(NDIlib_video_frame_v2_t, Option<ffi::CString>, Option<Vec<u8>>),
-    BorrowedRecv(NDIlib_video_frame_v2_t, &'a RecvInstance),
+    Recv(RecvVideoFrame),
     BorrowedGst(
         NDIlib_video_frame_v2_t,
         &'a gst_video::VideoFrameRef<&'a gst::BufferRef>,
@@ -472,57 +517,50 @@ impl std::error::Error for TryFromVideoFrameError {}
 impl<'a> VideoFrame<'a> {
     pub fn xres(&self) -> i32 {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                frame.xres
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame.xres,
         }
     }
 
     pub fn yres(&self) -> i32 {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                frame.yres
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame.yres,
         }
     }
 
     pub fn fourcc(&self) -> NDIlib_FourCC_video_type_e {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                frame.FourCC
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame.FourCC,
         }
     }
 
     pub fn frame_rate(&self) -> (i32, i32) {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                (frame.frame_rate_N, frame.frame_rate_D)
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => (frame.frame_rate_N, frame.frame_rate_D),
         }
     }
 
     pub fn picture_aspect_ratio(&self) -> f32 {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                frame.picture_aspect_ratio
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame.picture_aspect_ratio,
         }
     }
 
     pub fn frame_format_type(&self) -> NDIlib_frame_format_type_e {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                frame.frame_format_type
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame.frame_format_type,
         }
     }
 
     pub fn timecode(&self) -> i64 {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                frame.timecode
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame.timecode,
         }
     }
 
@@ -559,7 +597,7 @@ impl<'a> VideoFrame<'a> {
             return unsafe {
                 use std::slice;
                 match self {
-                    VideoFrame::BorrowedRecv(ref frame, _)
+                    VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
                     | VideoFrame::BorrowedGst(ref frame, _) => Some(slice::from_raw_parts(
                         frame.p_data as *const u8,
                         frame_size as usize,
@@ -582,7 +620,7 @@ impl<'a> VideoFrame<'a> {
             return unsafe {
                 use std::slice;
                 match self {
-                    VideoFrame::BorrowedRecv(ref frame, _)
+                    VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
                     | VideoFrame::BorrowedGst(ref frame, _) => Some(slice::from_raw_parts(
                         frame.p_data as *const u8,
                         frame.line_stride_or_data_size_in_bytes as usize,
@@ -619,12 +657,11 @@ impl<'a> VideoFrame<'a> {
             }
 
             let data = match self {
-                VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                    slice::from_raw_parts(
-                        frame.p_data as *const u8,
-                        frame.line_stride_or_data_size_in_bytes as usize,
-                    )
-                }
+                VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+                | VideoFrame::BorrowedGst(ref frame, _) => slice::from_raw_parts(
+                    frame.p_data as *const u8,
+                    frame.line_stride_or_data_size_in_bytes as usize,
+                ),
             };
 
             let mut cursor = Cursor::new(data);
@@ -669,7 +706,8 @@ impl<'a> VideoFrame<'a> {
 
     pub fn line_stride_or_data_size_in_bytes(&self) -> i32 {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => {
                 let stride = frame.line_stride_or_data_size_in_bytes;
 
                 if stride != 0 {
@@ -699,7 +737,8 @@ impl<'a> VideoFrame<'a> {
     pub fn metadata(&self) -> Option<&str> {
         unsafe {
             match self {
-                VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
+                VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+                | VideoFrame::BorrowedGst(ref frame, _) => {
                     if frame.p_metadata.is_null() {
                         None
                     } else {
@@ -712,15 +751,15 @@ impl<'a> VideoFrame<'a> {
 
     pub fn timestamp(&self) -> i64 {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => {
-                frame.timestamp
-            }
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame.timestamp,
         }
     }
 
     pub fn as_ptr(&self) -> *const NDIlib_video_frame_v2_t {
         match self {
-            VideoFrame::BorrowedRecv(ref frame, _) | VideoFrame::BorrowedGst(ref frame, _) => frame,
+            VideoFrame::Recv(RecvVideoFrame { ref frame, .. })
+            | VideoFrame::BorrowedGst(ref frame, _) => frame,
         }
     }
 
@@ -839,17 +878,79 @@ impl<'a> VideoFrame<'a> {
     }
 }
 
-impl<'a> Drop for VideoFrame<'a> {
-    #[allow(irrefutable_let_patterns)]
+/// Video frame received from the SDK, owned until it is given back
+#[derive(Debug)]
+pub struct RecvVideoFrame {
+    frame: NDIlib_video_frame_v2_t,
+    recv: RecvInstance,
+}
+
+// SAFETY: the frame data belongs to the SDK until it is freed, which can
+// happen from any thread, and the receiver is kept alive until then
+unsafe impl Send for RecvVideoFrame {}
+
+impl Drop for RecvVideoFrame {
     fn drop(&mut self) {
-        if let VideoFrame::BorrowedRecv(ref mut frame, recv) = *self {
-            unsafe {
-                NDIlib_recv_free_video_v2(recv.0.as_ptr() as *mut _, frame);
-            }
+        unsafe {
+            NDIlib_recv_free_video_v2(self.recv.as_ptr() as *mut _, &mut self.frame);
         }
     }
 }
 
+/// Received video frame wrapped in a buffer, released back to its
+/// receiver once the buffer memory is freed
+struct VideoFrameData {
+    frame: RecvVideoFrame,
+    data: *const u8,
+    size: usize,
+}
+
+// SAFETY: `data` points into the frame, which is only freed once this is
+// dropped, and is never written to
+unsafe impl Send for VideoFrameData {}
+
+impl AsRef<[u8]> for VideoFrameData {
+    fn as_ref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.data, self.size) }
+    }
+}
+
+impl Drop for VideoFrameData {
+    fn drop(&mut self) {
+        self.frame
+            .recv
+            .0
+            .wrapped_video_frames
+            .fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<'a> VideoFrame<'a> {
+    /// Wraps the data of a received frame into a buffer without copying it,
+    /// or gives the frame back if it has no data that can be wrapped or
+    /// too many frames of its receiver are wrapped already
+    pub fn into_buffer(self) -> Result<gst::Buffer, Self> {
+        let Some((data, size)) = self.data().map(|data| (data.as_ptr(), data.len())) else {
+            return Err(self);
+        };
+
+        let frame = match self {
+            VideoFrame::Recv(frame) => frame,
+            _ => return Err(self),
+        };
+
+        if !frame.recv.reserve_wrapped_video_frame() {
+            return Err(VideoFrame::Recv(frame));
+        }
+
+        Ok(gst::Buffer::from_slice(VideoFrameData {
+            frame,
+            data,
+            size,
+        }))
+    }
+}
+
 #[derive(Debug)]
 pub enum AudioFrame<'a> {
     Owned(
@@ -1048,6 +1149,18 @@ impl<'a> AudioFrame<'a> {
         info: &gst_audio::AudioInfo,
         buffer: &gst::BufferRef,
         timecode: i64,
+    ) -> Result<Self, TryFromAudioBufferError> {
+        Self::try_from_buffer_with_storage(info, buffer, timecode, Vec::new())
+    }
+
+    /// Like `try_from_buffer()` but converts into `dest_data`, reusing its
+    /// allocation if it is large enough. It can be retrieved again with
+    /// `into_storage()` once the frame was sent.
+    pub fn try_from_buffer_with_storage(
+        info: &gst_audio::AudioInfo,
+        buffer: &gst::BufferRef,
+        timecode: i64,
+        mut dest_data: Vec<f32>,
     ) -> Result<Self, TryFromAudioBufferError> {
         if info.format() != gst_audio::AUDIO_FORMAT_F32 {
             return Err(TryFromAudioBufferError);
@@ -1060,10 +1173,10 @@ impl<'a> AudioFrame<'a> {
 
         let no_samples = src_data.len() as i32 / info.channels() as i32;
         let channel_stride_or_data_size_in_bytes = no_samples * mem::size_of::<f32>() as i32;
-        let mut dest_data =
-            Vec::<f32>::with_capacity(no_samples as usize * info.channels() as usize);
+        dest_data.clear();
+        dest_data.reserve_exact(no_samples as usize * info.channels() as usize);
 
-        assert_eq!(dest_data.capacity(), src_data.len());
+        assert!(dest_data.capacity() >= src_data.len());
 
         unsafe {
             let dest_ptr = dest_data.as_mut_ptr();
@@ -1091,6 +1204,13 @@ impl<'a> AudioFrame<'a> {
 
         Ok(AudioFrame::Owned(dest, None, Some(dest_data)))
     }
+
+    pub fn into_storage(mut self) -> Option<Vec<f32>> {
+        match self {
+            AudioFrame::Owned(_, _, ref mut data) => data.take(),
+            AudioFrame::BorrowedRecv(..) => None,
+        }
+    }
 }
 
 impl<'a> Drop for AudioFrame<'a> {
@@ -1098,7 +1218,7 @@ impl<'a> Drop for AudioFrame<'a> {
     fn drop(&mut self) {
         if let AudioFrame::BorrowedRecv(ref mut frame, recv) = *self {
             unsafe {
-                NDIlib_recv_free_audio_v3(recv.0.as_ptr() as *mut _, frame);
+                NDIlib_recv_free_audio_v3(recv.as_ptr() as *mut _, frame);
             }
         }
     }
@@ -1200,7 +1320,7 @@ impl<'a> Drop for MetadataFrame<'a> {
     fn drop(&mut self) {
         if let MetadataFrame::Borrowed(ref mut frame, recv) = *self {
             unsafe {
-                NDIlib_recv_free_metadata(recv.0.as_ptr() as *mut _, frame);
+                NDIlib_recv_free_metadata(recv.as_ptr() as *mut _, frame);
             }
         }
     }
@@ -10,7 +10,7 @@ use std::sync::Mutex;
 
 use gst::glib::once_cell::sync::Lazy;
 
-use crate::ndi::SendInstance;
+use super::sender::Sender;
 
 static DEFAULT_SENDER_NDI_NAME: Lazy<String> = Lazy::new(|| {
     format!(
@@ -23,18 +23,20 @@ static DEFAULT_SENDER_NDI_NAME: Lazy<String> = Lazy::new(|| {
 #[derive(Debug)]
 struct Settings {
     ndi_name: String,
+    shared_sender: bool,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Settings {
             ndi_name: DEFAULT_SENDER_NDI_NAME.clone(),
+            shared_sender: false,
         }
     }
 }
 
 struct State {
-    send: SendInstance,
+    send: Sender,
     video_info: Option<gst_video::VideoInfo>,
     audio_info: Option<gst_audio::AudioInfo>,
 }
@@ -65,11 +67,18 @@ impl ObjectSubclass for NdiSink {
 impl ObjectImpl for NdiSink {
     fn properties() -> &'static [glib::ParamSpec] {
         static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
-            vec![glib::ParamSpecString::builder("ndi-name")
-                .nick("NDI Name")
-                .blurb("NDI Name to use")
-                .doc_show_default()
-                .build()]
+            vec![
+                glib::ParamSpecString::builder("ndi-name")
+                    .nick("NDI Name")
+                    .blurb("NDI Name to use")
+                    .doc_show_default()
+                    .build(),
+                glib::ParamSpecBoolean::builder("shared-sender")
+                    .nick("Shared Sender")
+                    .blurb("Register the sender in the process-wide sender registry, failing if another sink already uses the same NDI name, and share frame conversion buffers with other registered senders")
+                    .default_value(false)
+                    .build(),
+            ]
         });
 
         PROPERTIES.as_ref()
@@ -83,6 +92,10 @@ impl ObjectImpl for NdiSink {
                     .get::<String>()
                     .unwrap_or_else(|_| DEFAULT_SENDER_NDI_NAME.clone());
             }
+            "shared-sender" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.shared_sender = value.get().expect("type checked upstream");
+            }
             _ => unimplemented!(),
         };
     }
@@ -93,6 +106,10 @@ impl ObjectImpl for NdiSink {
                 let settings = self.settings.lock().unwrap();
                 settings.ndi_name.to_value()
             }
+            "shared-sender" => {
+                let settings = self.settings.lock().unwrap();
+                settings.shared_sender.to_value()
+            }
             _ => unimplemented!(),
         }
     }
@@ -116,43 +133,34 @@ impl ElementImpl for NdiSink {
 
     fn pad_templates() -> &'static [gst::PadTemplate] {
         static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
-            let caps = gst::Caps::builder_full()
-                .structure(
-                    gst::Structure::builder("video/x-raw")
-                        .field(
-                            "format",
-                            gst::List::new([
-                                gst_video::VideoFormat::Uyvy.to_str(),
-                                gst_video::VideoFormat::I420.to_str(),
-                                gst_video::VideoFormat::Nv12.to_str(),
-                                gst_video::VideoFormat::Nv21.to_str(),
-                                gst_video::VideoFormat::Yv12.to_str(),
-                                gst_video::VideoFormat::Bgra.to_str(),
-                                gst_video::VideoFormat::Bgrx.to_str(),
-                                gst_video::VideoFormat::Rgba.to_str(),
-                                gst_video::VideoFormat::Rgbx.to_str(),
-                            ]),
-                        )
-                        .field("width", gst::IntRange::<i32>::new(1, std::i32::MAX))
-                        .field("height", gst::IntRange::<i32>::new(1, std::i32::MAX))
-                        .field(
-                            "framerate",
-                            gst::FractionRange::new(
-                                gst::Fraction::new(0, 1),
-                                gst::Fraction::new(std::i32::MAX, 1),
-                            ),
-                        )
-                        .build(),
-                )
-                .structure(
-                    gst::Structure::builder("audio/x-raw")
-                        .field("format", gst_audio::AUDIO_FORMAT_F32.to_str())
-                        .field("rate", gst::IntRange::<i32>::new(1, i32::MAX))
-                        .field("channels", gst::IntRange::<i32>::new(1, i32::MAX))
-                        .field("layout", "interleaved")
-                        .build(),
-                )
+            let formats = [
+                gst_video::VideoFormat::Uyvy,
+                gst_video::VideoFormat::I420,
+                gst_video::VideoFormat::Nv12,
+                gst_video::VideoFormat::Nv21,
+                gst_video::VideoFormat::Yv12,
+                gst_video::VideoFormat::Bgra,
+                gst_video::VideoFormat::Bgrx,
+                gst_video::VideoFormat::Rgba,
+                gst_video::VideoFormat::Rgbx,
+            ];
+
+            let mut caps = gst_video::VideoCapsBuilder::new()
+                .format_list(formats)
                 .build();
+            // GL textures are downloaded when mapping the frames
+            caps.merge(
+                gst_video::VideoCapsBuilder::new()
+                    .features([crate::GL_MEMORY_FEATURE])
+                    .format_list(formats)
+                    .field("texture-target", "2D")
+                    .build(),
+            );
+            caps.merge(
+                gst_audio::AudioCapsBuilder::new_interleaved()
+                    .format(gst_audio::AUDIO_FORMAT_F32)
+                    .build(),
+            );
 
             let sink_pad_template = gst::PadTemplate::new(
                 "sink",
@@ -191,14 +199,7 @@ impl BaseSinkImpl for NdiSink {
         let mut state_storage = self.state.lock().unwrap();
         let settings = self.settings.lock().unwrap();
 
-        let send = SendInstance::builder(&settings.ndi_name)
-            .build()
-            .ok_or_else(|| {
-                gst::error_msg!(
-                    gst::ResourceError::OpenWrite,
-                    ["Could not create send instance"]
-                )
-            })?;
+        let send = Sender::new(&settings.ndi_name, settings.shared_sender)?;
 
         let state = State {
             send,
@@ -265,11 +266,16 @@ impl BaseSinkImpl for NdiSink {
         if let Some(ref info) = state.video_info {
             if let Some(audio_meta) = buffer.meta::<crate::ndisinkmeta::NdiSinkAudioMeta>() {
                 for (buffer, info, timecode) in audio_meta.buffers() {
-                    let frame = crate::ndi::AudioFrame::try_from_buffer(info, buffer, *timecode)
-                        .map_err(|_| {
-                            gst::error!(CAT, imp: self, "Unsupported audio frame");
-                            gst::FlowError::NotNegotiated
-                        })?;
+                    let frame = crate::ndi::AudioFrame::try_from_buffer_with_storage(
+                        info,
+                        buffer,
+                        *timecode,
+                        state.send.acquire_audio_buffer(),
+                    )
+                    .map_err(|_| {
+                        gst::error!(CAT, imp: self, "Unsupported audio frame");
+                        gst::FlowError::NotNegotiated
+                    })?;
 
                     gst::trace!(
                         CAT,
@@ -284,6 +290,29 @@ impl BaseSinkImpl for NdiSink {
                         info,
                     );
                     state.send.send_audio(&frame);
+
+                    if let Some(storage) = frame.into_storage() {
+                        state.send.release_audio_buffer(storage);
+                    }
+                }
+            }
+
+            if let Some(metadata_meta) = buffer.meta::<crate::ndisinkmeta::NdiSinkMetadataMeta>() {
+                for (metadata, timecode) in metadata_meta.metadata() {
+                    let frame = crate::ndi::MetadataFrame::new(*timecode, Some(metadata));
+
+                    gst::trace!(
+                        CAT,
+                        imp: self,
+                        "Sending metadata {:?} with timecode {}",
+                        metadata,
+                        if *timecode < 0 {
+                            gst::ClockTime::NONE.display()
+                        } else {
+                            Some((*timecode as u64 * 100).nseconds()).display()
+                        },
+                    );
+                    state.send.send_metadata(&frame);
                 }
             }
 
@@ -303,6 +332,7 @@ impl BaseSinkImpl for NdiSink {
                     .map(|time| (time.nseconds() / 100) as i64)
                     .unwrap_or(crate::ndisys::NDIlib_send_timecode_synthesize);
 
+                // Downloads GL textures
                 let frame = gst_video::VideoFrameRef::from_buffer_ref_readable(buffer, info)
                     .map_err(|_| {
                         gst::error!(CAT, imp: self, "Failed to map buffer");
@@ -344,11 +374,16 @@ impl BaseSinkImpl for NdiSink {
                 .map(|time| (time.nseconds() / 100) as i64)
                 .unwrap_or(crate::ndisys::NDIlib_send_timecode_synthesize);
 
-            let frame =
-                crate::ndi::AudioFrame::try_from_buffer(info, buffer, timecode).map_err(|_| {
-                    gst::error!(CAT, imp: self, "Unsupported audio frame");
-                    gst::FlowError::NotNegotiated
-                })?;
+            let frame = crate::ndi::AudioFrame::try_from_buffer_with_storage(
+                info,
+                buffer,
+                timecode,
+                state.send.acquire_audio_buffer(),
+            )
+            .map_err(|_| {
+                gst::error!(CAT, imp: self, "Unsupported audio frame");
+                gst::FlowError::NotNegotiated
+            })?;
 
             gst::trace!(
                 CAT,
@@ -363,6 +398,10 @@ impl BaseSinkImpl for NdiSink {
                 info,
             );
             state.send.send_audio(&frame);
+
+            if let Some(storage) = frame.into_storage() {
+                state.send.release_audio_buffer(storage);
+            }
         } else {
             return Err(gst::FlowError::Error);
         }
@@ -3,6 +3,7 @@
 use glib::prelude::*;
 
 mod imp;
+mod sender;
 
 glib::wrapper! {
     pub struct NdiSink(ObjectSubclass<imp::NdiSink>) @extends gst_base::BaseSink, gst::Element, gst::Object;
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use gst::glib::once_cell::sync::Lazy;
+
+use crate::ndi::SendInstance;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "ndisender",
+        gst::DebugColorFlags::empty(),
+        Some("NewTek NDI sender"),
+    )
+});
+
+/// Maximum number of audio conversion buffers kept around for reuse
+const MAX_POOLED_AUDIO_BUFFERS: usize = 32;
+
+/// NDI names of all senders currently registered in this process
+static SENDERS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Planar audio conversion buffers shared by all registered senders
+static AUDIO_BUFFER_POOL: Lazy<Mutex<Vec<Vec<f32>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Send instance of an `ndisink`.
+///
+/// Shared senders are registered in the process-wide sender registry, of
+/// which only one can exist per NDI name at any time, and use conversion
+/// buffers from a pool shared between all of them.
+#[derive(Debug)]
+pub struct Sender {
+    ndi_name: Option<String>,
+    send: SendInstance,
+}
+
+impl Sender {
+    pub fn new(ndi_name: &str, shared: bool) -> Result<Self, gst::ErrorMessage> {
+        let mut senders = SENDERS.lock().unwrap();
+
+        if shared && senders.contains(ndi_name) {
+            return Err(gst::error_msg!(
+                gst::ResourceError::Busy,
+                ["NDI name '{}' is already used by another sink", ndi_name]
+            ));
+        }
+
+        let send = SendInstance::builder(ndi_name).build().ok_or_else(|| {
+            gst::error_msg!(
+                gst::ResourceError::OpenWrite,
+                ["Could not create send instance"]
+            )
+        })?;
+
+        if !shared {
+            return Ok(Self {
+                ndi_name: None,
+                send,
+            });
+        }
+
+        senders.insert(ndi_name.to_string());
+        gst::debug!(CAT, "Registered sender '{}'", ndi_name);
+
+        Ok(Self {
+            ndi_name: Some(ndi_name.to_string()),
+            send,
+        })
+    }
+
+    /// Returns a previously released conversion buffer for shared senders,
+    /// or an empty one otherwise or if the pool is exhausted
+    pub fn acquire_audio_buffer(&self) -> Vec<f32> {
+        if self.ndi_name.is_none() {
+            return Vec::new();
+        }
+
+        AUDIO_BUFFER_POOL.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    pub fn release_audio_buffer(&self, buffer: Vec<f32>) {
+        if self.ndi_name.is_none() {
+            return;
+        }
+
+        let mut pool = AUDIO_BUFFER_POOL.lock().unwrap();
+
+        if pool.len() < MAX_POOLED_AUDIO_BUFFERS {
+            pool.push(buffer);
+        }
+    }
+}
+
+impl Deref for Sender {
+    type Target = SendInstance;
+
+    fn deref(&self) -> &SendInstance {
+        &self.send
+    }
+}
+
+impl DerefMut for Sender {
+    fn deref_mut(&mut self) -> &mut SendInstance {
+        &mut self.send
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        if let Some(ndi_name) = self.ndi_name.take() {
+            SENDERS.lock().unwrap().remove(&ndi_name);
+            gst::debug!(CAT, "Unregistered sender '{}'", ndi_name);
+        }
+    }
+}
@@ -10,6 +10,7 @@ use gst_base::subclass::prelude::*;
 use gst::glib::once_cell::sync::Lazy;
 
 use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
@@ -36,11 +37,15 @@ struct State {
         Option<gst::Segment>,
     )>,
     current_audio_buffers: Vec<(gst::Buffer, gst_audio::AudioInfo, i64)>,
+    current_metadata: Vec<(String, i64)>,
 }
 
 pub struct NdiSinkCombiner {
     video_pad: gst_base::AggregatorPad,
     audio_pad: Mutex<Option<gst_base::AggregatorPad>>,
+    metadata_pad: Mutex<Option<gst_base::AggregatorPad>>,
+    // Set while a gap is being sent on the metadata pad
+    metadata_gap_pending: AtomicBool,
     state: Mutex<Option<State>>,
 }
 
@@ -57,6 +62,8 @@ impl ObjectSubclass for NdiSinkCombiner {
         Self {
             video_pad,
             audio_pad: Mutex::new(None),
+            metadata_pad: Mutex::new(None),
+            metadata_gap_pending: AtomicBool::new(false),
             state: Mutex::new(None),
         }
     }
@@ -68,6 +75,32 @@ impl ObjectImpl for NdiSinkCombiner {
 
         let obj = self.obj();
         obj.add_pad(&self.video_pad).unwrap();
+
+        // Metadata is sparse but, when not live, the aggregator waits for all its pads to
+        // have data. Upstream of the metadata pad is not required to send gaps, send them
+        // on its behalf whenever a video buffer arrives and no metadata is queued.
+        let obj_weak = obj.downgrade();
+        self.video_pad
+            .add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+                let Some(obj) = obj_weak.upgrade() else {
+                    return gst::PadProbeReturn::Ok;
+                };
+                let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data else {
+                    return gst::PadProbeReturn::Ok;
+                };
+
+                let running_time = pad
+                    .sticky_event::<gst::event::Segment>(0)
+                    .and_then(|event| event.segment().clone().downcast::<gst::ClockTime>().ok())
+                    .and_then(|segment| segment.to_running_time(buffer.pts()));
+
+                if let Some(running_time) = running_time {
+                    obj.imp().fill_metadata_gap(running_time);
+                }
+
+                gst::PadProbeReturn::Ok
+            })
+            .unwrap();
     }
 }
 
@@ -89,20 +122,33 @@ impl ElementImpl for NdiSinkCombiner {
 
     fn pad_templates() -> &'static [gst::PadTemplate] {
         static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
-            let caps = gst_video::VideoCapsBuilder::new()
-                .format_list([
-                    gst_video::VideoFormat::Uyvy,
-                    gst_video::VideoFormat::I420,
-                    gst_video::VideoFormat::Nv12,
-                    gst_video::VideoFormat::Nv21,
-                    gst_video::VideoFormat::Yv12,
-                    gst_video::VideoFormat::Bgra,
-                    gst_video::VideoFormat::Bgrx,
-                    gst_video::VideoFormat::Rgba,
-                    gst_video::VideoFormat::Rgbx,
-                ])
+            let formats = [
+                gst_video::VideoFormat::Uyvy,
+                gst_video::VideoFormat::I420,
+                gst_video::VideoFormat::Nv12,
+                gst_video::VideoFormat::Nv21,
+                gst_video::VideoFormat::Yv12,
+                gst_video::VideoFormat::Bgra,
+                gst_video::VideoFormat::Bgrx,
+                gst_video::VideoFormat::Rgba,
+                gst_video::VideoFormat::Rgbx,
+            ];
+
+            let mut caps = gst_video::VideoCapsBuilder::new()
+                .format_list(formats)
                 .framerate_range(gst::Fraction::new(1, i32::MAX)..gst::Fraction::new(i32::MAX, 1))
                 .build();
+            // Passed through to ndisink
+            caps.merge(
+                gst_video::VideoCapsBuilder::new()
+                    .features([crate::GL_MEMORY_FEATURE])
+                    .format_list(formats)
+                    .framerate_range(
+                        gst::Fraction::new(1, i32::MAX)..gst::Fraction::new(i32::MAX, 1),
+                    )
+                    .field("texture-target", "2D")
+                    .build(),
+            );
             let src_pad_template = gst::PadTemplate::with_gtype(
                 "src",
                 gst::PadDirection::Src,
@@ -133,10 +179,21 @@ impl ElementImpl for NdiSinkCombiner {
                 gst_base::AggregatorPad::static_type(),
             )
             .unwrap();
+
+            let caps = gst::Caps::builder("application/x-ndi-metadata").build();
+            let metadata_sink_pad_template = gst::PadTemplate::with_gtype(
+                "metadata",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Request,
+                &caps,
+                gst_base::AggregatorPad::static_type(),
+            )
+            .unwrap();
             vec![
                 src_pad_template,
                 video_sink_pad_template,
                 audio_sink_pad_template,
+                metadata_sink_pad_template,
             ]
         });
 
@@ -150,10 +207,145 @@ impl ElementImpl for NdiSinkCombiner {
             gst::debug!(CAT, obj: pad, "Release audio pad");
             self.parent_release_pad(pad);
             *audio_pad_storage = None;
+            return;
+        }
+        drop(audio_pad_storage);
+
+        let mut metadata_pad_storage = self.metadata_pad.lock().unwrap();
+
+        if metadata_pad_storage.as_ref().map(|p| p.upcast_ref()) == Some(pad) {
+            gst::debug!(CAT, obj: pad, "Release metadata pad");
+            self.parent_release_pad(pad);
+            *metadata_pad_storage = None;
         }
     }
 }
 
+impl NdiSinkCombiner {
+    /// Sends a gap on the metadata pad up to `running_time` if no metadata is queued, so
+    /// that the aggregator does not wait for it
+    fn fill_metadata_gap(&self, running_time: gst::ClockTime) {
+        let Some(metadata_pad) = self.metadata_pad.lock().unwrap().clone() else {
+            return;
+        };
+
+        if metadata_pad.is_eos() || metadata_pad.has_buffer() {
+            return;
+        }
+
+        // No segment yet, upstream hasn't started
+        let Some(segment) = metadata_pad
+            .sticky_event::<gst::event::Segment>(0)
+            .and_then(|event| event.segment().clone().downcast::<gst::ClockTime>().ok())
+        else {
+            return;
+        };
+
+        let Some(position) = segment.position_from_running_time(running_time) else {
+            return;
+        };
+
+        if self.metadata_gap_pending.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        gst::trace!(CAT, obj: metadata_pad, "Sending gap up to {}", position);
+
+        // Sending the gap blocks if upstream queued metadata in the meantime, which the
+        // aggregator only consumes once the next video buffer arrives. Don't block the
+        // video streaming thread on that.
+        self.obj().call_async(move |obj| {
+            metadata_pad.send_event(gst::event::Gap::builder(position).build());
+            obj.imp()
+                .metadata_gap_pending
+                .store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Takes the metadata queued on `metadata_pad` that starts before `video_running_time_end`,
+    /// all of it if there is no such end
+    fn consume_metadata(
+        &self,
+        state: &mut State,
+        metadata_pad: &gst_base::AggregatorPad,
+        video_running_time_end: Option<gst::ClockTime>,
+    ) -> Result<(), gst::FlowError> {
+        // peek_buffer() calls into clip(), which doesn't take the state lock for the metadata
+        // pad
+        while let Some(metadata_buffer) = metadata_pad.peek_buffer() {
+            if metadata_buffer.size() == 0 {
+                // Skip empty/gap metadata buffer
+                metadata_pad.drop_buffer();
+                continue;
+            }
+
+            let metadata_segment = match metadata_pad.segment().downcast::<gst::ClockTime>() {
+                Ok(metadata_segment) => metadata_segment,
+                Err(metadata_segment) => {
+                    gst::error!(
+                        CAT,
+                        imp: self,
+                        "Metadata segment of wrong format {:?}",
+                        metadata_segment.format()
+                    );
+                    return Err(gst::FlowError::Error);
+                }
+            };
+
+            let metadata_running_time = metadata_segment.to_running_time(metadata_buffer.pts());
+
+            if metadata_running_time
+                .zip(video_running_time_end)
+                .map_or(false, |(metadata, video)| metadata >= video)
+            {
+                gst::trace!(
+                    CAT,
+                    imp: self,
+                    "Metadata at {} belongs to the next video buffer",
+                    metadata_running_time.display(),
+                );
+                break;
+            }
+
+            let timecode = self
+                .obj()
+                .base_time()
+                .zip(metadata_running_time)
+                .map(|(base_time, metadata_running_time)| {
+                    ((base_time.nseconds() + metadata_running_time.nseconds()) / 100) as i64
+                })
+                .unwrap_or(crate::ndisys::NDIlib_send_timecode_synthesize);
+
+            let metadata = metadata_buffer.map_readable().ok().and_then(|map| {
+                std::str::from_utf8(&map)
+                    .ok()
+                    .map(|s| s.trim_end_matches('\0'))
+                    .filter(|s| !s.contains('\0'))
+                    .map(String::from)
+            });
+
+            match metadata {
+                Some(metadata) => {
+                    gst::trace!(
+                        CAT,
+                        imp: self,
+                        "Including metadata {:?} with timecode {}",
+                        metadata,
+                        timecode,
+                    );
+                    state.current_metadata.push((metadata, timecode));
+                }
+                None => {
+                    gst::warning!(CAT, imp: self, "Dropping invalid metadata buffer");
+                }
+            }
+            metadata_pad.drop_buffer();
+        }
+
+        Ok(())
+    }
+}
+
 impl AggregatorImpl for NdiSinkCombiner {
     fn create_new_pad(
         &self,
@@ -161,23 +353,24 @@ impl AggregatorImpl for NdiSinkCombiner {
         _req_name: Option<&str>,
         _caps: Option<&gst::Caps>,
     ) -> Option<gst_base::AggregatorPad> {
-        let mut audio_pad_storage = self.audio_pad.lock().unwrap();
-
-        if audio_pad_storage.is_some() {
-            gst::error!(CAT, imp: self, "Audio pad already requested");
+        let (mut pad_storage, kind) = if templ == &self.obj().pad_template("audio").unwrap() {
+            (self.audio_pad.lock().unwrap(), "audio")
+        } else if templ == &self.obj().pad_template("metadata").unwrap() {
+            (self.metadata_pad.lock().unwrap(), "metadata")
+        } else {
+            gst::error!(CAT, imp: self, "Wrong pad template");
             return None;
-        }
+        };
 
-        let sink_templ = self.obj().pad_template("audio").unwrap();
-        if templ != &sink_templ {
-            gst::error!(CAT, imp: self, "Wrong pad template");
+        if pad_storage.is_some() {
+            gst::error!(CAT, imp: self, "{} pad already requested", kind);
             return None;
         }
 
         let pad = gst::PadBuilder::<gst_base::AggregatorPad>::from_template(templ).build();
-        *audio_pad_storage = Some(pad.clone());
+        *pad_storage = Some(pad.clone());
 
-        gst::debug!(CAT, imp: self, "Requested audio pad");
+        gst::debug!(CAT, imp: self, "Requested {} pad", kind);
 
         Some(pad)
     }
@@ -191,6 +384,7 @@ impl AggregatorImpl for NdiSinkCombiner {
             pending_segment: None,
             current_video_buffer: None,
             current_audio_buffers: Vec::new(),
+            current_metadata: Vec::new(),
         });
 
         gst::debug!(CAT, imp: self, "Started");
@@ -231,6 +425,11 @@ impl AggregatorImpl for NdiSinkCombiner {
             return Some(buffer);
         }
 
+        // Metadata is attached as a whole to the video frame it falls into
+        if self.metadata_pad.lock().unwrap().as_ref() == Some(agg_pad) {
+            return segment.clip(pts, pts).map(|_| buffer);
+        }
+
         let duration = buffer.duration();
 
         gst::trace!(
@@ -520,6 +719,12 @@ impl AggregatorImpl for NdiSinkCombiner {
             // far
         }
 
+        // Metadata is sparse, only take what is already queued and never wait for more
+        let metadata_pad = self.metadata_pad.lock().unwrap().clone();
+        if let Some(metadata_pad) = metadata_pad {
+            self.consume_metadata(state, &metadata_pad, current_video_running_time_end)?;
+        }
+
         let audio_buffers = mem::take(&mut state.current_audio_buffers);
 
         if !audio_buffers.is_empty() {
@@ -527,6 +732,13 @@ impl AggregatorImpl for NdiSinkCombiner {
             crate::ndisinkmeta::NdiSinkAudioMeta::add(current_video_buffer, audio_buffers);
         }
 
+        let metadata = mem::take(&mut state.current_metadata);
+
+        if !metadata.is_empty() {
+            let current_video_buffer = current_video_buffer.make_mut();
+            crate::ndisinkmeta::NdiSinkMetadataMeta::add(current_video_buffer, metadata);
+        }
+
         if let Some((video_buffer, video_running_time)) = next_video_buffer {
             state.current_video_buffer = Some((video_buffer, video_running_time, None, None));
             drop(state_storage);
@@ -51,6 +51,53 @@ impl fmt::Debug for NdiSinkAudioMeta {
     }
 }
 
+#[repr(transparent)]
+pub struct NdiSinkMetadataMeta(imp::NdiSinkMetadataMeta);
+
+unsafe impl Send for NdiSinkMetadataMeta {}
+unsafe impl Sync for NdiSinkMetadataMeta {}
+
+impl NdiSinkMetadataMeta {
+    pub fn add(
+        buffer: &mut gst::BufferRef,
+        metadata: Vec<(String, i64)>,
+    ) -> gst::MetaRefMut<Self, gst::meta::Standalone> {
+        unsafe {
+            // Manually dropping because gst_buffer_add_meta() takes ownership of the
+            // content of the struct
+            let mut params = mem::ManuallyDrop::new(imp::NdiSinkMetadataMetaParams { metadata });
+
+            let meta = gst::ffi::gst_buffer_add_meta(
+                buffer.as_mut_ptr(),
+                imp::ndi_sink_metadata_meta_get_info(),
+                &mut *params as *mut imp::NdiSinkMetadataMetaParams as glib::ffi::gpointer,
+            ) as *mut imp::NdiSinkMetadataMeta;
+
+            Self::from_mut_ptr(buffer, meta)
+        }
+    }
+
+    pub fn metadata(&self) -> &[(String, i64)] {
+        &self.0.metadata
+    }
+}
+
+unsafe impl MetaAPI for NdiSinkMetadataMeta {
+    type GstType = imp::NdiSinkMetadataMeta;
+
+    fn meta_api() -> glib::Type {
+        imp::ndi_sink_metadata_meta_api_get_type()
+    }
+}
+
+impl fmt::Debug for NdiSinkMetadataMeta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NdiSinkMetadataMeta")
+            .field("metadata", &self.metadata())
+            .finish()
+    }
+}
+
 mod imp {
     use glib::translate::*;
     use gst::glib::once_cell::sync::Lazy;
@@ -141,4 +188,89 @@ mod imp {
 
         META_INFO.0.as_ptr()
     }
+
+    pub(super) struct NdiSinkMetadataMetaParams {
+        pub metadata: Vec<(String, i64)>,
+    }
+
+    #[repr(C)]
+    pub struct NdiSinkMetadataMeta {
+        parent: gst::ffi::GstMeta,
+        pub(super) metadata: Vec<(String, i64)>,
+    }
+
+    pub(super) fn ndi_sink_metadata_meta_api_get_type() -> glib::Type {
+        static TYPE: Lazy<glib::Type> = Lazy::new(|| unsafe {
+            let t = from_glib(gst::ffi::gst_meta_api_type_register(
+                b"GstNdiSinkMetadataMetaAPI\0".as_ptr() as *const _,
+                [ptr::null::<std::os::raw::c_char>()].as_ptr() as *mut *const _,
+            ));
+
+            assert_ne!(t, glib::Type::INVALID);
+
+            t
+        });
+
+        *TYPE
+    }
+
+    unsafe extern "C" fn ndi_sink_metadata_meta_init(
+        meta: *mut gst::ffi::GstMeta,
+        params: glib::ffi::gpointer,
+        _buffer: *mut gst::ffi::GstBuffer,
+    ) -> glib::ffi::gboolean {
+        assert!(!params.is_null());
+
+        let meta = &mut *(meta as *mut NdiSinkMetadataMeta);
+        let params = ptr::read(params as *const NdiSinkMetadataMetaParams);
+
+        ptr::write(&mut meta.metadata, params.metadata);
+
+        true.into_glib()
+    }
+
+    unsafe extern "C" fn ndi_sink_metadata_meta_free(
+        meta: *mut gst::ffi::GstMeta,
+        _buffer: *mut gst::ffi::GstBuffer,
+    ) {
+        let meta = &mut *(meta as *mut NdiSinkMetadataMeta);
+
+        ptr::drop_in_place(&mut meta.metadata);
+    }
+
+    unsafe extern "C" fn ndi_sink_metadata_meta_transform(
+        dest: *mut gst::ffi::GstBuffer,
+        meta: *mut gst::ffi::GstMeta,
+        _buffer: *mut gst::ffi::GstBuffer,
+        _type_: glib::ffi::GQuark,
+        _data: glib::ffi::gpointer,
+    ) -> glib::ffi::gboolean {
+        let meta = &*(meta as *mut NdiSinkMetadataMeta);
+
+        super::NdiSinkMetadataMeta::add(gst::BufferRef::from_mut_ptr(dest), meta.metadata.clone());
+
+        true.into_glib()
+    }
+
+    pub(super) fn ndi_sink_metadata_meta_get_info() -> *const gst::ffi::GstMetaInfo {
+        struct MetaInfo(ptr::NonNull<gst::ffi::GstMetaInfo>);
+        unsafe impl Send for MetaInfo {}
+        unsafe impl Sync for MetaInfo {}
+
+        static META_INFO: Lazy<MetaInfo> = Lazy::new(|| unsafe {
+            MetaInfo(
+                ptr::NonNull::new(gst::ffi::gst_meta_register(
+                    ndi_sink_metadata_meta_api_get_type().into_glib(),
+                    b"GstNdiSinkMetadataMeta\0".as_ptr() as *const _,
+                    mem::size_of::<NdiSinkMetadataMeta>(),
+                    Some(ndi_sink_metadata_meta_init),
+                    Some(ndi_sink_metadata_meta_free),
+                    Some(ndi_sink_metadata_meta_transform),
+                ) as *mut gst::ffi::GstMetaInfo)
+                .expect("Failed to register meta API"),
+            )
+        });
+
+        META_INFO.0.as_ptr()
+    }
 }
@@ -999,7 +999,7 @@ impl Receiver {
 
         let info = self.create_video_info(element, &video_frame)?;
 
-        let mut buffer = self.create_video_buffer(element, pts, duration, &info, &video_frame)?;
+        let mut buffer = self.create_video_buffer(element, pts, duration, &info, video_frame)?;
         if discont {
             buffer
                 .get_mut()
@@ -1289,9 +1289,13 @@ impl Receiver {
         pts: gst::ClockTime,
         duration: Option<gst::ClockTime>,
         info: &VideoInfo,
-        video_frame: &VideoFrame,
+        video_frame: VideoFrame,
     ) -> Result<gst::Buffer, gst::FlowError> {
-        let mut buffer = self.copy_video_frame(element, info, video_frame)?;
+        let timecode = video_frame.timecode();
+        let timestamp = video_frame.timestamp();
+        let frame_format_type = video_frame.frame_format_type();
+
+        let mut buffer = self.wrap_video_frame(element, info, video_frame)?;
         {
             let buffer = buffer.get_mut().unwrap();
             buffer.set_pts(pts);
@@ -1300,21 +1304,21 @@ impl Receiver {
             gst::ReferenceTimestampMeta::add(
                 buffer,
                 &crate::TIMECODE_CAPS,
-                (video_frame.timecode() as u64 * 100).nseconds(),
+                (timecode as u64 * 100).nseconds(),
                 gst::ClockTime::NONE,
             );
-            if video_frame.timestamp() != ndisys::NDIlib_recv_timestamp_undefined {
+            if timestamp != ndisys::NDIlib_recv_timestamp_undefined {
                 gst::ReferenceTimestampMeta::add(
                     buffer,
                     &crate::TIMESTAMP_CAPS,
-                    (video_frame.timestamp() as u64 * 100).nseconds(),
+                    (timestamp as u64 * 100).nseconds(),
                     gst::ClockTime::NONE,
                 );
             }
 
             #[cfg(feature = "interlaced-fields")]
             {
-                match video_frame.frame_format_type() {
+                match frame_format_type {
                     ndisys::NDIlib_frame_format_type_e::NDIlib_frame_format_type_interleaved => {
                         buffer.set_video_flags(
                             gst_video::VideoBufferFlags::INTERLACED
@@ -1339,7 +1343,7 @@ impl Receiver {
 
             #[cfg(not(feature = "interlaced-fields"))]
             {
-                if video_frame.frame_format_type()
+                if frame_format_type
                     == ndisys::NDIlib_frame_format_type_e::NDIlib_frame_format_type_interleaved
                 {
                     buffer.set_video_flags(
@@ -1352,6 +1356,80 @@ impl Receiver {
         Ok(buffer)
     }
 
+    /// Wraps the frame received from the SDK into a buffer, which releases
+    /// it once freed, with a video meta if its stride isn't the default one.
+    /// Frames that can't be wrapped are copied.
+    fn wrap_video_frame(
+        &self,
+        element: &gst::Element,
+        info: &VideoInfo,
+        video_frame: VideoFrame,
+    ) -> Result<gst::Buffer, gst::FlowError> {
+        let layout = match info {
+            VideoInfo::Video(ref info) => {
+                Self::packed_video_stride(info, &video_frame).map(|stride| (info, stride))
+            }
+            #[cfg(feature = "advanced-sdk")]
+            _ => None,
+        };
+
+        let Some((video_info, stride)) = layout else {
+            return self.copy_video_frame(element, info, &video_frame);
+        };
+
+        match video_frame.into_buffer() {
+            Ok(mut buffer) => {
+                if video_info.stride()[0] != stride {
+                    gst_video::VideoMeta::add_full(
+                        buffer.get_mut().unwrap(),
+                        gst_video::VideoFrameFlags::empty(),
+                        video_info.format(),
+                        video_info.width(),
+                        video_info.height(),
+                        &[0],
+                        &[stride],
+                    )
+                    .map_err(|_| gst::FlowError::Error)?;
+                }
+
+                Ok(buffer)
+            }
+            Err(video_frame) => self.copy_video_frame(element, info, &video_frame),
+        }
+    }
+
+    /// Returns the stride of received frames that can be wrapped as is,
+    /// only single plane formats for now
+    fn packed_video_stride(info: &gst_video::VideoInfo, video_frame: &VideoFrame) -> Option<i32> {
+        let line_bytes = match info.format() {
+            gst_video::VideoFormat::Uyvy => 2 * info.width() as usize,
+            gst_video::VideoFormat::Bgra
+            | gst_video::VideoFormat::Bgrx
+            | gst_video::VideoFormat::Rgba
+            | gst_video::VideoFormat::Rgbx => 4 * info.width() as usize,
+            _ => return None,
+        };
+
+        // Separate fields are copied
+        if ![
+            ndisys::NDIlib_frame_format_type_e::NDIlib_frame_format_type_progressive,
+            ndisys::NDIlib_frame_format_type_e::NDIlib_frame_format_type_interleaved,
+        ]
+        .contains(&video_frame.frame_format_type())
+        {
+            return None;
+        }
+
+        let src = video_frame.data()?;
+        let stride = video_frame.line_stride_or_data_size_in_bytes();
+
+        if (stride as usize) < line_bytes || src.len() < info.height() as usize * stride as usize {
+            return None;
+        }
+
+        Some(stride)
+    }
+
     fn copy_video_frame(
         &self,
         #[allow(unused_variables)] element: &gst::Element,
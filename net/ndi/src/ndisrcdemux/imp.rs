@@ -22,6 +22,9 @@ struct State {
     combiner: gst_base::UniqueFlowCombiner,
     video_pad: Option<gst::Pad>,
     video_caps: Option<gst::Caps>,
+    /// Whether downstream handles the video meta of frames with
+    /// a non-default stride, queried again on reconfiguration
+    video_meta_supported: Option<bool>,
 
     audio_pad: Option<gst::Pad>,
     audio_caps: Option<gst::Caps>,
@@ -172,7 +175,8 @@ impl NdiSrcDemux {
 
         let mut state = self.state.lock().unwrap();
         let caps = meta.caps();
-        match meta.stream_type() {
+        let stream_type = meta.stream_type();
+        match stream_type {
             ndisrcmeta::StreamType::Audio => {
                 if let Some(ref pad) = state.audio_pad {
                     srcpad = pad.clone();
@@ -269,6 +273,7 @@ impl NdiSrcDemux {
                     gst::debug!(CAT, imp: self, "Video caps changed to {}", caps);
                     events.push(gst::event::Caps::new(&caps));
                     state.video_caps = Some(caps);
+                    state.video_meta_supported = None;
                 }
             }
         }
@@ -286,12 +291,88 @@ impl NdiSrcDemux {
             srcpad.push_event(ev);
         }
 
+        if stream_type == ndisrcmeta::StreamType::Video
+            && buffer.meta::<gst_video::VideoMeta>().is_some()
+        {
+            buffer = self.copy_video_if_needed(&srcpad, buffer)?;
+        }
+
         let res = srcpad.push(buffer);
 
         let mut state = self.state.lock().unwrap();
         state.combiner.update_pad_flow(&srcpad, res)
     }
 
+    /// Received frames are wrapped without copying and their layout
+    /// described with a video meta, copy them to the default layout if
+    /// downstream doesn't support that
+    fn copy_video_if_needed(
+        &self,
+        srcpad: &gst::Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::Buffer, gst::FlowError> {
+        let (caps, video_meta_supported) = {
+            let state = self.state.lock().unwrap();
+            (state.video_caps.clone(), state.video_meta_supported)
+        };
+        let caps = caps.ok_or(gst::FlowError::NotNegotiated)?;
+
+        let reconfigure = srcpad.check_reconfigure();
+        let video_meta_supported = match video_meta_supported {
+            Some(supported) if !reconfigure => supported,
+            _ => {
+                let mut query = gst::query::Allocation::new(Some(&caps), false);
+                let supported = srcpad.peer_query(&mut query)
+                    && query
+                        .find_allocation_meta::<gst_video::VideoMeta>()
+                        .is_some();
+
+                gst::debug!(CAT, imp: self, "Downstream supports video meta: {}", supported);
+                self.state.lock().unwrap().video_meta_supported = Some(supported);
+
+                supported
+            }
+        };
+
+        if video_meta_supported {
+            return Ok(buffer);
+        }
+
+        let info = gst_video::VideoInfo::from_caps(&caps).map_err(|_| {
+            gst::error!(CAT, imp: self, "Invalid video caps {}", caps);
+            gst::FlowError::NotNegotiated
+        })?;
+
+        let mut outbuf = gst::Buffer::with_size(info.size()).unwrap();
+        {
+            let outbuf = outbuf.get_mut().unwrap();
+            buffer
+                .copy_into(
+                    outbuf,
+                    gst::BufferCopyFlags::FLAGS
+                        | gst::BufferCopyFlags::TIMESTAMPS
+                        | gst::BufferCopyFlags::META,
+                    ..,
+                )
+                .map_err(|_| gst::FlowError::Error)?;
+
+            if let Some(meta) = outbuf.meta_mut::<gst_video::VideoMeta>() {
+                meta.remove().unwrap();
+            }
+        }
+
+        let in_frame = gst_video::VideoFrame::from_buffer_readable(buffer, &info)
+            .map_err(|_| gst::FlowError::Error)?;
+        let mut out_frame = gst_video::VideoFrame::from_buffer_writable(outbuf, &info)
+            .map_err(|_| gst::FlowError::Error)?;
+        in_frame.copy(&mut out_frame).map_err(|_| {
+            gst::error!(CAT, imp: self, "Failed to copy video frame");
+            gst::FlowError::Error
+        })?;
+
+        Ok(out_frame.into_buffer())
+    }
+
     fn sink_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
         use gst::EventView;
 
@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstndi::plugin_register_static().expect("ndi test");
+    });
+}
+
+const VIDEO_CAPS: &str = "video/x-raw,format=UYVY,width=16,height=16,framerate=30/1";
+const FRAME_DURATION: gst::ClockTime = gst::ClockTime::from_nseconds(33_333_333);
+
+fn video_buffer(n: u64) -> gst::Buffer {
+    let mut buffer = gst::Buffer::with_size(16 * 16 * 2).unwrap();
+    {
+        let buffer = buffer.get_mut().unwrap();
+        buffer.set_pts(FRAME_DURATION * n);
+        buffer.set_duration(FRAME_DURATION);
+    }
+    buffer
+}
+
+#[test]
+fn test_video_without_metadata() {
+    init();
+
+    let mut h_video =
+        gst_check::Harness::with_padnames("ndisinkcombiner", Some("video"), Some("src"));
+    let mut h_metadata =
+        gst_check::Harness::with_element(&h_video.element().unwrap(), Some("metadata"), None);
+
+    h_video.set_src_caps_str(VIDEO_CAPS);
+    h_metadata.set_src_caps_str("application/x-ndi-metadata");
+    h_video.play();
+    h_metadata.play();
+
+    // Metadata is sparse, no metadata is ever pushed and video must flow regardless
+    for n in 0..5 {
+        assert_eq!(h_video.push(video_buffer(n)), Ok(gst::FlowSuccess::Ok));
+    }
+
+    for n in 0..4 {
+        let buffer = h_video.pull().unwrap();
+        assert_eq!(buffer.pts(), Some(FRAME_DURATION * n));
+    }
+}
+
+#[test]
+fn test_video_with_sparse_metadata() {
+    init();
+
+    let mut h_video =
+        gst_check::Harness::with_padnames("ndisinkcombiner", Some("video"), Some("src"));
+    let mut h_metadata =
+        gst_check::Harness::with_element(&h_video.element().unwrap(), Some("metadata"), None);
+
+    h_video.set_src_caps_str(VIDEO_CAPS);
+    h_metadata.set_src_caps_str("application/x-ndi-metadata");
+    h_video.play();
+    h_metadata.play();
+
+    assert_eq!(h_video.push(video_buffer(0)), Ok(gst::FlowSuccess::Ok));
+    assert_eq!(h_video.push(video_buffer(1)), Ok(gst::FlowSuccess::Ok));
+
+    let buffer = h_video.pull().unwrap();
+    assert_eq!(buffer.pts(), Some(gst::ClockTime::ZERO));
+
+    // Falls into the second video buffer
+    let mut metadata = gst::Buffer::from_slice(b"<metadata/>");
+    metadata
+        .get_mut()
+        .unwrap()
+        .set_pts(FRAME_DURATION + gst::ClockTime::from_mseconds(1));
+    assert_eq!(h_metadata.push(metadata), Ok(gst::FlowSuccess::Ok));
+
+    for n in 2..5 {
+        assert_eq!(h_video.push(video_buffer(n)), Ok(gst::FlowSuccess::Ok));
+    }
+
+    for n in 1..4 {
+        let buffer = h_video.pull().unwrap();
+        assert_eq!(buffer.pts(), Some(FRAME_DURATION * n));
+    }
+}
@@ -263,6 +263,16 @@ enum NetworkUsage {
     Under,
 }
 
+impl NetworkUsage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NetworkUsage::Normal => "normal",
+            NetworkUsage::Over => "over",
+            NetworkUsage::Under => "under",
+        }
+    }
+}
+
 struct Detector {
     group: PacketGroup,              // Packet group that is being filled
     prev_group: Option<PacketGroup>, // Group that is ready to be used once "group" is filled
@@ -759,6 +769,47 @@ impl Default for State {
 }
 
 impl State {
+    /// Snapshot of the internal state of the estimator, for monitoring
+    fn stats(&self) -> gst::Structure {
+        let detector = &self.detector;
+        let (last_control_op, last_control_reason) = match &self.last_control_op {
+            BandwidthEstimationOp::Hold => ("hold", ""),
+            BandwidthEstimationOp::Decrease(reason) => ("decrease", reason.as_str()),
+            BandwidthEstimationOp::Increase(reason) => ("increase", reason.as_str()),
+        };
+
+        gst::Structure::builder("application/x-rtpgccbwe-stats")
+            .field("estimated-bitrate", self.estimated_bitrate)
+            .field("target-bitrate-on-delay", self.target_bitrate_on_delay)
+            .field("target-bitrate-on-loss", self.target_bitrate_on_loss)
+            .field("min-bitrate", self.min_bitrate)
+            .field("max-bitrate", self.max_bitrate)
+            .field("network-usage", detector.usage.as_str())
+            .field("measure", detector.measure.num_nanoseconds().unwrap_or(0))
+            .field(
+                "delay-gradient-estimate",
+                detector.estimate.num_nanoseconds().unwrap_or(0),
+            )
+            .field(
+                "overuse-threshold",
+                detector.threshold.num_nanoseconds().unwrap_or(0),
+            )
+            .field(
+                "overuse-estimate",
+                detector
+                    .last_overuse_estimate
+                    .num_nanoseconds()
+                    .unwrap_or(0),
+            )
+            .field("kalman-gain", detector.gain)
+            .field("loss-ratio", detector.loss_ratio())
+            .field("effective-bitrate", detector.effective_bitrate())
+            .field("rtt", detector.rtt().num_nanoseconds().unwrap_or(0))
+            .field("last-control-op", last_control_op)
+            .field("last-control-reason", last_control_reason)
+            .build()
+    }
+
     // 4. sending engine implementing a "leaky bucket"
     fn create_buffer_list(&mut self, bwe: &super::BandwidthEstimator) -> gst::BufferList {
         let now = time::Instant::now();
@@ -1273,6 +1324,35 @@ impl ObjectImpl for BandwidthEstimator {
                     .default_value(DEFAULT_MAX_BITRATE)
                     .mutable_ready()
                     .build(),
+                /*
+                 *  gcc:stats:
+                 *
+                 * Snapshot of the internal state of the estimator, for
+                 * monitoring and debugging purposes. Durations are in
+                 * nanoseconds:
+                 *
+                 * - `network-usage`: output of the over-use detector,
+                 *   "normal", "over" or "under"
+                 * - `measure`: last measured delay variation between two
+                 *   groups of packets
+                 * - `delay-gradient-estimate`: delay gradient estimated by
+                 *   the Kalman filter, the counterpart of the trendline slope
+                 *   of libwebrtc
+                 * - `overuse-threshold`: current adaptive threshold the
+                 *   estimate is compared to
+                 * - `overuse-estimate`: estimate at the time of the last
+                 *   over-use detection
+                 * - `kalman-gain`, `loss-ratio`, `effective-bitrate`, `rtt`
+                 * - `target-bitrate-on-delay`, `target-bitrate-on-loss`
+                 * - `last-control-op` and `last-control-reason`: last
+                 *   decision of the rate controller, "hold", "increase" or
+                 *   "decrease"
+                 */
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Internal state of the bandwidth estimator")
+                    .read_only()
+                    .build(),
             ]
         });
 
@@ -1314,6 +1394,10 @@ impl ObjectImpl for BandwidthEstimator {
                 let state = self.state.lock().unwrap();
                 state.estimated_bitrate.to_value()
             }
+            "stats" => {
+                let state = self.state.lock().unwrap();
+                state.stats().to_value()
+            }
             _ => unimplemented!(),
         }
     }
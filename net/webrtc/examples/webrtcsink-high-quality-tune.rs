@@ -35,7 +35,7 @@ fn main() -> Result<(), Error> {
     // To achieve that, we will connect to deep-element-added on the consumer
     // pipeline.
     webrtcsink.connect("consumer-pipeline-created", false, |values| {
-        let pipeline = values[2].get::<gst::Pipeline>().unwrap();
+        let pipeline = values[2].get::<gst::Bin>().unwrap();
 
         pipeline.connect("deep-element-added", false, |values| {
             let element = values[2].get::<gst::Element>().unwrap();
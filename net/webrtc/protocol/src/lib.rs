@@ -23,7 +23,14 @@ pub enum OutgoingMessage {
     PeerStatusChanged(PeerStatus),
     /// Instructs a peer to generate an offer and inform about the session ID
     #[serde(rename_all = "camelCase")]
-    StartSession { peer_id: String, session_id: String },
+    StartSession {
+        peer_id: String,
+        session_id: String,
+        /// The capabilities advertised by the consumer, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        capabilities: Option<ConsumerCapabilities>,
+    },
     /// Let consumer know that the requested session is starting with the specified identifier
     #[serde(rename_all = "camelCase")]
     SessionStarted { peer_id: String, session_id: String },
@@ -69,12 +76,34 @@ impl PeerStatus {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Capabilities a consumer advertises when requesting a session
+pub struct ConsumerCapabilities {
+    /// Maximum width of the video streams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_width: Option<u32>,
+    /// Maximum height of the video streams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    /// RTP encoding names of the supported codecs, e.g. `["VP8", "OPUS"]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub codecs: Option<Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 /// Ask the server to start a session with a producer peer
 pub struct StartSessionMessage {
     /// Identifies the peer
     pub peer_id: String,
+    /// Capabilities of the consumer, forwarded to the producer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub capabilities: Option<ConsumerCapabilities>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -109,6 +138,13 @@ pub enum PeerMessageInner {
         sdp_m_line_index: u32,
     },
     Sdp(SdpMessage),
+    /// Caps the bitrate the producer sends to the consumer at, 0 lifts
+    /// a previous cap
+    #[serde(rename_all = "camelCase")]
+    MaxBitrate {
+        /// The bitrate in bits per second
+        bitrate: u32,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
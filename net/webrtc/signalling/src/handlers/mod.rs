@@ -83,7 +83,7 @@ impl Handler {
             }
             p::IncomingMessage::SetPeerStatus(status) => self.set_peer_status(peer_id, &status),
             p::IncomingMessage::StartSession(message) => {
-                self.start_session(&message.peer_id, peer_id)
+                self.start_session(&message.peer_id, peer_id, message.capabilities)
             }
             p::IncomingMessage::Peer(peermsg) => self.handle_peer_message(peer_id, peermsg),
             p::IncomingMessage::List => self.list_producers(peer_id),
@@ -110,6 +110,15 @@ impl Handler {
             );
         }
 
+        if matches!(peermsg.peer_message, p::PeerMessageInner::MaxBitrate { .. })
+            && peer_id == session.producer
+        {
+            bail!(
+                r#"cannot forward max bitrate from "{peer_id}" to "{}" as "{peer_id}" is not the consumer"#,
+                session.consumer,
+            );
+        }
+
         self.items.push_back((
             session.other_peer_id(peer_id)?.to_owned(),
             p::OutgoingMessage::Peer(p::PeerMessage {
@@ -262,7 +271,12 @@ impl Handler {
 
     /// Start a session between two peers
     #[instrument(level = "debug", skip(self))]
-    fn start_session(&mut self, producer_id: &str, consumer_id: &str) -> Result<(), Error> {
+    fn start_session(
+        &mut self,
+        producer_id: &str,
+        consumer_id: &str,
+        capabilities: Option<p::ConsumerCapabilities>,
+    ) -> Result<(), Error> {
         self.peers.get(producer_id).map_or_else(
             || Err(anyhow!("No producer with ID: '{producer_id}'")),
             |peer| {
@@ -310,6 +324,7 @@ impl Handler {
             p::OutgoingMessage::StartSession {
                 peer_id: consumer_id.to_string(),
                 session_id: session_id.clone(),
+                capabilities,
             },
         ));
 
@@ -510,6 +525,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -535,6 +551,58 @@ mod tests {
             p::OutgoingMessage::StartSession {
                 peer_id: "consumer".to_string(),
                 session_id: session_id.to_string(),
+                capabilities: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_session_with_capabilities() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut handler = Handler::new(Box::pin(rx));
+
+        new_peer(&mut tx, &mut handler, "producer").await;
+
+        let message = p::IncomingMessage::SetPeerStatus(p::PeerStatus {
+            roles: vec![p::PeerRole::Producer],
+            meta: None,
+            peer_id: None,
+        });
+        tx.send(("producer".to_string(), Some(message)))
+            .await
+            .unwrap();
+
+        new_peer(&mut tx, &mut handler, "consumer").await;
+
+        let capabilities = p::ConsumerCapabilities {
+            max_width: Some(1280),
+            max_height: Some(720),
+            codecs: Some(vec!["VP8".to_string(), "OPUS".to_string()]),
+        };
+
+        let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+            peer_id: "producer".to_string(),
+            capabilities: Some(capabilities.clone()),
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "consumer");
+        let session_id = match sent_message {
+            p::OutgoingMessage::SessionStarted { ref session_id, .. } => session_id.to_string(),
+            _ => panic!("SessionStarted message missing {sent_message:?}"),
+        };
+
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "producer");
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::StartSession {
+                peer_id: "consumer".to_string(),
+                session_id,
+                capabilities: Some(capabilities),
             }
         );
     }
@@ -559,6 +627,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -582,7 +651,8 @@ mod tests {
                 "producer".into(),
                 p::OutgoingMessage::StartSession {
                     peer_id: "consumer".into(),
-                    session_id: session_id.clone()
+                    session_id: session_id.clone(),
+                    capabilities: None,
                 }
             )
         );
@@ -641,6 +711,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -696,6 +767,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -745,6 +817,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -799,6 +872,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -873,6 +947,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -935,6 +1010,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1023,6 +1099,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1063,6 +1140,83 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_max_bitrate() {
+        let (mut tx, rx) = mpsc::unbounded();
+        let mut handler = Handler::new(Box::pin(rx));
+
+        new_peer(&mut tx, &mut handler, "producer").await;
+
+        let message = p::IncomingMessage::SetPeerStatus(p::PeerStatus {
+            roles: vec![p::PeerRole::Producer],
+            meta: None,
+            peer_id: None,
+        });
+        tx.send(("producer".to_string(), Some(message)))
+            .await
+            .unwrap();
+
+        new_peer(&mut tx, &mut handler, "consumer").await;
+
+        let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
+            peer_id: "producer".to_string(),
+            capabilities: None,
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+        assert_eq!(peer_id, "consumer");
+        let session_id = match sent_message {
+            p::OutgoingMessage::SessionStarted {
+                ref peer_id,
+                ref session_id,
+            } => {
+                assert_eq!(peer_id, "producer");
+                session_id.to_string()
+            }
+            _ => panic!("SessionStarted message missing"),
+        };
+
+        let _ = handler.next().await.unwrap();
+
+        let message = p::IncomingMessage::Peer(p::PeerMessage {
+            session_id: session_id.clone(),
+            peer_message: p::PeerMessageInner::MaxBitrate { bitrate: 1_000_000 },
+        });
+        tx.send(("consumer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let (peer_id, sent_message) = handler.next().await.unwrap();
+
+        assert_eq!(peer_id, "producer");
+        assert_eq!(
+            sent_message,
+            p::OutgoingMessage::Peer(p::PeerMessage {
+                session_id: session_id.clone(),
+                peer_message: p::PeerMessageInner::MaxBitrate { bitrate: 1_000_000 }
+            })
+        );
+
+        let message = p::IncomingMessage::Peer(p::PeerMessage {
+            session_id,
+            peer_message: p::PeerMessageInner::MaxBitrate { bitrate: 1_000_000 },
+        });
+        tx.send(("producer".to_string(), Some(message)))
+            .await
+            .unwrap();
+        let response = handler.next().await.unwrap();
+
+        assert_eq!(response,
+            (
+                "producer".into(),
+                p::OutgoingMessage::Error {
+                    details: r#"cannot forward max bitrate from "producer" to "consumer" as "producer" is not the consumer"#.into()
+                }
+            )
+        );
+    }
+
     #[tokio::test]
     async fn test_start_session_no_producer() {
         let (mut tx, rx) = mpsc::unbounded();
@@ -1072,6 +1226,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1107,6 +1262,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1132,6 +1288,7 @@ mod tests {
             p::OutgoingMessage::StartSession {
                 peer_id: "consumer".to_string(),
                 session_id: session_id.clone(),
+                capabilities: None,
             }
         );
 
@@ -1196,6 +1353,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1222,6 +1380,7 @@ mod tests {
             p::OutgoingMessage::StartSession {
                 peer_id: "consumer".to_string(),
                 session_id: session_id.clone(),
+                capabilities: None,
             }
         );
 
@@ -1274,6 +1433,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1308,6 +1468,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("consumer".to_string(), Some(message)))
             .await
@@ -1329,6 +1490,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
 
         tx.send(("consumer".to_string(), Some(message)))
@@ -1378,6 +1540,7 @@ mod tests {
 
         let message = p::IncomingMessage::StartSession(p::StartSessionMessage {
             peer_id: "producer".to_string(),
+            capabilities: None,
         });
         tx.send(("producer-consumer".to_string(), Some(message)))
             .await
@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::protocol as p;
-use crate::signaller::{Signallable, SignallableImpl};
+use crate::signaller::{Signallable, SignallableExt, SignallableImpl, WebRTCSignallerState};
+use crate::utils::TlsSettings;
 use crate::RUNTIME;
 use anyhow::{anyhow, Error};
 use async_tungstenite::tungstenite::Message as WsMessage;
@@ -11,12 +12,14 @@ use gst::glib;
 use gst::glib::once_cell::sync::Lazy;
 use gst::glib::prelude::*;
 use gst::subclass::prelude::*;
-use std::path::PathBuf;
 use std::sync::Mutex;
 use tokio::task;
 
 use aws_config::default_provider::credentials::DefaultCredentialsChain;
-use aws_credential_types::{provider::ProvideCredentials, Credentials};
+use aws_credential_types::{
+    provider::{ProvideCredentials, SharedCredentialsProvider},
+    Credentials,
+};
 use aws_sdk_kinesisvideo::{
     types::{ChannelProtocol, ChannelRole, SingleMasterChannelEndpointConfiguration},
     Client,
@@ -52,7 +55,7 @@ struct State {
 #[derive(Clone)]
 struct Settings {
     address: Option<String>,
-    cafile: Option<PathBuf>,
+    tls: TlsSettings,
     access_key: Option<String>,
     secret_access_key: Option<String>,
     session_token: Option<String>,
@@ -64,7 +67,7 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             address: Some("ws://127.0.0.1:8443".to_string()),
-            cafile: None,
+            tls: Default::default(),
             access_key: None,
             secret_access_key: None,
             session_token: None,
@@ -107,6 +110,7 @@ impl Signaller {
                                             )
                                             .unwrap(),
                                         )),
+                                        &None::<gst::Structure>,
                                     ],
                                 );
                             } else {
@@ -177,15 +181,10 @@ impl Signaller {
     async fn connect(&self) -> Result<(), Error> {
         let settings = self.settings.lock().unwrap().clone();
 
-        let connector = if let Some(path) = settings.cafile {
-            let cert = tokio::fs::read_to_string(&path).await?;
-            let cert = tokio_native_tls::native_tls::Certificate::from_pem(cert.as_bytes())?;
-            let mut connector_builder = tokio_native_tls::native_tls::TlsConnector::builder();
-            let connector = connector_builder.add_root_certificate(cert).build()?;
-            Some(tokio_native_tls::TlsConnector::from(connector))
-        } else {
-            None
-        };
+        let connector = settings
+            .tls
+            .connector()?
+            .map(tokio_native_tls::TlsConnector::from);
 
         let region = aws_config::meta::region::RegionProviderChain::default_provider()
             .or_else(DEFAULT_AWS_REGION)
@@ -196,14 +195,14 @@ impl Signaller {
         let secret_access_key = settings.secret_access_key.as_ref();
         let session_token = settings.session_token.clone();
 
-        let credentials = match (access_key, secret_access_key) {
+        let credentials_provider = match (access_key, secret_access_key) {
             (Some(key), Some(secret_key)) => {
                 gst::debug!(
                     CAT,
                     imp: self,
                     "Using provided access and secret access key"
                 );
-                Ok(Credentials::new(
+                SharedCredentialsProvider::new(Credentials::new(
                     key.clone(),
                     secret_key.clone(),
                     session_token,
@@ -212,30 +211,29 @@ impl Signaller {
                 ))
             }
             _ => {
-                gst::debug!(CAT, imp: self, "Using default AWS credentials");
-                let cred = DefaultCredentialsChain::builder()
-                    .region(region.clone())
-                    .build()
-                    .await;
-                cred.provide_credentials().await
-            }
-        };
-
-        let credentials = match credentials {
-            Err(e) => {
-                anyhow::bail!("Failed to retrieve credentials with error {e}");
+                // Environment, profile, web identity, ECS task role and
+                // IMDSv2, in that order
+                gst::debug!(CAT, imp: self, "Using default AWS credentials chain");
+                SharedCredentialsProvider::new(
+                    DefaultCredentialsChain::builder()
+                        .region(region.clone())
+                        .build()
+                        .await,
+                )
             }
-            Ok(credentials) => credentials,
         };
 
         let Some(channel_name) = settings.channel_name else { anyhow::bail!("Channel name cannot be None!"); };
 
-        let client = Client::new(
-            &aws_config::from_env()
-                .credentials_provider(credentials.clone())
-                .load()
-                .await,
-        );
+        // The SDK clients cache the credentials of the provider and refresh
+        // them ahead of their expiration
+        let sdk_config = aws_config::from_env()
+            .region(region.clone())
+            .credentials_provider(credentials_provider.clone())
+            .load()
+            .await;
+
+        let client = Client::new(&sdk_config);
 
         let resp = client
             .describe_signaling_channel()
@@ -306,14 +304,9 @@ impl Signaller {
             endpoint_https_uri
         );
 
-        let signaling_config = aws_sdk_kinesisvideosignaling::config::Builder::from(
-            &aws_config::from_env()
-                .credentials_provider(credentials.clone())
-                .load()
-                .await,
-        )
-        .endpoint_url(endpoint_https_uri)
-        .build();
+        let signaling_config = aws_sdk_kinesisvideosignaling::config::Builder::from(&sdk_config)
+            .endpoint_url(endpoint_https_uri)
+            .build();
 
         let signaling_client = SignalingClient::from_conf(signaling_config);
 
@@ -367,6 +360,22 @@ impl Signaller {
             }),
         );
 
+        // Temporary credentials may have rotated since the clients above
+        // were created, sign the websocket URL with the current ones
+        let credentials = credentials_provider
+            .provide_credentials()
+            .await
+            .map_err(|err| anyhow!("Failed to retrieve credentials with error {err}"))?;
+
+        if let Some(expiry) = credentials.expiry() {
+            gst::debug!(
+                CAT,
+                imp: self,
+                "Signing with temporary credentials expiring at {}",
+                DateTime::<Utc>::from(expiry)
+            );
+        }
+
         let current_time = Utc::now();
 
         let signer = signer::SigV4Signer::new();
@@ -418,6 +427,8 @@ impl Signaller {
             async_tungstenite::tokio::connect_async_with_tls_connector(url, connector).await?;
 
         gst::info!(CAT, imp: self, "connected");
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connected, None);
 
         // Channel for asynchronously sending out websocket message
         let (mut ws_sink, mut ws_stream) = ws.split();
@@ -479,11 +490,17 @@ impl Signaller {
                         }
                         Ok(WsMessage::Close(reason)) => {
                             gst::info!(CAT, imp: imp, "websocket connection closed: {:?}", reason);
+                            imp.obj()
+                                .set_connection_state(WebRTCSignallerState::Disconnected, None);
                             imp.obj().emit_by_name::<()>("shutdown", &[]);
                             break;
                         }
                         Ok(_) => (),
                         Err(err) => {
+                            imp.obj().set_connection_state(
+                                WebRTCSignallerState::Error,
+                                Some(&format!("Error receiving: {err}")),
+                            );
                             imp.obj().emit_by_name::<()>(
                                 "error",
                                 &[&format!("{:?}", anyhow!("Error receiving: {err}"))],
@@ -514,9 +531,11 @@ impl SignallableImpl for Signaller {
     fn start(&self) {
         let this = self.obj().clone();
         let imp = self.downgrade();
+        this.set_connection_state(WebRTCSignallerState::Connecting, None);
         task::spawn(async move {
             if let Some(imp) = imp.upgrade() {
                 if let Err(err) = imp.connect().await {
+                    this.set_connection_state(WebRTCSignallerState::Error, Some(&err.to_string()));
                     this.emit_by_name::<()>("error", &[&format!("{:?}", anyhow!(err))]);
                 }
             }
@@ -620,6 +639,10 @@ impl SignallableImpl for Signaller {
                 }
             });
         }
+        drop(state);
+
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Disconnected, None);
     }
 
     fn end_session(&self, session_id: &str) {
@@ -646,14 +669,24 @@ impl ObjectImpl for Signaller {
                     .blurb("Address of the signalling server")
                     .default_value("ws://127.0.0.1:8443")
                     .build(),
-                glib::ParamSpecString::builder("cafile")
-                    .nick("CA file")
-                    .blurb("Path to a Certificate file to add to the set of roots the TLS connector will trust")
-                    .build(),
                 glib::ParamSpecString::builder("channel-name")
                     .nick("Channel name")
                     .blurb("Name of the channel to connect as master to")
                     .build(),
+                glib::ParamSpecString::builder("access-key")
+                    .nick("Access Key")
+                    .blurb("AWS access key ID, the default credentials chain is used if not set")
+                    .build(),
+                glib::ParamSpecString::builder("secret-access-key")
+                    .nick("Secret Access Key")
+                    .blurb(
+                        "AWS secret access key, the default credentials chain is used if not set",
+                    )
+                    .build(),
+                glib::ParamSpecString::builder("session-token")
+                    .nick("Session Token")
+                    .blurb("AWS session token of temporary access keys")
+                    .build(),
                 glib::ParamSpecInt::builder("ping-timeout")
                     .nick("Ping Timeout")
                     .blurb("How often (in seconds) to send pings to keep the websocket alive")
@@ -661,6 +694,9 @@ impl ObjectImpl for Signaller {
                     .minimum(1)
                     .build(),
             ]
+            .into_iter()
+            .chain(TlsSettings::properties())
+            .collect()
         });
 
         PROPERTIES.as_ref()
@@ -680,10 +716,9 @@ impl ObjectImpl for Signaller {
                     gst::error!(CAT, "address can't be None");
                 }
             }
-            "cafile" => {
-                let value: String = value.get().unwrap();
+            name if TlsSettings::PROPERTIES.contains(&name) => {
                 let mut settings = self.settings.lock().unwrap();
-                settings.cafile = Some(value.into());
+                settings.tls.set_property(name, value);
             }
             "access-key" => {
                 let mut settings = self.settings.lock().unwrap();
@@ -712,10 +747,9 @@ impl ObjectImpl for Signaller {
     fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
         match pspec.name() {
             "address" => self.settings.lock().unwrap().address.to_value(),
-            "cafile" => {
+            name if TlsSettings::PROPERTIES.contains(&name) => {
                 let settings = self.settings.lock().unwrap();
-                let cafile = settings.cafile.as_ref();
-                cafile.and_then(|file| file.to_str()).to_value()
+                settings.tls.property(name)
             }
             "access-key" => {
                 let settings = self.settings.lock().unwrap();
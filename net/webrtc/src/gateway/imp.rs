@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::utils::Codecs;
+use crate::webrtcsink::WhepServerSink;
+use crate::webrtcsrc::WhipServerSrc;
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use std::sync::Mutex;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "whipwhepgateway",
+        gst::DebugColorFlags::empty(),
+        Some("WHIP / WHEP gateway"),
+    )
+});
+
+const DEFAULT_WHIP_HOST_ADDR: &str = "http://127.0.0.1:8190";
+const DEFAULT_WHEP_HOST_ADDR: &str = "http://127.0.0.1:8191";
+const DEFAULT_VIDEO_STREAMS: u32 = 1;
+const DEFAULT_AUDIO_STREAMS: u32 = 1;
+
+#[derive(Clone)]
+struct Settings {
+    video_streams: u32,
+    audio_streams: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            video_streams: DEFAULT_VIDEO_STREAMS,
+            audio_streams: DEFAULT_AUDIO_STREAMS,
+        }
+    }
+}
+
+pub struct WhipWhepGateway {
+    whipsrc: WhipServerSrc,
+    whepsink: WhepServerSink,
+    settings: Mutex<Settings>,
+    /// The pads requested on both ends, linked two by two
+    requested_pads: Mutex<Vec<(gst::Pad, gst::Pad)>>,
+}
+
+impl WhipWhepGateway {
+    /// Links one pad of the ingest to one input of the egress per stream.
+    ///
+    /// The ingest pads are requested with the encoded caps the egress
+    /// accepts as is, the streams are thus only depayloaded and parsed,
+    /// then payloaded again for each viewer with the payload types it
+    /// negotiated
+    fn link_streams(&self) -> Result<(), glib::BoolError> {
+        let settings = self.settings.lock().unwrap().clone();
+        let mut requested_pads = self.requested_pads.lock().unwrap();
+
+        for (kind, n_streams, codecs) in [
+            ("video", settings.video_streams, Codecs::video_codecs()),
+            ("audio", settings.audio_streams, Codecs::audio_codecs()),
+        ] {
+            let caps = codecs
+                .iter()
+                .flat_map(|codec| codec.caps.iter().map(|s| s.to_owned()))
+                .collect::<gst::Caps>();
+
+            for _ in 0..n_streams {
+                let srcpad = self
+                    .whipsrc
+                    .pad_template(&format!("{kind}_req_%u"))
+                    .and_then(|templ| self.whipsrc.request_pad(&templ, None, Some(&caps)))
+                    .ok_or_else(|| glib::bool_error!("Failed to request {kind} ingest pad"))?;
+                let sinkpad = self
+                    .whepsink
+                    .request_pad_simple(&format!("{kind}_%u"))
+                    .ok_or_else(|| glib::bool_error!("Failed to request {kind} egress pad"))?;
+
+                gst::debug!(
+                    CAT,
+                    imp: self,
+                    "Linking {} to {}",
+                    srcpad.name(),
+                    sinkpad.name()
+                );
+
+                srcpad
+                    .link(&sinkpad)
+                    .map_err(|err| glib::bool_error!("Failed to link {kind} stream: {err}"))?;
+                requested_pads.push((srcpad, sinkpad));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn release_streams(&self) {
+        for (srcpad, sinkpad) in self.requested_pads.lock().unwrap().drain(..) {
+            let _ = srcpad.unlink(&sinkpad);
+            self.whipsrc.release_request_pad(&srcpad);
+            self.whepsink.release_request_pad(&sinkpad);
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for WhipWhepGateway {
+    const NAME: &'static str = "GstWhipWhepGateway";
+    type Type = super::WhipWhepGateway;
+    type ParentType = gst::Bin;
+
+    fn new() -> Self {
+        Self {
+            whipsrc: glib::Object::new(),
+            whepsink: glib::Object::new(),
+            settings: Default::default(),
+            requested_pads: Default::default(),
+        }
+    }
+}
+
+impl ObjectImpl for WhipWhepGateway {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.whipsrc
+            .property::<glib::Object>("signaller")
+            .set_property("host-addr", DEFAULT_WHIP_HOST_ADDR);
+        self.whepsink
+            .property::<glib::Object>("signaller")
+            .set_property("host-addr", DEFAULT_WHEP_HOST_ADDR);
+
+        let obj = self.obj();
+        obj.add_many([
+            self.whipsrc.upcast_ref::<gst::Element>(),
+            self.whepsink.upcast_ref(),
+        ])
+        .unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstWhipWhepGateway:whip-host-addr:
+                 *
+                 * The address WHIP clients POST their offer to, at
+                 * `<whip-host-addr>/endpoint`.
+                 */
+                glib::ParamSpecString::builder("whip-host-addr")
+                    .nick("WHIP Host Address")
+                    .blurb("The address the WHIP server listens on, e.g.: http://0.0.0.0:8190")
+                    .default_value(DEFAULT_WHIP_HOST_ADDR)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWhipWhepGateway:whep-host-addr:
+                 *
+                 * The address WHEP players POST their offer to, at
+                 * `<whep-host-addr>/endpoint`.
+                 */
+                glib::ParamSpecString::builder("whep-host-addr")
+                    .nick("WHEP Host Address")
+                    .blurb("The address the WHEP server listens on, e.g.: http://0.0.0.0:8191")
+                    .default_value(DEFAULT_WHEP_HOST_ADDR)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWhipWhepGateway:video-streams:
+                 *
+                 * The number of video streams the WHIP client sends.
+                 */
+                glib::ParamSpecUInt::builder("video-streams")
+                    .nick("Video Streams")
+                    .blurb("The number of video streams to forward")
+                    .maximum(16)
+                    .default_value(DEFAULT_VIDEO_STREAMS)
+                    .build(),
+                /**
+                 * GstWhipWhepGateway:audio-streams:
+                 *
+                 * The number of audio streams the WHIP client sends.
+                 */
+                glib::ParamSpecUInt::builder("audio-streams")
+                    .nick("Audio Streams")
+                    .blurb("The number of audio streams to forward")
+                    .maximum(16)
+                    .default_value(DEFAULT_AUDIO_STREAMS)
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "whip-host-addr" => {
+                self.whipsrc
+                    .property::<glib::Object>("signaller")
+                    .set_property_from_value("host-addr", value);
+            }
+            "whep-host-addr" => {
+                self.whepsink
+                    .property::<glib::Object>("signaller")
+                    .set_property_from_value("host-addr", value);
+            }
+            "video-streams" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.video_streams = value.get().expect("type checked upstream");
+            }
+            "audio-streams" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.audio_streams = value.get().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "whip-host-addr" => self
+                .whipsrc
+                .property::<glib::Object>("signaller")
+                .property_value("host-addr"),
+            "whep-host-addr" => self
+                .whepsink
+                .property::<glib::Object>("signaller")
+                .property_value("host-addr"),
+            "video-streams" => self.settings.lock().unwrap().video_streams.to_value(),
+            "audio-streams" => self.settings.lock().unwrap().audio_streams.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for WhipWhepGateway {}
+
+impl ElementImpl for WhipWhepGateway {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "WhipWhepGateway",
+                "Source/Sink/Network/WebRTC",
+                "Serves the streams received from a WHIP client to WHEP players without re-encoding",
+                "Mathieu Duponchelle <mathieu@centricular.com>, Thibault Saunier <tsaunier@igalia.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        if let gst::StateChange::NullToReady = transition {
+            // Pads can only be requested on the ingest before it starts
+            if let Err(err) = self.link_streams() {
+                gst::element_error!(
+                    self.obj(),
+                    gst::CoreError::Negotiation,
+                    ["Failed to link the ingest to the egress: {}", err]
+                );
+                self.release_streams();
+                return Err(gst::StateChangeError);
+            }
+        }
+
+        let ret = self.parent_change_state(transition);
+
+        if let gst::StateChange::ReadyToNull = transition {
+            self.release_streams();
+        }
+
+        ret
+    }
+}
+
+impl BinImpl for WhipWhepGateway {}
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: MPL-2.0
+
+/**
+ * SECTION:element-whipwhepgateway
+ *
+ * `whipwhepgateway` receives the streams of a WHIP client, such as OBS or
+ * `whipclientsink`, and serves them to any number of WHEP players, without
+ * decoding nor encoding them again:
+ *
+ * ``` bash
+ * gst-launch-1.0 whipwhepgateway whip-host-addr=http://0.0.0.0:8190 whep-host-addr=http://0.0.0.0:8191
+ * ```
+ *
+ * The WHIP client POSTs its offer to `<whip-host-addr>/endpoint` and the players
+ * to `<whep-host-addr>/endpoint`. Only one WHIP client is accepted at a time,
+ * and the number of streams it sends must match #GstWhipWhepGateway:video-streams
+ * and #GstWhipWhepGateway:audio-streams.
+ *
+ * The received streams are only depayloaded and parsed, then payloaded again for
+ * each player, with the payload types negotiated with it. Retransmissions and FEC
+ * are handled per player by its own `webrtcbin`. The codecs offered to players
+ * are those sent by the WHIP client, and players not supporting them are refused.
+ *
+ * The element posts EOS once the WHIP client ends its session, the sessions of
+ * the players then end.
+ */
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct WhipWhepGateway(ObjectSubclass<imp::WhipWhepGateway>) @extends gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy;
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "whipwhepgateway",
+        gst::Rank::None,
+        WhipWhepGateway::static_type(),
+    )
+}
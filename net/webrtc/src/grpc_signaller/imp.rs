@@ -0,0 +1,808 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::proto::{self, client_message, server_message};
+use crate::signaller::{prelude::*, Signallable, WebRTCSignallerRole, WebRTCSignallerState};
+use crate::utils::{gvalue_to_json, serialize_json_object};
+use crate::RUNTIME;
+use anyhow::{anyhow, Error};
+use futures::channel::mpsc;
+use futures::prelude::*;
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::glib::prelude::*;
+use gst::subclass::prelude::*;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtc-grpc-signaller",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC gRPC signaller"),
+    )
+});
+
+const DEFAULT_URI: &str = "http://127.0.0.1:50051";
+const DEFAULT_TIMEOUT: u32 = 20;
+
+pub struct Settings {
+    uri: String,
+    producer_peer_id: Option<String>,
+    role: WebRTCSignallerRole,
+    /// In seconds
+    timeout: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            uri: DEFAULT_URI.to_string(),
+            producer_peer_id: None,
+            role: Default::default(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Signaller {
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Sender for the messages of the request stream
+    sender: Option<mpsc::Sender<proto::ClientMessage>>,
+    receive_task_handle: Option<task::JoinHandle<()>>,
+    producers: HashSet<String>,
+    client_id: Option<String>,
+}
+
+impl Signaller {
+    async fn connect(&self) -> Result<(), Error> {
+        let (uri, role, timeout) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.uri.clone(), settings.role, settings.timeout)
+        };
+
+        if let WebRTCSignallerRole::Consumer = role {
+            self.producer_peer_id()
+                .ok_or_else(|| anyhow!("No target producer peer id set"))?;
+        }
+
+        let mut endpoint = tonic::transport::Endpoint::from_shared(uri.clone())?;
+        if timeout > 0 {
+            endpoint = endpoint.connect_timeout(Duration::from_secs(timeout as u64));
+        }
+        if uri.starts_with("https:") {
+            endpoint = endpoint.tls_config(tonic::transport::ClientTlsConfig::new())?;
+        }
+
+        let channel = endpoint.connect().await?;
+        let mut client = tonic::client::Grpc::new(channel);
+        client.ready().await?;
+
+        // 1000 is completely arbitrary, we simply don't want infinite piling
+        // up of messages as with unbounded
+        let (sender, receiver) = mpsc::channel::<proto::ClientMessage>(1000);
+        let codec =
+            tonic::codec::ProstCodec::<proto::ClientMessage, proto::ServerMessage>::default();
+        let mut stream = client
+            .streaming(
+                tonic::Request::new(receiver),
+                tonic::codegen::http::uri::PathAndQuery::from_static(proto::CONNECT_PATH),
+                codec,
+            )
+            .await?
+            .into_inner();
+
+        gst::info!(CAT, imp: self, "connected");
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connected, None);
+
+        // Before the welcome message is answered
+        self.state.lock().unwrap().sender = Some(sender);
+
+        let meta = self
+            .obj()
+            .emit_by_name::<Option<gst::Structure>>("request-meta", &[])
+            .and_then(|meta| gvalue_to_json(&meta.to_value()))
+            .map(|meta| meta.to_string())
+            .unwrap_or_default();
+
+        let receive_task_handle =
+            RUNTIME.spawn(glib::clone!(@weak-allow-none self as this => async move {
+                loop {
+                    let res = stream.message().await;
+
+                    let Some(ref this) = this else {
+                        break;
+                    };
+
+                    match res {
+                        Ok(Some(msg)) => {
+                            if let ControlFlow::Break(_) = this.handle_message(msg, &meta) {
+                                break;
+                            }
+                        }
+                        Ok(None) => {
+                            gst::info!(CAT, imp: this, "Stream closed by the server");
+                            this.obj().set_connection_state(WebRTCSignallerState::Disconnected, None);
+                            break;
+                        }
+                        Err(status) => {
+                            this.obj().set_connection_state(
+                                WebRTCSignallerState::Error,
+                                Some(&status.to_string()),
+                            );
+                            this.obj().emit_by_name::<()>(
+                                "error",
+                                &[&format!("Error receiving: {}", status)],
+                            );
+                            break;
+                        }
+                    }
+                }
+
+                let msg = "Stopped receiving";
+                this.map_or_else(|| gst::info!(CAT, "{msg}"),
+                    |this| gst::info!(CAT, imp: this, "{msg}")
+                );
+            }));
+
+        self.state.lock().unwrap().receive_task_handle = Some(receive_task_handle);
+
+        Ok(())
+    }
+
+    fn set_status(&self, meta: &str, peer_id: &str) {
+        self.state.lock().unwrap().client_id = Some(peer_id.to_string());
+
+        let role = self.settings.lock().unwrap().role;
+        let roles = match role {
+            WebRTCSignallerRole::Consumer => vec![],
+            WebRTCSignallerRole::Producer => vec![proto::PeerRole::Producer as i32],
+            WebRTCSignallerRole::Listener => vec![proto::PeerRole::Listener as i32],
+        };
+
+        self.send(client_message::Message::SetPeerStatus(
+            proto::SetPeerStatus {
+                roles,
+                meta: meta.to_string(),
+            },
+        ));
+
+        if matches!(role, WebRTCSignallerRole::Listener) {
+            self.send(client_message::Message::List(proto::ListProducers {}));
+        }
+    }
+
+    fn producer_peer_id(&self) -> Option<String> {
+        let settings = self.settings.lock().unwrap();
+
+        settings.producer_peer_id.clone()
+    }
+
+    fn send(&self, msg: client_message::Message) {
+        let state = self.state.lock().unwrap();
+        if let Some(mut sender) = state.sender.clone() {
+            RUNTIME.spawn(glib::clone!(@weak self as this => async move {
+                gst::log!(CAT, imp: this, "Sending message {:?}", msg);
+
+                let msg = proto::ClientMessage { message: Some(msg) };
+                if let Err(err) = sender.send(msg).await {
+                    this.obj().emit_by_name::<()>("error", &[&format!("Error: {}", err)]);
+                }
+            }));
+        }
+    }
+
+    fn start_session(&self) {
+        let role = self.settings.lock().unwrap().role;
+        if matches!(role, WebRTCSignallerRole::Consumer) {
+            let target_producer = self.producer_peer_id().unwrap();
+
+            self.send(client_message::Message::StartSession(proto::StartSession {
+                peer_id: target_producer.clone(),
+            }));
+
+            gst::info!(
+                CAT,
+                imp: self,
+                "Started session with producer peer id {target_producer}",
+            );
+        }
+    }
+
+    /// The meta of a peer, a JSON object if not empty
+    fn parse_meta(&self, meta: &str) -> Option<gst::Structure> {
+        if meta.is_empty() {
+            return None;
+        }
+
+        match serde_json::from_str::<serde_json::Value>(meta) {
+            Ok(serde_json::Value::Object(v)) => Some(serialize_json_object(&v)),
+            _ => {
+                gst::error!(CAT, imp: self, "Invalid json meta: {meta}");
+                None
+            }
+        }
+    }
+
+    fn handle_message(&self, msg: proto::ServerMessage, meta: &str) -> ControlFlow<()> {
+        gst::trace!(CAT, imp: self, "Received message {:?}", msg);
+
+        let Some(msg) = msg.message else {
+            gst::warning!(CAT, imp: self, "Ignoring empty or unknown message from server");
+            return ControlFlow::Continue(());
+        };
+
+        match msg {
+            server_message::Message::Welcome(proto::Welcome { peer_id }) => {
+                self.set_status(meta, &peer_id);
+                self.start_session();
+            }
+            server_message::Message::PeerStatusChanged(proto::PeerStatusChanged {
+                peer_id,
+                roles,
+                meta,
+            }) => {
+                let meta = self.parse_meta(&meta);
+
+                let mut state = self.state.lock().unwrap();
+                if roles.contains(&(proto::PeerRole::Producer as i32)) {
+                    if !state.producers.contains(&peer_id) {
+                        state.producers.insert(peer_id.clone());
+                        drop(state);
+
+                        self.obj()
+                            .emit_by_name::<()>("producer-added", &[&peer_id, &meta, &true]);
+                    }
+                } else if state.producers.remove(&peer_id) {
+                    drop(state);
+
+                    self.obj()
+                        .emit_by_name::<()>("producer-removed", &[&peer_id, &meta]);
+                }
+            }
+            server_message::Message::SessionRequested(proto::SessionRequested {
+                session_id,
+                peer_id,
+            }) => {
+                self.obj().emit_by_name::<()>(
+                    "session-requested",
+                    &[
+                        &session_id,
+                        &peer_id,
+                        &None::<gst_webrtc::WebRTCSessionDescription>,
+                        &None::<gst::Structure>,
+                    ],
+                );
+            }
+            server_message::Message::SessionStarted(proto::SessionStarted {
+                session_id,
+                peer_id,
+            }) => {
+                self.obj()
+                    .emit_by_name::<()>("session-started", &[&session_id, &peer_id]);
+            }
+            server_message::Message::EndSession(proto::EndSession { session_id }) => {
+                gst::info!(CAT, imp: self, "Session {session_id} ended");
+
+                self.obj()
+                    .emit_by_name::<bool>("session-ended", &[&session_id]);
+            }
+            server_message::Message::Sdp(proto::SessionDescription {
+                session_id,
+                r#type,
+                sdp,
+            }) => {
+                let desc_type = match proto::SdpType::try_from(r#type) {
+                    Ok(proto::SdpType::Offer) => gst_webrtc::WebRTCSDPType::Offer,
+                    Ok(proto::SdpType::Answer) => gst_webrtc::WebRTCSDPType::Answer,
+                    Err(_) => {
+                        self.obj().emit_by_name::<()>(
+                            "error",
+                            &[&format!("Unknown SDP type {}", r#type)],
+                        );
+
+                        return ControlFlow::Break(());
+                    }
+                };
+
+                let sdp = match gst_sdp::SDPMessage::parse_buffer(sdp.as_bytes()) {
+                    Ok(sdp) => sdp,
+                    Err(err) => {
+                        self.obj().emit_by_name::<()>(
+                            "error",
+                            &[&format!("Error parsing SDP: {sdp} {err:?}")],
+                        );
+
+                        return ControlFlow::Break(());
+                    }
+                };
+
+                let desc = gst_webrtc::WebRTCSessionDescription::new(desc_type, sdp);
+                self.obj()
+                    .emit_by_name::<()>("session-description", &[&session_id, &desc]);
+            }
+            server_message::Message::Ice(proto::IceCandidate {
+                session_id,
+                sdp_m_line_index,
+                candidate,
+            }) => {
+                let sdp_mid: Option<String> = None;
+                self.obj().emit_by_name::<()>(
+                    "handle-ice",
+                    &[&session_id, &sdp_m_line_index, &sdp_mid, &candidate],
+                );
+            }
+            server_message::Message::List(proto::ProducerList { producers }) => {
+                for producer in producers {
+                    let mut state = self.state.lock().unwrap();
+                    if !state.producers.contains(&producer.peer_id) {
+                        state.producers.insert(producer.peer_id.clone());
+                        drop(state);
+
+                        let meta = self.parse_meta(&producer.meta);
+                        self.obj().emit_by_name::<()>(
+                            "producer-added",
+                            &[&producer.peer_id, &meta, &false],
+                        );
+                    }
+                }
+            }
+            server_message::Message::Error(proto::Error { details }) => {
+                self.obj().emit_by_name::<()>(
+                    "error",
+                    &[&format!("Error message from server: {details}")],
+                );
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Signaller {
+    const NAME: &'static str = "GstGrpcWebRTCSignaller";
+    type Type = super::GrpcSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for Signaller {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPS: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstGrpcWebRTCSignaller:uri:
+                 *
+                 * URI of the gRPC signalling service, `https` URIs are
+                 * connected to over TLS.
+                 */
+                glib::ParamSpecString::builder("uri")
+                    .nick("URI")
+                    .blurb("URI of the gRPC signalling service")
+                    .default_value(Some(DEFAULT_URI))
+                    .build(),
+                glib::ParamSpecString::builder("producer-peer-id")
+                    .nick("Producer peer ID")
+                    .blurb("ID of the producer to request a session from, as a consumer")
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("role", WebRTCSignallerRole::Consumer)
+                    .nick("Role")
+                    .blurb("Role of the peer")
+                    .build(),
+                glib::ParamSpecUInt::builder("timeout")
+                    .nick("Timeout")
+                    .blurb("Value in seconds to wait for the connection to the gRPC service (0 = No timeout).")
+                    .maximum(3600)
+                    .default_value(DEFAULT_TIMEOUT)
+                    .build(),
+                glib::ParamSpecString::builder("client-id")
+                    .nick("Client ID")
+                    .blurb("ID the server assigned to this peer")
+                    .read_only()
+                    .build(),
+            ]
+        });
+
+        PROPS.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "uri" => {
+                settings.uri = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+                    .unwrap_or_else(|| DEFAULT_URI.to_string())
+            }
+            "producer-peer-id" => {
+                if !matches!(settings.role, WebRTCSignallerRole::Consumer) {
+                    gst::warning!(
+                        CAT,
+                        "Setting `producer-peer-id` doesn't make sense for {:?}",
+                        settings.role
+                    );
+                } else {
+                    settings.producer_peer_id = value
+                        .get::<Option<String>>()
+                        .expect("type checked upstream");
+                }
+            }
+            "role" => {
+                settings.role = value
+                    .get::<WebRTCSignallerRole>()
+                    .expect("type checked upstream")
+            }
+            "timeout" => {
+                settings.timeout = value.get::<u32>().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "uri" => settings.uri.to_value(),
+            "producer-peer-id" => settings.producer_peer_id.to_value(),
+            "role" => settings.role.to_value(),
+            "timeout" => settings.timeout.to_value(),
+            "client-id" => self.state.lock().unwrap().client_id.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl SignallableImpl for Signaller {
+    fn start(&self) {
+        gst::info!(CAT, imp: self, "Starting");
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connecting, None);
+        RUNTIME.spawn(glib::clone!(@weak self as this => async move {
+            if let Err(err) = this.connect().await {
+                this.obj().set_connection_state(WebRTCSignallerState::Error, Some(&err.to_string()));
+                this.obj().emit_by_name::<()>("error", &[&format!("Error connecting: {}", err)]);
+            }
+        }));
+    }
+
+    fn stop(&self) {
+        gst::info!(CAT, imp: self, "Stopping now");
+
+        let (sender, receive_task_handle) = {
+            let mut state = self.state.lock().unwrap();
+            state.producers.clear();
+            state.client_id = None;
+
+            (state.sender.take(), state.receive_task_handle.take())
+        };
+
+        // Closing the request stream lets the server know we are gone
+        if let Some(mut sender) = sender {
+            sender.close_channel();
+        }
+
+        if let Some(handle) = receive_task_handle {
+            handle.abort();
+            if let Err(err) = RUNTIME.block_on(handle) {
+                if !err.is_cancelled() {
+                    gst::warning!(CAT, imp: self, "Error while joining receive task: {}", err);
+                }
+            }
+        }
+
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Disconnected, None);
+    }
+
+    fn send_sdp(&self, session_id: &str, sdp: &gst_webrtc::WebRTCSessionDescription) {
+        gst::debug!(CAT, imp: self, "Sending SDP {sdp:#?}");
+
+        let r#type = match sdp.type_() {
+            gst_webrtc::WebRTCSDPType::Answer => proto::SdpType::Answer,
+            _ => proto::SdpType::Offer,
+        };
+
+        self.send(client_message::Message::Sdp(proto::SessionDescription {
+            session_id: session_id.to_string(),
+            r#type: r#type as i32,
+            sdp: sdp.sdp().as_text().unwrap(),
+        }));
+    }
+
+    fn add_ice(
+        &self,
+        session_id: &str,
+        candidate: &str,
+        sdp_m_line_index: u32,
+        _sdp_mid: Option<String>,
+    ) {
+        gst::debug!(
+            CAT,
+            imp: self,
+            "Adding ice candidate {candidate:?} for {sdp_m_line_index:?} on session {session_id}"
+        );
+
+        self.send(client_message::Message::Ice(proto::IceCandidate {
+            session_id: session_id.to_string(),
+            sdp_m_line_index,
+            candidate: candidate.to_string(),
+        }));
+    }
+
+    fn end_session(&self, session_id: &str) {
+        gst::debug!(CAT, imp: self, "Signalling session done {}", session_id);
+
+        self.send(client_message::Message::EndSession(proto::EndSession {
+            session_id: session_id.to_string(),
+        }));
+    }
+}
+
+impl GstObjectImpl for Signaller {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::Pin;
+    use std::sync::{mpsc as std_mpsc, Arc};
+    use std::task::{Context, Poll};
+    use tonic::codegen::{http, BoxFuture, StdError};
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    type ServerStream =
+        Pin<Box<dyn Stream<Item = Result<proto::ServerMessage, tonic::Status>> + Send>>;
+
+    /// A signalling service accepting a single client, forwarding the
+    /// messages it receives and sending the ones queued by the test
+    #[derive(Clone)]
+    struct MockService {
+        received: Arc<Mutex<std_mpsc::Sender<proto::ClientMessage>>>,
+        outgoing: Arc<Mutex<Option<mpsc::UnboundedReceiver<proto::ServerMessage>>>>,
+    }
+
+    impl tonic::server::StreamingService<proto::ClientMessage> for MockService {
+        type Response = proto::ServerMessage;
+        type ResponseStream = ServerStream;
+        type Future = BoxFuture<tonic::Response<ServerStream>, tonic::Status>;
+
+        fn call(
+            &mut self,
+            request: tonic::Request<tonic::Streaming<proto::ClientMessage>>,
+        ) -> Self::Future {
+            let received = self.received.clone();
+            let outgoing = self.outgoing.lock().unwrap().take();
+
+            Box::pin(async move {
+                let outgoing =
+                    outgoing.ok_or_else(|| tonic::Status::resource_exhausted("single client"))?;
+
+                let mut incoming = request.into_inner();
+                tokio::spawn(async move {
+                    while let Ok(Some(msg)) = incoming.message().await {
+                        let _ = received.lock().unwrap().send(msg);
+                    }
+                });
+
+                Ok(tonic::Response::new(
+                    Box::pin(outgoing.map(Ok::<_, tonic::Status>)) as ServerStream,
+                ))
+            })
+        }
+    }
+
+    impl<B> tonic::codegen::Service<http::Request<B>> for MockService
+    where
+        B: tonic::codegen::Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            assert_eq!(req.uri().path(), proto::CONNECT_PATH);
+
+            let service = self.clone();
+            Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.streaming(service, req).await)
+            })
+        }
+    }
+
+    impl tonic::server::NamedService for MockService {
+        const NAME: &'static str = "gstreamer.webrtc.signalling.v1.Signalling";
+    }
+
+    /// Our custom test harness around a signaller connected to a mock
+    /// signalling service
+    struct Harness {
+        signaller: super::super::GrpcSignaller,
+        received: std_mpsc::Receiver<proto::ClientMessage>,
+        outgoing: mpsc::UnboundedSender<proto::ServerMessage>,
+        server_handle: task::JoinHandle<()>,
+    }
+
+    impl Harness {
+        fn new(role: WebRTCSignallerRole) -> Harness {
+            gst::init().unwrap();
+
+            let (received_sender, received) = std_mpsc::channel();
+            let (outgoing, outgoing_receiver) = mpsc::unbounded();
+            let service = MockService {
+                received: Arc::new(Mutex::new(received_sender)),
+                outgoing: Arc::new(Mutex::new(Some(outgoing_receiver))),
+            };
+
+            let listener = RUNTIME
+                .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+                .unwrap();
+            let addr = listener.local_addr().unwrap();
+            let incoming = futures::stream::unfold(listener, |listener| async move {
+                let res = listener.accept().await.map(|(stream, _)| stream);
+                Some((res, listener))
+            });
+
+            let server_handle = RUNTIME.spawn(async move {
+                tonic::transport::Server::builder()
+                    .add_service(service)
+                    .serve_with_incoming(incoming)
+                    .await
+                    .unwrap();
+            });
+
+            let signaller = super::super::GrpcSignaller::new(role);
+            signaller.set_property("uri", format!("http://{addr}"));
+
+            Harness {
+                signaller,
+                received,
+                outgoing,
+                server_handle,
+            }
+        }
+
+        fn send(&self, msg: server_message::Message) {
+            self.outgoing
+                .unbounded_send(proto::ServerMessage { message: Some(msg) })
+                .unwrap();
+        }
+
+        fn wait_for_message(&self) -> client_message::Message {
+            self.received
+                .recv_timeout(TIMEOUT)
+                .unwrap()
+                .message
+                .unwrap()
+        }
+    }
+
+    impl Drop for Harness {
+        fn drop(&mut self) {
+            self.signaller.stop();
+            self.server_handle.abort();
+        }
+    }
+
+    #[test]
+    fn producer_session() {
+        let h = Harness::new(WebRTCSignallerRole::Producer);
+
+        let (sender, receiver) = std_mpsc::channel();
+        let sender = Mutex::new(sender);
+        h.signaller
+            .connect("session-requested", false, move |args| {
+                let session_id = args[1].get::<String>().unwrap();
+                let peer_id = args[2].get::<String>().unwrap();
+                let _ = sender.lock().unwrap().send((session_id, peer_id));
+                None
+            });
+
+        h.send(server_message::Message::Welcome(proto::Welcome {
+            peer_id: "producer".to_string(),
+        }));
+        h.signaller.start();
+
+        match h.wait_for_message() {
+            client_message::Message::SetPeerStatus(status) => {
+                assert_eq!(status.roles, vec![proto::PeerRole::Producer as i32]);
+            }
+            msg => panic!("Expected the peer status, got {msg:?}"),
+        }
+        assert_eq!(
+            h.signaller
+                .property::<Option<String>>("client-id")
+                .as_deref(),
+            Some("producer")
+        );
+
+        h.send(server_message::Message::SessionRequested(
+            proto::SessionRequested {
+                session_id: "session".to_string(),
+                peer_id: "consumer".to_string(),
+            },
+        ));
+        assert_eq!(
+            receiver.recv_timeout(TIMEOUT).unwrap(),
+            ("session".to_string(), "consumer".to_string())
+        );
+
+        h.signaller.add_ice("session", "candidate", 1, None);
+        match h.wait_for_message() {
+            client_message::Message::Ice(ice) => {
+                assert_eq!(ice.session_id, "session");
+                assert_eq!(ice.sdp_m_line_index, 1);
+                assert_eq!(ice.candidate, "candidate");
+            }
+            msg => panic!("Expected a candidate, got {msg:?}"),
+        }
+
+        h.signaller.end_session("session");
+        match h.wait_for_message() {
+            client_message::Message::EndSession(end) => assert_eq!(end.session_id, "session"),
+            msg => panic!("Expected the session to end, got {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn consumer_starts_session() {
+        let h = Harness::new(WebRTCSignallerRole::Consumer);
+        h.signaller.set_property("producer-peer-id", "producer");
+
+        h.send(server_message::Message::Welcome(proto::Welcome {
+            peer_id: "consumer".to_string(),
+        }));
+        h.signaller.start();
+
+        // Both messages are sent from their own task
+        let (mut status, mut start) = (None, None);
+        for _ in 0..2 {
+            match h.wait_for_message() {
+                client_message::Message::SetPeerStatus(msg) => status = Some(msg),
+                client_message::Message::StartSession(msg) => start = Some(msg),
+                msg => panic!("Unexpected message {msg:?}"),
+            }
+        }
+        assert!(status.unwrap().roles.is_empty());
+        assert_eq!(start.unwrap().peer_id, "producer");
+    }
+
+    #[test]
+    fn server_error() {
+        let h = Harness::new(WebRTCSignallerRole::Producer);
+
+        let (sender, receiver) = std_mpsc::channel();
+        let sender = Mutex::new(sender);
+        h.signaller.connect("error", false, move |args| {
+            let _ = sender
+                .lock()
+                .unwrap()
+                .send(args[1].get::<String>().unwrap());
+            None
+        });
+
+        h.send(server_message::Message::Error(proto::Error {
+            details: "overloaded".to_string(),
+        }));
+        h.signaller.start();
+
+        let err = receiver.recv_timeout(TIMEOUT).unwrap();
+        assert!(err.contains("overloaded"), "{err}");
+    }
+}
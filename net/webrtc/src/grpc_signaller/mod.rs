@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::{Signallable, WebRTCSignallerRole};
+use gst::glib;
+
+mod imp;
+mod proto;
+
+glib::wrapper! {
+    pub struct GrpcSignaller(ObjectSubclass<imp::Signaller>) @implements Signallable;
+}
+
+unsafe impl Send for GrpcSignaller {}
+unsafe impl Sync for GrpcSignaller {}
+
+impl Default for GrpcSignaller {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl GrpcSignaller {
+    pub fn new(role: WebRTCSignallerRole) -> Self {
+        glib::Object::builder().property("role", role).build()
+    }
+}
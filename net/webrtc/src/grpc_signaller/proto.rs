@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Messages of `signalling.proto`, written out rather than generated so
+//! that building the plugin doesn't require `protoc`. Keep both in sync.
+
+/// Path of the bidirectional streaming RPC peers connect with
+pub const CONNECT_PATH: &str = "/gstreamer.webrtc.signalling.v1.Signalling/Connect";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum PeerRole {
+    Unspecified = 0,
+    Producer = 1,
+    Listener = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum SdpType {
+    Offer = 0,
+    Answer = 1,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ClientMessage {
+    #[prost(oneof = "client_message::Message", tags = "1, 2, 3, 4, 5, 6")]
+    pub message: Option<client_message::Message>,
+}
+
+pub mod client_message {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Message {
+        #[prost(message, tag = "1")]
+        SetPeerStatus(super::SetPeerStatus),
+        #[prost(message, tag = "2")]
+        StartSession(super::StartSession),
+        #[prost(message, tag = "3")]
+        EndSession(super::EndSession),
+        #[prost(message, tag = "4")]
+        Sdp(super::SessionDescription),
+        #[prost(message, tag = "5")]
+        Ice(super::IceCandidate),
+        #[prost(message, tag = "6")]
+        List(super::ListProducers),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServerMessage {
+    #[prost(oneof = "server_message::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
+    pub message: Option<server_message::Message>,
+}
+
+pub mod server_message {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Message {
+        #[prost(message, tag = "1")]
+        Welcome(super::Welcome),
+        #[prost(message, tag = "2")]
+        PeerStatusChanged(super::PeerStatusChanged),
+        #[prost(message, tag = "3")]
+        SessionRequested(super::SessionRequested),
+        #[prost(message, tag = "4")]
+        SessionStarted(super::SessionStarted),
+        #[prost(message, tag = "5")]
+        EndSession(super::EndSession),
+        #[prost(message, tag = "6")]
+        Sdp(super::SessionDescription),
+        #[prost(message, tag = "7")]
+        Ice(super::IceCandidate),
+        #[prost(message, tag = "8")]
+        List(super::ProducerList),
+        #[prost(message, tag = "9")]
+        Error(super::Error),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SetPeerStatus {
+    #[prost(enumeration = "PeerRole", repeated, tag = "1")]
+    pub roles: Vec<i32>,
+    #[prost(string, tag = "2")]
+    pub meta: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StartSession {
+    #[prost(string, tag = "1")]
+    pub peer_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EndSession {
+    #[prost(string, tag = "1")]
+    pub session_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SessionDescription {
+    #[prost(string, tag = "1")]
+    pub session_id: String,
+    #[prost(enumeration = "SdpType", tag = "2")]
+    pub r#type: i32,
+    #[prost(string, tag = "3")]
+    pub sdp: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct IceCandidate {
+    #[prost(string, tag = "1")]
+    pub session_id: String,
+    #[prost(uint32, tag = "2")]
+    pub sdp_m_line_index: u32,
+    #[prost(string, tag = "3")]
+    pub candidate: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListProducers {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Welcome {
+    #[prost(string, tag = "1")]
+    pub peer_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PeerStatusChanged {
+    #[prost(string, tag = "1")]
+    pub peer_id: String,
+    #[prost(enumeration = "PeerRole", repeated, tag = "2")]
+    pub roles: Vec<i32>,
+    #[prost(string, tag = "3")]
+    pub meta: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SessionRequested {
+    #[prost(string, tag = "1")]
+    pub session_id: String,
+    #[prost(string, tag = "2")]
+    pub peer_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SessionStarted {
+    #[prost(string, tag = "1")]
+    pub session_id: String,
+    #[prost(string, tag = "2")]
+    pub peer_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Producer {
+    #[prost(string, tag = "1")]
+    pub peer_id: String,
+    #[prost(string, tag = "2")]
+    pub meta: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProducerList {
+    #[prost(message, repeated, tag = "1")]
+    pub producers: Vec<Producer>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Error {
+    #[prost(string, tag = "1")]
+    pub details: String,
+}
@@ -0,0 +1,571 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::{Signallable, SignallableExt, SignallableImpl, WebRTCSignallerState};
+use crate::RUNTIME;
+use futures::channel::oneshot;
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_webrtc::{WebRTCICEGatheringState, WebRTCSDPType, WebRTCSessionDescription};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Mutex;
+use std::time::Duration;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtc-http-server-signaller",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC WHIP / WHEP server signaller"),
+    )
+});
+
+const DEFAULT_HOST_ADDR: &str = "http://127.0.0.1:8190";
+const DEFAULT_ENDPOINT_PATH: &str = "/endpoint";
+const DEFAULT_MAX_SESSIONS: u32 = 0;
+const DEFAULT_ANSWER_TIMEOUT: u32 = 10;
+
+#[derive(Clone)]
+struct Settings {
+    host_addr: String,
+    endpoint_path: String,
+    max_sessions: u32,
+    /// In seconds
+    answer_timeout: u32,
+    /// Any token is accepted when empty
+    accepted_tokens: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            host_addr: DEFAULT_HOST_ADDR.to_string(),
+            endpoint_path: DEFAULT_ENDPOINT_PATH.to_string(),
+            max_sessions: DEFAULT_MAX_SESSIONS,
+            answer_timeout: DEFAULT_ANSWER_TIMEOUT,
+            accepted_tokens: Vec::new(),
+        }
+    }
+}
+
+/// A session started by a client POSTing its offer, the request is answered
+/// once both the local description and its candidates are known
+#[derive(Default)]
+struct Session {
+    /// Taken when the answer is sent
+    answer_sender: Option<oneshot::Sender<String>>,
+    answer: Option<gst_sdp::SDPMessage>,
+    /// Local candidates with their mline index, set once gathering is complete
+    candidates: Option<Vec<(u32, String)>>,
+}
+
+impl Session {
+    /// Neither WHIP nor WHEP let the server trickle its candidates, they
+    /// are all written in the answer
+    fn try_send_answer(&mut self) {
+        let (Some(answer), Some(candidates)) = (&self.answer, &self.candidates) else {
+            return;
+        };
+        let Some(answer_sender) = self.answer_sender.take() else {
+            return;
+        };
+
+        let mut answer = answer.clone();
+        for (mline, candidate) in candidates {
+            if let Some(media) = answer.media_mut(*mline) {
+                let _ = media.add_attribute("candidate", Some(candidate.as_str()));
+            }
+        }
+        for mline in 0..answer.medias_len() {
+            if let Some(media) = answer.media_mut(mline) {
+                let _ = media.add_attribute("end-of-candidates", None);
+            }
+        }
+
+        let _ = answer_sender.send(answer.as_text().unwrap());
+    }
+}
+
+#[derive(Default)]
+struct State {
+    sessions: HashMap<String, Session>,
+    server_handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+#[derive(Default)]
+pub struct Signaller {
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+/// Starts a response, with the headers browsers need to reach us from
+/// another origin
+fn response_builder(status: StatusCode) -> hyper::http::response::Builder {
+    Response::builder()
+        .status(status)
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Methods", "OPTIONS, POST, DELETE")
+        .header(
+            "Access-Control-Allow-Headers",
+            "Authorization, Content-Type",
+        )
+        .header("Access-Control-Expose-Headers", "Location")
+}
+
+fn response(status: StatusCode) -> Response<Body> {
+    response_builder(status).body(Body::empty()).unwrap()
+}
+
+impl Signaller {
+    fn raise_error(&self, msg: String) {
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Error, Some(&msg));
+        self.obj()
+            .emit_by_name::<()>("error", &[&format!("Error: {msg}")]);
+    }
+
+    async fn handle_request(&self, req: Request<Body>) -> Response<Body> {
+        let endpoint_path = self.settings.lock().unwrap().endpoint_path.clone();
+        let path = req.uri().path().to_string();
+
+        gst::debug!(CAT, imp: self, "{} request on {path}", req.method());
+
+        if req.method() == Method::OPTIONS {
+            return response(StatusCode::NO_CONTENT);
+        }
+
+        if path == endpoint_path {
+            return match *req.method() {
+                Method::POST => self.handle_offer(req).await,
+                _ => response(StatusCode::METHOD_NOT_ALLOWED),
+            };
+        }
+
+        let Some(session_id) = path
+            .strip_prefix(&endpoint_path)
+            .and_then(|path| path.strip_prefix('/'))
+        else {
+            return response(StatusCode::NOT_FOUND);
+        };
+
+        match *req.method() {
+            Method::DELETE => self.handle_delete(session_id),
+            // Neither trickle ICE nor ICE restarts are supported
+            _ => response(StatusCode::METHOD_NOT_ALLOWED),
+        }
+    }
+
+    async fn handle_offer(&self, req: Request<Body>) -> Response<Body> {
+        let (max_sessions, answer_timeout, endpoint_path, accepted_tokens) = {
+            let settings = self.settings.lock().unwrap();
+            (
+                settings.max_sessions,
+                settings.answer_timeout,
+                settings.endpoint_path.clone(),
+                settings.accepted_tokens.clone(),
+            )
+        };
+
+        let token = req
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string);
+
+        let accepted = accepted_tokens.is_empty()
+            || token
+                .as_ref()
+                .map_or(false, |token| accepted_tokens.contains(token));
+
+        if !accepted || !self.obj().emit_by_name::<bool>("validate-token", &[&token]) {
+            gst::warning!(CAT, imp: self, "Rejecting offer with an invalid token");
+            return response_builder(StatusCode::UNAUTHORIZED)
+                .header(hyper::header::WWW_AUTHENTICATE, "Bearer")
+                .body(Body::empty())
+                .unwrap();
+        }
+
+        let offer = match hyper::body::to_bytes(req.into_body())
+            .await
+            .ok()
+            .and_then(|body| gst_sdp::SDPMessage::parse_buffer(&body).ok())
+        {
+            Some(sdp) => WebRTCSessionDescription::new(WebRTCSDPType::Offer, sdp),
+            None => {
+                gst::warning!(CAT, imp: self, "Rejecting request without a valid offer");
+                return response(StatusCode::BAD_REQUEST);
+            }
+        };
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let (answer_sender, answer_receiver) = oneshot::channel();
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if max_sessions > 0 && state.sessions.len() >= max_sessions as usize {
+                gst::warning!(
+                    CAT,
+                    imp: self,
+                    "Rejecting offer, {max_sessions} sessions running already"
+                );
+                return response(StatusCode::SERVICE_UNAVAILABLE);
+            }
+
+            state.sessions.insert(
+                session_id.clone(),
+                Session {
+                    answer_sender: Some(answer_sender),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let resource_url = format!("{endpoint_path}/{session_id}");
+        gst::info!(CAT, imp: self, "Session {session_id} requested, resource {resource_url}");
+
+        self.obj()
+            .emit_by_name::<()>("client-connected", &[&session_id, &resource_url]);
+
+        self.obj().emit_by_name::<()>(
+            "session-requested",
+            &[&session_id, &session_id, &offer, &None::<gst::Structure>],
+        );
+
+        match tokio::time::timeout(Duration::from_secs(answer_timeout as u64), answer_receiver)
+            .await
+        {
+            Ok(Ok(answer)) => response_builder(StatusCode::CREATED)
+                .header(hyper::header::CONTENT_TYPE, "application/sdp")
+                .header(hyper::header::LOCATION, resource_url)
+                .body(Body::from(answer))
+                .unwrap(),
+            _ => {
+                gst::warning!(CAT, imp: self, "No answer for session {session_id}");
+
+                if self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .sessions
+                    .remove(&session_id)
+                    .is_some()
+                {
+                    self.obj()
+                        .emit_by_name::<bool>("session-ended", &[&session_id]);
+                }
+
+                response(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    fn handle_delete(&self, session_id: &str) -> Response<Body> {
+        if self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .remove(session_id)
+            .is_none()
+        {
+            return response(StatusCode::NOT_FOUND);
+        }
+
+        gst::info!(CAT, imp: self, "Session {session_id} ended by the client");
+        self.obj()
+            .emit_by_name::<bool>("session-ended", &[&session_id]);
+
+        response(StatusCode::OK)
+    }
+
+    fn on_ice_gathering_complete(&self, session_id: &str, webrtcbin: &gst::Element) {
+        let Some(local_description) =
+            webrtcbin.property::<Option<WebRTCSessionDescription>>("local-description")
+        else {
+            gst::warning!(CAT, imp: self, "No local description for session {session_id}");
+            return;
+        };
+
+        let sdp = local_description.sdp();
+        let candidates = sdp
+            .medias()
+            .enumerate()
+            .flat_map(|(mline, media)| {
+                media
+                    .attributes()
+                    .filter(|attr| attr.key() == "candidate")
+                    .filter_map(|attr| attr.value())
+                    .map(move |candidate| (mline as u32, candidate.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(session) = state.sessions.get_mut(session_id) {
+            session.candidates = Some(candidates);
+            session.try_send_answer();
+        }
+    }
+}
+
+impl SignallableImpl for Signaller {
+    fn start(&self) {
+        let host_addr = self.settings.lock().unwrap().host_addr.clone();
+        let Some(addr) = url::Url::parse(&host_addr)
+            .ok()
+            .and_then(|url| url.socket_addrs(|| Some(80)).ok())
+            .and_then(|addrs| addrs.into_iter().next())
+        else {
+            self.raise_error(format!("Invalid host address {host_addr}"));
+            return;
+        };
+
+        let obj_weak = self.obj().downgrade();
+        let make_service = hyper::service::make_service_fn(move |_conn| {
+            let obj_weak = obj_weak.clone();
+
+            async move {
+                Ok::<_, Infallible>(hyper::service::service_fn(move |req| {
+                    let obj_weak = obj_weak.clone();
+
+                    async move {
+                        let response = match obj_weak.upgrade() {
+                            Some(obj) => obj.imp().handle_request(req).await,
+                            None => response(StatusCode::SERVICE_UNAVAILABLE),
+                        };
+
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+
+        let builder = {
+            let _guard = RUNTIME.enter();
+            hyper::Server::try_bind(&addr)
+        };
+        let server = match builder {
+            Ok(builder) => builder.serve(make_service),
+            Err(err) => {
+                self.raise_error(format!("Could not listen on {addr}: {err}"));
+                return;
+            }
+        };
+
+        gst::info!(CAT, imp: self, "Listening on {}", server.local_addr());
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connected, None);
+
+        let server_handle = RUNTIME.spawn(async move {
+            if let Err(err) = server.await {
+                gst::error!(CAT, "HTTP server error: {err}");
+            }
+        });
+        self.state.lock().unwrap().server_handle = Some(server_handle);
+    }
+
+    fn stop(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(server_handle) = state.server_handle.take() {
+            server_handle.abort();
+        }
+        // Pending requests fail with their dropped senders
+        state.sessions.clear();
+        drop(state);
+
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Disconnected, None);
+    }
+
+    fn send_sdp(&self, session_id: &str, sdp: &WebRTCSessionDescription) {
+        if sdp.type_() != WebRTCSDPType::Answer {
+            gst::warning!(
+                CAT,
+                imp: self,
+                "Clients send the offers, not sending {:?} for session {session_id}",
+                sdp.type_()
+            );
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(session) = state.sessions.get_mut(session_id) {
+            session.answer = Some(sdp.sdp().to_owned());
+            session.try_send_answer();
+        }
+    }
+
+    fn end_session(&self, session_id: &str) {
+        // The client is expected to notice, there is no way to tell it
+        self.state.lock().unwrap().sessions.remove(session_id);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Signaller {
+    const NAME: &'static str = "GstRSHttpServerSignaller";
+    type Type = super::HttpServerSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for Signaller {
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstRSHttpServerSignaller::validate-token:
+                 * @token: (nullable): the bearer token of the request, %NULL if
+                 *         it had no 'Authorization: Bearer' header
+                 *
+                 * Emitted for each offer POSTed by a client with one of the
+                 * #GstRSHttpServerSignaller:accepted-tokens, before the session is
+                 * requested. Offers are rejected with 401 Unauthorized as soon as a
+                 * handler returns %FALSE, they are all accepted when no handler is
+                 * connected.
+                 *
+                 * Returns: %TRUE if the client is authorized
+                 */
+                glib::subclass::Signal::builder("validate-token")
+                    .param_types([String::static_type()])
+                    .return_type::<bool>()
+                    .accumulator(|_hint, ret, value| {
+                        *ret = value.clone();
+                        value.get::<bool>().unwrap()
+                    })
+                    .class_handler(|_, _| Some(true.to_value()))
+                    .build(),
+                /**
+                 * GstRSHttpServerSignaller::client-connected:
+                 * @session_id: the ID of the new session
+                 * @resource_url: the path of the session resource, sent to the
+                 *                client in the Location header of the answer
+                 *
+                 * Emitted when the offer of a new client is accepted, before the
+                 * session is requested. Each client gets its own resource, the
+                 * session ends with #GstRSWebRTCSignallableIface::session-ended.
+                 */
+                glib::subclass::Signal::builder("client-connected")
+                    .param_types([String::static_type(), String::static_type()])
+                    .build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        // The session ID is used as the peer ID
+        self.obj().connect_closure(
+            "consumer-added",
+            false,
+            glib::closure!(|signaller: &super::HttpServerSignaller,
+                            session_id: &str,
+                            webrtcbin: &gst::Element| {
+                let obj_weak = signaller.downgrade();
+                let session_id = session_id.to_string();
+                webrtcbin.connect_notify(Some("ice-gathering-state"), move |webrtcbin, _pspec| {
+                    let Some(obj) = obj_weak.upgrade() else {
+                        return;
+                    };
+
+                    if webrtcbin.property::<WebRTCICEGatheringState>("ice-gathering-state")
+                        == WebRTCICEGatheringState::Complete
+                    {
+                        gst::info!(CAT, obj: obj, "ICE gathering complete for {session_id}");
+                        obj.imp().on_ice_gathering_complete(&session_id, webrtcbin);
+                    }
+                });
+            }),
+        );
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("host-addr")
+                    .nick("Host Address")
+                    .blurb("The address the HTTP server listens on, e.g.: http://0.0.0.0:8190")
+                    .default_value(DEFAULT_HOST_ADDR)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("endpoint-path")
+                    .nick("Endpoint Path")
+                    .blurb("The path clients POST their offers to, sessions are then reached at <endpoint-path>/<session id>")
+                    .default_value(DEFAULT_ENDPOINT_PATH)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-sessions")
+                    .nick("Maximum Sessions")
+                    .blurb("Offers are rejected with 503 Service Unavailable once that many sessions are running (0 = No limit)")
+                    .default_value(DEFAULT_MAX_SESSIONS)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("answer-timeout")
+                    .nick("Answer Timeout")
+                    .blurb("Value in seconds to wait for the answer before failing the request")
+                    .minimum(1)
+                    .maximum(3600)
+                    .default_value(DEFAULT_ANSWER_TIMEOUT)
+                    .mutable_ready()
+                    .build(),
+                gst::ParamSpecArray::builder("accepted-tokens")
+                    .nick("Accepted Tokens")
+                    .blurb("Offers without one of these bearer tokens are rejected with 401 Unauthorized (empty = Any token)")
+                    .element_spec(&glib::ParamSpecString::builder("accepted-token")
+                        .nick("Accepted Token")
+                        .blurb("A bearer token clients may authenticate with")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "host-addr" => {
+                settings.host_addr = value.get().expect("type checked upstream");
+            }
+            "endpoint-path" => {
+                let path = value.get::<String>().expect("type checked upstream");
+                settings.endpoint_path = path.trim_end_matches('/').to_string();
+            }
+            "max-sessions" => {
+                settings.max_sessions = value.get().expect("type checked upstream");
+            }
+            "answer-timeout" => {
+                settings.answer_timeout = value.get().expect("type checked upstream");
+            }
+            "accepted-tokens" => {
+                settings.accepted_tokens = value
+                    .get::<gst::Array>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .filter_map(|v| v.get::<String>().ok())
+                    .collect();
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "host-addr" => settings.host_addr.to_value(),
+            "endpoint-path" => settings.endpoint_path.to_value(),
+            "max-sessions" => settings.max_sessions.to_value(),
+            "answer-timeout" => settings.answer_timeout.to_value(),
+            "accepted-tokens" => gst::Array::new(&settings.accepted_tokens).to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::Signallable;
+use gst::glib;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct HttpServerSignaller(ObjectSubclass<imp::Signaller>) @implements Signallable;
+}
+
+unsafe impl Send for HttpServerSignaller {}
+unsafe impl Sync for HttpServerSignaller {}
+
+impl Default for HttpServerSignaller {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl HttpServerSignaller {
+    /// Creates a signaller accepting at most `max_sessions` sessions at
+    /// once, 0 for no limit
+    pub fn with_max_sessions(max_sessions: u32) -> Self {
+        glib::Object::builder()
+            .property("max-sessions", max_sessions)
+            .build()
+    }
+}
@@ -0,0 +1,567 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::protocol::{ConnectionState, IncomingMessage, OutgoingMessage, Role, SdpType};
+use crate::signaller::{prelude::*, Signallable, WebRTCSignallerRole, WebRTCSignallerState};
+use crate::utils::{gvalue_to_json, serialize_json_object};
+use crate::RUNTIME;
+use anyhow::{anyhow, Context, Error};
+use futures::channel::mpsc;
+use futures::prelude::*;
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::glib::prelude::*;
+use gst::subclass::prelude::*;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::task;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtc-ipc-signaller",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC external process signaller"),
+    )
+});
+
+/// How long the external signaller has to exit once stopped
+const STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+type Reader = Box<dyn AsyncRead + Send + Unpin>;
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+
+#[derive(Default)]
+pub struct Settings {
+    command: Option<String>,
+    socket_path: Option<String>,
+    producer_peer_id: Option<String>,
+    role: WebRTCSignallerRole,
+}
+
+#[derive(Default)]
+pub struct Signaller {
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Sender for the messages written by the send task
+    sender: Option<mpsc::Sender<OutgoingMessage>>,
+    send_task_handle: Option<task::JoinHandle<()>>,
+    receive_task_handle: Option<task::JoinHandle<()>>,
+    /// The external signaller, when we spawned it
+    child: Option<tokio::process::Child>,
+}
+
+impl Signaller {
+    /// Spawns the external signaller, or connects to it
+    async fn open(&self) -> Result<(Reader, Writer, Option<tokio::process::Child>), Error> {
+        let (command, socket_path) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.command.clone(), settings.socket_path.clone())
+        };
+
+        if let Some(command) = command {
+            let argv = glib::shell_parse_argv(&command).context("Invalid command")?;
+            let (program, args) = argv.split_first().ok_or_else(|| anyhow!("Empty command"))?;
+
+            gst::info!(CAT, imp: self, "Spawning {command}");
+
+            // The standard error is left to the process, for its logs
+            let mut child = tokio::process::Command::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .with_context(|| format!("Failed to spawn {command}"))?;
+
+            let stdin = child.stdin.take().unwrap();
+            let stdout = child.stdout.take().unwrap();
+
+            Ok((Box::new(stdout), Box::new(stdin), Some(child)))
+        } else if let Some(socket_path) = socket_path {
+            gst::info!(CAT, imp: self, "Connecting to {socket_path}");
+
+            #[cfg(unix)]
+            {
+                let stream = tokio::net::UnixStream::connect(&socket_path)
+                    .await
+                    .with_context(|| format!("Failed to connect to {socket_path}"))?;
+                let (reader, writer) = stream.into_split();
+
+                Ok((Box::new(reader), Box::new(writer), None))
+            }
+
+            #[cfg(not(unix))]
+            {
+                Err(anyhow!("Unix sockets are not supported on this platform"))
+            }
+        } else {
+            Err(anyhow!("Either command or socket-path must be set"))
+        }
+    }
+
+    async fn connect(&self) -> Result<(), Error> {
+        let (role, producer_peer_id) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.role, settings.producer_peer_id.clone())
+        };
+
+        if let WebRTCSignallerRole::Consumer = role {
+            producer_peer_id
+                .as_ref()
+                .ok_or_else(|| anyhow!("No target producer peer id set"))?;
+        }
+
+        let (reader, mut writer, child) = self.open().await?;
+
+        // 1000 is completely arbitrary, we simply don't want infinite piling
+        // up of messages as with unbounded
+        let (mut sender, mut receiver) = mpsc::channel::<OutgoingMessage>(1000);
+
+        let send_task_handle =
+            RUNTIME.spawn(glib::clone!(@weak-allow-none self as this => async move {
+                while let Some(msg) = receiver.next().await {
+                    let mut line = serde_json::to_string(&msg).unwrap();
+                    line.push('\n');
+
+                    let res = async {
+                        writer.write_all(line.as_bytes()).await?;
+                        writer.flush().await
+                    }
+                    .await;
+
+                    if let Err(err) = res {
+                        if let Some(ref this) = this {
+                            this.obj().emit_by_name::<()>(
+                                "error",
+                                &[&format!("Error sending: {}", err)],
+                            );
+                        }
+                        break;
+                    }
+                }
+
+                // Lets the external signaller know we are gone
+                let _ = writer.shutdown().await;
+            }));
+
+        let mut lines = BufReader::new(reader).lines();
+        let receive_task_handle =
+            RUNTIME.spawn(glib::clone!(@weak-allow-none self as this => async move {
+                loop {
+                    let res = lines.next_line().await;
+
+                    let Some(ref this) = this else {
+                        break;
+                    };
+
+                    match res {
+                        Ok(Some(line)) if line.trim().is_empty() => (),
+                        Ok(Some(line)) => match serde_json::from_str::<IncomingMessage>(&line) {
+                            Ok(msg) => this.handle_message(msg),
+                            Err(err) => {
+                                gst::warning!(CAT, imp: this, "Ignoring invalid message {line}: {err}");
+                            }
+                        },
+                        Ok(None) => {
+                            let msg = "The external signaller closed the connection";
+                            this.obj().set_connection_state(WebRTCSignallerState::Error, Some(msg));
+                            this.obj().emit_by_name::<()>("error", &[&msg]);
+                            break;
+                        }
+                        Err(err) => {
+                            this.obj().set_connection_state(
+                                WebRTCSignallerState::Error,
+                                Some(&err.to_string()),
+                            );
+                            this.obj().emit_by_name::<()>(
+                                "error",
+                                &[&format!("Error receiving: {}", err)],
+                            );
+                            break;
+                        }
+                    }
+                }
+
+                let msg = "Stopped receiving";
+                this.map_or_else(|| gst::info!(CAT, "{msg}"),
+                    |this| gst::info!(CAT, imp: this, "{msg}")
+                );
+            }));
+
+        let meta = self
+            .obj()
+            .emit_by_name::<Option<gst::Structure>>("request-meta", &[])
+            .and_then(|meta| gvalue_to_json(&meta.to_value()));
+        let role = match role {
+            WebRTCSignallerRole::Consumer => Role::Consumer,
+            WebRTCSignallerRole::Producer => Role::Producer,
+            WebRTCSignallerRole::Listener => Role::Listener,
+        };
+
+        // Queued first, before any other message can be sent
+        sender.try_send(OutgoingMessage::Start {
+            role,
+            producer_peer_id,
+            meta,
+        })?;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.sender = Some(sender);
+            state.send_task_handle = Some(send_task_handle);
+            state.receive_task_handle = Some(receive_task_handle);
+            state.child = child;
+        }
+
+        gst::info!(CAT, imp: self, "connected");
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connected, None);
+
+        Ok(())
+    }
+
+    /// Queues a message, the order of the messages is preserved
+    fn send(&self, msg: OutgoingMessage) {
+        gst::log!(CAT, imp: self, "Sending message {:?}", msg);
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(sender) = state.sender.as_mut() {
+            if let Err(err) = sender.try_send(msg) {
+                drop(state);
+                self.obj()
+                    .emit_by_name::<()>("error", &[&format!("Error: {}", err)]);
+            }
+        }
+    }
+
+    /// The meta of a peer, a JSON object if any
+    fn parse_meta(&self, meta: Option<serde_json::Value>) -> Option<gst::Structure> {
+        match meta? {
+            serde_json::Value::Object(v) => Some(serialize_json_object(&v)),
+            meta => {
+                gst::error!(CAT, imp: self, "Invalid json meta: {meta}");
+                None
+            }
+        }
+    }
+
+    fn parse_sdp(
+        &self,
+        sdp_type: SdpType,
+        sdp: &str,
+    ) -> Option<gst_webrtc::WebRTCSessionDescription> {
+        let desc_type = match sdp_type {
+            SdpType::Offer => gst_webrtc::WebRTCSDPType::Offer,
+            SdpType::Answer => gst_webrtc::WebRTCSDPType::Answer,
+        };
+
+        match gst_sdp::SDPMessage::parse_buffer(sdp.as_bytes()) {
+            Ok(sdp) => Some(gst_webrtc::WebRTCSessionDescription::new(desc_type, sdp)),
+            Err(err) => {
+                self.obj()
+                    .emit_by_name::<()>("error", &[&format!("Error parsing SDP: {sdp} {err:?}")]);
+
+                None
+            }
+        }
+    }
+
+    fn handle_message(&self, msg: IncomingMessage) {
+        gst::trace!(CAT, imp: self, "Received message {:?}", msg);
+
+        match msg {
+            IncomingMessage::StateChanged { state, message } => {
+                let state = match state {
+                    ConnectionState::Disconnected => WebRTCSignallerState::Disconnected,
+                    ConnectionState::Connecting => WebRTCSignallerState::Connecting,
+                    ConnectionState::Connected => WebRTCSignallerState::Connected,
+                    ConnectionState::Reconnecting => WebRTCSignallerState::Reconnecting,
+                    ConnectionState::Error => WebRTCSignallerState::Error,
+                };
+
+                self.obj().set_connection_state(state, message.as_deref());
+            }
+            IncomingMessage::SessionRequested {
+                session_id,
+                peer_id,
+                offer,
+            } => {
+                let offer = match offer {
+                    Some(offer) => match self.parse_sdp(SdpType::Offer, &offer) {
+                        Some(offer) => Some(offer),
+                        None => return,
+                    },
+                    None => None,
+                };
+
+                self.obj().emit_by_name::<()>(
+                    "session-requested",
+                    &[&session_id, &peer_id, &offer, &None::<gst::Structure>],
+                );
+            }
+            IncomingMessage::SessionStarted {
+                session_id,
+                peer_id,
+            } => {
+                self.obj()
+                    .emit_by_name::<()>("session-started", &[&session_id, &peer_id]);
+            }
+            IncomingMessage::SessionEnded { session_id } => {
+                gst::info!(CAT, imp: self, "Session {session_id} ended");
+
+                self.obj()
+                    .emit_by_name::<bool>("session-ended", &[&session_id]);
+            }
+            IncomingMessage::Sdp {
+                session_id,
+                sdp_type,
+                sdp,
+            } => {
+                if let Some(desc) = self.parse_sdp(sdp_type, &sdp) {
+                    self.obj()
+                        .emit_by_name::<()>("session-description", &[&session_id, &desc]);
+                }
+            }
+            IncomingMessage::Ice {
+                session_id,
+                sdp_m_line_index,
+                sdp_mid,
+                candidate,
+            } => {
+                self.obj().emit_by_name::<()>(
+                    "handle-ice",
+                    &[&session_id, &sdp_m_line_index, &sdp_mid, &candidate],
+                );
+            }
+            IncomingMessage::ProducerAdded {
+                peer_id,
+                meta,
+                new_connection,
+            } => {
+                let meta = self.parse_meta(meta);
+                self.obj()
+                    .emit_by_name::<()>("producer-added", &[&peer_id, &meta, &new_connection]);
+            }
+            IncomingMessage::ProducerRemoved { peer_id, meta } => {
+                let meta = self.parse_meta(meta);
+                self.obj()
+                    .emit_by_name::<()>("producer-removed", &[&peer_id, &meta]);
+            }
+            IncomingMessage::Error { details } => {
+                self.obj().emit_by_name::<()>(
+                    "error",
+                    &[&format!("Error message from signaller: {details}")],
+                );
+            }
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Signaller {
+    const NAME: &'static str = "GstIpcWebRTCSignaller";
+    type Type = super::IpcSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for Signaller {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPS: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstIpcWebRTCSignaller:command:
+                 *
+                 * Command line of the external signaller, spawned when
+                 * starting. Messages are exchanged over its standard input
+                 * and output, its standard error is inherited.
+                 */
+                glib::ParamSpecString::builder("command")
+                    .nick("Command")
+                    .blurb("Command line of the external signaller to spawn, e.g.: python3 signaller.py")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstIpcWebRTCSignaller:socket-path:
+                 *
+                 * Path of the unix socket an external signaller listens on,
+                 * used when #GstIpcWebRTCSignaller:command isn't set.
+                 */
+                glib::ParamSpecString::builder("socket-path")
+                    .nick("Socket Path")
+                    .blurb("Path of the unix socket of the external signaller to connect to")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("producer-peer-id")
+                    .nick("Producer peer ID")
+                    .blurb("ID of the producer to request a session from, as a consumer")
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("role", WebRTCSignallerRole::Consumer)
+                    .nick("Role")
+                    .blurb("Role of the peer")
+                    .build(),
+            ]
+        });
+
+        PROPS.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "command" => {
+                settings.command = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+            }
+            "socket-path" => {
+                settings.socket_path = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+            }
+            "producer-peer-id" => {
+                if !matches!(settings.role, WebRTCSignallerRole::Consumer) {
+                    gst::warning!(
+                        CAT,
+                        "Setting `producer-peer-id` doesn't make sense for {:?}",
+                        settings.role
+                    );
+                } else {
+                    settings.producer_peer_id = value
+                        .get::<Option<String>>()
+                        .expect("type checked upstream");
+                }
+            }
+            "role" => {
+                settings.role = value
+                    .get::<WebRTCSignallerRole>()
+                    .expect("type checked upstream")
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "command" => settings.command.to_value(),
+            "socket-path" => settings.socket_path.to_value(),
+            "producer-peer-id" => settings.producer_peer_id.to_value(),
+            "role" => settings.role.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl SignallableImpl for Signaller {
+    fn start(&self) {
+        gst::info!(CAT, imp: self, "Starting");
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connecting, None);
+        RUNTIME.spawn(glib::clone!(@weak self as this => async move {
+            if let Err(err) = this.connect().await {
+                this.obj().set_connection_state(WebRTCSignallerState::Error, Some(&err.to_string()));
+                this.obj().emit_by_name::<()>("error", &[&format!("Error connecting: {}", err)]);
+            }
+        }));
+    }
+
+    fn stop(&self) {
+        gst::info!(CAT, imp: self, "Stopping now");
+
+        let (sender, send_task_handle, receive_task_handle, child) = {
+            let mut state = self.state.lock().unwrap();
+
+            (
+                state.sender.take(),
+                state.send_task_handle.take(),
+                state.receive_task_handle.take(),
+                state.child.take(),
+            )
+        };
+
+        if let Some(handle) = receive_task_handle {
+            handle.abort();
+        }
+
+        if let Some(mut sender) = sender {
+            let _ = sender.try_send(OutgoingMessage::Stop);
+            sender.close_channel();
+        }
+
+        // Lets the queued messages be written and the external signaller
+        // exit on its own
+        RUNTIME.block_on(async {
+            if let Some(mut handle) = send_task_handle {
+                if tokio::time::timeout(STOP_TIMEOUT, &mut handle)
+                    .await
+                    .is_err()
+                {
+                    handle.abort();
+                }
+            }
+
+            if let Some(mut child) = child {
+                if tokio::time::timeout(STOP_TIMEOUT, child.wait())
+                    .await
+                    .is_err()
+                {
+                    gst::warning!(CAT, imp: self, "External signaller didn't exit, killing it");
+                    let _ = child.kill().await;
+                }
+            }
+        });
+
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Disconnected, None);
+    }
+
+    fn send_sdp(&self, session_id: &str, sdp: &gst_webrtc::WebRTCSessionDescription) {
+        gst::debug!(CAT, imp: self, "Sending SDP {sdp:#?}");
+
+        let sdp_type = match sdp.type_() {
+            gst_webrtc::WebRTCSDPType::Answer => SdpType::Answer,
+            _ => SdpType::Offer,
+        };
+
+        self.send(OutgoingMessage::Sdp {
+            session_id: session_id.to_string(),
+            sdp_type,
+            sdp: sdp.sdp().as_text().unwrap(),
+        });
+    }
+
+    fn add_ice(
+        &self,
+        session_id: &str,
+        candidate: &str,
+        sdp_m_line_index: u32,
+        sdp_mid: Option<String>,
+    ) {
+        gst::debug!(
+            CAT,
+            imp: self,
+            "Adding ice candidate {candidate:?} for {sdp_m_line_index:?} on session {session_id}"
+        );
+
+        self.send(OutgoingMessage::Ice {
+            session_id: session_id.to_string(),
+            sdp_m_line_index,
+            sdp_mid,
+            candidate: candidate.to_string(),
+        });
+    }
+
+    fn end_session(&self, session_id: &str) {
+        gst::debug!(CAT, imp: self, "Signalling session done {}", session_id);
+
+        self.send(OutgoingMessage::EndSession {
+            session_id: session_id.to_string(),
+        });
+    }
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::{Signallable, WebRTCSignallerRole};
+use gst::glib;
+
+mod imp;
+mod protocol;
+
+glib::wrapper! {
+    pub struct IpcSignaller(ObjectSubclass<imp::Signaller>) @implements Signallable;
+}
+
+unsafe impl Send for IpcSignaller {}
+unsafe impl Sync for IpcSignaller {}
+
+impl Default for IpcSignaller {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl IpcSignaller {
+    pub fn new(role: WebRTCSignallerRole) -> Self {
+        glib::Object::builder().property("role", role).build()
+    }
+}
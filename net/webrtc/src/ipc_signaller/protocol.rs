@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Messages exchanged with the external signaller, one JSON object per
+//! line. They mirror the virtual methods and the signals of the
+//! Signallable interface.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum SdpType {
+    Offer,
+    Answer,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    Consumer,
+    Producer,
+    Listener,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Reconnecting,
+    Error,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+/// Messages sent to the external signaller
+pub enum OutgoingMessage {
+    /// Sent first, the signaller is expected to connect to its service
+    #[serde(rename_all = "camelCase")]
+    Start {
+        role: Role,
+        /// The producer to request a session from, for consumers
+        #[serde(skip_serializing_if = "Option::is_none")]
+        producer_peer_id: Option<String>,
+        /// As provided by the element, to announce the peer with
+        #[serde(skip_serializing_if = "Option::is_none")]
+        meta: Option<serde_json::Value>,
+    },
+    /// Sent last, the signaller is expected to disconnect
+    Stop,
+    /// Description of a session, to send to the remote peer
+    #[serde(rename_all = "camelCase")]
+    Sdp {
+        session_id: String,
+        sdp_type: SdpType,
+        sdp: String,
+    },
+    /// Local candidate of a session, to send to the remote peer
+    #[serde(rename_all = "camelCase")]
+    Ice {
+        session_id: String,
+        sdp_m_line_index: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sdp_mid: Option<String>,
+        candidate: String,
+    },
+    /// The element ended a session
+    #[serde(rename_all = "camelCase")]
+    EndSession { session_id: String },
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+/// Messages received from the external signaller
+pub enum IncomingMessage {
+    /// The state of the connection to the service of the signaller
+    #[serde(rename_all = "camelCase")]
+    StateChanged {
+        state: ConnectionState,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// A remote peer requested a session, with its offer if it sends one
+    #[serde(rename_all = "camelCase")]
+    SessionRequested {
+        session_id: String,
+        peer_id: String,
+        #[serde(default)]
+        offer: Option<String>,
+    },
+    /// The session requested as a consumer started
+    #[serde(rename_all = "camelCase")]
+    SessionStarted { session_id: String, peer_id: String },
+    /// The remote peer ended a session
+    #[serde(rename_all = "camelCase")]
+    SessionEnded { session_id: String },
+    /// Description of a session, from the remote peer
+    #[serde(rename_all = "camelCase")]
+    Sdp {
+        session_id: String,
+        sdp_type: SdpType,
+        sdp: String,
+    },
+    /// Remote candidate of a session
+    #[serde(rename_all = "camelCase")]
+    Ice {
+        session_id: String,
+        sdp_m_line_index: u32,
+        #[serde(default)]
+        sdp_mid: Option<String>,
+        candidate: String,
+    },
+    /// A producer is available, for listeners
+    #[serde(rename_all = "camelCase")]
+    ProducerAdded {
+        peer_id: String,
+        #[serde(default)]
+        meta: Option<serde_json::Value>,
+        #[serde(default)]
+        new_connection: bool,
+    },
+    /// A producer isn't available anymore, for listeners
+    #[serde(rename_all = "camelCase")]
+    ProducerRemoved {
+        peer_id: String,
+        #[serde(default)]
+        meta: Option<serde_json::Value>,
+    },
+    /// Fatal error of the signaller
+    #[serde(rename_all = "camelCase")]
+    Error { details: String },
+}
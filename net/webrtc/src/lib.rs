@@ -15,16 +15,23 @@ use gst::glib::once_cell::sync::Lazy;
 use tokio::runtime;
 
 mod aws_kvs_signaller;
+mod gateway;
+mod grpc_signaller;
+mod http_server_signaller;
+mod ipc_signaller;
 mod livekit_signaller;
 pub mod signaller;
+mod sip_signaller;
 pub mod utils;
 pub mod webrtcsink;
 pub mod webrtcsrc;
+mod whep_signaller;
 mod whip_signaller;
 
 fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     webrtcsink::register(plugin)?;
     webrtcsrc::register(Some(plugin))?;
+    gateway::register(plugin)?;
 
     Ok(())
 }
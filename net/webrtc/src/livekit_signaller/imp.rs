@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::signaller::{Signallable, SignallableImpl};
+use crate::signaller::{Signallable, SignallableExt, SignallableImpl, WebRTCSignallerState};
 
 use crate::utils::{wait_async, WaitError};
 use crate::RUNTIME;
@@ -11,6 +11,7 @@ use gst::glib;
 use gst::glib::once_cell::sync::Lazy;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
+use prost::Message as _;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -30,6 +31,7 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 });
 
 const DEFAULT_TRACK_PUBLISH_TIMEOUT: u32 = 10;
+const DEFAULT_TOKEN_TTL: u32 = 6 * 60 * 60;
 
 #[derive(Clone)]
 struct Settings {
@@ -40,6 +42,7 @@ struct Settings {
     identity: Option<String>,
     room_name: Option<String>,
     auth_token: Option<String>,
+    token_ttl: u32,
     timeout: u32,
 }
 
@@ -53,6 +56,7 @@ impl Default for Settings {
             identity: Some("gstreamer".to_string()),
             room_name: None,
             auth_token: None,
+            token_ttl: DEFAULT_TOKEN_TTL,
             timeout: DEFAULT_TRACK_PUBLISH_TIMEOUT,
         }
     }
@@ -79,6 +83,27 @@ struct Connection {
     channels: Option<Channels>,
 }
 
+/// The subset of the LiveKit `DataPacket` message needed to publish user
+/// data, independent from the prost version livekit-protocol is built with
+#[derive(Clone, PartialEq, prost::Message)]
+struct DataPacket {
+    /// 0 for reliable, 1 for lossy
+    #[prost(int32, tag = "1")]
+    kind: i32,
+    #[prost(message, optional, tag = "2")]
+    user: Option<UserPacket>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct UserPacket {
+    #[prost(bytes = "vec", tag = "2")]
+    payload: Vec<u8>,
+    #[prost(string, repeated, tag = "6")]
+    destination_identities: Vec<String>,
+    #[prost(string, optional, tag = "4")]
+    topic: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct IceCandidateJson {
@@ -93,6 +118,60 @@ impl Signaller {
             .emit_by_name::<()>("error", &[&format!("Error: {msg}")]);
     }
 
+    /// Publishes `payload` to the participants of the room over the
+    /// reliable or lossy data channel, returns whether it was sent
+    fn publish_data(
+        &self,
+        topic: Option<String>,
+        payload: &[u8],
+        reliable: bool,
+        destination_identities: Vec<String>,
+    ) -> bool {
+        let channel = self
+            .connection
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|connection| connection.channels.as_ref())
+            .map(|channels| {
+                if reliable {
+                    channels.reliable_channel.clone()
+                } else {
+                    channels.lossy_channel.clone()
+                }
+            });
+
+        let Some(channel) = channel else {
+            gst::warning!(CAT, imp: self, "No data channel to publish data on yet");
+            return false;
+        };
+
+        if channel.ready_state() != gst_webrtc::WebRTCDataChannelState::Open {
+            gst::warning!(CAT, imp: self, "Data channel {} isn't open", channel.label().unwrap_or_default());
+            return false;
+        }
+
+        gst::trace!(
+            CAT,
+            imp: self,
+            "Publishing {} bytes of data with topic {topic:?}",
+            payload.len()
+        );
+
+        let packet = DataPacket {
+            kind: if reliable { 0 } else { 1 },
+            user: Some(UserPacket {
+                payload: payload.to_vec(),
+                destination_identities,
+                topic,
+            }),
+        };
+
+        channel.send_data(Some(&glib::Bytes::from_owned(packet.encode_to_vec())));
+
+        true
+    }
+
     async fn signal_task(&self, mut signal_events: signal_client::SignalEvents) {
         loop {
             match wait_async(&self.signal_task_canceller, signal_events.recv(), 0).await {
@@ -203,17 +282,10 @@ impl SignallableImpl for Signaller {
 
             if let Some(auth_token) = &settings.auth_token {
                 auth_token.clone()
-            } else if let (
-                Some(api_key),
-                Some(secret_key),
-                Some(identity),
-                Some(participant_name),
-                Some(room_name),
-            ) = (
+            } else if let (Some(api_key), Some(secret_key), Some(identity), Some(room_name)) = (
                 &settings.api_key,
                 &settings.secret_key,
                 &settings.identity,
-                &settings.participant_name,
                 &settings.room_name,
             ) {
                 let grants = VideoGrants {
@@ -222,12 +294,23 @@ impl SignallableImpl for Signaller {
                     room: room_name.clone(),
                     ..Default::default()
                 };
-                let access_token = AccessToken::with_api_key(api_key, secret_key)
-                    .with_name(participant_name)
+                let mut access_token = AccessToken::with_api_key(api_key, secret_key)
                     .with_identity(identity)
+                    .with_ttl(std::time::Duration::from_secs(settings.token_ttl.into()))
                     .with_grants(grants);
+                if let Some(participant_name) = &settings.participant_name {
+                    access_token = access_token.with_name(participant_name);
+                }
                 match access_token.to_jwt() {
-                    Ok(token) => token,
+                    Ok(token) => {
+                        gst::debug!(
+                            CAT,
+                            imp: self,
+                            "Minted access token for {identity} in room {room_name}, valid for {}s",
+                            settings.token_ttl
+                        );
+                        token
+                    }
                     Err(err) => {
                         self.raise_error(format!(
                             "{:?}",
@@ -254,10 +337,14 @@ impl SignallableImpl for Signaller {
 
             let options = signal_client::SignalOptions::default();
             gst::debug!(CAT, imp: imp, "Connecting to {}", wsurl);
+            imp.obj()
+                .set_connection_state(WebRTCSignallerState::Connecting, None);
 
             let res = signal_client::SignalClient::connect(&wsurl, &auth_token, options).await;
             let (signal_client, join_response, signal_events) = match res {
                 Err(err) => {
+                    imp.obj()
+                        .set_connection_state(WebRTCSignallerState::Error, Some(&err.to_string()));
                     imp.obj()
                         .emit_by_name::<()>("error", &[&format!("{:?}", anyhow!("Error: {err}"))]);
                     return;
@@ -272,6 +359,8 @@ impl SignallableImpl for Signaller {
                 "Connected with JoinResponse: {:?}",
                 join_response
             );
+            imp.obj()
+                .set_connection_state(WebRTCSignallerState::Connected, None);
 
             let weak_imp = imp.downgrade();
             let signal_task = RUNTIME.spawn(async move {
@@ -338,6 +427,7 @@ impl SignallableImpl for Signaller {
                     &"unique",
                     &"unique",
                     &None::<gst_webrtc::WebRTCSessionDescription>,
+                    &None::<gst::Structure>,
                 ],
             );
         });
@@ -573,6 +663,9 @@ impl SignallableImpl for Signaller {
             block_on(connection.signal_task).unwrap();
             block_on(connection.signal_client.close());
         }
+
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Disconnected, None);
     }
 
     fn end_session(&self, session_id: &str) {
@@ -589,6 +682,58 @@ impl ObjectSubclass for Signaller {
 }
 
 impl ObjectImpl for Signaller {
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstLiveKitWebRTCSinkSignaller::publish-data:
+                 * @topic: (nullable): the topic of the data, for receivers to filter on
+                 * @payload: the data to publish
+                 * @reliable: whether to send the data over the reliable data channel,
+                 *   or over the lossy one
+                 * @destination_identities: (nullable): the identities of the participants
+                 *   to send the data to, all participants if empty
+                 *
+                 * Publishes data to the participants of the room through the data
+                 * channels of the session, as the LiveKit client SDKs do with
+                 * `publishData()`.
+                 *
+                 * Returns: %TRUE if the data was sent, %FALSE if the data channel
+                 * isn't open yet
+                 */
+                glib::subclass::Signal::builder("publish-data")
+                    .param_types([
+                        String::static_type(),
+                        glib::Bytes::static_type(),
+                        bool::static_type(),
+                        Vec::<String>::static_type(),
+                    ])
+                    .action()
+                    .class_handler(|_, args| {
+                        let signaller = args[0]
+                            .get::<super::LiveKitSignaller>()
+                            .expect("signal arg");
+                        let topic = args[1].get::<Option<String>>().expect("signal arg");
+                        let payload = args[2].get::<glib::Bytes>().expect("signal arg");
+                        let reliable = args[3].get::<bool>().expect("signal arg");
+                        let destination_identities =
+                            args[4].get::<Vec<String>>().expect("signal arg");
+
+                        Some(
+                            signaller
+                                .imp()
+                                .publish_data(topic, &payload, reliable, destination_identities)
+                                .to_value(),
+                        )
+                    })
+                    .return_type::<bool>()
+                    .build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
     fn properties() -> &'static [glib::ParamSpec] {
         static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
             vec![
@@ -627,6 +772,13 @@ impl ObjectImpl for Signaller {
                     .blurb("Name of the room to join (mandatory)")
                     .mutable_ready()
                     .build(),
+                glib::ParamSpecUInt::builder("token-ttl")
+                    .nick("Token TTL")
+                    .blurb("Validity in seconds of the token minted from api-key and secret-key")
+                    .minimum(1)
+                    .default_value(DEFAULT_TOKEN_TTL)
+                    .mutable_ready()
+                    .build(),
                 glib::ParamSpecUInt::builder("timeout")
                     .nick("Timeout")
                     .blurb("Value in seconds to timeout join requests.")
@@ -674,6 +826,9 @@ impl ObjectImpl for Signaller {
             "auth-token" => {
                 settings.auth_token = value.get().unwrap();
             }
+            "token-ttl" => {
+                settings.token_ttl = value.get().unwrap();
+            }
             "timeout" => {
                 settings.timeout = value.get().unwrap();
             }
@@ -691,6 +846,7 @@ impl ObjectImpl for Signaller {
             "identity" => settings.identity.to_value(),
             "room-name" => settings.room_name.to_value(),
             "auth-token" => settings.auth_token.to_value(),
+            "token-ttl" => settings.token_ttl.to_value(),
             "timeout" => settings.timeout.to_value(),
             channel @ ("reliable-channel" | "lossy-channel") => {
                 let channel = if let Some(connection) = &*self.connection.lock().unwrap() {
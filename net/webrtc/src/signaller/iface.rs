@@ -3,6 +3,88 @@ use gst::glib::once_cell::sync::Lazy;
 use gst::glib::subclass::*;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
+use std::sync::Mutex;
+
+/// State of the connection of a signaller to its server
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstRSWebRTCSignallerState")]
+pub enum WebRTCSignallerState {
+    #[default]
+    Disconnected,
+    Connecting,
+    Connected,
+    Reconnecting,
+    Error,
+}
+
+/// What happened to the connection of a signaller, as notified with
+/// #GstRSWebRTCSignallableIface::state-changed
+#[derive(Debug, Default)]
+struct ConnectionStats {
+    state: WebRTCSignallerState,
+    connections: u32,
+    reconnections: u32,
+    errors: u32,
+    last_error: Option<String>,
+}
+
+impl ConnectionStats {
+    fn update(&mut self, state: WebRTCSignallerState, message: Option<String>) {
+        match state {
+            WebRTCSignallerState::Connected => self.connections += 1,
+            WebRTCSignallerState::Reconnecting => self.reconnections += 1,
+            WebRTCSignallerState::Error => {
+                self.errors += 1;
+                self.last_error = message;
+            }
+            _ => (),
+        }
+
+        self.state = state;
+    }
+
+    fn to_structure(&self) -> gst::Structure {
+        gst::Structure::builder("application/x-webrtc-signaller-connection-stats")
+            .field("state", self.state)
+            .field("connections", self.connections)
+            .field("reconnections", self.reconnections)
+            .field("errors", self.errors)
+            .field("last-error", self.last_error.as_deref())
+            .build()
+    }
+}
+
+static CONNECTION_STATS_QUARK: Lazy<glib::Quark> =
+    Lazy::new(|| glib::Quark::from_str("gst-rswebrtc-signaller-connection-stats"));
+/// Serializes the accesses to the connection stats of all the signallers,
+/// which are only updated on state changes
+static CONNECTION_STATS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Gives access to the connection stats of `signallable`, kept along
+/// with the object whatever its implementation
+fn with_connection_stats<R>(
+    signallable: &super::Signallable,
+    f: impl FnOnce(&mut ConnectionStats) -> R,
+) -> R {
+    let _guard = CONNECTION_STATS_LOCK.lock().unwrap();
+
+    // SAFETY: the data is only ever set here with the same type, and
+    // accessed while holding the lock
+    unsafe {
+        if signallable
+            .qdata::<ConnectionStats>(*CONNECTION_STATS_QUARK)
+            .is_none()
+        {
+            signallable.set_qdata(*CONNECTION_STATS_QUARK, ConnectionStats::default());
+        }
+
+        let mut stats = signallable
+            .qdata::<ConnectionStats>(*CONNECTION_STATS_QUARK)
+            .unwrap();
+        f(stats.as_mut())
+    }
+}
 
 #[derive(Copy, Clone)]
 #[repr(C)]
@@ -12,6 +94,7 @@ pub struct Signallable {
     pub stop: fn(&super::Signallable),
     pub send_sdp: fn(&super::Signallable, &str, &gst_webrtc::WebRTCSessionDescription),
     pub add_ice: fn(&super::Signallable, &str, &str, u32, Option<String>),
+    pub add_ice_batch: fn(&super::Signallable, &str, &[(u32, String)], bool),
     pub end_session: fn(&super::Signallable, &str),
 }
 
@@ -35,6 +118,16 @@ impl Signallable {
         _sdp_mid: Option<String>,
     ) {
     }
+    fn add_ice_batch(
+        iface: &super::Signallable,
+        session_id: &str,
+        candidates: &[(u32, String)],
+        _end_of_candidates: bool,
+    ) {
+        for (sdp_m_line_index, candidate) in candidates {
+            iface.add_ice(session_id, candidate, *sdp_m_line_index, None);
+        }
+    }
     fn end_session(_iface: &super::Signallable, _session_id: &str) {}
 }
 
@@ -48,6 +141,7 @@ unsafe impl prelude::ObjectInterface for Signallable {
         self.stop = Signallable::stop;
         self.send_sdp = Signallable::send_sdp;
         self.add_ice = Signallable::add_ice;
+        self.add_ice_batch = Signallable::add_ice_batch;
         self.end_session = Signallable::end_session;
     }
 
@@ -110,17 +204,40 @@ unsafe impl prelude::ObjectInterface for Signallable {
                  * @session_id: The ID of the producer that was added
                  * @peer_id: The ID of the consumer peer who wants to initiate a
                  *           session
+                 * @offer: (nullable): The offer of the consumer, if it made one
+                 * @capabilities: (nullable): The capabilities advertised by the
+                 *                consumer, if any
                  *
                  * Notify the underlying webrtc object that a session has been requested from the
                  * peer.
+                 *
+                 * The capabilities structure may contain `max-width` and
+                 * `max-height` (unsigned integers), `codecs` (array of RTP
+                 * encoding names) and `user-agent` (string, matched against the
+                 * interop rules of webrtcsink) fields.
                  */
                 Signal::builder("session-requested")
                     .param_types([
                         str::static_type(),
                         str::static_type(),
                         gst_webrtc::WebRTCSessionDescription::static_type(),
+                        gst::Structure::static_type(),
                     ])
                     .build(),
+                /**
+                 * GstRSWebRTCSignallableIface::session-bitrate-ceiling:
+                 * @self: The object implementing #GstRSWebRTCSignallableIface
+                 * @session_id: The ID of the session
+                 * @bitrate: The maximum bitrate in bits per second, 0 for no limit
+                 *
+                 * Notify the underlying webrtc object that the bitrate of a session
+                 * should not exceed @bitrate, whatever the congestion control
+                 * estimates, for instance when the consumer renders a small
+                 * viewport or is billed per byte.
+                 */
+                Signal::builder("session-bitrate-ceiling")
+                    .param_types([str::static_type(), u32::static_type()])
+                    .build(),
                 /**
                  * GstRSWebRTCSignallableIface::error:
                  * @self: The object implementing #GstRSWebRTCSignallableIface
@@ -129,6 +246,74 @@ unsafe impl prelude::ObjectInterface for Signallable {
                 Signal::builder("error")
                     .param_types([str::static_type()])
                     .build(),
+                /**
+                 * GstRSWebRTCSignallableIface::state-changed:
+                 * @self: The object implementing #GstRSWebRTCSignallableIface
+                 * @state: The new state of the connection to the signalling server
+                 * @message: (nullable): Details about the change, the cause of
+                 *           the failure for the `error` state
+                 *
+                 * Emitted by the signaller when its connection to the signalling
+                 * server changes state, whatever the protocol, for applications to
+                 * surface the health of the signalling. Signallers reconnecting on
+                 * their own go through the `reconnecting` state.
+                 *
+                 * The counters of #GstRSWebRTCSignallableIface::connection-stats
+                 * are updated before the handlers are called.
+                 */
+                Signal::builder("state-changed")
+                    .run_first()
+                    .param_types([
+                        WebRTCSignallerState::static_type(),
+                        <Option<String>>::static_type(),
+                    ])
+                    .class_handler(|_token, args| {
+                        let this = args[0usize]
+                            .get::<&super::Signallable>()
+                            .unwrap_or_else(|e| {
+                                panic!("Wrong type for argument {}: {:?}", 0usize, e)
+                            });
+                        let state = args[1usize]
+                            .get::<WebRTCSignallerState>()
+                            .unwrap_or_else(|e| {
+                                panic!("Wrong type for argument {}: {:?}", 1usize, e)
+                            });
+                        let message = args[2usize]
+                            .get::<Option<String>>()
+                            .unwrap_or_else(|e| {
+                                panic!("Wrong type for argument {}: {:?}", 2usize, e)
+                            });
+
+                        with_connection_stats(this, |stats| stats.update(state, message));
+
+                        None
+                    })
+                    .build(),
+                /**
+                 * GstRSWebRTCSignallableIface::connection-stats:
+                 * @self: The object implementing #GstRSWebRTCSignallableIface
+                 *
+                 * Returns the current `state` of the connection to the signalling
+                 * server, along with how many times the signaller got `connections`,
+                 * went through `reconnections` and `errors`, and the `last-error`
+                 * message, as notified with
+                 * #GstRSWebRTCSignallableIface::state-changed.
+                 *
+                 * Return: The connection stats of the signaller
+                 */
+                Signal::builder("connection-stats")
+                    .flags(glib::SignalFlags::ACTION)
+                    .return_type::<gst::Structure>()
+                    .class_handler(|_token, args| {
+                        let this = args[0usize]
+                            .get::<&super::Signallable>()
+                            .unwrap_or_else(|e| {
+                                panic!("Wrong type for argument {}: {:?}", 0usize, e)
+                            });
+
+                        Some(with_connection_stats(this, |stats| stats.to_structure()).to_value())
+                    })
+                    .build(),
                 /**
                  * GstRSWebRTCSignallableIface::request-meta:
                  * @self: The object implementing #GstRSWebRTCSignallableIface
@@ -299,6 +484,19 @@ unsafe impl prelude::ObjectInterface for Signallable {
                 Signal::builder("consumer-removed")
                     .param_types([String::static_type(), gst::Element::static_type()])
                     .build(),
+                /**
+                 * GstRSWebRTCSignallableIface::end-of-stream:
+                 * @self: The object implementing #GstRSWebRTCSignallableIface
+                 * @session_id: Id of the session whose stream ended
+                 *
+                 * Emitted by webrtcsink once the media of a session was flushed
+                 * after its input reached EOS, right before ending the session.
+                 * Signallers can relay it so that the peer can tell the stream
+                 * ended from the session being interrupted.
+                 */
+                Signal::builder("end-of-stream")
+                    .param_types([str::static_type()])
+                    .build(),
                 /**
                  * GstRSWebRTCSignallableIface::send-session-description:
                  * @self: The object implementing #GstRSWebRTCSignallableIface
@@ -386,6 +584,67 @@ unsafe impl prelude::ObjectInterface for Signallable {
                         false
                     })
                     .build(),
+                /**
+                 * GstRSWebRTCSignallableIface::send-ice-batch:
+                 * @self: The object implementing #GstRSWebRTCSignallableIface
+                 * @session_id: Id of the session being described
+                 * @candidates: #GstStructure named `ice-candidate` with the
+                 *              `candidate` and its `sdp-m-line-index`
+                 * @end_of_candidates: Whether gathering is complete and no
+                 *                     candidate will follow
+                 *
+                 * Send a batch of candidates to the peer at once, as gathered
+                 * when the `ice-candidate-batch-interval` of webrtcsink is set.
+                 * HTTP-based signallers can send them in a single request, for
+                 * instance a WHIP PATCH, and signal the end of candidates in it.
+                 *
+                 * The default implementation sends each candidate with
+                 * #GstRSWebRTCSignallableIface::send-ice and ignores
+                 * @end_of_candidates.
+                 */
+                Signal::builder("send-ice-batch")
+                    .param_types([
+                        str::static_type(),
+                        gst::Array::static_type(),
+                        bool::static_type(),
+                    ])
+                    .return_type::<bool>()
+                    .class_handler(|_tokens, args| {
+                        let this = args[0usize]
+                            .get::<&super::Signallable>()
+                            .unwrap_or_else(|e| {
+                                panic!("Wrong type for argument {}: {:?}", 0usize, e)
+                            });
+                        let session_id = args[1usize].get::<&str>().unwrap_or_else(|e| {
+                            panic!("Wrong type for argument {}: {:?}", 1usize, e)
+                        });
+                        let candidates = args[2usize].get::<gst::Array>().unwrap_or_else(|e| {
+                            panic!("Wrong type for argument {}: {:?}", 2usize, e)
+                        });
+                        let end_of_candidates = args[3usize].get::<bool>().unwrap_or_else(|e| {
+                            panic!("Wrong type for argument {}: {:?}", 3usize, e)
+                        });
+                        let candidates = candidates
+                            .iter()
+                            .filter_map(|candidate| {
+                                let s = candidate.get::<gst::Structure>().ok()?;
+                                Some((
+                                    s.get::<u32>("sdp-m-line-index").ok()?,
+                                    s.get::<String>("candidate").ok()?,
+                                ))
+                            })
+                            .collect::<Vec<_>>();
+                        let vtable = this.interface::<super::Signallable>().unwrap();
+                        let vtable = vtable.as_ref();
+                        (vtable.add_ice_batch)(this, session_id, &candidates, end_of_candidates);
+
+                        Some(false.into())
+                    })
+                    .accumulator(move |_hint, output, input| {
+                        *output = input.clone();
+                        false
+                    })
+                    .build(),
             ]
         });
         SIGNALS.as_ref()
@@ -446,6 +705,19 @@ where
         }
         iface.add_ice = add_ice_trampoline::<Obj>;
 
+        fn add_ice_batch_trampoline<Obj: types::ObjectSubclass + SignallableImpl>(
+            this: &super::Signallable,
+            session_id: &str,
+            candidates: &[(u32, String)],
+            end_of_candidates: bool,
+        ) {
+            let this = this
+                .dynamic_cast_ref::<<Obj as types::ObjectSubclass>::Type>()
+                .unwrap();
+            SignallableImpl::add_ice_batch(this.imp(), session_id, candidates, end_of_candidates)
+        }
+        iface.add_ice_batch = add_ice_batch_trampoline::<Obj>;
+
         fn end_session_trampoline<Obj: types::ObjectSubclass + SignallableImpl>(
             this: &super::Signallable,
             session_id: &str,
@@ -471,6 +743,18 @@ pub trait SignallableImpl: object::ObjectImpl + 'static {
         _sdp_mid: Option<String>,
     ) {
     }
+    fn add_ice_batch(
+        &self,
+        session_id: &str,
+        candidates: &[(u32, String)],
+        _end_of_candidates: bool,
+    ) {
+        let obj = self.obj();
+        let signallable = obj.dynamic_cast_ref::<super::Signallable>().unwrap();
+        for (sdp_m_line_index, candidate) in candidates {
+            signallable.add_ice(session_id, candidate, *sdp_m_line_index, None);
+        }
+    }
     fn end_session(&self, _session_id: &str) {}
 }
 
@@ -485,7 +769,15 @@ pub trait SignallableExt: 'static {
         sdp_m_line_index: u32,
         sdp_mid: Option<String>,
     );
+    fn add_ice_batch(
+        &self,
+        session_id: &str,
+        candidates: &[(u32, String)],
+        end_of_candidates: bool,
+    );
     fn end_session(&self, session_id: &str);
+    fn set_connection_state(&self, state: WebRTCSignallerState, message: Option<&str>);
+    fn connection_stats(&self) -> gst::Structure;
 }
 
 impl<Obj: glib::IsA<super::Signallable>> SignallableExt for Obj {
@@ -514,7 +806,34 @@ impl<Obj: glib::IsA<super::Signallable>> SignallableExt for Obj {
         );
     }
 
+    fn add_ice_batch(
+        &self,
+        session_id: &str,
+        candidates: &[(u32, String)],
+        end_of_candidates: bool,
+    ) {
+        let candidates = gst::Array::new(candidates.iter().map(|(sdp_m_line_index, candidate)| {
+            gst::Structure::builder("ice-candidate")
+                .field("sdp-m-line-index", sdp_m_line_index)
+                .field("candidate", candidate)
+                .build()
+        }));
+
+        self.emit_by_name::<bool>(
+            "send-ice-batch",
+            &[&session_id, &candidates, &end_of_candidates],
+        );
+    }
+
     fn end_session(&self, session_id: &str) {
         self.emit_by_name::<bool>("end-session", &[&session_id]);
     }
+
+    fn set_connection_state(&self, state: WebRTCSignallerState, message: Option<&str>) {
+        self.emit_by_name::<()>("state-changed", &[&state, &message]);
+    }
+
+    fn connection_stats(&self) -> gst::Structure {
+        self.emit_by_name::<gst::Structure>("connection-stats", &[])
+    }
 }
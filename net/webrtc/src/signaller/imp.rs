@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::signaller::{prelude::*, Signallable};
-use crate::utils::{gvalue_to_json, serialize_json_object};
+use crate::signaller::{prelude::*, Signallable, WebRTCSignallerState};
+use crate::utils::{gvalue_to_json, serialize_json_object, TlsSettings};
 use crate::RUNTIME;
 use anyhow::{anyhow, Error};
 use async_tungstenite::tungstenite::Message as WsMessage;
@@ -35,8 +35,9 @@ pub enum WebRTCSignallerRole {
 pub struct Settings {
     uri: Url,
     producer_peer_id: Option<String>,
-    cafile: Option<String>,
+    tls: TlsSettings,
     role: WebRTCSignallerRole,
+    consumer_capabilities: Option<gst::Structure>,
 }
 
 impl Default for Settings {
@@ -44,8 +45,9 @@ impl Default for Settings {
         Self {
             uri: Url::from_str("ws://127.0.0.1:8443").unwrap(),
             producer_peer_id: None,
-            cafile: Default::default(),
+            tls: Default::default(),
             role: Default::default(),
+            consumer_capabilities: None,
         }
     }
 }
@@ -103,23 +105,16 @@ impl Signaller {
     }
 
     async fn connect(&self) -> Result<(), Error> {
-        let obj = self.obj();
-
-        let role = self.settings.lock().unwrap().role;
+        let (role, tls) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.role, settings.tls.clone())
+        };
         if let super::WebRTCSignallerRole::Consumer = role {
             self.producer_peer_id()
                 .ok_or_else(|| anyhow!("No target producer peer id set"))?;
         }
 
-        let connector = if let Some(path) = obj.property::<Option<String>>("cafile") {
-            let cert = tokio::fs::read_to_string(&path).await?;
-            let cert = tokio_native_tls::native_tls::Certificate::from_pem(cert.as_bytes())?;
-            let mut connector_builder = tokio_native_tls::native_tls::TlsConnector::builder();
-            let connector = connector_builder.add_root_certificate(cert).build()?;
-            Some(tokio_native_tls::TlsConnector::from(connector))
-        } else {
-            None
-        };
+        let connector = tls.connector()?.map(tokio_native_tls::TlsConnector::from);
 
         let mut uri = self.uri();
         uri.set_query(None);
@@ -131,6 +126,8 @@ impl Signaller {
         .await??;
 
         gst::info!(CAT, imp: self, "connected");
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connected, None);
 
         // Channel for asynchronously sending out websocket message
         let (mut ws_sink, mut ws_stream) = ws.split();
@@ -180,7 +177,10 @@ impl Signaller {
 
                 let msg = "Stopped websocket receiving";
                 this.map_or_else(|| gst::info!(CAT, "{msg}"),
-                    |this| gst::info!(CAT, imp: this, "{msg}")
+                    |this| {
+                        gst::info!(CAT, imp: this, "{msg}");
+                        this.obj().set_connection_state(WebRTCSignallerState::Disconnected, None);
+                    }
                 );
             }));
 
@@ -195,12 +195,27 @@ impl Signaller {
     fn set_status(&self, meta: &Option<serde_json::Value>, peer_id: &str) {
         self.state.lock().unwrap().client_id = Some(peer_id.to_string());
 
-        let role = self.settings.lock().unwrap().role;
+        let (role, producer_peer_id) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.role, settings.producer_peer_id.clone())
+        };
+        // Consumers without a producer to consume from listen for one to be
+        // selected among the announced producers
+        let listening = match role {
+            super::WebRTCSignallerRole::Consumer => producer_peer_id.is_none(),
+            super::WebRTCSignallerRole::Producer => false,
+            super::WebRTCSignallerRole::Listener => true,
+        };
+
         self.send(p::IncomingMessage::SetPeerStatus(match role {
             super::WebRTCSignallerRole::Consumer => p::PeerStatus {
                 meta: meta.clone(),
                 peer_id: Some(peer_id.to_string()),
-                roles: vec![],
+                roles: if listening {
+                    vec![p::PeerRole::Listener]
+                } else {
+                    vec![]
+                },
             },
             super::WebRTCSignallerRole::Producer => p::PeerStatus {
                 meta: meta.clone(),
@@ -214,7 +229,7 @@ impl Signaller {
             },
         }));
 
-        if matches!(role, super::WebRTCSignallerRole::Listener) {
+        if listening {
             self.send(p::IncomingMessage::List);
         }
     }
@@ -237,12 +252,25 @@ impl Signaller {
     }
 
     pub fn start_session(&self) {
-        let role = self.settings.lock().unwrap().role;
+        let (role, capabilities) = {
+            let settings = self.settings.lock().unwrap();
+            (
+                settings.role,
+                settings
+                    .consumer_capabilities
+                    .as_ref()
+                    .map(|s| capabilities_from_structure(s)),
+            )
+        };
         if matches!(role, super::WebRTCSignallerRole::Consumer) {
-            let target_producer = self.producer_peer_id().unwrap();
+            let Some(target_producer) = self.producer_peer_id() else {
+                gst::info!(CAT, imp: self, "Waiting for a producer to be selected");
+                return;
+            };
 
             self.send(p::IncomingMessage::StartSession(p::StartSessionMessage {
                 peer_id: target_producer.clone(),
+                capabilities,
             }));
 
             gst::info!(
@@ -311,6 +339,7 @@ impl Signaller {
                         p::OutgoingMessage::StartSession {
                             session_id,
                             peer_id,
+                            capabilities,
                         } => {
                             assert!(matches!(
                                 self.obj().property::<WebRTCSignallerRole>("role"),
@@ -323,6 +352,7 @@ impl Signaller {
                                     &session_id,
                                     &peer_id,
                                     &None::<gst_webrtc::WebRTCSessionDescription>,
+                                    &capabilities.as_ref().map(capabilities_to_structure),
                                 ],
                             );
                         }
@@ -374,6 +404,17 @@ impl Signaller {
                                     &[&session_id, &sdp_m_line_index, &sdp_mid, &candidate],
                                 );
                             }
+                            p::PeerMessageInner::MaxBitrate { bitrate } => {
+                                gst::info!(
+                                    CAT,
+                                    imp: self,
+                                    "Session {session_id} requested a max bitrate of {bitrate}"
+                                );
+                                self.obj().emit_by_name::<()>(
+                                    "session-bitrate-ceiling",
+                                    &[&session_id, &bitrate],
+                                );
+                            }
                         },
                         p::OutgoingMessage::List { producers } => {
                             for producer in producers {
@@ -446,16 +487,29 @@ impl ObjectImpl for Signaller {
                 glib::ParamSpecString::builder("producer-peer-id")
                     .flags(glib::ParamFlags::READWRITE)
                     .build(),
-                glib::ParamSpecString::builder("cafile")
-                    .flags(glib::ParamFlags::READWRITE)
-                    .build(),
                 glib::ParamSpecEnum::builder_with_default("role", WebRTCSignallerRole::Consumer)
                     .flags(glib::ParamFlags::READWRITE)
                     .build(),
                 glib::ParamSpecString::builder("client-id")
                     .flags(glib::ParamFlags::READABLE)
                     .build(),
+                /**
+                 * GstWebRTCSignaller:consumer-capabilities:
+                 *
+                 * Capabilities advertised to the producer when requesting a
+                 * session as a consumer, for instance
+                 * `capabilities,max-height=(uint)720,codecs=<VP8,OPUS>`.
+                 *
+                 * See #GstRSWebRTCSignallableIface::session-requested for the
+                 * supported fields.
+                 */
+                glib::ParamSpecBoxed::builder::<gst::Structure>("consumer-capabilities")
+                    .flags(glib::ParamFlags::READWRITE)
+                    .build(),
             ]
+            .into_iter()
+            .chain(TlsSettings::properties())
+            .collect()
         });
 
         PROPS.as_ref()
@@ -478,21 +532,34 @@ impl ObjectImpl for Signaller {
                         settings.role
                     );
                 } else {
-                    settings.producer_peer_id = value
+                    let producer_peer_id = value
                         .get::<Option<String>>()
                         .expect("type checked upstream");
+                    let selected = producer_peer_id.is_some()
+                        && settings.producer_peer_id.is_none()
+                        && self.state.lock().unwrap().client_id.is_some();
+                    settings.producer_peer_id = producer_peer_id;
+                    drop(settings);
+
+                    // Selected among the producers announced while connected
+                    if selected {
+                        self.start_session();
+                    }
                 }
             }
-            "cafile" => {
-                self.settings.lock().unwrap().cafile = value
-                    .get::<Option<String>>()
-                    .expect("type checked upstream")
+            name if TlsSettings::PROPERTIES.contains(&name) => {
+                self.settings.lock().unwrap().tls.set_property(name, value)
             }
             "role" => {
                 self.settings.lock().unwrap().role = value
                     .get::<WebRTCSignallerRole>()
                     .expect("type checked upstream")
             }
+            "consumer-capabilities" => {
+                self.settings.lock().unwrap().consumer_capabilities = value
+                    .get::<Option<gst::Structure>>()
+                    .expect("type checked upstream")
+            }
             _ => unimplemented!(),
         }
     }
@@ -512,19 +579,67 @@ impl ObjectImpl for Signaller {
 
                 settings.producer_peer_id.to_value()
             }
-            "cafile" => settings.cafile.to_value(),
+            name if TlsSettings::PROPERTIES.contains(&name) => settings.tls.property(name),
             "role" => settings.role.to_value(),
             "client-id" => self.state.lock().unwrap().client_id.to_value(),
+            "consumer-capabilities" => settings.consumer_capabilities.to_value(),
             _ => unimplemented!(),
         }
     }
 }
 
+fn capabilities_from_structure(s: &gst::StructureRef) -> p::ConsumerCapabilities {
+    let get_dimension = |field: &str| {
+        s.get::<u32>(field)
+            .ok()
+            .or_else(|| s.get::<i32>(field).ok().map(|v| v.max(0) as u32))
+    };
+
+    let codecs = s
+        .get::<gst::Array>("codecs")
+        .map(|codecs| codecs.to_vec())
+        .or_else(|_| s.get::<gst::List>("codecs").map(|codecs| codecs.to_vec()))
+        .ok()
+        .map(|codecs| {
+            codecs
+                .iter()
+                .filter_map(|codec| codec.get::<String>().ok())
+                .collect()
+        });
+
+    p::ConsumerCapabilities {
+        max_width: get_dimension("max-width"),
+        max_height: get_dimension("max-height"),
+        codecs,
+    }
+}
+
+fn capabilities_to_structure(capabilities: &p::ConsumerCapabilities) -> gst::Structure {
+    let mut s = gst::Structure::new_empty("capabilities");
+
+    if let Some(max_width) = capabilities.max_width {
+        s.set("max-width", max_width);
+    }
+
+    if let Some(max_height) = capabilities.max_height {
+        s.set("max-height", max_height);
+    }
+
+    if let Some(ref codecs) = capabilities.codecs {
+        s.set("codecs", gst::Array::new(codecs));
+    }
+
+    s
+}
+
 impl SignallableImpl for Signaller {
     fn start(&self) {
         gst::info!(CAT, imp: self, "Starting");
+        self.obj()
+            .set_connection_state(WebRTCSignallerState::Connecting, None);
         RUNTIME.spawn(glib::clone!(@weak self as this => async move {
             if let Err(err) = this.connect().await {
+                this.obj().set_connection_state(WebRTCSignallerState::Error, Some(&err.to_string()));
                 this.obj().emit_by_name::<()>("error", &[&format!("Error receiving: {}", err)]);
             }
         }));
@@ -552,6 +667,10 @@ impl SignallableImpl for Signaller {
                     }
                 }
             });
+        } else {
+            // The receive task notifies the disconnection otherwise
+            self.obj()
+                .set_connection_state(WebRTCSignallerState::Disconnected, None);
         }
         state.producers.clear();
         state.client_id = None;
@@ -46,6 +46,7 @@ impl Signaller {
 
 pub use iface::SignallableExt;
 pub use iface::SignallableImpl;
+pub use iface::WebRTCSignallerState;
 
 unsafe impl Send for Signallable {}
 unsafe impl Sync for Signallable {}
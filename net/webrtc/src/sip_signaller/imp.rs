@@ -0,0 +1,937 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::protocol::{
+    header_uri, random_token, DigestChallenge, Message, SipUri, BRANCH_MAGIC_COOKIE,
+};
+use super::transport::{Connection, Handler};
+use super::SipTransport;
+use crate::signaller::{Signallable, SignallableImpl};
+use crate::utils::{wait, WaitError};
+use crate::RUNTIME;
+use anyhow::Context;
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_webrtc::{WebRTCICEGatheringState, WebRTCSDPType, WebRTCSessionDescription};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtc-sip-signaller",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC SIP signaller"),
+    )
+});
+
+const DEFAULT_TRANSPORT: SipTransport = SipTransport::Udp;
+const DEFAULT_REGISTER_EXPIRES: u32 = 3600;
+const DEFAULT_TIMEOUT: u32 = 15;
+const SESSION_ID: &str = "unique";
+const USER_AGENT: &str = "GStreamer WebRTC SIP signaller";
+const ALLOW: &str = "INVITE, ACK, CANCEL, BYE, OPTIONS";
+
+#[derive(Clone)]
+struct Settings {
+    target_uri: Option<String>,
+    local_uri: Option<String>,
+    registrar_uri: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    transport: SipTransport,
+    register_expires: u32,
+    timeout: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            target_uri: None,
+            local_uri: None,
+            registrar_uri: None,
+            username: None,
+            password: None,
+            transport: DEFAULT_TRANSPORT,
+            register_expires: DEFAULT_REGISTER_EXPIRES,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// The call placed to the target, see RFC 3261 section 12 for dialogs
+#[derive(Debug, Clone)]
+struct Call {
+    call_id: String,
+    /// The INVITE as last sent, for it to be cancelled while ringing
+    invite: Option<Message>,
+    /// Set once the call is answered
+    dialog: Option<Dialog>,
+    /// Kept around to be sent again when the 2xx response to the INVITE
+    /// is retransmitted
+    ack: Option<Message>,
+}
+
+#[derive(Debug, Clone)]
+struct Dialog {
+    remote_target: String,
+    /// The To header with the tag of the remote party
+    remote: String,
+    route_set: Vec<String>,
+}
+
+#[derive(Default)]
+struct State {
+    connection: Option<Arc<Connection>>,
+    register_call_id: Option<String>,
+    registered: bool,
+    call: Option<Call>,
+    /// Set once ICE gathering completed and the call is being placed
+    call_placed: bool,
+    /// Our tag, generated once per start: in the dialog of the call, and
+    /// in the responses to requests outside of it
+    local_tag: String,
+    cseq: u32,
+    tasks: Vec<tokio::task::JoinHandle<()>>,
+}
+
+/// Signaller placing a call to a SIP endpoint: the offer is sent in an
+/// INVITE once ICE gathering is complete, and the answer is read from the
+/// 200 OK response
+#[derive(Default)]
+pub struct Signaller {
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+    canceller: Mutex<Option<futures::future::AbortHandle>>,
+}
+
+/// Forwards the messages of the connection that aren't responses to our
+/// requests
+struct SignallerHandler(glib::WeakRef<super::SipSignaller>);
+
+impl Handler for SignallerHandler {
+    fn handle_request(&self, request: Message) {
+        if let Some(obj) = self.0.upgrade() {
+            obj.imp().handle_request(request);
+        }
+    }
+
+    fn handle_stray_response(&self, response: Message) {
+        if let Some(obj) = self.0.upgrade() {
+            obj.imp().handle_stray_response(response);
+        }
+    }
+
+    fn connection_closed(&self) {
+        if let Some(obj) = self.0.upgrade() {
+            obj.imp().connection_closed();
+        }
+    }
+}
+
+impl Signaller {
+    fn raise_error(&self, msg: String) {
+        self.obj()
+            .emit_by_name::<()>("error", &[&format!("Error: {msg}")]);
+    }
+
+    /// Returns the connection to the registrar or, without one, to the
+    /// target, connecting if needed
+    async fn connection(&self) -> Result<Arc<Connection>, anyhow::Error> {
+        if let Some(connection) = self.state.lock().unwrap().connection.clone() {
+            return Ok(connection);
+        }
+
+        let settings = self.settings.lock().unwrap().clone();
+        let server = settings
+            .registrar_uri
+            .as_deref()
+            .or(settings.target_uri.as_deref())
+            .context("Target URI must be set")?;
+        let server = SipUri::parse(server)?;
+
+        let transport = if server.secure {
+            SipTransport::Tls
+        } else {
+            settings.transport
+        };
+        let port = server.port.unwrap_or(match transport {
+            SipTransport::Tls => 5061,
+            _ => 5060,
+        });
+
+        let handler = SignallerHandler(self.obj().downgrade());
+        let connection = Connection::connect(transport, &server.host, port, handler)
+            .await
+            .with_context(|| format!("Connecting to {}:{port}", server.host))?;
+
+        gst::info!(
+            CAT,
+            imp: self,
+            "Connected to {}:{port} from {}",
+            server.host,
+            connection.local_addr
+        );
+
+        let connection = Arc::new(connection);
+        self.state.lock().unwrap().connection = Some(connection.clone());
+
+        Ok(connection)
+    }
+
+    /// Our address of record, as set with the local-uri property or made
+    /// up from the user name and the host of the registrar
+    fn local_uri(&self, connection: &Connection) -> String {
+        let settings = self.settings.lock().unwrap();
+        if let Some(local_uri) = &settings.local_uri {
+            return local_uri.clone();
+        }
+
+        let user = settings.username.as_deref().unwrap_or("gstreamer");
+        let host = settings
+            .registrar_uri
+            .as_deref()
+            .and_then(|uri| SipUri::parse(uri).ok())
+            .map(|uri| uri.host)
+            .unwrap_or_else(|| connection.local_addr.ip().to_string());
+
+        format!("sip:{user}@{host}")
+    }
+
+    fn contact(&self, connection: &Connection) -> String {
+        let user = self
+            .settings
+            .lock()
+            .unwrap()
+            .username
+            .clone()
+            .unwrap_or_else(|| "gstreamer".to_string());
+
+        format!(
+            "<sip:{user}@{};transport={}>",
+            connection.local_addr,
+            connection.transport.name()
+        )
+    }
+
+    /// Sends `request`, which only lacks the headers specific to each
+    /// transaction, then answers a digest authentication challenge once
+    /// if the server issues one. Returns the request as last sent along
+    /// with its final response
+    async fn send_request(
+        &self,
+        connection: &Connection,
+        request: Message,
+    ) -> Result<(Message, Message), anyhow::Error> {
+        let (username, password, timeout) = {
+            let settings = self.settings.lock().unwrap();
+            (
+                settings.username.clone(),
+                settings.password.clone(),
+                settings.timeout,
+            )
+        };
+        let timeout = (timeout > 0).then(|| Duration::from_secs(timeout.into()));
+
+        let method = request.method().unwrap_or_default().to_string();
+        let uri = request
+            .start_line
+            .split(' ')
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+
+        let mut authorization: Option<(&str, String)> = None;
+
+        loop {
+            let cseq = {
+                let mut state = self.state.lock().unwrap();
+                state.cseq += 1;
+                state.cseq
+            };
+
+            let mut sent = request.clone();
+            sent.headers.insert(
+                0,
+                (
+                    "Via".to_string(),
+                    format!(
+                        "SIP/2.0/{} {};branch={BRANCH_MAGIC_COOKIE}{};rport",
+                        connection.transport.name().to_uppercase(),
+                        connection.local_addr,
+                        random_token()
+                    ),
+                ),
+            );
+            sent = sent
+                .with_header("Max-Forwards", "70")
+                .with_header("CSeq", format!("{cseq} {method}"))
+                .with_header("User-Agent", USER_AGENT);
+            if let Some((name, value)) = &authorization {
+                sent = sent.with_header(name, value.clone());
+            }
+
+            if method == "INVITE" {
+                if let Some(call) = self.state.lock().unwrap().call.as_mut() {
+                    call.invite = Some(sent.clone());
+                }
+            }
+
+            let response = connection.transaction(&sent, timeout).await?;
+            let status = response.status().unwrap_or_default();
+
+            gst::debug!(CAT, imp: self, "{method} answered with {}", response.start_line);
+
+            // Final responses other than 2xx are acknowledged within the
+            // transaction
+            if method == "INVITE" && status >= 300 {
+                connection.send(&non_2xx_ack(&sent, &response));
+            }
+
+            let (challenge_header, authorization_header) = match status {
+                401 => ("WWW-Authenticate", "Authorization"),
+                407 => ("Proxy-Authenticate", "Proxy-Authorization"),
+                _ => return Ok((sent, response)),
+            };
+
+            // Credentials were rejected
+            if authorization.is_some() {
+                return Ok((sent, response));
+            }
+
+            let (Some(username), Some(password)) = (&username, &password) else {
+                return Ok((sent, response));
+            };
+
+            let Some(challenge) = response
+                .header(challenge_header)
+                .and_then(DigestChallenge::parse)
+            else {
+                return Ok((sent, response));
+            };
+
+            authorization = Some((
+                authorization_header,
+                challenge.authorization(&method, &uri, username, password, &random_token()),
+            ));
+        }
+    }
+
+    /// Registers our contact for `expires` seconds, 0 removing the
+    /// registration
+    async fn register(&self, expires: u32) -> Result<(), anyhow::Error> {
+        let registrar = self
+            .settings
+            .lock()
+            .unwrap()
+            .registrar_uri
+            .clone()
+            .context("Registrar URI must be set")?;
+        let connection = self.connection().await?;
+        let local_uri = self.local_uri(&connection);
+
+        let call_id = self
+            .state
+            .lock()
+            .unwrap()
+            .register_call_id
+            .get_or_insert_with(random_token)
+            .clone();
+
+        let request = Message::request("REGISTER", &registrar)
+            .with_header("From", format!("<{local_uri}>;tag={}", random_token()))
+            .with_header("To", format!("<{local_uri}>"))
+            .with_header("Call-ID", call_id)
+            .with_header("Contact", self.contact(&connection))
+            .with_header("Expires", expires.to_string());
+
+        let (_, response) = self.send_request(&connection, request).await?;
+        match response.status() {
+            Some(200..=299) => {
+                gst::info!(CAT, imp: self, "Registered with {registrar} for {expires}s");
+                self.state.lock().unwrap().registered = expires > 0;
+                Ok(())
+            }
+            _ => anyhow::bail!("Registration failed: {}", response.start_line),
+        }
+    }
+
+    /// Refreshes the registration halfway through its validity, for as
+    /// long as the signaller runs
+    fn spawn_register_refresh(&self, expires: u32) {
+        let interval = Duration::from_secs((expires / 2).max(1).into());
+        let obj = self.obj().downgrade();
+
+        let task = RUNTIME.spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let Some(obj) = obj.upgrade() else {
+                    break;
+                };
+
+                if let Err(err) = obj.imp().register(expires).await {
+                    obj.imp().raise_error(format!("{err:#}"));
+                    break;
+                }
+            }
+        });
+
+        self.state.lock().unwrap().tasks.push(task);
+    }
+
+    /// Places the call with the local description of `webrtcbin` as
+    /// offer, and hands the answer over to the sink or source
+    async fn invite(&self, webrtcbin: &gst::Element) -> Result<(), anyhow::Error> {
+        let target = self
+            .settings
+            .lock()
+            .unwrap()
+            .target_uri
+            .clone()
+            .context("Target URI must be set")?;
+        let offer = webrtcbin
+            .property::<Option<WebRTCSessionDescription>>("local-description")
+            .context("No local description to send")?;
+        let sdp = offer.sdp().as_text()?;
+
+        let connection = self.connection().await?;
+        let local_uri = self.local_uri(&connection);
+        let call_id = random_token();
+        let local_tag = self.state.lock().unwrap().local_tag.clone();
+
+        self.state.lock().unwrap().call = Some(Call {
+            call_id: call_id.clone(),
+            invite: None,
+            dialog: None,
+            ack: None,
+        });
+
+        let request = Message::request("INVITE", &target)
+            .with_header("From", format!("<{local_uri}>;tag={local_tag}"))
+            .with_header("To", format!("<{target}>"))
+            .with_header("Call-ID", call_id)
+            .with_header("Contact", self.contact(&connection))
+            .with_header("Allow", ALLOW)
+            .with_body("application/sdp", sdp);
+
+        let (invite, response) = self.send_request(&connection, request).await?;
+        if !matches!(response.status(), Some(200..=299)) {
+            self.state.lock().unwrap().call = None;
+            anyhow::bail!("Call failed: {}", response.start_line);
+        }
+
+        let remote = response
+            .header("To")
+            .context("No To header in the response")?
+            .to_string();
+        let remote_target = response
+            .header("Contact")
+            .map(|contact| header_uri(contact).to_string())
+            .unwrap_or(target);
+        let route_set = response
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("Record-Route"))
+            .flat_map(|(_, value)| value.split(','))
+            .map(|route| route.trim().to_string())
+            .rev()
+            .collect::<Vec<_>>();
+        let cseq = invite
+            .header("CSeq")
+            .and_then(|cseq| cseq.split_whitespace().next())
+            .unwrap_or_default()
+            .to_string();
+
+        let dialog = Dialog {
+            remote_target,
+            remote,
+            route_set,
+        };
+
+        let ack = {
+            let mut state = self.state.lock().unwrap();
+            let Some(call) = state.call.as_mut() else {
+                anyhow::bail!("Call ended while ringing");
+            };
+
+            let ack = in_dialog_request(&connection, call, &dialog, "ACK")
+                .with_header("CSeq", format!("{cseq} ACK"));
+            call.ack = Some(ack.clone());
+            call.dialog = Some(dialog.clone());
+            ack
+        };
+
+        connection.send(&ack);
+
+        gst::info!(CAT, imp: self, "Call answered by {}", dialog.remote);
+
+        let answer = gst_sdp::SDPMessage::parse_buffer(response.body.as_bytes())
+            .context("Could not parse the answer")?;
+        self.obj().emit_by_name::<()>(
+            "session-description",
+            &[
+                &SESSION_ID,
+                &WebRTCSessionDescription::new(WebRTCSDPType::Answer, answer),
+            ],
+        );
+
+        Ok(())
+    }
+
+    /// Ends the call: established calls are hung up, ringing ones are
+    /// cancelled
+    fn hang_up(&self) {
+        let (connection, call) = {
+            let mut state = self.state.lock().unwrap();
+            for task in state.tasks.drain(..) {
+                task.abort();
+            }
+            (state.connection.clone(), state.call.take())
+        };
+
+        let (Some(connection), Some(call)) = (connection, call) else {
+            return;
+        };
+
+        let Some(dialog) = &call.dialog else {
+            if let Some(invite) = &call.invite {
+                gst::debug!(CAT, imp: self, "Cancelling call {}", call.call_id);
+                connection.send(&cancel(invite));
+            }
+            return;
+        };
+
+        gst::debug!(CAT, imp: self, "Hanging up call {}", call.call_id);
+
+        let bye = in_dialog_request(&connection, &call, dialog, "BYE");
+        let timeout = self.settings.lock().unwrap().timeout;
+        let future = async {
+            self.send_request(&connection, bye)
+                .await
+                .map_err(|err| gst::error_msg!(gst::ResourceError::Failed, ["BYE failed: {err:#}"]))
+        };
+
+        match wait(&self.canceller, future, timeout) {
+            Ok((_, response)) => {
+                gst::debug!(CAT, imp: self, "Response to BYE: {}", response.start_line)
+            }
+            Err(WaitError::FutureAborted) => gst::warning!(CAT, imp: self, "BYE aborted"),
+            Err(WaitError::FutureError(err)) => gst::error!(CAT, imp: self, "{err}"),
+        }
+    }
+
+    fn unregister(&self) {
+        if !self.state.lock().unwrap().registered {
+            return;
+        }
+
+        let timeout = self.settings.lock().unwrap().timeout;
+        let future = async {
+            self.register(0)
+                .await
+                .map_err(|err| gst::error_msg!(gst::ResourceError::Failed, ["{err:#}"]))
+        };
+
+        if let Err(err) = wait(&self.canceller, future, timeout) {
+            gst::warning!(CAT, imp: self, "Failed to unregister: {err:?}");
+        }
+    }
+
+    fn cancel(&self) {
+        // Interrupt requests in progress, if any
+        if let Some(canceller) = &*self.canceller.lock().unwrap() {
+            canceller.abort();
+        }
+    }
+
+    fn handle_request(&self, request: Message) {
+        let (connection, local_tag) = {
+            let state = self.state.lock().unwrap();
+            (state.connection.clone(), state.local_tag.clone())
+        };
+        let Some(connection) = connection else {
+            return;
+        };
+
+        gst::debug!(CAT, imp: self, "Received {}", request.start_line);
+
+        match request.method() {
+            Some("ACK") => (),
+            Some("BYE") => {
+                let ends_call = {
+                    let mut state = self.state.lock().unwrap();
+                    let ends_call = state.call.as_ref().map_or(false, |call| {
+                        Some(call.call_id.as_str()) == request.header("Call-ID")
+                    });
+                    if ends_call {
+                        state.call = None;
+                    }
+                    ends_call
+                };
+
+                if ends_call {
+                    connection.send(&Message::response(&request, 200, "OK", &local_tag));
+                    gst::info!(CAT, imp: self, "Remote party hung up");
+                    self.obj()
+                        .emit_by_name::<bool>("session-ended", &[&SESSION_ID]);
+                } else {
+                    connection.send(&Message::response(
+                        &request,
+                        481,
+                        "Call/Transaction Does Not Exist",
+                        &local_tag,
+                    ));
+                }
+            }
+            Some("OPTIONS") => connection.send(
+                &Message::response(&request, 200, "OK", &local_tag).with_header("Allow", ALLOW),
+            ),
+            // Incoming calls and re-INVITEs are not supported
+            Some("INVITE") => connection.send(&Message::response(
+                &request,
+                488,
+                "Not Acceptable Here",
+                &local_tag,
+            )),
+            Some("CANCEL") => connection.send(&Message::response(
+                &request,
+                481,
+                "Call/Transaction Does Not Exist",
+                &local_tag,
+            )),
+            _ => connection.send(&Message::response(
+                &request,
+                501,
+                "Not Implemented",
+                &local_tag,
+            )),
+        }
+    }
+
+    fn handle_stray_response(&self, response: Message) {
+        let state = self.state.lock().unwrap();
+
+        // The server didn't get our ACK and sends its answer again
+        if let (Some(connection), Some(call)) = (&state.connection, &state.call) {
+            if let Some(ack) = &call.ack {
+                if response.cseq_method() == Some("INVITE")
+                    && matches!(response.status(), Some(200..=299))
+                    && response.header("Call-ID") == Some(call.call_id.as_str())
+                {
+                    connection.send(ack);
+                    return;
+                }
+            }
+        }
+
+        gst::debug!(CAT, imp: self, "Ignoring stray {}", response.start_line);
+    }
+
+    fn connection_closed(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.connection.take().is_some() {
+            drop(state);
+            self.raise_error("Connection to the SIP server closed".to_string());
+        }
+    }
+}
+
+/// Builds a request within the dialog of an answered call, without the
+/// headers specific to each transaction
+fn in_dialog_request(
+    connection: &Connection,
+    call: &Call,
+    dialog: &Dialog,
+    method: &str,
+) -> Message {
+    let local = call
+        .invite
+        .as_ref()
+        .and_then(|invite| invite.header("From"))
+        .map(|from| from.to_string())
+        .unwrap_or_default();
+
+    let mut request = Message::request(method, &dialog.remote_target);
+    for route in &dialog.route_set {
+        request = request.with_header("Route", route.clone());
+    }
+
+    request = request
+        .with_header("From", local)
+        .with_header("To", dialog.remote.clone())
+        .with_header("Call-ID", call.call_id.clone());
+
+    // ACKs are sent outside of any transaction, and need their own Via
+    if method == "ACK" {
+        request.headers.insert(
+            0,
+            (
+                "Via".to_string(),
+                format!(
+                    "SIP/2.0/{} {};branch={BRANCH_MAGIC_COOKIE}{};rport",
+                    connection.transport.name().to_uppercase(),
+                    connection.local_addr,
+                    random_token()
+                ),
+            ),
+        );
+        request = request.with_header("Max-Forwards", "70");
+    }
+
+    request
+}
+
+/// The ACK acknowledging a final response other than 2xx to `invite`,
+/// which belongs to the INVITE transaction
+fn non_2xx_ack(invite: &Message, response: &Message) -> Message {
+    let uri = invite.start_line.split(' ').nth(1).unwrap_or_default();
+    let mut ack = Message::request("ACK", uri);
+
+    for name in ["Via", "Max-Forwards", "From"] {
+        if let Some(value) = invite.header(name) {
+            ack = ack.with_header(name, value);
+        }
+    }
+    if let Some(to) = response.header("To") {
+        ack = ack.with_header("To", to);
+    }
+    if let Some(call_id) = invite.header("Call-ID") {
+        ack = ack.with_header("Call-ID", call_id);
+    }
+    if let Some(cseq) = invite
+        .header("CSeq")
+        .and_then(|cseq| cseq.split_whitespace().next())
+    {
+        ack = ack.with_header("CSeq", format!("{cseq} ACK"));
+    }
+
+    ack
+}
+
+/// The CANCEL of a ringing `invite`, which is matched with it through
+/// the same branch
+fn cancel(invite: &Message) -> Message {
+    let uri = invite.start_line.split(' ').nth(1).unwrap_or_default();
+    let mut cancel = Message::request("CANCEL", uri);
+
+    for name in ["Via", "Max-Forwards", "From", "To", "Call-ID"] {
+        if let Some(value) = invite.header(name) {
+            cancel = cancel.with_header(name, value);
+        }
+    }
+    if let Some(cseq) = invite
+        .header("CSeq")
+        .and_then(|cseq| cseq.split_whitespace().next())
+    {
+        cancel = cancel.with_header("CSeq", format!("{cseq} CANCEL"));
+    }
+
+    cancel
+}
+
+impl SignallableImpl for Signaller {
+    fn start(&self) {
+        let settings = self.settings.lock().unwrap().clone();
+        if settings.target_uri.is_none() {
+            self.raise_error("Target URI must be set".to_string());
+            return;
+        }
+
+        self.state.lock().unwrap().local_tag = random_token();
+
+        let obj = self.obj().downgrade();
+        let task = RUNTIME.spawn(async move {
+            let Some(obj) = obj.upgrade() else {
+                return;
+            };
+            let this = obj.imp();
+
+            if settings.registrar_uri.is_some() {
+                if let Err(err) = this.register(settings.register_expires).await {
+                    this.raise_error(format!("{err:#}"));
+                    return;
+                }
+
+                if settings.register_expires > 0 {
+                    this.spawn_register_refresh(settings.register_expires);
+                }
+            }
+
+            // The sink or source creates the offer, which we send once ICE
+            // gathering is complete
+            obj.emit_by_name::<()>(
+                "session-requested",
+                &[
+                    &SESSION_ID,
+                    &SESSION_ID,
+                    &None::<WebRTCSessionDescription>,
+                    &None::<gst::Structure>,
+                ],
+            );
+        });
+
+        self.state.lock().unwrap().tasks.push(task);
+    }
+
+    fn stop(&self) {
+        self.cancel();
+        self.hang_up();
+        self.unregister();
+
+        *self.state.lock().unwrap() = State::default();
+    }
+
+    fn end_session(&self, session_id: &str) {
+        if session_id != SESSION_ID {
+            gst::warning!(CAT, imp: self, "Ignoring request to end unknown session {session_id}");
+            return;
+        }
+
+        self.cancel();
+        self.hang_up();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Signaller {
+    const NAME: &'static str = "GstSipWebRTCSignaller";
+    type Type = super::SipSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for Signaller {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.obj().connect_closure(
+            "consumer-added",
+            false,
+            glib::closure!(|signaller: &super::SipSignaller,
+                            _peer_id: &str,
+                            webrtcbin: &gst::Element| {
+                let obj_weak = signaller.downgrade();
+                webrtcbin.connect_notify(Some("ice-gathering-state"), move |webrtcbin, _pspec| {
+                    let Some(obj) = obj_weak.upgrade() else {
+                        return;
+                    };
+
+                    let gathering_state =
+                        webrtcbin.property::<WebRTCICEGatheringState>("ice-gathering-state");
+
+                    if gathering_state == WebRTCICEGatheringState::Complete {
+                        // Gathering completes again when new candidates get
+                        // gathered, the call is only placed once
+                        {
+                            let mut state = obj.imp().state.lock().unwrap();
+                            if state.call_placed {
+                                gst::debug!(CAT, obj: obj, "Call already placed");
+                                return;
+                            }
+                            state.call_placed = true;
+                        }
+
+                        gst::info!(CAT, obj: obj, "ICE gathering complete, placing the call");
+
+                        let webrtcbin = webrtcbin.clone();
+                        let obj_weak = obj.downgrade();
+                        let task = RUNTIME.spawn(async move {
+                            let Some(obj) = obj_weak.upgrade() else {
+                                return;
+                            };
+
+                            if let Err(err) = obj.imp().invite(&webrtcbin).await {
+                                obj.imp().raise_error(format!("{err:#}"));
+                            }
+                        });
+
+                        obj.imp().state.lock().unwrap().tasks.push(task);
+                    }
+                });
+            }),
+        );
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecString::builder("target-uri")
+                    .nick("Target URI")
+                    .blurb("The SIP URI to call, e.g.: sip:1000@pbx.example.com")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("local-uri")
+                    .nick("Local URI")
+                    .blurb("Our SIP address of record, made up from the user name and the registrar host if not set")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("registrar-uri")
+                    .nick("Registrar URI")
+                    .blurb("The SIP URI of the registrar to register with before calling, e.g.: sip:pbx.example.com. All requests are sent to its host when set")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("username")
+                    .nick("Username")
+                    .blurb("User name to answer digest authentication challenges with")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("password")
+                    .nick("Password")
+                    .blurb("Password to answer digest authentication challenges with")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("transport", DEFAULT_TRANSPORT)
+                    .nick("Transport")
+                    .blurb("The transport to reach the SIP server with, sips: URIs always use TLS")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("register-expires")
+                    .nick("Register Expires")
+                    .blurb("Seconds the registration is requested for, it is refreshed halfway through")
+                    .minimum(1)
+                    .default_value(DEFAULT_REGISTER_EXPIRES)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("timeout")
+                    .nick("Timeout")
+                    .blurb("Value in seconds to wait for the SIP server to respond (0 = No timeout).")
+                    .maximum(3600)
+                    .default_value(DEFAULT_TIMEOUT)
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "target-uri" => settings.target_uri = value.get().unwrap(),
+            "local-uri" => settings.local_uri = value.get().unwrap(),
+            "registrar-uri" => settings.registrar_uri = value.get().unwrap(),
+            "username" => settings.username = value.get().unwrap(),
+            "password" => settings.password = value.get().unwrap(),
+            "transport" => settings.transport = value.get().unwrap(),
+            "register-expires" => settings.register_expires = value.get().unwrap(),
+            "timeout" => settings.timeout = value.get().unwrap(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "target-uri" => settings.target_uri.to_value(),
+            "local-uri" => settings.local_uri.to_value(),
+            "registrar-uri" => settings.registrar_uri.to_value(),
+            "username" => settings.username.to_value(),
+            "password" => settings.password.to_value(),
+            "transport" => settings.transport.to_value(),
+            "register-expires" => settings.register_expires.to_value(),
+            "timeout" => settings.timeout.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
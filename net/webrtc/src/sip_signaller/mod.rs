@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::Signallable;
+use gst::glib;
+
+mod imp;
+mod protocol;
+mod transport;
+
+#[derive(Debug, Default, Eq, PartialEq, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstSipWebRTCSignallerTransport")]
+pub enum SipTransport {
+    #[default]
+    #[enum_value(name = "UDP", nick = "udp")]
+    Udp,
+    #[enum_value(name = "TCP", nick = "tcp")]
+    Tcp,
+    #[enum_value(name = "TLS over TCP", nick = "tls")]
+    Tls,
+}
+
+impl SipTransport {
+    /// Name of the transport in Via headers and transport URI parameters
+    fn name(&self) -> &'static str {
+        match self {
+            SipTransport::Udp => "udp",
+            SipTransport::Tcp => "tcp",
+            SipTransport::Tls => "tls",
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct SipSignaller(ObjectSubclass<imp::Signaller>) @implements Signallable;
+}
+
+unsafe impl Send for SipSignaller {}
+unsafe impl Sync for SipSignaller {}
+
+impl Default for SipSignaller {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
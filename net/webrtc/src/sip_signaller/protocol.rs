@@ -0,0 +1,438 @@
+// SPDX-License-Identifier: MPL-2.0
+
+/// The subset of SIP (RFC 3261) needed to place a call
+use gst::glib;
+use std::fmt;
+
+/// Magic cookie starting the branch parameter of RFC 3261 compliant Via headers
+pub const BRANCH_MAGIC_COOKIE: &str = "z9hG4bK";
+
+/// Largest message we accept from the remote peer, headers included
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// A SIP request or response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub start_line: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Message {
+    pub fn request(method: &str, uri: &str) -> Self {
+        Self {
+            start_line: format!("{method} {uri} SIP/2.0"),
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    /// Builds a response to `request`, echoing the headers identifying
+    /// its transaction, `local_tag` is added to a To header that carries no tag
+    pub fn response(request: &Message, status: u16, reason: &str, local_tag: &str) -> Self {
+        let mut response = Self {
+            start_line: format!("SIP/2.0 {status} {reason}"),
+            headers: Vec::new(),
+            body: String::new(),
+        };
+
+        for (name, value) in &request.headers {
+            let name = expand_compact_name(name);
+            if ["Via", "From", "Call-ID", "CSeq"]
+                .iter()
+                .any(|header| header.eq_ignore_ascii_case(name))
+            {
+                response.headers.push((name.to_string(), value.clone()));
+            } else if name.eq_ignore_ascii_case("To") {
+                // Requests outside of a dialog are answered with our tag
+                let to = if header_param(value, "tag").is_none() && status > 100 {
+                    format!("{value};tag={local_tag}")
+                } else {
+                    value.clone()
+                };
+                response.headers.push((name.to_string(), to));
+            }
+        }
+
+        response
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, content_type: &str, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self.with_header("Content-Type", content_type)
+    }
+
+    /// First value of the header `name`, compact forms included
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| expand_compact_name(header).eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Status code, for responses
+    pub fn status(&self) -> Option<u16> {
+        self.start_line
+            .strip_prefix("SIP/2.0 ")?
+            .split(' ')
+            .next()?
+            .parse()
+            .ok()
+    }
+
+    /// Method, for requests
+    pub fn method(&self) -> Option<&str> {
+        if self.start_line.starts_with("SIP/2.0 ") {
+            None
+        } else {
+            self.start_line.split(' ').next()
+        }
+    }
+
+    /// The method of the transaction, from the CSeq header
+    pub fn cseq_method(&self) -> Option<&str> {
+        self.header("CSeq")?.split_whitespace().nth(1)
+    }
+
+    /// The branch parameter of the topmost Via header, which identifies
+    /// the transaction
+    pub fn branch(&self) -> Option<&str> {
+        header_param(self.header("Via")?, "branch")
+    }
+
+    /// Parses the first message of `data`, returning it along with the
+    /// number of bytes it spans, or `None` if `data` doesn't hold a whole
+    /// message yet
+    pub fn parse(data: &[u8]) -> Result<Option<(Self, usize)>, anyhow::Error> {
+        // Keep-alives and stray line breaks between messages
+        let skipped = data
+            .iter()
+            .take_while(|byte| **byte == b'\r' || **byte == b'\n')
+            .count();
+        let data = &data[skipped..];
+
+        let Some(head_len) = data.windows(4).position(|window| window == b"\r\n\r\n") else {
+            if data.len() > MAX_MESSAGE_SIZE {
+                anyhow::bail!("Message headers exceed {MAX_MESSAGE_SIZE} bytes");
+            }
+            return Ok(None);
+        };
+
+        let head = std::str::from_utf8(&data[..head_len])?;
+        let mut lines = head.split("\r\n");
+        let start_line = lines
+            .next()
+            .filter(|line| !line.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing start line"))?
+            .to_string();
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in lines {
+            // Folded header values
+            if line.starts_with(' ') || line.starts_with('\t') {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid header line {line}"))?;
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+
+        let mut message = Self {
+            start_line,
+            headers,
+            body: String::new(),
+        };
+
+        let content_length = match message.header("Content-Length") {
+            Some(length) => length.parse::<usize>()?,
+            None => 0,
+        };
+
+        let body_start = head_len + 4;
+        let body_end = body_start
+            .checked_add(content_length)
+            .filter(|end| *end <= MAX_MESSAGE_SIZE)
+            .ok_or_else(|| anyhow::anyhow!("Message exceeds {MAX_MESSAGE_SIZE} bytes"))?;
+
+        if data.len() < body_end {
+            return Ok(None);
+        }
+
+        message.body = String::from_utf8(data[body_start..body_end].to_vec())?;
+
+        Ok(Some((message, skipped + body_end)))
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\r\n", self.start_line)?;
+        for (name, value) in &self.headers {
+            if !expand_compact_name(name).eq_ignore_ascii_case("Content-Length") {
+                write!(f, "{name}: {value}\r\n")?;
+            }
+        }
+        write!(
+            f,
+            "Content-Length: {}\r\n\r\n{}",
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+fn expand_compact_name(name: &str) -> &str {
+    match name {
+        "v" => "Via",
+        "f" => "From",
+        "t" => "To",
+        "i" => "Call-ID",
+        "m" => "Contact",
+        "l" => "Content-Length",
+        "c" => "Content-Type",
+        _ => name,
+    }
+}
+
+/// Value of the parameter `name` of a header such as
+/// `<sip:alice@example.com>;tag=1234`
+pub fn header_param<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    // Parameters of the URI itself are enclosed in angle brackets
+    let params = match value.rfind('>') {
+        Some(end) => &value[end + 1..],
+        None => value,
+    };
+
+    params.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// The URI of a name-addr such as `"Alice" <sip:alice@example.com>;tag=1`
+pub fn header_uri(value: &str) -> &str {
+    match (value.find('<'), value.find('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value.split(';').next().unwrap_or(value).trim(),
+    }
+}
+
+/// The parts of a `sip:` or `sips:` URI we need to reach its host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SipUri {
+    pub secure: bool,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl SipUri {
+    pub fn parse(uri: &str) -> Result<Self, anyhow::Error> {
+        let uri = header_uri(uri);
+        let (secure, rest) = if let Some(rest) = uri.strip_prefix("sips:") {
+            (true, rest)
+        } else if let Some(rest) = uri.strip_prefix("sip:") {
+            (false, rest)
+        } else {
+            anyhow::bail!("Not a SIP URI: {uri}");
+        };
+
+        let rest = rest
+            .split(|c| c == ';' || c == '?')
+            .next()
+            .unwrap_or_default();
+        let hostport = rest.rsplit_once('@').map_or(rest, |(_, hostport)| hostport);
+
+        let (host, port) = if let Some(ipv6) = hostport.strip_prefix('[') {
+            let (host, port) = ipv6
+                .split_once(']')
+                .ok_or_else(|| anyhow::anyhow!("Invalid IPv6 host in {uri}"))?;
+            (host, port.strip_prefix(':'))
+        } else {
+            match hostport.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (hostport, None),
+            }
+        };
+
+        if host.is_empty() {
+            anyhow::bail!("No host in {uri}");
+        }
+
+        let port = port
+            .map(|port| port.parse::<u16>())
+            .transpose()
+            .map_err(|err| anyhow::anyhow!("Invalid port in {uri}: {err}"))?;
+
+        Ok(Self {
+            secure,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+/// A `WWW-Authenticate` or `Proxy-Authenticate` digest challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestChallenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub qop_auth: bool,
+}
+
+impl DigestChallenge {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (scheme, params) = value.trim().split_once(char::is_whitespace)?;
+        if !scheme.eq_ignore_ascii_case("Digest") {
+            return None;
+        }
+
+        let mut realm = None;
+        let mut nonce = None;
+        let mut opaque = None;
+        let mut qop_auth = false;
+
+        for (key, value) in split_auth_params(params) {
+            match key.to_ascii_lowercase().as_str() {
+                "realm" => realm = Some(value),
+                "nonce" => nonce = Some(value),
+                "opaque" => opaque = Some(value),
+                "qop" => qop_auth = value.split(',').any(|qop| qop.trim() == "auth"),
+                "algorithm" if !value.eq_ignore_ascii_case("MD5") => return None,
+                _ => (),
+            }
+        }
+
+        Some(Self {
+            realm: realm?,
+            nonce: nonce?,
+            opaque,
+            qop_auth,
+        })
+    }
+
+    /// The `Authorization` header value answering the challenge as per
+    /// RFC 2617, `cnonce` is only used with `qop=auth`
+    pub fn authorization(
+        &self,
+        method: &str,
+        uri: &str,
+        username: &str,
+        password: &str,
+        cnonce: &str,
+    ) -> String {
+        let ha1 = md5_hex(&format!("{username}:{}:{password}", self.realm));
+        let ha2 = md5_hex(&format!("{method}:{uri}"));
+
+        let mut authorization = format!(
+            "Digest username=\"{username}\", realm=\"{}\", nonce=\"{}\", uri=\"{uri}\", algorithm=MD5",
+            self.realm, self.nonce
+        );
+
+        let response = if self.qop_auth {
+            authorization.push_str(&format!(", qop=auth, nc=00000001, cnonce=\"{cnonce}\""));
+            md5_hex(&format!(
+                "{ha1}:{}:00000001:{cnonce}:auth:{ha2}",
+                self.nonce
+            ))
+        } else {
+            md5_hex(&format!("{ha1}:{}:{ha2}", self.nonce))
+        };
+
+        authorization.push_str(&format!(", response=\"{response}\""));
+        if let Some(opaque) = &self.opaque {
+            authorization.push_str(&format!(", opaque=\"{opaque}\""));
+        }
+
+        authorization
+    }
+}
+
+/// Splits comma separated `key=value` parameters, values may be quoted
+/// and contain commas
+fn split_auth_params(params: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut rest = params.trim();
+
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let after = after.trim_start();
+
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match after.find(',') {
+                Some(end) => (&after[..end], &after[end..]),
+                None => (after, ""),
+            }
+        };
+
+        result.push((key, value.trim().to_string()));
+        rest = remaining.trim_start().trim_start_matches(',');
+    }
+
+    result
+}
+
+fn md5_hex(data: &str) -> String {
+    let mut checksum = glib::Checksum::new(glib::ChecksumType::Md5).unwrap();
+    checksum.update(data.as_bytes());
+    checksum.string().unwrap().to_string()
+}
+
+/// A random token for branches, tags and Call-IDs
+pub fn random_token() -> String {
+    format!("{:016x}", fastrand::u64(..))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_complete_message() {
+        let data = b"MESSAGE sip:bob@example.com SIP/2.0\r\nContent-Length: 5\r\n\r\nhello";
+
+        let (message, consumed) = Message::parse(data).unwrap().unwrap();
+        assert_eq!(message.body, "hello");
+        assert_eq!(consumed, data.len());
+
+        // The body isn't complete yet
+        assert!(Message::parse(&data[..data.len() - 1]).unwrap().is_none());
+    }
+
+    #[test]
+    fn reject_huge_content_length() {
+        let data = b"MESSAGE sip:bob@example.com SIP/2.0\r\n\
+                     Content-Length: 18446744073709551615\r\n\r\n";
+        assert!(Message::parse(data).is_err());
+
+        let data = format!(
+            "MESSAGE sip:bob@example.com SIP/2.0\r\nContent-Length: {}\r\n\r\n",
+            MAX_MESSAGE_SIZE
+        );
+        assert!(Message::parse(data.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn reject_endless_headers() {
+        let data = vec![b'a'; MAX_MESSAGE_SIZE + 1];
+        assert!(Message::parse(&data).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::protocol::{Message, MAX_MESSAGE_SIZE};
+use super::SipTransport;
+use crate::RUNTIME;
+use anyhow::Context;
+use futures::channel::mpsc;
+use futures::prelude::*;
+use gst::glib::once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtc-sip-transport",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC SIP signaller transport"),
+    )
+});
+
+/// RFC 3261 timer T1, the initial retransmission interval over UDP
+const T1: Duration = Duration::from_millis(500);
+/// RFC 3261 timer T2, the maximum retransmission interval over UDP
+const T2: Duration = Duration::from_secs(4);
+
+/// Receives the requests of the server and the responses no transaction
+/// is waiting for, such as retransmitted 2xx responses to an INVITE
+pub trait Handler: Send + Sync + 'static {
+    fn handle_request(&self, request: Message);
+    fn handle_stray_response(&self, response: Message);
+    fn connection_closed(&self);
+}
+
+type Transactions = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Message>>>>;
+
+/// A connection to the SIP server, whose messages are read by a task
+/// dispatching responses to their transaction
+pub struct Connection {
+    pub transport: SipTransport,
+    pub local_addr: SocketAddr,
+    sender: mpsc::UnboundedSender<String>,
+    transactions: Transactions,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Connection {
+    pub async fn connect<H: Handler>(
+        transport: SipTransport,
+        host: &str,
+        port: u16,
+        handler: H,
+    ) -> Result<Self, anyhow::Error> {
+        let addr = tokio::net::lookup_host((host, port))
+            .await
+            .with_context(|| format!("Resolving {host}"))?
+            .next()
+            .with_context(|| format!("No address for {host}"))?;
+
+        gst::debug!(CAT, "Connecting to {addr} over {transport:?}");
+
+        let (sender, receiver) = mpsc::unbounded();
+        let transactions = Transactions::default();
+
+        let (local_addr, task) = match transport {
+            SipTransport::Udp => {
+                let bind_addr: SocketAddr = if addr.is_ipv4() {
+                    ([0, 0, 0, 0], 0).into()
+                } else {
+                    ([0u16; 8], 0).into()
+                };
+                let socket = tokio::net::UdpSocket::bind(bind_addr).await?;
+                socket.connect(addr).await?;
+                let local_addr = socket.local_addr()?;
+
+                let task = RUNTIME.spawn(run_datagrams(
+                    socket,
+                    receiver,
+                    transactions.clone(),
+                    handler,
+                ));
+
+                (local_addr, task)
+            }
+            SipTransport::Tcp => {
+                let stream = tokio::net::TcpStream::connect(addr).await?;
+                let local_addr = stream.local_addr()?;
+
+                let task =
+                    RUNTIME.spawn(run_stream(stream, receiver, transactions.clone(), handler));
+
+                (local_addr, task)
+            }
+            SipTransport::Tls => {
+                let stream = tokio::net::TcpStream::connect(addr).await?;
+                let local_addr = stream.local_addr()?;
+                let connector = tokio_native_tls::TlsConnector::from(
+                    tokio_native_tls::native_tls::TlsConnector::new()?,
+                );
+                let stream = connector.connect(host, stream).await?;
+
+                let task =
+                    RUNTIME.spawn(run_stream(stream, receiver, transactions.clone(), handler));
+
+                (local_addr, task)
+            }
+        };
+
+        Ok(Self {
+            transport,
+            local_addr,
+            sender,
+            transactions,
+            task,
+        })
+    }
+
+    /// Sends `message` outside of any transaction, as for ACKs and
+    /// responses
+    pub fn send(&self, message: &Message) {
+        gst::trace!(CAT, "Sending {}", message.start_line);
+        let _ = self.sender.unbounded_send(message.to_string());
+    }
+
+    /// Sends `request` and waits for its final response. Over UDP, the
+    /// request is retransmitted until the server answers. `timeout` only
+    /// applies until the first response for INVITEs, after which the
+    /// callee may take its time to pick up
+    pub async fn transaction(
+        &self,
+        request: &Message,
+        timeout: Option<Duration>,
+    ) -> Result<Message, anyhow::Error> {
+        let branch = request
+            .branch()
+            .expect("requests are sent with a branch")
+            .to_string();
+
+        let (sender, receiver) = mpsc::unbounded();
+        self.transactions
+            .lock()
+            .unwrap()
+            .insert(branch.clone(), sender);
+
+        let res = self.wait_final_response(request, receiver, timeout).await;
+
+        self.transactions.lock().unwrap().remove(&branch);
+
+        res
+    }
+
+    async fn wait_final_response(
+        &self,
+        request: &Message,
+        mut receiver: mpsc::UnboundedReceiver<Message>,
+        timeout: Option<Duration>,
+    ) -> Result<Message, anyhow::Error> {
+        let start = tokio::time::Instant::now();
+        let mut interval = T1;
+        let mut next_retransmit = (self.transport == SipTransport::Udp).then_some(start + T1);
+        let mut deadline = timeout.map(|timeout| start + timeout);
+
+        self.send(request);
+
+        loop {
+            let wake_up = [next_retransmit, deadline].into_iter().flatten().min();
+
+            let response = match wake_up {
+                Some(wake_up) => match tokio::time::timeout_at(wake_up, receiver.next()).await {
+                    Ok(response) => response,
+                    Err(_) if deadline == Some(wake_up) => {
+                        anyhow::bail!("Timeout waiting for a response to {}", request.start_line)
+                    }
+                    Err(_) => {
+                        gst::debug!(CAT, "Retransmitting {}", request.start_line);
+                        self.send(request);
+                        interval = (interval * 2).min(T2);
+                        next_retransmit = Some(wake_up + interval);
+                        continue;
+                    }
+                },
+                None => receiver.next().await,
+            };
+
+            let response = response.context("Connection closed")?;
+            gst::trace!(CAT, "Received {}", response.start_line);
+
+            if response.status().map_or(false, |status| status >= 200) {
+                return Ok(response);
+            }
+
+            // The server got the request
+            next_retransmit = None;
+            if request.method() == Some("INVITE") {
+                deadline = None;
+            }
+        }
+    }
+}
+
+fn dispatch<H: Handler>(message: Message, transactions: &Transactions, handler: &H) {
+    if message.status().is_none() {
+        handler.handle_request(message);
+        return;
+    }
+
+    let sender = message
+        .branch()
+        .and_then(|branch| transactions.lock().unwrap().get(branch).cloned());
+
+    match sender {
+        Some(sender) => {
+            let _ = sender.unbounded_send(message);
+        }
+        None => handler.handle_stray_response(message),
+    }
+}
+
+async fn run_datagrams<H: Handler>(
+    socket: tokio::net::UdpSocket,
+    mut outgoing: mpsc::UnboundedReceiver<String>,
+    transactions: Transactions,
+    handler: H,
+) {
+    let mut buf = vec![0u8; 65535];
+
+    loop {
+        tokio::select! {
+            msg = outgoing.next() => {
+                let Some(msg) = msg else {
+                    break;
+                };
+
+                if let Err(err) = socket.send(msg.as_bytes()).await {
+                    gst::warning!(CAT, "Failed to send datagram: {err}");
+                }
+            }
+            res = socket.recv(&mut buf) => match res {
+                Ok(len) => match Message::parse(&buf[..len]) {
+                    Ok(Some((message, _))) => dispatch(message, &transactions, &handler),
+                    Ok(None) => gst::warning!(CAT, "Ignoring truncated datagram"),
+                    Err(err) => gst::warning!(CAT, "Ignoring invalid datagram: {err}"),
+                },
+                // For instance when nothing listens on the port of the server,
+                // retransmissions may reach it later on
+                Err(err) => gst::warning!(CAT, "Failed to receive datagram: {err}"),
+            }
+        }
+    }
+}
+
+async fn run_stream<S, H>(
+    stream: S,
+    mut outgoing: mpsc::UnboundedReceiver<String>,
+    transactions: Transactions,
+    handler: H,
+) where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    H: Handler,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let mut buf = Vec::new();
+    let mut chunk = vec![0u8; 8192];
+
+    loop {
+        tokio::select! {
+            msg = outgoing.next() => {
+                let Some(msg) = msg else {
+                    break;
+                };
+
+                if let Err(err) = writer.write_all(msg.as_bytes()).await {
+                    gst::warning!(CAT, "Failed to send message: {err}");
+                    break;
+                }
+            }
+            res = reader.read(&mut chunk) => {
+                let len = match res {
+                    Ok(0) => break,
+                    Ok(len) => len,
+                    Err(err) => {
+                        gst::warning!(CAT, "Failed to receive: {err}");
+                        break;
+                    }
+                };
+
+                buf.extend_from_slice(&chunk[..len]);
+
+                // Incomplete messages can't grow past the maximum size,
+                // don't let a hostile peer make us buffer more
+                let mut oversized = false;
+
+                loop {
+                    match Message::parse(&buf) {
+                        Ok(Some((message, consumed))) => {
+                            buf.drain(..consumed);
+                            dispatch(message, &transactions, &handler);
+                        }
+                        Ok(None) => break,
+                        Err(_) if buf.len() > MAX_MESSAGE_SIZE => {
+                            oversized = true;
+                            break;
+                        }
+                        Err(err) => {
+                            gst::warning!(CAT, "Dropping invalid data: {err}");
+                            buf.clear();
+                            break;
+                        }
+                    }
+                }
+
+                if oversized {
+                    gst::warning!(
+                        CAT,
+                        "Closing connection, message exceeds {MAX_MESSAGE_SIZE} bytes"
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    handler.connection_closed();
+}
@@ -277,9 +277,173 @@ pub fn parse_redirect_location(
     }
 }
 
-pub fn build_reqwest_client(pol: Policy) -> reqwest::Client {
-    let client_builder = reqwest::Client::builder();
-    client_builder.redirect(pol).build().unwrap()
+/// Builds the client of the HTTP based signallers, going through `proxy`
+/// if set rather than the proxy of the environment, and connecting with
+/// `tls_connector` if set, see [`TlsSettings::connector`]
+pub fn build_reqwest_client(
+    pol: Policy,
+    proxy: Option<&str>,
+    tls_connector: Option<tokio_native_tls::native_tls::TlsConnector>,
+) -> reqwest::Client {
+    let mut client_builder = reqwest::Client::builder().redirect(pol);
+
+    if let Some(proxy) = proxy {
+        client_builder =
+            client_builder.proxy(reqwest::Proxy::all(proxy).expect("proxy checked when set"));
+    }
+
+    if let Some(tls_connector) = tls_connector {
+        client_builder = client_builder.use_preconfigured_tls(tls_connector);
+    }
+
+    client_builder.build().unwrap()
+}
+
+/// TLS configuration of the connections of a signaller to its server,
+/// exposed as the same properties by all the signallers supporting it
+#[derive(Debug, Default, Clone)]
+pub struct TlsSettings {
+    /// PEM file of certificates to trust on top of the system ones
+    pub cafile: Option<String>,
+    /// PEM file of the certificate to authenticate with
+    pub client_certificate: Option<String>,
+    /// PEM file of the PKCS #8 private key of `client_certificate`
+    pub client_key: Option<String>,
+    /// Accept any server certificate, for lab setups only
+    pub insecure_skip_verify: bool,
+}
+
+impl TlsSettings {
+    pub const PROPERTIES: [&'static str; 4] = [
+        "cafile",
+        "client-certificate",
+        "client-key",
+        "insecure-skip-verify",
+    ];
+
+    pub fn properties() -> Vec<glib::ParamSpec> {
+        vec![
+            glib::ParamSpecString::builder("cafile")
+                .nick("CA file")
+                .blurb("Path to a Certificate file to add to the set of roots the TLS connector will trust")
+                .build(),
+            glib::ParamSpecString::builder("client-certificate")
+                .nick("Client certificate")
+                .blurb("Path to a PEM certificate file to authenticate to the server with, requires client-key")
+                .build(),
+            glib::ParamSpecString::builder("client-key")
+                .nick("Client key")
+                .blurb("Path to the PEM PKCS #8 private key file of the client certificate")
+                .build(),
+            glib::ParamSpecBoolean::builder("insecure-skip-verify")
+                .nick("Insecure skip verify")
+                .blurb("Accept invalid server certificates and host names, for testing setups only")
+                .default_value(false)
+                .build(),
+        ]
+    }
+
+    pub fn set_property(&mut self, name: &str, value: &glib::Value) {
+        match name {
+            "cafile" => self.cafile = value.get().expect("type checked upstream"),
+            "client-certificate" => {
+                self.client_certificate = value.get().expect("type checked upstream")
+            }
+            "client-key" => self.client_key = value.get().expect("type checked upstream"),
+            "insecure-skip-verify" => {
+                self.insecure_skip_verify = value.get().expect("type checked upstream");
+
+                if self.insecure_skip_verify {
+                    gst::warning!(CAT, "Server certificates won't be verified");
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn property(&self, name: &str) -> glib::Value {
+        match name {
+            "cafile" => self.cafile.to_value(),
+            "client-certificate" => self.client_certificate.to_value(),
+            "client-key" => self.client_key.to_value(),
+            "insecure-skip-verify" => self.insecure_skip_verify.to_value(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Connector applying the settings, `None` when they are the defaults
+    pub fn connector(&self) -> Result<Option<tokio_native_tls::native_tls::TlsConnector>, Error> {
+        use tokio_native_tls::native_tls;
+
+        if self.cafile.is_none()
+            && self.client_certificate.is_none()
+            && self.client_key.is_none()
+            && !self.insecure_skip_verify
+        {
+            return Ok(None);
+        }
+
+        let mut builder = native_tls::TlsConnector::builder();
+
+        if let Some(path) = &self.cafile {
+            let cert = std::fs::read(path).with_context(|| format!("reading CA file {path}"))?;
+            builder.add_root_certificate(native_tls::Certificate::from_pem(&cert)?);
+        }
+
+        match (&self.client_certificate, &self.client_key) {
+            (Some(cert_path), Some(key_path)) => {
+                let cert = std::fs::read(cert_path)
+                    .with_context(|| format!("reading client certificate {cert_path}"))?;
+                let key = std::fs::read(key_path)
+                    .with_context(|| format!("reading client key {key_path}"))?;
+                builder.identity(native_tls::Identity::from_pkcs8(&cert, &key)?);
+            }
+            (None, None) => (),
+            _ => anyhow::bail!("client-certificate and client-key must be set together"),
+        }
+
+        if self.insecure_skip_verify {
+            builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+
+        Ok(Some(builder.build()?))
+    }
+}
+
+/// Whether `proxy` is a URL requests can be sent through, with the http,
+/// https, socks5 or socks5h scheme
+pub fn is_valid_proxy(proxy: &str) -> bool {
+    reqwest::Url::parse(proxy).map_or(false, |url| {
+        ["http", "https", "socks5", "socks5h"].contains(&url.scheme())
+    }) && reqwest::Proxy::all(proxy).is_ok()
+}
+
+/// Headers made of the string fields of `extra_headers`, sent along with
+/// the requests of the HTTP based signallers
+pub fn extra_headers(extra_headers: Option<&gst::Structure>) -> HeaderMap {
+    let mut headermap = HeaderMap::new();
+
+    for (name, value) in extra_headers.iter().flat_map(|s| s.iter()) {
+        let header = value.get::<String>().ok().and_then(|value| {
+            Some((
+                reqwest::header::HeaderName::from_bytes(name.as_bytes()).ok()?,
+                reqwest::header::HeaderValue::from_str(&value).ok()?,
+            ))
+        });
+
+        match header {
+            Some((name, value)) => {
+                headermap.append(name, value);
+            }
+            None => {
+                gst::warning!(CAT, "Ignoring invalid extra header {name}: {value:?}");
+            }
+        }
+    }
+
+    headermap
 }
 
 pub fn set_ice_servers(
@@ -357,6 +521,46 @@ pub fn set_ice_servers(
     Ok(())
 }
 
+/// Rewrites the type preference of an ICE `candidate` line according to
+/// its position in `type_order` (e.g. `["relay", "srflx", "host"]`),
+/// candidate types that aren't listed get the lowest preference.
+///
+/// Returns `None` if the candidate couldn't be parsed.
+pub fn prioritize_ice_candidate(candidate: &str, type_order: &[String]) -> Option<String> {
+    // candidate:<foundation> <component> <transport> <priority> <address> <port> typ <type> ...
+    let mut fields = candidate.split(' ').collect::<Vec<_>>();
+
+    let priority = fields.get(3)?.parse::<u32>().ok()?;
+    let type_ = fields
+        .iter()
+        .position(|field| *field == "typ")
+        .and_then(|idx| fields.get(idx + 1))?;
+
+    let type_preference = type_order
+        .iter()
+        .position(|t| t == type_)
+        .map_or(0, |idx| 126u32.saturating_sub(idx as u32));
+
+    let priority = (type_preference << 24) | (priority & 0x00ff_ffff);
+    let priority = priority.to_string();
+    fields[3] = &priority;
+
+    Some(fields.join(" "))
+}
+
+/// Replaces the credentials of a `turn(s)://` server URI, for instance
+/// with ephemeral ones obtained through the TURN REST API.
+///
+/// Returns `None` if the URI couldn't be parsed.
+pub fn set_turn_credentials(turn_server: &str, username: &str, password: &str) -> Option<String> {
+    let mut url = url::Url::parse(turn_server).ok()?;
+
+    url.set_username(username).ok()?;
+    url.set_password(Some(password)).ok()?;
+
+    Some(url.to_string())
+}
+
 /// Wrapper around `gst::ElementFactory::make` with a better error
 /// message
 pub fn make_element(element: &str, name: Option<&str>) -> Result<gst::Element, Error> {
@@ -370,6 +574,33 @@ pub fn make_element(element: &str, name: Option<&str>) -> Result<gst::Element, E
         .with_context(|| format!("Failed to make element {element}"))
 }
 
+/// Describes `clock` as the value of a RFC 7273 `ts-refclk` attribute,
+/// clocks not shared over the network are local to the producer
+pub fn clock_ts_refclk(clock: &gst::Clock) -> String {
+    match clock.type_().name() {
+        "GstNtpClock" => format!(
+            "ntp={}:{}",
+            clock.property::<String>("address"),
+            clock.property::<i32>("port")
+        ),
+        "GstPtpClock" => {
+            let grandmaster_clock_id = clock
+                .property::<u64>("grandmaster-clock-id")
+                .to_be_bytes()
+                .iter()
+                .map(|byte| format!("{byte:02X}"))
+                .collect::<Vec<_>>()
+                .join("-");
+
+            format!(
+                "ptp=IEEE1588-2008:{grandmaster_clock_id}:{}",
+                clock.property::<u32>("domain")
+            )
+        }
+        _ => "local".to_string(),
+    }
+}
+
 #[derive(Debug)]
 struct DecodingInfo {
     has_decoder: AtomicBool,
@@ -386,6 +617,8 @@ impl Clone for DecodingInfo {
 #[derive(Clone, Debug)]
 struct EncodingInfo {
     encoder: gst::ElementFactory,
+    /// All the encoders that can be used for the codec, by decreasing rank
+    encoders: Vec<gst::ElementFactory>,
     payloader: gst::ElementFactory,
     output_filter: Option<gst::Caps>,
 }
@@ -411,18 +644,20 @@ impl Codec {
         payloaders: &glib::List<gst::ElementFactory>,
     ) -> Self {
         let has_decoder = Self::has_decoder_for_caps(caps, decoders);
-        let encoder = Self::get_encoder_for_caps(caps, encoders);
+        let encoders = Self::get_encoders_for_caps(caps, encoders);
         let payloader = Self::get_payloader_for_codec(name, payloaders);
 
-        let encoding_info = if let (Some(encoder), Some(payloader)) = (encoder, payloader) {
-            Some(EncodingInfo {
-                encoder,
-                payloader,
-                output_filter: None,
-            })
-        } else {
-            None
-        };
+        let encoding_info =
+            if let (Some(encoder), Some(payloader)) = (encoders.first().cloned(), payloader) {
+                Some(EncodingInfo {
+                    encoder,
+                    encoders,
+                    payloader,
+                    output_filter: None,
+                })
+            } else {
+                None
+            };
 
         Self {
             caps: caps.clone(),
@@ -492,13 +727,13 @@ impl Codec {
         }
     }
 
-    fn get_encoder_for_caps(
+    fn get_encoders_for_caps(
         caps: &gst::Caps,
         encoders: &glib::List<gst::ElementFactory>,
-    ) -> Option<gst::ElementFactory> {
+    ) -> Vec<gst::ElementFactory> {
         encoders
             .iter()
-            .find(|factory| {
+            .filter(|factory| {
                 factory.static_pad_templates().iter().any(|template| {
                     let template_caps = template.caps();
                     template.direction() == gst::PadDirection::Src
@@ -507,6 +742,7 @@ impl Codec {
                 })
             })
             .cloned()
+            .collect()
     }
 
     fn get_payloader_for_codec(
@@ -640,6 +876,48 @@ impl Codec {
             .map(|info| info.encoder.name().to_string())
     }
 
+    /// Selects the highest ranked hardware encoder when available if
+    /// `prefer_hardware` is true, the highest ranked software encoder
+    /// when available otherwise.
+    pub fn set_prefer_hardware_encoder(&mut self, prefer_hardware: bool) {
+        if let Some(info) = self.encoding_info.as_mut() {
+            if let Some(encoder) = info
+                .encoders
+                .iter()
+                .find(|factory| {
+                    factory.has_type(gst::ElementFactoryType::HARDWARE) == prefer_hardware
+                })
+                .or_else(|| info.encoders.first())
+            {
+                info.encoder = encoder.clone();
+            }
+        }
+    }
+
+    /// Falls back to the highest ranked encoder accepted by `is_available`
+    /// when the currently selected encoder isn't, returns whether an
+    /// available encoder is selected.
+    pub fn select_available_encoder<F>(&mut self, is_available: F) -> bool
+    where
+        F: Fn(&gst::ElementFactory) -> bool,
+    {
+        let Some(info) = self.encoding_info.as_mut() else {
+            return false;
+        };
+
+        if is_available(&info.encoder) {
+            return true;
+        }
+
+        match info.encoders.iter().find(|factory| is_available(factory)) {
+            Some(encoder) => {
+                info.encoder = encoder.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn set_output_filter(&mut self, caps: gst::Caps) {
         if let Some(info) = self.encoding_info.as_mut() {
             info.output_filter = Some(caps);
@@ -825,6 +1103,18 @@ impl Codecs {
             .collect()
     }
 
+    /// Returns the codec of an already payloaded stream, with the payload
+    /// type it is using
+    pub fn find_for_payloaded_caps(caps: &gst::Caps) -> Option<Codec> {
+        let s = caps.structure(0)?;
+        let encoding_name = s.get::<&str>("encoding-name").ok()?.to_uppercase();
+
+        let mut codec = Self::find(&encoding_name)?;
+        codec.set_pt(s.get::<i32>("payload").ok()?);
+
+        Some(codec)
+    }
+
     pub fn audio_codec_names() -> Vec<String> {
         CODECS
             .iter()
@@ -879,6 +1169,11 @@ pub fn is_raw_caps(caps: &gst::Caps) -> bool {
     ["video/x-raw", "audio/x-raw"].contains(&caps.structure(0).unwrap().name().as_str())
 }
 
+pub fn is_rtp_caps(caps: &gst::Caps) -> bool {
+    caps.structure(0)
+        .map_or(false, |s| s.name() == "application/x-rtp")
+}
+
 pub fn cleanup_codec_caps(mut caps: gst::Caps) -> gst::Caps {
     assert!(caps.is_fixed());
 
@@ -901,3 +1196,24 @@ pub struct NavigationEvent {
     #[serde(flatten)]
     pub event: gst_video::NavigationEvent,
 }
+
+/// Quality constraints a consumer can request, a value of 0 removes
+/// the constraint
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum QualityPreference {
+    /// Maximum height of the video, the width follows the aspect ratio
+    PreferredResolution { height: u32 },
+    /// Maximum framerate of the video, in frames per second
+    PreferredFramerate { framerate: u32 },
+    /// Maximum bitrate of the video encoder, in bits per second
+    MaxBitrate { bitrate: u32 },
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct QualityPreferenceMessage {
+    /// Applies to all video streams of the consumer when None
+    pub mid: Option<String>,
+    #[serde(flatten)]
+    pub preference: QualityPreference,
+}
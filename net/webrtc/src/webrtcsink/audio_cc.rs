@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-audiocc",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink audio congestion control"),
+    )
+});
+
+/* opusenc only generates inband FEC data when it expects some loss */
+pub const AUDIO_FEC_PACKET_LOSS_PERCENTAGE: i32 = 10;
+/* Audio gets a fixed share of the estimated bandwidth, never more than
+ * the bitrate its encoder was configured with */
+const AUDIO_BITRATE_RATIO: f64 = 0.1;
+/* Keeps speech intelligible on very constrained links */
+pub const DEFAULT_AUDIO_MIN_BITRATE: u32 = 16000;
+/* The highest bitrate opusenc accepts, the bitrate the encoder is
+ * configured with is the actual ceiling */
+pub const DEFAULT_AUDIO_MAX_BITRATE: u32 = 650000;
+/* Below this, inband FEC would starve the primary encoding */
+const AUDIO_FEC_MIN_BITRATE: i32 = 24000;
+
+/// Wrapper around GStreamer audio encoder element, lets congestion
+/// control back off the audio bitrate on very constrained links
+pub struct AudioEncoder {
+    factory_name: String,
+    element: gst::Element,
+    session_id: String,
+    fec: bool,
+    min_bitrate: i32,
+    /// The bitrate the encoder was configured with, unless capped by the
+    /// settings
+    max_bitrate: i32,
+}
+
+impl AudioEncoder {
+    /// Wraps `element` if we know how to drive it, keeping its bitrate
+    /// between the `min_bitrate` and `max_bitrate` settings
+    pub fn new(
+        element: &gst::Element,
+        session_id: &str,
+        fec: bool,
+        min_bitrate: u32,
+        max_bitrate: u32,
+    ) -> Option<Self> {
+        let factory_name: String = element.factory()?.name().into();
+
+        // Only adapt encoders we know how to drive
+        if factory_name != "opusenc" {
+            return None;
+        }
+
+        // Congestion control only ever lowers the bitrate the application
+        // configured
+        let max_bitrate = element.property::<i32>("bitrate").min(max_bitrate as i32);
+
+        Some(Self {
+            factory_name,
+            element: element.clone(),
+            session_id: session_id.to_string(),
+            fec,
+            min_bitrate: (min_bitrate as i32).min(max_bitrate),
+            max_bitrate,
+        })
+    }
+
+    /// Share of the bandwidth estimated for the whole session given to
+    /// this encoder
+    pub fn target_bitrate(&self, estimated_bitrate: i32) -> i32 {
+        ((estimated_bitrate as f64 * AUDIO_BITRATE_RATIO) as i32)
+            .clamp(self.min_bitrate, self.max_bitrate)
+    }
+
+    fn bitrate(&self) -> i32 {
+        match self.factory_name.as_str() {
+            "opusenc" => self.element.property::<i32>("bitrate"),
+            factory => unimplemented!("Factory {} is currently not supported", factory),
+        }
+    }
+
+    /// Adapts the audio bitrate to the bandwidth estimated for the
+    /// whole session
+    pub fn set_bitrate(&mut self, element: &super::BaseWebRTCSink, estimated_bitrate: i32) {
+        let bitrate = self.target_bitrate(estimated_bitrate);
+
+        if bitrate == self.bitrate() {
+            return;
+        }
+
+        gst::log!(
+            CAT,
+            obj: element,
+            "session {}: setting bitrate {} on audio encoder {:?}",
+            self.session_id,
+            bitrate,
+            self.element
+        );
+
+        match self.factory_name.as_str() {
+            "opusenc" => {
+                self.element.set_property("bitrate", bitrate);
+                self.element
+                    .set_property("inband-fec", self.fec && bitrate >= AUDIO_FEC_MIN_BITRATE);
+            }
+            factory => unimplemented!("Factory {} is currently not supported", factory),
+        }
+    }
+
+    pub fn gather_stats(&self) -> gst::Structure {
+        let mut ret = gst::Structure::builder("application/x-webrtcsink-audio-encoder-stats")
+            .field("bitrate", self.bitrate())
+            .build();
+
+        if self.factory_name == "opusenc" {
+            ret.set("inband-fec", self.element.property::<bool>("inband-fec"));
+        }
+
+        ret
+    }
+}
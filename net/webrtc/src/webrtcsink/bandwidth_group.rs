@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib::once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-bandwidthgroup",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink bandwidth group"),
+    )
+});
+
+#[derive(Debug, Clone, Copy)]
+struct Member {
+    /// Total bitrate of the uplink according to this member
+    capacity: u32,
+    /// Bitrate this member would like to send at
+    demand: u32,
+}
+
+/// The sessions of all webrtcsink instances sharing a bandwidth group,
+/// by group name
+static GROUPS: Lazy<Mutex<HashMap<String, HashMap<u64, Member>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_MEMBER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The share of a session in a bandwidth group, removed from the group
+/// when dropped
+#[derive(Debug)]
+pub struct Membership {
+    group: String,
+    id: u64,
+}
+
+impl Membership {
+    /// Joins `group`, whose members share an uplink of `capacity` bits per
+    /// second. When members disagree on the capacity, the smallest one
+    /// wins
+    pub fn join(group: &str, capacity: u32) -> Self {
+        let id = NEXT_MEMBER_ID.fetch_add(1, Ordering::Relaxed);
+
+        let mut groups = GROUPS.lock().unwrap();
+        let members = groups.entry(group.to_string()).or_default();
+        members.insert(
+            id,
+            Member {
+                capacity,
+                demand: 0,
+            },
+        );
+
+        gst::debug!(
+            CAT,
+            "Member {id} joined bandwidth group {group}, now {} members",
+            members.len()
+        );
+
+        Self {
+            group: group.to_string(),
+            id,
+        }
+    }
+
+    /// Records that this member would like to send at `demand` bits per
+    /// second, and returns the bitrate it may use.
+    ///
+    /// The budget of the group is shared fairly: members demanding less
+    /// than an even share leave the rest of it to the others. The returned
+    /// bitrate doesn't depend on `demand`, so that a member whose estimate
+    /// was capped by the group can grow again once the others back off.
+    pub fn ceiling(&self, demand: u32) -> u32 {
+        let mut groups = GROUPS.lock().unwrap();
+        let members = groups
+            .get_mut(&self.group)
+            .expect("members are removed on drop only");
+
+        if let Some(member) = members.get_mut(&self.id) {
+            member.demand = demand;
+        }
+
+        let capacity = members
+            .values()
+            .map(|member| member.capacity)
+            .min()
+            .unwrap_or(0);
+
+        let mut others = members
+            .iter()
+            .filter(|(id, _)| **id != self.id)
+            .map(|(_, member)| member.demand)
+            .collect::<Vec<_>>();
+        others.sort_unstable();
+
+        let mut remaining = capacity as u64;
+        let mut n_sharing = others.len() as u64 + 1;
+
+        for demand in others {
+            if demand as u64 * n_sharing >= remaining {
+                break;
+            }
+
+            remaining -= demand as u64;
+            n_sharing -= 1;
+        }
+
+        let ceiling = (remaining / n_sharing) as u32;
+
+        gst::trace!(
+            CAT,
+            "Member {} of bandwidth group {} demands {demand}, ceiling is {ceiling}",
+            self.id,
+            self.group
+        );
+
+        ceiling
+    }
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        let mut groups = GROUPS.lock().unwrap();
+        if let Some(members) = groups.get_mut(&self.group) {
+            members.remove(&self.id);
+
+            gst::debug!(
+                CAT,
+                "Member {} left bandwidth group {}, {} members left",
+                self.id,
+                self.group,
+                members.len()
+            );
+
+            if members.is_empty() {
+                groups.remove(&self.group);
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::RUNTIME;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-drain",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink session draining"),
+    )
+});
+
+/// Calls `on_drained` from the runtime once all the media handed to
+/// `webrtcbin` was sent, returns false without calling it if that is
+/// already the case
+pub fn watch_webrtcbin<F>(webrtcbin: &gst::Element, on_drained: F) -> bool
+where
+    F: FnOnce() + Send + 'static,
+{
+    // EOS reaching webrtcbin doesn't mean the media was sent yet, it
+    // still has to go through rtpbin and the transport. The RTP
+    // source pads of the SRTP encoders feed the ICE sinks directly,
+    // once EOS reached them all the media of the session was sent.
+    let pads: Vec<gst::Pad> = webrtcbin
+        .downcast_ref::<gst::Bin>()
+        .unwrap()
+        .iterate_all_by_element_factory_name("srtpenc")
+        .into_iter()
+        .flatten()
+        .flat_map(|srtpenc| srtpenc.src_pads())
+        .filter(|pad| pad.name().starts_with("rtp_src_"))
+        .filter(|pad| pad.sticky_event::<gst::event::Eos>(0).is_none())
+        .collect();
+
+    if pads.is_empty() {
+        return false;
+    }
+
+    gst::debug!(
+        CAT,
+        obj: webrtcbin,
+        "Waiting for EOS on {} RTP source pads",
+        pads.len()
+    );
+
+    let pending = Arc::new(AtomicU64::new(pads.len() as u64));
+    let on_drained = Arc::new(Mutex::new(Some(on_drained)));
+
+    for pad in pads {
+        let pending = pending.clone();
+        let on_drained = on_drained.clone();
+        pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_pad, info| {
+            let Some(gst::PadProbeData::Event(ref event)) = info.data else {
+                return gst::PadProbeReturn::Ok;
+            };
+
+            if event.type_() != gst::EventType::Eos {
+                return gst::PadProbeReturn::Ok;
+            }
+
+            if pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+                if let Some(on_drained) = on_drained.lock().unwrap().take() {
+                    // Not ending the session from its streaming thread
+                    RUNTIME.spawn(async move { on_drained() });
+                }
+            }
+
+            gst::PadProbeReturn::Remove
+        });
+    }
+
+    true
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use std::cell::RefCell;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Error};
+
+use crate::utils::Codec;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-encoderpool",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink encoder pool"),
+    )
+});
+
+/// Number of hardware encoder slots reserved across all sessions and
+/// discoveries, some hardware (e.g. consumer NVIDIA GPUs) only supports a
+/// limited number of concurrent encoding sessions
+static HARDWARE_ENCODERS: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+
+/// Held while running an NVENC discovery pipeline, concurrent NVENC
+/// session creation is prone to failing
+static NVENC_DISCOVERY: Lazy<futures::lock::Mutex<()>> =
+    Lazy::new(|| futures::lock::Mutex::new(()));
+
+/// An encoder slot, a hardware encoder slot is given back to the pool
+/// when dropped
+#[derive(Debug)]
+pub struct Slot {
+    hardware: bool,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if self.hardware {
+            let mut encoders = HARDWARE_ENCODERS.lock().unwrap();
+            *encoders -= 1;
+
+            gst::trace!(CAT, "Released hardware encoder slot, {} in use", *encoders);
+        }
+    }
+}
+
+impl Slot {
+    /// Keeps the slot reserved for as long as `encoder` is alive
+    pub fn attach(self, encoder: &gst::Element) {
+        if self.hardware {
+            gst::debug!(CAT, obj: encoder, "Attaching hardware encoder slot");
+        }
+
+        let _ = encoder.add_weak_ref_notify(move || drop(self));
+    }
+}
+
+/// Reserves a slot for an encoder instantiated from `factory` without
+/// exceeding `max_hardware_encoders` (0 means unlimited). Checking and
+/// reserving is atomic, concurrent sessions and discoveries can't end up
+/// exceeding the limit.
+fn reserve(factory: &gst::ElementFactory, max_hardware_encoders: u32) -> Option<Slot> {
+    if !factory.has_type(gst::ElementFactoryType::HARDWARE) {
+        return Some(Slot { hardware: false });
+    }
+
+    let mut encoders = HARDWARE_ENCODERS.lock().unwrap();
+
+    gst::trace!(
+        CAT,
+        "{} hardware encoders in use, limit is {}",
+        *encoders,
+        max_hardware_encoders
+    );
+
+    if max_hardware_encoders != 0 && *encoders >= max_hardware_encoders as usize {
+        return None;
+    }
+
+    *encoders += 1;
+
+    Some(Slot { hardware: true })
+}
+
+/// Selects the highest ranked encoder of `codec` a slot could be reserved
+/// for, falling back from hardware encoders to other encoders once the
+/// limit is reached. Returns `None` for codecs that are not encoded, and
+/// an error if no encoder is available.
+pub fn reserve_encoder(
+    codec: &mut Codec,
+    max_hardware_encoders: u32,
+) -> Result<Option<Slot>, Error> {
+    if codec.encoder_factory().is_none() {
+        return Ok(None);
+    }
+
+    let slot = RefCell::new(None);
+    codec.select_available_encoder(|factory| match reserve(factory, max_hardware_encoders) {
+        Some(reserved) => {
+            *slot.borrow_mut() = Some(reserved);
+            true
+        }
+        None => false,
+    });
+
+    match slot.into_inner() {
+        Some(slot) => Ok(Some(slot)),
+        None => Err(anyhow!(
+            "No {} encoder available under the limit of {} hardware encoders",
+            codec.name,
+            max_hardware_encoders
+        )),
+    }
+}
+
+/// Waits until no other discovery pipeline is using an NVENC encoder if
+/// `factory` is one, the returned guard must be kept for as long as the
+/// discovery pipeline is running
+pub async fn serialize_discovery(
+    factory: Option<&gst::ElementFactory>,
+) -> Option<futures::lock::MutexGuard<'static, ()>> {
+    let factory = factory?;
+
+    if !factory.name().starts_with("nv") || !factory.has_type(gst::ElementFactoryType::HARDWARE) {
+        return None;
+    }
+
+    gst::trace!(
+        CAT,
+        "Waiting for NVENC discovery slot for {}",
+        factory.name()
+    );
+
+    Some(NVENC_DISCOVERY.lock().await)
+}
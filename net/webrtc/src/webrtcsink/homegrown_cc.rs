@@ -16,6 +16,40 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     )
 });
 
+pub const DEFAULT_INCREASE_FACTOR: f64 = 1.03;
+pub const DEFAULT_DECREASE_FACTOR: f64 = 0.96;
+pub const DEFAULT_LOSS_THRESHOLD_HIGH: f64 = 10.;
+pub const DEFAULT_LOSS_THRESHOLD_LOW: f64 = 2.;
+pub const DEFAULT_UPDATE_INTERVAL: u32 = 100;
+
+/// User-tunable parameters of the controller
+#[derive(Debug, Clone, Copy)]
+pub struct CongestionControllerParams {
+    /// Factor applied on multiplicative increase of the bitrate
+    pub increase_factor: f64,
+    /// Highest factor applied when the delay-based controller decreases
+    /// the bitrate
+    pub decrease_factor: f64,
+    /// Packet loss percentage above which the bitrate is decreased
+    pub loss_threshold_high: f64,
+    /// Packet loss percentage below which the bitrate is increased
+    pub loss_threshold_low: f64,
+    /// Interval in milliseconds between two runs of the delay-based controller
+    pub update_interval: u32,
+}
+
+impl Default for CongestionControllerParams {
+    fn default() -> Self {
+        Self {
+            increase_factor: DEFAULT_INCREASE_FACTOR,
+            decrease_factor: DEFAULT_DECREASE_FACTOR,
+            loss_threshold_high: DEFAULT_LOSS_THRESHOLD_HIGH,
+            loss_threshold_low: DEFAULT_LOSS_THRESHOLD_LOW,
+            update_interval: DEFAULT_UPDATE_INTERVAL,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum IncreaseType {
     /// Increase bitrate by value
@@ -92,10 +126,44 @@ pub struct CongestionController {
 
     min_bitrate: u32,
     max_bitrate: u32,
+
+    params: CongestionControllerParams,
+
+    /// Bitrate currently spent on retransmissions, deducted from what
+    /// the encoders are allowed to produce
+    rtx_bitrate: i32,
+
+    /// Bitrate given to the audio encoders, deducted as well
+    audio_bitrate: i32,
+
+    /// Maximum bitrate requested through the signaller
+    bitrate_ceiling: Option<u32>,
+
+    /// Last operation applied, for reporting purposes
+    last_control_op: Option<CongestionControlOp>,
 }
 
 impl CongestionController {
-    pub fn new(peer_id: &str, min_bitrate: u32, max_bitrate: u32) -> Self {
+    pub fn new(
+        peer_id: &str,
+        min_bitrate: u32,
+        max_bitrate: u32,
+        mut params: CongestionControllerParams,
+    ) -> Self {
+        // The thresholds are set one at a time, only check them together
+        if params.loss_threshold_low > params.loss_threshold_high {
+            gst::warning!(
+                CAT,
+                "Swapping inverted loss thresholds, low {} > high {}",
+                params.loss_threshold_low,
+                params.loss_threshold_high
+            );
+            std::mem::swap(
+                &mut params.loss_threshold_low,
+                &mut params.loss_threshold_high,
+            );
+        }
+
         Self {
             target_bitrate_on_delay: 0,
             target_bitrate_on_loss: 0,
@@ -105,7 +173,81 @@ impl CongestionController {
             peer_id: peer_id.to_string(),
             min_bitrate,
             max_bitrate,
+            params,
+            rtx_bitrate: 0,
+            audio_bitrate: 0,
+            bitrate_ceiling: None,
+            last_control_op: None,
+        }
+    }
+
+    /// The bitrate currently targeted for all streams
+    pub fn target_bitrate(&self) -> i32 {
+        i32::min(self.target_bitrate_on_delay, self.target_bitrate_on_loss)
+    }
+
+    /// Updates the bitrate measured for retransmissions
+    pub fn set_rtx_bitrate(&mut self, rtx_bitrate: u32) {
+        self.rtx_bitrate = rtx_bitrate as i32;
+    }
+
+    /// Updates the bitrate given to the audio encoders
+    pub fn set_audio_bitrate(&mut self, audio_bitrate: u32) {
+        self.audio_bitrate = audio_bitrate as i32;
+    }
+
+    /// Caps the bitrate targeted for all streams, `None` lifts the cap
+    pub fn set_bitrate_ceiling(&mut self, bitrate_ceiling: Option<u32>) {
+        self.bitrate_ceiling = bitrate_ceiling;
+    }
+
+    /// Upper bound of the bitrate targeted for all streams
+    fn max_target_bitrate(&self, n_encoders: i32) -> i32 {
+        let max_bitrate = self.max_bitrate as i32 * n_encoders;
+
+        self.bitrate_ceiling
+            .map_or(max_bitrate, |ceiling| max_bitrate.min(ceiling as i32))
+            .max(self.min_bitrate as i32 * n_encoders)
+    }
+
+    /// Bitrate each encoder is allowed to produce out of `target_bitrate`.
+    /// The target covers retransmissions and audio as well, which are
+    /// shared equally between the encoders
+    fn encoder_bitrate(&self, target_bitrate: i32, n_encoders: i32) -> i32 {
+        (target_bitrate - (self.rtx_bitrate + self.audio_bitrate) / n_encoders.max(1))
+            .max(self.min_bitrate as i32)
+    }
+
+    /// Snapshot of the internal state of the controller
+    pub fn gather_stats(&self) -> gst::Structure {
+        let mut ret = gst::Structure::builder("application/x-webrtcsink-homegrown-cc-state")
+            .field("target-bitrate-on-delay", self.target_bitrate_on_delay)
+            .field("target-bitrate-on-loss", self.target_bitrate_on_loss)
+            .field("bitrate-stdev", self.bitrate_emvar.sqrt())
+            .field("rtx-bitrate", self.rtx_bitrate)
+            .field("audio-bitrate", self.audio_bitrate)
+            .field(
+                "last-operation",
+                match self.last_control_op {
+                    None => "none",
+                    Some(CongestionControlOp::Hold) => "hold",
+                    Some(CongestionControlOp::Decrease { .. }) => "decrease",
+                    Some(CongestionControlOp::Increase(IncreaseType::Additive(_))) => {
+                        "additive-increase"
+                    }
+                    Some(CongestionControlOp::Increase(IncreaseType::Multiplicative(_))) => {
+                        "multiplicative-increase"
+                    }
+                },
+            )
+            .build();
+
+        // Only tracked after a first decrease from the delay-based controller
+        if let Some(ema) = self.bitrate_ema {
+            ret.set("bitrate-ema", ema);
         }
+
+        ret
     }
 
     fn update_delay(
@@ -139,10 +281,16 @@ impl CongestionController {
 
         if delay_factor > 0.1 {
             let (factor, reason) = if delay_factor < 0.64 {
-                (0.96, format!("low delay factor {delay_factor}"))
+                (
+                    self.params.decrease_factor,
+                    format!("low delay factor {delay_factor}"),
+                )
             } else {
                 (
-                    delay_factor.sqrt().sqrt().clamp(0.8, 0.96),
+                    delay_factor.sqrt().sqrt().clamp(
+                        0.8_f64.min(self.params.decrease_factor),
+                        self.params.decrease_factor,
+                    ),
                     format!("High delay factor {delay_factor}"),
                 )
             };
@@ -179,7 +327,7 @@ impl CongestionController {
                         self.peer_id
                     );
                     /* Multiplicative increase */
-                    IncreaseType::Multiplicative(1.03)
+                    IncreaseType::Multiplicative(self.params.increase_factor)
                 } else if target_bitrate > ema + 7. * bitrate_stdev {
                     gst::trace!(
                         CAT,
@@ -192,7 +340,7 @@ impl CongestionController {
                      * multiplicative increase
                      */
                     self.bitrate_ema.take();
-                    IncreaseType::Multiplicative(1.03)
+                    IncreaseType::Multiplicative(self.params.increase_factor)
                 } else {
                     let rtt_ms = rtt * 1000.;
                     let response_time_ms = 100. + rtt_ms;
@@ -229,7 +377,7 @@ impl CongestionController {
                     "consumer {}: outside congestion window",
                     self.peer_id
                 );
-                IncreaseType::Multiplicative(1.03)
+                IncreaseType::Multiplicative(self.params.increase_factor)
             })
         }
     }
@@ -239,14 +387,14 @@ impl CongestionController {
             ControllerType::Loss => {
                 self.target_bitrate_on_loss = bitrate.clamp(
                     self.min_bitrate as i32 * n_encoders,
-                    self.max_bitrate as i32 * n_encoders,
+                    self.max_target_bitrate(n_encoders),
                 )
             }
 
             ControllerType::Delay => {
                 self.target_bitrate_on_delay = bitrate.clamp(
                     self.min_bitrate as i32 * n_encoders,
-                    self.max_bitrate as i32 * n_encoders,
+                    self.max_target_bitrate(n_encoders),
                 )
             }
         }
@@ -300,12 +448,12 @@ impl CongestionController {
         self.apply_control_op(
             element,
             encoders,
-            if loss_percentage > 10. {
+            if loss_percentage > self.params.loss_threshold_high {
                 CongestionControlOp::Decrease {
                     factor: ((100. - (0.5 * loss_percentage)) / 100.).clamp(0.7, 0.98),
                     reason: format!("High loss: {loss_percentage}"),
                 }
-            } else if loss_percentage > 2. {
+            } else if loss_percentage > self.params.loss_threshold_low {
                 CongestionControlOp::Hold
             } else {
                 CongestionControlOp::Increase(IncreaseType::Multiplicative(1.05))
@@ -385,7 +533,7 @@ impl CongestionController {
         let target_bitrate =
             i32::min(self.target_bitrate_on_delay, self.target_bitrate_on_loss).clamp(
                 self.min_bitrate as i32 * n_encoders,
-                self.max_bitrate as i32 * n_encoders,
+                self.max_target_bitrate(n_encoders),
             ) / n_encoders;
 
         if target_bitrate != prev_bitrate {
@@ -412,11 +560,73 @@ impl CongestionController {
 
         let fec_percentage = (fec_ratio * 50f64) as u32;
 
+        let encoder_bitrate = self.encoder_bitrate(target_bitrate, n_encoders);
+
         for encoder in encoders.iter_mut() {
-            encoder.set_bitrate(element, target_bitrate);
+            encoder.set_bitrate(element, encoder_bitrate);
             encoder
                 .transceiver
                 .set_property("fec-percentage", fec_percentage);
         }
+
+        self.last_control_op = Some(control_op);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> CongestionController {
+        CongestionController::new("peer", 100_000, 5_000_000, Default::default())
+    }
+
+    #[test]
+    fn inverted_loss_thresholds_are_swapped() {
+        let params = CongestionControllerParams {
+            loss_threshold_high: 2.,
+            loss_threshold_low: 10.,
+            ..Default::default()
+        };
+        let cc = CongestionController::new("peer", 100_000, 5_000_000, params);
+
+        assert_eq!(cc.params.loss_threshold_low, 2.);
+        assert_eq!(cc.params.loss_threshold_high, 10.);
+    }
+
+    #[test]
+    fn encoder_bitrate_without_retransmissions() {
+        let cc = controller();
+
+        assert_eq!(cc.encoder_bitrate(1_000_000, 2), 1_000_000);
+    }
+
+    #[test]
+    fn encoder_bitrate_deducts_retransmissions() {
+        let mut cc = controller();
+
+        cc.set_rtx_bitrate(200_000);
+        assert_eq!(cc.encoder_bitrate(1_000_000, 1), 800_000);
+        // Shared between the encoders
+        assert_eq!(cc.encoder_bitrate(1_000_000, 2), 900_000);
+    }
+
+    #[test]
+    fn encoder_bitrate_deducts_audio() {
+        let mut cc = controller();
+
+        cc.set_audio_bitrate(64_000);
+        assert_eq!(cc.encoder_bitrate(1_000_000, 1), 936_000);
+
+        cc.set_rtx_bitrate(200_000);
+        assert_eq!(cc.encoder_bitrate(1_000_000, 2), 868_000);
+    }
+
+    #[test]
+    fn encoder_bitrate_is_clamped_to_minimum() {
+        let mut cc = controller();
+
+        cc.set_rtx_bitrate(2_000_000);
+        assert_eq!(cc.encoder_bitrate(1_000_000, 1), 100_000);
     }
 }
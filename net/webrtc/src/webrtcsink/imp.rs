@@ -1,6 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::utils::{cleanup_codec_caps, is_raw_caps, make_element, Codec, Codecs, NavigationEvent};
+use crate::utils::{
+    cleanup_codec_caps, clock_ts_refclk, is_raw_caps, is_rtp_caps, make_element,
+    prioritize_ice_candidate, Codec, Codecs, NavigationEvent, QualityPreference,
+    QualityPreferenceMessage,
+};
 use anyhow::Context;
 use gst::glib;
 use gst::prelude::*;
@@ -8,7 +12,7 @@ use gst::subclass::prelude::*;
 use gst_rtp::prelude::*;
 use gst_utils::StreamProducer;
 use gst_video::subclass::prelude::*;
-use gst_webrtc::{WebRTCDataChannel, WebRTCICETransportPolicy};
+use gst_webrtc::{WebRTCDataChannel, WebRTCICETransportPolicy, WebRTCPriorityType};
 
 use futures::prelude::*;
 
@@ -17,13 +21,38 @@ use gst::glib::once_cell::sync::Lazy;
 use std::collections::HashMap;
 
 use std::ops::Mul;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{mpsc, Arc, Condvar, Mutex};
 
-use super::homegrown_cc::CongestionController;
-use super::{WebRTCSinkCongestionControl, WebRTCSinkError, WebRTCSinkMitigationMode};
+use super::audio_cc::{
+    AudioEncoder, AUDIO_FEC_PACKET_LOSS_PERCENTAGE, DEFAULT_AUDIO_MAX_BITRATE,
+    DEFAULT_AUDIO_MIN_BITRATE,
+};
+use super::bandwidth_group;
+use super::drain;
+use super::encoder_pool;
+use super::homegrown_cc::{self, CongestionController, CongestionControllerParams};
+use super::interop::{self, Workarounds};
+use super::memory_budget::{self, MemoryBudget};
+use super::placeholder::InputWatchdog;
+use super::playout_delay::{PLAYOUT_DELAY_MAX_MS, RTP_PLAYOUT_DELAY_URI};
+use super::quantizer::{QpParser, QpStats};
+use super::session_migration::{ResumeTimeout, RetiredSignallers, SignallerSignals};
+use super::streams;
+use super::thumbnail::ThumbnailGenerator;
+use super::turn;
+use super::{
+    PlayoutDelayExtension, WebRTCSinkCongestionControl, WebRTCSinkError,
+    WebRTCSinkMemoryBudgetPolicy, WebRTCSinkMitigationMode, WebRTCSinkSignallerMigrationPolicy,
+    WebRTCSinkSignallerType,
+};
 use crate::aws_kvs_signaller::AwsKvsSignaller;
+use crate::grpc_signaller::GrpcSignaller;
+use crate::http_server_signaller::HttpServerSignaller;
+use crate::ipc_signaller::IpcSignaller;
 use crate::livekit_signaller::LiveKitSignaller;
 use crate::signaller::{prelude::*, Signallable, Signaller, WebRTCSignallerRole};
+use crate::sip_signaller::SipSignaller;
 use crate::whip_signaller::WhipSignaller;
 use crate::RUNTIME;
 use std::collections::{BTreeMap, HashSet};
@@ -40,8 +69,10 @@ const CUDA_MEMORY_FEATURE: &str = "memory:CUDAMemory";
 const GL_MEMORY_FEATURE: &str = "memory:GLMemory";
 const NVMM_MEMORY_FEATURE: &str = "memory:NVMM";
 
-const RTP_TWCC_URI: &str =
+pub const RTP_TWCC_URI: &str =
     "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01";
+const RTP_NTP64_URI: &str = "urn:ietf:params:rtp-hdrext:ntp-64";
+const RTP_AUDIO_LEVEL_URI: &str = "urn:ietf:params:rtp-hdrext:ssrc-audio-level";
 
 const DEFAULT_STUN_SERVER: Option<&str> = Some("stun://stun.l.google.com:19302");
 const DEFAULT_MIN_BITRATE: u32 = 1000;
@@ -54,12 +85,64 @@ const DEFAULT_CONGESTION_CONTROL: WebRTCSinkCongestionControl =
     WebRTCSinkCongestionControl::GoogleCongestionControl;
 const DEFAULT_DO_FEC: bool = true;
 const DEFAULT_DO_RETRANSMISSION: bool = true;
+const DEFAULT_RTX_MAX_SIZE_TIME: u32 = 0;
+const DEFAULT_RTX_MAX_SIZE_PACKETS: u32 = 100;
 const DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION: bool = false;
+const DEFAULT_ENABLE_FRAME_TIMING: bool = false;
+const DEFAULT_THUMBNAIL_INTERVAL: u32 = 0;
+const DEFAULT_THUMBNAIL_WIDTH: u32 = 320;
+const DEFAULT_DO_CLOCK_SIGNALLING: bool = false;
+const DEFAULT_DO_AUDIO_LEVEL: bool = false;
+const DEFAULT_DSCP: WebRTCPriorityType = WebRTCPriorityType::Low;
+const DEFAULT_TWCC_EXTENSION_ID: u32 = 0;
 const DEFAULT_ICE_TRANSPORT_POLICY: WebRTCICETransportPolicy = WebRTCICETransportPolicy::All;
 const DEFAULT_START_BITRATE: u32 = 2048000;
+const DEFAULT_AUDIO_ONLY_BITRATE: u32 = 0;
+const DEFAULT_MIN_FRAMERATE: u32 = 0;
+/// Number of steps of the bitrate mitigation ladder
+const MITIGATION_STEPS: u32 = 3;
+/// Framerate high framerate content is lowered to before the last step
+/// of the mitigation ladder
+const MITIGATION_NOMINAL_FRAMERATE: i32 = 30;
+const DEFAULT_PREFER_HARDWARE_ENCODERS: bool = true;
+const DEFAULT_MAX_HARDWARE_ENCODERS: u32 = 0;
+const DEFAULT_BANDWIDTH_GROUP_BITRATE: u32 = 10000000;
+const DEFAULT_AUDIO_FEC: bool = false;
+const DEFAULT_AUDIO_DTX: bool = false;
+const DEFAULT_INPUT_TIMEOUT: u32 = 0;
+const DEFAULT_STATS_COLLECTION_INTERVAL: u32 = 100;
+const DEFAULT_DRAIN_TIMEOUT: u32 = 0;
+const DEFAULT_STATS_MESSAGE_INTERVAL: u32 = 0;
+const DEFAULT_VERBOSE_STATS: bool = false;
+const DEFAULT_DISCOVERY_CONCURRENCY: u32 = 1;
+const DEFAULT_SHARED_PIPELINE: bool = false;
+const DEFAULT_ICE_CANDIDATE_BATCH_INTERVAL: u32 = 0;
+const DEFAULT_SESSION_RESUME_TIMEOUT: u32 = 0;
+const DEFAULT_CONNECT_TIMEOUT: u32 = 0;
+const DEFAULT_REQUIRE_APPROVAL: bool = false;
+const DEFAULT_SIGNALLER_MIGRATION_POLICY: WebRTCSinkSignallerMigrationPolicy =
+    WebRTCSinkSignallerMigrationPolicy::EndExistingSessions;
+const DEFAULT_MEMORY_BUDGET: u64 = 0;
+const DEFAULT_MEMORY_BUDGET_POLICY: WebRTCSinkMemoryBudgetPolicy =
+    WebRTCSinkMemoryBudgetPolicy::Warn;
+/* Video is only restored once the bitrate is comfortably above the
+ * audio-only threshold, to avoid flapping around it */
+const AUDIO_ONLY_RECOVERY_FACTOR: f64 = 1.2;
 /* Start adding some FEC when the bitrate > 2Mbps as we found experimentally
  * that it is not worth it below that threshold */
 const DO_FEC_THRESHOLD: u32 = 2000000;
+/* Highest ID of the one-byte RTP header extensions, RFC 8285 */
+const MAX_HEADER_EXTENSION_ID: u32 = 14;
+/* Shortest window the retransmission bitrate is measured over, shorter
+ * ones are too noisy given the bursty nature of retransmissions */
+const RTX_BITRATE_WINDOW: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Playout delay hint sent to consumers, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PlayoutDelay {
+    min: u32,
+    max: u32,
+}
 
 #[derive(Debug, Clone, Copy)]
 struct CCInfo {
@@ -67,6 +150,11 @@ struct CCInfo {
     min_bitrate: u32,
     max_bitrate: u32,
     start_bitrate: u32,
+    audio_only_bitrate: u32,
+    min_framerate: u32,
+    homegrown: CongestionControllerParams,
+    audio_min_bitrate: u32,
+    audio_max_bitrate: u32,
 }
 
 /// User configuration
@@ -75,13 +163,86 @@ struct Settings {
     video_caps: gst::Caps,
     audio_caps: gst::Caps,
     turn_servers: gst::Array,
+    /// Candidate types by decreasing preference, empty to keep the
+    /// priorities computed by the ICE agents
+    ice_candidate_type_order: Vec<String>,
+    /// Local IP addresses to gather ICE candidates on, empty to use all of them
+    ice_local_addresses: Vec<String>,
+    /// Milliseconds to accumulate local candidates for before sending
+    /// them, 0 to send them as soon as they are gathered
+    ice_candidate_batch_interval: u32,
+    /// Allowed DTLS-SRTP protection profiles, empty to allow all of them
+    srtp_profiles: Vec<String>,
+    /// Workarounds to apply to the sessions of the remote peers matching
+    /// the user agent of each rule
+    interop_rules: Vec<gst::Structure>,
     stun_server: Option<String>,
     cc_info: CCInfo,
     do_fec: bool,
     do_retransmission: bool,
+    /// Retransmission history of each stream, in milliseconds and packets,
+    /// 0 for no limit
+    rtx_max_size_time: u32,
+    rtx_max_size_packets: u32,
     enable_data_channel_navigation: bool,
+    enable_frame_timing: bool,
+    /// Interval in milliseconds between the thumbnails sent to consumers,
+    /// 0 if disabled
+    thumbnail_interval: u32,
+    thumbnail_width: u32,
+    do_clock_signalling: bool,
+    do_audio_level: bool,
+    dscp: WebRTCPriorityType,
+    /// ID of the TWCC header extension when offering, 0 to pick the first
+    /// available one
+    twcc_extension_id: u32,
+    /// Header extension IDs left to the application
+    reserved_extension_ids: Vec<u32>,
     meta: Option<gst::Structure>,
+    /// Identifies this producer among the replicas of a deployment
+    instance_id: Option<String>,
+    /// Free form labels of this producer, for routing consumers
+    labels: Option<gst::Structure>,
     ice_transport_policy: WebRTCICETransportPolicy,
+    playout_delay: Option<PlayoutDelay>,
+    prefer_hardware_encoders: bool,
+    max_hardware_encoders: u32,
+    /// Name of the bandwidth group the sessions share their uplink in
+    /// with the sessions of other instances
+    bandwidth_group: Option<String>,
+    /// Total bitrate of the uplink shared by the bandwidth group
+    bandwidth_group_bitrate: u32,
+    audio_fec: bool,
+    audio_dtx: bool,
+    input_timeout: u32,
+    stats_collection_interval: u32,
+    /// Maximum time in milliseconds sessions may take to flush their media
+    /// on EOS, 0 to tear them down right away
+    drain_timeout: u32,
+    /// Time in milliseconds sessions ended by the signaller are kept alive
+    /// for, in case they are requested again, 0 to tear them down right away
+    session_resume_timeout: u32,
+    /// Time in milliseconds sessions have to get connected once negotiation
+    /// started, 0 to wait forever
+    connect_timeout: u32,
+    /// Whether media only flows to new sessions once the application
+    /// approves them
+    require_approval: bool,
+    /// Interval in milliseconds at which stats are posted on the bus, 0 if
+    /// disabled
+    stats_message_interval: u32,
+    /// Whether the stats include the internal state of the bandwidth
+    /// estimator
+    verbose_stats: bool,
+    discovery_concurrency: u32,
+    shared_pipeline: bool,
+    /// What happens to running sessions when the signaller is replaced
+    signaller_migration_policy: WebRTCSinkSignallerMigrationPolicy,
+    /// Maximum number of bytes queued for all the sessions, 0 if unbounded
+    memory_budget: u64,
+    /// What happens when the data queued for the sessions exceeds the
+    /// memory budget
+    memory_budget_policy: WebRTCSinkMemoryBudgetPolicy,
     signaller: Signallable,
 }
 
@@ -140,6 +301,12 @@ struct InputStream {
     out_caps: Option<gst::Caps>,
     /// Pace input data
     clocksync: Option<gst::Element>,
+    /// Switches to placeholder data when the input stalls
+    input_selector: Option<gst::Element>,
+    /// Feeds placeholder data when a raw input stalls
+    watchdog: Option<Arc<InputWatchdog>>,
+    /// Encodes the thumbnails sent to consumers
+    thumbnails: Option<Arc<ThumbnailGenerator>>,
     /// The serial number picked for this stream
     serial: u32,
     /// Whether the input stream is video or not
@@ -163,6 +330,9 @@ struct WebRTCPad {
     stream_name: Option<String>,
     /// The payload selected in the answer, None at first
     payload: Option<i32>,
+    /// Whether the stream is encoded with a hardware encoder if possible,
+    /// its caps were discovered with the encoder picked accordingly
+    prefer_hardware_encoder: bool,
 }
 
 /// Wrapper around GStreamer encoder element, keeps track of factory
@@ -174,22 +344,78 @@ pub struct VideoEncoder {
     codec_name: String,
     element: gst::Element,
     filter: gst::Element,
-    halved_framerate: gst::Fraction,
+    /// Framerate the mitigation ladder doesn't go below, 0/1 for none
+    min_framerate: gst::Fraction,
     video_info: gst_video::VideoInfo,
     session_id: String,
     mitigation_mode: WebRTCSinkMitigationMode,
+    /// Bitrate below which video gets dropped, 0 if disabled
+    audio_only_bitrate: u32,
+    /// Set while video frames are being dropped
+    audio_only_probe: Option<gst::PadProbeId>,
+    /// Constraints requested by the consumer
+    preferences: QualityPreferences,
+    /// Last bitrate requested by congestion control, before applying
+    /// the consumer constraints
+    target_bitrate: Option<i32>,
+    /// Quantizer of the frames encoded since stats were last gathered
+    qp_stats: Arc<Mutex<QpStats>>,
     pub transceiver: gst_webrtc::WebRTCRTPTransceiver,
 }
 
+/// Glass to network latency of the buffers sent for a stream since stats
+/// were last gathered, that is the time between the running time of their
+/// PTS and the moment they were handed to webrtcbin
+#[derive(Debug, Default, Clone, Copy)]
+struct LatencyStats {
+    sum: gst::ClockTime,
+    count: u64,
+    max: gst::ClockTime,
+}
+
+impl LatencyStats {
+    fn add(&mut self, latency: gst::ClockTime) {
+        self.sum += latency;
+        self.count += 1;
+        self.max = self.max.max(latency);
+    }
+
+    /// Returns the average and maximum latency if any buffer was
+    /// accounted for
+    fn summary(&self) -> Option<(gst::ClockTime, gst::ClockTime)> {
+        (self.count > 0).then(|| (self.sum / self.count, self.max))
+    }
+}
+
+/// Latest correspondence between the RTP timestamps of an outgoing stream
+/// and the running time of the buffers they were derived from
+#[derive(Debug, Clone, Copy)]
+struct RtpTimeMapping {
+    ssrc: u32,
+    clock_rate: Option<i32>,
+    rtp_timestamp: u32,
+    running_time: gst::ClockTime,
+}
+
+/// Quality constraints requested by a consumer for a video stream
+#[derive(Debug, Default, Clone, Copy)]
+struct QualityPreferences {
+    height: Option<i32>,
+    framerate: Option<gst::Fraction>,
+    max_bitrate: Option<i32>,
+}
+
 struct Session {
     id: String,
 
-    pipeline: gst::Pipeline,
+    /// The session pipeline, or a bin in the shared pipeline
+    pipeline: gst::Bin,
     webrtcbin: gst::Element,
     rtprtxsend: Option<gst::Element>,
     webrtc_pads: HashMap<u32, WebRTCPad>,
     peer_id: String,
     encoders: Vec<VideoEncoder>,
+    audio_encoders: Vec<AudioEncoder>,
 
     // Our Homegrown controller (if cc_info.heuristic == Homegrown)
     congestion_controller: Option<CongestionController>,
@@ -204,10 +430,67 @@ struct Session {
     links: HashMap<u32, gst_utils::ConsumptionLink>,
     stats_sigid: Option<glib::SignalHandlerId>,
 
+    // When true, our producers don't feed the session
+    paused: bool,
+    /// Set until the application approves the session, which stays
+    /// paused meanwhile
+    awaiting_approval: bool,
+
     // When not None, constructed from offer SDP
     codecs: Option<BTreeMap<i32, Codec>>,
 
+    /// Collects the stats, and runs the homegrown congestion controller
+    /// if any
     stats_collection_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Interval at which stats are collected, 0 if disabled
+    stats_collection_interval: u32,
+
+    /// DTLS-SRTP protection profile negotiated with the consumer
+    srtp_profile: Option<String>,
+    /// When ICE connected, which starts the DTLS handshake
+    ice_connected_at: Option<std::time::Instant>,
+    /// Fingerprints, role and handshake duration of the DTLS connection,
+    /// once established
+    dtls_info: Option<gst::Structure>,
+
+    /// Data channel the timing of the video frames is sent over
+    frame_timing_channel: Option<WebRTCDataChannel>,
+
+    /// Data channel the thumbnails of the video streams are sent over
+    thumbnail_channel: Option<WebRTCDataChannel>,
+
+    /// Capabilities advertised by the consumer when requesting the session
+    capabilities: Option<gst::Structure>,
+
+    /// Codec negotiated for each connected input stream, by stream name
+    negotiated_codecs: BTreeMap<String, Codec>,
+    /// Latency of each connected input stream, by stream name
+    latencies: BTreeMap<String, Arc<Mutex<LatencyStats>>>,
+    /// RTP timestamp mapping of each connected input stream, by stream name
+    rtp_time_mappings: BTreeMap<String, Arc<Mutex<Option<RtpTimeMapping>>>>,
+
+    /// Packets retransmitted at the last measurement, and when it was taken
+    rtx_packets_sent: Option<(u64, std::time::Instant)>,
+    /// Bitrate spent on retransmissions, deducted from the encoders budget
+    rtx_bitrate: u32,
+    /// Maximum bitrate of the session requested through the signaller
+    bitrate_ceiling: Option<u32>,
+    /// Share of the session in its bandwidth group, if any
+    bandwidth_group: Option<bandwidth_group::Membership>,
+
+    /// Local candidates waiting for the current batch to be sent, with
+    /// their m-line index
+    pending_candidates: Vec<(u32, String)>,
+
+    /// TURN servers handed to webrtcbin, with their credentials
+    turn_servers: Vec<String>,
+
+    /// Set while the session waits to be resumed after the signaller
+    /// ended it, tears it down once the grace period is over
+    resume_timeout: Option<ResumeTimeout>,
+    /// Set until the session gets connected, tears it down if that takes
+    /// longer than the connect-timeout
+    connect_timeout_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -216,18 +499,6 @@ enum SignallerState {
     Stopped,
 }
 
-// Used to ensure signal are disconnected when a new signaller is is
-#[allow(dead_code)]
-struct SignallerSignals {
-    error: glib::SignalHandlerId,
-    request_meta: glib::SignalHandlerId,
-    session_requested: glib::SignalHandlerId,
-    session_ended: glib::SignalHandlerId,
-    session_description: glib::SignalHandlerId,
-    handle_ice: glib::SignalHandlerId,
-    shutdown: glib::SignalHandlerId,
-}
-
 /* Our internal state */
 struct State {
     signaller_state: SignallerState,
@@ -246,7 +517,57 @@ struct State {
     navigation_handler: Option<NavigationEventHandler>,
     mids: HashMap<String, String>,
     signaller_signals: Option<SignallerSignals>,
+    /// Signallers replaced while running with the keep-until-closed policy
+    retired_signallers: RetiredSignallers,
     finalizing_sessions: Arc<(Mutex<HashSet<String>>, Condvar)>,
+    /// Sessions whose peer connection is established, used to wait for
+    /// consumers
+    connected_sessions: Arc<(Mutex<HashSet<String>>, Condvar)>,
+    /// Pipeline hosting the bins of all sessions in shared-pipeline mode
+    shared_pipeline: Option<gst::Pipeline>,
+    /// Set while the sessions flush their media after all input streams
+    /// reached EOS
+    draining: bool,
+    /// EOS messages of our sinks, held back until draining is over
+    held_eos_messages: Vec<gst::Message>,
+    /// Posts the stats on the bus while playing
+    stats_message_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Checks the data queued for the sessions against the memory budget
+    /// while playing
+    memory_budget_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Whether the data queued for the sessions exceeded the memory budget
+    memory_budget: MemoryBudget,
+}
+
+/// Keeps the structures of `caps` matching one of the codecs named in
+/// `allowed_codecs`, either encoded media caps or RTP caps
+fn filter_caps_by_codecs(caps: &gst::Caps, allowed_codecs: &[String]) -> gst::Caps {
+    let codecs = allowed_codecs
+        .iter()
+        .filter_map(|name| Codecs::find(&name.to_ascii_uppercase()))
+        .collect::<Vec<_>>();
+
+    let mut ret = gst::Caps::new_empty();
+    let ret_mut = ret.get_mut().unwrap();
+
+    for s in caps.iter() {
+        let allowed = match s.get::<&str>("encoding-name") {
+            Ok(encoding_name) => codecs
+                .iter()
+                .any(|codec| codec.name.eq_ignore_ascii_case(encoding_name)),
+            Err(_) => codecs.iter().any(|codec| {
+                codec
+                    .caps
+                    .can_intersect(&gst::Caps::builder_full().structure(s.to_owned()).build())
+            }),
+        };
+
+        if allowed {
+            ret_mut.append_structure(s.to_owned());
+        }
+    }
+
+    ret
 }
 
 fn create_navigation_event(sink: &super::BaseWebRTCSink, msg: &str) {
@@ -287,6 +608,33 @@ fn create_navigation_event(sink: &super::BaseWebRTCSink, msg: &str) {
     }
 }
 
+fn apply_quality_preference(
+    sink: &super::BaseWebRTCSink,
+    session_id: &str,
+    msg: QualityPreferenceMessage,
+) {
+    gst::log!(
+        CAT,
+        obj: sink,
+        "Applying quality preference for session {}: {:?}",
+        session_id,
+        msg
+    );
+
+    let this = sink.imp();
+    let mut state = this.state.lock().unwrap();
+
+    if let Some(session) = state.sessions.get_mut(session_id) {
+        for encoder in session.encoders.iter_mut() {
+            if msg.mid.is_some() && encoder.transceiver.mid().as_deref() != msg.mid.as_deref() {
+                continue;
+            }
+
+            encoder.set_quality_preference(sink, msg.preference);
+        }
+    }
+}
+
 /// Simple utility for tearing down a pipeline cleanly
 struct PipelineWrapper(gst::Pipeline);
 
@@ -317,22 +665,98 @@ impl Default for Settings {
                 .collect::<gst::Caps>(),
             stun_server: DEFAULT_STUN_SERVER.map(String::from),
             turn_servers: gst::Array::new(Vec::new() as Vec<glib::SendValue>),
+            ice_candidate_type_order: Vec::new(),
+            ice_local_addresses: Vec::new(),
+            ice_candidate_batch_interval: DEFAULT_ICE_CANDIDATE_BATCH_INTERVAL,
+            srtp_profiles: Vec::new(),
+            interop_rules: Vec::new(),
             cc_info: CCInfo {
                 heuristic: WebRTCSinkCongestionControl::GoogleCongestionControl,
                 min_bitrate: DEFAULT_MIN_BITRATE,
                 max_bitrate: DEFAULT_MAX_BITRATE,
                 start_bitrate: DEFAULT_START_BITRATE,
+                audio_only_bitrate: DEFAULT_AUDIO_ONLY_BITRATE,
+                min_framerate: DEFAULT_MIN_FRAMERATE,
+                homegrown: CongestionControllerParams::default(),
+                audio_min_bitrate: DEFAULT_AUDIO_MIN_BITRATE,
+                audio_max_bitrate: DEFAULT_AUDIO_MAX_BITRATE,
             },
             do_fec: DEFAULT_DO_FEC,
             do_retransmission: DEFAULT_DO_RETRANSMISSION,
+            rtx_max_size_time: DEFAULT_RTX_MAX_SIZE_TIME,
+            rtx_max_size_packets: DEFAULT_RTX_MAX_SIZE_PACKETS,
             enable_data_channel_navigation: DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION,
+            enable_frame_timing: DEFAULT_ENABLE_FRAME_TIMING,
+            thumbnail_interval: DEFAULT_THUMBNAIL_INTERVAL,
+            thumbnail_width: DEFAULT_THUMBNAIL_WIDTH,
+            do_clock_signalling: DEFAULT_DO_CLOCK_SIGNALLING,
+            do_audio_level: DEFAULT_DO_AUDIO_LEVEL,
+            dscp: DEFAULT_DSCP,
+            twcc_extension_id: DEFAULT_TWCC_EXTENSION_ID,
+            reserved_extension_ids: Vec::new(),
             meta: None,
+            instance_id: None,
+            labels: None,
             ice_transport_policy: DEFAULT_ICE_TRANSPORT_POLICY,
+            playout_delay: None,
+            prefer_hardware_encoders: DEFAULT_PREFER_HARDWARE_ENCODERS,
+            max_hardware_encoders: DEFAULT_MAX_HARDWARE_ENCODERS,
+            bandwidth_group: None,
+            bandwidth_group_bitrate: DEFAULT_BANDWIDTH_GROUP_BITRATE,
+            audio_fec: DEFAULT_AUDIO_FEC,
+            audio_dtx: DEFAULT_AUDIO_DTX,
+            input_timeout: DEFAULT_INPUT_TIMEOUT,
+            stats_collection_interval: DEFAULT_STATS_COLLECTION_INTERVAL,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            session_resume_timeout: DEFAULT_SESSION_RESUME_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            require_approval: DEFAULT_REQUIRE_APPROVAL,
+            stats_message_interval: DEFAULT_STATS_MESSAGE_INTERVAL,
+            verbose_stats: DEFAULT_VERBOSE_STATS,
+            discovery_concurrency: DEFAULT_DISCOVERY_CONCURRENCY,
+            shared_pipeline: DEFAULT_SHARED_PIPELINE,
+            signaller_migration_policy: DEFAULT_SIGNALLER_MIGRATION_POLICY,
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            memory_budget_policy: DEFAULT_MEMORY_BUDGET_POLICY,
             signaller: signaller.upcast(),
         }
     }
 }
 
+/// IDs of the RTP header extensions we add to our streams when offering
+#[derive(Debug, Clone, Copy)]
+struct HeaderExtensionIds {
+    twcc: Option<u32>,
+    playout_delay: Option<u32>,
+    ntp64: Option<u32>,
+    audio_level: Option<u32>,
+}
+
+impl Settings {
+    /// Assigns IDs to our header extensions, leaving out those reserved for
+    /// the extensions added by the application
+    fn header_extension_ids(&self) -> HeaderExtensionIds {
+        let mut free_ids = (1..=MAX_HEADER_EXTENSION_ID).filter(|id| {
+            *id != self.twcc_extension_id && !self.reserved_extension_ids.contains(id)
+        });
+
+        let twcc = match self.twcc_extension_id {
+            0 => free_ids.next(),
+            id => Some(id),
+        };
+        let playout_delay = free_ids.next();
+        let ntp64 = self.do_clock_signalling.then(|| free_ids.next()).flatten();
+        let audio_level = self.do_audio_level.then(|| free_ids.next()).flatten();
+
+        HeaderExtensionIds {
+            twcc,
+            playout_delay,
+            ntp64,
+            audio_level,
+        }
+    }
+}
+
 impl Default for State {
     fn default() -> Self {
         Self {
@@ -348,11 +772,73 @@ impl Default for State {
             navigation_handler: None,
             mids: HashMap::new(),
             signaller_signals: Default::default(),
+            retired_signallers: RetiredSignallers::default(),
             finalizing_sessions: Arc::new((Mutex::new(HashSet::new()), Condvar::new())),
+            connected_sessions: Arc::new((Mutex::new(HashSet::new()), Condvar::new())),
+            shared_pipeline: None,
+            draining: false,
+            held_eos_messages: Vec::new(),
+            stats_message_handle: None,
+            memory_budget_handle: None,
+            memory_budget: MemoryBudget::default(),
         }
     }
 }
 
+/// Bitrate of `rtx_packets` retransmissions of `packet_size` bytes on
+/// average, sent over `elapsed`
+fn retransmission_bitrate(rtx_packets: u64, packet_size: u64, elapsed: std::time::Duration) -> u32 {
+    if elapsed.is_zero() {
+        return 0;
+    }
+
+    (rtx_packets as f64 * packet_size as f64 * 8. / elapsed.as_secs_f64()) as u32
+}
+
+/// Name of the DTLS-SRTP protection profile (RFC 5764, RFC 7714) matching
+/// the `srtpenc` cipher and authentication nicks
+fn srtp_profile_name(cipher: &str, auth: &str) -> String {
+    match (cipher, auth) {
+        ("aes-128-icm", "hmac-sha1-80") => "SRTP_AES128_CM_SHA1_80".to_string(),
+        ("aes-128-icm", "hmac-sha1-32") => "SRTP_AES128_CM_SHA1_32".to_string(),
+        ("aes-128-gcm", _) => "SRTP_AEAD_AES_128_GCM".to_string(),
+        ("aes-256-gcm", _) => "SRTP_AEAD_AES_256_GCM".to_string(),
+        (cipher, auth) => format!("{cipher}/{auth}"),
+    }
+}
+
+/// Value of a DTLS related SDP attribute, looked up in the first media
+/// that carries it as bundled medias share their transport, then at the
+/// session level
+fn sdp_dtls_attribute(sdp: &gst_sdp::SDPMessageRef, key: &str) -> Option<String> {
+    sdp.medias()
+        .find_map(|media| media.attribute_val(key))
+        .or_else(|| sdp.attribute_val(key))
+        .map(str::to_string)
+}
+
+/// DTLS role we play according to the `setup` attributes of the local and
+/// remote descriptions (RFC 5763)
+fn dtls_role(local_setup: Option<&str>, remote_setup: Option<&str>) -> &'static str {
+    match (local_setup, remote_setup) {
+        (Some("active"), _) => "client",
+        (Some("passive"), _) => "server",
+        (_, Some("active")) => "server",
+        (_, Some("passive")) => "client",
+        _ => "unknown",
+    }
+}
+
+/// Whether an allocation query is for raw video, which we answer with the
+/// requirements of our encoders
+fn is_raw_video_allocation(query: &gst::query::Allocation) -> bool {
+    query
+        .get()
+        .0
+        .and_then(|caps| caps.structure(0))
+        .map_or(false, |s| s.name() == "video/x-raw")
+}
+
 fn make_converter_for_video_caps(caps: &gst::Caps, codec: &Codec) -> Result<gst::Element, Error> {
     assert!(caps.is_fixed());
 
@@ -491,7 +977,9 @@ fn add_nv4l2enc_force_keyunit_workaround(enc: &gst::Element) {
 
 /// Default configuration for known encoders, can be disabled
 /// by returning True from an encoder-setup handler.
-fn configure_encoder(enc: &gst::Element, start_bitrate: u32) {
+fn configure_encoder(enc: &gst::Element, settings: &Settings) {
+    let start_bitrate = settings.cc_info.start_bitrate;
+
     if let Some(factory) = enc.factory() {
         match factory.name().as_str() {
             "vp8enc" | "vp9enc" => {
@@ -547,6 +1035,13 @@ fn configure_encoder(enc: &gst::Element, start_bitrate: u32) {
                 enc.set_property_from_str("control-rate", "constant_bitrate");
                 add_nv4l2enc_force_keyunit_workaround(enc);
             }
+            "opusenc" => {
+                enc.set_property("inband-fec", settings.audio_fec);
+                if settings.audio_fec {
+                    enc.set_property("packet-loss-percentage", AUDIO_FEC_PACKET_LOSS_PERCENTAGE);
+                }
+                enc.set_property("dtx", settings.audio_dtx);
+            }
             _ => (),
         }
     }
@@ -569,9 +1064,30 @@ struct EncodingChainBuilder {
     /// The SSRC to use for the RTP stream if any
     /// Filter element between the encoder and the payloader.
     encoded_filter: Option<gst::Element>,
+    /// Filter element on the raw video, before it is converted for the
+    /// encoder
+    video_filter: Option<gst::Element>,
     ssrc: Option<u32>,
     /// The TWCC ID to use for payloaded stream
     twcc: Option<u32>,
+    /// The playout delay to signal on video streams if any
+    playout_delay: Option<PlayoutDelay>,
+    /// The playout delay extension ID to enforce for payloaded stream
+    playout_delay_id: Option<u32>,
+    /// The RFC 6051 NTP-64 extension ID to enforce for payloaded stream
+    ntp64: Option<u32>,
+    /// Whether to measure the level of raw audio for the RFC 6464
+    /// extension
+    audio_level: bool,
+    /// The RFC 6464 audio level extension ID to enforce for payloaded
+    /// stream
+    audio_level_id: Option<u32>,
+    /// Prefix the names of our elements start with, followed by their
+    /// role in the chain
+    name_prefix: Option<String>,
+    /// Encoder slot reserved in the pool, kept for as long as the encoder
+    /// is alive
+    encoder_slot: Option<encoder_pool::Slot>,
 }
 
 impl EncodingChainBuilder {
@@ -586,8 +1102,16 @@ impl EncodingChainBuilder {
             output_caps: output_caps.clone(),
             codec: codec.clone(),
             encoded_filter,
+            video_filter: None,
             ssrc: None,
             twcc: None,
+            playout_delay: None,
+            playout_delay_id: None,
+            ntp64: None,
+            audio_level: false,
+            audio_level_id: None,
+            name_prefix: None,
+            encoder_slot: None,
         }
     }
 
@@ -596,12 +1120,62 @@ impl EncodingChainBuilder {
         self
     }
 
+    fn video_filter(mut self, video_filter: Option<gst::Element>) -> Self {
+        self.video_filter = video_filter;
+        self
+    }
+
     fn twcc(mut self, twcc: u32) -> Self {
         self.twcc = Some(twcc);
         self
     }
 
-    fn build(self, pipeline: &gst::Pipeline, src: &gst::Element) -> Result<EncodingChain, Error> {
+    fn playout_delay(mut self, playout_delay: Option<PlayoutDelay>) -> Self {
+        self.playout_delay = playout_delay;
+        self
+    }
+
+    fn playout_delay_id(mut self, playout_delay_id: u32) -> Self {
+        self.playout_delay_id = Some(playout_delay_id);
+        self
+    }
+
+    fn ntp64(mut self, ntp64: u32) -> Self {
+        self.ntp64 = Some(ntp64);
+        self
+    }
+
+    fn audio_level(mut self, audio_level: bool) -> Self {
+        self.audio_level = audio_level;
+        self
+    }
+
+    fn audio_level_id(mut self, audio_level_id: u32) -> Self {
+        self.audio_level_id = Some(audio_level_id);
+        self
+    }
+
+    fn name_prefix(mut self, name_prefix: String) -> Self {
+        self.name_prefix = Some(name_prefix);
+        self
+    }
+
+    fn encoder_slot(mut self, encoder_slot: Option<encoder_pool::Slot>) -> Self {
+        self.encoder_slot = encoder_slot;
+        self
+    }
+
+    /// Names an element we created after its role in the chain, elements
+    /// provided by the application keep their name
+    fn set_role_name(&self, element: &gst::Element, role: &str) {
+        if let Some(ref prefix) = self.name_prefix {
+            if let Err(err) = element.set_name(&format!("{prefix}-{role}")) {
+                gst::warning!(CAT, obj: element, "Failed to name {role}: {err}");
+            }
+        }
+    }
+
+    fn build(mut self, pipeline: &gst::Bin, src: &gst::Element) -> Result<EncodingChain, Error> {
         gst::trace!(
             CAT,
             obj: pipeline,
@@ -613,26 +1187,70 @@ impl EncodingChainBuilder {
             twcc = self.twcc,
         );
 
+        // Already payloaded streams are passed through as is
+        if is_rtp_caps(&self.input_caps) {
+            let pay_filter = gst::ElementFactory::make("capsfilter")
+                .property("caps", self.output_caps.clone())
+                .build()
+                .with_context(|| "Failed to make element capsfilter")?;
+            self.set_role_name(&pay_filter, "pay-capsfilter");
+
+            pipeline.add(&pay_filter).unwrap();
+            src.link(&pay_filter)
+                .with_context(|| "Linking payloaded input")?;
+
+            return Ok(EncodingChain {
+                raw_filter: None,
+                encoder: None,
+                pay_filter,
+            });
+        }
+
         let needs_encoding = is_raw_caps(&self.input_caps);
         let mut elements: Vec<gst::Element> = Vec::new();
 
         let (raw_filter, encoder) = if needs_encoding {
-            elements.push(match self.codec.is_video() {
+            if let Some(ref video_filter) = self.video_filter {
+                elements.push(video_filter.clone());
+            }
+
+            let converter = match self.codec.is_video() {
                 true => make_converter_for_video_caps(&self.input_caps, &self.codec)?.upcast(),
                 false => {
                     gst::parse_bin_from_description("audioresample ! audioconvert", true)?.upcast()
                 }
-            });
+            };
+            self.set_role_name(&converter, "converter");
+            elements.push(converter);
 
             let raw_filter = self.codec.raw_converter_filter()?;
+            self.set_role_name(&raw_filter, "raw-capsfilter");
             elements.push(raw_filter.clone());
 
+            // Attaches the level meta the audio level extension is written from
+            if self.audio_level && !self.codec.is_video() {
+                let level = gst::ElementFactory::make("level")
+                    .property("audio-level-meta", true)
+                    .property("post-messages", false)
+                    .build()
+                    .with_context(|| "Failed to make element level")?;
+                self.set_role_name(&level, "level");
+                elements.push(level);
+            }
+
             let encoder = self
                 .codec
                 .build_encoder()
                 .expect("We should always have an encoder for negotiated codecs")?;
+            self.set_role_name(&encoder, "encoder");
+            if let Some(slot) = self.encoder_slot.take() {
+                slot.attach(&encoder);
+            }
             elements.push(encoder.clone());
-            elements.push(make_element("capsfilter", None)?);
+
+            let encoder_filter = make_element("capsfilter", None)?;
+            self.set_role_name(&encoder_filter, "encoder-capsfilter");
+            elements.push(encoder_filter);
 
             (Some(raw_filter), Some(encoder))
         } else {
@@ -640,18 +1258,19 @@ impl EncodingChainBuilder {
         };
 
         if let Some(parser) = self.codec.build_parser()? {
+            self.set_role_name(&parser, "parser");
             elements.push(parser);
         }
 
         // Only force the profile when output caps were not specified, either
         // through input caps or because we are answering an offer
         let force_profile = self.output_caps.is_any() && needs_encoding;
-        elements.push(
-            gst::ElementFactory::make("capsfilter")
-                .property("caps", self.codec.parser_caps(force_profile))
-                .build()
-                .with_context(|| "Failed to make element capsfilter")?,
-        );
+        let parser_filter = gst::ElementFactory::make("capsfilter")
+            .property("caps", self.codec.parser_caps(force_profile))
+            .build()
+            .with_context(|| "Failed to make element capsfilter")?;
+        self.set_role_name(&parser_filter, "parser-capsfilter");
+        elements.push(parser_filter);
 
         if let Some(ref encoded_filter) = self.encoded_filter {
             elements.push(encoded_filter.clone());
@@ -682,12 +1301,68 @@ impl EncodingChainBuilder {
             twcc_extension.set_id(idx);
             pay.emit_by_name::<()>("add-extension", &[&twcc_extension]);
         }
+
+        /* Same goes for the RFC 6051 rapid synchronization extension, the
+         * payloader creates it by itself once negotiated */
+        if let Some(idx) = self.ntp64 {
+            let ntp64_extension = gst_rtp::RTPHeaderExtension::create_from_uri(RTP_NTP64_URI)
+                .with_context(|| "Failed to create ntp-64 header extension")?;
+            ntp64_extension.set_id(idx);
+            pay.emit_by_name::<()>("add-extension", &[&ntp64_extension]);
+        }
+
+        if let Some(idx) = self.audio_level_id.filter(|_| !self.codec.is_video()) {
+            let audio_level_extension =
+                gst_rtp::RTPHeaderExtension::create_from_uri(RTP_AUDIO_LEVEL_URI)
+                    .with_context(|| "Failed to create audio level header extension")?;
+            audio_level_extension.set_id(idx);
+            pay.emit_by_name::<()>("add-extension", &[&audio_level_extension]);
+        }
+
+        /* Similarly, the playout delay extension is only added explicitly
+         * to the offer caps, afterwards the payloader will request it when
+         * the negotiated caps contain the matching extmap */
+        if let Some(playout_delay) = self.playout_delay.filter(|_| self.codec.is_video()) {
+            let make_extension = move || {
+                glib::Object::builder::<PlayoutDelayExtension>()
+                    .property("min-delay", playout_delay.min)
+                    .property("max-delay", playout_delay.max)
+                    .build()
+            };
+
+            if let Some(idx) = self.playout_delay_id {
+                let playout_delay_extension = make_extension();
+                playout_delay_extension.set_id(idx);
+                pay.emit_by_name::<()>("add-extension", &[&playout_delay_extension]);
+            } else {
+                pay.connect("request-extension", false, move |args| {
+                    let ext_id = args[1].get::<u32>().unwrap();
+                    let ext_uri = args[2].get::<&str>().unwrap();
+
+                    if ext_uri != RTP_PLAYOUT_DELAY_URI {
+                        return None;
+                    }
+
+                    let playout_delay_extension = make_extension();
+                    playout_delay_extension.set_id(ext_id);
+
+                    Some(
+                        playout_delay_extension
+                            .upcast::<gst_rtp::RTPHeaderExtension>()
+                            .to_value(),
+                    )
+                });
+            }
+        }
+
+        self.set_role_name(&pay, "payloader");
         elements.push(pay);
 
         let pay_filter = gst::ElementFactory::make("capsfilter")
-            .property("caps", self.output_caps)
+            .property("caps", self.output_caps.clone())
             .build()
             .with_context(|| "Failed to make payloader")?;
+        self.set_role_name(&pay_filter, "pay-capsfilter");
         elements.push(pay_filter.clone());
 
         for element in &elements {
@@ -712,9 +1387,50 @@ impl VideoEncoder {
         video_info: gst_video::VideoInfo,
         session_id: &str,
         codec_name: &str,
+        audio_only_bitrate: u32,
+        min_framerate: u32,
         transceiver: gst_webrtc::WebRTCRTPTransceiver,
     ) -> Option<Self> {
-        let halved_framerate = video_info.fps().mul(gst::Fraction::new(1, 2));
+        let qp_stats = Arc::new(Mutex::new(QpStats::default()));
+
+        if let Some(srcpad) = encoding_elements
+            .encoder
+            .as_ref()
+            .and_then(|encoder| encoder.static_pad("src"))
+        {
+            let qp_stats = qp_stats.clone();
+            let qp_parser = Mutex::new(QpParser::new(codec_name));
+            srcpad.add_probe(
+                gst::PadProbeType::BUFFER | gst::PadProbeType::EVENT_DOWNSTREAM,
+                move |_pad, info| {
+                    match info.data {
+                        Some(gst::PadProbeData::Buffer(ref buffer)) => {
+                            if let Some(qp) = buffer
+                                .map_readable()
+                                .ok()
+                                .and_then(|map| qp_parser.lock().unwrap().parse(&map))
+                            {
+                                qp_stats.lock().unwrap().add(qp);
+                            }
+                        }
+                        Some(gst::PadProbeData::Event(ref event)) => {
+                            if let gst::EventView::Caps(caps) = event.view() {
+                                let codec_data = caps
+                                    .caps()
+                                    .structure(0)
+                                    .and_then(|s| s.get::<gst::Buffer>("codec_data").ok());
+                                let map = codec_data.as_ref().and_then(|b| b.map_readable().ok());
+                                qp_parser.lock().unwrap().set_codec_data(map.as_deref());
+                            }
+                        }
+                        _ => (),
+                    }
+
+                    gst::PadProbeReturn::Ok
+                },
+            );
+        }
+
         Some(Self {
             factory_name: encoding_elements
                 .encoder
@@ -726,10 +1442,15 @@ impl VideoEncoder {
             codec_name: codec_name.to_string(),
             element: encoding_elements.encoder.as_ref()?.clone(),
             filter: encoding_elements.raw_filter.as_ref()?.clone(),
-            halved_framerate,
+            min_framerate: gst::Fraction::new(min_framerate as i32, 1),
             video_info,
             session_id: session_id.to_string(),
             mitigation_mode: WebRTCSinkMitigationMode::NONE,
+            audio_only_bitrate,
+            audio_only_probe: None,
+            preferences: QualityPreferences::default(),
+            target_bitrate: None,
+            qp_stats,
             transceiver,
         })
     }
@@ -747,6 +1468,37 @@ impl VideoEncoder {
         }
     }
 
+    /// Framerate at `step` of the mitigation ladder, from 1 for the mildest
+    /// to [`MITIGATION_STEPS`], or `None` if the framerate isn't lowered.
+    ///
+    /// Each step halves the framerate, but only down to
+    /// [`MITIGATION_NOMINAL_FRAMERATE`] before the last step, which halves
+    /// it once more: 240 fps content goes through 120, 60 then 30 fps, while
+    /// 30 fps content is only halved at the last step. The framerate never
+    /// goes below the configured minimum.
+    fn mitigated_framerate(&self, step: u32) -> Option<gst::Fraction> {
+        let fps = self.video_info.fps();
+        if fps.numer() == 0 {
+            return None;
+        }
+
+        let max = |a: gst::Fraction, b: gst::Fraction| if a < b { b } else { a };
+        let nominal = gst::Fraction::new(MITIGATION_NOMINAL_FRAMERATE, 1);
+        let floor = if fps < nominal { fps } else { nominal };
+
+        let mut framerate = fps;
+        for i in 1..=step.min(MITIGATION_STEPS) {
+            framerate = framerate.mul(gst::Fraction::new(1, 2));
+            if i < MITIGATION_STEPS {
+                framerate = max(framerate, floor);
+            }
+        }
+
+        let framerate = max(framerate, self.min_framerate);
+
+        (framerate < fps).then_some(framerate)
+    }
+
     fn scale_height_round_2(&self, height: i32) -> i32 {
         let ratio = gst_video::calculate_display_ratio(
             self.video_info.width(),
@@ -761,7 +1513,161 @@ impl VideoEncoder {
         (width + 1) & !1
     }
 
+    /// Caps the preferred height to the maximum resolution the consumer
+    /// advertised it can handle, and restricts the encoder caps right away
+    /// instead of waiting for congestion control to first adjust the bitrate
+    fn apply_capabilities(
+        &mut self,
+        element: &super::BaseWebRTCSink,
+        capabilities: &gst::StructureRef,
+    ) {
+        let max_height = capabilities.get::<u32>("max-height").ok();
+        let ratio = gst_video::calculate_display_ratio(
+            self.video_info.width(),
+            self.video_info.height(),
+            self.video_info.par(),
+            gst::Fraction::new(1, 1),
+        );
+
+        // No constraint is derived from the width if the display ratio
+        // of the input can't be computed
+        let height_for_max_width = match (capabilities.get::<u32>("max-width").ok(), ratio) {
+            (Some(max_width), Some(ratio)) => (max_width as i32)
+                .mul_div_floor(ratio.denom(), ratio.numer())
+                .map(|height| height as u32),
+            _ => None,
+        };
+
+        let Some(max_height) = [max_height, height_for_max_width]
+            .into_iter()
+            .flatten()
+            .min()
+        else {
+            return;
+        };
+
+        let max_height = (max_height as i32) & !1;
+
+        if max_height <= 0 || self.preferences.height.map_or(false, |h| max_height >= h) {
+            return;
+        }
+
+        self.preferences.height = Some(max_height);
+
+        let current_caps = self.filter.property::<gst::Caps>("caps");
+        let mut s = current_caps.structure(0).unwrap().to_owned();
+        let height = s
+            .get::<i32>("height")
+            .unwrap_or(self.video_info.height() as i32);
+
+        if max_height < height {
+            s.set("height", max_height);
+            s.set("width", self.scale_height_round_2(max_height));
+
+            let caps = gst::Caps::builder_full_with_any_features()
+                .structure(s)
+                .build();
+
+            gst::info!(
+                CAT,
+                obj: element,
+                "session {}: clamping encoder caps to {} as requested by the consumer",
+                self.session_id,
+                caps
+            );
+
+            self.filter.set_property("caps", caps);
+        }
+    }
+
+    /// Drops all raw video frames before they reach the encoder, leaving
+    /// only audio flowing for the session
+    fn disable_video(&mut self, element: &super::BaseWebRTCSink, bitrate: i32) {
+        if self.audio_only_probe.is_some() {
+            return;
+        }
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "session {}: bitrate {} below audio-only threshold {}, disabling video",
+            self.session_id,
+            bitrate,
+            self.audio_only_bitrate
+        );
+
+        let pad = self.element.static_pad("sink").unwrap();
+        self.audio_only_probe = pad.add_probe(
+            gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST,
+            |_, _| gst::PadProbeReturn::Drop,
+        );
+    }
+
+    fn enable_video(&mut self, element: &super::BaseWebRTCSink, bitrate: i32) {
+        let Some(probe_id) = self.audio_only_probe.take() else {
+            return;
+        };
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "session {}: bitrate {} recovered, enabling video",
+            self.session_id,
+            bitrate
+        );
+
+        self.element
+            .static_pad("sink")
+            .unwrap()
+            .remove_probe(probe_id);
+
+        // The consumer needs a keyframe to start decoding again
+        let event = gst_video::UpstreamForceKeyUnitEvent::builder()
+            .all_headers(true)
+            .build();
+        self.element.static_pad("src").unwrap().send_event(event);
+    }
+
+    fn set_quality_preference(
+        &mut self,
+        element: &super::BaseWebRTCSink,
+        preference: QualityPreference,
+    ) {
+        let non_zero = |value: u32| (value > 0).then_some(value as i32);
+
+        match preference {
+            QualityPreference::PreferredResolution { height } => {
+                self.preferences.height = non_zero(height);
+            }
+            QualityPreference::PreferredFramerate { framerate } => {
+                self.preferences.framerate =
+                    non_zero(framerate).map(|framerate| gst::Fraction::new(framerate, 1));
+            }
+            QualityPreference::MaxBitrate { bitrate } => {
+                self.preferences.max_bitrate = non_zero(bitrate);
+            }
+        }
+
+        let bitrate = self.target_bitrate.unwrap_or_else(|| self.bitrate());
+        self.set_bitrate(element, bitrate);
+    }
+
     pub(crate) fn set_bitrate(&mut self, element: &super::BaseWebRTCSink, bitrate: i32) {
+        self.target_bitrate = Some(bitrate);
+        let bitrate = match self.preferences.max_bitrate {
+            Some(max_bitrate) => bitrate.min(max_bitrate),
+            None => bitrate,
+        };
+
+        if self.audio_only_bitrate > 0 {
+            if bitrate < self.audio_only_bitrate as i32 {
+                self.disable_video(element, bitrate);
+            } else if bitrate as f64 >= self.audio_only_bitrate as f64 * AUDIO_ONLY_RECOVERY_FACTOR
+            {
+                self.enable_video(element, bitrate);
+            }
+        }
+
         match self.factory_name.as_str() {
             "vp8enc" | "vp9enc" => self.element.set_property("target-bitrate", bitrate),
             "x264enc" | "nvh264enc" | "vaapih264enc" | "vaapivp8enc" => self
@@ -778,43 +1684,62 @@ impl VideoEncoder {
 
         // Hardcoded thresholds, may be tuned further in the future, and
         // adapted according to the codec in use
-        if bitrate < 500000 {
-            let height = 360i32.min(self.video_info.height() as i32);
-            let width = self.scale_height_round_2(height);
-
-            s.set("height", height);
-            s.set("width", width);
-
-            if self.halved_framerate.numer() != 0 {
-                s.set("framerate", self.halved_framerate);
-            }
-
-            self.mitigation_mode =
-                WebRTCSinkMitigationMode::DOWNSAMPLED | WebRTCSinkMitigationMode::DOWNSCALED;
+        let (height, step) = if bitrate < 500000 {
+            (Some(360i32), 3)
         } else if bitrate < 1000000 {
-            let height = 360i32.min(self.video_info.height() as i32);
-            let width = self.scale_height_round_2(height);
+            (Some(360i32), 2)
+        } else if bitrate < 2000000 {
+            (Some(720i32), 1)
+        } else {
+            (None, 0)
+        };
 
-            s.set("height", height);
-            s.set("width", width);
-            s.remove_field("framerate");
+        self.mitigation_mode = WebRTCSinkMitigationMode::NONE;
 
-            self.mitigation_mode = WebRTCSinkMitigationMode::DOWNSCALED;
-        } else if bitrate < 2000000 {
-            let height = 720i32.min(self.video_info.height() as i32);
+        if let Some(height) = height {
+            let height = height.min(self.video_info.height() as i32);
             let width = self.scale_height_round_2(height);
 
             s.set("height", height);
             s.set("width", width);
-            s.remove_field("framerate");
 
-            self.mitigation_mode = WebRTCSinkMitigationMode::DOWNSCALED;
+            self.mitigation_mode |= WebRTCSinkMitigationMode::DOWNSCALED;
         } else {
             s.remove_field("height");
             s.remove_field("width");
+        }
+
+        if let Some(framerate) = self.mitigated_framerate(step) {
+            s.set("framerate", framerate);
+
+            self.mitigation_mode |= WebRTCSinkMitigationMode::DOWNSAMPLED;
+        } else {
             s.remove_field("framerate");
+        }
+
+        if let Some(preferred_height) = self.preferences.height {
+            let height = s
+                .get::<i32>("height")
+                .unwrap_or(self.video_info.height() as i32);
+
+            if preferred_height < height {
+                s.set("height", preferred_height);
+                s.set("width", self.scale_height_round_2(preferred_height));
+            }
+        }
+
+        if let Some(preferred_framerate) = self.preferences.framerate {
+            let framerate = s
+                .get::<gst::Fraction>("framerate")
+                .unwrap_or(self.video_info.fps());
+
+            if framerate.numer() != 0 && preferred_framerate < framerate {
+                s.set("framerate", preferred_framerate);
+            }
+        }
 
-            self.mitigation_mode = WebRTCSinkMitigationMode::NONE;
+        if self.audio_only_probe.is_some() {
+            self.mitigation_mode |= WebRTCSinkMitigationMode::AUDIO_ONLY;
         }
 
         let caps = gst::Caps::builder_full_with_any_features()
@@ -837,7 +1762,7 @@ impl VideoEncoder {
     }
 
     fn gather_stats(&self) -> gst::Structure {
-        gst::Structure::builder("application/x-webrtcsink-video-encoder-stats")
+        let mut s = gst::Structure::builder("application/x-webrtcsink-video-encoder-stats")
             .field("bitrate", self.bitrate())
             .field("mitigation-mode", self.mitigation_mode)
             .field("codec-name", self.codec_name.as_str())
@@ -845,14 +1770,40 @@ impl VideoEncoder {
                 "fec-percentage",
                 self.transceiver.property::<u32>("fec-percentage"),
             )
-            .build()
+            .build();
+
+        // High quantizers at a bitrate below target hint at the bitrate
+        // starving the encoder, high quantizers at target at a source too
+        // complex for the bitrate
+        let qp_stats = std::mem::take(&mut *self.qp_stats.lock().unwrap());
+        if let Some((average, min, max)) = qp_stats.summary() {
+            s.set("qp-average", average);
+            s.set("qp-min", min);
+            s.set("qp-max", max);
+        }
+
+        s
     }
 }
 
 impl State {
     fn finalize_session(&mut self, session: &mut Session) {
         gst::info!(CAT, "Ending session {}", session.id);
-        session.pipeline.debug_to_dot_file_with_ts(
+
+        if let Some(resume_timeout) = session.resume_timeout.take() {
+            resume_timeout.cancel();
+        }
+
+        if let Some(handle) = session.connect_timeout_handle.take() {
+            handle.abort();
+        }
+
+        let (connected_sessions, cvar) = &*self.connected_sessions;
+        if connected_sessions.lock().unwrap().remove(&session.id) {
+            cvar.notify_all();
+        }
+
+        session.pipeline.debug_to_dot_file_with_ts(
             gst::DebugGraphDetails::all(),
             format!("removing-session-{}-", session.id),
         );
@@ -870,12 +1821,16 @@ impl State {
 
         let pipeline = session.pipeline.clone();
         RUNTIME.spawn_blocking(move || {
-            if let Some(stats_collection_handle) = stats_collection_handle {
-                stats_collection_handle.abort();
-                let _ = RUNTIME.block_on(stats_collection_handle);
+            if let Some(handle) = stats_collection_handle {
+                handle.abort();
+                let _ = RUNTIME.block_on(handle);
             }
 
             let _ = pipeline.set_state(gst::State::Null);
+            // Only the case for session bins in the shared pipeline
+            if let Some(parent) = pipeline.parent().and_downcast::<gst::Bin>() {
+                let _ = parent.remove(&pipeline);
+            }
             drop(pipeline);
 
             let (sessions, cvar) = &*finalizing_sessions;
@@ -906,12 +1861,13 @@ impl State {
 impl Session {
     fn new(
         id: String,
-        pipeline: gst::Pipeline,
+        pipeline: gst::Bin,
         webrtcbin: gst::Element,
         peer_id: String,
         congestion_controller: Option<CongestionController>,
         rtpgccbwe: Option<gst::Element>,
         cc_info: CCInfo,
+        stats_collection_interval: u32,
     ) -> Self {
         Self {
             id,
@@ -926,14 +1882,99 @@ impl Session {
             sdp: None,
             webrtc_pads: HashMap::new(),
             encoders: Vec::new(),
+            audio_encoders: Vec::new(),
             links: HashMap::new(),
             stats_sigid: None,
             codecs: None,
             stats_collection_handle: None,
+            stats_collection_interval,
+            paused: false,
+            awaiting_approval: false,
+            srtp_profile: None,
+            ice_connected_at: None,
+            dtls_info: None,
+            frame_timing_channel: None,
+            thumbnail_channel: None,
+            capabilities: None,
+            negotiated_codecs: BTreeMap::new(),
+            latencies: BTreeMap::new(),
+            rtp_time_mappings: BTreeMap::new(),
+            rtx_packets_sent: None,
+            rtx_bitrate: 0,
+            bitrate_ceiling: None,
+            bandwidth_group: None,
+            pending_candidates: Vec::new(),
+            turn_servers: Vec::new(),
+            resume_timeout: None,
+            connect_timeout_handle: None,
         }
     }
 
-    fn gather_stats(&self) -> gst::Structure {
+    /// Prefix of the names of the elements carrying `stream_name` in this
+    /// session, followed by their role, see the documentation of the element
+    fn element_name_prefix(&self, stream_name: &str) -> String {
+        format!("{}-{stream_name}", self.id)
+    }
+
+    /// The appsrc the input stream `stream_name` feeds this session through
+    fn appsrc(&self, stream_name: &str) -> Option<gst::Element> {
+        self.pipeline
+            .by_name(&format!("{}-appsrc", self.element_name_prefix(stream_name)))
+    }
+
+    /// Bytes queued in the appsrcs the input streams feed the session with
+    fn queued_bytes(&self) -> u64 {
+        self.negotiated_codecs
+            .keys()
+            .filter_map(|stream_name| self.appsrc(stream_name))
+            .map(|appsrc| appsrc.property::<u64>("current-level-bytes"))
+            .sum()
+    }
+
+    /// Bitrate of each of `n_encoders` encoders when no congestion control
+    /// estimates it
+    fn uncontrolled_bitrate(&self, n_encoders: usize) -> i32 {
+        let max_bitrate = self.cc_info.max_bitrate;
+        let demand = max_bitrate.saturating_mul(n_encoders.max(1) as u32);
+
+        self.ceiling(demand).map_or(max_bitrate, |ceiling| {
+            (ceiling / n_encoders.max(1) as u32).min(max_bitrate)
+        }) as i32
+    }
+
+    /// Maximum bitrate of the session, from the signaller and the share of
+    /// the session in its bandwidth group given it would like to send at
+    /// `demand`
+    fn ceiling(&self, demand: u32) -> Option<u32> {
+        let group_ceiling = self
+            .bandwidth_group
+            .as_ref()
+            .map(|membership| membership.ceiling(demand));
+
+        [self.bitrate_ceiling, group_ceiling]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Caps the next control operations of the homegrown congestion
+    /// controller, the bandwidth group share following the current target
+    fn update_congestion_controller_ceiling(&mut self) {
+        let Some(demand) = self
+            .congestion_controller
+            .as_ref()
+            .map(|congestion_controller| congestion_controller.target_bitrate())
+        else {
+            return;
+        };
+
+        let ceiling = self.ceiling(demand.max(0) as u32);
+        if let Some(congestion_controller) = self.congestion_controller.as_mut() {
+            congestion_controller.set_bitrate_ceiling(ceiling);
+        }
+    }
+
+    fn gather_stats(&self, verbose: bool) -> gst::Structure {
         let mut ret = self.stats.to_owned();
 
         let encoder_stats = self
@@ -943,15 +1984,315 @@ impl Session {
             .map(|s| s.to_send_value())
             .collect::<gst::Array>();
 
-        let our_stats = gst::Structure::builder("application/x-webrtcsink-consumer-stats")
+        let audio_encoder_stats = self
+            .audio_encoders
+            .iter()
+            .map(AudioEncoder::gather_stats)
+            .map(|s| s.to_send_value())
+            .collect::<gst::Array>();
+
+        let mut our_stats = gst::Structure::builder("application/x-webrtcsink-consumer-stats")
             .field("video-encoders", encoder_stats)
+            .field("audio-encoders", audio_encoder_stats)
+            .field("rtcp-stats", self.gather_rtcp_stats())
             .build();
 
+        if let Some(congestion_controller) = self.congestion_controller.as_ref() {
+            our_stats.set("cc-state", congestion_controller.gather_stats());
+        }
+
+        if verbose {
+            // Older versions of rtpgccbwe don't expose their state
+            if let Some(rtpgccbwe) = self
+                .rtpgccbwe
+                .as_ref()
+                .filter(|rtpgccbwe| rtpgccbwe.has_property("stats", None))
+            {
+                our_stats.set("gcc-stats", rtpgccbwe.property::<gst::Structure>("stats"));
+            }
+        }
+
+        if let Some(srtp_profile) = self.srtp_profile.as_ref() {
+            our_stats.set("srtp-profile", srtp_profile);
+        }
+
+        if let Some(dtls_info) = self.dtls_info.as_ref() {
+            our_stats.set("dtls", dtls_info);
+        }
+
+        our_stats.set("queued-bytes", self.queued_bytes());
+        our_stats.set("rtx-bitrate", self.rtx_bitrate);
+        our_stats.set("bitrate-ceiling", self.bitrate_ceiling.unwrap_or(0));
+
+        let negotiated_codecs = self
+            .negotiated_codecs
+            .iter()
+            .map(|(stream_name, codec)| {
+                gst::Structure::builder("application/x-webrtcsink-negotiated-codec")
+                    .field("stream-name", stream_name)
+                    .field("codec-name", &codec.name)
+                    .field("caps", &codec.caps)
+                    .field("payload-type", codec.payload())
+                    .build()
+                    .to_send_value()
+            })
+            .collect::<gst::Array>();
+        our_stats.set("negotiated-codecs", negotiated_codecs);
+
+        let latencies = self
+            .latencies
+            .iter()
+            .filter_map(|(stream_name, latency_stats)| {
+                let latency_stats = std::mem::take(&mut *latency_stats.lock().unwrap());
+                let (average, max) = latency_stats.summary()?;
+
+                Some(
+                    gst::Structure::builder("application/x-webrtcsink-stream-latency")
+                        .field("stream-name", stream_name)
+                        .field("average", average)
+                        .field("max", max)
+                        .build()
+                        .to_send_value(),
+                )
+            })
+            .collect::<gst::Array>();
+        our_stats.set("latency", latencies);
+
+        // Lets recorders running in parallel align their archives with what
+        // the consumer received: the clock time is the one sender reports
+        // derive their NTP time from with clock signalling
+        let base_time = self.pipeline.base_time();
+        let ts_refclk = self.pipeline.clock().map(|clock| clock_ts_refclk(&clock));
+        let rtp_time_mappings = self
+            .rtp_time_mappings
+            .iter()
+            .filter_map(|(stream_name, mapping)| {
+                let mapping = (*mapping.lock().unwrap())?;
+
+                let mut s = gst::Structure::builder("application/x-webrtcsink-rtp-time-mapping")
+                    .field("stream-name", stream_name)
+                    .field("ssrc", mapping.ssrc)
+                    .field("rtp-timestamp", mapping.rtp_timestamp)
+                    .field("running-time", mapping.running_time)
+                    .build();
+
+                if let Some(clock_rate) = mapping.clock_rate {
+                    s.set("clock-rate", clock_rate);
+                }
+                if let Some(base_time) = base_time {
+                    s.set("clock-time", mapping.running_time + base_time);
+                }
+                if let Some(ts_refclk) = ts_refclk.as_ref() {
+                    s.set("ts-refclk", ts_refclk);
+                }
+
+                Some(s.to_send_value())
+            })
+            .collect::<gst::Array>();
+        our_stats.set("rtp-time-mappings", rtp_time_mappings);
+
         ret.set("consumer-stats", our_stats);
 
         ret
     }
 
+    /// Returns the stats of all the sources of the internal RTP sessions
+    /// of webrtcbin
+    fn rtp_source_stats(&self) -> Vec<gst::Structure> {
+        let Some(rtpbin) = self
+            .webrtcbin
+            .dynamic_cast_ref::<gst::ChildProxy>()
+            .unwrap()
+            .child_by_name("rtpbin")
+        else {
+            return vec![];
+        };
+
+        let mut session_ids = self
+            .webrtc_pads
+            .values()
+            .map(|pad| pad.media_idx)
+            .collect::<Vec<_>>();
+        session_ids.sort_unstable();
+        session_ids.dedup();
+
+        let mut ret = vec![];
+
+        for session_id in session_ids {
+            let Some(rtp_session) =
+                rtpbin.emit_by_name::<Option<gst::Element>>("get-session", &[&session_id])
+            else {
+                continue;
+            };
+
+            let stats = rtp_session.property::<gst::Structure>("stats");
+            let Ok(source_stats) = stats.get::<glib::ValueArray>("source-stats") else {
+                continue;
+            };
+
+            ret.extend(
+                source_stats
+                    .iter()
+                    .filter_map(|v| v.get::<gst::Structure>().ok()),
+            );
+        }
+
+        ret
+    }
+
+    /// Extracts the RTCP feedback reported by the consumer for each of
+    /// our outgoing streams from the internal RTP sessions of webrtcbin
+    fn gather_rtcp_stats(&self) -> gst::Array {
+        let mut ret = vec![];
+
+        for s in self.rtp_source_stats() {
+            let Ok(ssrc) = s.get::<u32>("ssrc") else {
+                continue;
+            };
+
+            // Only report on our own outgoing streams
+            let Some(webrtc_pad) = self.webrtc_pads.get(&ssrc) else {
+                continue;
+            };
+
+            let round_trip = s.get::<u32>("rb-round-trip").unwrap_or(0);
+            let fraction_lost = s.get::<u32>("rb-fractionlost").unwrap_or(0);
+
+            ret.push(
+                gst::Structure::builder("application/x-webrtcsink-rtcp-stats")
+                    .field("ssrc", ssrc)
+                    .field("stream-name", webrtc_pad.stream_name.as_deref())
+                    .field("have-rb", s.get::<bool>("have-rb").unwrap_or(false))
+                    // Round trip is expressed in units of 1/65536 seconds
+                    .field(
+                        "round-trip-time",
+                        (round_trip as u64)
+                            .mul_div_round(gst::ClockTime::SECOND.nseconds(), 65536)
+                            .unwrap_or(0),
+                    )
+                    .field("jitter", s.get::<u32>("rb-jitter").unwrap_or(0))
+                    // Fraction lost is expressed in units of 1/256
+                    .field("fraction-lost", fraction_lost as f64 / 256.)
+                    .field("packets-lost", s.get::<i32>("rb-packetslost").unwrap_or(0))
+                    .field("nack-count", s.get::<u32>("recv-nack-count").unwrap_or(0))
+                    .field("pli-count", s.get::<u32>("recv-pli-count").unwrap_or(0))
+                    .field("fir-count", s.get::<u32>("recv-fir-count").unwrap_or(0))
+                    .build(),
+            );
+        }
+
+        gst::Array::new(ret)
+    }
+
+    /// Bitrate the audio encoders take out of the bandwidth estimated
+    /// for the session
+    fn audio_bitrate(&self, estimated_bitrate: i32) -> u32 {
+        self.audio_encoders
+            .iter()
+            .map(|encoder| encoder.target_bitrate(estimated_bitrate) as u32)
+            .sum()
+    }
+
+    /// Measures the bitrate spent on retransmissions.
+    ///
+    /// The sources carrying retransmissions also carry the padding that
+    /// rtprtxsend stuffs the link with to probe the bandwidth, their byte
+    /// counts can thus not be used. Instead, the number of packets
+    /// retransmitted on request is taken from rtprtxsend, and weighed
+    /// with the average size of the packets of our streams.
+    fn update_rtx_bitrate(&mut self) -> u32 {
+        let rtx_packets_sent = self
+            .webrtcbin
+            .downcast_ref::<gst::Bin>()
+            .unwrap()
+            .iterate_all_by_element_factory_name("rtprtxsend")
+            .into_iter()
+            .flatten()
+            .map(|rtprtxsend| rtprtxsend.property::<u32>("num-rtx-packets") as u64)
+            .sum::<u64>();
+        let now = std::time::Instant::now();
+
+        if let Some((prev_packets_sent, prev_time)) = self.rtx_packets_sent {
+            let elapsed = now.duration_since(prev_time);
+            if elapsed < RTX_BITRATE_WINDOW {
+                return self.rtx_bitrate;
+            }
+
+            let (octets_sent, packets_sent) = self
+                .rtp_source_stats()
+                .iter()
+                .filter(|s| {
+                    s.get::<bool>("internal").unwrap_or(false)
+                        && s.get::<bool>("is-sender").unwrap_or(false)
+                        && s.get::<u32>("ssrc")
+                            .map_or(false, |ssrc| self.webrtc_pads.contains_key(&ssrc))
+                })
+                .fold((0u64, 0u64), |(octets, packets), s| {
+                    (
+                        octets + s.get::<u64>("octets-sent").unwrap_or(0),
+                        packets + s.get::<u64>("packets-sent").unwrap_or(0),
+                    )
+                });
+
+            self.rtx_bitrate = retransmission_bitrate(
+                rtx_packets_sent.saturating_sub(prev_packets_sent),
+                octets_sent.checked_div(packets_sent).unwrap_or(0),
+                elapsed,
+            );
+        }
+
+        self.rtx_packets_sent = Some((rtx_packets_sent, now));
+
+        self.rtx_bitrate
+    }
+
+    /// Called when an input stream is removed while running, stops feeding
+    /// the matching WebRTCPads and marks their transceivers as inactive
+    fn disconnect_input_stream(
+        &mut self,
+        element: &super::BaseWebRTCSink,
+        stream_name: &str,
+    ) -> bool {
+        let mut disconnected = false;
+
+        for webrtc_pad in self.webrtc_pads.values_mut() {
+            if webrtc_pad.stream_name.as_deref() != Some(stream_name) {
+                continue;
+            }
+
+            gst::info!(
+                CAT,
+                obj: element,
+                "Disconnecting input stream {} from consumer {}",
+                stream_name,
+                self.peer_id
+            );
+
+            webrtc_pad.stream_name = None;
+            disconnected = true;
+
+            // Dropping the link stops the producer from feeding our appsrc
+            self.links.remove(&webrtc_pad.ssrc);
+
+            let transceiver = webrtc_pad
+                .pad
+                .property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
+
+            transceiver.set_property(
+                "direction",
+                gst_webrtc::WebRTCRTPTransceiverDirection::Inactive,
+            );
+
+            self.encoders.retain(|enc| enc.transceiver != transceiver);
+        }
+
+        self.negotiated_codecs.remove(stream_name);
+        self.latencies.remove(stream_name);
+        self.rtp_time_mappings.remove(stream_name);
+
+        disconnected
+    }
+
     /// Called when we have received an answer, connects an InputStream
     /// to a given WebRTCPad
     fn connect_input_stream(
@@ -987,7 +2328,13 @@ impl Session {
 
         let payload = webrtc_pad.payload.unwrap();
 
-        let codec = match self.codecs {
+        let mut codec = match self.codecs {
+            _ if is_rtp_caps(&webrtc_pad.in_caps) => {
+                gst::debug!(CAT, obj: element, "Passing payloaded stream through");
+
+                Codecs::find_for_payloaded_caps(&webrtc_pad.in_caps)
+                    .ok_or_else(|| anyhow!("No codec for payloaded stream {}", stream_name))?
+            }
             Some(ref codecs) => {
                 gst::debug!(CAT, obj: element, "Picking codec from remote offer");
 
@@ -1006,12 +2353,39 @@ impl Session {
             }
         };
 
-        let appsrc = make_element("appsrc", Some(stream_name))?;
+        if is_raw_caps(&webrtc_pad.in_caps) {
+            codec.set_prefer_hardware_encoder(webrtc_pad.prefer_hardware_encoder);
+        }
+
+        // Falls back to another encoder when the hardware encoder limit is
+        // reached, the stream can't be connected when there is none
+        let encoder_slot = if is_raw_caps(&webrtc_pad.in_caps) {
+            let max_hardware_encoders =
+                element.imp().settings.lock().unwrap().max_hardware_encoders;
+            encoder_pool::reserve_encoder(&mut codec, max_hardware_encoders)
+                .with_context(|| format!("Connecting input stream {stream_name}"))?
+        } else {
+            None
+        };
+
+        let name_prefix = self.element_name_prefix(stream_name);
+
+        let appsrc = make_element("appsrc", Some(&format!("{name_prefix}-appsrc")))?;
         self.pipeline.add(&appsrc).unwrap();
 
-        let pay_filter = make_element("capsfilter", None)?;
+        let pay_filter = make_element(
+            "capsfilter",
+            Some(&format!("{name_prefix}-webrtc-capsfilter")),
+        )?;
         self.pipeline.add(&pay_filter).unwrap();
 
+        if let (Some(channel), true) = (
+            self.frame_timing_channel.as_ref(),
+            codec.is_video() && !is_rtp_caps(&webrtc_pad.in_caps),
+        ) {
+            add_frame_timing_probe(&appsrc, channel, stream_name);
+        }
+
         let output_caps = codec.output_filter().unwrap_or_else(gst::Caps::new_any);
 
         let encoding_chain = EncodingChainBuilder::new(
@@ -1024,10 +2398,26 @@ impl Session {
             ),
         )
         .ssrc(webrtc_pad.ssrc)
+        .video_filter(
+            codec
+                .is_video()
+                .then(|| {
+                    element.emit_by_name::<Option<gst::Element>>(
+                        "request-video-filter",
+                        &[&self.peer_id, &stream_name, &webrtc_pad.in_caps],
+                    )
+                })
+                .flatten(),
+        )
+        .playout_delay(element.imp().settings.lock().unwrap().playout_delay)
+        .audio_level(element.imp().settings.lock().unwrap().do_audio_level)
+        .name_prefix(name_prefix)
+        .encoder_slot(encoder_slot)
         .build(&self.pipeline, &appsrc)?;
 
         if let Some(ref enc) = encoding_chain.encoder {
             element.emit_by_name::<bool>("encoder-setup", &[&self.peer_id, &stream_name, &enc]);
+            warn_on_encoder_delay(element, enc, stream_name);
         }
 
         // At this point, the peer has provided its answer, and we want to
@@ -1073,20 +2463,31 @@ impl Session {
 
         pay_filter.set_property("caps", caps);
 
-        if codec.is_video() {
+        if is_rtp_caps(&webrtc_pad.in_caps) {
+            // Payloaded streams have no encoder to control
+        } else if codec.is_video() {
             let video_info = gst_video::VideoInfo::from_caps(&webrtc_pad.in_caps)?;
             if let Some(mut enc) = VideoEncoder::new(
                 &encoding_chain,
                 video_info,
                 &self.id,
                 codec.caps.structure(0).unwrap().name(),
+                self.cc_info.audio_only_bitrate,
+                self.cc_info.min_framerate,
                 transceiver,
             ) {
+                if let Some(ref capabilities) = self.capabilities {
+                    enc.apply_capabilities(element, capabilities);
+                }
+
                 match self.cc_info.heuristic {
                     WebRTCSinkCongestionControl::Disabled => {
                         // If congestion control is disabled, we simply use the highest
                         // known "safe" value for the bitrate.
-                        enc.set_bitrate(element, self.cc_info.max_bitrate as i32);
+                        enc.set_bitrate(
+                            element,
+                            self.uncontrolled_bitrate(self.encoders.len() + 1),
+                        );
                         enc.transceiver.set_property("fec-percentage", 50u32);
                     }
                     WebRTCSinkCongestionControl::Homegrown => {
@@ -1098,7 +2499,10 @@ impl Session {
                         } else {
                             /* If congestion control is disabled, we simply use the highest
                              * known "safe" value for the bitrate. */
-                            enc.set_bitrate(element, self.cc_info.max_bitrate as i32);
+                            enc.set_bitrate(
+                                element,
+                                self.uncontrolled_bitrate(self.encoders.len() + 1),
+                            );
                             enc.transceiver.set_property("fec-percentage", 50u32);
                         }
                     }
@@ -1112,6 +2516,19 @@ impl Session {
                     rtpgccbwe.set_property("max-bitrate", max_bitrate);
                 }
             }
+        } else {
+            let fec = element.imp().settings.lock().unwrap().audio_fec;
+            if let Some(enc) = encoding_chain.encoder.as_ref().and_then(|encoder| {
+                AudioEncoder::new(
+                    encoder,
+                    &self.id,
+                    fec,
+                    self.cc_info.audio_min_bitrate,
+                    self.cc_info.audio_max_bitrate,
+                )
+            }) {
+                self.audio_encoders.push(enc);
+            }
         }
 
         let appsrc = appsrc.downcast::<gst_app::AppSrc>().unwrap();
@@ -1124,13 +2541,39 @@ impl Session {
 
         let srcpad = pay_filter.static_pad("src").unwrap();
 
+        let latency_stats = Arc::new(Mutex::new(LatencyStats::default()));
+        add_latency_probe(element, &srcpad, stream_name, latency_stats.clone());
+        self.latencies
+            .insert(stream_name.to_string(), latency_stats);
+
+        let rtp_time_mapping = Arc::new(Mutex::new(None));
+        add_rtp_time_mapping_probe(&srcpad, rtp_time_mapping.clone());
+        self.rtp_time_mappings
+            .insert(stream_name.to_string(), rtp_time_mapping);
+
         srcpad
             .link(&webrtc_pad.pad)
             .with_context(|| format!("Connecting input stream for {}", self.peer_id))?;
 
         match producer.add_consumer(&appsrc) {
             Ok(link) => {
+                link.set_discard(self.paused);
                 self.links.insert(webrtc_pad.ssrc, link);
+
+                // Let upstream pick up the allocation requirements of our encoder
+                if codec.is_video() && is_raw_caps(&webrtc_pad.in_caps) {
+                    if let Some(sinkpad) = producer.appsink().static_pad("sink") {
+                        sinkpad.push_event(gst::event::Reconfigure::new());
+                    }
+                }
+
+                element.emit_by_name::<()>(
+                    "session-negotiated",
+                    &[&self.id, &stream_name, &codec.caps],
+                );
+                self.negotiated_codecs
+                    .insert(stream_name.to_string(), codec);
+
                 Ok(())
             }
             Err(err) => Err(anyhow!("Could not link producer: {:?}", err)),
@@ -1146,8 +2589,19 @@ impl Drop for PipelineWrapper {
 
 impl InputStream {
     /// Called when transitioning state up to Paused
-    fn prepare(&mut self, element: &super::BaseWebRTCSink) -> Result<(), Error> {
+    fn prepare(
+        &mut self,
+        element: &super::BaseWebRTCSink,
+        input_timeout: u32,
+        thumbnail_interval: u32,
+        thumbnail_width: u32,
+    ) -> Result<(), Error> {
         let clocksync = make_element("clocksync", None)?;
+        if let Some(sink_pad) = self.sink_pad.downcast_ref::<super::WebRTCSinkPad>() {
+            clocksync.set_property("sync", sink_pad.imp().sync());
+            clocksync.set_property("ts-offset", sink_pad.imp().ts_offset());
+        }
+
         let appsink = make_element("appsink", None)?
             .downcast::<gst_app::AppSink>()
             .unwrap();
@@ -1159,15 +2613,49 @@ impl InputStream {
             .link(&appsink)
             .with_context(|| format!("Linking input stream {}", self.sink_pad.name()))?;
 
+        // The watchdog is only set up once the input caps are known to be
+        // raw, see BaseWebRTCSink::watch_input()
+        let target = if input_timeout > 0 {
+            let input_selector = make_element("input-selector", None)?;
+            input_selector.set_property("sync-streams", false);
+            element.add(&input_selector).unwrap();
+            input_selector
+                .link(&clocksync)
+                .with_context(|| format!("Linking input stream {}", self.sink_pad.name()))?;
+
+            let target = input_selector.request_pad_simple("sink_%u").unwrap();
+            self.input_selector = Some(input_selector);
+            target
+        } else {
+            clocksync.static_pad("sink").unwrap()
+        };
+
         element
             .sync_children_states()
             .with_context(|| format!("Linking input stream {}", self.sink_pad.name()))?;
 
-        self.sink_pad
-            .set_target(Some(&clocksync.static_pad("sink").unwrap()))
-            .unwrap();
+        self.sink_pad.set_target(Some(&target)).unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+
+        if self.is_video && thumbnail_interval > 0 {
+            let element_weak = element.downgrade();
+            let stream_name = self.sink_pad.name().to_string();
+            self.thumbnails = Some(Arc::new(ThumbnailGenerator::new(
+                &producer,
+                &self.sink_pad.name(),
+                thumbnail_interval,
+                thumbnail_width,
+                move |sample| {
+                    if let Some(element) = element_weak.upgrade() {
+                        element.imp().send_thumbnail(&stream_name, sample);
+                    }
+                },
+            )?));
+        }
 
-        self.producer = Some(StreamProducer::from(&appsink));
+        self.clocksync = Some(clocksync);
+        self.producer = Some(producer);
 
         Ok(())
     }
@@ -1176,6 +2664,14 @@ impl InputStream {
     fn unprepare(&mut self, element: &super::BaseWebRTCSink) {
         self.sink_pad.set_target(None::<&gst::Pad>).unwrap();
 
+        self.watchdog = None;
+        self.thumbnails = None;
+
+        if let Some(input_selector) = self.input_selector.take() {
+            element.remove(&input_selector).unwrap();
+            input_selector.set_state(gst::State::Null).unwrap();
+        }
+
         if let Some(clocksync) = self.clocksync.take() {
             element.remove(&clocksync).unwrap();
             clocksync.set_state(gst::State::Null).unwrap();
@@ -1211,8 +2707,203 @@ impl InputStream {
     }
 }
 
+/// Measures the latency of each buffer handed to webrtcbin through `pad`,
+/// and warns once if their PTS go backwards, as B-frames make them do:
+/// frame reordering delays the whole stream
+fn add_latency_probe(
+    element: &super::BaseWebRTCSink,
+    pad: &gst::Pad,
+    stream_name: &str,
+    latency_stats: Arc<Mutex<LatencyStats>>,
+) {
+    let element = element.downgrade();
+    let stream_name = stream_name.to_string();
+    let last_pts = AtomicU64::new(u64::MAX);
+    let reordering_reported = AtomicBool::new(false);
+
+    pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+        let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        let Some(pts) = buffer.pts() else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        let prev_pts = last_pts.swap(pts.nseconds(), Ordering::Relaxed);
+        if prev_pts != u64::MAX
+            && pts.nseconds() < prev_pts
+            && !reordering_reported.swap(true, Ordering::Relaxed)
+        {
+            if let Some(element) = element.upgrade() {
+                gst::warning!(
+                    CAT,
+                    obj: element,
+                    "Frames of stream {stream_name} are reordered, disable B-frames \
+                    in its encoder for low latency"
+                );
+            }
+        }
+
+        let running_time = pad
+            .sticky_event::<gst::event::Segment>(0)
+            .and_then(|event| {
+                event
+                    .segment()
+                    .downcast_ref::<gst::ClockTime>()
+                    .and_then(|segment| segment.to_running_time(pts))
+            });
+
+        let now = pad
+            .parent_element()
+            .and_then(|parent| parent.current_running_time());
+
+        if let Some(latency) = now
+            .zip(running_time)
+            .and_then(|(now, running_time)| now.checked_sub(running_time))
+        {
+            latency_stats.lock().unwrap().add(latency);
+        }
+
+        gst::PadProbeReturn::Ok
+    });
+}
+
+/// Keeps track of the RTP timestamp of the last buffer flowing through
+/// `pad`, after payloading, along with the running time of its PTS
+fn add_rtp_time_mapping_probe(pad: &gst::Pad, mapping: Arc<Mutex<Option<RtpTimeMapping>>>) {
+    pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+        let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        let Ok(rtp_buffer) = gst_rtp::RTPBuffer::from_buffer_readable(buffer) else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        let Some(running_time) = buffer.pts().and_then(|pts| {
+            pad.sticky_event::<gst::event::Segment>(0)
+                .and_then(|event| {
+                    event
+                        .segment()
+                        .downcast_ref::<gst::ClockTime>()
+                        .and_then(|segment| segment.to_running_time(pts))
+                })
+        }) else {
+            return gst::PadProbeReturn::Ok;
+        };
+
+        let mut mapping = mapping.lock().unwrap();
+
+        // The clock rate doesn't change for the lifetime of the stream
+        let clock_rate = match *mapping {
+            Some(mapping) => mapping.clock_rate,
+            None => pad.current_caps().and_then(|caps| {
+                caps.structure(0)
+                    .and_then(|s| s.get::<i32>("clock-rate").ok())
+            }),
+        };
+
+        *mapping = Some(RtpTimeMapping {
+            ssrc: rtp_buffer.ssrc(),
+            clock_rate,
+            rtp_timestamp: rtp_buffer.timestamp(),
+            running_time,
+        });
+
+        gst::PadProbeReturn::Ok
+    });
+}
+
+/// Warns about encoder settings known to delay frames, reordering them
+/// or looking ahead
+fn warn_on_encoder_delay(element: &super::BaseWebRTCSink, enc: &gst::Element, stream_name: &str) {
+    let frames = |property: &str| -> u64 {
+        let Some(pspec) = enc.find_property(property) else {
+            return 0;
+        };
+
+        let value = enc.property_value(property);
+        match pspec.value_type() {
+            glib::Type::U32 => value.get::<u32>().unwrap() as u64,
+            glib::Type::I32 => value.get::<i32>().unwrap().max(0) as u64,
+            _ => 0,
+        }
+    };
+
+    for (property, delay) in [
+        ("bframes", "reordering"),
+        ("max-bframes", "reordering"),
+        ("b-frames", "reordering"),
+        ("num-bframes", "reordering"),
+        ("lag-in-frames", "lookahead"),
+    ] {
+        let n_frames = frames(property);
+        if n_frames > 0 {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "{property}={n_frames} on the encoder of stream {stream_name} introduces \
+                {delay} delay, set it to 0 for low latency"
+            );
+        }
+    }
+}
+
+/// Sends the timing of each buffer pushed by `appsrc` over `channel`
+fn add_frame_timing_probe(appsrc: &gst::Element, channel: &WebRTCDataChannel, stream_name: &str) {
+    let channel = channel.clone();
+    let stream_name = stream_name.to_string();
+    let seqnum = AtomicU64::new(0);
+
+    appsrc
+        .static_pad("src")
+        .unwrap()
+        .add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+            let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data else {
+                return gst::PadProbeReturn::Ok;
+            };
+
+            let seqnum = seqnum.fetch_add(1, Ordering::Relaxed);
+
+            if channel.ready_state() != gst_webrtc::WebRTCDataChannelState::Open {
+                return gst::PadProbeReturn::Ok;
+            }
+
+            let pts = pad
+                .sticky_event::<gst::event::Segment>(0)
+                .and_then(|event| {
+                    event
+                        .segment()
+                        .downcast_ref::<gst::ClockTime>()
+                        .and_then(|segment| segment.to_running_time(buffer.pts()))
+                });
+
+            let capture_time = buffer
+                .iter_meta::<gst::ReferenceTimestampMeta>()
+                .find(|meta| {
+                    meta.reference()
+                        .structure(0)
+                        .map_or(false, |s| s.name() == "timestamp/x-unix")
+                })
+                .map(|meta| meta.timestamp().nseconds())
+                .unwrap_or_else(|| glib::real_time() as u64 * 1_000);
+
+            let msg = serde_json::json!({
+                "stream": stream_name,
+                "seqnum": seqnum,
+                "pts": pts.map(|pts| pts.nseconds()),
+                "capture-time": capture_time,
+            });
+
+            channel.send_string(Some(&msg.to_string()));
+
+            gst::PadProbeReturn::Ok
+        });
+}
+
 impl NavigationEventHandler {
-    fn new(element: &super::BaseWebRTCSink, webrtcbin: &gst::Element) -> Self {
+    fn new(element: &super::BaseWebRTCSink, webrtcbin: &gst::Element, session_id: &str) -> Self {
         gst::info!(CAT, "Creating navigation data channel");
         let channel = webrtcbin.emit_by_name::<WebRTCDataChannel>(
             "create-data-channel",
@@ -1225,12 +2916,20 @@ impl NavigationEventHandler {
         );
 
         let weak_element = element.downgrade();
+        let session_id = session_id.to_string();
         Self((
             channel.connect("on-message-string", false, move |values| {
                 if let Some(element) = weak_element.upgrade() {
                     let _channel = values[0].get::<WebRTCDataChannel>().unwrap();
                     let msg = values[1].get::<&str>().unwrap();
-                    create_navigation_event(&element, msg);
+
+                    // Quality preferences share the channel with navigation events
+                    match serde_json::from_str::<QualityPreferenceMessage>(msg) {
+                        Ok(preference) => {
+                            apply_quality_preference(&element, &session_id, preference)
+                        }
+                        Err(_) => create_navigation_event(&element, msg),
+                    }
                 }
 
                 None
@@ -1259,10 +2958,10 @@ impl BaseWebRTCSink {
         element: &super::BaseWebRTCSink,
         webrtcbin: &gst::Element,
         webrtc_pads: &mut HashMap<u32, WebRTCPad>,
+        media_idx: u32,
         is_video: bool,
     ) {
         let ssrc = BaseWebRTCSink::generate_ssrc(element, webrtc_pads);
-        let media_idx = webrtc_pads.len() as i32;
 
         let pad = webrtcbin
             .request_pad_simple(&format!("sink_{}", media_idx))
@@ -1286,25 +2985,42 @@ impl BaseWebRTCSink {
             WebRTCPad {
                 pad,
                 in_caps: gst::Caps::new_empty(),
-                media_idx: media_idx as u32,
+                media_idx,
                 ssrc,
                 stream_name: None,
                 payload: None,
+                prefer_hardware_encoder: false,
             },
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn request_webrtcbin_pad(
         element: &super::BaseWebRTCSink,
         webrtcbin: &gst::Element,
+        peer_id: &str,
         stream: &mut InputStream,
         media: Option<&gst_sdp::SDPMediaRef>,
+        media_idx: u32,
         settings: &Settings,
+        workarounds: &Workarounds,
         webrtc_pads: &mut HashMap<u32, WebRTCPad>,
         codecs: &mut BTreeMap<i32, Codec>,
     ) {
-        let ssrc = BaseWebRTCSink::generate_ssrc(element, webrtc_pads);
-        let media_idx = webrtc_pads.len() as i32;
+        // Already payloaded streams keep their SSRC unless it is taken
+        let ssrc = stream
+            .in_caps
+            .as_ref()
+            .filter(|caps| is_rtp_caps(caps))
+            .and_then(|caps| caps.structure(0).unwrap().get::<u32>("ssrc").ok())
+            .filter(|ssrc| !webrtc_pads.contains_key(ssrc))
+            .unwrap_or_else(|| BaseWebRTCSink::generate_ssrc(element, webrtc_pads));
+
+        let mut prefer_hardware = is_raw_caps(stream.in_caps.as_ref().unwrap())
+            && element.emit_by_name::<bool>(
+                "request-hardware-encoder",
+                &[&peer_id, &stream.sink_pad.name().as_str()],
+            );
 
         let mut payloader_caps = match media {
             Some(media) => {
@@ -1317,6 +3033,7 @@ impl BaseWebRTCSink {
                     &stream.in_caps.as_ref().unwrap().clone(),
                     &stream.sink_pad.name(),
                     settings,
+                    prefer_hardware,
                 )
                 .await;
 
@@ -1340,7 +3057,17 @@ impl BaseWebRTCSink {
                     }
                 }
             }
-            None => stream.out_caps.as_ref().unwrap().to_owned(),
+            None => {
+                match BaseWebRTCSink::rediscover_caps(element, stream, settings, prefer_hardware)
+                    .await
+                {
+                    Some(caps) => caps,
+                    None => {
+                        prefer_hardware = settings.prefer_hardware_encoders;
+                        stream.out_caps.as_ref().unwrap().to_owned()
+                    }
+                }
+            }
         };
 
         if payloader_caps.is_empty() {
@@ -1348,24 +3075,63 @@ impl BaseWebRTCSink {
                 element,
                 webrtcbin,
                 webrtc_pads,
+                media_idx,
                 stream.is_video,
             );
         } else {
             let payloader_caps_mut = payloader_caps.make_mut();
             payloader_caps_mut.set("ssrc", ssrc);
 
-            gst::info!(
-                CAT,
-                obj: element,
-                "Requesting WebRTC pad with caps {}",
-                payloader_caps
-            );
+            // Advertise the inband FEC data opusenc will produce
+            if settings.audio_fec
+                && payloader_caps_mut
+                    .structure(0)
+                    .and_then(|s| s.get::<&str>("encoding-name").ok())
+                    == Some("OPUS")
+            {
+                payloader_caps_mut.set("useinbandfec", "1");
+            }
 
-            let pad = webrtcbin
-                .request_pad_simple(&format!("sink_{}", media_idx))
+            let sink_pad = stream
+                .sink_pad
+                .downcast_ref::<super::WebRTCSinkPad>()
                 .unwrap();
 
-            let transceiver = pad.property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
+            // When answering, the offerer already picked the mid
+            if let (Some(mid), None) = (sink_pad.imp().mid(), media) {
+                payloader_caps_mut.set("a-mid", mid);
+            }
+
+            if let Some(name) = sink_pad.imp().track_name() {
+                payloader_caps_mut.set("a-label", name);
+            }
+            if let Some(role) = sink_pad.imp().track_role() {
+                payloader_caps_mut.set("a-content", role);
+            }
+            if let Some(language) = sink_pad.imp().track_language() {
+                payloader_caps_mut.set("a-lang", language);
+            }
+
+            // RFC 7273, the RTP timestamps follow the clock of the sender
+            if let (true, Some(clock)) = (settings.do_clock_signalling, element.clock()) {
+                payloader_caps_mut.set("a-ts-refclk", clock_ts_refclk(&clock));
+                payloader_caps_mut.set("a-mediaclk", "sender");
+            }
+
+            workarounds.apply_to_payloader_caps(payloader_caps_mut);
+
+            gst::info!(
+                CAT,
+                obj: element,
+                "Requesting WebRTC pad with caps {}",
+                payloader_caps
+            );
+
+            let pad = webrtcbin
+                .request_pad_simple(&format!("sink_{}", media_idx))
+                .unwrap();
+
+            let transceiver = pad.property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
 
             transceiver.set_property(
                 "direction",
@@ -1374,6 +3140,13 @@ impl BaseWebRTCSink {
 
             transceiver.set_property("codec-preferences", &payloader_caps);
 
+            // webrtcbin marks the packets of the sender according to its priority
+            transceiver
+                .property::<gst_webrtc::WebRTCRTPSender>("sender")
+                .set_property("priority", settings.dscp);
+
+            streams::set_msid(element, sink_pad, stream.is_video, &pad);
+
             if stream.sink_pad.name().starts_with("video_") {
                 if settings.do_fec {
                     transceiver.set_property("fec-type", gst_webrtc::WebRTCFECType::UlpRed);
@@ -1387,26 +3160,131 @@ impl BaseWebRTCSink {
                 WebRTCPad {
                     pad,
                     in_caps: stream.in_caps.as_ref().unwrap().clone(),
-                    media_idx: media_idx as u32,
+                    media_idx,
                     ssrc,
                     stream_name: Some(stream.sink_pad.name().to_string()),
                     payload: None,
+                    prefer_hardware_encoder: prefer_hardware,
                 },
             );
         }
     }
 
+    /// The caps of a stream are discovered with the encoders picked
+    /// according to #RsBaseWebRTCSink:prefer-hardware-encoders. Runs the
+    /// discovery again when `prefer_hardware` picks other encoders for a
+    /// consumer, returns None if the stream can't be offered with them.
+    async fn rediscover_caps(
+        element: &super::BaseWebRTCSink,
+        stream: &mut InputStream,
+        settings: &Settings,
+        prefer_hardware: bool,
+    ) -> Option<gst::Caps> {
+        let out_caps = stream.out_caps.as_ref().unwrap().to_owned();
+
+        if prefer_hardware == settings.prefer_hardware_encoders {
+            return Some(out_caps);
+        }
+
+        let discovered_codecs = {
+            let state = element.imp().state.lock().unwrap();
+            out_caps
+                .iter()
+                .filter_map(|s| state.codecs.get(&s.get::<i32>("payload").ok()?).cloned())
+                .collect::<Vec<_>>()
+        };
+
+        let encoder_changes = discovered_codecs.iter().any(|codec| {
+            let mut requested = codec.clone();
+            requested.set_prefer_hardware_encoder(prefer_hardware);
+            let mut discovered = codec.clone();
+            discovered.set_prefer_hardware_encoder(settings.prefer_hardware_encoders);
+
+            requested.encoder_factory() != discovered.encoder_factory()
+        });
+
+        if !encoder_changes {
+            return Some(out_caps);
+        }
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "Discovering the caps of stream {} again with the encoders picked for the consumer",
+            stream.sink_pad.name()
+        );
+
+        let in_caps = stream.in_caps.as_ref().unwrap().clone();
+        let stream_name = stream.sink_pad.name();
+        let discovery_info = stream.create_discovery(DiscoveryType::CodecSelection);
+        let output_caps = gst::Caps::new_any();
+
+        let futs = discovered_codecs.into_iter().map(|codec| {
+            BaseWebRTCSink::run_discovery_pipeline(
+                element,
+                &stream_name,
+                &discovery_info,
+                codec,
+                in_caps.clone(),
+                &output_caps,
+                settings.header_extension_ids(),
+                prefer_hardware,
+            )
+        });
+
+        let mut payloader_caps = gst::Caps::new_empty();
+        let payloader_caps_mut = payloader_caps.make_mut();
+
+        for ret in futures::future::join_all(futs).await {
+            match ret {
+                Ok(s) => payloader_caps_mut.append_structure(s),
+                Err(err) => {
+                    gst::warning!(
+                        CAT,
+                        obj: element,
+                        "Codec discovery pipeline failed: {}",
+                        err
+                    );
+                }
+            }
+        }
+
+        stream.remove_discovery(&discovery_info);
+
+        if payloader_caps.is_empty() {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Stream {stream_name} can't be encoded with the encoders picked for the consumer, using the default ones"
+            );
+            return None;
+        }
+
+        Some(payloader_caps)
+    }
+
     /// Prepare for accepting consumers, by setting
     /// up StreamProducers for each of our sink pads
     fn prepare(&self, element: &super::BaseWebRTCSink) -> Result<(), Error> {
         gst::debug!(CAT, obj: element, "preparing");
 
+        let (input_timeout, thumbnail_interval, thumbnail_width) = {
+            let settings = self.settings.lock().unwrap();
+            (
+                settings.input_timeout,
+                settings.thumbnail_interval,
+                settings.thumbnail_width,
+            )
+        };
+
         self.state
             .lock()
             .unwrap()
             .streams
             .iter_mut()
-            .try_for_each(|(_, stream)| stream.prepare(element))?;
+            .try_for_each(|(_, stream)| {
+                stream.prepare(element, input_timeout, thumbnail_interval, thumbnail_width)
+            })?;
 
         Ok(())
     }
@@ -1420,6 +3298,7 @@ impl BaseWebRTCSink {
         let signaller = settings.signaller.clone();
         drop(settings);
         let mut state = self.state.lock().unwrap();
+        let retired_signallers = std::mem::take(&mut state.retired_signallers);
 
         let session_ids: Vec<_> = state.sessions.keys().map(|k| k.to_owned()).collect();
 
@@ -1449,6 +3328,8 @@ impl BaseWebRTCSink {
 
         state.codec_discovery_done = false;
         state.codecs = BTreeMap::new();
+        state.draining = false;
+        state.held_eos_messages.clear();
 
         let signaller_state = state.signaller_state;
         if state.signaller_state == SignallerState::Started {
@@ -1458,10 +3339,15 @@ impl BaseWebRTCSink {
         drop(state);
         gst::debug!(CAT, obj: element, "Ending sessions");
         for session in sessions {
-            signaller.end_session(&session.id);
+            retired_signallers
+                .signaller(&session.id)
+                .unwrap_or(&signaller)
+                .end_session(&session.id);
         }
         gst::debug!(CAT, obj: element, "All sessions have started finalizing");
 
+        retired_signallers.stop();
+
         if signaller_state == SignallerState::Started {
             gst::info!(CAT, obj: element, "Stopping signaller");
             signaller.stop();
@@ -1478,6 +3364,11 @@ impl BaseWebRTCSink {
 
         gst::debug!(CAT, obj: element, "All sessions are done finalizing");
 
+        if let Some(pipeline) = self.state.lock().unwrap().shared_pipeline.take() {
+            gst::debug!(CAT, obj: element, "Stopping shared session pipeline");
+            let _ = pipeline.set_state(gst::State::Null);
+        }
+
         Ok(())
     }
 
@@ -1501,17 +3392,33 @@ impl BaseWebRTCSink {
                 "request-meta",
                 false,
                 glib::closure!(@watch instance => move |_signaler: glib::Object| -> Option<gst::Structure> {
-                    let meta = instance.imp().settings.lock().unwrap().meta.clone();
+                    instance.imp().meta()
+                })
+            ),
 
-                    meta
+            session_bitrate_ceiling: signaler.connect_closure(
+                "session-bitrate-ceiling",
+                false,
+                glib::closure!(@watch instance => move |_signaler: glib::Object, session_id: &str, bitrate: u32| {
+                    instance.imp().set_bitrate_ceiling(instance, session_id, bitrate);
                 })
             ),
 
             session_requested: signaler.connect_closure(
                 "session-requested",
                 false,
-                glib::closure!(@watch instance => move |_signaler: glib::Object, session_id: &str, peer_id: &str, offer: Option<&gst_webrtc::WebRTCSessionDescription>|{
-                    if let Err(err) = instance.imp().start_session(session_id, peer_id, offer) {
+                glib::closure!(@watch instance => move |signaler: glib::Object, session_id: &str, peer_id: &str, offer: Option<&gst_webrtc::WebRTCSessionDescription>, capabilities: Option<&gst::Structure>|{
+                    if instance.imp().settings.lock().unwrap().signaller.upcast_ref::<glib::Object>() != &signaler {
+                        gst::warning!(CAT, obj: instance, "Ignoring session {session_id} requested by a replaced signaller");
+                        return;
+                    }
+
+                    if let Some(capabilities) = capabilities {
+                        gst::info!(CAT, obj: instance, "Session {session_id} requested with capabilities {capabilities}");
+                    }
+
+                    let offer = offer.map(|offer| instance.imp().munge_sdp(session_id, false, offer.clone()));
+                    if let Err(err) = instance.imp().start_session(session_id, peer_id, offer.as_ref(), capabilities) {
                         gst::warning!(CAT, "{}", err);
                     }
                 })
@@ -1526,7 +3433,8 @@ impl BaseWebRTCSink {
                         session_description: &gst_webrtc::WebRTCSessionDescription| {
 
                         if session_description.type_() == gst_webrtc::WebRTCSDPType::Answer {
-                            instance.imp().handle_sdp_answer(instance, session_id, session_description);
+                            let answer = instance.imp().munge_sdp(session_id, false, session_description.clone());
+                            instance.imp().handle_sdp_answer(instance, session_id, &answer);
                         } else {
                             gst::error!(CAT, obj: instance, "Unsupported SDP Type");
                         }
@@ -1553,7 +3461,7 @@ impl BaseWebRTCSink {
                 "session-ended",
                 false,
                 glib::closure!(@watch instance => move |_signaler: glib::Object, session_id: &str|{
-                    if let Err(err) = instance.imp().remove_session(instance, session_id, false) {
+                    if let Err(err) = instance.imp().on_session_ended(instance, session_id) {
                         gst::warning!(CAT, "{}", err);
                     }
                     false
@@ -1570,17 +3478,118 @@ impl BaseWebRTCSink {
         });
     }
 
-    /// When using a custom signaller
+    /// When using a custom signaller. When running, the new signaller is
+    /// started right away and the sessions of the previous one are handled
+    /// according to the signaller-migration-policy
     pub fn set_signaller(&self, signaller: Signallable) -> Result<(), Error> {
-        let sigobj = signaller.clone();
-        let mut settings = self.settings.lock().unwrap();
+        let element = self.obj();
+        let settings = self.settings.lock().unwrap();
+        let old_signaller = settings.signaller.clone();
+        let policy = settings.signaller_migration_policy;
+        drop(settings);
 
-        self.connect_signaller(&sigobj);
-        settings.signaller = signaller;
+        if old_signaller == signaller {
+            return Ok(());
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let running = state.signaller_state == SignallerState::Started;
+        let old_signals = state.signaller_signals.take();
+        let session_ids = state
+            .sessions
+            .keys()
+            .filter(|session_id| state.retired_signallers.signaller(session_id).is_none())
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        if running
+            && policy == WebRTCSinkSignallerMigrationPolicy::KeepUntilClosed
+            && !session_ids.is_empty()
+        {
+            gst::info!(
+                CAT,
+                imp: self,
+                "Replacing signaller, keeping it for its {} sessions",
+                session_ids.len()
+            );
+
+            if let Some(signals) = old_signals {
+                state
+                    .retired_signallers
+                    .retire(old_signaller, signals, session_ids);
+            }
+            drop(state);
+        } else {
+            drop(state);
+
+            if running {
+                gst::info!(
+                    CAT,
+                    imp: self,
+                    "Replacing signaller, ending its {} sessions",
+                    session_ids.len()
+                );
+
+                for session_id in session_ids {
+                    let _ = self.remove_session(&element, &session_id, true);
+                }
+            }
+
+            if let Some(old_signals) = old_signals {
+                old_signals.disconnect(&old_signaller);
+            }
+
+            if running {
+                old_signaller.stop();
+            }
+        }
+
+        self.connect_signaller(&signaller);
+        self.settings.lock().unwrap().signaller = signaller.clone();
+
+        if running {
+            signaller.start();
+        }
 
         Ok(())
     }
 
+    /// Returns the signaller handling a session, which is not the current
+    /// one for sessions kept by a replaced signaller
+    fn session_signaller(&self, session_id: &str) -> Signallable {
+        let settings = self.settings.lock().unwrap();
+        let state = self.state.lock().unwrap();
+
+        state
+            .retired_signallers
+            .signaller(session_id)
+            .unwrap_or(&settings.signaller)
+            .clone()
+    }
+
+    /// Called once a session ended, stops the replaced signaller that kept
+    /// it if that was its last session
+    fn release_session_signaller(&self, session_id: &str) {
+        let Some(retired) = self
+            .state
+            .lock()
+            .unwrap()
+            .retired_signallers
+            .release(session_id)
+        else {
+            return;
+        };
+
+        gst::info!(
+            CAT,
+            imp: self,
+            "Last session of a replaced signaller ended, stopping it"
+        );
+
+        // We may be called from a signal handler of that signaller
+        RUNTIME.spawn_blocking(move || retired.stop());
+    }
+
     /// Called by the signaller when it wants to shut down gracefully
     fn shutdown(&self, element: &super::BaseWebRTCSink) {
         gst::info!(CAT, "Shutting down");
@@ -1593,9 +3602,7 @@ impl BaseWebRTCSink {
         offer: gst_webrtc::WebRTCSessionDescription,
         session_id: &str,
     ) {
-        let settings = self.settings.lock().unwrap();
-        let signaller = settings.signaller.clone();
-        drop(settings);
+        let signaller = self.session_signaller(session_id);
         let state = self.state.lock().unwrap();
 
         if let Some(session) = state.sessions.get(session_id) {
@@ -1604,6 +3611,7 @@ impl BaseWebRTCSink {
                 .emit_by_name::<()>("set-local-description", &[&offer, &None::<gst::Promise>]);
             drop(state);
 
+            let offer = self.munge_sdp(session_id, true, offer);
             signaller.send_sdp(session_id, &offer);
         }
     }
@@ -1614,9 +3622,7 @@ impl BaseWebRTCSink {
         answer: gst_webrtc::WebRTCSessionDescription,
         session_id: &str,
     ) {
-        let settings = self.settings.lock().unwrap();
-        let signaller = settings.signaller.clone();
-        drop(settings);
+        let signaller = self.session_signaller(session_id);
         let mut state = self.state.lock().unwrap();
 
         if let Some(mut session) = state.sessions.remove(session_id) {
@@ -1640,12 +3646,28 @@ impl BaseWebRTCSink {
             state.sessions.insert(session.id.clone(), session);
 
             drop(state);
+            let answer = self.munge_sdp(&session_id, true, answer);
             signaller.send_sdp(&session_id, &answer);
 
             self.on_remote_description_set(element, session_id)
         }
     }
 
+    /// Lets the application tweak a description right before it is sent
+    /// to the consumer, or right after it was received
+    fn munge_sdp(
+        &self,
+        session_id: &str,
+        local: bool,
+        desc: gst_webrtc::WebRTCSessionDescription,
+    ) -> gst_webrtc::WebRTCSessionDescription {
+        self.obj()
+            .emit_by_name::<gst_webrtc::WebRTCSessionDescription>(
+                "munge-sdp",
+                &[&session_id, &local, &desc],
+            )
+    }
+
     fn on_remote_description_offer_set(&self, element: &super::BaseWebRTCSink, session_id: String) {
         let state = self.state.lock().unwrap();
 
@@ -1714,7 +3736,12 @@ impl BaseWebRTCSink {
         in_caps: &gst::Caps,
         stream_name: &str,
         settings: &Settings,
+        prefer_hardware: bool,
     ) -> Option<Codec> {
+        if is_rtp_caps(in_caps) {
+            return BaseWebRTCSink::select_payloaded_codec(element, media, in_caps);
+        }
+
         let user_caps = match media.media() {
             Some("audio") => &settings.audio_caps,
             Some("video") => &settings.video_caps,
@@ -1773,6 +3800,13 @@ impl BaseWebRTCSink {
         }
 
         let mut twcc_idx = None;
+        let mut playout_delay_idx = None;
+        let mut ntp64_idx = None;
+        let mut audio_level_idx = None;
+        let (do_clock_signalling, do_audio_level) = {
+            let settings = element.imp().settings.lock().unwrap();
+            (settings.do_clock_signalling, settings.do_audio_level)
+        };
 
         for attribute in media.attributes() {
             if attribute.key() == "extmap" {
@@ -1788,12 +3822,62 @@ impl BaseWebRTCSink {
                                     "Failed to parse twcc index: {idx_str}"
                                 );
                             }
+                        } else if ext == RTP_PLAYOUT_DELAY_URI {
+                            if let Ok(idx) = idx_str.parse::<u32>() {
+                                playout_delay_idx = Some(idx);
+                            } else {
+                                gst::warning!(
+                                    CAT,
+                                    obj: element,
+                                    "Failed to parse playout delay index: {idx_str}"
+                                );
+                            }
+                        } else if ext == RTP_NTP64_URI && do_clock_signalling {
+                            if let Ok(idx) = idx_str.parse::<u32>() {
+                                ntp64_idx = Some(idx);
+                            } else {
+                                gst::warning!(
+                                    CAT,
+                                    obj: element,
+                                    "Failed to parse ntp-64 index: {idx_str}"
+                                );
+                            }
+                        } else if ext == RTP_AUDIO_LEVEL_URI && do_audio_level {
+                            if let Ok(idx) = idx_str.parse::<u32>() {
+                                audio_level_idx = Some(idx);
+                            } else {
+                                gst::warning!(
+                                    CAT,
+                                    obj: element,
+                                    "Failed to parse audio level index: {idx_str}"
+                                );
+                            }
                         }
                     }
                 }
             }
         }
 
+        let extension_ids = HeaderExtensionIds {
+            twcc: twcc_idx,
+            playout_delay: playout_delay_idx,
+            ntp64: ntp64_idx,
+            audio_level: audio_level_idx,
+        };
+
+        for idx in [twcc_idx, playout_delay_idx, ntp64_idx, audio_level_idx]
+            .into_iter()
+            .flatten()
+        {
+            if settings.reserved_extension_ids.contains(&idx) {
+                gst::warning!(
+                    CAT,
+                    obj: element,
+                    "Header extension ID {idx} picked by the offerer is reserved"
+                );
+            }
+        }
+
         let futs = ordered_codecs_and_caps
             .iter()
             .flat_map(|(_, codecs_and_caps)| codecs_and_caps)
@@ -1805,7 +3889,8 @@ impl BaseWebRTCSink {
                     codec.clone(),
                     in_caps.clone(),
                     caps,
-                    twcc_idx,
+                    extension_ids,
+                    prefer_hardware,
                 )
                 .await
                 .map(|s| {
@@ -1815,9 +3900,14 @@ impl BaseWebRTCSink {
                 })
             });
 
-        /* Run sequentially to avoid NVENC collisions */
-        for fut in futs {
-            if let Ok(codec) = fut.await {
+        /* Results are consumed in preference order, pending probes get
+         * cancelled as soon as one succeeds. NVENC probes are serialized
+         * in run_discovery_pipeline() to avoid collisions */
+        let mut results =
+            futures::stream::iter(futs).buffered(settings.discovery_concurrency as usize);
+
+        while let Some(res) = results.next().await {
+            if let Ok(codec) = res {
                 return Some(codec);
             }
         }
@@ -1825,6 +3915,59 @@ impl BaseWebRTCSink {
         None
     }
 
+    /// Already payloaded streams can't be transcoded, the offer must list
+    /// their codec with the payload type they are using
+    fn select_payloaded_codec(
+        element: &super::BaseWebRTCSink,
+        media: &gst_sdp::SDPMediaRef,
+        in_caps: &gst::Caps,
+    ) -> Option<Codec> {
+        let mut codec = Codecs::find_for_payloaded_caps(in_caps)?;
+        let payload = codec.payload().unwrap();
+
+        let Some(mut caps) = media
+            .formats()
+            .filter_map(|format| format.parse::<i32>().ok())
+            .find(|pt| *pt == payload)
+            .and_then(|pt| media.caps_from_media(pt))
+        else {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Payload type {payload} of payloaded stream not offered"
+            );
+            return None;
+        };
+
+        let s = caps.make_mut().structure_mut(0).unwrap();
+
+        if !s
+            .get::<&str>("encoding-name")
+            .map_or(false, |name| name.eq_ignore_ascii_case(&codec.name))
+        {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Payload type {payload} offered for another codec than {}",
+                codec.name
+            );
+            return None;
+        }
+
+        s.filter_map_in_place(|quark, value| {
+            if quark.as_str().starts_with("rtcp-fb-") {
+                None
+            } else {
+                Some(value)
+            }
+        });
+        s.set_name("application/x-rtp");
+
+        codec.set_output_filter(caps);
+
+        Some(codec)
+    }
+
     fn negotiate(
         &self,
         element: &super::BaseWebRTCSink,
@@ -1855,58 +3998,7 @@ impl BaseWebRTCSink {
                     .webrtcbin
                     .emit_by_name::<()>("set-remote-description", &[&offer, &promise]);
             } else {
-                let element = element.downgrade();
-                gst::debug!(CAT, "Creating offer for session {}", session_id);
-                let session_id = session_id.to_string();
-                let promise = gst::Promise::with_change_func(move |reply| {
-                    gst::debug!(CAT, "Created offer for session {}", session_id);
-
-                    if let Some(element) = element.upgrade() {
-                        let this = element.imp();
-                        let reply = match reply {
-                            Ok(Some(reply)) => reply,
-                            Ok(None) => {
-                                gst::warning!(
-                                    CAT,
-                                    obj: element,
-                                    "Promise returned without a reply for {}",
-                                    session_id
-                                );
-                                let _ = this.remove_session(&element, &session_id, true);
-                                return;
-                            }
-                            Err(err) => {
-                                gst::warning!(
-                                    CAT,
-                                    obj: element,
-                                    "Promise returned with an error for {}: {:?}",
-                                    session_id,
-                                    err
-                                );
-                                let _ = this.remove_session(&element, &session_id, true);
-                                return;
-                            }
-                        };
-
-                        if let Ok(offer) = reply.value("offer").map(|offer| {
-                            offer.get::<gst_webrtc::WebRTCSessionDescription>().unwrap()
-                        }) {
-                            this.on_offer_created(&element, offer, &session_id);
-                        } else {
-                            gst::warning!(
-                                CAT,
-                                "Reply without an offer for session {}: {:?}",
-                                session_id,
-                                reply
-                            );
-                            let _ = this.remove_session(&element, &session_id, true);
-                        }
-                    }
-                });
-
-                session
-                    .webrtcbin
-                    .emit_by_name::<()>("create-offer", &[&None::<gst::Structure>, &promise]);
+                Self::create_offer(element, &session.webrtcbin, session_id, None);
             }
         } else {
             gst::debug!(
@@ -1919,17 +4011,256 @@ impl BaseWebRTCSink {
         }
     }
 
-    fn on_ice_candidate(
-        &self,
-        _element: &super::BaseWebRTCSink,
-        session_id: String,
-        sdp_m_line_index: u32,
-        candidate: String,
-    ) {
+    /// Creates an offer for a session and sends it to the consumer once
+    /// created, `options` are passed on to webrtcbin
+    fn create_offer(
+        element: &super::BaseWebRTCSink,
+        webrtcbin: &gst::Element,
+        session_id: &str,
+        options: Option<&gst::Structure>,
+    ) {
+        let element = element.downgrade();
+        gst::debug!(CAT, "Creating offer for session {}", session_id);
+        let session_id = session_id.to_string();
+        let promise = gst::Promise::with_change_func(move |reply| {
+            gst::debug!(CAT, "Created offer for session {}", session_id);
+
+            if let Some(element) = element.upgrade() {
+                let this = element.imp();
+                let reply = match reply {
+                    Ok(Some(reply)) => reply,
+                    Ok(None) => {
+                        gst::warning!(
+                            CAT,
+                            obj: element,
+                            "Promise returned without a reply for {}",
+                            session_id
+                        );
+                        let _ = this.remove_session(&element, &session_id, true);
+                        return;
+                    }
+                    Err(err) => {
+                        gst::warning!(
+                            CAT,
+                            obj: element,
+                            "Promise returned with an error for {}: {:?}",
+                            session_id,
+                            err
+                        );
+                        let _ = this.remove_session(&element, &session_id, true);
+                        return;
+                    }
+                };
+
+                if let Ok(offer) = reply
+                    .value("offer")
+                    .map(|offer| offer.get::<gst_webrtc::WebRTCSessionDescription>().unwrap())
+                {
+                    this.on_offer_created(&element, offer, &session_id);
+                } else {
+                    gst::warning!(
+                        CAT,
+                        "Reply without an offer for session {}: {:?}",
+                        session_id,
+                        reply
+                    );
+                    let _ = this.remove_session(&element, &session_id, true);
+                }
+            }
+        });
+
+        webrtcbin.emit_by_name::<()>("create-offer", &[&options, &promise]);
+    }
+
+    fn on_ice_candidate(
+        &self,
+        element: &super::BaseWebRTCSink,
+        session_id: String,
+        sdp_m_line_index: u32,
+        candidate: String,
+    ) {
+        if !element.emit_by_name::<bool>(
+            "candidate-filter",
+            &[&session_id, &sdp_m_line_index, &candidate],
+        ) {
+            gst::debug!(
+                CAT,
+                obj: element,
+                "Not sending filtered out candidate {candidate} of session {session_id}"
+            );
+            return;
+        }
+
+        let signaller = self.session_signaller(&session_id);
         let settings = self.settings.lock().unwrap();
-        let signaller = settings.signaller.clone();
+        let candidate = if settings.ice_candidate_type_order.is_empty() {
+            candidate
+        } else {
+            prioritize_ice_candidate(&candidate, &settings.ice_candidate_type_order)
+                .unwrap_or(candidate)
+        };
+        let batch_interval = settings.ice_candidate_batch_interval;
         drop(settings);
-        signaller.add_ice(&session_id, &candidate, sdp_m_line_index, None)
+
+        if batch_interval == 0 {
+            signaller.add_ice(&session_id, &candidate, sdp_m_line_index, None);
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let Some(session) = state.sessions.get_mut(&session_id) else {
+            return;
+        };
+
+        session
+            .pending_candidates
+            .push((sdp_m_line_index, candidate));
+
+        // The first candidate of a batch schedules the sending of the batch
+        if session.pending_candidates.len() == 1 {
+            let element = element.downgrade();
+            RUNTIME.spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(batch_interval as u64)).await;
+
+                if let Some(element) = element.upgrade() {
+                    element.imp().send_pending_candidates(&session_id, false);
+                }
+            });
+        }
+    }
+
+    /// Sends the current batch of candidates, `end_of_candidates` once
+    /// gathering is complete, in which case the batch is sent right away
+    fn send_pending_candidates(&self, session_id: &str, end_of_candidates: bool) {
+        let signaller = self.session_signaller(session_id);
+        let candidates = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .get_mut(session_id)
+            .map(|session| std::mem::take(&mut session.pending_candidates))
+            .unwrap_or_default();
+
+        // The batch was already sent along with the end of candidates
+        if candidates.is_empty() && !end_of_candidates {
+            return;
+        }
+
+        gst::debug!(
+            CAT,
+            imp: self,
+            "Sending a batch of {} candidates for session {session_id}{}",
+            candidates.len(),
+            if end_of_candidates {
+                ", end of candidates"
+            } else {
+                ""
+            }
+        );
+
+        signaller.add_ice_batch(session_id, &candidates, end_of_candidates);
+    }
+
+    /// Returns the pipeline hosting all sessions in shared-pipeline mode,
+    /// creating and starting it for the first session
+    fn shared_pipeline(&self) -> Result<gst::Pipeline, gst::StateChangeError> {
+        let element = self.obj();
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(ref pipeline) = state.shared_pipeline {
+            return Ok(pipeline.clone());
+        }
+
+        gst::info!(CAT, imp: self, "Creating shared session pipeline");
+
+        let pipeline = gst::Pipeline::builder()
+            .name(format!("{}-shared-pipeline", element.name()))
+            .build();
+
+        pipeline.use_clock(element.clock().as_ref());
+        pipeline.set_start_time(gst::ClockTime::NONE);
+        pipeline.set_base_time(element.base_time().unwrap());
+        // The pipeline only posts EOS once all the sessions are EOS, the
+        // EOS of each session bin is forwarded to us as an element message
+        pipeline.set_property("message-forward", true);
+
+        let mut bus_stream = pipeline.bus().unwrap().stream();
+        let element_clone = element.downgrade();
+        let pipeline_clone = pipeline.downgrade();
+
+        RUNTIME.spawn(async move {
+            while let Some(msg) = bus_stream.next().await {
+                let Some(element) = element_clone.upgrade() else {
+                    break;
+                };
+                let Some(pipeline) = pipeline_clone.upgrade() else {
+                    break;
+                };
+                let this = element.imp();
+
+                // The session the message originates from, if any
+                let session_id = |src: Option<&gst::Object>| {
+                    let src = src?;
+                    let state = this.state.lock().unwrap();
+                    state
+                        .sessions
+                        .values()
+                        .find(|session| src.has_as_ancestor(&session.pipeline))
+                        .map(|session| session.id.clone())
+                };
+
+                match msg.view() {
+                    gst::MessageView::Error(err) => {
+                        // Only tear down the session the error originates from
+                        let session_id = session_id(msg.src());
+
+                        gst::error!(
+                            CAT,
+                            "session {:?} error: {}, details: {:?}",
+                            session_id,
+                            err.error(),
+                            err.debug()
+                        );
+
+                        if let Some(session_id) = session_id {
+                            let _ = this.remove_session(&element, &session_id, true);
+                        }
+                    }
+                    gst::MessageView::Element(element_msg) => {
+                        let Some(forwarded) = element_msg
+                            .structure()
+                            .filter(|s| s.name() == "GstBinForwarded")
+                            .and_then(|s| s.get::<gst::Message>("message").ok())
+                        else {
+                            continue;
+                        };
+
+                        if let gst::MessageView::Eos(..) = forwarded.view() {
+                            if let Some(session_id) = session_id(forwarded.src()) {
+                                gst::error!(
+                                    CAT,
+                                    "Unexpected end of stream in session {}",
+                                    session_id,
+                                );
+                                let _ = this.remove_session(&element, &session_id, true);
+                            }
+                        }
+                    }
+                    gst::MessageView::Latency(..) => {
+                        gst::info!(CAT, obj: pipeline, "Recalculating latency");
+                        let _ = pipeline.recalculate_latency();
+                    }
+                    _ => (),
+                }
+            }
+        });
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        state.shared_pipeline = Some(pipeline.clone());
+
+        Ok(pipeline)
     }
 
     /// Called by the signaller to add a new session
@@ -1938,13 +4269,48 @@ impl BaseWebRTCSink {
         session_id: &str,
         peer_id: &str,
         offer: Option<&gst_webrtc::WebRTCSessionDescription>,
+        capabilities: Option<&gst::Structure>,
     ) -> Result<(), WebRTCSinkError> {
-        let pipeline = gst::Pipeline::builder()
-            .name(format!("session-pipeline-{session_id}"))
-            .build();
+        if self.resume_session(session_id, peer_id, offer.is_some()) {
+            return Ok(());
+        }
 
-        self.obj()
-            .emit_by_name::<()>("consumer-pipeline-created", &[&peer_id, &pipeline]);
+        let shared_pipeline = if self.settings.lock().unwrap().shared_pipeline {
+            Some(
+                self.shared_pipeline()
+                    .map_err(|err| WebRTCSinkError::SessionPipelineError {
+                        session_id: session_id.to_string(),
+                        peer_id: peer_id.to_string(),
+                        details: err.to_string(),
+                    })?,
+            )
+        } else {
+            None
+        };
+
+        let pipeline = if let Some(ref shared_pipeline) = shared_pipeline {
+            let bin = gst::Bin::builder()
+                .name(format!("session-bin-{session_id}"))
+                .build();
+            // Our state changes must not affect the other sessions
+            bin.set_property("async-handling", true);
+
+            self.obj()
+                .emit_by_name::<()>("consumer-pipeline-created", &[&peer_id, &bin]);
+
+            bin
+        } else {
+            let pipeline = gst::Pipeline::builder()
+                .name(format!("session-pipeline-{session_id}"))
+                .build();
+
+            self.obj()
+                .emit_by_name::<()>("consumer-pipeline-created", &[&peer_id, &pipeline]);
+
+            pipeline.upcast()
+        };
+
+        let turn_servers = self.turn_servers(session_id, peer_id);
 
         let settings = self.settings.lock().unwrap();
         let mut state = self.state.lock().unwrap();
@@ -1952,6 +4318,8 @@ impl BaseWebRTCSink {
         let session_id = session_id.to_string();
         let element = self.obj().clone();
 
+        let capabilities = capabilities.cloned();
+
         if state.sessions.contains_key(&session_id) {
             return Err(WebRTCSinkError::DuplicateSessionId(session_id));
         }
@@ -1978,13 +4346,38 @@ impl BaseWebRTCSink {
             webrtcbin.set_property("stun-server", stun_server);
         }
 
-        for turn_server in settings.turn_servers.iter() {
-            webrtcbin.emit_by_name::<bool>("add-turn-server", &[&turn_server]);
+        turn::set_servers(&element, &webrtcbin, &turn_servers, &[]);
+
+        if !settings.ice_local_addresses.is_empty() {
+            if webrtcbin.has_property("ice-agent", None) {
+                let ice_agent = webrtcbin.property::<glib::Object>("ice-agent");
+
+                // Adding an address disables the automatic discovery of the
+                // local addresses by the agent
+                for address in settings.ice_local_addresses.iter() {
+                    if !ice_agent.emit_by_name::<bool>("add-local-ip-address", &[address]) {
+                        gst::warning!(
+                            CAT,
+                            obj: element,
+                            "Failed to restrict ICE gathering to {address}"
+                        );
+                    }
+                }
+            } else {
+                gst::warning!(
+                    CAT,
+                    obj: element,
+                    "webrtcbin doesn't expose its ICE agent, ignoring ice-local-addresses"
+                );
+            }
         }
 
         let rtpgccbwe = match settings.cc_info.heuristic {
             WebRTCSinkCongestionControl::GoogleCongestionControl => {
-                let rtpgccbwe = match gst::ElementFactory::make("rtpgccbwe").build() {
+                let rtpgccbwe = match gst::ElementFactory::make("rtpgccbwe")
+                    .name(format!("{session_id}-rtpgccbwe"))
+                    .build()
+                {
                     Err(err) => {
                         glib::g_warning!(
                             "webrtcsink",
@@ -2048,6 +4441,27 @@ impl BaseWebRTCSink {
             _ => None,
         };
 
+        webrtcbin.connect_closure(
+            "deep-element-added",
+            false,
+            glib::closure!(@watch element, @strong session_id
+                    => move |_webrtcbin: gst::Element, _bin: gst::Bin, e: gst::Element| {
+                if e.factory().map_or(false, |f| f.name() == "srtpenc") {
+                    // The DTLS encoder configures both at once with the
+                    // negotiated protection profile
+                    let element = element.downgrade();
+                    let session_id = session_id.clone();
+                    e.connect_notify(Some("rtp-auth"), move |srtpenc, _pspec| {
+                        if let Some(element) = element.upgrade() {
+                            element.imp().on_srtp_keys_set(&element, &session_id, srtpenc);
+                        }
+                    });
+                } else if e.factory().map_or(false, |f| f.name() == "rtprtxsend") {
+                    element.imp().configure_rtprtxsend(&e);
+                }
+            }),
+        );
+
         pipeline.add(&webrtcbin).unwrap();
 
         let element_clone = element.downgrade();
@@ -2070,11 +4484,26 @@ impl BaseWebRTCSink {
         let element_clone = element.downgrade();
         let peer_id_clone = peer_id.clone();
         let session_id_clone = session_id.clone();
+        let connected_sessions = state.connected_sessions.clone();
         webrtcbin.connect_notify(Some("connection-state"), move |webrtcbin, _pspec| {
             if let Some(element) = element_clone.upgrade() {
                 let state =
                     webrtcbin.property::<gst_webrtc::WebRTCPeerConnectionState>("connection-state");
 
+                {
+                    let (sessions, cvar) = &*connected_sessions;
+                    let mut sessions = sessions.lock().unwrap();
+                    let changed = if state == gst_webrtc::WebRTCPeerConnectionState::Connected {
+                        sessions.insert(session_id_clone.clone())
+                    } else {
+                        sessions.remove(&session_id_clone)
+                    };
+
+                    if changed {
+                        cvar.notify_all();
+                    }
+                }
+
                 match state {
                     gst_webrtc::WebRTCPeerConnectionState::Failed => {
                         let this = element.imp();
@@ -2087,6 +4516,11 @@ impl BaseWebRTCSink {
                         );
                         let _ = this.remove_session(&element, &session_id_clone, true);
                     }
+                    gst_webrtc::WebRTCPeerConnectionState::Connected => {
+                        element
+                            .imp()
+                            .on_session_connected(&element, &session_id_clone, webrtcbin);
+                    }
                     _ => {
                         gst::log!(
                             CAT,
@@ -2110,6 +4544,19 @@ impl BaseWebRTCSink {
                     .property::<gst_webrtc::WebRTCICEConnectionState>("ice-connection-state");
                 let this = element.imp();
 
+                if matches!(
+                    state,
+                    gst_webrtc::WebRTCICEConnectionState::Connected
+                        | gst_webrtc::WebRTCICEConnectionState::Completed
+                ) {
+                    let mut state = this.state.lock().unwrap();
+                    if let Some(session) = state.sessions.get_mut(&session_id_clone) {
+                        session
+                            .ice_connected_at
+                            .get_or_insert_with(std::time::Instant::now);
+                    }
+                }
+
                 match state {
                     gst_webrtc::WebRTCICEConnectionState::Failed => {
                         gst::warning!(
@@ -2167,10 +4614,16 @@ impl BaseWebRTCSink {
                     peer_id_clone,
                     state
                 );
+
+                let this = element.imp();
+                let batching = this.settings.lock().unwrap().ice_candidate_batch_interval > 0;
+                if batching && state == gst_webrtc::WebRTCICEGatheringState::Complete {
+                    this.send_pending_candidates(&session_id_clone, true);
+                }
             }
         });
 
-        let session = Session::new(
+        let mut session = Session::new(
             session_id.clone(),
             pipeline.clone(),
             webrtcbin.clone(),
@@ -2180,12 +4633,22 @@ impl BaseWebRTCSink {
                     &peer_id,
                     settings.cc_info.min_bitrate,
                     settings.cc_info.max_bitrate,
+                    settings.cc_info.homegrown,
                 )),
                 _ => None,
             },
             rtpgccbwe,
             settings.cc_info,
+            settings.stats_collection_interval,
         );
+        session.capabilities = capabilities.clone();
+        session.turn_servers = turn_servers;
+        session.bandwidth_group = settings.bandwidth_group.as_deref().map(|group| {
+            bandwidth_group::Membership::join(group, settings.bandwidth_group_bitrate)
+        });
+        // Negotiation happens as usual, media is discarded until approval
+        session.awaiting_approval = settings.require_approval;
+        session.paused = settings.require_approval;
 
         let rtpbin = webrtcbin
             .dynamic_cast_ref::<gst::ChildProxy>()
@@ -2193,6 +4656,14 @@ impl BaseWebRTCSink {
             .child_by_name("rtpbin")
             .unwrap();
 
+        if settings.do_clock_signalling {
+            // Derive the NTP time of the sender reports from the pipeline
+            // clock, and not from the time they are sent at, so that receivers
+            // can map audio and video onto the same timeline
+            rtpbin.set_property_from_str("ntp-time-source", "clock-time");
+            rtpbin.set_property("rtcp-sync-send-time", false);
+        }
+
         if session.congestion_controller.is_some() {
             let session_id_str = session_id.to_string();
             rtpbin.connect_closure("on-new-ssrc", true,
@@ -2221,62 +4692,67 @@ impl BaseWebRTCSink {
                 );
         }
 
-        let clock = element.clock();
+        if let Some(pipeline) = pipeline.downcast_ref::<gst::Pipeline>() {
+            let clock = element.clock();
 
-        pipeline.use_clock(clock.as_ref());
-        pipeline.set_start_time(gst::ClockTime::NONE);
-        pipeline.set_base_time(element.base_time().unwrap());
+            pipeline.use_clock(clock.as_ref());
+            pipeline.set_start_time(gst::ClockTime::NONE);
+            pipeline.set_base_time(element.base_time().unwrap());
 
-        let mut bus_stream = pipeline.bus().unwrap().stream();
-        let element_clone = element.downgrade();
-        let pipeline_clone = pipeline.downgrade();
-        let session_id_clone = session_id.clone();
+            let mut bus_stream = pipeline.bus().unwrap().stream();
+            let element_clone = element.downgrade();
+            let pipeline_clone = pipeline.downgrade();
+            let session_id_clone = session_id.clone();
 
-        RUNTIME.spawn(async move {
-            while let Some(msg) = bus_stream.next().await {
-                let Some(element) = element_clone.upgrade() else { break; };
-                let Some(pipeline) = pipeline_clone.upgrade() else { break; };
-                let this = element.imp();
-                match msg.view() {
-                    gst::MessageView::Error(err) => {
-                        gst::error!(
-                            CAT,
-                            "session {} error: {}, details: {:?}",
-                            session_id_clone,
-                            err.error(),
-                            err.debug()
-                        );
-                        let _ = this.remove_session(&element, &session_id_clone, true);
-                    }
-                    gst::MessageView::StateChanged(state_changed) => {
-                        if state_changed.src() == Some(pipeline.upcast_ref()) {
-                            pipeline.debug_to_dot_file_with_ts(
-                                gst::DebugGraphDetails::all(),
-                                format!(
-                                    "webrtcsink-session-{}-{:?}-to-{:?}",
-                                    session_id_clone,
-                                    state_changed.old(),
-                                    state_changed.current()
-                                ),
+            RUNTIME.spawn(async move {
+                while let Some(msg) = bus_stream.next().await {
+                    let Some(element) = element_clone.upgrade() else { break; };
+                    let Some(pipeline) = pipeline_clone.upgrade() else { break; };
+                    let this = element.imp();
+                    match msg.view() {
+                        gst::MessageView::Error(err) => {
+                            gst::error!(
+                                CAT,
+                                "session {} error: {}, details: {:?}",
+                                session_id_clone,
+                                err.error(),
+                                err.debug()
                             );
+                            let _ = this.remove_session(&element, &session_id_clone, true);
                         }
+                        gst::MessageView::StateChanged(state_changed) => {
+                            if state_changed.src() == Some(pipeline.upcast_ref()) {
+                                pipeline.debug_to_dot_file_with_ts(
+                                    gst::DebugGraphDetails::all(),
+                                    format!(
+                                        "webrtcsink-session-{}-{:?}-to-{:?}",
+                                        session_id_clone,
+                                        state_changed.old(),
+                                        state_changed.current()
+                                    ),
+                                );
+                            }
+                        }
+                        gst::MessageView::Latency(..) => {
+                            gst::info!(CAT, obj: pipeline, "Recalculating latency");
+                            let _ = pipeline.recalculate_latency();
+                        }
+                        gst::MessageView::Eos(..) => {
+                            gst::error!(
+                                CAT,
+                                "Unexpected end of stream in session {}",
+                                session_id_clone,
+                            );
+                            let _ = this.remove_session(&element, &session_id_clone, true);
+                        }
+                        _ => (),
                     }
-                    gst::MessageView::Latency(..) => {
-                        gst::info!(CAT, obj: pipeline, "Recalculating latency");
-                        let _ = pipeline.recalculate_latency();
-                    }
-                    gst::MessageView::Eos(..) => {
-                        gst::error!(
-                            CAT,
-                            "Unexpected end of stream in session {}",
-                            session_id_clone,
-                        );
-                        let _ = this.remove_session(&element, &session_id_clone, true);
-                    }
-                    _ => (),
                 }
-            }
-        });
+            });
+        } else if let Some(ref shared_pipeline) = shared_pipeline {
+            // Clock, base time and bus messages are handled by the shared pipeline
+            shared_pipeline.add(&pipeline).unwrap();
+        }
 
         state.sessions.insert(session_id.to_string(), session);
 
@@ -2290,40 +4766,118 @@ impl BaseWebRTCSink {
             if let Some(element) = element_clone.upgrade() {
                 let this = element.imp();
 
-                let settings_clone = this.settings.lock().unwrap().clone();
+                let mut settings_clone = this.settings.lock().unwrap().clone();
                 let signaller = settings_clone.signaller.clone();
 
-                let mut webrtc_pads: HashMap<u32, WebRTCPad> = HashMap::new();
-                let mut codecs: BTreeMap<i32, Codec> = BTreeMap::new();
+                // Only consider the codecs the consumer said it can decode
+                let allowed_codecs = capabilities
+                    .as_ref()
+                    .and_then(|capabilities| capabilities.get::<gst::Array>("codecs").ok())
+                    .map(|codecs| {
+                        codecs
+                            .iter()
+                            .filter_map(|codec| codec.get::<String>().ok())
+                            .collect::<Vec<_>>()
+                    });
 
-                if let Some(ref offer) = offer_clone {
-                    for media in offer.sdp().medias() {
-                        let media_is_video = match media.media() {
-                            Some("audio") => false,
-                            Some("video") => true,
-                            _ => {
-                                continue;
-                            }
-                        };
+                if let Some(ref allowed_codecs) = allowed_codecs {
+                    settings_clone.video_caps =
+                        filter_caps_by_codecs(&settings_clone.video_caps, allowed_codecs);
+                    settings_clone.audio_caps =
+                        filter_caps_by_codecs(&settings_clone.audio_caps, allowed_codecs);
+
+                    for stream in streams.iter_mut() {
+                        stream.out_caps = stream
+                            .out_caps
+                            .as_ref()
+                            .map(|caps| filter_caps_by_codecs(caps, allowed_codecs));
+                    }
+                }
+
+                let user_agent = capabilities
+                    .as_ref()
+                    .and_then(|capabilities| capabilities.get::<String>("user-agent").ok())
+                    .or_else(|| {
+                        offer_clone
+                            .as_ref()
+                            .map(|offer| interop::sdp_user_agent(offer.sdp()))
+                    })
+                    .unwrap_or_default();
+                let workarounds =
+                    Workarounds::for_user_agent(&settings_clone.interop_rules, &user_agent);
+                if workarounds != Workarounds::default() {
+                    gst::info!(
+                        CAT,
+                        obj: element,
+                        "Applying {workarounds:?} to session {session_id} with user agent {user_agent:?}"
+                    );
+                }
+                settings_clone.do_fec &= !workarounds.disable_fec;
+                settings_clone.do_retransmission &= !workarounds.disable_retransmission;
+
+                let stream_names = streams
+                    .iter()
+                    .map(|stream| stream.sink_pad.name().to_string())
+                    .collect::<Vec<_>>();
+                let selected_streams = element.emit_by_name::<gst::Array>(
+                    "request-stream-selection",
+                    &[&session_id, &peer_id, &gst::Array::new(&stream_names)],
+                );
+                let selected_streams = selected_streams
+                    .iter()
+                    .filter_map(|name| name.get::<String>().ok())
+                    .collect::<HashSet<_>>();
+
+                streams.retain(|stream| {
+                    let selected = selected_streams.contains(stream.sink_pad.name().as_str());
+                    if !selected {
+                        gst::info!(
+                            CAT,
+                            obj: element,
+                            "Not offering stream {} to consumer {peer_id}",
+                            stream.sink_pad.name()
+                        );
+                    }
+                    selected
+                });
 
-                        if let Some(idx) = streams.iter().position(|s| {
-                            let structname =
-                                s.in_caps.as_ref().unwrap().structure(0).unwrap().name();
-                            let stream_is_video = structname.starts_with("video/");
+                let mut webrtc_pads: HashMap<u32, WebRTCPad> = HashMap::new();
+                let mut codecs: BTreeMap<i32, Codec> = BTreeMap::new();
 
-                            if !stream_is_video {
-                                assert!(structname.starts_with("audio/"));
+                if let Some(ref offer) = offer_clone {
+                    for (media_idx, media) in offer.sdp().medias().enumerate() {
+                        let media_idx = media_idx as u32;
+                        let media_is_video = match media.media() {
+                            Some("audio") => false,
+                            Some("video") => true,
+                            Some("application") => {
+                                // Answered by webrtcbin itself, we only need to
+                                // keep the indices of our transceivers aligned
+                                // with the offered m-lines
+                                gst::debug!(
+                                    CAT,
+                                    obj: element,
+                                    "Offer contains application media at index {media_idx}"
+                                );
+                                continue;
                             }
+                            _ => {
+                                continue;
+                            }
+                        };
 
-                            media_is_video == stream_is_video
-                        }) {
+                        if let Some(idx) = streams.iter().position(|s| media_is_video == s.is_video)
+                        {
                             let mut stream = streams.remove(idx);
                             BaseWebRTCSink::request_webrtcbin_pad(
                                 &element,
                                 &webrtcbin,
+                                &peer_id,
                                 &mut stream,
                                 Some(media),
+                                media_idx,
                                 &settings_clone,
+                                &workarounds,
                                 &mut webrtc_pads,
                                 &mut codecs,
                             )
@@ -2333,18 +4887,23 @@ impl BaseWebRTCSink {
                                 &element,
                                 &webrtcbin,
                                 &mut webrtc_pads,
+                                media_idx,
                                 media_is_video,
                             );
                         }
                     }
                 } else {
                     for mut stream in streams {
+                        let media_idx = webrtc_pads.len() as u32;
                         BaseWebRTCSink::request_webrtcbin_pad(
                             &element,
                             &webrtcbin,
+                            &peer_id,
                             &mut stream,
                             None,
+                            media_idx,
                             &settings_clone,
+                            &workarounds,
                             &mut webrtc_pads,
                             &mut codecs,
                         )
@@ -2353,6 +4912,9 @@ impl BaseWebRTCSink {
                 }
 
                 let enable_data_channel_navigation = settings_clone.enable_data_channel_navigation;
+                let enable_frame_timing = settings_clone.enable_frame_timing;
+                let enable_thumbnails = settings_clone.thumbnail_interval > 0;
+                let do_clock_signalling = settings_clone.do_clock_signalling;
 
                 drop(settings_clone);
 
@@ -2380,8 +4942,64 @@ impl BaseWebRTCSink {
 
                 if enable_data_channel_navigation {
                     let mut state = this.state.lock().unwrap();
-                    state.navigation_handler =
-                        Some(NavigationEventHandler::new(&element, &webrtcbin));
+                    state.navigation_handler = Some(NavigationEventHandler::new(
+                        &element,
+                        &webrtcbin,
+                        &session_id,
+                    ));
+                }
+
+                if enable_frame_timing {
+                    gst::info!(CAT, "Creating frame timing data channel");
+                    // Late timing information is useless, don't retransmit it
+                    let channel = webrtcbin.emit_by_name::<WebRTCDataChannel>(
+                        "create-data-channel",
+                        &[
+                            &"frame-timing",
+                            &gst::Structure::builder("config")
+                                .field("ordered", false)
+                                .field("max-retransmits", 0i32)
+                                .build(),
+                        ],
+                    );
+
+                    let mut state = this.state.lock().unwrap();
+                    if let Some(session) = state.sessions.get_mut(&session_id) {
+                        session.frame_timing_channel = Some(channel);
+                    }
+                }
+
+                if enable_thumbnails {
+                    gst::info!(CAT, "Creating thumbnail data channel");
+                    // Only the latest thumbnail matters
+                    let channel = webrtcbin.emit_by_name::<WebRTCDataChannel>(
+                        "create-data-channel",
+                        &[
+                            &"thumbnails",
+                            &gst::Structure::builder("config")
+                                .field("ordered", false)
+                                .field("max-retransmits", 0i32)
+                                .field("priority", gst_webrtc::WebRTCPriorityType::VeryLow)
+                                .build(),
+                        ],
+                    );
+
+                    let mut state = this.state.lock().unwrap();
+                    if let Some(session) = state.sessions.get_mut(&session_id) {
+                        session.thumbnail_channel = Some(channel);
+                    }
+                }
+
+                if do_clock_signalling {
+                    gst::info!(CAT, "Creating clock data channel");
+                    let channel = webrtcbin.emit_by_name::<WebRTCDataChannel>(
+                        "create-data-channel",
+                        &[&"clock", &None::<gst::Structure>],
+                    );
+
+                    channel.connect_on_open(glib::clone!(@weak element => move |channel| {
+                        element.imp().send_clock_info(channel);
+                    }));
                 }
 
                 // This is intentionally emitted with the pipeline in the Ready state,
@@ -2399,6 +5017,7 @@ impl BaseWebRTCSink {
                 //
                 // This is completely safe, as we know that by now all conditions are gathered:
                 // webrtcbin is in the Ready state, and all its transceivers have codec_preferences.
+                this.start_connect_timeout(&element, &session_id);
                 this.negotiate(&element, &session_id, offer_clone.as_ref());
 
                 if let Err(err) = pipeline.set_state(gst::State::Playing) {
@@ -2416,6 +5035,63 @@ impl BaseWebRTCSink {
         Ok(())
     }
 
+    /// Blocks until at least `n_consumers` sessions are connected, returns
+    /// false if that didn't happen within `timeout`
+    fn wait_for_consumers(&self, n_consumers: u32, timeout: Option<gst::ClockTime>) -> bool {
+        let connected_sessions = self.state.lock().unwrap().connected_sessions.clone();
+        let (sessions, cvar) = &*connected_sessions;
+        let sessions = sessions.lock().unwrap();
+
+        gst::debug!(
+            CAT,
+            imp: self,
+            "Waiting for {n_consumers} connected consumers, {} already are",
+            sessions.len()
+        );
+
+        let not_enough = |sessions: &mut HashSet<String>| sessions.len() < n_consumers as usize;
+
+        let sessions = match timeout {
+            Some(timeout) => {
+                cvar.wait_timeout_while(sessions, timeout.into(), not_enough)
+                    .unwrap()
+                    .0
+            }
+            None => cvar.wait_while(sessions, not_enough).unwrap(),
+        };
+
+        sessions.len() >= n_consumers as usize
+    }
+
+    /// Describes each input stream, for introspection by applications
+    fn streams_info(&self) -> gst::Array {
+        let state = self.state.lock().unwrap();
+        let connected_sessions = state.connected_sessions.0.lock().unwrap();
+
+        let mut streams = state.streams.iter().collect::<Vec<_>>();
+        streams.sort_by_key(|(name, _)| *name);
+
+        gst::Array::new(streams.into_iter().map(|(name, stream)| {
+            let n_connected_sessions = state
+                .sessions
+                .values()
+                .filter(|session| {
+                    session.negotiated_codecs.contains_key(name)
+                        && connected_sessions.contains(&session.id)
+                })
+                .count() as u32;
+
+            streams::stream_info(
+                name,
+                stream.serial,
+                stream.is_video,
+                stream.in_caps.as_ref(),
+                stream.out_caps.as_ref(),
+                n_connected_sessions,
+            )
+        }))
+    }
+
     /// Called by the signaller to remove a consumer
     fn remove_session(
         &self,
@@ -2423,9 +5099,7 @@ impl BaseWebRTCSink {
         session_id: &str,
         signal: bool,
     ) -> Result<(), WebRTCSinkError> {
-        let settings = self.settings.lock().unwrap();
-        let signaller = settings.signaller.clone();
-        drop(settings);
+        let signaller = self.session_signaller(session_id);
         let mut state = self.state.lock().unwrap();
 
         if !state.sessions.contains_key(session_id) {
@@ -2440,631 +5114,2044 @@ impl BaseWebRTCSink {
                 signaller.end_session(session_id);
             }
             element.emit_by_name::<()>("consumer-removed", &[&session.peer_id, &session.webrtcbin]);
+            self.release_session_signaller(session_id);
         }
 
         Ok(())
     }
 
-    fn process_loss_stats(
-        &self,
-        element: &super::BaseWebRTCSink,
-        session_id: &str,
-        stats: &gst::Structure,
-    ) {
-        let mut state = element.imp().state.lock().unwrap();
-        if let Some(session) = state.sessions.get_mut(session_id) {
-            if let Some(congestion_controller) = session.congestion_controller.as_mut() {
-                congestion_controller.loss_control(element, stats, &mut session.encoders);
-            }
-            session.stats = stats.to_owned();
+    /// Tears the session down unless it gets connected within the
+    /// connect-timeout, so that sessions with peers that went away before
+    /// completing ICE and DTLS don't hold on to their resources
+    fn start_connect_timeout(&self, element: &super::BaseWebRTCSink, session_id: &str) {
+        let connect_timeout = self.settings.lock().unwrap().connect_timeout;
+        if connect_timeout == 0 {
+            return;
         }
-    }
 
-    fn process_stats(
-        &self,
-        element: &super::BaseWebRTCSink,
-        webrtcbin: gst::Element,
-        session_id: &str,
-    ) {
+        let mut state = self.state.lock().unwrap();
+        let connected_sessions = state.connected_sessions.clone();
+        let Some(session) = state.sessions.get_mut(session_id) else {
+            return;
+        };
+
+        let peer_id = session.peer_id.clone();
+        let element = element.downgrade();
         let session_id = session_id.to_string();
-        let promise = gst::Promise::with_change_func(
-            glib::clone!(@strong session_id, @weak element => move |reply| {
-                if let Ok(Some(stats)) = reply {
+        session.connect_timeout_handle = Some(RUNTIME.spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(connect_timeout as u64)).await;
 
-                    let mut state = element.imp().state.lock().unwrap();
-                    if let Some(session) = state.sessions.get_mut(&session_id) {
-                        if let Some(congestion_controller) = session.congestion_controller.as_mut() {
-                            congestion_controller.delay_control(&element, stats, &mut session.encoders,);
-                        }
-                        session.stats = stats.to_owned();
-                    }
-                }
-            }),
-        );
+            let Some(element) = element.upgrade() else {
+                return;
+            };
 
-        webrtcbin.emit_by_name::<()>("get-stats", &[&None::<gst::Pad>, &promise]);
+            if connected_sessions.0.lock().unwrap().contains(&session_id) {
+                return;
+            }
+
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Session {session_id} (peer {peer_id}) didn't connect within {connect_timeout} ms, ending it"
+            );
+            gst::element_warning!(
+                element,
+                gst::ResourceError::Failed,
+                ["Session {session_id} with peer {peer_id} timed out while connecting"],
+                details: gst::Structure::builder("webrtcsink-connect-timeout")
+                    .field("session-id", &session_id)
+                    .field("peer-id", &peer_id)
+                    .field("timeout", connect_timeout)
+                    .build()
+            );
+
+            let _ = element.imp().remove_session(&element, &session_id, true);
+        }));
     }
 
-    fn set_rtptrxsend(
+    /// Called when the signaller ends a session, which is kept alive for
+    /// the session-resume-timeout in case the signaller requests it again,
+    /// for instance after reconnecting
+    fn on_session_ended(
         &self,
         element: &super::BaseWebRTCSink,
         session_id: &str,
-        rtprtxsend: gst::Element,
-    ) {
-        let mut state = element.imp().state.lock().unwrap();
-
-        if let Some(session) = state.sessions.get_mut(session_id) {
-            session.rtprtxsend = Some(rtprtxsend);
+    ) -> Result<(), WebRTCSinkError> {
+        let resume_timeout = self.settings.lock().unwrap().session_resume_timeout;
+        if resume_timeout == 0 {
+            return self.remove_session(element, session_id, false);
         }
-    }
 
-    fn set_bitrate(&self, element: &super::BaseWebRTCSink, session_id: &str, bitrate: u32) {
-        let settings = element.imp().settings.lock().unwrap();
-        let mut state = element.imp().state.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+        let Some(session) = state.sessions.get_mut(session_id) else {
+            return Err(WebRTCSinkError::NoSessionWithId(session_id.to_string()));
+        };
 
-        if let Some(session) = state.sessions.get_mut(session_id) {
-            let n_encoders = session.encoders.len();
+        if session.resume_timeout.is_some() {
+            return Ok(());
+        }
 
-            let fec_ratio = {
-                if settings.do_fec && bitrate > DO_FEC_THRESHOLD {
-                    (bitrate as f64 - DO_FEC_THRESHOLD as f64)
-                        / ((session.cc_info.max_bitrate as usize * n_encoders) as f64
-                            - DO_FEC_THRESHOLD as f64)
-                } else {
-                    0f64
-                }
+        gst::info!(
+            CAT,
+            obj: element,
+            "Session {session_id} ended, keeping it for {resume_timeout} ms in case it resumes"
+        );
+
+        let element = element.downgrade();
+        let session_id = session_id.to_string();
+        session.resume_timeout = Some(ResumeTimeout::new(resume_timeout, move || {
+            let Some(element) = element.upgrade() else {
+                return;
             };
 
-            let fec_percentage = fec_ratio * 50f64;
-            let encoders_bitrate =
-                ((bitrate as f64) / (1. + (fec_percentage / 100.)) / (n_encoders as f64)) as i32;
+            // Cancelling has no effect once the timeout expired, the session
+            // may have been resumed in the meantime
+            let timed_out = {
+                let mut state = element.imp().state.lock().unwrap();
+                state
+                    .sessions
+                    .get_mut(&session_id)
+                    .map_or(false, |session| session.resume_timeout.take().is_some())
+            };
 
-            if let Some(rtpxsend) = session.rtprtxsend.as_ref() {
-                rtpxsend.set_property("stuffing-kbps", (bitrate as f64 / 1000.) as i32);
+            if timed_out {
+                gst::info!(CAT, obj: element, "Session {session_id} wasn't resumed");
+                let _ = element.imp().remove_session(&element, &session_id, false);
             }
+        }));
 
-            for encoder in session.encoders.iter_mut() {
-                encoder.set_bitrate(element, encoders_bitrate);
-                encoder
-                    .transceiver
-                    .set_property("fec-percentage", (fec_percentage as u32).min(100));
-            }
-        }
+        Ok(())
     }
 
-    fn on_remote_description_set(&self, element: &super::BaseWebRTCSink, session_id: String) {
+    /// Re-attaches a session kept alive after the signaller ended it, and
+    /// sends the consumer a new offer over the signaller, returns whether
+    /// the session was resumed
+    fn resume_session(&self, session_id: &str, peer_id: &str, has_offer: bool) -> bool {
+        let element = self.obj();
         let mut state = self.state.lock().unwrap();
-        let mut remove = false;
-        let codecs = state.codecs.clone();
 
-        if let Some(mut session) = state.sessions.remove(&session_id) {
-            for webrtc_pad in session.webrtc_pads.clone().values() {
-                let transceiver = webrtc_pad
-                    .pad
-                    .property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
+        let Some(session) = state.sessions.get_mut(session_id) else {
+            return false;
+        };
 
-                let Some(ref stream_name) = webrtc_pad.stream_name else { continue; };
+        let Some(resume_timeout) = session.resume_timeout.take() else {
+            return false;
+        };
+        resume_timeout.cancel();
 
-                if let Some(mid) = transceiver.mid() {
-                    state.mids.insert(mid.to_string(), stream_name.clone());
-                }
+        // Only we can renegotiate with the consumer when we made the offer
+        let resumable = session.peer_id == peer_id && session.codecs.is_none() && !has_offer;
+        drop(state);
 
-                if let Some(producer) = state
-                    .streams
-                    .get(stream_name)
-                    .and_then(|stream| stream.producer.clone())
-                {
-                    drop(state);
-                    if let Err(err) =
-                        session.connect_input_stream(element, &producer, webrtc_pad, &codecs)
-                    {
-                        gst::error!(
+        if !resumable {
+            gst::info!(
+                CAT,
+                obj: element,
+                "Session {session_id} can't be resumed, starting it over"
+            );
+            let _ = self.remove_session(&element, session_id, false);
+            return false;
+        }
+
+        gst::info!(CAT, obj: element, "Resuming session {session_id}");
+
+        self.restart_ice(Some(session_id))
+    }
+
+    /// Returns the TURN servers to use for a session, with the credentials
+    /// provided by the application through the `request-turn-credentials`
+    /// signal if any. Must not be called with the settings or state locked.
+    fn turn_servers(&self, session_id: &str, peer_id: &str) -> Vec<String> {
+        let element = self.obj();
+        let credentials = turn::credentials(
+            &element,
+            element.emit_by_name::<Option<gst::Structure>>(
+                "request-turn-credentials",
+                &[&session_id, &peer_id],
+            ),
+        );
+
+        let settings = self.settings.lock().unwrap();
+
+        turn::with_credentials(&element, &settings.turn_servers, credentials.as_ref())
+    }
+
+    /// Hands the current TURN servers to the matching sessions and
+    /// renegotiates them with an ICE restart, returns whether at least one
+    /// session was restarted
+    fn restart_ice(&self, session_id: Option<&str>) -> bool {
+        let element = self.obj();
+        let sessions = {
+            let state = self.state.lock().unwrap();
+
+            state
+                .sessions
+                .values()
+                .filter(|session| session_id.map_or(true, |id| id == session.id))
+                .filter(|session| {
+                    // The remote offerer is in charge of renegotiating
+                    if session.codecs.is_some() {
+                        gst::warning!(
                             CAT,
-                            obj: element,
-                            "Failed to connect input stream {} for session {}: {}",
-                            stream_name,
-                            session_id,
-                            err
+                            imp: self,
+                            "Can't restart ICE for session {}, consumer made the offer",
+                            session.id
                         );
-                        remove = true;
-                        state = self.state.lock().unwrap();
-                        break;
+                        return false;
                     }
-                    state = self.state.lock().unwrap();
-                } else {
-                    gst::error!(
-                        CAT,
-                        obj: element,
-                        "No producer to connect session {} to",
-                        session_id,
-                    );
-                    remove = true;
-                    break;
-                }
+
+                    true
+                })
+                .map(|session| {
+                    (
+                        session.id.clone(),
+                        session.peer_id.clone(),
+                        session.webrtcbin.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut turn_servers_replaced = true;
+        for (session_id, peer_id, webrtcbin) in sessions.iter() {
+            gst::info!(CAT, imp: self, "Restarting ICE for session {}", session_id);
+
+            let turn_servers = self.turn_servers(session_id, peer_id);
+            {
+                let mut state = self.state.lock().unwrap();
+                let Some(session) = state.sessions.get_mut(session_id) else {
+                    continue;
+                };
+
+                turn_servers_replaced &=
+                    turn::set_servers(&element, webrtcbin, &turn_servers, &session.turn_servers);
+                session.turn_servers = turn_servers;
             }
 
-            session.pipeline.debug_to_dot_file_with_ts(
-                gst::DebugGraphDetails::all(),
-                format!("webrtcsink-peer-{session_id}-remote-description-set",),
+            Self::create_offer(
+                &element,
+                webrtcbin,
+                session_id,
+                Some(
+                    &gst::Structure::builder("options")
+                        .field("ice-restart", true)
+                        .build(),
+                ),
             );
+        }
 
-            let element_clone = element.downgrade();
-            let webrtcbin = session.webrtcbin.downgrade();
-            let session_id_clone = session_id.clone();
-            session.stats_collection_handle = Some(RUNTIME.spawn(async move {
-                let mut interval = tokio::time::interval(std::time::Duration::from_millis(100));
-
-                loop {
-                    interval.tick().await;
-                    let element_clone = element_clone.clone();
-                    if let (Some(webrtcbin), Some(element)) =
-                        (webrtcbin.upgrade(), element_clone.upgrade())
-                    {
-                        element
-                            .imp()
-                            .process_stats(&element, webrtcbin, &session_id_clone);
-                    } else {
-                        break;
-                    }
-                }
-            }));
-
-            if remove {
-                state.finalize_session(&mut session);
-                drop(state);
-                let settings = self.settings.lock().unwrap();
-                let signaller = settings.signaller.clone();
-                drop(settings);
-                signaller.end_session(&session_id);
-            } else {
-                state.sessions.insert(session.id.clone(), session);
-            }
-        }
+        !sessions.is_empty() && turn_servers_replaced
     }
 
-    /// Called by the signaller with an ice candidate
-    fn handle_ice(
-        &self,
-        session_id: &str,
-        sdp_m_line_index: Option<u32>,
-        _sdp_mid: Option<String>,
-        candidate: &str,
-    ) {
-        let state = self.state.lock().unwrap();
+    /// Lets media flow to a session held by require-approval, returns
+    /// whether the session was awaiting approval
+    fn approve_session(&self, session_id: &str) -> bool {
+        {
+            let mut state = self.state.lock().unwrap();
 
-        let sdp_m_line_index = match sdp_m_line_index {
-            Some(sdp_m_line_index) => sdp_m_line_index,
-            None => {
-                gst::warning!(CAT, "No mandatory SDP m-line index");
-                return;
+            let Some(session) = state.sessions.get_mut(session_id) else {
+                gst::warning!(CAT, imp: self, "No session with id {session_id}");
+                return false;
+            };
+
+            if !session.awaiting_approval {
+                gst::warning!(CAT, imp: self, "Session {session_id} isn't awaiting approval");
+                return false;
             }
-        };
 
-        if let Some(session) = state.sessions.get(session_id) {
-            gst::trace!(CAT, "adding ice candidate for session {}", session_id);
-            session
-                .webrtcbin
-                .emit_by_name::<()>("add-ice-candidate", &[&sdp_m_line_index, &candidate]);
-        } else {
-            gst::warning!(CAT, "No consumer with ID {session_id}");
+            gst::info!(CAT, imp: self, "Session {session_id} approved");
+            session.awaiting_approval = false;
         }
+
+        self.set_session_paused(session_id, false)
     }
 
-    fn handle_sdp_answer(
-        &self,
-        element: &super::BaseWebRTCSink,
-        session_id: &str,
-        desc: &gst_webrtc::WebRTCSessionDescription,
-    ) {
+    /// Stops or restarts feeding a session, without tearing down its
+    /// connection, returns whether the session was found
+    fn set_session_paused(&self, session_id: &str, paused: bool) -> bool {
         let mut state = self.state.lock().unwrap();
 
-        if let Some(session) = state.sessions.get_mut(session_id) {
-            let sdp = desc.sdp();
+        let Some(session) = state.sessions.get_mut(session_id) else {
+            gst::warning!(CAT, imp: self, "No session with id {session_id}");
+            return false;
+        };
 
-            session.sdp = Some(sdp.to_owned());
+        if session.awaiting_approval && !paused {
+            gst::warning!(
+                CAT,
+                imp: self,
+                "Session {session_id} can't be resumed before it is approved"
+            );
+            return false;
+        }
 
-            for webrtc_pad in session.webrtc_pads.values_mut() {
-                let media_idx = webrtc_pad.media_idx;
-                /* TODO: support partial answer, webrtcbin doesn't seem
-                 * very well equipped to deal with this at the moment */
-                if let Some(media) = sdp.media(media_idx) {
-                    if media.attribute_val("inactive").is_some() {
-                        let media_str = sdp
-                            .media(webrtc_pad.media_idx)
-                            .and_then(|media| media.as_text().ok());
+        if session.paused == paused {
+            return true;
+        }
 
-                        gst::warning!(
-                            CAT,
-                            "consumer from session {} refused media {}: {:?}",
-                            session_id,
-                            media_idx,
-                            media_str
-                        );
-                        if let Some(_session) = state.end_session(session_id) {
-                            drop(state);
-                            let settings = self.settings.lock().unwrap();
-                            let signaller = settings.signaller.clone();
-                            drop(settings);
-                            signaller.end_session(session_id);
-                        }
+        gst::info!(
+            CAT,
+            imp: self,
+            "{} session {session_id}",
+            if paused { "Pausing" } else { "Resuming" }
+        );
 
-                        gst::warning!(
-                            CAT,
-                            obj: element,
-                            "Consumer refused media {session_id}, {media_idx}"
-                        );
-                        return;
-                    }
-                }
+        session.paused = paused;
 
-                if let Some(payload) = sdp
-                    .media(webrtc_pad.media_idx)
-                    .and_then(|media| media.format(0))
-                    .and_then(|format| format.parse::<i32>().ok())
-                {
-                    webrtc_pad.payload = Some(payload);
-                } else {
-                    gst::warning!(
-                        CAT,
-                        "consumer from session {} did not provide valid payload for media index {} for session {}",
-                        session_id,
-                        media_idx,
-                        session_id,
-                    );
+        for link in session.links.values() {
+            link.set_discard(paused);
+        }
 
-                    if let Some(_session) = state.end_session(session_id) {
-                        drop(state);
-                        let settings = self.settings.lock().unwrap();
-                        let signaller = settings.signaller.clone();
-                        drop(settings);
-                        signaller.end_session(session_id);
-                    }
+        let direction = if paused {
+            gst_webrtc::WebRTCRTPTransceiverDirection::Inactive
+        } else {
+            gst_webrtc::WebRTCRTPTransceiverDirection::Sendonly
+        };
 
-                    gst::warning!(CAT, obj: element, "Consumer did not provide valid payload for media session: {session_id} media_ix: {media_idx}");
-                    return;
-                }
+        for webrtc_pad in session.webrtc_pads.values() {
+            if webrtc_pad.stream_name.is_none() {
+                continue;
             }
 
-            let element = element.downgrade();
-            let session_id = session_id.to_string();
+            webrtc_pad
+                .pad
+                .property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver")
+                .set_property("direction", direction);
+        }
 
-            let promise = gst::Promise::with_change_func(move |reply| {
-                gst::debug!(CAT, "received reply {:?}", reply);
-                if let Some(element) = element.upgrade() {
-                    let this = element.imp();
+        drop(state);
 
-                    this.on_remote_description_set(&element, session_id);
-                }
-            });
+        self.renegotiate(session_id);
 
-            session
-                .webrtcbin
-                .emit_by_name::<()>("set-remote-description", &[desc, &promise]);
-        } else {
-            gst::warning!(CAT, "No consumer with ID {session_id}");
+        if !paused {
+            // Let the consumer resume decoding as soon as possible
+            self.force_keyframe(Some(session_id), None);
         }
+
+        true
     }
 
-    async fn run_discovery_pipeline(
-        element: &super::BaseWebRTCSink,
-        stream_name: &str,
-        discovery_info: &DiscoveryInfo,
-        codec: Codec,
-        input_caps: gst::Caps,
-        output_caps: &gst::Caps,
-        twcc: Option<u32>,
-    ) -> Result<gst::Structure, Error> {
-        let pipe = PipelineWrapper(gst::Pipeline::default());
+    /// Sends a new offer to the consumer of a session, so that it learns
+    /// about changes to the direction of its transceivers
+    fn renegotiate(&self, session_id: &str) {
+        let webrtcbin = {
+            let state = self.state.lock().unwrap();
 
-        let has_raw_input = is_raw_caps(&input_caps);
-        let src = discovery_info.create_src();
-        let mut elements = vec![src.clone().upcast::<gst::Element>()];
-        let encoding_chain_src = if codec.is_video() && has_raw_input {
-            elements.push(make_converter_for_video_caps(&input_caps, &codec)?);
+            let Some(session) = state.sessions.get(session_id) else {
+                return;
+            };
 
-            let capsfilter = make_element("capsfilter", Some("raw_capsfilter"))?;
-            elements.push(capsfilter.clone());
+            // The remote offerer is in charge of renegotiating
+            if session.codecs.is_some() {
+                gst::info!(
+                    CAT,
+                    imp: self,
+                    "Consumer made the offer, session {session_id} changes apply on its next offer"
+                );
+                return;
+            }
 
-            capsfilter
-        } else {
-            src.clone().upcast::<gst::Element>()
+            session.webrtcbin.clone()
         };
 
-        gst::debug!(
-            CAT,
-            obj: element,
-            "Running discovery pipeline for input caps {input_caps} and output caps {output_caps} with codec {codec:?}"
-        );
-
-        gst::debug!(CAT, obj: element, "Running discovery pipeline");
-        let elements_slice = &elements.iter().collect::<Vec<_>>();
-        pipe.0.add_many(elements_slice).unwrap();
-        gst::Element::link_many(elements_slice)
-            .with_context(|| format!("Running discovery pipeline for caps {input_caps}"))?;
-
-        let mut encoding_chain_builder = EncodingChainBuilder::new(
-            &src.caps()
-                .expect("Caps should always be set when starting discovery"),
-            output_caps,
-            &codec,
-            element.emit_by_name::<Option<gst::Element>>(
-                "request-encoded-filter",
-                &[&Option::<String>::None, &stream_name, &codec.caps],
-            ),
-        );
-        if let Some(twcc) = twcc {
-            encoding_chain_builder = encoding_chain_builder.twcc(twcc)
-        }
-        let encoding_chain = encoding_chain_builder.build(&pipe.0, &encoding_chain_src)?;
-
-        if let Some(ref enc) = encoding_chain.encoder {
-            element.emit_by_name::<bool>(
-                "encoder-setup",
-                &[&"discovery".to_string(), &stream_name, &enc],
+        let signaling_state =
+            webrtcbin.property::<gst_webrtc::WebRTCSignalingState>("signaling-state");
+        if signaling_state != gst_webrtc::WebRTCSignalingState::Stable {
+            gst::warning!(
+                CAT,
+                imp: self,
+                "Not renegotiating session {session_id} in signaling state {signaling_state:?}"
             );
+            return;
         }
 
-        let sink = gst_app::AppSink::builder()
-            .callbacks(
-                gst_app::AppSinkCallbacks::builder()
-                    .new_event(|sink| {
-                        let obj = sink.pull_object().ok();
-                        if let Some(event) = obj.and_then(|o| o.downcast::<gst::Event>().ok()) {
-                            if let gst::EventView::Caps(caps) = event.view() {
-                                sink.post_message(gst::message::Application::new(
-                                    gst::Structure::builder("payloaded_caps")
-                                        .field("caps", &caps.caps().to_owned())
-                                        .build(),
-                                ))
-                                .expect("Could not send message");
-                            }
-                        }
+        gst::info!(CAT, imp: self, "Renegotiating session {session_id}");
 
-                        true
-                    })
-                    .build(),
-            )
-            .build();
-        pipe.0.add(sink.upcast_ref::<gst::Element>()).unwrap();
-        encoding_chain
-            .pay_filter
-            .link(&sink)
-            .with_context(|| format!("Running discovery pipeline for caps {input_caps}"))?;
+        Self::create_offer(&self.obj(), &webrtcbin, session_id, None);
+    }
 
-        let mut stream = pipe.0.bus().unwrap().stream();
+    /// Requests a keyframe from the encoders of the matching sessions and
+    /// streams, returns whether at least one stream was found
+    fn force_keyframe(&self, session_id: Option<&str>, stream_name: Option<&str>) -> bool {
+        // Upstream may call back into us while handling the event, only
+        // send it once the state is unlocked
+        let srcpads = {
+            let state = self.state.lock().unwrap();
+            let mut srcpads = Vec::new();
 
-        pipe.0
-            .set_state(gst::State::Playing)
-            .with_context(|| format!("Running discovery pipeline for caps {input_caps}"))?;
+            for session in state
+                .sessions
+                .values()
+                .filter(|session| session_id.map_or(true, |id| id == session.id))
+            {
+                for webrtc_pad in session.webrtc_pads.values() {
+                    let Some(ref pad_stream_name) = webrtc_pad.stream_name else {
+                        continue;
+                    };
 
-        while let Some(msg) = stream.next().await {
-            match msg.view() {
-                gst::MessageView::Error(err) => {
-                    gst::error!(CAT, "Error in discovery pipeline: {err:#?}");
-                    pipe.0.debug_to_dot_file_with_ts(
-                        gst::DebugGraphDetails::all(),
-                        "webrtcsink-discovery-error",
-                    );
-                    return Err(err.error().into());
-                }
-                gst::MessageView::StateChanged(s) => {
-                    if msg.src() == Some(pipe.0.upcast_ref()) {
-                        pipe.0.debug_to_dot_file_with_ts(
-                            gst::DebugGraphDetails::all(),
-                            format!(
-                                "webrtcsink-discovery-{}-{:?}-{:?}",
-                                pipe.0.name(),
-                                s.old(),
-                                s.current()
-                            ),
+                    if stream_name.map_or(false, |name| name != pad_stream_name.as_str()) {
+                        continue;
+                    }
+
+                    if let Some(srcpad) = webrtc_pad.pad.peer() {
+                        gst::debug!(
+                            CAT,
+                            imp: self,
+                            "Forcing keyframe for stream {} in session {}",
+                            pad_stream_name,
+                            session.id
                         );
+
+                        srcpads.push(srcpad);
                     }
-                    continue;
                 }
-                gst::MessageView::Application(appmsg) => {
-                    let caps = match appmsg.structure() {
-                        Some(s) => {
-                            if s.name().as_str() != "payloaded_caps" {
-                                continue;
-                            }
+            }
 
-                            s.get::<gst::Caps>("caps").unwrap()
-                        }
-                        _ => continue,
-                    };
+            srcpads
+        };
 
-                    gst::info!(CAT, "Discovery pipeline got caps {caps:?}");
-                    pipe.0.debug_to_dot_file_with_ts(
-                        gst::DebugGraphDetails::all(),
-                        "webrtcsink-discovery-done",
-                    );
+        for srcpad in srcpads.iter() {
+            srcpad.send_event(
+                gst_video::UpstreamForceKeyUnitEvent::builder()
+                    .all_headers(true)
+                    .build(),
+            );
+        }
 
-                    if let Some(s) = caps.structure(0) {
-                        let mut s = s.to_owned();
-                        s.remove_fields([
-                            "timestamp-offset",
-                            "seqnum-offset",
-                            "ssrc",
-                            "sprop-parameter-sets",
-                            "a-framerate",
-                        ]);
-                        s.set("payload", codec.payload().unwrap());
-                        gst::debug!(
-                            CAT,
-                            obj: element,
-                            "Codec discovery pipeline for caps {input_caps} with codec {codec:?} succeeded: {s}"
-                        );
-                        return Ok(s);
-                    } else {
-                        return Err(anyhow!("Discovered empty caps"));
-                    }
+        !srcpads.is_empty()
+    }
+
+    fn process_loss_stats(
+        &self,
+        element: &super::BaseWebRTCSink,
+        session_id: &str,
+        stats: &gst::Structure,
+    ) {
+        let mut state = element.imp().state.lock().unwrap();
+        if let Some(session) = state.sessions.get_mut(session_id) {
+            let rtx_bitrate = session.update_rtx_bitrate();
+            let audio_bitrate = session
+                .congestion_controller
+                .as_ref()
+                .map_or(0, |cc| session.audio_bitrate(cc.target_bitrate()));
+            if let Some(congestion_controller) = session.congestion_controller.as_mut() {
+                congestion_controller.set_rtx_bitrate(rtx_bitrate);
+                congestion_controller.set_audio_bitrate(audio_bitrate);
+                congestion_controller.loss_control(element, stats, &mut session.encoders);
+
+                let bitrate = congestion_controller.target_bitrate();
+                for encoder in session.audio_encoders.iter_mut() {
+                    encoder.set_bitrate(element, bitrate);
                 }
-                _ => {
+            }
+            session.update_congestion_controller_ceiling();
+            session.stats = stats.to_owned();
+        }
+    }
+
+    /// Periodically requests the stats of `session`, each reply is stored
+    /// and runs the homegrown congestion controller when either is due, at
+    /// their own interval. None if neither needs the stats.
+    fn spawn_stats_task(
+        &self,
+        element: &super::BaseWebRTCSink,
+        session: &Session,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let collection_interval = session.stats_collection_interval;
+        let control_interval = if session.congestion_controller.is_some() {
+            session.cc_info.homegrown.update_interval
+        } else {
+            0
+        };
+
+        // Ticks at the shortest interval, the other one is rounded to a
+        // multiple of it
+        let period = [collection_interval, control_interval]
+            .into_iter()
+            .filter(|interval| *interval > 0)
+            .min()?;
+        let ticks = |interval: u32| ((interval + period / 2) / period) as u64;
+        let (collection_ticks, control_ticks) =
+            (ticks(collection_interval), ticks(control_interval));
+
+        let element_clone = element.downgrade();
+        let webrtcbin = session.webrtcbin.downgrade();
+        let session_id_clone = session.id.clone();
+        let stats_pending = Arc::new(AtomicBool::new(false));
+        Some(RUNTIME.spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_millis(period as u64));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+            let (mut collect, mut control) = (false, false);
+            for tick in 0u64.. {
+                interval.tick().await;
+
+                collect |= collection_ticks > 0 && tick % collection_ticks == 0;
+                control |= control_ticks > 0 && tick % control_ticks == 0;
+
+                // Coalesce requests, webrtcbin may take longer than the
+                // interval to answer with many consumers. What was due is
+                // served by the next request.
+                if !(collect || control) || stats_pending.swap(true, Ordering::SeqCst) {
                     continue;
                 }
+
+                let element_clone = element_clone.clone();
+                if let (Some(webrtcbin), Some(element)) =
+                    (webrtcbin.upgrade(), element_clone.upgrade())
+                {
+                    element.imp().process_stats(
+                        &element,
+                        webrtcbin,
+                        &session_id_clone,
+                        stats_pending.clone(),
+                        std::mem::take(&mut collect),
+                        std::mem::take(&mut control),
+                    );
+                } else {
+                    break;
+                }
+            }
+        }))
+    }
+
+    fn process_stats(
+        &self,
+        element: &super::BaseWebRTCSink,
+        webrtcbin: gst::Element,
+        session_id: &str,
+        stats_pending: Arc<AtomicBool>,
+        collect: bool,
+        control: bool,
+    ) {
+        let session_id = session_id.to_string();
+        let promise = gst::Promise::with_change_func(
+            glib::clone!(@strong session_id, @weak element => move |reply| {
+                stats_pending.store(false, Ordering::SeqCst);
+
+                if let Ok(Some(stats)) = reply {
+
+                    let mut state = element.imp().state.lock().unwrap();
+                    if let Some(session) = state.sessions.get_mut(&session_id) {
+                        if collect {
+                            session.stats = stats.to_owned();
+                        }
+
+                        if !control {
+                            return;
+                        }
+
+                        let rtx_bitrate = session.update_rtx_bitrate();
+                        let audio_bitrate = session
+                            .congestion_controller
+                            .as_ref()
+                            .map_or(0, |cc| session.audio_bitrate(cc.target_bitrate()));
+                        if let Some(congestion_controller) = session.congestion_controller.as_mut() {
+                            congestion_controller.set_rtx_bitrate(rtx_bitrate);
+                            congestion_controller.set_audio_bitrate(audio_bitrate);
+                            congestion_controller.delay_control(&element, stats, &mut session.encoders,);
+
+                            let bitrate = congestion_controller.target_bitrate();
+                            for encoder in session.audio_encoders.iter_mut() {
+                                encoder.set_bitrate(&element, bitrate);
+                            }
+                        }
+                        session.update_congestion_controller_ceiling();
+                    }
+                }
+            }),
+        );
+
+        webrtcbin.emit_by_name::<()>("get-stats", &[&None::<gst::Pad>, &promise]);
+    }
+
+    fn configure_rtprtxsend(&self, rtprtxsend: &gst::Element) {
+        let settings = self.settings.lock().unwrap();
+        rtprtxsend.set_property("max-size-time", settings.rtx_max_size_time);
+        rtprtxsend.set_property("max-size-packets", settings.rtx_max_size_packets);
+    }
+
+    /// Applies the retransmission history settings to running sessions
+    fn configure_retransmission(&self) {
+        let webrtcbins = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .filter_map(|session| session.webrtcbin.clone().downcast::<gst::Bin>().ok())
+            .collect::<Vec<_>>();
+
+        for webrtcbin in webrtcbins {
+            for rtprtxsend in webrtcbin
+                .iterate_all_by_element_factory_name("rtprtxsend")
+                .into_iter()
+                .flatten()
+            {
+                self.configure_rtprtxsend(&rtprtxsend);
             }
         }
+    }
 
-        unreachable!()
+    fn set_rtptrxsend(
+        &self,
+        element: &super::BaseWebRTCSink,
+        session_id: &str,
+        rtprtxsend: gst::Element,
+    ) {
+        let mut state = element.imp().state.lock().unwrap();
+
+        if let Some(session) = state.sessions.get_mut(session_id) {
+            session.rtprtxsend = Some(rtprtxsend);
+        }
     }
 
-    async fn lookup_caps(
+    /// Called once the DTLS handshake configured the SRTP keys of one of the
+    /// transports of a session, tears down the session if the negotiated
+    /// protection profile isn't allowed
+    fn on_srtp_keys_set(
+        &self,
         element: &super::BaseWebRTCSink,
-        discovery_info: DiscoveryInfo,
-        name: String,
-        output_caps: gst::Caps,
-        codecs: &Codecs,
-    ) -> Result<(), Error> {
-        let futs = if let Some(codec) = codecs.find_for_encoded_caps(&discovery_info.caps) {
-            let mut caps = discovery_info.caps.clone();
+        session_id: &str,
+        srtpenc: &gst::Element,
+    ) {
+        let nick = |property: &str| {
+            glib::EnumValue::from_value(&srtpenc.property_value(property))
+                .map(|(_, value)| value.nick().to_string())
+                .unwrap_or_default()
+        };
+        let (cipher, auth) = (nick("rtp-cipher"), nick("rtp-auth"));
 
-            gst::info!(
+        if cipher == "null" {
+            return;
+        }
+
+        let profile = srtp_profile_name(&cipher, &auth);
+        let allowed = {
+            let settings = self.settings.lock().unwrap();
+            settings.srtp_profiles.is_empty() || settings.srtp_profiles.contains(&profile)
+        };
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "Session {session_id} negotiated SRTP profile {profile}"
+        );
+
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(session) = state.sessions.get_mut(session_id) {
+            session.srtp_profile = Some(profile.clone());
+        }
+
+        drop(state);
+
+        if !allowed {
+            gst::warning!(
                 CAT,
                 obj: element,
-                "Stream is already encoded with codec {}, still need to payload it",
-                codec.name
+                "SRTP profile {profile} of session {session_id} is not allowed, ending session"
             );
+            let _ = self.remove_session(element, session_id, true);
+        }
+    }
 
-            caps = cleanup_codec_caps(caps);
+    /// Called once the DTLS handshake of a session completed, records the
+    /// parameters of the DTLS connection and lets the application know
+    fn on_session_connected(
+        &self,
+        element: &super::BaseWebRTCSink,
+        session_id: &str,
+        webrtcbin: &gst::Element,
+    ) {
+        let description = |property: &str| {
+            webrtcbin
+                .property::<Option<gst_webrtc::WebRTCSessionDescription>>(property)
+                .map(|description| description.sdp())
+        };
+        let (Some(local), Some(remote)) = (
+            description("local-description"),
+            description("remote-description"),
+        ) else {
+            return;
+        };
 
-            vec![BaseWebRTCSink::run_discovery_pipeline(
-                element,
-                &name,
-                &discovery_info,
-                codec,
-                caps,
-                &output_caps,
-                Some(1),
-            )]
-        } else {
-            let sink_caps = discovery_info.caps.clone();
+        let local_setup = sdp_dtls_attribute(&local, "setup");
+        let remote_setup = sdp_dtls_attribute(&remote, "setup");
+        let role = dtls_role(local_setup.as_deref(), remote_setup.as_deref());
 
-            let is_video = match sink_caps.structure(0).unwrap().name().as_str() {
-                "video/x-raw" => true,
-                "audio/x-raw" => false,
-                _ => unreachable!(),
-            };
+        let mut info = gst::Structure::builder("application/x-webrtcsink-dtls-info")
+            .field("role", role)
+            .field(
+                "local-fingerprint",
+                sdp_dtls_attribute(&local, "fingerprint").unwrap_or_default(),
+            )
+            .field(
+                "remote-fingerprint",
+                sdp_dtls_attribute(&remote, "fingerprint").unwrap_or_default(),
+            )
+            .build();
 
-            codecs
-                .iter()
-                .filter(|codec| codec.is_video() == is_video)
-                .map(|codec| {
-                    BaseWebRTCSink::run_discovery_pipeline(
-                        element,
-                        &name,
-                        &discovery_info,
-                        codec.clone(),
-                        sink_caps.clone(),
-                        &output_caps,
-                        Some(1),
-                    )
-                })
-                .collect()
+        let mut state = self.state.lock().unwrap();
+        let Some(session) = state.sessions.get_mut(session_id) else {
+            return;
         };
 
-        let mut payloader_caps = gst::Caps::new_empty();
-        let payloader_caps_mut = payloader_caps.make_mut();
+        if let Some(handle) = session.connect_timeout_handle.take() {
+            handle.abort();
+        }
 
-        for ret in futures::future::join_all(futs).await {
-            match ret {
-                Ok(s) => {
-                    payloader_caps_mut.append_structure(s);
-                }
-                Err(err) => {
-                    /* We don't consider this fatal, as long as we end up with one
-                     * potential codec for each input stream
-                     */
-                    gst::warning!(
-                        CAT,
-                        obj: element,
-                        "Codec discovery pipeline failed: {}",
-                        err
-                    );
+        // Renegotiations and ICE restarts don't redo the handshake
+        if session.dtls_info.is_some() {
+            return;
+        }
+
+        if let Some(ice_connected_at) = session.ice_connected_at {
+            let duration =
+                gst::ClockTime::from_nseconds(ice_connected_at.elapsed().as_nanos() as u64);
+            info.set("handshake-duration", duration);
+        }
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "Session {session_id} connected, DTLS parameters: {info}"
+        );
+
+        session.dtls_info = Some(info.clone());
+        drop(state);
+
+        element.emit_by_name::<()>("session-connected", &[&session_id, &info]);
+    }
+
+    fn set_bitrate(&self, element: &super::BaseWebRTCSink, session_id: &str, bitrate: u32) {
+        let settings = element.imp().settings.lock().unwrap();
+        let mut state = element.imp().state.lock().unwrap();
+
+        if let Some(session) = state.sessions.get_mut(session_id) {
+            let n_encoders = session.encoders.len();
+
+            // The signaller and the bandwidth group may cap the bitrate
+            // below the estimate
+            let bitrate = session
+                .ceiling(bitrate)
+                .map_or(bitrate, |ceiling| bitrate.min(ceiling));
+
+            // Retransmissions are not accounted for by the encoders, leave
+            // room for them in the estimated bitrate
+            let rtx_bitrate = session.update_rtx_bitrate();
+            // Audio takes its share of the estimate off the top
+            let audio_bitrate = session.audio_bitrate(bitrate as i32);
+            let video_bitrate = bitrate
+                .saturating_sub(rtx_bitrate)
+                .saturating_sub(audio_bitrate)
+                .max(session.cc_info.min_bitrate * n_encoders as u32);
+
+            let fec_ratio = {
+                if settings.do_fec && bitrate > DO_FEC_THRESHOLD {
+                    (bitrate as f64 - DO_FEC_THRESHOLD as f64)
+                        / ((session.cc_info.max_bitrate as usize * n_encoders) as f64
+                            - DO_FEC_THRESHOLD as f64)
+                } else {
+                    0f64
                 }
+            };
+
+            let fec_percentage = fec_ratio * 50f64;
+            let encoders_bitrate = ((video_bitrate as f64)
+                / (1. + (fec_percentage / 100.))
+                / (n_encoders as f64)) as i32;
+
+            if let Some(rtpxsend) = session.rtprtxsend.as_ref() {
+                rtpxsend.set_property("stuffing-kbps", (bitrate as f64 / 1000.) as i32);
+            }
+
+            for encoder in session.encoders.iter_mut() {
+                encoder.set_bitrate(element, encoders_bitrate);
+                encoder
+                    .transceiver
+                    .set_property("fec-percentage", (fec_percentage as u32).min(100));
+            }
+
+            for encoder in session.audio_encoders.iter_mut() {
+                encoder.set_bitrate(element, bitrate as i32);
+            }
+        }
+    }
+
+    /// Caps the bitrate of a session on top of congestion control, 0 lifts
+    /// the cap
+    fn set_bitrate_ceiling(&self, element: &super::BaseWebRTCSink, session_id: &str, bitrate: u32) {
+        let ceiling = (bitrate > 0).then_some(bitrate);
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "Setting bitrate ceiling of session {session_id} to {ceiling:?}"
+        );
+
+        let mut state = self.state.lock().unwrap();
+        let Some(session) = state.sessions.get_mut(session_id) else {
+            gst::warning!(CAT, obj: element, "No session {session_id} to cap the bitrate of");
+            return;
+        };
+
+        session.bitrate_ceiling = ceiling;
+
+        if session.congestion_controller.is_some() {
+            // Picked up by the next control operation
+            session.update_congestion_controller_ceiling();
+        } else if let Some(rtpgccbwe) = session.rtpgccbwe.clone() {
+            drop(state);
+
+            // Don't wait for the estimate to change
+            self.set_bitrate(
+                element,
+                session_id,
+                rtpgccbwe.property::<u32>("estimated-bitrate"),
+            );
+        } else {
+            let bitrate = session.uncontrolled_bitrate(session.encoders.len());
+            for encoder in session.encoders.iter_mut() {
+                encoder.set_bitrate(element, bitrate);
+            }
+        }
+    }
+
+    fn on_remote_description_set(&self, element: &super::BaseWebRTCSink, session_id: String) {
+        let mut state = self.state.lock().unwrap();
+        let mut remove = false;
+        let codecs = state.codecs.clone();
+
+        if let Some(mut session) = state.sessions.remove(&session_id) {
+            for webrtc_pad in session.webrtc_pads.clone().values() {
+                let transceiver = webrtc_pad
+                    .pad
+                    .property::<gst_webrtc::WebRTCRTPTransceiver>("transceiver");
+
+                let Some(ref stream_name) = webrtc_pad.stream_name else { continue; };
+
+                if let Some(mid) = transceiver.mid() {
+                    state.mids.insert(mid.to_string(), stream_name.clone());
+                }
+
+                // Already connected by a previous negotiation
+                if session.links.contains_key(&webrtc_pad.ssrc) {
+                    continue;
+                }
+
+                if let Some(producer) = state
+                    .streams
+                    .get(stream_name)
+                    .and_then(|stream| stream.producer.clone())
+                {
+                    drop(state);
+                    if let Err(err) =
+                        session.connect_input_stream(element, &producer, webrtc_pad, &codecs)
+                    {
+                        gst::error!(
+                            CAT,
+                            obj: element,
+                            "Failed to connect input stream {} for session {}: {}",
+                            stream_name,
+                            session_id,
+                            err
+                        );
+                        remove = true;
+                        state = self.state.lock().unwrap();
+                        break;
+                    }
+                    state = self.state.lock().unwrap();
+                } else {
+                    gst::error!(
+                        CAT,
+                        obj: element,
+                        "No producer to connect session {} to",
+                        session_id,
+                    );
+                    remove = true;
+                    break;
+                }
+            }
+
+            session.pipeline.debug_to_dot_file_with_ts(
+                gst::DebugGraphDetails::all(),
+                format!("webrtcsink-peer-{session_id}-remote-description-set",),
+            );
+
+            if session.stats_collection_handle.is_none() {
+                session.stats_collection_handle = self.spawn_stats_task(element, &session);
+            }
+
+            if remove {
+                state.finalize_session(&mut session);
+                drop(state);
+                self.session_signaller(&session_id).end_session(&session_id);
+                self.release_session_signaller(&session_id);
+            } else {
+                state.sessions.insert(session.id.clone(), session);
+            }
+        }
+    }
+
+    /// Called by the signaller with an ice candidate
+    fn handle_ice(
+        &self,
+        session_id: &str,
+        sdp_m_line_index: Option<u32>,
+        _sdp_mid: Option<String>,
+        candidate: &str,
+    ) {
+        let settings = self.settings.lock().unwrap();
+        let candidate = if settings.ice_candidate_type_order.is_empty() {
+            candidate.to_string()
+        } else {
+            prioritize_ice_candidate(candidate, &settings.ice_candidate_type_order)
+                .unwrap_or_else(|| candidate.to_string())
+        };
+        drop(settings);
+
+        let state = self.state.lock().unwrap();
+
+        let sdp_m_line_index = match sdp_m_line_index {
+            Some(sdp_m_line_index) => sdp_m_line_index,
+            None => {
+                gst::warning!(CAT, "No mandatory SDP m-line index");
+                return;
+            }
+        };
+
+        if let Some(session) = state.sessions.get(session_id) {
+            gst::trace!(CAT, "adding ice candidate for session {}", session_id);
+            session
+                .webrtcbin
+                .emit_by_name::<()>("add-ice-candidate", &[&sdp_m_line_index, &candidate]);
+        } else {
+            gst::warning!(CAT, "No consumer with ID {session_id}");
+        }
+    }
+
+    fn handle_sdp_answer(
+        &self,
+        element: &super::BaseWebRTCSink,
+        session_id: &str,
+        desc: &gst_webrtc::WebRTCSessionDescription,
+    ) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(session) = state.sessions.get_mut(session_id) {
+            let sdp = desc.sdp();
+
+            session.sdp = Some(sdp.to_owned());
+
+            for webrtc_pad in session.webrtc_pads.values_mut() {
+                let media_idx = webrtc_pad.media_idx;
+                /* TODO: support partial answer, webrtcbin doesn't seem
+                 * very well equipped to deal with this at the moment */
+                if let Some(media) = sdp.media(media_idx) {
+                    // Released or paused streams are expected to be
+                    // inactive when renegotiating
+                    if media.attribute_val("inactive").is_some()
+                        && webrtc_pad.stream_name.is_some()
+                        && !session.paused
+                    {
+                        let media_str = sdp
+                            .media(webrtc_pad.media_idx)
+                            .and_then(|media| media.as_text().ok());
+
+                        gst::warning!(
+                            CAT,
+                            "consumer from session {} refused media {}: {:?}",
+                            session_id,
+                            media_idx,
+                            media_str
+                        );
+                        if let Some(_session) = state.end_session(session_id) {
+                            drop(state);
+                            self.session_signaller(session_id).end_session(session_id);
+                            self.release_session_signaller(session_id);
+                        }
+
+                        gst::warning!(
+                            CAT,
+                            obj: element,
+                            "Consumer refused media {session_id}, {media_idx}"
+                        );
+                        return;
+                    }
+                }
+
+                if let Some(payload) = sdp
+                    .media(webrtc_pad.media_idx)
+                    .and_then(|media| media.format(0))
+                    .and_then(|format| format.parse::<i32>().ok())
+                {
+                    webrtc_pad.payload = Some(payload);
+                } else {
+                    gst::warning!(
+                        CAT,
+                        "consumer from session {} did not provide valid payload for media index {} for session {}",
+                        session_id,
+                        media_idx,
+                        session_id,
+                    );
+
+                    if let Some(_session) = state.end_session(session_id) {
+                        drop(state);
+                        self.session_signaller(session_id).end_session(session_id);
+                        self.release_session_signaller(session_id);
+                    }
+
+                    gst::warning!(CAT, obj: element, "Consumer did not provide valid payload for media session: {session_id} media_ix: {media_idx}");
+                    return;
+                }
+            }
+
+            let element = element.downgrade();
+            let session_id = session_id.to_string();
+
+            let promise = gst::Promise::with_change_func(move |reply| {
+                gst::debug!(CAT, "received reply {:?}", reply);
+                if let Some(element) = element.upgrade() {
+                    let this = element.imp();
+
+                    this.on_remote_description_set(&element, session_id);
+                }
+            });
+
+            session
+                .webrtcbin
+                .emit_by_name::<()>("set-remote-description", &[desc, &promise]);
+        } else {
+            gst::warning!(CAT, "No consumer with ID {session_id}");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_discovery_pipeline(
+        element: &super::BaseWebRTCSink,
+        stream_name: &str,
+        discovery_info: &DiscoveryInfo,
+        codec: Codec,
+        input_caps: gst::Caps,
+        output_caps: &gst::Caps,
+        extension_ids: HeaderExtensionIds,
+        prefer_hardware: bool,
+    ) -> Result<gst::Structure, Error> {
+        let pipe = PipelineWrapper(gst::Pipeline::default());
+
+        let max_hardware_encoders = element.imp().settings.lock().unwrap().max_hardware_encoders;
+        let mut codec = codec;
+        codec.set_prefer_hardware_encoder(prefer_hardware);
+        let encoder_slot = if is_raw_caps(&input_caps) {
+            encoder_pool::reserve_encoder(&mut codec, max_hardware_encoders)?
+        } else {
+            None
+        };
+        let _discovery_guard =
+            encoder_pool::serialize_discovery(codec.encoder_factory().as_ref()).await;
+
+        let has_raw_input = is_raw_caps(&input_caps);
+        let src = discovery_info.create_src();
+        let mut elements = vec![src.clone().upcast::<gst::Element>()];
+        let encoding_chain_src = if codec.is_video() && has_raw_input {
+            elements.push(make_converter_for_video_caps(&input_caps, &codec)?);
+
+            let capsfilter = make_element("capsfilter", Some("raw_capsfilter"))?;
+            elements.push(capsfilter.clone());
+
+            capsfilter
+        } else {
+            src.clone().upcast::<gst::Element>()
+        };
+
+        gst::debug!(
+            CAT,
+            obj: element,
+            "Running discovery pipeline for input caps {input_caps} and output caps {output_caps} with codec {codec:?}"
+        );
+
+        gst::debug!(CAT, obj: element, "Running discovery pipeline");
+        let elements_slice = &elements.iter().collect::<Vec<_>>();
+        pipe.0.add_many(elements_slice).unwrap();
+        gst::Element::link_many(elements_slice)
+            .with_context(|| format!("Running discovery pipeline for caps {input_caps}"))?;
+
+        let mut encoding_chain_builder = EncodingChainBuilder::new(
+            &src.caps()
+                .expect("Caps should always be set when starting discovery"),
+            output_caps,
+            &codec,
+            element.emit_by_name::<Option<gst::Element>>(
+                "request-encoded-filter",
+                &[&Option::<String>::None, &stream_name, &codec.caps],
+            ),
+        )
+        .encoder_slot(encoder_slot);
+        if let Some(twcc) = extension_ids.twcc {
+            encoding_chain_builder = encoding_chain_builder.twcc(twcc)
+        }
+        if let Some(playout_delay_id) = extension_ids.playout_delay {
+            encoding_chain_builder = encoding_chain_builder
+                .playout_delay(element.imp().settings.lock().unwrap().playout_delay)
+                .playout_delay_id(playout_delay_id)
+        }
+        if let Some(ntp64) = extension_ids.ntp64 {
+            encoding_chain_builder = encoding_chain_builder.ntp64(ntp64)
+        }
+        if let Some(audio_level_id) = extension_ids.audio_level {
+            encoding_chain_builder = encoding_chain_builder.audio_level_id(audio_level_id)
+        }
+        let encoding_chain =
+            encoding_chain_builder.build(pipe.0.upcast_ref(), &encoding_chain_src)?;
+
+        if let Some(ref enc) = encoding_chain.encoder {
+            element.emit_by_name::<bool>(
+                "encoder-setup",
+                &[&"discovery".to_string(), &stream_name, &enc],
+            );
+        }
+
+        let sink = gst_app::AppSink::builder()
+            .callbacks(
+                gst_app::AppSinkCallbacks::builder()
+                    .new_event(|sink| {
+                        let obj = sink.pull_object().ok();
+                        if let Some(event) = obj.and_then(|o| o.downcast::<gst::Event>().ok()) {
+                            if let gst::EventView::Caps(caps) = event.view() {
+                                sink.post_message(gst::message::Application::new(
+                                    gst::Structure::builder("payloaded_caps")
+                                        .field("caps", &caps.caps().to_owned())
+                                        .build(),
+                                ))
+                                .expect("Could not send message");
+                            }
+                        }
+
+                        true
+                    })
+                    .build(),
+            )
+            .build();
+        pipe.0.add(sink.upcast_ref::<gst::Element>()).unwrap();
+        encoding_chain
+            .pay_filter
+            .link(&sink)
+            .with_context(|| format!("Running discovery pipeline for caps {input_caps}"))?;
+
+        let mut stream = pipe.0.bus().unwrap().stream();
+
+        pipe.0
+            .set_state(gst::State::Playing)
+            .with_context(|| format!("Running discovery pipeline for caps {input_caps}"))?;
+
+        while let Some(msg) = stream.next().await {
+            match msg.view() {
+                gst::MessageView::Error(err) => {
+                    gst::error!(CAT, "Error in discovery pipeline: {err:#?}");
+                    pipe.0.debug_to_dot_file_with_ts(
+                        gst::DebugGraphDetails::all(),
+                        "webrtcsink-discovery-error",
+                    );
+                    return Err(err.error().into());
+                }
+                gst::MessageView::StateChanged(s) => {
+                    if msg.src() == Some(pipe.0.upcast_ref()) {
+                        pipe.0.debug_to_dot_file_with_ts(
+                            gst::DebugGraphDetails::all(),
+                            format!(
+                                "webrtcsink-discovery-{}-{:?}-{:?}",
+                                pipe.0.name(),
+                                s.old(),
+                                s.current()
+                            ),
+                        );
+                    }
+                    continue;
+                }
+                gst::MessageView::Application(appmsg) => {
+                    let caps = match appmsg.structure() {
+                        Some(s) => {
+                            if s.name().as_str() != "payloaded_caps" {
+                                continue;
+                            }
+
+                            s.get::<gst::Caps>("caps").unwrap()
+                        }
+                        _ => continue,
+                    };
+
+                    gst::info!(CAT, "Discovery pipeline got caps {caps:?}");
+                    pipe.0.debug_to_dot_file_with_ts(
+                        gst::DebugGraphDetails::all(),
+                        "webrtcsink-discovery-done",
+                    );
+
+                    if let Some(s) = caps.structure(0) {
+                        let mut s = s.to_owned();
+                        s.remove_fields([
+                            "timestamp-offset",
+                            "seqnum-offset",
+                            "ssrc",
+                            "sprop-parameter-sets",
+                            "a-framerate",
+                        ]);
+                        s.set("payload", codec.payload().unwrap());
+                        gst::debug!(
+                            CAT,
+                            obj: element,
+                            "Codec discovery pipeline for caps {input_caps} with codec {codec:?} succeeded: {s}"
+                        );
+                        return Ok(s);
+                    } else {
+                        return Err(anyhow!("Discovered empty caps"));
+                    }
+                }
+                _ => {
+                    continue;
+                }
+            }
+        }
+
+        unreachable!()
+    }
+
+    async fn lookup_caps(
+        element: &super::BaseWebRTCSink,
+        discovery_info: DiscoveryInfo,
+        name: String,
+        output_caps: gst::Caps,
+        codecs: &Codecs,
+    ) -> Result<(), Error> {
+        if is_rtp_caps(&discovery_info.caps) {
+            let out_caps = BaseWebRTCSink::payloaded_output_caps(element, &discovery_info.caps)?;
+
+            let mut state = element.imp().state.lock().unwrap();
+            if let Some(stream) = state.streams.get_mut(&name) {
+                stream.out_caps = Some(out_caps);
+            }
+
+            return Ok(());
+        }
+
+        let (extension_ids, prefer_hardware) = {
+            let settings = element.imp().settings.lock().unwrap();
+            (
+                settings.header_extension_ids(),
+                settings.prefer_hardware_encoders,
+            )
+        };
+
+        if extension_ids.twcc.is_none() {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "No header extension ID left for TWCC, congestion control will not work"
+            );
+        }
+
+        let futs = if let Some(codec) = codecs.find_for_encoded_caps(&discovery_info.caps) {
+            let mut caps = discovery_info.caps.clone();
+
+            gst::info!(
+                CAT,
+                obj: element,
+                "Stream is already encoded with codec {}, still need to payload it",
+                codec.name
+            );
+
+            caps = cleanup_codec_caps(caps);
+
+            vec![BaseWebRTCSink::run_discovery_pipeline(
+                element,
+                &name,
+                &discovery_info,
+                codec,
+                caps,
+                &output_caps,
+                extension_ids,
+                prefer_hardware,
+            )]
+        } else {
+            let sink_caps = discovery_info.caps.clone();
+
+            let is_video = match sink_caps.structure(0).unwrap().name().as_str() {
+                "video/x-raw" => true,
+                "audio/x-raw" => false,
+                _ => unreachable!(),
+            };
+
+            codecs
+                .iter()
+                .filter(|codec| codec.is_video() == is_video)
+                .map(|codec| {
+                    BaseWebRTCSink::run_discovery_pipeline(
+                        element,
+                        &name,
+                        &discovery_info,
+                        codec.clone(),
+                        sink_caps.clone(),
+                        &output_caps,
+                        extension_ids,
+                        prefer_hardware,
+                    )
+                })
+                .collect()
+        };
+
+        let mut payloader_caps = gst::Caps::new_empty();
+        let payloader_caps_mut = payloader_caps.make_mut();
+
+        for ret in futures::future::join_all(futs).await {
+            match ret {
+                Ok(s) => {
+                    payloader_caps_mut.append_structure(s);
+                }
+                Err(err) => {
+                    /* We don't consider this fatal, as long as we end up with one
+                     * potential codec for each input stream
+                     */
+                    gst::warning!(
+                        CAT,
+                        obj: element,
+                        "Codec discovery pipeline failed: {}",
+                        err
+                    );
+                }
+            }
+        }
+
+        let mut state = element.imp().state.lock().unwrap();
+        if let Some(stream) = state.streams.get_mut(&name) {
+            stream.out_caps = Some(payloader_caps.clone());
+        }
+
+        if payloader_caps.is_empty() {
+            anyhow::bail!("No caps found for stream {name}");
+        }
+
+        Ok(())
+    }
+
+    /// Validates the caps of an already payloaded stream, and returns the
+    /// caps to offer it with, its payload type being passed through
+    fn payloaded_output_caps(
+        element: &super::BaseWebRTCSink,
+        caps: &gst::Caps,
+    ) -> Result<gst::Caps, Error> {
+        let codec = Codecs::find_for_payloaded_caps(caps)
+            .ok_or_else(|| anyhow!("Unsupported payloaded stream {caps}"))?;
+
+        {
+            let settings = element.imp().settings.lock().unwrap();
+            let user_caps = if codec.is_video() {
+                &settings.video_caps
+            } else {
+                &settings.audio_caps
+            };
+
+            if !codec.caps.can_intersect(user_caps) {
+                anyhow::bail!("Payloaded stream codec {} is not allowed", codec.name);
+            }
+        }
+
+        let payload = codec.payload().unwrap();
+        if let Some(other) = element
+            .imp()
+            .state
+            .lock()
+            .unwrap()
+            .codecs
+            .get(&payload)
+            .filter(|other| other.name != codec.name)
+        {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Payloaded stream uses payload type {payload}, also assigned to {}",
+                other.name
+            );
+        }
+
+        let mut s = caps.structure(0).unwrap().to_owned();
+        s.remove_fields(["timestamp-offset", "seqnum-offset", "ssrc"]);
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "Stream is already payloaded with codec {}, passing it through",
+            codec.name
+        );
+
+        Ok(gst::Caps::builder_full().structure(s).build())
+    }
+
+    fn gather_stats(&self) -> gst::Structure {
+        let (instance_id, labels, verbose) = {
+            let settings = self.settings.lock().unwrap();
+            (
+                settings.instance_id.clone(),
+                settings.labels.clone(),
+                settings.verbose_stats,
+            )
+        };
+
+        let mut ret = gst::Structure::from_iter(
+            "application/x-webrtcsink-stats",
+            self.state
+                .lock()
+                .unwrap()
+                .sessions
+                .iter()
+                .map(|(name, consumer)| {
+                    (
+                        name.as_str(),
+                        consumer.gather_stats(verbose).to_send_value(),
+                    )
+                }),
+        );
+
+        if let Some(instance_id) = instance_id {
+            ret.set("instance-id", instance_id);
+        }
+        if let Some(labels) = labels {
+            ret.set("labels", labels);
+        }
+
+        ret
+    }
+
+    /// The metadata advertised through the signaller, completed with our
+    /// instance ID and labels, and the description of the tracks set on our
+    /// sink pads
+    fn meta(&self) -> Option<gst::Structure> {
+        let (mut meta, instance_id, labels) = {
+            let settings = self.settings.lock().unwrap();
+            (
+                settings.meta.clone(),
+                settings.instance_id.clone(),
+                settings.labels.clone(),
+            )
+        };
+
+        if instance_id.is_some() || labels.is_some() {
+            let meta = meta.get_or_insert_with(|| gst::Structure::new_empty("meta"));
+
+            if let Some(instance_id) = instance_id {
+                meta.set("instance-id", instance_id);
+            }
+            if let Some(labels) = labels {
+                meta.set("labels", labels);
+            }
+        }
+
+        let mut streams = self
+            .state
+            .lock()
+            .unwrap()
+            .streams
+            .values()
+            .map(|stream| (stream.serial, stream.is_video, stream.sink_pad.clone()))
+            .collect::<Vec<_>>();
+        streams.sort_by_key(|(serial, ..)| *serial);
+
+        let tracks = streams
+            .into_iter()
+            .filter_map(|(_, is_video, sink_pad)| {
+                let pad = sink_pad
+                    .downcast_ref::<super::WebRTCSinkPad>()
+                    .unwrap()
+                    .imp();
+                let (name, role, language) =
+                    (pad.track_name(), pad.track_role(), pad.track_language());
+
+                if name.is_none() && role.is_none() && language.is_none() {
+                    return None;
+                }
+
+                let mut track = gst::Structure::builder("track")
+                    .field("stream", sink_pad.name().as_str())
+                    .field("kind", if is_video { "video" } else { "audio" })
+                    .build();
+
+                for (field, value) in [
+                    ("name", name),
+                    ("role", role),
+                    ("language", language),
+                    ("msid", pad.msid()),
+                ] {
+                    if let Some(value) = value {
+                        track.set(field, value);
+                    }
+                }
+
+                Some(track.to_send_value())
+            })
+            .collect::<gst::Array>();
+
+        if tracks.is_empty() {
+            return meta;
+        }
+
+        let mut meta = meta.unwrap_or_else(|| gst::Structure::new_empty("meta"));
+        meta.set("tracks", tracks);
+
+        Some(meta)
+    }
+
+    /// Sends a thumbnail of one of our video streams to the consumers
+    fn send_thumbnail(&self, stream_name: &str, sample: &gst::Sample) {
+        let Some(buffer) = sample.buffer() else {
+            return;
+        };
+        let Ok(map) = buffer.map_readable() else {
+            return;
+        };
+
+        let channels = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .filter_map(|session| session.thumbnail_channel.clone())
+            .filter(|channel| channel.ready_state() == gst_webrtc::WebRTCDataChannelState::Open)
+            .collect::<Vec<_>>();
+
+        if channels.is_empty() {
+            return;
+        }
+
+        let msg = serde_json::json!({
+            "stream": stream_name,
+            "pts": buffer.pts().map(|pts| pts.nseconds()),
+            "data": data_encoding::BASE64.encode(&map),
+        })
+        .to_string();
+
+        for channel in channels {
+            channel.send_string(Some(&msg));
+        }
+    }
+
+    /// Tells a consumer which clock the sender reports of the session are
+    /// timestamped against, for it to synchronize the streams of all the
+    /// producers sharing that clock
+    fn send_clock_info(&self, channel: &WebRTCDataChannel) {
+        let element = self.obj();
+        let Some(clock) = element.clock() else {
+            gst::warning!(CAT, imp: self, "No clock to signal");
+            return;
+        };
+
+        let msg = serde_json::json!({
+            "ts-refclk": clock_ts_refclk(&clock),
+            "mediaclk": "sender",
+            "base-time": element.base_time().map(|time| time.nseconds()),
+            "clock-time": clock.time().map(|time| time.nseconds()),
+        });
+
+        channel.send_string(Some(&msg.to_string()));
+    }
+
+    fn spawn_stats_messages(&self, interval: u32) -> tokio::task::JoinHandle<()> {
+        let element = self.obj().downgrade();
+
+        RUNTIME.spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_millis(interval as u64));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+
+                let Some(element) = element.upgrade() else {
+                    break;
+                };
+
+                let stats = element.imp().gather_stats();
+                let _ = element
+                    .post_message(gst::message::Element::builder(stats).src(&element).build());
+            }
+        })
+    }
+
+    /// Warns when the data queued for all the sessions, for instance when
+    /// consumers stall, exceeds the memory budget, and ends the sessions
+    /// queuing the most if the policy says so
+    fn check_memory_budget(&self, element: &super::BaseWebRTCSink) {
+        let (budget, policy) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.memory_budget, settings.memory_budget_policy)
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let sessions = state
+            .sessions
+            .values()
+            .map(|session| (session.id.clone(), session.queued_bytes()))
+            .collect::<Vec<_>>();
+        let queued_bytes = sessions.iter().map(|(_, bytes)| bytes).sum::<u64>();
+
+        let first_exceeded = state.memory_budget.update(element, budget, queued_bytes);
+        drop(state);
+
+        if queued_bytes <= budget {
+            return;
+        }
+
+        if first_exceeded {
+            memory_budget::post_exceeded(element, budget, queued_bytes);
+        }
+
+        if policy != WebRTCSinkMemoryBudgetPolicy::EndSessions {
+            return;
+        }
+
+        for (session_id, bytes) in memory_budget::sessions_to_end(sessions, budget) {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Ending session {session_id} with {bytes} bytes queued to honor the memory budget"
+            );
+            let _ = self.remove_session(element, &session_id, true);
+        }
+    }
+
+    fn sink_event(
+        &self,
+        pad: &gst::Pad,
+        element: &super::BaseWebRTCSink,
+        event: gst::Event,
+    ) -> bool {
+        use gst::EventView;
+
+        if let EventView::Eos(..) = event.view() {
+            self.start_drain_if_needed(element, pad);
+        }
+
+        if let EventView::Caps(e) = event.view() {
+            if let Some(caps) = pad.current_caps() {
+                if caps.is_strictly_equal(e.caps()) {
+                    // Nothing changed
+                    return true;
+                } else {
+                    gst::error!(
+                        CAT,
+                        obj: pad,
+                        "Renegotiation is not supported (old: {}, new: {})",
+                        caps,
+                        e.caps()
+                    );
+                    return false;
+                }
+            } else {
+                gst::info!(CAT, obj: pad, "Received caps event {:?}", e);
+
+                self.state
+                    .lock()
+                    .unwrap()
+                    .streams
+                    .iter_mut()
+                    .for_each(|(_, stream)| {
+                        if stream.sink_pad.upcast_ref::<gst::Pad>() == pad {
+                            // We do not want VideoInfo to consider max-framerate
+                            // when computing fps, so we strip it away here
+                            let mut caps = e.caps().to_owned();
+                            {
+                                let mut_caps = caps.get_mut().unwrap();
+                                if let Some(s) = mut_caps.structure_mut(0) {
+                                    if s.has_name("video/x-raw") {
+                                        s.remove_field("max-framerate");
+                                    }
+                                }
+                            }
+                            stream.in_caps = Some(caps.to_owned());
+                        }
+                    });
+
+                self.watch_input(element, pad, e.caps());
+            }
+        }
+
+        gst::Pad::event_default(pad, Some(element), event)
+    }
+
+    /// Called with the input caps, sends placeholder data when a raw input
+    /// in system memory stalls
+    fn watch_input(&self, element: &super::BaseWebRTCSink, pad: &gst::Pad, caps: &gst::Caps) {
+        let system_memory = caps.features(0).map_or(true, |features| {
+            features.contains(gst::CAPS_FEATURE_MEMORY_SYSTEM_MEMORY)
+        });
+        if !is_raw_caps(caps) || !system_memory {
+            return;
+        }
+
+        let input_timeout = self.settings.lock().unwrap().input_timeout;
+
+        // The watchdog adds elements, don't hold the state meanwhile
+        let Some(live_pad) = self
+            .state
+            .lock()
+            .unwrap()
+            .streams
+            .values()
+            .find(|stream| stream.sink_pad.upcast_ref::<gst::Pad>() == pad)
+            .filter(|stream| stream.input_selector.is_some() && stream.watchdog.is_none())
+            .and_then(|stream| stream.sink_pad.target())
+        else {
+            return;
+        };
+
+        let watchdog = match InputWatchdog::new(
+            element.upcast_ref(),
+            &live_pad,
+            &pad.name(),
+            caps,
+            gst::ClockTime::from_mseconds(input_timeout as u64),
+        ) {
+            Ok(watchdog) => watchdog,
+            Err(err) => {
+                gst::warning!(CAT, obj: pad, "No placeholder data for stalled input: {err:?}");
+                return;
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(stream) = state
+            .streams
+            .values_mut()
+            .find(|stream| stream.sink_pad.upcast_ref::<gst::Pad>() == pad)
+            .filter(|stream| stream.input_selector.is_some())
+        {
+            stream.watchdog = Some(Arc::new(watchdog));
+        }
+    }
+
+    /// Called with the EOS of each input stream, starts draining the sessions
+    /// if it was the last one
+    fn start_drain_if_needed(&self, element: &super::BaseWebRTCSink, pad: &gst::Pad) {
+        let drain_timeout = self.settings.lock().unwrap().drain_timeout;
+        if drain_timeout == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        // The EOS of the pad at hand isn't sticky yet
+        let all_eos = state.streams.values().all(|stream| {
+            stream.sink_pad.upcast_ref::<gst::Pad>() == pad
+                || stream.sink_pad.sticky_event::<gst::event::Eos>(0).is_some()
+        });
+
+        if !all_eos || state.draining || state.sessions.is_empty() {
+            return;
+        }
+
+        gst::info!(
+            CAT,
+            obj: element,
+            "All input streams reached EOS, draining {} sessions",
+            state.sessions.len()
+        );
+
+        state.draining = true;
+
+        let mut drained_sessions = Vec::new();
+
+        for session in state.sessions.values() {
+            let element = element.downgrade();
+            let session_id = session.id.clone();
+            let watching = drain::watch_webrtcbin(&session.webrtcbin, move || {
+                if let Some(element) = element.upgrade() {
+                    element.imp().on_session_drained(&element, &session_id);
+                }
+            });
+
+            if !watching {
+                drained_sessions.push(session.id.clone());
+            }
+        }
+
+        drop(state);
+
+        let element_weak = element.downgrade();
+        RUNTIME.spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(drain_timeout as u64)).await;
+
+            if let Some(element) = element_weak.upgrade() {
+                element.imp().finish_drain(&element, true);
+            }
+        });
+
+        for session_id in drained_sessions {
+            self.on_session_drained(element, &session_id);
+        }
+    }
+
+    fn on_session_drained(&self, element: &super::BaseWebRTCSink, session_id: &str) {
+        gst::info!(CAT, obj: element, "Session {session_id} is drained, ending it");
+
+        self.end_drained_session(element, session_id);
+
+        if self.state.lock().unwrap().sessions.is_empty() {
+            self.finish_drain(element, false);
+        }
+    }
+
+    /// Notifies the peer that the stream ended, as opposed to the session
+    /// being interrupted, then ends the session
+    fn end_drained_session(&self, element: &super::BaseWebRTCSink, session_id: &str) {
+        if !self.state.lock().unwrap().sessions.contains_key(session_id) {
+            return;
+        }
+
+        self.session_signaller(session_id)
+            .emit_by_name::<()>("end-of-stream", &[&session_id]);
+
+        let _ = self.remove_session(element, session_id, true);
+    }
+
+    /// Releases the EOS of the element, ending the sessions that couldn't
+    /// drain in time if `timed_out`
+    fn finish_drain(&self, element: &super::BaseWebRTCSink, timed_out: bool) {
+        let (messages, session_ids) = {
+            let mut state = self.state.lock().unwrap();
+
+            if !state.draining {
+                return;
+            }
+
+            state.draining = false;
+
+            (
+                std::mem::take(&mut state.held_eos_messages),
+                state.sessions.keys().cloned().collect::<Vec<_>>(),
+            )
+        };
+
+        if timed_out {
+            for session_id in session_ids {
+                gst::warning!(
+                    CAT,
+                    obj: element,
+                    "Session {session_id} didn't drain in time, ending it"
+                );
+                self.end_drained_session(element, &session_id);
+            }
+        }
+
+        gst::info!(CAT, obj: element, "Done draining");
+
+        for message in messages {
+            self.parent_handle_message(message);
+        }
+    }
+
+    /// Restricts the template caps of a sink pad to the formats we can
+    /// actually encode, or simply payload, with the codecs at hand
+    fn encodable_caps(&self, pad: &gst::Pad) -> gst::Caps {
+        let codecs = {
+            let state = self.state.lock().unwrap();
+            if !state.codecs.is_empty() {
+                Some(Codecs::from_map(&state.codecs))
+            } else {
+                None
+            }
+        }
+        .unwrap_or_else(|| {
+            let settings = self.settings.lock().unwrap();
+            Codecs::list_encoders(settings.video_caps.iter().chain(settings.audio_caps.iter()))
+        });
+
+        let mut ret = gst::Caps::new_empty();
+        let ret_mut = ret.get_mut().unwrap();
+
+        for (s, features) in pad.pad_template_caps().iter_with_features() {
+            let is_raw = ["video/x-raw", "audio/x-raw"].contains(&s.name().as_str());
+            let caps = gst::Caps::builder_full().structure(s.to_owned()).build();
+
+            let encodable = codecs.iter().any(|codec| {
+                if is_raw {
+                    codec.is_video() == s.has_name("video/x-raw")
+                } else {
+                    codec.caps.can_intersect(&caps)
+                }
+            });
+
+            if encodable {
+                ret_mut.append_structure_full(s.to_owned(), Some(features.to_owned()));
+            }
+        }
+
+        ret
+    }
+
+    fn sink_query(
+        &self,
+        pad: &gst::Pad,
+        element: &super::BaseWebRTCSink,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryViewMut;
+
+        match query.view_mut() {
+            QueryViewMut::Caps(q) => {
+                let caps = pad
+                    .current_caps()
+                    .unwrap_or_else(|| self.encodable_caps(pad));
+
+                let caps = match q.filter() {
+                    Some(filter) => {
+                        filter.intersect_with_mode(&caps, gst::CapsIntersectMode::First)
+                    }
+                    None => caps,
+                };
+
+                gst::log!(CAT, obj: pad, "Returning caps {caps}");
+
+                q.set_result(&caps);
+
+                true
+            }
+            QueryViewMut::AcceptCaps(q) => {
+                let accepted = match pad.current_caps() {
+                    Some(current_caps) => q.caps().is_strictly_equal(&current_caps),
+                    None => q.caps().is_subset(&self.encodable_caps(pad)),
+                };
+
+                q.set_result(accepted);
+
+                true
+            }
+            QueryViewMut::Allocation(q) if is_raw_video_allocation(q) => {
+                self.propose_allocation(pad, q)
+            }
+            _ => gst::Pad::query_default(pad, Some(element), query),
+        }
+    }
+
+    /// Answers allocation queries for raw video with the memory and
+    /// alignment requirements of the encoders of the sessions consuming
+    /// the stream, so that they can take the buffers of upstream as is
+    /// rather than copying them
+    fn propose_allocation(&self, pad: &gst::Pad, query: &mut gst::query::Allocation) -> bool {
+        let (caps, need_pool) = query.get_owned();
+        let Some(caps) = caps else {
+            return false;
+        };
+        let Ok(info) = gst_video::VideoInfo::from_caps(&caps) else {
+            return false;
+        };
+
+        let appsrcs = self
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .filter_map(|session| session.appsrc(&pad.name()))
+            .collect::<Vec<_>>();
+
+        let mut allocator = None;
+        let mut params = gst::AllocationParams::default();
+        let mut padding = [0u32; 4];
+        let mut stride_align = [0u32; gst_video::VIDEO_MAX_PLANES];
+
+        for appsrc in appsrcs {
+            let Some(peer) = appsrc.static_pad("src").and_then(|srcpad| srcpad.peer()) else {
+                continue;
+            };
+
+            let mut session_query = gst::query::Allocation::new(Some(&caps), true);
+            if !peer.query(&mut session_query) {
+                continue;
             }
-        }
 
-        let mut state = element.imp().state.lock().unwrap();
-        if let Some(stream) = state.streams.get_mut(&name) {
-            stream.out_caps = Some(payloader_caps.clone());
-        }
+            for (session_allocator, session_params) in session_query.allocation_params() {
+                allocator = allocator.or(session_allocator);
+                params = gst::AllocationParams::new(
+                    params.flags() | session_params.flags(),
+                    params.align().max(session_params.align()),
+                    params.prefix().max(session_params.prefix()),
+                    params.padding().max(session_params.padding()),
+                );
+            }
 
-        if payloader_caps.is_empty() {
-            anyhow::bail!("No caps found for stream {name}");
-        }
+            for (session_pool, ..) in session_query.allocation_pools() {
+                let Some(alignment) = session_pool.and_then(|pool| pool.config().video_alignment())
+                else {
+                    continue;
+                };
 
-        Ok(())
-    }
+                for (padding, session_padding) in padding.iter_mut().zip([
+                    alignment.padding_top(),
+                    alignment.padding_bottom(),
+                    alignment.padding_left(),
+                    alignment.padding_right(),
+                ]) {
+                    *padding = (*padding).max(session_padding);
+                }
 
-    fn gather_stats(&self) -> gst::Structure {
-        gst::Structure::from_iter(
-            "application/x-webrtcsink-stats",
-            self.state
-                .lock()
-                .unwrap()
-                .sessions
-                .iter()
-                .map(|(name, consumer)| (name.as_str(), consumer.gather_stats().to_send_value())),
-        )
-    }
+                for (align, session_align) in stride_align.iter_mut().zip(alignment.stride_align())
+                {
+                    *align |= session_align;
+                }
+            }
+        }
 
-    fn sink_event(
-        &self,
-        pad: &gst::Pad,
-        element: &super::BaseWebRTCSink,
-        event: gst::Event,
-    ) -> bool {
-        use gst::EventView;
+        gst::debug!(
+            CAT,
+            obj: pad,
+            "Proposing allocator {allocator:?} with {params:?}, padding {padding:?} and stride alignment {stride_align:?}"
+        );
 
-        if let EventView::Caps(e) = event.view() {
-            if let Some(caps) = pad.current_caps() {
-                if caps.is_strictly_equal(e.caps()) {
-                    // Nothing changed
-                    return true;
-                } else {
-                    gst::error!(
-                        CAT,
-                        obj: pad,
-                        "Renegotiation is not supported (old: {}, new: {})",
-                        caps,
-                        e.caps()
-                    );
-                    return false;
-                }
-            } else {
-                gst::info!(CAT, obj: pad, "Received caps event {:?}", e);
+        if need_pool {
+            let pool = gst_video::VideoBufferPool::new();
+            let mut config = pool.config();
+            config.set_params(Some(&caps), info.size() as u32, 0, 0);
+            config.set_allocator(allocator.as_ref(), Some(&params));
+            config.add_option(&gst_video::BUFFER_POOL_OPTION_VIDEO_META);
+
+            if padding.iter().chain(stride_align.iter()).any(|v| *v != 0) {
+                config.add_option(&gst_video::BUFFER_POOL_OPTION_VIDEO_ALIGNMENT);
+                config.set_video_alignment(&gst_video::VideoAlignment::new(
+                    padding[0],
+                    padding[1],
+                    padding[2],
+                    padding[3],
+                    &stride_align,
+                ));
+            }
 
-                self.state
-                    .lock()
-                    .unwrap()
-                    .streams
-                    .iter_mut()
-                    .for_each(|(_, stream)| {
-                        if stream.sink_pad.upcast_ref::<gst::Pad>() == pad {
-                            // We do not want VideoInfo to consider max-framerate
-                            // when computing fps, so we strip it away here
-                            let mut caps = e.caps().to_owned();
-                            {
-                                let mut_caps = caps.get_mut().unwrap();
-                                if let Some(s) = mut_caps.structure_mut(0) {
-                                    if s.has_name("video/x-raw") {
-                                        s.remove_field("max-framerate");
-                                    }
-                                }
-                            }
-                            stream.in_caps = Some(caps.to_owned());
-                        }
-                    });
+            match pool.set_config(config) {
+                Ok(()) => {
+                    // Alignment may have grown the buffers
+                    let size = pool
+                        .config()
+                        .params()
+                        .map_or(info.size() as u32, |(_, size, ..)| size);
+                    query.add_allocation_pool(Some(&pool), size, 0, 0);
+                }
+                Err(err) => {
+                    gst::warning!(CAT, obj: pad, "Failed to configure buffer pool: {err}");
+                }
             }
         }
 
-        gst::Pad::event_default(pad, Some(element), event)
+        query.add_allocation_param(allocator.as_ref(), params);
+        query.add_allocation_meta::<gst_video::VideoMeta>(None);
+
+        true
     }
 
     fn start_stream_discovery_if_needed(&self, stream_name: &str, buffer: &gst::Buffer) {
         let (codecs, discovery_info) = {
             let mut state = self.state.lock().unwrap();
-            let stream = state.streams.get_mut(stream_name).unwrap();
+            let Some(stream) = state.streams.get_mut(stream_name) else {
+                return;
+            };
 
             // Discovery already happened... nothing to do here.
             if stream.out_caps.is_some() {
@@ -3159,8 +7246,10 @@ impl BaseWebRTCSink {
         }));
 
         let mut state = self.state.lock().unwrap();
-        let stream = state.streams.get_mut(stream_name).unwrap();
-        stream.remove_discovery(&discovery_info);
+        // The stream may have been released in the meantime
+        if let Some(stream) = state.streams.get_mut(stream_name) {
+            stream.remove_discovery(&discovery_info);
+        }
     }
 
     fn chain(
@@ -3199,53 +7288,628 @@ impl ObjectImpl for BaseWebRTCSink {
                     .blurb("Governs what video codecs will be proposed")
                     .mutable_ready()
                     .build(),
-                glib::ParamSpecBoxed::builder::<gst::Caps>("audio-caps")
-                    .nick("Audio encoder caps")
-                    .blurb("Governs what audio codecs will be proposed")
+                glib::ParamSpecBoxed::builder::<gst::Caps>("audio-caps")
+                    .nick("Audio encoder caps")
+                    .blurb("Governs what audio codecs will be proposed")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("stun-server")
+                    .nick("STUN Server")
+                    .blurb("The STUN server of the form stun://hostname:port")
+                    .default_value(DEFAULT_STUN_SERVER)
+                    .build(),
+                gst::ParamSpecArray::builder("turn-servers")
+                    .nick("List of TURN Servers to user")
+                    .blurb("The TURN servers of the form <\"turn(s)://username:password@host:port\", \"turn(s)://username1:password1@host1:port1\">")
+                    .element_spec(&glib::ParamSpecString::builder("turn-server")
+                        .nick("TURN Server")
+                        .blurb("The TURN server of the form turn(s)://username:password@host:port.")
+                        .build()
+                    )
+                    .mutable_playing()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:ice-candidate-type-order:
+                 *
+                 * ICE candidate types ("host", "srflx", "prflx" and "relay") by
+                 * decreasing preference. The priorities of both the local and
+                 * remote candidates are rewritten accordingly, with the types that
+                 * aren't listed coming last. For instance, <"relay", "srflx">
+                 * avoids waiting for host candidates to time out on networks
+                 * that blackhole them.
+                 *
+                 * When empty, the priorities computed by the ICE agents are used.
+                 */
+                gst::ParamSpecArray::builder("ice-candidate-type-order")
+                    .nick("ICE candidate type order")
+                    .blurb("ICE candidate types by decreasing preference, of the form <\"relay\", \"srflx\", \"host\">")
+                    .element_spec(&glib::ParamSpecString::builder("ice-candidate-type")
+                        .nick("ICE candidate type")
+                        .blurb("One of host, srflx, prflx or relay")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:ice-local-addresses:
+                 *
+                 * Local IP addresses the ICE agents of the sessions gather their
+                 * candidates on, for instance <"192.168.1.10", "fd00::10"> to make
+                 * the media of a multi-homed host leave through specific network
+                 * interfaces. Interface names aren't supported, use the addresses
+                 * assigned to them instead.
+                 *
+                 * When empty, candidates are gathered on all the local addresses.
+                 */
+                gst::ParamSpecArray::builder("ice-local-addresses")
+                    .nick("ICE local addresses")
+                    .blurb("Local IP addresses to gather ICE candidates on, all of them if empty")
+                    .element_spec(&glib::ParamSpecString::builder("ice-local-address")
+                        .nick("ICE local address")
+                        .blurb("A local IPv4 or IPv6 address")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:ice-candidate-batch-interval:
+                 *
+                 * Accumulate the local ICE candidates gathered within this many
+                 * milliseconds of the first one and send them through the
+                 * signaller together, rather than as soon as each of them is
+                 * gathered. This trades connection setup time for fewer bursts
+                 * of signalling traffic.
+                 *
+                 * Batches are sent with #GstRSWebRTCSignallableIface::send-ice-batch,
+                 * which HTTP-based signallers can implement with a single request.
+                 * The pending batch is sent as soon as gathering completes, along
+                 * with the end of candidates.
+                 *
+                 * 0 sends each candidate as soon as it is gathered.
+                 */
+                glib::ParamSpecUInt::builder("ice-candidate-batch-interval")
+                    .nick("ICE candidate batch interval")
+                    .blurb("Milliseconds to accumulate local ICE candidates for before sending them, 0 to send them right away")
+                    .default_value(DEFAULT_ICE_CANDIDATE_BATCH_INTERVAL)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:srtp-profiles:
+                 *
+                 * DTLS-SRTP protection profiles consumers are allowed to negotiate,
+                 * for instance <"SRTP_AEAD_AES_128_GCM", "SRTP_AEAD_AES_256_GCM">.
+                 * webrtcbin doesn't allow restricting the profiles it offers, so
+                 * sessions that negotiate another profile are ended right after
+                 * the DTLS handshake.
+                 *
+                 * The negotiated profile is reported as `srtp-profile` in the
+                 * consumer stats. When empty, all profiles are allowed.
+                 */
+                gst::ParamSpecArray::builder("srtp-profiles")
+                    .nick("SRTP profiles")
+                    .blurb("Allowed DTLS-SRTP protection profiles, empty to allow all of them")
+                    .element_spec(&glib::ParamSpecString::builder("srtp-profile")
+                        .nick("SRTP profile")
+                        .blurb("One of SRTP_AES128_CM_SHA1_80, SRTP_AES128_CM_SHA1_32, SRTP_AEAD_AES_128_GCM or SRTP_AEAD_AES_256_GCM")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:interop-rules:
+                 *
+                 * Workarounds for the shortcomings of some remote peers, as an
+                 * array of structures holding a `user-agent` regular expression
+                 * and the workarounds to apply to the sessions of the peers it
+                 * matches:
+                 *
+                 * - `disable-twcc` (boolean): don't negotiate transport-wide
+                 *   congestion control feedback, for peers that send broken
+                 *   feedback
+                 * - `disable-fec` (boolean): don't offer ULPFEC / RED
+                 * - `disable-retransmission` (boolean): don't offer RTX
+                 * - `h264-packetization-mode` (integer, 0 or 1): packetization
+                 *   mode advertised for H264 streams
+                 *
+                 * For instance `<"rule, user-agent=(string)SDPARTA, disable-twcc=true">`.
+                 * The workarounds of all the matching rules are applied.
+                 *
+                 * The user agent is the `user-agent` field of the capabilities
+                 * the signaller advertised for the session, see
+                 * #GstRSWebRTCSignallableIface::session-requested. Otherwise,
+                 * when the consumer sends the offer, it is identified by the
+                 * username of the origin and the session name of that offer,
+                 * which is where browsers write their name. Sessions we offer
+                 * to without a signalled user agent are matched against an
+                 * empty string.
+                 */
+                gst::ParamSpecArray::builder("interop-rules")
+                    .nick("Interoperability rules")
+                    .blurb("Workarounds to apply to the sessions of the remote peers matching the user agent of each rule")
+                    .element_spec(&glib::ParamSpecBoxed::builder::<gst::Structure>("interop-rule")
+                        .nick("Interoperability rule")
+                        .blurb("A user-agent regular expression and the workarounds for the matching peers")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("congestion-control", DEFAULT_CONGESTION_CONTROL)
+                    .nick("Congestion control")
+                    .blurb("Defines how congestion is controlled, if at all")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("min-bitrate")
+                    .nick("Minimal Bitrate")
+                    .blurb("Minimal bitrate to use (in bit/sec) when computing it through the congestion control algorithm")
+                    .minimum(1)
+                    .maximum(u32::MAX)
+                    .default_value(DEFAULT_MIN_BITRATE)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-bitrate")
+                    .nick("Maximum Bitrate")
+                    .blurb("Maximum bitrate to use (in bit/sec) when computing it through the congestion control algorithm")
+                    .minimum(1)
+                    .maximum(u32::MAX)
+                    .default_value(DEFAULT_MAX_BITRATE)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("start-bitrate")
+                    .nick("Start Bitrate")
+                    .blurb("Start bitrate to use (in bit/sec)")
+                    .minimum(1)
+                    .maximum(u32::MAX)
+                    .default_value(DEFAULT_START_BITRATE)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:homegrown-increase-factor:
+                 *
+                 * Only used when #RsBaseWebRTCSink:congestion-control is homegrown.
+                 */
+                glib::ParamSpecDouble::builder("homegrown-increase-factor")
+                    .nick("Homegrown increase factor")
+                    .blurb("Factor applied to the bitrate on multiplicative increase by the homegrown congestion controller")
+                    .minimum(1.)
+                    .maximum(2.)
+                    .default_value(homegrown_cc::DEFAULT_INCREASE_FACTOR)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:homegrown-decrease-factor:
+                 *
+                 * Only used when #RsBaseWebRTCSink:congestion-control is homegrown.
+                 */
+                glib::ParamSpecDouble::builder("homegrown-decrease-factor")
+                    .nick("Homegrown decrease factor")
+                    .blurb("Highest factor applied to the bitrate when the homegrown congestion controller detects delay")
+                    .minimum(0.1)
+                    .maximum(1.)
+                    .default_value(homegrown_cc::DEFAULT_DECREASE_FACTOR)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:homegrown-loss-threshold-high:
+                 *
+                 * Only used when #RsBaseWebRTCSink:congestion-control is homegrown.
+                 * The two thresholds are swapped when a session starts if this one
+                 * is lower than #RsBaseWebRTCSink:homegrown-loss-threshold-low.
+                 */
+                glib::ParamSpecDouble::builder("homegrown-loss-threshold-high")
+                    .nick("Homegrown high loss threshold")
+                    .blurb("Packet loss percentage above which the homegrown congestion controller decreases the bitrate")
+                    .minimum(0.)
+                    .maximum(100.)
+                    .default_value(homegrown_cc::DEFAULT_LOSS_THRESHOLD_HIGH)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:homegrown-loss-threshold-low:
+                 *
+                 * Only used when #RsBaseWebRTCSink:congestion-control is homegrown.
+                 * The two thresholds are swapped when a session starts if this one
+                 * is higher than #RsBaseWebRTCSink:homegrown-loss-threshold-high.
+                 */
+                glib::ParamSpecDouble::builder("homegrown-loss-threshold-low")
+                    .nick("Homegrown low loss threshold")
+                    .blurb("Packet loss percentage below which the homegrown congestion controller increases the bitrate")
+                    .minimum(0.)
+                    .maximum(100.)
+                    .default_value(homegrown_cc::DEFAULT_LOSS_THRESHOLD_LOW)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:homegrown-update-interval:
+                 *
+                 * Interval at which the delay-based homegrown congestion
+                 * controller runs, independently of
+                 * #RsBaseWebRTCSink:stats-collection-interval. The statistics
+                 * are requested once for both, at the shortest interval, the
+                 * longer one is rounded to a multiple of it.
+                 */
+                glib::ParamSpecUInt::builder("homegrown-update-interval")
+                    .nick("Homegrown update interval")
+                    .blurb("Interval in milliseconds at which the homegrown congestion controller runs")
+                    .minimum(10)
+                    .maximum(10000)
+                    .default_value(homegrown_cc::DEFAULT_UPDATE_INTERVAL)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:prefer-hardware-encoders:
+                 *
+                 * Whether hardware encoders should be picked over software
+                 * encoders when both are available for a codec. This can be
+                 * overridden for each consumer with
+                 * #RsBaseWebRTCSink::request-hardware-encoder.
+                 */
+                glib::ParamSpecBoolean::builder("prefer-hardware-encoders")
+                    .nick("Prefer hardware encoders")
+                    .blurb("Whether to pick hardware encoders over software encoders when available")
+                    .default_value(DEFAULT_PREFER_HARDWARE_ENCODERS)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:max-hardware-encoders:
+                 *
+                 * Maximum number of hardware encoders alive at the same time,
+                 * across all consumers and discoveries. When the limit is reached,
+                 * the next available encoder for the codec is used instead,
+                 * useful with hardware that only supports a few concurrent
+                 * encoding sessions (e.g. NVENC on consumer GPUs). Streams
+                 * for which no encoder is left fail to connect.
+                 *
+                 * 0 means unlimited.
+                 */
+                glib::ParamSpecUInt::builder("max-hardware-encoders")
+                    .nick("Maximum hardware encoders")
+                    .blurb("Maximum number of concurrent hardware encoders, 0 for unlimited")
+                    .default_value(DEFAULT_MAX_HARDWARE_ENCODERS)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:bandwidth-group:
+                 *
+                 * Name of a bandwidth group shared with the other webrtcsink
+                 * instances of the process that set the same name. The sessions
+                 * of all the members split #RsBaseWebRTCSink:bandwidth-group-bitrate
+                 * fairly between them instead of each assuming the whole uplink
+                 * is theirs: sessions estimating less than an even share leave
+                 * the rest of it to the others.
+                 *
+                 * The share of each session is applied on top of its congestion
+                 * control estimate, and updated on its next control operation.
+                 */
+                glib::ParamSpecString::builder("bandwidth-group")
+                    .nick("Bandwidth group")
+                    .blurb("Name of the group of webrtcsink instances sharing the uplink, NULL for none")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:bandwidth-group-bitrate:
+                 *
+                 * Total bitrate of the uplink shared by the sessions of
+                 * #RsBaseWebRTCSink:bandwidth-group, in bits per second. When
+                 * the members of a group disagree, the smallest value is used.
+                 */
+                glib::ParamSpecUInt::builder("bandwidth-group-bitrate")
+                    .nick("Bandwidth group bitrate")
+                    .blurb("Total bitrate of the uplink shared by the bandwidth group")
+                    .minimum(1)
+                    .default_value(DEFAULT_BANDWIDTH_GROUP_BITRATE)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:audio-fec:
+                 *
+                 * Whether Opus inband Forward Error Correction should be
+                 * enabled on audio encoders. It is signalled with
+                 * `useinbandfec=1` in the SDP.
+                 */
+                glib::ParamSpecBoolean::builder("audio-fec")
+                    .nick("Audio FEC")
+                    .blurb("Whether to enable inband FEC on Opus audio encoders")
+                    .default_value(DEFAULT_AUDIO_FEC)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:audio-dtx:
+                 *
+                 * Whether Opus discontinuous transmission should be enabled
+                 * on audio encoders, reducing the bitrate during silence.
+                 */
+                glib::ParamSpecBoolean::builder("audio-dtx")
+                    .nick("Audio DTX")
+                    .blurb("Whether to enable discontinuous transmission on Opus audio encoders")
+                    .default_value(DEFAULT_AUDIO_DTX)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:audio-min-bitrate:
+                 *
+                 * Congestion control gives Opus audio encoders a share of the
+                 * estimated bandwidth, never lower than this. Setting it to the
+                 * bitrate the encoders are configured with, or higher, keeps
+                 * the audio bitrate fixed.
+                 */
+                glib::ParamSpecUInt::builder("audio-min-bitrate")
+                    .nick("Minimal Audio Bitrate")
+                    .blurb("Minimal bitrate to use (in bit/sec) for audio encoders when computing it through the congestion control algorithm")
+                    .minimum(4000)
+                    .maximum(650000)
+                    .default_value(DEFAULT_AUDIO_MIN_BITRATE)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:audio-max-bitrate:
+                 *
+                 * Congestion control never raises the bitrate of Opus audio
+                 * encoders above this, nor above the bitrate they were
+                 * configured with, e.g. from #RsBaseWebRTCSink::encoder-setup.
+                 */
+                glib::ParamSpecUInt::builder("audio-max-bitrate")
+                    .nick("Maximum Audio Bitrate")
+                    .blurb("Maximum bitrate to use (in bit/sec) for audio encoders when computing it through the congestion control algorithm")
+                    .minimum(4000)
+                    .maximum(650000)
+                    .default_value(DEFAULT_AUDIO_MAX_BITRATE)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:input-timeout:
+                 *
+                 * When a raw input stream doesn't receive any buffer for this
+                 * many milliseconds, placeholder data (black frames or silence)
+                 * is sent to the consumers until the stream resumes, instead of
+                 * letting their RTP streams stall. An element message named
+                 * `webrtcsink-input-stalled` is posted when the input stalls
+                 * and when it resumes.
+                 *
+                 * 0 disables placeholders.
+                 */
+                glib::ParamSpecUInt::builder("input-timeout")
+                    .nick("Input timeout")
+                    .blurb("Time in milliseconds without input before sending placeholder data, 0 to disable")
+                    .default_value(DEFAULT_INPUT_TIMEOUT)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:stats-collection-interval:
+                 *
+                 * Interval at which the WebRTC statistics of each consumer are
+                 * collected, 0 disables periodic collection. The homegrown
+                 * congestion controller runs on the same statistics requests, at
+                 * #RsBaseWebRTCSink:homegrown-update-interval.
+                 */
+                glib::ParamSpecUInt::builder("stats-collection-interval")
+                    .nick("Stats collection interval")
+                    .blurb("Interval in milliseconds at which consumer statistics are collected, 0 to disable")
+                    .default_value(DEFAULT_STATS_COLLECTION_INTERVAL)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:drain-timeout:
+                 *
+                 * When all input streams reach EOS, give the sessions up to this
+                 * many milliseconds to push the media still queued in their
+                 * encoding chains and transports to the consumers. Once drained,
+                 * #GstRSWebRTCSignallableIface::end-of-stream is emitted on the
+                 * signaller and the session is ended, and the element only posts EOS once
+                 * all sessions are drained or the timeout expired, so that the
+                 * tail of the media isn't cut off by the application shutting
+                 * the pipeline down.
+                 *
+                 * 0 ends the sessions as soon as EOS is reached.
+                 */
+                glib::ParamSpecUInt::builder("drain-timeout")
+                    .nick("Drain timeout")
+                    .blurb("Maximum time in milliseconds to wait for sessions to flush their media on EOS, 0 to disable draining")
+                    .default_value(DEFAULT_DRAIN_TIMEOUT)
                     .mutable_ready()
                     .build(),
-                glib::ParamSpecString::builder("stun-server")
-                    .nick("STUN Server")
-                    .blurb("The STUN server of the form stun://hostname:port")
-                    .default_value(DEFAULT_STUN_SERVER)
+                /**
+                 * RsBaseWebRTCSink:session-resume-timeout:
+                 *
+                 * When the signaller ends a session, keep it alive for this many
+                 * milliseconds rather than tearing it down right away. Media keeps
+                 * flowing to the consumer meanwhile, as the peer connection doesn't
+                 * depend on the signalling channel.
+                 *
+                 * If the signaller requests a session with the same ID for the same
+                 * consumer within that time, for instance after reconnecting to its
+                 * server, the session is resumed: a new offer with an ICE restart is
+                 * sent to the consumer through the signaller. Sessions the consumer
+                 * made the offer for are started over instead.
+                 *
+                 * 0 tears sessions down as soon as the signaller ends them.
+                 */
+                glib::ParamSpecUInt::builder("session-resume-timeout")
+                    .nick("Session resume timeout")
+                    .blurb("Time in milliseconds to keep sessions ended by the signaller for, in case they resume, 0 to disable")
+                    .default_value(DEFAULT_SESSION_RESUME_TIMEOUT)
+                    .mutable_ready()
                     .build(),
-                gst::ParamSpecArray::builder("turn-servers")
-                    .nick("List of TURN Servers to user")
-                    .blurb("The TURN servers of the form <\"turn(s)://username:password@host:port\", \"turn(s)://username1:password1@host1:port1\">")
-                    .element_spec(&glib::ParamSpecString::builder("turn-server")
-                        .nick("TURN Server")
-                        .blurb("The TURN server of the form turn(s)://username:password@host:port.")
-                        .build()
-                    )
+                /**
+                 * RsBaseWebRTCSink:connect-timeout:
+                 *
+                 * Maximum time in milliseconds a session may take to get connected,
+                 * that is to complete ICE and the DTLS handshake, once negotiation
+                 * started. Sessions that don't are ended through the signaller, and
+                 * a warning message with a `webrtcsink-connect-timeout` details
+                 * structure holding the `session-id` and `peer-id` is posted on the
+                 * bus.
+                 *
+                 * This avoids accumulating the encoders of half-open sessions with
+                 * peers that went away during negotiation.
+                 *
+                 * 0 waits for sessions to connect forever.
+                 */
+                glib::ParamSpecUInt::builder("connect-timeout")
+                    .nick("Connect timeout")
+                    .blurb("Time in milliseconds sessions have to get connected once negotiation started, 0 to disable")
+                    .default_value(DEFAULT_CONNECT_TIMEOUT)
                     .mutable_ready()
                     .build(),
-                glib::ParamSpecEnum::builder_with_default("congestion-control", DEFAULT_CONGESTION_CONTROL)
-                    .nick("Congestion control")
-                    .blurb("Defines how congestion is controlled, if at all")
+                /**
+                 * RsBaseWebRTCSink:require-approval:
+                 *
+                 * Hold new sessions until the application calls
+                 * #RsBaseWebRTCSink::approve-session, for instance from
+                 * #RsBaseWebRTCSink::consumer-added. Held sessions are negotiated
+                 * and connected as usual, but no media is sent to them, which can
+                 * be used to implement waiting rooms or pay-walls.
+                 */
+                glib::ParamSpecBoolean::builder("require-approval")
+                    .nick("Require approval")
+                    .blurb("Whether media only flows to new sessions once approved with the approve-session signal")
+                    .default_value(DEFAULT_REQUIRE_APPROVAL)
+                    .mutable_playing()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:signaller-migration-policy:
+                 *
+                 * What happens to the running sessions when the signaller is replaced
+                 * while the element is running: either they are ended, or the previous
+                 * signaller keeps handling them and is only stopped once the last of
+                 * them ended. In both cases the new signaller is started right away,
+                 * and new sessions requested by the previous signaller are refused.
+                 */
+                glib::ParamSpecEnum::builder_with_default("signaller-migration-policy", DEFAULT_SIGNALLER_MIGRATION_POLICY)
+                    .nick("Signaller migration policy")
+                    .blurb("What happens to the sessions of the signaller when replacing it while running")
+                    .mutable_playing()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:memory-budget:
+                 *
+                 * Maximum number of bytes queued for all the sessions together,
+                 * waiting to be encoded or sent, as happens when many consumers
+                 * stall at once. The queued data is checked periodically while
+                 * playing, and a warning message with a
+                 * `webrtcsink-memory-budget-exceeded` details structure holding
+                 * the `queued-bytes` and the `budget` is posted on the bus when it
+                 * exceeds the budget. See #RsBaseWebRTCSink:memory-budget-policy
+                 * for ending sessions as well.
+                 *
+                 * The bytes queued for each session are reported as `queued-bytes`
+                 * in its consumer stats.
+                 *
+                 * 0 disables the checks.
+                 */
+                glib::ParamSpecUInt64::builder("memory-budget")
+                    .nick("Memory budget")
+                    .blurb("Maximum number of bytes queued for all the sessions, 0 for no limit")
+                    .default_value(DEFAULT_MEMORY_BUDGET)
                     .mutable_ready()
                     .build(),
-                glib::ParamSpecUInt::builder("min-bitrate")
-                    .nick("Minimal Bitrate")
-                    .blurb("Minimal bitrate to use (in bit/sec) when computing it through the congestion control algorithm")
-                    .minimum(1)
-                    .maximum(u32::MAX)
-                    .default_value(DEFAULT_MIN_BITRATE)
+                /**
+                 * RsBaseWebRTCSink:memory-budget-policy:
+                 *
+                 * What happens when the data queued for the sessions exceeds the
+                 * #RsBaseWebRTCSink:memory-budget: besides the warning message, the
+                 * sessions queuing the most can be ended until the rest fits in the
+                 * budget again.
+                 */
+                glib::ParamSpecEnum::builder_with_default("memory-budget-policy", DEFAULT_MEMORY_BUDGET_POLICY)
+                    .nick("Memory budget policy")
+                    .blurb("What happens when the data queued for the sessions exceeds the memory budget")
+                    .mutable_playing()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:stats-message-interval:
+                 *
+                 * Interval at which the #RsBaseWebRTCSink:stats are posted on the
+                 * bus while playing, as an element message holding an
+                 * `application/x-webrtcsink-stats` structure, for applications to
+                 * consume them without polling the property. 0 disables the
+                 * messages.
+                 */
+                glib::ParamSpecUInt::builder("stats-message-interval")
+                    .nick("Stats message interval")
+                    .blurb("Interval in milliseconds at which stats are posted on the bus, 0 to disable")
+                    .default_value(DEFAULT_STATS_MESSAGE_INTERVAL)
                     .mutable_ready()
                     .build(),
-                glib::ParamSpecUInt::builder("max-bitrate")
-                    .nick("Maximum Bitrate")
-                    .blurb("Maximum bitrate to use (in bit/sec) when computing it through the congestion control algorithm")
+                /**
+                 * RsBaseWebRTCSink:verbose-stats:
+                 *
+                 * Whether the stats of each consumer include a `gcc-stats`
+                 * structure, the internal state of its rtpgccbwe bandwidth
+                 * estimator (delay gradient estimate, over-use threshold and
+                 * state, last rate control decision...), when the
+                 * `gcc` congestion control is in use. Refer to the
+                 * `stats` property of rtpgccbwe for the fields.
+                 */
+                glib::ParamSpecBoolean::builder("verbose-stats")
+                    .nick("Verbose stats")
+                    .blurb("Whether to include the internal state of the bandwidth estimator in the stats")
+                    .default_value(DEFAULT_VERBOSE_STATS)
+                    .mutable_playing()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:discovery-concurrency:
+                 *
+                 * Maximum number of codec discovery pipelines run in parallel
+                 * when selecting a codec for a consumer offer. The codec
+                 * preference order is preserved, and probes for NVENC encoders
+                 * are always serialized to avoid collisions.
+                 */
+                glib::ParamSpecUInt::builder("discovery-concurrency")
+                    .nick("Discovery concurrency")
+                    .blurb("Maximum number of codec discovery pipelines run in parallel during session setup")
                     .minimum(1)
-                    .maximum(u32::MAX)
-                    .default_value(DEFAULT_MAX_BITRATE)
+                    .default_value(DEFAULT_DISCOVERY_CONCURRENCY)
                     .mutable_ready()
                     .build(),
-                glib::ParamSpecUInt::builder("start-bitrate")
-                    .nick("Start Bitrate")
-                    .blurb("Start bitrate to use (in bit/sec)")
-                    .minimum(1)
-                    .maximum(u32::MAX)
-                    .default_value(DEFAULT_START_BITRATE)
+                /**
+                 * RsBaseWebRTCSink:shared-pipeline:
+                 *
+                 * Host all consumer sessions in a single pipeline, with a bin per
+                 * consumer instead of a pipeline per consumer. This reduces the
+                 * number of threads and the clock synchronization overhead when
+                 * serving many peers. #RsBaseWebRTCSink::consumer-pipeline-created
+                 * is then emitted with the bin of each consumer.
+                 */
+                glib::ParamSpecBoolean::builder("shared-pipeline")
+                    .nick("Shared pipeline")
+                    .blurb("Whether to host all consumer sessions in a single pipeline")
+                    .default_value(DEFAULT_SHARED_PIPELINE)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:audio-only-bitrate:
+                 *
+                 * When congestion control lowers the bitrate of a video encoder
+                 * below this value, video frames are dropped for that consumer
+                 * and only audio keeps flowing. Video is restored once the bitrate
+                 * recovers above this threshold.
+                 *
+                 * 0 disables the audio-only mitigation.
+                 */
+                glib::ParamSpecUInt::builder("audio-only-bitrate")
+                    .nick("Audio only bitrate")
+                    .blurb("Video bitrate (in bit/sec) below which video is disabled for a consumer, 0 to never disable video")
+                    .default_value(DEFAULT_AUDIO_ONLY_BITRATE)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:min-framerate:
+                 *
+                 * Framerate congestion control mitigations never lower the video
+                 * framerate below. As the bitrate decreases, high framerate
+                 * content is halved at each step of the mitigation ladder, e.g.
+                 * 240 fps content goes through 120, 60 then 30 fps, while 30 fps
+                 * content is only halved at the lowest bitrates. Raising this
+                 * value trades resolution for smoothness, as with fast paced game
+                 * capture.
+                 *
+                 * 0 lets the framerate be halved down to the last step.
+                 */
+                glib::ParamSpecUInt::builder("min-framerate")
+                    .nick("Minimum framerate")
+                    .blurb("Framerate congestion control never lowers the video framerate below, 0 for no minimum")
+                    .maximum(i32::MAX as u32)
+                    .default_value(DEFAULT_MIN_FRAMERATE)
                     .mutable_ready()
                     .build(),
                 glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
@@ -3265,21 +7929,235 @@ impl ObjectImpl for BaseWebRTCSink {
                     .default_value(DEFAULT_DO_RETRANSMISSION)
                     .mutable_ready()
                     .build(),
+                /**
+                 * RsBaseWebRTCSink:rtx-max-size-time:
+                 *
+                 * How long in milliseconds sent packets are kept around to be
+                 * retransmitted, 0 for no limit. The history must cover the round
+                 * trip time of the consumers for their retransmission requests to be
+                 * honored, the default packet based limit only covers a few frames.
+                 *
+                 * Can be changed while running, and applies to all the sessions.
+                 */
+                glib::ParamSpecUInt::builder("rtx-max-size-time")
+                    .nick("Retransmission max size time")
+                    .blurb("Time in milliseconds sent packets are kept around for retransmission, 0 for no limit")
+                    .default_value(DEFAULT_RTX_MAX_SIZE_TIME)
+                    .mutable_playing()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:rtx-max-size-packets:
+                 *
+                 * How many sent packets of each stream are kept around to be
+                 * retransmitted, 0 for no limit.
+                 *
+                 * Can be changed while running, and applies to all the sessions.
+                 */
+                glib::ParamSpecUInt::builder("rtx-max-size-packets")
+                    .nick("Retransmission max size packets")
+                    .blurb("Number of sent packets kept around for retransmission, 0 for no limit")
+                    .default_value(DEFAULT_RTX_MAX_SIZE_PACKETS)
+                    .mutable_playing()
+                    .build(),
                 glib::ParamSpecBoolean::builder("enable-data-channel-navigation")
                     .nick("Enable data channel navigation")
                     .blurb("Enable navigation events through a dedicated WebRTCDataChannel")
                     .default_value(DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION)
                     .mutable_ready()
                     .build(),
+                /**
+                 * RsBaseWebRTCSink:enable-frame-timing:
+                 *
+                 * Open a `frame-timing` data channel with each consumer, over which
+                 * a JSON message is sent for each video frame, for instance
+                 * `{"stream":"video_0","seqnum":42,"pts":1400000000,"capture-time":1700000000000000000}`.
+                 *
+                 * `pts` is the running time of the frame and `capture-time` the
+                 * UNIX time in nanoseconds at which it was captured, as reported
+                 * by a `timestamp/x-unix` reference timestamp meta, or at which it
+                 * was sent otherwise. Receivers can use it to measure the
+                 * end-to-end latency.
+                 */
+                glib::ParamSpecBoolean::builder("enable-frame-timing")
+                    .nick("Enable frame timing")
+                    .blurb("Send the timing of each video frame over a dedicated WebRTCDataChannel")
+                    .default_value(DEFAULT_ENABLE_FRAME_TIMING)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:thumbnail-interval:
+                 *
+                 * When not 0, open a `thumbnails` data channel with each consumer,
+                 * over which a JPEG thumbnail of each raw video input stream is
+                 * sent at this interval in milliseconds, for instance to show
+                 * previews in a stream selection UI. Each thumbnail is sent as
+                 * `{"stream":"video_0","pts":1400000000,"data":"<base64 JPEG>"}`.
+                 */
+                glib::ParamSpecUInt::builder("thumbnail-interval")
+                    .nick("Thumbnail interval")
+                    .blurb("Interval in milliseconds between the thumbnails sent over a dedicated WebRTCDataChannel, 0 to disable")
+                    .default_value(DEFAULT_THUMBNAIL_INTERVAL)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:thumbnail-width:
+                 *
+                 * Width of the thumbnails, their height preserves the aspect
+                 * ratio of the video.
+                 */
+                glib::ParamSpecUInt::builder("thumbnail-width")
+                    .nick("Thumbnail width")
+                    .blurb("Width of the thumbnails")
+                    .minimum(16)
+                    .maximum(1920)
+                    .default_value(DEFAULT_THUMBNAIL_WIDTH)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:do-clock-signalling:
+                 *
+                 * Timestamp the RTCP sender reports of all streams against the
+                 * pipeline clock and offer the RFC 6051 rapid synchronization
+                 * header extension, for receivers to synchronize audio and video
+                 * without waiting for the first sender reports.
+                 *
+                 * When multiple producers share a common clock, for instance a
+                 * #GstPtpClock or a #GstNtpClock, receivers can also synchronize
+                 * their streams with each other: that clock is advertised in the
+                 * SDP as per RFC 7273, and sent as JSON over a "clock" data channel
+                 * along with the base time of the producer.
+                 */
+                glib::ParamSpecBoolean::builder("do-clock-signalling")
+                    .nick("Do clock signalling")
+                    .blurb("Signal the pipeline clock in RTCP sender reports and RFC 6051 header extensions")
+                    .default_value(DEFAULT_DO_CLOCK_SIGNALLING)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:do-audio-level:
+                 *
+                 * Measure the level of raw audio input streams with a `level` element
+                 * and offer the RFC 6464 client-to-mixer audio level header extension,
+                 * for SFUs and receivers to detect active speakers without decoding.
+                 *
+                 * Streams encoded upstream carry the extension without levels.
+                 */
+                glib::ParamSpecBoolean::builder("do-audio-level")
+                    .nick("Do audio level")
+                    .blurb("Send the level of audio streams in RFC 6464 header extensions")
+                    .default_value(DEFAULT_DO_AUDIO_LEVEL)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:twcc-extension-id:
+                 *
+                 * ID of the transport-wide congestion control header extension
+                 * in our offers, when 0 the first ID that isn't reserved through
+                 * #RsBaseWebRTCSink:reserved-extension-ids is used.
+                 *
+                 * When answering, the ID picked by the offerer is used.
+                 */
+                glib::ParamSpecUInt::builder("twcc-extension-id")
+                    .nick("TWCC extension ID")
+                    .blurb("ID of the TWCC header extension when offering, 0 to pick one")
+                    .maximum(MAX_HEADER_EXTENSION_ID)
+                    .default_value(DEFAULT_TWCC_EXTENSION_ID)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:reserved-extension-ids:
+                 *
+                 * Header extension IDs the application uses for its own
+                 * extensions, for instance those added to the payloaders from
+                 * #RsBaseWebRTCSink::encoder-setup. The extensions added by
+                 * webrtcsink are assigned the remaining IDs in our offers.
+                 */
+                gst::ParamSpecArray::builder("reserved-extension-ids")
+                    .nick("Reserved extension IDs")
+                    .blurb("Header extension IDs left to the application")
+                    .element_spec(&glib::ParamSpecUInt::builder("reserved-extension-id")
+                        .nick("Reserved extension ID")
+                        .blurb("A header extension ID")
+                        .minimum(1)
+                        .maximum(MAX_HEADER_EXTENSION_ID)
+                        .default_value(1)
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:dscp:
+                 *
+                 * The priority of the outgoing media, from which webrtcbin
+                 * derives the DSCP marking of the packets as recommended by
+                 * RFC 8837, for networks prioritizing traffic accordingly:
+                 *
+                 * - very-low: CS1 for all media
+                 * - low: DF (no marking) for all media
+                 * - medium: EF for audio, AF42 for video
+                 * - high: EF for audio, AF41 for video
+                 */
+                glib::ParamSpecEnum::builder_with_default("dscp", DEFAULT_DSCP)
+                    .nick("DSCP")
+                    .blurb("Priority from which the DSCP marking of the outgoing media packets is derived")
+                    .mutable_ready()
+                    .build(),
                 glib::ParamSpecBoxed::builder::<gst::Structure>("meta")
                     .nick("Meta")
                     .blurb("Free form metadata about the producer")
                     .build(),
+                /**
+                 * RsBaseWebRTCSink:instance-id:
+                 *
+                 * Identifies this producer among the replicas of a horizontally
+                 * scaled deployment. It is advertised through the signaller as the
+                 * `instance-id` field of the producer meta when registering, and
+                 * echoed as a field of the #RsBaseWebRTCSink:stats, for orchestration
+                 * layers to route consumers to a specific replica and correlate
+                 * metrics across processes.
+                 */
+                glib::ParamSpecString::builder("instance-id")
+                    .nick("Instance ID")
+                    .blurb("Identifier of this producer replica, advertised in the meta and stats")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink:labels:
+                 *
+                 * Free form labels of this producer, for instance its region or
+                 * capacity tier, advertised and echoed like #RsBaseWebRTCSink:instance-id
+                 * as the `labels` field.
+                 */
+                glib::ParamSpecBoxed::builder::<gst::Structure>("labels")
+                    .nick("Labels")
+                    .blurb("Free form labels of this producer, advertised in the meta and stats")
+                    .mutable_ready()
+                    .build(),
                 glib::ParamSpecEnum::builder_with_default("ice-transport-policy", DEFAULT_ICE_TRANSPORT_POLICY)
                     .nick("ICE Transport Policy")
                     .blurb("The policy to apply for ICE transport")
                     .mutable_ready()
                     .build(),
+                /**
+                 * RsBaseWebRTCSink:playout-delay:
+                 *
+                 * Minimum and maximum playout delay in milliseconds, of the
+                 * form <min, max>, signalled to the consumers through the
+                 * playout-delay RTP header extension on video streams.
+                 *
+                 * An empty array disables the extension.
+                 */
+                gst::ParamSpecArray::builder("playout-delay")
+                    .nick("Playout delay")
+                    .blurb("Minimum and maximum playout delay in milliseconds of the form <min, max>, empty to disable")
+                    .element_spec(&glib::ParamSpecUInt::builder("delay")
+                        .nick("Delay")
+                        .blurb("Playout delay in milliseconds")
+                        .maximum(PLAYOUT_DELAY_MAX_MS)
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
                 glib::ParamSpecObject::builder::<Signallable>("signaller")
                     .flags(glib::ParamFlags::READABLE  | gst::PARAM_FLAG_MUTABLE_READY)
                     .blurb("The Signallable object to use to handle WebRTC Signalling")
@@ -3316,23 +8194,202 @@ impl ObjectImpl for BaseWebRTCSink {
                 let mut settings = self.settings.lock().unwrap();
                 settings.turn_servers = value.get::<gst::Array>().expect("type checked upstream")
             }
-            "congestion-control" => {
+            "ice-candidate-type-order" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.ice_candidate_type_order = value
+                    .get::<gst::Array>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .filter_map(|v| v.get::<String>().ok())
+                    .collect();
+            }
+            "ice-local-addresses" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.ice_local_addresses = value
+                    .get::<gst::Array>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .filter_map(|v| v.get::<String>().ok())
+                    .collect();
+            }
+            "ice-candidate-batch-interval" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.ice_candidate_batch_interval =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "srtp-profiles" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.srtp_profiles = value
+                    .get::<gst::Array>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .filter_map(|v| v.get::<String>().ok())
+                    .collect();
+            }
+            "interop-rules" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.interop_rules = value
+                    .get::<gst::Array>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .filter_map(|v| v.get::<gst::Structure>().ok())
+                    .filter(|rule| match interop::validate_rule(rule) {
+                        Ok(()) => true,
+                        Err(err) => {
+                            gst::error!(CAT, imp: self, "Ignoring interop rule: {err}");
+                            false
+                        }
+                    })
+                    .collect();
+            }
+            "congestion-control" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.heuristic = value
+                    .get::<WebRTCSinkCongestionControl>()
+                    .expect("type checked upstream");
+            }
+            "min-bitrate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.min_bitrate = value.get::<u32>().expect("type checked upstream");
+            }
+            "max-bitrate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.max_bitrate = value.get::<u32>().expect("type checked upstream");
+            }
+            "start-bitrate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.start_bitrate = value.get::<u32>().expect("type checked upstream");
+            }
+            "audio-only-bitrate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.audio_only_bitrate =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "min-framerate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.min_framerate = value.get::<u32>().expect("type checked upstream");
+            }
+            "prefer-hardware-encoders" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.prefer_hardware_encoders =
+                    value.get::<bool>().expect("type checked upstream");
+            }
+            "max-hardware-encoders" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.max_hardware_encoders = value.get::<u32>().expect("type checked upstream");
+            }
+            "bandwidth-group" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.bandwidth_group = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+            }
+            "bandwidth-group-bitrate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.bandwidth_group_bitrate =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "audio-fec" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.audio_fec = value.get::<bool>().expect("type checked upstream");
+            }
+            "audio-dtx" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.audio_dtx = value.get::<bool>().expect("type checked upstream");
+            }
+            "audio-min-bitrate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.audio_min_bitrate =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "audio-max-bitrate" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.audio_max_bitrate =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "input-timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.input_timeout = value.get::<u32>().expect("type checked upstream");
+            }
+            "stats-collection-interval" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.stats_collection_interval =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "drain-timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.drain_timeout = value.get::<u32>().expect("type checked upstream");
+            }
+            "session-resume-timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.session_resume_timeout =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "connect-timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.connect_timeout = value.get::<u32>().expect("type checked upstream");
+            }
+            "require-approval" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.require_approval = value.get::<bool>().expect("type checked upstream");
+            }
+            "signaller-migration-policy" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.signaller_migration_policy = value
+                    .get::<WebRTCSinkSignallerMigrationPolicy>()
+                    .expect("type checked upstream");
+            }
+            "memory-budget" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.memory_budget = value.get::<u64>().expect("type checked upstream");
+            }
+            "memory-budget-policy" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.memory_budget_policy = value
+                    .get::<WebRTCSinkMemoryBudgetPolicy>()
+                    .expect("type checked upstream");
+            }
+            "stats-message-interval" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.stats_message_interval =
+                    value.get::<u32>().expect("type checked upstream");
+            }
+            "verbose-stats" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.verbose_stats = value.get::<bool>().expect("type checked upstream");
+            }
+            "shared-pipeline" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.shared_pipeline = value.get::<bool>().expect("type checked upstream");
+            }
+            "discovery-concurrency" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.discovery_concurrency = value.get::<u32>().expect("type checked upstream");
+            }
+            "homegrown-increase-factor" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.cc_info.homegrown.increase_factor =
+                    value.get::<f64>().expect("type checked upstream");
+            }
+            "homegrown-decrease-factor" => {
                 let mut settings = self.settings.lock().unwrap();
-                settings.cc_info.heuristic = value
-                    .get::<WebRTCSinkCongestionControl>()
-                    .expect("type checked upstream");
+                settings.cc_info.homegrown.decrease_factor =
+                    value.get::<f64>().expect("type checked upstream");
             }
-            "min-bitrate" => {
+            "homegrown-loss-threshold-high" => {
                 let mut settings = self.settings.lock().unwrap();
-                settings.cc_info.min_bitrate = value.get::<u32>().expect("type checked upstream");
+                settings.cc_info.homegrown.loss_threshold_high =
+                    value.get::<f64>().expect("type checked upstream");
             }
-            "max-bitrate" => {
+            "homegrown-loss-threshold-low" => {
                 let mut settings = self.settings.lock().unwrap();
-                settings.cc_info.max_bitrate = value.get::<u32>().expect("type checked upstream");
+                settings.cc_info.homegrown.loss_threshold_low =
+                    value.get::<f64>().expect("type checked upstream");
             }
-            "start-bitrate" => {
+            "homegrown-update-interval" => {
                 let mut settings = self.settings.lock().unwrap();
-                settings.cc_info.start_bitrate = value.get::<u32>().expect("type checked upstream");
+                settings.cc_info.homegrown.update_interval =
+                    value.get::<u32>().expect("type checked upstream");
             }
             "do-fec" => {
                 let mut settings = self.settings.lock().unwrap();
@@ -3342,23 +8399,111 @@ impl ObjectImpl for BaseWebRTCSink {
                 let mut settings = self.settings.lock().unwrap();
                 settings.do_retransmission = value.get::<bool>().expect("type checked upstream");
             }
+            "rtx-max-size-time" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtx_max_size_time = value.get::<u32>().expect("type checked upstream");
+                drop(settings);
+                self.configure_retransmission();
+            }
+            "rtx-max-size-packets" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.rtx_max_size_packets = value.get::<u32>().expect("type checked upstream");
+                drop(settings);
+                self.configure_retransmission();
+            }
             "enable-data-channel-navigation" => {
                 let mut settings = self.settings.lock().unwrap();
                 settings.enable_data_channel_navigation =
                     value.get::<bool>().expect("type checked upstream");
             }
+            "enable-frame-timing" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.enable_frame_timing = value.get::<bool>().expect("type checked upstream");
+            }
+            "thumbnail-interval" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.thumbnail_interval = value.get::<u32>().expect("type checked upstream");
+            }
+            "thumbnail-width" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.thumbnail_width = value.get::<u32>().expect("type checked upstream");
+            }
+            "do-clock-signalling" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.do_clock_signalling = value.get::<bool>().expect("type checked upstream");
+            }
+            "do-audio-level" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.do_audio_level = value.get::<bool>().expect("type checked upstream");
+            }
+            "twcc-extension-id" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.twcc_extension_id = value.get::<u32>().expect("type checked upstream");
+            }
+            "reserved-extension-ids" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.reserved_extension_ids = value
+                    .get::<gst::Array>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .filter_map(|v| v.get::<u32>().ok())
+                    .collect();
+            }
+            "dscp" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.dscp = value
+                    .get::<WebRTCPriorityType>()
+                    .expect("type checked upstream");
+            }
             "meta" => {
                 let mut settings = self.settings.lock().unwrap();
                 settings.meta = value
                     .get::<Option<gst::Structure>>()
                     .expect("type checked upstream")
             }
+            "instance-id" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.instance_id = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream")
+            }
+            "labels" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.labels = value
+                    .get::<Option<gst::Structure>>()
+                    .expect("type checked upstream")
+            }
             "ice-transport-policy" => {
                 let mut settings = self.settings.lock().unwrap();
                 settings.ice_transport_policy = value
                     .get::<WebRTCICETransportPolicy>()
                     .expect("type checked upstream");
             }
+            "playout-delay" => {
+                let mut settings = self.settings.lock().unwrap();
+                let delays = value
+                    .get::<gst::Array>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .map(|v| v.get::<u32>())
+                    .collect::<Result<Vec<_>, _>>();
+
+                // Invalid values are rejected and the previous setting kept
+                settings.playout_delay = match delays.as_deref() {
+                    Ok([]) => None,
+                    Ok(&[min, max]) if min <= max && max <= PLAYOUT_DELAY_MAX_MS => {
+                        Some(PlayoutDelay { min, max })
+                    }
+                    _ => {
+                        gst::warning!(
+                            CAT,
+                            imp: self,
+                            "Ignoring invalid playout delay {value:?}, expected <min, max> with min <= max"
+                        );
+                        return;
+                    }
+                };
+            }
             _ => unimplemented!(),
         }
     }
@@ -3385,6 +8530,26 @@ impl ObjectImpl for BaseWebRTCSink {
                 let settings = self.settings.lock().unwrap();
                 settings.turn_servers.to_value()
             }
+            "ice-candidate-type-order" => {
+                let settings = self.settings.lock().unwrap();
+                gst::Array::new(&settings.ice_candidate_type_order).to_value()
+            }
+            "ice-local-addresses" => {
+                let settings = self.settings.lock().unwrap();
+                gst::Array::new(&settings.ice_local_addresses).to_value()
+            }
+            "ice-candidate-batch-interval" => {
+                let settings = self.settings.lock().unwrap();
+                settings.ice_candidate_batch_interval.to_value()
+            }
+            "srtp-profiles" => {
+                let settings = self.settings.lock().unwrap();
+                gst::Array::new(&settings.srtp_profiles).to_value()
+            }
+            "interop-rules" => {
+                let settings = self.settings.lock().unwrap();
+                gst::Array::new(&settings.interop_rules).to_value()
+            }
             "min-bitrate" => {
                 let settings = self.settings.lock().unwrap();
                 settings.cc_info.min_bitrate.to_value()
@@ -3397,6 +8562,118 @@ impl ObjectImpl for BaseWebRTCSink {
                 let settings = self.settings.lock().unwrap();
                 settings.cc_info.start_bitrate.to_value()
             }
+            "audio-only-bitrate" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.audio_only_bitrate.to_value()
+            }
+            "min-framerate" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.min_framerate.to_value()
+            }
+            "prefer-hardware-encoders" => {
+                let settings = self.settings.lock().unwrap();
+                settings.prefer_hardware_encoders.to_value()
+            }
+            "max-hardware-encoders" => {
+                let settings = self.settings.lock().unwrap();
+                settings.max_hardware_encoders.to_value()
+            }
+            "bandwidth-group" => {
+                let settings = self.settings.lock().unwrap();
+                settings.bandwidth_group.to_value()
+            }
+            "bandwidth-group-bitrate" => {
+                let settings = self.settings.lock().unwrap();
+                settings.bandwidth_group_bitrate.to_value()
+            }
+            "audio-fec" => {
+                let settings = self.settings.lock().unwrap();
+                settings.audio_fec.to_value()
+            }
+            "audio-dtx" => {
+                let settings = self.settings.lock().unwrap();
+                settings.audio_dtx.to_value()
+            }
+            "audio-min-bitrate" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.audio_min_bitrate.to_value()
+            }
+            "audio-max-bitrate" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.audio_max_bitrate.to_value()
+            }
+            "input-timeout" => {
+                let settings = self.settings.lock().unwrap();
+                settings.input_timeout.to_value()
+            }
+            "stats-collection-interval" => {
+                let settings = self.settings.lock().unwrap();
+                settings.stats_collection_interval.to_value()
+            }
+            "drain-timeout" => {
+                let settings = self.settings.lock().unwrap();
+                settings.drain_timeout.to_value()
+            }
+            "session-resume-timeout" => {
+                let settings = self.settings.lock().unwrap();
+                settings.session_resume_timeout.to_value()
+            }
+            "connect-timeout" => {
+                let settings = self.settings.lock().unwrap();
+                settings.connect_timeout.to_value()
+            }
+            "require-approval" => {
+                let settings = self.settings.lock().unwrap();
+                settings.require_approval.to_value()
+            }
+            "signaller-migration-policy" => {
+                let settings = self.settings.lock().unwrap();
+                settings.signaller_migration_policy.to_value()
+            }
+            "memory-budget" => {
+                let settings = self.settings.lock().unwrap();
+                settings.memory_budget.to_value()
+            }
+            "memory-budget-policy" => {
+                let settings = self.settings.lock().unwrap();
+                settings.memory_budget_policy.to_value()
+            }
+            "stats-message-interval" => {
+                let settings = self.settings.lock().unwrap();
+                settings.stats_message_interval.to_value()
+            }
+            "verbose-stats" => {
+                let settings = self.settings.lock().unwrap();
+                settings.verbose_stats.to_value()
+            }
+            "shared-pipeline" => {
+                let settings = self.settings.lock().unwrap();
+                settings.shared_pipeline.to_value()
+            }
+            "discovery-concurrency" => {
+                let settings = self.settings.lock().unwrap();
+                settings.discovery_concurrency.to_value()
+            }
+            "homegrown-increase-factor" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.homegrown.increase_factor.to_value()
+            }
+            "homegrown-decrease-factor" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.homegrown.decrease_factor.to_value()
+            }
+            "homegrown-loss-threshold-high" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.homegrown.loss_threshold_high.to_value()
+            }
+            "homegrown-loss-threshold-low" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.homegrown.loss_threshold_low.to_value()
+            }
+            "homegrown-update-interval" => {
+                let settings = self.settings.lock().unwrap();
+                settings.cc_info.homegrown.update_interval.to_value()
+            }
             "do-fec" => {
                 let settings = self.settings.lock().unwrap();
                 settings.do_fec.to_value()
@@ -3405,19 +8682,74 @@ impl ObjectImpl for BaseWebRTCSink {
                 let settings = self.settings.lock().unwrap();
                 settings.do_retransmission.to_value()
             }
+            "rtx-max-size-time" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtx_max_size_time.to_value()
+            }
+            "rtx-max-size-packets" => {
+                let settings = self.settings.lock().unwrap();
+                settings.rtx_max_size_packets.to_value()
+            }
             "enable-data-channel-navigation" => {
                 let settings = self.settings.lock().unwrap();
                 settings.enable_data_channel_navigation.to_value()
             }
+            "enable-frame-timing" => {
+                let settings = self.settings.lock().unwrap();
+                settings.enable_frame_timing.to_value()
+            }
+            "thumbnail-interval" => {
+                let settings = self.settings.lock().unwrap();
+                settings.thumbnail_interval.to_value()
+            }
+            "thumbnail-width" => {
+                let settings = self.settings.lock().unwrap();
+                settings.thumbnail_width.to_value()
+            }
+            "do-clock-signalling" => {
+                let settings = self.settings.lock().unwrap();
+                settings.do_clock_signalling.to_value()
+            }
+            "do-audio-level" => {
+                let settings = self.settings.lock().unwrap();
+                settings.do_audio_level.to_value()
+            }
+            "twcc-extension-id" => {
+                let settings = self.settings.lock().unwrap();
+                settings.twcc_extension_id.to_value()
+            }
+            "reserved-extension-ids" => {
+                let settings = self.settings.lock().unwrap();
+                gst::Array::new(&settings.reserved_extension_ids).to_value()
+            }
+            "dscp" => {
+                let settings = self.settings.lock().unwrap();
+                settings.dscp.to_value()
+            }
             "stats" => self.gather_stats().to_value(),
             "meta" => {
                 let settings = self.settings.lock().unwrap();
                 settings.meta.to_value()
             }
+            "instance-id" => {
+                let settings = self.settings.lock().unwrap();
+                settings.instance_id.to_value()
+            }
+            "labels" => {
+                let settings = self.settings.lock().unwrap();
+                settings.labels.to_value()
+            }
             "ice-transport-policy" => {
                 let settings = self.settings.lock().unwrap();
                 settings.ice_transport_policy.to_value()
             }
+            "playout-delay" => {
+                let settings = self.settings.lock().unwrap();
+                match settings.playout_delay {
+                    Some(PlayoutDelay { min, max }) => gst::Array::new([min, max]).to_value(),
+                    None => gst::Array::new(Vec::<u32>::new()).to_value(),
+                }
+            }
             "signaller" => self.settings.lock().unwrap().signaller.to_value(),
             _ => unimplemented!(),
         }
@@ -3447,49 +8779,275 @@ impl ObjectImpl for BaseWebRTCSink {
                  * #GstBin::deep-element-added and tweak properties of any element used
                  * by the pipeline.
                  *
-                 * This provides access to the lower level components of webrtcsink, and
-                 * no guarantee is made that its internals will remain stable, use with caution!
+                 * When #RsBaseWebRTCSink:shared-pipeline is enabled, consumers don't
+                 * get their own pipeline and @pipeline is the bin of the consumer in
+                 * the shared pipeline instead.
+                 *
+                 * This provides access to the lower level components of webrtcsink, and
+                 * no guarantee is made that its internals will remain stable, use with caution!
+                 *
+                 * This is emitted *before* #RsBaseWebRTCSink::consumer-added .
+                 */
+                glib::subclass::Signal::builder("consumer-pipeline-created")
+                    .param_types([String::static_type(), gst::Bin::static_type()])
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::consumer_removed:
+                 * @consumer_id: Identifier of the consumer that was removed
+                 * @webrtcbin: The webrtcbin connected to the newly removed consumer
+                 *
+                 * This signal is emitted right after the connection with a consumer
+                 * has been dropped.
+                 */
+                glib::subclass::Signal::builder("consumer-removed")
+                    .param_types([String::static_type(), gst::Element::static_type()])
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::session-negotiated:
+                 * @session_id: Identifier of the session
+                 * @stream_name: The name of the corresponding input stream
+                 * @caps: The caps of the codec negotiated for that stream
+                 *
+                 * This signal is emitted once the codec of an input stream has
+                 * been agreed upon with the consumer and the stream is connected
+                 * to the session.
+                 */
+                glib::subclass::Signal::builder("session-negotiated")
+                    .param_types([
+                        String::static_type(),
+                        String::static_type(),
+                        gst::Caps::static_type(),
+                    ])
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::session-connected:
+                 * @session_id: Identifier of the session
+                 * @dtls_info: The parameters of the DTLS connection
+                 *
+                 * This signal is emitted once the DTLS handshake with the consumer
+                 * completed. @dtls_info holds the DTLS `role` webrtcsink played
+                 * (`client` or `server`), the `local-fingerprint` and
+                 * `remote-fingerprint` of the certificates as advertised in the
+                 * SDP, and the `handshake-duration` from the moment ICE connected.
+                 *
+                 * The same information is reported as `dtls` in the
+                 * consumer stats of the session, and can help diagnose
+                 * middleboxes interfering with the handshake.
+                 */
+                glib::subclass::Signal::builder("session-connected")
+                    .param_types([String::static_type(), gst::Structure::static_type()])
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::get_sessions:
+                 *
+                 * List all sessions (by ID).
+                 */
+                glib::subclass::Signal::builder("get-sessions")
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let this = element.imp();
+
+                        let res = Some(
+                            this.state
+                                .lock()
+                                .unwrap()
+                                .sessions
+                                .keys()
+                                .cloned()
+                                .collect::<Vec<String>>()
+                                .to_value(),
+                        );
+                        res
+                    })
+                    .return_type::<Vec<String>>()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::get-streams:
+                 *
+                 * Describes each input stream, sorted by name, with a structure
+                 * holding:
+                 *
+                 * - name: the name of the sink pad
+                 * - serial: the serial number of the stream, among streams of
+                 *   the same type
+                 * - is-video: whether the stream is video
+                 * - in-caps: the caps of the input, if received yet
+                 * - codecs: the encoding names discovered for the stream
+                 * - discovery: "waiting-for-caps", "discovering" or "done"
+                 * - n-connected-sessions: the number of connected sessions
+                 *   the stream was negotiated with
+                 *
+                 * Returns: an array of structures, one per stream
+                 */
+                glib::subclass::Signal::builder("get-streams")
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let this = element.imp();
+
+                        Some(this.streams_info().to_value())
+                    })
+                    .return_type::<gst::Array>()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::wait-for-consumers:
+                 * @n_consumers: The number of consumers to wait for
+                 * @timeout: The maximum time to wait for, or %GST_CLOCK_TIME_NONE
+                 *   to wait forever
+                 *
+                 * Blocks until at least @n_consumers sessions have their peer
+                 * connection established, for instance in scripted pipelines or
+                 * broadcast automation that must not start playback before viewers
+                 * are attached.
+                 *
+                 * Must not be called from a streaming thread of the element.
+                 *
+                 * Returns: %TRUE if enough consumers are connected, %FALSE if
+                 * @timeout expired first
+                 */
+                glib::subclass::Signal::builder("wait-for-consumers")
+                    .param_types([u32::static_type(), gst::ClockTime::static_type()])
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let n_consumers = args[1].get::<u32>().expect("signal arg");
+                        let timeout = args[2].get::<Option<gst::ClockTime>>().expect("signal arg");
+                        let this = element.imp();
+
+                        Some(this.wait_for_consumers(n_consumers, timeout).to_value())
+                    })
+                    .return_type::<bool>()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::pause-session:
+                 * @session_id: The ID of the session to pause
+                 *
+                 * Stops feeding media to the session, without tearing down the
+                 * underlying ICE and DTLS connection. When webrtcsink made the
+                 * offer, the session is renegotiated with its media inactive,
+                 * and again with its media active on resume-session.
+                 *
+                 * Returns: %TRUE if the session was found
+                 */
+                glib::subclass::Signal::builder("pause-session")
+                    .param_types([String::static_type()])
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let session_id = args[1].get::<&str>().expect("signal arg");
+                        let this = element.imp();
+
+                        Some(this.set_session_paused(session_id, true).to_value())
+                    })
+                    .return_type::<bool>()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::resume-session:
+                 * @session_id: The ID of the session to resume
+                 *
+                 * Resumes feeding media to a session previously paused with
+                 * #RsBaseWebRTCSink::pause-session.
+                 *
+                 * Sessions awaiting approval with #RsBaseWebRTCSink:require-approval
+                 * can only be resumed with #RsBaseWebRTCSink::approve-session.
+                 *
+                 * Returns: %TRUE if the session was found
+                 */
+                glib::subclass::Signal::builder("resume-session")
+                    .param_types([String::static_type()])
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let session_id = args[1].get::<&str>().expect("signal arg");
+                        let this = element.imp();
+
+                        Some(this.set_session_paused(session_id, false).to_value())
+                    })
+                    .return_type::<bool>()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::approve-session:
+                 * @session_id: The ID of the session to approve
+                 *
+                 * Starts feeding media to a session held because of
+                 * #RsBaseWebRTCSink:require-approval, for instance once the consumer
+                 * left a waiting room or paid for the stream.
                  *
-                 * This is emitted *before* #RsBaseWebRTCSink::consumer-added .
+                 * Returns: %TRUE if the session was awaiting approval
                  */
-                glib::subclass::Signal::builder("consumer-pipeline-created")
-                    .param_types([String::static_type(), gst::Pipeline::static_type()])
+                glib::subclass::Signal::builder("approve-session")
+                    .param_types([String::static_type()])
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let session_id = args[1].get::<&str>().expect("signal arg");
+                        let this = element.imp();
+
+                        Some(this.approve_session(session_id).to_value())
+                    })
+                    .return_type::<bool>()
                     .build(),
                 /**
-                 * RsBaseWebRTCSink::consumer_removed:
-                 * @consumer_id: Identifier of the consumer that was removed
-                 * @webrtcbin: The webrtcbin connected to the newly removed consumer
+                 * RsBaseWebRTCSink::restart-ice:
+                 * @session_id: (nullable): The ID of the session, or %NULL for all sessions
                  *
-                 * This signal is emitted right after the connection with a consumer
-                 * has been dropped.
+                 * Renegotiates the matching sessions with an ICE restart, using the
+                 * current #RsBaseWebRTCSink:turn-servers. This is useful when TURN
+                 * credentials are short-lived: update the property or the credentials
+                 * returned by #RsBaseWebRTCSink::request-turn-credentials, then restart
+                 * ICE for existing sessions to pick up the new servers.
+                 *
+                 * webrtcbin can't forget TURN servers, only the first server handed
+                 * to a session is replaced. When the others changed, or all servers
+                 * were removed, the sessions are still restarted with the current
+                 * servers added, but keep the stale ones and %FALSE is returned. End
+                 * these sessions for the consumers to reconnect if the stale servers
+                 * must not be used anymore.
+                 *
+                 * Sessions for which the consumer made the offer can't be restarted
+                 * by us and are skipped.
+                 *
+                 * Returns: %TRUE if at least one session was restarted, and the
+                 * restarted sessions only use the current TURN servers
                  */
-                glib::subclass::Signal::builder("consumer-removed")
-                    .param_types([String::static_type(), gst::Element::static_type()])
+                glib::subclass::Signal::builder("restart-ice")
+                    .param_types([Option::<String>::static_type()])
+                    .action()
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let session_id = args[1].get::<Option<String>>().expect("signal arg");
+                        let this = element.imp();
+
+                        Some(this.restart_ice(session_id.as_deref()).to_value())
+                    })
+                    .return_type::<bool>()
                     .build(),
                 /**
-                 * RsBaseWebRTCSink::get_sessions:
+                 * RsBaseWebRTCSink::force-keyframe:
+                 * @session_id: (nullable): The ID of the session, or %NULL for all sessions
+                 * @stream_name: (nullable): The name of the input pad, or %NULL for all streams
                  *
-                 * List all sessions (by ID).
+                 * Requests a keyframe from the encoders feeding the matching sessions
+                 * and streams, for instance when a recorder attaches mid-stream.
+                 *
+                 * Returns: %TRUE if at least one matching stream was found
                  */
-                glib::subclass::Signal::builder("get-sessions")
+                glib::subclass::Signal::builder("force-keyframe")
+                    .param_types([Option::<String>::static_type(), Option::<String>::static_type()])
                     .action()
                     .class_handler(|_, args| {
                         let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let session_id = args[1].get::<Option<String>>().expect("signal arg");
+                        let stream_name = args[2].get::<Option<String>>().expect("signal arg");
                         let this = element.imp();
 
-                        let res = Some(
-                            this.state
-                                .lock()
-                                .unwrap()
-                                .sessions
-                                .keys()
-                                .cloned()
-                                .collect::<Vec<String>>()
+                        Some(
+                            this.force_keyframe(session_id.as_deref(), stream_name.as_deref())
                                 .to_value(),
-                        );
-                        res
+                        )
                     })
-                    .return_type::<Vec<String>>()
+                    .return_type::<bool>()
                     .build(),
                 /**
                  * RsBaseWebRTCSink::encoder-setup:
@@ -3524,12 +9082,137 @@ impl ObjectImpl for BaseWebRTCSink {
 
                         let this = element.imp();
                         let settings = this.settings.lock().unwrap();
-                        configure_encoder(&enc, settings.cc_info.start_bitrate);
+                        configure_encoder(&enc, &settings);
 
                         // Return false here so that latter handlers get called
                         Some(false.to_value())
                     })
                     .build(),
+                /**
+                 * RsBaseWebRTCSink::request-hardware-encoder:
+                 * @consumer_id: Identifier of the consumer
+                 * @pad_name: The name of the corresponding input pad
+                 *
+                 * This signal can be used to decide, for each consumer, whether
+                 * its stream should be encoded with a hardware encoder, for
+                 * instance to reserve a limited number of hardware encoding
+                 * sessions to some consumers. It is emitted before negotiating
+                 * with the consumer, the caps of the stream are discovered
+                 * again when the picked encoder differs from the default one.
+                 *
+                 * The first handler to return stops the emission, by default
+                 * the value of #RsBaseWebRTCSink:prefer-hardware-encoders is used.
+                 *
+                 * Returns: True to prefer a hardware encoder, False to prefer
+                 * a software encoder
+                 */
+                glib::subclass::Signal::builder("request-hardware-encoder")
+                    .param_types([String::static_type(), String::static_type()])
+                    .return_type::<bool>()
+                    .accumulator(|_hint, ret, value| {
+                        *ret = value.clone();
+                        false
+                    })
+                    .class_handler(|_, args| {
+                        let element = args[0].get::<super::BaseWebRTCSink>().expect("signal arg");
+                        let this = element.imp();
+
+                        Some(
+                            this.settings
+                                .lock()
+                                .unwrap()
+                                .prefer_hardware_encoders
+                                .to_value(),
+                        )
+                    })
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::request-stream-selection:
+                 * @session_id: Identifier of the session
+                 * @peer_id: Identifier of the consumer
+                 * @streams: The names of all the input streams
+                 *
+                 * This signal can be used to offer only a subset of the input
+                 * streams to a given consumer, for instance only the audio or
+                 * one of several cameras. No transceiver is created for the
+                 * streams left out of the returned array.
+                 *
+                 * The first handler to return stops the emission, by default
+                 * all the streams are offered.
+                 */
+                glib::subclass::Signal::builder("request-stream-selection")
+                    .param_types([
+                        String::static_type(),
+                        String::static_type(),
+                        gst::Array::static_type(),
+                    ])
+                    .return_type::<gst::Array>()
+                    .accumulator(|_hint, ret, value| {
+                        *ret = value.clone();
+                        false
+                    })
+                    .class_handler(|_, args| Some(args[3].clone()))
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::munge-sdp:
+                 * @session_id: Identifier of the session
+                 * @local: Whether the description is ours or the consumer's
+                 * @description: The session description
+                 *
+                 * This signal can be used to modify the SDP exchanged with
+                 * a consumer, for instance to add bandwidth lines or remove
+                 * header extensions for interoperability with picky endpoints.
+                 *
+                 * Our descriptions are emitted after being set on webrtcbin,
+                 * right before being sent through the signaller, and the
+                 * descriptions of the consumer right after being received,
+                 * before being set on webrtcbin.
+                 *
+                 * The first handler to return stops the emission, by default
+                 * the description is left untouched.
+                 */
+                glib::subclass::Signal::builder("munge-sdp")
+                    .param_types([
+                        String::static_type(),
+                        bool::static_type(),
+                        gst_webrtc::WebRTCSessionDescription::static_type(),
+                    ])
+                    .return_type::<gst_webrtc::WebRTCSessionDescription>()
+                    .accumulator(|_hint, ret, value| {
+                        *ret = value.clone();
+                        false
+                    })
+                    .class_handler(|_, args| Some(args[3].clone()))
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::candidate-filter:
+                 * @session_id: Identifier of the session
+                 * @sdp_m_line_index: The m-line index of the candidate
+                 * @candidate: The local ICE candidate
+                 *
+                 * This signal can be used to keep some of the local candidates
+                 * from being sent to a consumer, for instance host or link-local
+                 * candidates that would leak private addresses, or the candidates
+                 * of an address family the consumer can't reach.
+                 *
+                 * The first handler to return stops the emission, by default
+                 * all the candidates are sent.
+                 *
+                 * Returns: True to send the candidate, False to drop it
+                 */
+                glib::subclass::Signal::builder("candidate-filter")
+                    .param_types([
+                        String::static_type(),
+                        u32::static_type(),
+                        String::static_type(),
+                    ])
+                    .return_type::<bool>()
+                    .accumulator(|_hint, ret, value| {
+                        *ret = value.clone();
+                        false
+                    })
+                    .class_handler(|_, _| Some(true.to_value()))
+                    .build(),
                 /**
                  * RsWebRTCSink::request-encoded-filter:
                  * @consumer_id: Identifier of the consumer
@@ -3551,6 +9234,50 @@ impl ObjectImpl for BaseWebRTCSink {
                     ])
                     .return_type::<gst::Element>()
                     .build(),
+                /**
+                 * RsBaseWebRTCSink::request-video-filter:
+                 * @consumer_id: Identifier of the consumer
+                 * @pad_name: The name of the corresponding input pad
+                 * @raw_caps: The Caps of the raw video stream
+                 *
+                 * The raw video counterpart of #RsWebRTCSink::request-encoded-filter,
+                 * emitted for each session when connecting a raw video input stream.
+                 * The returned element is inserted before the encoder of that session
+                 * only, for instance an `overlaycomposition` or `textoverlay` adding
+                 * the name of the viewer as a watermark.
+                 *
+                 * The filter receives the input stream as is, it must handle the
+                 * conversions it needs itself.
+                 *
+                 * Returns: (nullable): the element to insert.
+                 */
+                glib::subclass::Signal::builder("request-video-filter")
+                    .param_types([
+                        String::static_type(),
+                        String::static_type(),
+                        gst::Caps::static_type(),
+                    ])
+                    .return_type::<gst::Element>()
+                    .build(),
+                /**
+                 * RsBaseWebRTCSink::request-turn-credentials:
+                 * @session_id: The ID of the session
+                 * @peer_id: The ID of the consumer
+                 *
+                 * Emitted when creating a session, and when restarting its ICE with
+                 * #RsBaseWebRTCSink::restart-ice, to let applications provide
+                 * credentials for the #RsBaseWebRTCSink:turn-servers, for instance
+                 * ephemeral ones minted with the TURN REST API, instead of embedding
+                 * long-lived secrets in the server URIs. The credentials of the
+                 * URIs, if any, are replaced.
+                 *
+                 * Returns: (nullable): a structure with `username` and `password`
+                 * string fields, or %NULL to use the servers as configured
+                 */
+                glib::subclass::Signal::builder("request-turn-credentials")
+                    .param_types([String::static_type(), String::static_type()])
+                    .return_type::<Option<gst::Structure>>()
+                    .build(),
             ]
         });
 
@@ -3593,11 +9320,18 @@ impl ElementImpl for BaseWebRTCSink {
                 caps_builder = caps_builder.structure(codec.caps.structure(0).unwrap().to_owned());
             }
 
-            let video_pad_template = gst::PadTemplate::new(
+            caps_builder = caps_builder.structure(
+                gst::Structure::builder("application/x-rtp")
+                    .field("media", "video")
+                    .build(),
+            );
+
+            let video_pad_template = gst::PadTemplate::with_gtype(
                 "video_%u",
                 gst::PadDirection::Sink,
                 gst::PadPresence::Request,
                 &caps_builder.build(),
+                super::WebRTCSinkPad::static_type(),
             )
             .unwrap();
 
@@ -3606,11 +9340,17 @@ impl ElementImpl for BaseWebRTCSink {
             for codec in Codecs::audio_codecs() {
                 caps_builder = caps_builder.structure(codec.caps.structure(0).unwrap().to_owned());
             }
-            let audio_pad_template = gst::PadTemplate::new(
+            caps_builder = caps_builder.structure(
+                gst::Structure::builder("application/x-rtp")
+                    .field("media", "audio")
+                    .build(),
+            );
+            let audio_pad_template = gst::PadTemplate::with_gtype(
                 "audio_%u",
                 gst::PadDirection::Sink,
                 gst::PadPresence::Request,
                 &caps_builder.build(),
+                super::WebRTCSinkPad::static_type(),
             )
             .unwrap();
 
@@ -3665,6 +9405,13 @@ impl ElementImpl for BaseWebRTCSink {
                     |this| this.sink_event(pad.upcast_ref(), &this.obj(), event),
                 )
             })
+            .query_function(|pad, parent, query| {
+                BaseWebRTCSink::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this| this.sink_query(pad.upcast_ref(), &this.obj(), query),
+                )
+            })
             .build();
 
         sink_pad.set_active(true).unwrap();
@@ -3679,6 +9426,9 @@ impl ElementImpl for BaseWebRTCSink {
                 in_caps: None,
                 out_caps: None,
                 clocksync: None,
+                input_selector: None,
+                watchdog: None,
+                thumbnails: None,
                 is_video,
                 serial,
                 discoveries: Default::default(),
@@ -3688,6 +9438,53 @@ impl ElementImpl for BaseWebRTCSink {
         Some(sink_pad.upcast())
     }
 
+    fn release_pad(&self, pad: &gst::Pad) {
+        let element = self.obj();
+        let name = pad.name();
+
+        let mut state = self.state.lock().unwrap();
+
+        let Some(mut stream) = state.streams.remove(name.as_str()) else {
+            return;
+        };
+
+        gst::info!(CAT, obj: element, "Releasing input stream {}", name);
+
+        // Let pending discoveries for this stream run to completion,
+        // their results are discarded
+        for discovery_info in stream.discoveries.drain(..) {
+            for src in discovery_info.srcs() {
+                let _ = src.end_of_stream();
+            }
+        }
+
+        let disconnected_sessions = state
+            .sessions
+            .values_mut()
+            .filter_map(|session| {
+                session
+                    .disconnect_input_stream(&element, name.as_str())
+                    .then(|| session.id.clone())
+            })
+            .collect::<Vec<_>>();
+
+        state
+            .mids
+            .retain(|_, stream_name| stream_name != name.as_str());
+
+        drop(state);
+
+        // Wait for the streaming thread to stop before tearing down
+        let _ = pad.set_active(false);
+        stream.unprepare(&element);
+        element.remove_pad(pad).unwrap();
+
+        // Let the consumers know the media is now inactive
+        for session_id in disconnected_sessions {
+            self.renegotiate(&session_id);
+        }
+    }
+
     fn change_state(
         &self,
         transition: gst::StateChange,
@@ -3743,14 +9540,38 @@ impl ElementImpl for BaseWebRTCSink {
             gst::StateChange::PausedToPlaying => {
                 let settings = self.settings.lock().unwrap();
                 let signaller = settings.signaller.clone();
+                let stats_message_interval = settings.stats_message_interval;
+                let memory_budget = settings.memory_budget;
                 drop(settings);
                 let mut state = self.state.lock().unwrap();
+
+                if stats_message_interval > 0 {
+                    state.stats_message_handle =
+                        Some(self.spawn_stats_messages(stats_message_interval));
+                }
+
+                if memory_budget > 0 {
+                    state.memory_budget_handle =
+                        Some(memory_budget::spawn_checks(&element, |element| {
+                            element.imp().check_memory_budget(element)
+                        }));
+                }
+
                 if state.should_start_signaller(&element) {
                     state.signaller_state = SignallerState::Started;
                     drop(state);
                     signaller.start();
                 }
             }
+            gst::StateChange::PlayingToPaused => {
+                let mut state = self.state.lock().unwrap();
+                if let Some(handle) = state.stats_message_handle.take() {
+                    handle.abort();
+                }
+                if let Some(handle) = state.memory_budget_handle.take() {
+                    handle.abort();
+                }
+            }
             _ => (),
         }
 
@@ -3758,7 +9579,20 @@ impl ElementImpl for BaseWebRTCSink {
     }
 }
 
-impl BinImpl for BaseWebRTCSink {}
+impl BinImpl for BaseWebRTCSink {
+    fn handle_message(&self, message: gst::Message) {
+        if let gst::MessageView::Eos(..) = message.view() {
+            let mut state = self.state.lock().unwrap();
+            if state.draining {
+                gst::debug!(CAT, imp: self, "Holding back EOS until sessions are drained");
+                state.held_eos_messages.push(message);
+                return;
+            }
+        }
+
+        self.parent_handle_message(message)
+    }
+}
 
 impl ChildProxyImpl for BaseWebRTCSink {
     fn child_by_index(&self, _index: u32) -> Option<glib::Object> {
@@ -3795,9 +9629,146 @@ impl NavigationImpl for BaseWebRTCSink {
 }
 
 #[derive(Default)]
-pub struct WebRTCSink {}
+pub struct WebRTCSink {
+    signaller_type: Mutex<WebRTCSinkSignallerType>,
+    self_test: Mutex<bool>,
+}
+
+impl WebRTCSink {
+    fn set_signaller(&self, signaller: Signallable) -> bool {
+        let element = self.obj();
+        let ws = element.upcast_ref::<super::BaseWebRTCSink>().imp();
+
+        if let Err(err) = ws.set_signaller(signaller) {
+            gst::error!(CAT, imp: self, "Failed to set signaller: {err}");
+            false
+        } else {
+            true
+        }
+    }
+
+    fn create_signaller(signaller_type: WebRTCSinkSignallerType) -> Signallable {
+        match signaller_type {
+            WebRTCSinkSignallerType::Default => {
+                Signaller::new(WebRTCSignallerRole::Producer).upcast()
+            }
+            WebRTCSinkSignallerType::AwsKvs => AwsKvsSignaller::default().upcast(),
+            WebRTCSinkSignallerType::Whip => WhipSignaller::default().upcast(),
+            WebRTCSinkSignallerType::LiveKit => LiveKitSignaller::default().upcast(),
+            WebRTCSinkSignallerType::Sip => SipSignaller::default().upcast(),
+            WebRTCSinkSignallerType::Grpc => {
+                GrpcSignaller::new(WebRTCSignallerRole::Producer).upcast()
+            }
+            WebRTCSinkSignallerType::Ipc => {
+                IpcSignaller::new(WebRTCSignallerRole::Producer).upcast()
+            }
+        }
+    }
+}
+
+impl ObjectImpl for WebRTCSink {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstWebRTCSink:signaller-type:
+                 *
+                 * The type of signaller to instantiate, allows selecting the
+                 * signalling protocol without an application, the signaller can then
+                 * be configured through the child proxy interface, for instance:
+                 *
+                 * ``` shell
+                 * webrtcsink signaller-type=whip signaller::whip-endpoint=http://...
+                 * ```
+                 */
+                glib::ParamSpecEnum::builder_with_default(
+                    "signaller-type",
+                    WebRTCSinkSignallerType::default(),
+                )
+                .nick("Signaller type")
+                .blurb("The type of signaller to use, replacing the current signaller")
+                .mutable_ready()
+                .build(),
+                /**
+                 * GstWebRTCSink:self-test:
+                 *
+                 * Replaces the signaller with one answering the offers of the sink
+                 * locally, for instance as a deployment health check:
+                 *
+                 * ``` shell
+                 * gst-launch-1.0 -m videotestsrc ! webrtcsink self-test=true
+                 * ```
+                 *
+                 * Once media has been flowing over the loopback session for ten
+                 * seconds, an element message named `webrtcsink-self-test` is
+                 * posted with the following fields, then the sink posts EOS:
+                 *
+                 * - `success`: whether media could be negotiated and sent
+                 * - `codecs`: the negotiated codecs
+                 * - `bitrate`: the achieved bitrate in bits per second
+                 * - `round-trip-time`: the round trip time reported by the receiver, if any
+                 *
+                 * Disabling it restores a signaller of #GstWebRTCSink:signaller-type.
+                 */
+                glib::ParamSpecBoolean::builder("self-test")
+                    .nick("Self test")
+                    .blurb("Negotiate a loopback session and report on it instead of using the signaller")
+                    .default_value(false)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "signaller-type" => {
+                let signaller_type = value
+                    .get::<WebRTCSinkSignallerType>()
+                    .expect("type checked upstream");
+
+                // Picked up again when disabling the self-test
+                if *self.self_test.lock().unwrap() {
+                    *self.signaller_type.lock().unwrap() = signaller_type;
+                    return;
+                }
+
+                if self.set_signaller(Self::create_signaller(signaller_type)) {
+                    *self.signaller_type.lock().unwrap() = signaller_type;
+                }
+            }
+            "self-test" => {
+                let self_test = value.get::<bool>().expect("type checked upstream");
+
+                if self_test == *self.self_test.lock().unwrap() {
+                    return;
+                }
+
+                let signaller: Signallable = if self_test {
+                    let element = self.obj();
+                    super::SelfTestSignaller::new(element.upcast_ref()).upcast()
+                } else {
+                    Self::create_signaller(*self.signaller_type.lock().unwrap())
+                };
 
-impl ObjectImpl for WebRTCSink {}
+                if self.set_signaller(signaller) {
+                    *self.self_test.lock().unwrap() = self_test;
+                }
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "signaller-type" => self.signaller_type.lock().unwrap().to_value(),
+            "self-test" => self.self_test.lock().unwrap().to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
 
 impl GstObjectImpl for WebRTCSink {}
 
@@ -3946,3 +9917,43 @@ impl ObjectSubclass for LiveKitWebRTCSink {
     type Type = super::LiveKitWebRTCSink;
     type ParentType = super::BaseWebRTCSink;
 }
+
+#[derive(Default)]
+pub struct WhepServerSink {}
+
+impl ObjectImpl for WhepServerSink {
+    fn constructed(&self) {
+        let element = self.obj();
+        let ws = element.upcast_ref::<super::BaseWebRTCSink>().imp();
+
+        let _ = ws.set_signaller(HttpServerSignaller::default().upcast());
+    }
+}
+
+impl GstObjectImpl for WhepServerSink {}
+
+impl ElementImpl for WhepServerSink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "WhepServerSink",
+                "Sink/Network/WebRTC",
+                "WebRTC sink with WHEP server signaller",
+                "Mathieu Duponchelle <mathieu@centricular.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl BinImpl for WhepServerSink {}
+
+impl BaseWebRTCSinkImpl for WhepServerSink {}
+
+#[glib::object_subclass]
+impl ObjectSubclass for WhepServerSink {
+    const NAME: &'static str = "GstWhepServerSink";
+    type Type = super::WhepServerSink;
+    type ParentType = super::BaseWebRTCSink;
+}
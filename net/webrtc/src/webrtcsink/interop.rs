@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::imp::RTP_TWCC_URI;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-interop",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink interoperability workarounds"),
+    )
+});
+
+/// Workarounds for the shortcomings of some remote peers, gathered from
+/// the rules matching their user agent
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Workarounds {
+    /// Don't negotiate transport-wide congestion control feedback
+    pub disable_twcc: bool,
+    /// Don't offer ULPFEC / RED
+    pub disable_fec: bool,
+    /// Don't offer retransmission
+    pub disable_retransmission: bool,
+    /// Packetization mode to advertise for H264 streams
+    pub h264_packetization_mode: Option<i32>,
+}
+
+impl Workarounds {
+    /// Merges the workarounds of all the rules whose `user-agent` pattern
+    /// matches @user_agent
+    pub fn for_user_agent(rules: &[gst::Structure], user_agent: &str) -> Self {
+        let mut ret = Self::default();
+
+        for rule in rules {
+            let Ok(pattern) = rule.get::<&str>("user-agent") else {
+                continue;
+            };
+
+            match Regex::new(pattern) {
+                Ok(re) if re.is_match(user_agent) => {
+                    gst::debug!(CAT, "User agent {user_agent:?} matches rule {rule}");
+
+                    ret.disable_twcc |= rule.get::<bool>("disable-twcc").unwrap_or(false);
+                    ret.disable_fec |= rule.get::<bool>("disable-fec").unwrap_or(false);
+                    ret.disable_retransmission |=
+                        rule.get::<bool>("disable-retransmission").unwrap_or(false);
+                    if let Ok(mode) = rule.get::<i32>("h264-packetization-mode") {
+                        ret.h264_packetization_mode = Some(mode);
+                    }
+                }
+                Ok(_) => (),
+                Err(err) => {
+                    gst::warning!(CAT, "Ignoring rule {rule} with invalid pattern: {err}");
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Adapts the caps we request a webrtcbin pad with, before they end up
+    /// in the SDP
+    pub fn apply_to_payloader_caps(&self, caps: &mut gst::CapsRef) {
+        for s in caps.iter_mut() {
+            if self.disable_twcc {
+                s.filter_map_in_place(|quark, value| {
+                    let name = quark.as_str();
+
+                    if name == "rtcp-fb-transport-cc"
+                        || (name.starts_with("extmap-") && extmap_uri(&value) == Some(RTP_TWCC_URI))
+                    {
+                        None
+                    } else {
+                        Some(value)
+                    }
+                });
+            }
+
+            if let Some(mode) = self.h264_packetization_mode {
+                if s.get::<&str>("encoding-name").ok() == Some("H264") {
+                    s.set("packetization-mode", mode.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// The URI of an `extmap-N` caps field, either a plain string or a
+/// (direction, uri, attributes) array
+fn extmap_uri(value: &glib::SendValue) -> Option<&str> {
+    value.get::<&str>().ok().or_else(|| {
+        value
+            .get::<&gst::ArrayRef>()
+            .ok()
+            .and_then(|array| array.as_slice().get(1))
+            .and_then(|uri| uri.get::<&str>().ok())
+    })
+}
+
+/// Checks a rule set on the element, so that mistakes are caught early
+pub fn validate_rule(rule: &gst::Structure) -> Result<(), String> {
+    let pattern = rule
+        .get::<&str>("user-agent")
+        .map_err(|_| format!("rule {rule} has no user-agent pattern"))?;
+
+    Regex::new(pattern).map_err(|err| format!("rule {rule} has an invalid pattern: {err}"))?;
+
+    match rule.get_optional::<i32>("h264-packetization-mode") {
+        Ok(None) | Ok(Some(0..=1)) => Ok(()),
+        _ => Err(format!(
+            "rule {rule} has an invalid h264-packetization-mode, expected 0 or 1"
+        )),
+    }
+}
+
+/// Identification of the remote peer from its SDP, browsers write their
+/// name in the origin username or session name (Firefox for instance
+/// writes "mozilla...THIS_IS_SDPARTA-<version>")
+pub fn sdp_user_agent(sdp: &gst_sdp::SDPMessageRef) -> String {
+    [
+        sdp.origin().and_then(|origin| origin.username()),
+        sdp.session_name(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|s| *s != "-")
+    .collect::<Vec<_>>()
+    .join(" ")
+}
@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+
+use crate::RUNTIME;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-memorybudget",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink memory budget"),
+    )
+});
+
+/// Interval in milliseconds at which the data queued for the sessions is
+/// checked against the memory budget
+const CHECK_INTERVAL: u64 = 500;
+
+/// Tracks whether the data queued for the sessions exceeds the budget
+#[derive(Debug, Default)]
+pub struct MemoryBudget {
+    /// Whether the budget was exceeded at the last check
+    exceeded: bool,
+}
+
+impl MemoryBudget {
+    /// Accounts for the bytes queued for all the sessions at this check,
+    /// returns whether that just exceeded the budget
+    pub fn update(
+        &mut self,
+        element: &super::BaseWebRTCSink,
+        budget: u64,
+        queued_bytes: u64,
+    ) -> bool {
+        if queued_bytes <= budget {
+            if std::mem::take(&mut self.exceeded) {
+                gst::info!(
+                    CAT,
+                    obj: element,
+                    "Back under the memory budget, {queued_bytes} bytes queued"
+                );
+            }
+            return false;
+        }
+
+        !std::mem::replace(&mut self.exceeded, true)
+    }
+}
+
+/// Calls `check` periodically for as long as `element` is alive
+pub fn spawn_checks(
+    element: &super::BaseWebRTCSink,
+    check: fn(&super::BaseWebRTCSink),
+) -> tokio::task::JoinHandle<()> {
+    let element = element.downgrade();
+
+    RUNTIME.spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(CHECK_INTERVAL));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            let Some(element) = element.upgrade() else {
+                break;
+            };
+
+            check(&element);
+        }
+    })
+}
+
+/// Warns the application that the data queued for the sessions exceeds
+/// the budget
+pub fn post_exceeded(element: &super::BaseWebRTCSink, budget: u64, queued_bytes: u64) {
+    gst::warning!(
+        CAT,
+        obj: element,
+        "{queued_bytes} bytes queued for the sessions, over the memory budget of {budget}"
+    );
+    gst::element_warning!(
+        element,
+        gst::ResourceError::NoSpaceLeft,
+        ["Data queued for the sessions exceeds the memory budget"],
+        details: gst::Structure::builder("webrtcsink-memory-budget-exceeded")
+            .field("queued-bytes", queued_bytes)
+            .field("budget", budget)
+            .build()
+    );
+}
+
+/// Picks the sessions to end to get back under the budget from the IDs of
+/// the `sessions` and the bytes queued for them, those queuing the most
+/// first
+pub fn sessions_to_end(mut sessions: Vec<(String, u64)>, budget: u64) -> Vec<(String, u64)> {
+    let mut queued_bytes = sessions.iter().map(|(_, bytes)| bytes).sum::<u64>();
+
+    sessions.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    sessions.retain(|(_, bytes)| {
+        if queued_bytes <= budget {
+            return false;
+        }
+
+        queued_bytes -= bytes;
+        true
+    });
+
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sessions(bytes: &[u64]) -> Vec<(String, u64)> {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(idx, bytes)| (format!("session-{idx}"), *bytes))
+            .collect()
+    }
+
+    #[test]
+    fn under_budget_ends_nothing() {
+        assert!(sessions_to_end(sessions(&[100, 200]), 300).is_empty());
+    }
+
+    #[test]
+    fn largest_sessions_are_ended_first() {
+        assert_eq!(
+            sessions_to_end(sessions(&[100, 400, 200]), 350),
+            vec![(String::from("session-1"), 400)]
+        );
+        assert_eq!(
+            sessions_to_end(sessions(&[100, 400, 200]), 150),
+            vec![
+                (String::from("session-1"), 400),
+                (String::from("session-2"), 200)
+            ]
+        );
+    }
+}
@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use crate::signaller::Signallable;
+use crate::sip_signaller::SipTransport;
 
 /**
  * SECTION:element-webrtcsink
  * @symbols:
  *   - GstBaseWebRTCSink
+ *   - GstWebRTCSinkPad
  *   - GstRSWebRTCSignallableIface
  *
  * `webrtcsink` is an element that can be used to serve media streams
@@ -17,6 +19,42 @@ use crate::signaller::Signallable;
  *
  * See the [documentation of the plugin](plugin-rswebrtc) for more information
  * on features and usage.
+ *
+ * ## Element names
+ *
+ * The elements of each session are named after the session ID, so that debug
+ * logs, tracers and dot graphs can be related to sessions:
+ *
+ * - `session-pipeline-<session-id>` for the pipeline of the session, or
+ *   `session-bin-<session-id>` when #GstBaseWebRTCSink:shared-pipeline is set
+ * - `webrtcbin-<session-id>` for its webrtcbin
+ * - `<session-id>-rtpgccbwe` for its bandwidth estimator
+ * - `<session-id>-<stream-name>-<role>` for the elements carrying the input
+ *   stream `<stream-name>`, for instance `video_0`, where `<role>` is one of
+ *   `appsrc`, `converter`, `raw-capsfilter`, `level`, `encoder`,
+ *   `encoder-capsfilter`, `parser`, `parser-capsfilter`, `payloader`,
+ *   `pay-capsfilter` and `webrtc-capsfilter`, in that order from the input
+ *
+ * For instance `GST_DEBUG="*:2,x264enc:6"` logs are tagged with
+ * `<abc-123-video_0-encoder>` for the video encoder of session `abc-123`. The
+ * filters returned from the #GstBaseWebRTCSink::request-video-filter and
+ * #GstBaseWebRTCSink::request-encoded-filter signals keep their name.
+ */
+
+/**
+ * SECTION:element-whepserversink
+ *
+ * `whepserversink` is a #GstBaseWebRTCSink acting as a WHEP endpoint, serving its
+ * streams to WHEP players such as `whepwebrtcsrc` or browsers:
+ *
+ * ``` bash
+ * gst-launch-1.0 videotestsrc ! whepserversink signaller::host-addr=http://0.0.0.0:8190
+ * ```
+ *
+ * Players POST their offer to `signaller::endpoint-path`, `/endpoint` by default,
+ * and end their session with a DELETE request on the URL of the Location header of
+ * the answer. The answer is sent once ICE gathering is complete, with all the
+ * candidates. Set `signaller::max-sessions` to limit the number of players.
  */
 
 /**
@@ -36,14 +74,33 @@ use gst::glib;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
 
+mod audio_cc;
+mod bandwidth_group;
+mod drain;
+mod encoder_pool;
 mod homegrown_cc;
 
 mod imp;
+mod interop;
+mod memory_budget;
+mod pad;
+mod placeholder;
+mod playout_delay;
+mod quantizer;
+mod self_test;
+mod session_migration;
+mod streams;
+mod thumbnail;
+mod turn;
 
 glib::wrapper! {
     pub struct BaseWebRTCSink(ObjectSubclass<imp::BaseWebRTCSink>) @extends gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
 }
 
+glib::wrapper! {
+    pub struct WebRTCSinkPad(ObjectSubclass<pad::WebRTCSinkPad>) @extends gst::GhostPad, gst::ProxyPad, gst::Pad, gst::Object;
+}
+
 glib::wrapper! {
     pub struct WebRTCSink(ObjectSubclass<imp::WebRTCSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
 }
@@ -60,6 +117,18 @@ glib::wrapper! {
     pub struct LiveKitWebRTCSink(ObjectSubclass<imp::LiveKitWebRTCSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
 }
 
+glib::wrapper! {
+    pub struct WhepServerSink(ObjectSubclass<imp::WhepServerSink>) @extends BaseWebRTCSink, gst::Bin, gst::Element, gst::Object, @implements gst::ChildProxy, gst_video::Navigation;
+}
+
+glib::wrapper! {
+    pub struct SelfTestSignaller(ObjectSubclass<self_test::SelfTestSignaller>) @implements Signallable;
+}
+
+glib::wrapper! {
+    pub struct PlayoutDelayExtension(ObjectSubclass<playout_delay::PlayoutDelayExtension>) @extends gst_rtp::RTPHeaderExtension, gst::Element, gst::Object;
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum WebRTCSinkError {
     #[error("no session with id")]
@@ -86,6 +155,19 @@ impl Default for BaseWebRTCSink {
     }
 }
 
+impl SelfTestSignaller {
+    pub fn new(element: &BaseWebRTCSink) -> Self {
+        let signaller: SelfTestSignaller = glib::Object::new();
+
+        signaller.imp().set_element(element);
+
+        signaller
+    }
+}
+
+unsafe impl Send for SelfTestSignaller {}
+unsafe impl Sync for SelfTestSignaller {}
+
 impl BaseWebRTCSink {
     pub fn with_signaller(signaller: Signallable) -> Self {
         let ret: BaseWebRTCSink = glib::Object::new();
@@ -95,6 +177,15 @@ impl BaseWebRTCSink {
 
         ret
     }
+
+    /// Replaces the signaller, also while running: the new signaller is
+    /// then started right away, and the sessions of the previous one are
+    /// handled according to the signaller-migration-policy property
+    pub fn set_signaller(&self, signaller: Signallable) -> Result<(), glib::BoolError> {
+        self.imp()
+            .set_signaller(signaller)
+            .map_err(|err| glib::bool_error!("Failed to set signaller: {err}"))
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
@@ -109,6 +200,61 @@ pub enum WebRTCSinkCongestionControl {
     GoogleCongestionControl,
 }
 
+#[derive(Debug, Default, Eq, PartialEq, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstWebRTCSinkSignallerType")]
+pub enum WebRTCSinkSignallerType {
+    #[default]
+    #[enum_value(
+        name = "Default: the signaller for our signalling server",
+        nick = "default"
+    )]
+    Default,
+    #[enum_value(name = "AWS Kinesis Video Streams signaller", nick = "aws-kvs")]
+    AwsKvs,
+    #[enum_value(name = "WHIP client signaller", nick = "whip")]
+    Whip,
+    #[enum_value(name = "LiveKit signaller", nick = "livekit")]
+    LiveKit,
+    #[enum_value(name = "SIP signaller", nick = "sip")]
+    Sip,
+    #[enum_value(name = "gRPC signaller", nick = "grpc")]
+    Grpc,
+    #[enum_value(name = "External process signaller", nick = "ipc")]
+    Ipc,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstWebRTCSinkSignallerMigrationPolicy")]
+pub enum WebRTCSinkSignallerMigrationPolicy {
+    #[default]
+    #[enum_value(
+        name = "End existing sessions: the sessions of the previous signaller are ended",
+        nick = "end-existing-sessions"
+    )]
+    EndExistingSessions,
+    #[enum_value(
+        name = "Keep until closed: the previous signaller handles its sessions until they end",
+        nick = "keep-until-closed"
+    )]
+    KeepUntilClosed,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstWebRTCSinkMemoryBudgetPolicy")]
+pub enum WebRTCSinkMemoryBudgetPolicy {
+    #[default]
+    #[enum_value(name = "Warn: only post a warning message", nick = "warn")]
+    Warn,
+    #[enum_value(
+        name = "End sessions: end the sessions queuing the most data until back under budget",
+        nick = "end-sessions"
+    )]
+    EndSessions,
+}
+
 #[glib::flags(name = "GstWebRTCSinkMitigationMode")]
 enum WebRTCSinkMitigationMode {
     #[flags_value(name = "No mitigation applied", nick = "none")]
@@ -117,11 +263,19 @@ enum WebRTCSinkMitigationMode {
     DOWNSCALED = 0b00000001,
     #[flags_value(name = "Lowered framerate", nick = "downsampled")]
     DOWNSAMPLED = 0b00000010,
+    #[flags_value(name = "Video disabled, audio only", nick = "audio-only")]
+    AUDIO_ONLY = 0b00000100,
 }
 
 pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     BaseWebRTCSink::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    WebRTCSinkPad::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     WebRTCSinkCongestionControl::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    WebRTCSinkSignallerType::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    SipTransport::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    WebRTCSinkSignallerMigrationPolicy::static_type()
+        .mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    WebRTCSinkMemoryBudgetPolicy::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     gst::Element::register(
         Some(plugin),
         "webrtcsink",
@@ -146,6 +300,12 @@ pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
         gst::Rank::None,
         LiveKitWebRTCSink::static_type(),
     )?;
+    gst::Element::register(
+        Some(plugin),
+        "whepserversink",
+        gst::Rank::None,
+        WhepServerSink::static_type(),
+    )?;
 
     Ok(())
 }
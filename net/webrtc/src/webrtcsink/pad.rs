@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use std::sync::Mutex;
+
+const DEFAULT_SYNC: bool = true;
+const DEFAULT_TS_OFFSET: i64 = 0;
+
+#[derive(Debug)]
+struct Settings {
+    msid: Option<String>,
+    mid: Option<String>,
+    track_name: Option<String>,
+    track_role: Option<String>,
+    track_language: Option<String>,
+    sync: bool,
+    ts_offset: i64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            msid: None,
+            mid: None,
+            track_name: None,
+            track_role: None,
+            track_language: None,
+            sync: DEFAULT_SYNC,
+            ts_offset: DEFAULT_TS_OFFSET,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WebRTCSinkPad {
+    settings: Mutex<Settings>,
+}
+
+impl WebRTCSinkPad {
+    pub fn msid(&self) -> Option<String> {
+        self.settings.lock().unwrap().msid.clone()
+    }
+
+    pub fn mid(&self) -> Option<String> {
+        self.settings.lock().unwrap().mid.clone()
+    }
+
+    pub fn track_name(&self) -> Option<String> {
+        self.settings.lock().unwrap().track_name.clone()
+    }
+
+    pub fn track_role(&self) -> Option<String> {
+        self.settings.lock().unwrap().track_role.clone()
+    }
+
+    pub fn track_language(&self) -> Option<String> {
+        self.settings.lock().unwrap().track_language.clone()
+    }
+
+    pub fn sync(&self) -> bool {
+        self.settings.lock().unwrap().sync
+    }
+
+    pub fn ts_offset(&self) -> i64 {
+        self.settings.lock().unwrap().ts_offset
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for WebRTCSinkPad {
+    const NAME: &'static str = "GstWebRTCSinkPad";
+    type Type = super::WebRTCSinkPad;
+    type ParentType = gst::GhostPad;
+}
+
+impl ObjectImpl for WebRTCSinkPad {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPS: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstWebRTCSinkPad:msid:
+                 *
+                 * The media stream identification written in the SDP for the
+                 * stream of this pad, for consumers to identify tracks without
+                 * relying on the pad order.
+                 *
+                 * When several audio pads exist, those without an msid each get
+                 * their own, made of their #GstWebRTCSinkPad:track-name and pad
+                 * name, so that browsers can tell the audio tracks apart. Set the
+                 * same msid on pads to have consumers play them in sync as one
+                 * media stream.
+                 */
+                glib::ParamSpecString::builder("msid")
+                    .nick("MSID")
+                    .blurb("Media stream identification to write in the SDP for this stream")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSinkPad:mid:
+                 *
+                 * The preferred media identification of the stream of this pad in
+                 * the offers we create. When answering an offer, the identification
+                 * chosen by the offerer is used instead.
+                 */
+                glib::ParamSpecString::builder("mid")
+                    .nick("MID")
+                    .blurb("Preferred media identification of this stream in the SDP offers")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSinkPad:track-name:
+                 *
+                 * Human readable name of the track, for instance "Camera 2",
+                 * written as the RFC 4574 label of its media in the SDP and
+                 * advertised along with the other tracks in the producer meta.
+                 */
+                glib::ParamSpecString::builder("track-name")
+                    .nick("Track name")
+                    .blurb("Human readable name of the track")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSinkPad:track-role:
+                 *
+                 * Role of the track, written as the RFC 4796 content of its media
+                 * in the SDP, for instance "main", "alt", "slides" or "speaker".
+                 */
+                glib::ParamSpecString::builder("track-role")
+                    .nick("Track role")
+                    .blurb("Role of the track, such as main, alt, slides or speaker")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSinkPad:track-language:
+                 *
+                 * Language of the track as an RFC 5646 tag, for instance "en",
+                 * written as the language of its media in the SDP.
+                 */
+                glib::ParamSpecString::builder("track-language")
+                    .nick("Track language")
+                    .blurb("Language tag of the track")
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSinkPad:sync:
+                 *
+                 * Whether the input stream is paced against the clock before being
+                 * handed to the sessions. Pacing is what clocks non-live producers
+                 * such as file sources, disabling it forwards buffers as soon as they
+                 * arrive, for producers that are already clocked upstream or to push
+                 * a non-live stream as fast as possible.
+                 */
+                glib::ParamSpecBoolean::builder("sync")
+                    .nick("Sync")
+                    .blurb("Pace the input stream against the clock")
+                    .default_value(DEFAULT_SYNC)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSinkPad:ts-offset:
+                 *
+                 * Offset in nanoseconds added to the running time of the buffers
+                 * when pacing the input stream, for instance to delay a stream
+                 * against the others.
+                 */
+                glib::ParamSpecInt64::builder("ts-offset")
+                    .nick("Timestamp offset")
+                    .blurb("Offset in nanoseconds added to the running time when pacing the input stream")
+                    .default_value(DEFAULT_TS_OFFSET)
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPS.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "msid" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.msid = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+            }
+            "mid" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.mid = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+            }
+            "track-name" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.track_name = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+            }
+            "track-role" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.track_role = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+            }
+            "track-language" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.track_language = value
+                    .get::<Option<String>>()
+                    .expect("type checked upstream");
+            }
+            "sync" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.sync = value.get::<bool>().expect("type checked upstream");
+            }
+            "ts-offset" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.ts_offset = value.get::<i64>().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "msid" => self.settings.lock().unwrap().msid.to_value(),
+            "mid" => self.settings.lock().unwrap().mid.to_value(),
+            "track-name" => self.settings.lock().unwrap().track_name.to_value(),
+            "track-role" => self.settings.lock().unwrap().track_role.to_value(),
+            "track-language" => self.settings.lock().unwrap().track_language.to_value(),
+            "sync" => self.settings.lock().unwrap().sync.to_value(),
+            "ts-offset" => self.settings.lock().unwrap().ts_offset.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for WebRTCSinkPad {}
+impl PadImpl for WebRTCSinkPad {}
+impl ProxyPadImpl for WebRTCSinkPad {}
+impl GhostPadImpl for WebRTCSinkPad {}
@@ -0,0 +1,343 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use anyhow::{Context, Error};
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::utils::make_element;
+use crate::RUNTIME;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-placeholder",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink input placeholder"),
+    )
+});
+
+/* Used when the input caps don't specify a framerate */
+const DEFAULT_VIDEO_FRAME_DURATION: gst::ClockTime = gst::ClockTime::from_mseconds(33);
+const AUDIO_CHUNK_DURATION: gst::ClockTime = gst::ClockTime::from_mseconds(20);
+
+#[derive(Default)]
+struct State {
+    last_buffer: Option<gst::Buffer>,
+    last_activity: Option<Instant>,
+    next_pts: Option<gst::ClockTime>,
+    stalled: bool,
+}
+
+/// Watches a raw input stream entering the `input-selector` in front of
+/// its clocksync. When upstream stops producing for longer than the
+/// configured timeout, the selector is switched over to a dedicated appsrc
+/// fed with placeholder buffers (black frames or silence), so that
+/// consumers keep receiving media instead of seeing their RTP streams
+/// stall. The first buffer from upstream switches it back.
+#[derive(Debug)]
+pub struct InputWatchdog {
+    live_pad: gst::Pad,
+    placeholder_pad: gst::Pad,
+    appsrc: gst_app::AppSrc,
+    probe_id: Option<gst::PadProbeId>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl InputWatchdog {
+    /// `live_pad` is the selector sink pad fed by the input stream with
+    /// `caps`, the placeholder appsrc is added to `bin`
+    pub fn new(
+        bin: &gst::Bin,
+        live_pad: &gst::Pad,
+        stream_name: &str,
+        caps: &gst::Caps,
+        timeout: gst::ClockTime,
+    ) -> Result<Self, Error> {
+        let selector = live_pad
+            .parent_element()
+            .context("Input selector pad without parent")?;
+
+        let appsrc = make_element("appsrc", Some(&format!("placeholder-{stream_name}")))?
+            .downcast::<gst_app::AppSrc>()
+            .unwrap();
+        appsrc.set_caps(Some(caps));
+        appsrc.set_format(gst::Format::Time);
+
+        bin.add(&appsrc).unwrap();
+
+        let placeholder_pad = selector
+            .request_pad_simple("sink_%u")
+            .context("Requesting placeholder pad")?;
+        appsrc
+            .static_pad("src")
+            .unwrap()
+            .link(&placeholder_pad)
+            .with_context(|| format!("Linking placeholder source for {stream_name}"))?;
+        appsrc
+            .sync_state_with_parent()
+            .with_context(|| format!("Starting placeholder source for {stream_name}"))?;
+
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let probe_id = live_pad.add_probe(gst::PadProbeType::BUFFER, {
+            let state = state.clone();
+            let bin_weak = bin.downgrade();
+            let stream_name = stream_name.to_string();
+            move |pad, info| {
+                let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data else {
+                    return gst::PadProbeReturn::Ok;
+                };
+
+                let mut state = state.lock().unwrap();
+                state.last_buffer = Some(buffer.clone());
+                state.last_activity = Some(Instant::now());
+
+                if state.stalled {
+                    state.stalled = false;
+
+                    // Switch back before the selector handles this buffer
+                    if let Some(selector) = pad.parent_element() {
+                        selector.set_property("active-pad", pad);
+                    }
+                    drop(state);
+
+                    gst::info!(CAT, obj: pad, "Input resumed");
+
+                    if let Some(bin) = bin_weak.upgrade() {
+                        Self::post_stalled(&bin, &stream_name, false);
+                    }
+                }
+
+                gst::PadProbeReturn::Ok
+            }
+        });
+
+        let stream_name = stream_name.to_string();
+        let bin_weak = bin.downgrade();
+        let handle = RUNTIME.spawn({
+            let placeholder_pad = placeholder_pad.clone();
+            let appsrc = appsrc.clone();
+            let caps = caps.clone();
+
+            async move {
+                let mut interval =
+                    tokio::time::interval(Duration::from_millis(AUDIO_CHUNK_DURATION.mseconds()));
+
+                loop {
+                    interval.tick().await;
+
+                    let Some(bin) = bin_weak.upgrade() else {
+                        break;
+                    };
+
+                    Self::check_input(
+                        &bin,
+                        &placeholder_pad,
+                        &appsrc,
+                        &stream_name,
+                        &caps,
+                        &state,
+                        timeout,
+                    );
+                }
+            }
+        });
+
+        Ok(Self {
+            live_pad: live_pad.clone(),
+            placeholder_pad,
+            appsrc,
+            probe_id,
+            handle,
+        })
+    }
+
+    fn post_stalled(bin: &gst::Bin, stream_name: &str, stalled: bool) {
+        let _ = bin.post_message(
+            gst::message::Element::builder(
+                gst::Structure::builder("webrtcsink-input-stalled")
+                    .field("stream-name", stream_name)
+                    .field("stalled", stalled)
+                    .build(),
+            )
+            .src(bin)
+            .build(),
+        );
+    }
+
+    fn check_input(
+        bin: &gst::Bin,
+        placeholder_pad: &gst::Pad,
+        appsrc: &gst_app::AppSrc,
+        stream_name: &str,
+        caps: &gst::Caps,
+        state: &Mutex<State>,
+        timeout: gst::ClockTime,
+    ) {
+        let mut state = state.lock().unwrap();
+
+        let Some(last_activity) = state.last_activity else {
+            // Nothing to keep alive until the stream has started
+            return;
+        };
+
+        let mut just_stalled = false;
+        if !state.stalled {
+            if last_activity.elapsed() < Duration::from_millis(timeout.mseconds()) {
+                return;
+            }
+
+            state.stalled = true;
+            state.next_pts = None;
+            just_stalled = true;
+
+            gst::info!(CAT, obj: placeholder_pad, "Input stalled, sending placeholder data");
+
+            if let Some(selector) = placeholder_pad.parent_element() {
+                selector.set_property("active-pad", placeholder_pad);
+            }
+        }
+
+        // The placeholder segment starts at zero, timestamp according to
+        // the current running time so that we don't burst the frames
+        // missed during the timeout downstream
+        let placeholder = bin.current_running_time().and_then(|pts| {
+            if state.next_pts.map_or(false, |next_pts| next_pts > pts) {
+                // Not yet time for the next placeholder
+                return None;
+            }
+
+            let mut buffer = Self::create_placeholder(caps, state.last_buffer.as_ref())?;
+            {
+                let buffer = buffer.make_mut();
+                buffer.set_pts(pts);
+                buffer.set_dts(gst::ClockTime::NONE);
+                buffer.unset_flags(gst::BufferFlags::DISCONT | gst::BufferFlags::DELTA_UNIT);
+            }
+
+            state.next_pts = buffer.duration().map(|duration| pts + duration);
+
+            Some(buffer)
+        });
+        drop(state);
+
+        if just_stalled {
+            Self::post_stalled(bin, stream_name, true);
+        }
+
+        if let Some(buffer) = placeholder {
+            // Pushed from the appsrc streaming thread
+            if let Err(err) = appsrc.push_buffer(buffer) {
+                gst::debug!(CAT, obj: placeholder_pad, "Failed to push placeholder: {err:?}");
+            }
+        }
+    }
+
+    /// Black frame for usual raw video formats, falling back to a copy of
+    /// the last frame, silence for raw audio
+    fn create_placeholder(
+        caps: &gst::Caps,
+        last_buffer: Option<&gst::Buffer>,
+    ) -> Option<gst::Buffer> {
+        let s = caps.structure(0)?;
+
+        if s.name().starts_with("video/x-raw") {
+            let info = gst_video::VideoInfo::from_caps(caps).ok()?;
+            let duration = if info.fps().numer() > 0 {
+                gst::ClockTime::SECOND
+                    .mul_div_floor(info.fps().denom() as u64, info.fps().numer() as u64)
+            } else {
+                None
+            }
+            .unwrap_or(DEFAULT_VIDEO_FRAME_DURATION);
+
+            let mut buffer = match Self::create_black_frame(&info) {
+                Some(buffer) => buffer,
+                None => last_buffer?.copy(),
+            };
+            buffer.get_mut().unwrap().set_duration(duration);
+
+            Some(buffer)
+        } else if s.name().starts_with("audio/x-raw") {
+            let info = gst_audio::AudioInfo::from_caps(caps).ok()?;
+            let n_frames = AUDIO_CHUNK_DURATION
+                .nseconds()
+                .mul_div_floor(info.rate() as u64, gst::ClockTime::SECOND.nseconds())?;
+
+            let mut buffer =
+                gst::Buffer::with_size(n_frames as usize * info.bpf() as usize).ok()?;
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_duration(AUDIO_CHUNK_DURATION);
+                let mut map = buffer.map_writable().ok()?;
+                info.format_info().fill_silence(map.as_mut_slice());
+            }
+
+            Some(buffer)
+        } else {
+            None
+        }
+    }
+
+    fn create_black_frame(info: &gst_video::VideoInfo) -> Option<gst::Buffer> {
+        use gst_video::VideoFormat::*;
+
+        let (luma, chroma) = match info.format() {
+            I420 | Yv12 | Nv12 | Nv21 | Y42b | Y444 => (16u8, 128u8),
+            Rgbx | Bgrx | Xrgb | Xbgr | Rgba | Bgra | Argb | Abgr | Rgb | Bgr => (0u8, 0u8),
+            _ => return None,
+        };
+
+        // Black frames must be opaque
+        let alpha_offset = match info.format() {
+            Rgba | Bgra => Some(3),
+            Argb | Abgr => Some(0),
+            _ => None,
+        };
+
+        let buffer = gst::Buffer::with_size(info.size()).ok()?;
+        let mut frame = gst_video::VideoFrame::from_buffer_writable(buffer, info).ok()?;
+
+        for plane in 0..frame.n_planes() {
+            frame
+                .plane_data_mut(plane)
+                .ok()?
+                .fill(if plane == 0 { luma } else { chroma });
+        }
+
+        if let Some(alpha_offset) = alpha_offset {
+            let stride = frame.plane_stride()[0] as usize;
+            let (width, height) = (frame.width() as usize, frame.height() as usize);
+            let data = frame.plane_data_mut(0).ok()?;
+
+            for line in data.chunks_mut(stride).take(height) {
+                for pixel in line.get_mut(..width * 4)?.chunks_exact_mut(4) {
+                    pixel[alpha_offset] = 255;
+                }
+            }
+        }
+
+        Some(frame.into_buffer())
+    }
+}
+
+impl Drop for InputWatchdog {
+    fn drop(&mut self) {
+        self.handle.abort();
+
+        if let Some(probe_id) = self.probe_id.take() {
+            self.live_pad.remove_probe(probe_id);
+        }
+
+        let _ = self.appsrc.set_state(gst::State::Null);
+
+        if let Some(selector) = self.placeholder_pad.parent_element() {
+            selector.release_request_pad(&self.placeholder_pad);
+        }
+
+        if let Some(bin) = self.appsrc.parent().and_downcast::<gst::Bin>() {
+            let _ = bin.remove(&self.appsrc);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_rtp::subclass::prelude::*;
+use std::sync::Mutex;
+
+pub const RTP_PLAYOUT_DELAY_URI: &str =
+    "http://www.webrtc.org/experiments/rtp-hdrext/playout-delay";
+
+/* The extension expresses delays as 12 bit values in 10 ms units */
+const PLAYOUT_DELAY_GRANULARITY_MS: u32 = 10;
+const PLAYOUT_DELAY_MAX_VALUE: u32 = 0xfff;
+pub const PLAYOUT_DELAY_MAX_MS: u32 = PLAYOUT_DELAY_MAX_VALUE * PLAYOUT_DELAY_GRANULARITY_MS;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-playoutdelay",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink playout delay RTP header extension"),
+    )
+});
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Settings {
+    min_delay: u32,
+    max_delay: u32,
+}
+
+/// Writes the playout-delay RTP header extension, hinting the receiver
+/// about the minimum and maximum delay it should apply before rendering
+#[derive(Default)]
+pub struct PlayoutDelayExtension {
+    settings: Mutex<Settings>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for PlayoutDelayExtension {
+    const NAME: &'static str = "GstWebRTCSinkPlayoutDelayExtension";
+    type Type = super::PlayoutDelayExtension;
+    type ParentType = gst_rtp::RTPHeaderExtension;
+}
+
+impl ObjectImpl for PlayoutDelayExtension {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("min-delay")
+                    .nick("Minimum delay")
+                    .blurb("Minimum playout delay in milliseconds")
+                    .maximum(PLAYOUT_DELAY_MAX_MS)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("max-delay")
+                    .nick("Maximum delay")
+                    .blurb("Maximum playout delay in milliseconds")
+                    .maximum(PLAYOUT_DELAY_MAX_MS)
+                    .mutable_playing()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "min-delay" => {
+                settings.min_delay = value.get::<u32>().expect("type checked upstream");
+            }
+            "max-delay" => {
+                settings.max_delay = value.get::<u32>().expect("type checked upstream");
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+        match pspec.name() {
+            "min-delay" => settings.min_delay.to_value(),
+            "max-delay" => settings.max_delay.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl GstObjectImpl for PlayoutDelayExtension {}
+
+impl ElementImpl for PlayoutDelayExtension {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Playout Delay RTP Header Extension",
+                "Network/Extension/RTPHeader",
+                "Writes the playout-delay RTP header extension",
+                "Mathieu Duponchelle <mathieu@centricular.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl RTPHeaderExtensionImpl for PlayoutDelayExtension {
+    const URI: &'static str = RTP_PLAYOUT_DELAY_URI;
+
+    fn supported_flags(&self) -> gst_rtp::RTPHeaderExtensionFlags {
+        gst_rtp::RTPHeaderExtensionFlags::ONE_BYTE | gst_rtp::RTPHeaderExtensionFlags::TWO_BYTE
+    }
+
+    fn max_size(&self, _input: &gst::BufferRef) -> usize {
+        3
+    }
+
+    fn write(
+        &self,
+        _input: &gst::BufferRef,
+        _write_flags: gst_rtp::RTPHeaderExtensionFlags,
+        _output: &gst::BufferRef,
+        output_data: &mut [u8],
+    ) -> Result<usize, gst::LoggableError> {
+        if output_data.len() < 3 {
+            return Err(gst::loggable_error!(
+                CAT,
+                "Not enough space to write playout delay"
+            ));
+        }
+
+        let settings = *self.settings.lock().unwrap();
+        let min = (settings.min_delay / PLAYOUT_DELAY_GRANULARITY_MS).min(PLAYOUT_DELAY_MAX_VALUE);
+        let max =
+            (settings.max_delay / PLAYOUT_DELAY_GRANULARITY_MS).clamp(min, PLAYOUT_DELAY_MAX_VALUE);
+
+        output_data[0] = (min >> 4) as u8;
+        output_data[1] = (((min & 0xf) << 4) | (max >> 8)) as u8;
+        output_data[2] = (max & 0xff) as u8;
+
+        Ok(3)
+    }
+
+    fn read(
+        &self,
+        _read_flags: gst_rtp::RTPHeaderExtensionFlags,
+        input_data: &[u8],
+        _output: &mut gst::BufferRef,
+    ) -> Result<(), gst::LoggableError> {
+        if input_data.len() < 3 {
+            return Err(gst::loggable_error!(
+                CAT,
+                "Not enough data to read playout delay"
+            ));
+        }
+
+        let min = ((input_data[0] as u32) << 4) | ((input_data[1] as u32) >> 4);
+        let max = (((input_data[1] as u32) & 0xf) << 8) | input_data[2] as u32;
+
+        gst::trace!(
+            CAT,
+            imp: self,
+            "Read playout delay min: {}ms, max: {}ms",
+            min * PLAYOUT_DELAY_GRANULARITY_MS,
+            max * PLAYOUT_DELAY_GRANULARITY_MS
+        );
+
+        Ok(())
+    }
+}
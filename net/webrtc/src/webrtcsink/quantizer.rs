@@ -0,0 +1,1088 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Extraction of the base quantizer index of encoded frames, for the
+//! codecs whose frame headers make it cheap to find
+
+use std::collections::HashMap;
+
+/// Quantizer statistics over the frames produced since the last reset
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QpStats {
+    sum: u64,
+    count: u64,
+    min: u32,
+    max: u32,
+}
+
+impl QpStats {
+    pub fn add(&mut self, qp: u32) {
+        if self.count == 0 {
+            self.min = qp;
+            self.max = qp;
+        } else {
+            self.min = self.min.min(qp);
+            self.max = self.max.max(qp);
+        }
+
+        self.sum += qp as u64;
+        self.count += 1;
+    }
+
+    /// Returns the average, minimum and maximum quantizer if any frame
+    /// was accounted for
+    pub fn summary(&self) -> Option<(f64, u32, u32)> {
+        (self.count > 0).then(|| (self.sum as f64 / self.count as f64, self.min, self.max))
+    }
+}
+
+/// Extracts the base quantizer index of the encoded frames of a stream
+#[derive(Debug)]
+pub struct QpParser {
+    codec_name: String,
+    h264: H264Parser,
+}
+
+impl QpParser {
+    /// `codec_name` is the name of the caps structure of the stream
+    pub fn new(codec_name: &str) -> Self {
+        Self {
+            codec_name: codec_name.to_string(),
+            h264: H264Parser::default(),
+        }
+    }
+
+    /// Sets the `codec_data` of the stream caps, H.264 streams in the
+    /// `avc` stream format carry their parameter sets there
+    pub fn set_codec_data(&mut self, codec_data: Option<&[u8]>) {
+        if self.codec_name == "video/x-h264" {
+            self.h264.set_codec_data(codec_data);
+        }
+    }
+
+    /// Returns the base quantizer index of an encoded frame, if supported
+    pub fn parse(&mut self, data: &[u8]) -> Option<u32> {
+        match self.codec_name.as_str() {
+            "video/x-vp8" => parse_vp8_qp(data),
+            "video/x-vp9" => parse_vp9_qp(data),
+            "video/x-h264" => self.h264.parse(data),
+            _ => None,
+        }
+    }
+}
+
+/// Boolean entropy decoder of RFC 6386, section 7
+struct BoolDecoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    value: u32,
+    range: u32,
+    bit_count: u32,
+}
+
+impl<'a> BoolDecoder<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        if data.len() < 2 {
+            return None;
+        }
+
+        Some(Self {
+            data,
+            pos: 2,
+            value: (data[0] as u32) << 8 | data[1] as u32,
+            range: 255,
+            bit_count: 0,
+        })
+    }
+
+    fn read_bool(&mut self, probability: u32) -> Option<bool> {
+        let split = 1 + (((self.range - 1) * probability) >> 8);
+        let big_split = split << 8;
+
+        let ret = if self.value >= big_split {
+            self.range -= split;
+            self.value -= big_split;
+            true
+        } else {
+            self.range = split;
+            false
+        };
+
+        while self.range < 128 {
+            self.value <<= 1;
+            self.range <<= 1;
+            self.bit_count += 1;
+
+            if self.bit_count == 8 {
+                self.bit_count = 0;
+                self.value |= *self.data.get(self.pos)? as u32;
+                self.pos += 1;
+            }
+        }
+
+        Some(ret)
+    }
+
+    fn read_literal(&mut self, bits: u32) -> Option<u32> {
+        let mut ret = 0;
+
+        for _ in 0..bits {
+            ret = (ret << 1) | self.read_bool(128)? as u32;
+        }
+
+        Some(ret)
+    }
+
+    fn read_flag(&mut self) -> Option<bool> {
+        self.read_bool(128)
+    }
+
+    /// Skips an optional value, present when its flag is set
+    fn skip_optional(&mut self, bits: u32) -> Option<()> {
+        if self.read_flag()? {
+            self.read_literal(bits)?;
+        }
+
+        Some(())
+    }
+}
+
+/// Returns the y_ac_qi of a VP8 frame, RFC 6386 section 19.2
+fn parse_vp8_qp(data: &[u8]) -> Option<u32> {
+    let key_frame = data.first()? & 0x01 == 0;
+    let header_size = if key_frame { 10 } else { 3 };
+
+    let mut bd = BoolDecoder::new(data.get(header_size..)?)?;
+
+    if key_frame {
+        // color_space and clamping_type
+        bd.read_literal(2)?;
+    }
+
+    if bd.read_flag()? {
+        let update_mb_segmentation_map = bd.read_flag()?;
+
+        if bd.read_flag()? {
+            // segment_feature_mode
+            bd.read_flag()?;
+
+            // Quantizer then loop filter updates, with their sign
+            for _ in 0..4 {
+                bd.skip_optional(8)?;
+            }
+            for _ in 0..4 {
+                bd.skip_optional(7)?;
+            }
+        }
+
+        if update_mb_segmentation_map {
+            for _ in 0..3 {
+                bd.skip_optional(8)?;
+            }
+        }
+    }
+
+    // filter_type, loop_filter_level and sharpness_level
+    bd.read_literal(10)?;
+
+    if bd.read_flag()? && bd.read_flag()? {
+        // Reference frame then mode deltas, with their sign
+        for _ in 0..8 {
+            bd.skip_optional(7)?;
+        }
+    }
+
+    // log2_nbr_of_dct_partitions
+    bd.read_literal(2)?;
+
+    bd.read_literal(7)
+}
+
+/// MSB first bit reader for the uncompressed VP9 header and the H.264
+/// RBSPs
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, bits: u32) -> Option<u32> {
+        let mut ret = 0;
+
+        for _ in 0..bits {
+            let byte = self.data.get(self.pos / 8)?;
+            let bit = (byte >> (7 - self.pos % 8)) & 1;
+            ret = (ret << 1) | bit as u32;
+            self.pos += 1;
+        }
+
+        Some(ret)
+    }
+
+    fn read_flag(&mut self) -> Option<bool> {
+        self.read(1).map(|bit| bit == 1)
+    }
+
+    /// Unsigned Exp-Golomb code, H.264 section 9.1
+    fn read_ue(&mut self) -> Option<u32> {
+        let mut leading_zeros = 0;
+        while !self.read_flag()? {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return None;
+            }
+        }
+
+        Some(((1u64 << leading_zeros) - 1 + self.read(leading_zeros)? as u64) as u32)
+    }
+
+    /// Signed Exp-Golomb code, H.264 section 9.1.1
+    fn read_se(&mut self) -> Option<i32> {
+        let value = self.read_ue()?;
+
+        Some(if value % 2 == 1 {
+            ((value / 2) + 1) as i32
+        } else {
+            -((value / 2) as i32)
+        })
+    }
+}
+
+/// Returns the base_q_idx of a VP9 frame, VP9 bitstream specification
+/// section 6.2
+fn parse_vp9_qp(data: &[u8]) -> Option<u32> {
+    const SYNC_CODE: u32 = 0x498342;
+    const CS_RGB: u32 = 7;
+
+    let mut br = BitReader { data, pos: 0 };
+
+    if br.read(2)? != 2 {
+        return None;
+    }
+
+    let profile_low_bit = br.read(1)?;
+    let profile = (br.read(1)? << 1) | profile_low_bit;
+    if profile == 3 {
+        br.read(1)?;
+    }
+
+    // show_existing_frame, no new frame is coded
+    if br.read_flag()? {
+        return None;
+    }
+
+    let key_frame = !br.read_flag()?;
+    let show_frame = br.read_flag()?;
+    let error_resilient_mode = br.read_flag()?;
+
+    let color_config = |br: &mut BitReader| -> Option<()> {
+        if profile >= 2 {
+            // ten_or_twelve_bit
+            br.read(1)?;
+        }
+
+        let color_space = br.read(3)?;
+        if color_space != CS_RGB {
+            // color_range
+            br.read(1)?;
+            if profile == 1 || profile == 3 {
+                // subsampling_x, subsampling_y and reserved_zero
+                br.read(3)?;
+            }
+        } else if profile == 1 || profile == 3 {
+            br.read(1)?;
+        }
+
+        Some(())
+    };
+
+    let render_size = |br: &mut BitReader| -> Option<()> {
+        if br.read_flag()? {
+            br.read(32)?;
+        }
+
+        Some(())
+    };
+
+    if key_frame {
+        if br.read(24)? != SYNC_CODE {
+            return None;
+        }
+
+        color_config(&mut br)?;
+        // frame_width_minus_1 and frame_height_minus_1
+        br.read(32)?;
+        render_size(&mut br)?;
+    } else {
+        let intra_only = if show_frame { false } else { br.read_flag()? };
+
+        if !error_resilient_mode {
+            // reset_frame_context
+            br.read(2)?;
+        }
+
+        if intra_only {
+            if br.read(24)? != SYNC_CODE {
+                return None;
+            }
+
+            if profile > 0 {
+                color_config(&mut br)?;
+            }
+
+            // refresh_frame_flags, frame size
+            br.read(8)?;
+            br.read(32)?;
+            render_size(&mut br)?;
+        } else {
+            // refresh_frame_flags, then ref_frame_idx and
+            // ref_frame_sign_bias of each reference
+            br.read(8)?;
+            br.read(12)?;
+
+            let mut found_ref = false;
+            for _ in 0..3 {
+                if br.read_flag()? {
+                    found_ref = true;
+                    break;
+                }
+            }
+
+            if !found_ref {
+                br.read(32)?;
+            }
+            render_size(&mut br)?;
+
+            // allow_high_precision_mv
+            br.read(1)?;
+
+            // is_filter_switchable
+            if !br.read_flag()? {
+                br.read(2)?;
+            }
+        }
+    }
+
+    if !error_resilient_mode {
+        // refresh_frame_context and frame_parallel_decoding_mode
+        br.read(2)?;
+    }
+
+    // frame_context_idx
+    br.read(2)?;
+
+    // loop_filter_level and loop_filter_sharpness
+    br.read(9)?;
+
+    // loop_filter_delta_enabled and loop_filter_delta_update
+    if br.read_flag()? && br.read_flag()? {
+        // Reference frame then mode deltas, with their sign
+        for _ in 0..6 {
+            if br.read_flag()? {
+                br.read(7)?;
+            }
+        }
+    }
+
+    br.read(8)
+}
+
+/// The fields of an H.264 sequence parameter set the slice header
+/// depends on, H.264 section 7.3.2.1.1
+#[derive(Debug, Clone)]
+struct H264Sps {
+    separate_colour_plane: bool,
+    chroma_array_type: u32,
+    log2_max_frame_num: u32,
+    pic_order_cnt_type: u32,
+    log2_max_pic_order_cnt_lsb: u32,
+    delta_pic_order_always_zero: bool,
+    frame_mbs_only: bool,
+}
+
+/// The fields of an H.264 picture parameter set the slice header depends
+/// on, H.264 section 7.3.2.2
+#[derive(Debug, Clone)]
+struct H264Pps {
+    sps_id: u32,
+    entropy_coding_mode: bool,
+    bottom_field_pic_order_in_frame_present: bool,
+    num_ref_idx_l0_default_active: u32,
+    num_ref_idx_l1_default_active: u32,
+    weighted_pred: bool,
+    weighted_bipred_idc: u32,
+    pic_init_qp: i32,
+    redundant_pic_cnt_present: bool,
+}
+
+const H264_NAL_SLICE: u8 = 1;
+const H264_NAL_IDR_SLICE: u8 = 5;
+const H264_NAL_SPS: u8 = 7;
+const H264_NAL_PPS: u8 = 8;
+
+const H264_SLICE_P: u32 = 0;
+const H264_SLICE_B: u32 = 1;
+const H264_SLICE_I: u32 = 2;
+const H264_SLICE_SP: u32 = 3;
+const H264_SLICE_SI: u32 = 4;
+
+/// Keeps track of the parameter sets of an H.264 stream, in order to
+/// parse the quantizer of the slices
+#[derive(Debug, Default)]
+struct H264Parser {
+    sps: HashMap<u32, H264Sps>,
+    pps: HashMap<u32, H264Pps>,
+    /// Size of the NAL unit lengths in the `avc` stream format, the
+    /// stream is in the `byte-stream` format when unset
+    nal_length_size: Option<usize>,
+}
+
+impl H264Parser {
+    /// Parses the AVCDecoderConfigurationRecord of ISO/IEC 14496-15
+    fn set_codec_data(&mut self, codec_data: Option<&[u8]>) {
+        self.nal_length_size = None;
+
+        let Some(codec_data) = codec_data else {
+            return;
+        };
+
+        let Some(&length_size) = codec_data.get(4) else {
+            return;
+        };
+        self.nal_length_size = Some((length_size & 0x03) as usize + 1);
+
+        let mut pos = 5;
+        for mask in [0x1f, 0xff] {
+            let Some(&count) = codec_data.get(pos) else {
+                return;
+            };
+            pos += 1;
+
+            for _ in 0..(count & mask) {
+                let Some(len) = codec_data.get(pos..pos + 2) else {
+                    return;
+                };
+                let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+                pos += 2;
+
+                let Some(nal) = codec_data.get(pos..pos + len) else {
+                    return;
+                };
+                self.handle_nal(nal);
+                pos += len;
+            }
+        }
+    }
+
+    /// Returns the quantizer of the first slice of an access unit
+    fn parse(&mut self, data: &[u8]) -> Option<u32> {
+        let nals = match self.nal_length_size {
+            Some(length_size) => split_length_prefixed(data, length_size),
+            None => split_byte_stream(data),
+        };
+
+        let mut ret = None;
+        for nal in nals {
+            let qp = self.handle_nal(nal);
+            if ret.is_none() {
+                ret = qp;
+            }
+        }
+
+        ret
+    }
+
+    /// Keeps track of parameter sets, returns the quantizer of slices
+    fn handle_nal(&mut self, nal: &[u8]) -> Option<u32> {
+        let header = *nal.first()?;
+        let nal_ref_idc = (header >> 5) & 0x03;
+        let nal_unit_type = header & 0x1f;
+        let rbsp = unescape_rbsp(&nal[1..]);
+
+        match nal_unit_type {
+            H264_NAL_SPS => {
+                let (id, sps) = parse_h264_sps(&rbsp)?;
+                self.sps.insert(id, sps);
+                None
+            }
+            H264_NAL_PPS => {
+                let (id, pps) = parse_h264_pps(&rbsp)?;
+                self.pps.insert(id, pps);
+                None
+            }
+            H264_NAL_SLICE | H264_NAL_IDR_SLICE => parse_h264_slice_qp(
+                &rbsp,
+                nal_ref_idc,
+                nal_unit_type == H264_NAL_IDR_SLICE,
+                &self.sps,
+                &self.pps,
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Splits an H.264 `byte-stream` into its NAL units, Annex B
+fn split_byte_stream(data: &[u8]) -> Vec<&[u8]> {
+    let mut starts = vec![];
+
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            starts.push(i + 3);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(idx, &start)| {
+            let mut end = starts.get(idx + 1).map_or(data.len(), |next| next - 3);
+            // Trailing zeros belong to the next start code
+            while end > start && data[end - 1] == 0 {
+                end -= 1;
+            }
+            &data[start..end]
+        })
+        .collect()
+}
+
+/// Splits an H.264 `avc` stream into its NAL units
+fn split_length_prefixed(data: &[u8], length_size: usize) -> Vec<&[u8]> {
+    let mut ret = vec![];
+
+    let mut pos = 0;
+    while let Some(len) = data.get(pos..pos + length_size) {
+        let len = len.iter().fold(0usize, |len, &b| (len << 8) | b as usize);
+        pos += length_size;
+
+        let Some(nal) = data.get(pos..pos + len) else {
+            break;
+        };
+        ret.push(nal);
+        pos += len;
+    }
+
+    ret
+}
+
+/// Removes the emulation prevention bytes of a NAL unit, H.264 section
+/// 7.4.1
+fn unescape_rbsp(data: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+
+    for &b in data {
+        if zeros >= 2 && b == 0x03 {
+            zeros = 0;
+            continue;
+        }
+
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+        ret.push(b);
+    }
+
+    ret
+}
+
+fn skip_h264_scaling_list(br: &mut BitReader, size: u32) -> Option<()> {
+    let mut last_scale = 8;
+    let mut next_scale = 8;
+
+    for _ in 0..size {
+        if next_scale != 0 {
+            let delta_scale = br.read_se()?;
+            next_scale = (last_scale + delta_scale + 256) % 256;
+        }
+        if next_scale != 0 {
+            last_scale = next_scale;
+        }
+    }
+
+    Some(())
+}
+
+fn parse_h264_sps(rbsp: &[u8]) -> Option<(u32, H264Sps)> {
+    let mut br = BitReader { data: rbsp, pos: 0 };
+
+    let profile_idc = br.read(8)?;
+    // constraint_set flags and level_idc
+    br.read(16)?;
+    let id = br.read_ue()?;
+
+    let mut chroma_format_idc = 1;
+    let mut separate_colour_plane = false;
+    if matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format_idc = br.read_ue()?;
+        if chroma_format_idc == 3 {
+            separate_colour_plane = br.read_flag()?;
+        }
+        // bit_depth_luma_minus8 and bit_depth_chroma_minus8
+        br.read_ue()?;
+        br.read_ue()?;
+        // qpprime_y_zero_transform_bypass_flag
+        br.read(1)?;
+
+        if br.read_flag()? {
+            let n_lists = if chroma_format_idc != 3 { 8 } else { 12 };
+            for i in 0..n_lists {
+                if br.read_flag()? {
+                    skip_h264_scaling_list(&mut br, if i < 6 { 16 } else { 64 })?;
+                }
+            }
+        }
+    }
+
+    let log2_max_frame_num = br.read_ue()? + 4;
+    let pic_order_cnt_type = br.read_ue()?;
+    let mut log2_max_pic_order_cnt_lsb = 0;
+    let mut delta_pic_order_always_zero = false;
+    match pic_order_cnt_type {
+        0 => log2_max_pic_order_cnt_lsb = br.read_ue()? + 4,
+        1 => {
+            delta_pic_order_always_zero = br.read_flag()?;
+            // offset_for_non_ref_pic and offset_for_top_to_bottom_field
+            br.read_se()?;
+            br.read_se()?;
+            for _ in 0..br.read_ue()? {
+                // offset_for_ref_frame
+                br.read_se()?;
+            }
+        }
+        _ => (),
+    }
+
+    // max_num_ref_frames, gaps_in_frame_num_value_allowed_flag then the
+    // picture size in macroblocks
+    br.read_ue()?;
+    br.read(1)?;
+    br.read_ue()?;
+    br.read_ue()?;
+    let frame_mbs_only = br.read_flag()?;
+
+    if log2_max_frame_num > 16 || log2_max_pic_order_cnt_lsb > 16 {
+        return None;
+    }
+
+    Some((
+        id,
+        H264Sps {
+            separate_colour_plane,
+            chroma_array_type: if separate_colour_plane {
+                0
+            } else {
+                chroma_format_idc
+            },
+            log2_max_frame_num,
+            pic_order_cnt_type,
+            log2_max_pic_order_cnt_lsb,
+            delta_pic_order_always_zero,
+            frame_mbs_only,
+        },
+    ))
+}
+
+fn parse_h264_pps(rbsp: &[u8]) -> Option<(u32, H264Pps)> {
+    let mut br = BitReader { data: rbsp, pos: 0 };
+
+    let id = br.read_ue()?;
+    let sps_id = br.read_ue()?;
+    let entropy_coding_mode = br.read_flag()?;
+    let bottom_field_pic_order_in_frame_present = br.read_flag()?;
+
+    // Slice groups are only ever used by the Baseline profile, and not
+    // produced by the encoders we use
+    if br.read_ue()? != 0 {
+        return None;
+    }
+
+    let num_ref_idx_l0_default_active = br.read_ue()? + 1;
+    let num_ref_idx_l1_default_active = br.read_ue()? + 1;
+    let weighted_pred = br.read_flag()?;
+    let weighted_bipred_idc = br.read(2)?;
+    let pic_init_qp = 26 + br.read_se()?;
+    // pic_init_qs_minus26 and chroma_qp_index_offset
+    br.read_se()?;
+    br.read_se()?;
+    // deblocking_filter_control_present_flag and constrained_intra_pred_flag
+    br.read(2)?;
+    let redundant_pic_cnt_present = br.read_flag()?;
+
+    Some((
+        id,
+        H264Pps {
+            sps_id,
+            entropy_coding_mode,
+            bottom_field_pic_order_in_frame_present,
+            num_ref_idx_l0_default_active,
+            num_ref_idx_l1_default_active,
+            weighted_pred,
+            weighted_bipred_idc,
+            pic_init_qp,
+            redundant_pic_cnt_present,
+        },
+    ))
+}
+
+fn skip_h264_ref_pic_list_modification(br: &mut BitReader) -> Option<()> {
+    if br.read_flag()? {
+        loop {
+            match br.read_ue()? {
+                0..=2 => {
+                    br.read_ue()?;
+                }
+                3 => break,
+                _ => return None,
+            }
+        }
+    }
+
+    Some(())
+}
+
+fn skip_h264_pred_weights(
+    br: &mut BitReader,
+    num_ref_idx_active: u32,
+    chroma_array_type: u32,
+) -> Option<()> {
+    for _ in 0..num_ref_idx_active {
+        if br.read_flag()? {
+            // luma_weight and luma_offset
+            br.read_se()?;
+            br.read_se()?;
+        }
+
+        if chroma_array_type != 0 && br.read_flag()? {
+            // chroma_weight and chroma_offset of both components
+            for _ in 0..4 {
+                br.read_se()?;
+            }
+        }
+    }
+
+    Some(())
+}
+
+/// Returns the SliceQPY of a slice, H.264 section 7.4.3
+fn parse_h264_slice_qp(
+    rbsp: &[u8],
+    nal_ref_idc: u8,
+    idr: bool,
+    sps: &HashMap<u32, H264Sps>,
+    pps: &HashMap<u32, H264Pps>,
+) -> Option<u32> {
+    let mut br = BitReader { data: rbsp, pos: 0 };
+
+    // first_mb_in_slice
+    br.read_ue()?;
+    let slice_type = br.read_ue()? % 5;
+    let pps = pps.get(&br.read_ue()?)?;
+    let sps = sps.get(&pps.sps_id)?;
+
+    if sps.separate_colour_plane {
+        // colour_plane_id
+        br.read(2)?;
+    }
+
+    // frame_num
+    br.read(sps.log2_max_frame_num)?;
+
+    let mut field_pic = false;
+    if !sps.frame_mbs_only {
+        field_pic = br.read_flag()?;
+        if field_pic {
+            // bottom_field_flag
+            br.read(1)?;
+        }
+    }
+
+    if idr {
+        // idr_pic_id
+        br.read_ue()?;
+    }
+
+    if sps.pic_order_cnt_type == 0 {
+        // pic_order_cnt_lsb
+        br.read(sps.log2_max_pic_order_cnt_lsb)?;
+        if pps.bottom_field_pic_order_in_frame_present && !field_pic {
+            // delta_pic_order_cnt_bottom
+            br.read_se()?;
+        }
+    }
+
+    if sps.pic_order_cnt_type == 1 && !sps.delta_pic_order_always_zero {
+        // delta_pic_order_cnt
+        br.read_se()?;
+        if pps.bottom_field_pic_order_in_frame_present && !field_pic {
+            br.read_se()?;
+        }
+    }
+
+    if pps.redundant_pic_cnt_present {
+        // redundant_pic_cnt
+        br.read_ue()?;
+    }
+
+    if slice_type == H264_SLICE_B {
+        // direct_spatial_mv_pred_flag
+        br.read(1)?;
+    }
+
+    let mut num_ref_idx_l0_active = pps.num_ref_idx_l0_default_active;
+    let mut num_ref_idx_l1_active = pps.num_ref_idx_l1_default_active;
+    if matches!(slice_type, H264_SLICE_P | H264_SLICE_SP | H264_SLICE_B) && br.read_flag()? {
+        num_ref_idx_l0_active = br.read_ue()? + 1;
+        if slice_type == H264_SLICE_B {
+            num_ref_idx_l1_active = br.read_ue()? + 1;
+        }
+    }
+
+    if num_ref_idx_l0_active > 32 || num_ref_idx_l1_active > 32 {
+        return None;
+    }
+
+    if slice_type != H264_SLICE_I && slice_type != H264_SLICE_SI {
+        skip_h264_ref_pic_list_modification(&mut br)?;
+        if slice_type == H264_SLICE_B {
+            skip_h264_ref_pic_list_modification(&mut br)?;
+        }
+    }
+
+    if (pps.weighted_pred && matches!(slice_type, H264_SLICE_P | H264_SLICE_SP))
+        || (pps.weighted_bipred_idc == 1 && slice_type == H264_SLICE_B)
+    {
+        // luma_log2_weight_denom and chroma_log2_weight_denom
+        br.read_ue()?;
+        if sps.chroma_array_type != 0 {
+            br.read_ue()?;
+        }
+
+        skip_h264_pred_weights(&mut br, num_ref_idx_l0_active, sps.chroma_array_type)?;
+        if slice_type == H264_SLICE_B {
+            skip_h264_pred_weights(&mut br, num_ref_idx_l1_active, sps.chroma_array_type)?;
+        }
+    }
+
+    if nal_ref_idc != 0 {
+        if idr {
+            // no_output_of_prior_pics_flag and long_term_reference_flag
+            br.read(2)?;
+        } else if br.read_flag()? {
+            // adaptive_ref_pic_marking_mode_flag
+            loop {
+                match br.read_ue()? {
+                    0 => break,
+                    3 => {
+                        br.read_ue()?;
+                        br.read_ue()?;
+                    }
+                    1 | 2 | 4 | 6 => {
+                        br.read_ue()?;
+                    }
+                    5 => (),
+                    _ => return None,
+                }
+            }
+        }
+    }
+
+    if pps.entropy_coding_mode && slice_type != H264_SLICE_I && slice_type != H264_SLICE_SI {
+        // cabac_init_idc
+        br.read_ue()?;
+    }
+
+    let qp = pps.pic_init_qp + br.read_se()?;
+
+    (0..=51).contains(&qp).then_some(qp as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// VP8 key frame, 320x240, loop filter deltas, y_ac_qi 42
+    const VP8_KEY_FRAME: &[u8] = &[
+        0x90, 0x01, 0x00, 0x9d, 0x01, 0x2a, 0x40, 0x01, 0xf0, 0x00, 0x05, 0x07, 0x04, 0x83, 0x83,
+        0x88, 0x85, 0x84, 0x88, 0x15, 0x02, 0x00,
+    ];
+    /// VP8 inter frame, segmentation with quantizer and loop filter
+    /// updates, y_ac_qi 100
+    const VP8_INTER_FRAME: &[u8] = &[
+        0x91, 0x02, 0x00, 0xf7, 0xb2, 0xe4, 0xcd, 0xf2, 0x91, 0xd4, 0xf6, 0x9f, 0x9e, 0xcf, 0x4f,
+        0x90, 0x2e, 0x8f, 0x2f, 0xe4, 0x33, 0xbf, 0x80,
+    ];
+    /// VP9 profile 0 key frame, 320x240, base_q_idx 60
+    const VP9_KEY_FRAME: &[u8] = &[
+        0x82, 0x49, 0x83, 0x42, 0x20, 0x13, 0xf0, 0x0e, 0xf4, 0x14, 0x38, 0x24, 0x1c, 0x18, 0x78,
+        0x00, 0x09, 0x1a, 0x00,
+    ];
+    /// VP9 profile 0 inter frame, base_q_idx 120
+    const VP9_INTER_FRAME: &[u8] = &[
+        0x86, 0x00, 0x40, 0x92, 0x98, 0x28, 0x70, 0x48, 0x38, 0x31, 0xe0, 0x00, 0x12, 0x34,
+    ];
+    /// VP9 profile 1 key frame, base_q_idx 200
+    const VP9_PROFILE_1_KEY_FRAME: &[u8] = &[
+        0xa2, 0x49, 0x83, 0x42, 0x20, 0x02, 0x7e, 0x01, 0xde, 0x82, 0x87, 0x04, 0x83, 0x83, 0x32,
+        0x00, 0x01, 0x23, 0x40,
+    ];
+    /// VP9 frame showing an existing frame, nothing is coded
+    const VP9_SHOW_EXISTING_FRAME: &[u8] = &[0x88];
+
+    /// H.264 High profile SPS, picture order count type 0
+    const H264_SPS: &[u8] = &[0x67, 0x64, 0x00, 0x1f, 0xac, 0xd9, 0x00, 0x50, 0x05, 0xb9];
+    /// H.264 PPS with CABAC, weighted prediction and pic_init_qp 23
+    const H264_PPS: &[u8] = &[0x68, 0xeb, 0xe3, 0xcb, 0x22, 0xc0];
+    /// H.264 IDR slice, SliceQPY 25
+    const H264_IDR_SLICE: &[u8] = &[0x65, 0x88, 0x84, 0x00, 0x9e, 0xaf, 0x36];
+    /// H.264 P slice with reference list modification, prediction weights
+    /// and memory management operations, SliceQPY 22
+    const H264_P_SLICE: &[u8] = &[
+        0x41, 0x9a, 0x63, 0x57, 0x21, 0x8d, 0x07, 0x8e, 0x04, 0x08, 0x3e, 0x45, 0x77, 0xd5, 0x55,
+        0x60,
+    ];
+    /// H.264 SPS with 16 bits frame numbers and picture order counts
+    const H264_LONG_SPS: &[u8] = &[
+        0x67, 0x64, 0x00, 0x1f, 0xac, 0x1b, 0x1a, 0x40, 0x14, 0x01, 0x6e, 0x40,
+    ];
+    /// H.264 I slice for the SPS above, with an emulation prevention
+    /// byte, SliceQPY 26
+    const H264_ESCAPED_SLICE: &[u8] = &[
+        0x61, 0x88, 0x80, 0x00, 0x00, 0x03, 0x00, 0x0d, 0xea, 0xf3, 0x60,
+    ];
+
+    fn byte_stream(nals: &[&[u8]]) -> Vec<u8> {
+        nals.iter()
+            .flat_map(|nal| [&[0x00, 0x00, 0x00, 0x01], *nal].concat())
+            .collect()
+    }
+
+    fn length_prefixed(nals: &[&[u8]]) -> Vec<u8> {
+        nals.iter()
+            .flat_map(|nal| [&(nal.len() as u32).to_be_bytes(), *nal].concat())
+            .collect()
+    }
+
+    #[test]
+    fn test_vpx() {
+        for (codec_name, data, expected) in [
+            ("video/x-vp8", VP8_KEY_FRAME, Some(42)),
+            ("video/x-vp8", VP8_INTER_FRAME, Some(100)),
+            ("video/x-vp9", VP9_KEY_FRAME, Some(60)),
+            ("video/x-vp9", VP9_INTER_FRAME, Some(120)),
+            ("video/x-vp9", VP9_PROFILE_1_KEY_FRAME, Some(200)),
+            ("video/x-vp9", VP9_SHOW_EXISTING_FRAME, None),
+            ("video/x-av1", VP9_KEY_FRAME, None),
+        ] {
+            assert_eq!(
+                QpParser::new(codec_name).parse(data),
+                expected,
+                "{codec_name} {data:02x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_vpx_truncated() {
+        // Shortest prefixes the quantizer can be parsed from
+        for (codec_name, data, header_len) in [
+            ("video/x-vp8", VP8_KEY_FRAME, 22),
+            ("video/x-vp8", VP8_INTER_FRAME, 22),
+            ("video/x-vp9", VP9_KEY_FRAME, 15),
+            ("video/x-vp9", VP9_INTER_FRAME, 11),
+            ("video/x-vp9", VP9_PROFILE_1_KEY_FRAME, 16),
+        ] {
+            let mut parser = QpParser::new(codec_name);
+
+            for len in 0..header_len {
+                assert_eq!(parser.parse(&data[..len]), None, "{codec_name} {len}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_h264_byte_stream() {
+        let mut parser = QpParser::new("video/x-h264");
+
+        // No parameter sets yet
+        assert_eq!(parser.parse(&byte_stream(&[H264_IDR_SLICE])), None);
+
+        assert_eq!(
+            parser.parse(&byte_stream(&[H264_SPS, H264_PPS, H264_IDR_SLICE])),
+            Some(25)
+        );
+        assert_eq!(parser.parse(&byte_stream(&[H264_P_SLICE])), Some(22));
+
+        // Three bytes start codes
+        let mut data = vec![0x00, 0x00, 0x01];
+        data.extend_from_slice(H264_P_SLICE);
+        assert_eq!(parser.parse(&data), Some(22));
+    }
+
+    #[test]
+    fn test_h264_avc() {
+        let mut parser = QpParser::new("video/x-h264");
+
+        let mut codec_data = vec![0x01, 0x64, 0x00, 0x1f, 0xff, 0xe1];
+        codec_data.extend_from_slice(&(H264_SPS.len() as u16).to_be_bytes());
+        codec_data.extend_from_slice(H264_SPS);
+        codec_data.push(0x01);
+        codec_data.extend_from_slice(&(H264_PPS.len() as u16).to_be_bytes());
+        codec_data.extend_from_slice(H264_PPS);
+        parser.set_codec_data(Some(&codec_data));
+
+        assert_eq!(parser.parse(&length_prefixed(&[H264_IDR_SLICE])), Some(25));
+        assert_eq!(parser.parse(&length_prefixed(&[H264_P_SLICE])), Some(22));
+
+        // Back to byte-stream
+        parser.set_codec_data(None);
+        assert_eq!(parser.parse(&byte_stream(&[H264_P_SLICE])), Some(22));
+    }
+
+    #[test]
+    fn test_h264_emulation_prevention() {
+        let mut parser = QpParser::new("video/x-h264");
+
+        assert_eq!(
+            parser.parse(&byte_stream(&[H264_LONG_SPS, H264_PPS, H264_ESCAPED_SLICE])),
+            Some(26)
+        );
+    }
+
+    #[test]
+    fn test_h264_truncated() {
+        let mut parser = QpParser::new("video/x-h264");
+        assert_eq!(parser.parse(&byte_stream(&[H264_SPS, H264_PPS])), None);
+
+        for slice in [H264_IDR_SLICE, H264_P_SLICE] {
+            // The slice QP delta is coded right before the last 4 bytes
+            for len in 0..slice.len() - 4 {
+                assert_eq!(
+                    parser.parse(&byte_stream(&[&slice[..len]])),
+                    None,
+                    "{slice:02x?} {len}"
+                );
+            }
+        }
+
+        // Truncated parameter sets are ignored
+        let mut parser = QpParser::new("video/x-h264");
+        for len in 0..H264_SPS.len() {
+            assert_eq!(
+                parser.parse(&byte_stream(&[&H264_SPS[..len], H264_PPS, H264_IDR_SLICE])),
+                None
+            );
+        }
+
+        // As are truncated NAL units in the avc format
+        parser.set_codec_data(Some(&[0x01, 0x64, 0x00, 0x1f, 0xff, 0xe1, 0x00]));
+        let data = length_prefixed(&[H264_IDR_SLICE]);
+        for len in 0..data.len() {
+            assert_eq!(parser.parse(&data[..len]), None);
+        }
+    }
+}
@@ -0,0 +1,372 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::{Signallable, SignallableImpl};
+use crate::RUNTIME;
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-selftest",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink self-test signaller"),
+    )
+});
+
+const SESSION_ID: &str = "self-test";
+
+/// How long the media flows through the loopback session before reporting
+const TEST_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct State {
+    /// Pipeline answering the offer of the sink and consuming its media
+    receiver: Option<gst::Pipeline>,
+    receiver_webrtcbin: Option<gst::Element>,
+    /// webrtcbin of the session under test
+    sender_webrtcbin: Option<gst::Element>,
+    codecs: Vec<String>,
+    started_at: Option<Instant>,
+    timeout_handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// Signaller answering the offers of its `webrtcsink` with a local
+/// `webrtcbin`, then reporting on the loopback session as an element
+/// message posted by the sink
+#[derive(Default)]
+pub struct SelfTestSignaller {
+    element: Mutex<glib::WeakRef<super::BaseWebRTCSink>>,
+    state: Mutex<State>,
+}
+
+impl SelfTestSignaller {
+    pub(super) fn set_element(&self, element: &super::BaseWebRTCSink) {
+        *self.element.lock().unwrap() = element.downgrade();
+    }
+
+    fn raise_error(&self, msg: String) {
+        self.obj().emit_by_name::<()>("error", &[&msg]);
+    }
+
+    fn on_offer_set(&self) {
+        let Some(webrtcbin) = self.state.lock().unwrap().receiver_webrtcbin.clone() else {
+            return;
+        };
+
+        let obj_weak = self.obj().downgrade();
+        let promise = gst::Promise::with_change_func(move |reply| {
+            let Some(obj) = obj_weak.upgrade() else {
+                return;
+            };
+
+            match reply {
+                Ok(Some(reply)) => match reply.value("answer").map(|answer| {
+                    answer
+                        .get::<gst_webrtc::WebRTCSessionDescription>()
+                        .unwrap()
+                }) {
+                    Ok(answer) => obj.imp().on_answer_created(answer),
+                    Err(_) => obj
+                        .imp()
+                        .raise_error(format!("Reply without an answer: {reply:?}")),
+                },
+                reply => obj
+                    .imp()
+                    .raise_error(format!("Failed to create answer: {reply:?}")),
+            }
+        });
+
+        webrtcbin.emit_by_name::<()>("create-answer", &[&None::<gst::Structure>, &promise]);
+    }
+
+    fn on_answer_created(&self, answer: gst_webrtc::WebRTCSessionDescription) {
+        let mut state = self.state.lock().unwrap();
+        let Some(webrtcbin) = state.receiver_webrtcbin.clone() else {
+            return;
+        };
+
+        webrtcbin.emit_by_name::<()>("set-local-description", &[&answer, &None::<gst::Promise>]);
+
+        state.codecs = answer
+            .sdp()
+            .medias()
+            .filter(|media| media.port() != 0 && media.attribute_val("inactive").is_none())
+            .filter_map(|media| {
+                let payload = media.format(0)?.parse::<i32>().ok()?;
+                let caps = media.caps_from_media(payload)?;
+                caps.structure(0)?.get::<String>("encoding-name").ok()
+            })
+            .collect();
+        state.started_at = Some(Instant::now());
+
+        gst::info!(
+            CAT,
+            imp: self,
+            "Negotiated codecs {:?}, measuring for {:?}",
+            state.codecs,
+            TEST_DURATION
+        );
+
+        let obj_weak = self.obj().downgrade();
+        state.timeout_handle = Some(RUNTIME.spawn(async move {
+            tokio::time::sleep(TEST_DURATION).await;
+
+            if let Some(obj) = obj_weak.upgrade() {
+                obj.imp().report();
+            }
+        }));
+        drop(state);
+
+        self.obj()
+            .emit_by_name::<()>("session-description", &[&SESSION_ID, &answer]);
+    }
+
+    fn report(&self) {
+        let Some(webrtcbin) = self.state.lock().unwrap().sender_webrtcbin.clone() else {
+            self.raise_error("Session under test was never created".to_string());
+            return;
+        };
+
+        let obj_weak = self.obj().downgrade();
+        let promise = gst::Promise::with_change_func(move |reply| {
+            let Some(obj) = obj_weak.upgrade() else {
+                return;
+            };
+
+            match reply {
+                Ok(Some(stats)) => obj.imp().post_report(stats),
+                reply => obj
+                    .imp()
+                    .raise_error(format!("Failed to gather stats: {reply:?}")),
+            }
+        });
+
+        webrtcbin.emit_by_name::<()>("get-stats", &[&None::<gst::Pad>, &promise]);
+    }
+
+    fn post_report(&self, stats: &gst::StructureRef) {
+        let mut bytes_sent = 0u64;
+        let mut round_trip_time = None::<f64>;
+
+        for (_, value) in stats.iter() {
+            let Ok(s) = value.get::<gst::Structure>() else {
+                continue;
+            };
+
+            match s.get::<gst_webrtc::WebRTCStatsType>("type") {
+                Ok(gst_webrtc::WebRTCStatsType::OutboundRtp) => {
+                    bytes_sent += s.get::<u64>("bytes-sent").unwrap_or(0);
+                }
+                Ok(gst_webrtc::WebRTCStatsType::RemoteInboundRtp) => {
+                    if let Ok(rtt) = s.get::<f64>("round-trip-time") {
+                        round_trip_time = Some(round_trip_time.map_or(rtt, |max| max.max(rtt)));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let (codecs, elapsed) = {
+            let state = self.state.lock().unwrap();
+            (
+                state.codecs.clone(),
+                state
+                    .started_at
+                    .map_or(TEST_DURATION, |started_at| started_at.elapsed()),
+            )
+        };
+
+        let bitrate = (bytes_sent * 8 * 1000)
+            .checked_div(elapsed.as_millis() as u64)
+            .unwrap_or(0);
+
+        let report = gst::Structure::builder("webrtcsink-self-test")
+            .field("success", !codecs.is_empty() && bytes_sent > 0)
+            .field("codecs", gst::Array::new(&codecs))
+            .field("bitrate", bitrate)
+            .field(
+                "round-trip-time",
+                round_trip_time.map(|rtt| gst::ClockTime::from_nseconds((rtt * 1e9) as u64)),
+            )
+            .build();
+
+        gst::info!(CAT, imp: self, "Self-test done: {report}");
+
+        if let Some(element) = self.element.lock().unwrap().upgrade() {
+            let _ =
+                element.post_message(gst::message::Element::builder(report).src(&element).build());
+        }
+
+        // Don't tear the session down from the thread of its webrtcbin
+        let obj_weak = self.obj().downgrade();
+        RUNTIME.spawn(async move {
+            if let Some(obj) = obj_weak.upgrade() {
+                obj.emit_by_name::<bool>("session-ended", &[&SESSION_ID]);
+                obj.imp().teardown();
+                obj.emit_by_name::<()>("shutdown", &[]);
+            }
+        });
+    }
+
+    fn teardown(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(handle) = state.timeout_handle.take() {
+            handle.abort();
+        }
+
+        let receiver = state.receiver.take();
+        *state = State::default();
+        drop(state);
+
+        if let Some(receiver) = receiver {
+            let _ = receiver.set_state(gst::State::Null);
+        }
+    }
+}
+
+impl SignallableImpl for SelfTestSignaller {
+    fn start(&self) {
+        let receiver = gst::Pipeline::builder()
+            .name("webrtcsink-self-test-receiver")
+            .build();
+
+        let webrtcbin = match gst::ElementFactory::make("webrtcbin")
+            .property_from_str("bundle-policy", "max-bundle")
+            .build()
+        {
+            Ok(webrtcbin) => webrtcbin,
+            Err(err) => {
+                self.raise_error(format!("Failed to create receiver: {err}"));
+                return;
+            }
+        };
+
+        webrtcbin.connect_pad_added(|webrtcbin, pad| {
+            if pad.direction() != gst::PadDirection::Src {
+                return;
+            }
+
+            let Some(receiver) = webrtcbin.parent().and_downcast::<gst::Bin>() else {
+                return;
+            };
+
+            let sink = gst::ElementFactory::make("fakesink")
+                .property("async", false)
+                .property("sync", false)
+                .build()
+                .unwrap();
+
+            receiver.add(&sink).unwrap();
+            sink.sync_state_with_parent().unwrap();
+            pad.link(&sink.static_pad("sink").unwrap()).unwrap();
+        });
+
+        let obj_weak = self.obj().downgrade();
+        webrtcbin.connect("on-ice-candidate", false, move |values| {
+            if let Some(obj) = obj_weak.upgrade() {
+                let sdp_m_line_index = values[1].get::<u32>().expect("Invalid argument");
+                let candidate = values[2].get::<String>().expect("Invalid argument");
+                obj.emit_by_name::<()>(
+                    "handle-ice",
+                    &[&SESSION_ID, &sdp_m_line_index, &None::<String>, &candidate],
+                );
+            }
+            None
+        });
+
+        receiver.add(&webrtcbin).unwrap();
+
+        if let Err(err) = receiver.set_state(gst::State::Playing) {
+            self.raise_error(format!("Failed to start receiver: {err}"));
+            return;
+        }
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.receiver = Some(receiver);
+            state.receiver_webrtcbin = Some(webrtcbin);
+        }
+
+        gst::info!(CAT, imp: self, "Requesting loopback session");
+
+        self.obj().emit_by_name::<()>(
+            "session-requested",
+            &[
+                &SESSION_ID,
+                &SESSION_ID,
+                &None::<gst_webrtc::WebRTCSessionDescription>,
+                &None::<gst::Structure>,
+            ],
+        );
+    }
+
+    fn stop(&self) {
+        self.teardown();
+    }
+
+    fn send_sdp(&self, _session_id: &str, sdp: &gst_webrtc::WebRTCSessionDescription) {
+        if sdp.type_() != gst_webrtc::WebRTCSDPType::Offer {
+            gst::warning!(CAT, imp: self, "Ignoring unexpected {:?}", sdp.type_());
+            return;
+        }
+
+        let Some(webrtcbin) = self.state.lock().unwrap().receiver_webrtcbin.clone() else {
+            return;
+        };
+
+        let obj_weak = self.obj().downgrade();
+        let promise = gst::Promise::with_change_func(move |_reply| {
+            if let Some(obj) = obj_weak.upgrade() {
+                obj.imp().on_offer_set();
+            }
+        });
+
+        webrtcbin.emit_by_name::<()>("set-remote-description", &[sdp, &promise]);
+    }
+
+    fn add_ice(
+        &self,
+        _session_id: &str,
+        candidate: &str,
+        sdp_m_line_index: u32,
+        _sdp_mid: Option<String>,
+    ) {
+        if let Some(ref webrtcbin) = self.state.lock().unwrap().receiver_webrtcbin {
+            webrtcbin.emit_by_name::<()>("add-ice-candidate", &[&sdp_m_line_index, &candidate]);
+        }
+    }
+
+    fn end_session(&self, _session_id: &str) {
+        self.teardown();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for SelfTestSignaller {
+    const NAME: &'static str = "GstWebRTCSinkSelfTestSignaller";
+    type Type = super::SelfTestSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for SelfTestSignaller {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        // The sink hands us the webrtcbin of the session under test
+        self.obj().connect_closure(
+            "consumer-added",
+            false,
+            glib::closure!(|signaller: &super::SelfTestSignaller,
+                            _peer_id: &str,
+                            webrtcbin: &gst::Element| {
+                signaller.imp().state.lock().unwrap().sender_webrtcbin = Some(webrtcbin.clone());
+            }),
+        );
+    }
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+use std::collections::HashSet;
+
+use crate::signaller::{Signallable, SignallableExt};
+use crate::RUNTIME;
+
+// Used to ensure signal are disconnected when a new signaller is is
+pub struct SignallerSignals {
+    pub error: glib::SignalHandlerId,
+    pub request_meta: glib::SignalHandlerId,
+    pub session_bitrate_ceiling: glib::SignalHandlerId,
+    pub session_requested: glib::SignalHandlerId,
+    pub session_ended: glib::SignalHandlerId,
+    pub session_description: glib::SignalHandlerId,
+    pub handle_ice: glib::SignalHandlerId,
+    pub shutdown: glib::SignalHandlerId,
+}
+
+impl SignallerSignals {
+    pub fn disconnect(self, signaller: &Signallable) {
+        signaller.disconnect(self.error);
+        signaller.disconnect(self.request_meta);
+        signaller.disconnect(self.session_bitrate_ceiling);
+        signaller.disconnect(self.session_requested);
+        signaller.disconnect(self.session_ended);
+        signaller.disconnect(self.session_description);
+        signaller.disconnect(self.handle_ice);
+        signaller.disconnect(self.shutdown);
+    }
+}
+
+/// A replaced signaller, still handling the sessions it requested until
+/// they end
+pub struct RetiredSignaller {
+    signaller: Signallable,
+    signals: SignallerSignals,
+    session_ids: HashSet<String>,
+}
+
+impl RetiredSignaller {
+    /// Disconnects from the signaller and stops it
+    pub fn stop(self) {
+        self.signals.disconnect(&self.signaller);
+        self.signaller.stop();
+    }
+}
+
+/// Signallers replaced while running with the keep-until-closed policy
+#[derive(Default)]
+pub struct RetiredSignallers(Vec<RetiredSignaller>);
+
+impl RetiredSignallers {
+    /// Keeps `signaller` running for the sessions of `session_ids`
+    pub fn retire(
+        &mut self,
+        signaller: Signallable,
+        signals: SignallerSignals,
+        session_ids: HashSet<String>,
+    ) {
+        self.0.push(RetiredSignaller {
+            signaller,
+            signals,
+            session_ids,
+        });
+    }
+
+    /// Returns the replaced signaller handling a session, if any
+    pub fn signaller(&self, session_id: &str) -> Option<&Signallable> {
+        self.0
+            .iter()
+            .find(|retired| retired.session_ids.contains(session_id))
+            .map(|retired| &retired.signaller)
+    }
+
+    /// Forgets a session that ended, returns the replaced signaller that
+    /// handled it if that was its last session
+    pub fn release(&mut self, session_id: &str) -> Option<RetiredSignaller> {
+        let idx = self
+            .0
+            .iter()
+            .position(|retired| retired.session_ids.contains(session_id))?;
+
+        let retired = &mut self.0[idx];
+        retired.session_ids.remove(session_id);
+        if !retired.session_ids.is_empty() {
+            return None;
+        }
+
+        Some(self.0.remove(idx))
+    }
+
+    /// Disconnects from all the replaced signallers and stops them
+    pub fn stop(self) {
+        for retired in self.0 {
+            retired.stop();
+        }
+    }
+}
+
+/// Keeps a session ended by the signaller alive for a while, in case the
+/// signaller requests it again, for instance after reconnecting
+#[derive(Debug)]
+pub struct ResumeTimeout(tokio::task::JoinHandle<()>);
+
+impl ResumeTimeout {
+    /// Calls `on_timeout` once `timeout` milliseconds elapsed, unless
+    /// cancelled in the meantime
+    pub fn new<F>(timeout: u32, on_timeout: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        Self(RUNTIME.spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(timeout as u64)).await;
+            on_timeout();
+        }))
+    }
+
+    pub fn cancel(self) {
+        self.0.abort();
+    }
+}
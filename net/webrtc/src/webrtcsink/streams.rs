@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-streams",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink input streams"),
+    )
+});
+
+/// MSID of an audio stream whose pad doesn't set one, made of its track
+/// name if any, restricted to the characters RFC 8830 allows, and of its
+/// pad name to keep it unique
+fn audio_stream_msid(pad_name: &str, track_name: Option<&str>) -> String {
+    match track_name {
+        Some(track_name) => {
+            let track_name = track_name
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`{|}~".contains(c) {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .take(63usize.saturating_sub(pad_name.len()))
+                .collect::<String>();
+
+            format!("{track_name}-{pad_name}")
+        }
+        None => pad_name.to_string(),
+    }
+}
+
+/// Sets the MSID of the stream fed by `sink_pad` on the webrtcbin `pad`
+/// it is linked to
+pub fn set_msid(
+    element: &super::BaseWebRTCSink,
+    sink_pad: &super::WebRTCSinkPad,
+    is_video: bool,
+    pad: &gst::Pad,
+) {
+    // webrtcbin would otherwise put all the tracks in the same media
+    // stream, leaving consumers no way to tell audio tracks apart
+    let msid = sink_pad.imp().msid().or_else(|| {
+        let n_audio_pads = element
+            .sink_pads()
+            .iter()
+            .filter(|pad| pad.name().starts_with("audio_"))
+            .count();
+
+        (!is_video && n_audio_pads > 1)
+            .then(|| audio_stream_msid(&sink_pad.name(), sink_pad.imp().track_name().as_deref()))
+    });
+
+    let Some(msid) = msid else {
+        return;
+    };
+
+    if pad.has_property("msid", Some(String::static_type())) {
+        pad.set_property("msid", msid);
+    } else {
+        gst::warning!(
+            CAT,
+            obj: element,
+            "webrtcbin doesn't support setting the msid, ignoring {msid}"
+        );
+    }
+}
+
+/// Describes an input stream, for introspection by applications
+pub fn stream_info(
+    name: &str,
+    serial: u32,
+    is_video: bool,
+    in_caps: Option<&gst::Caps>,
+    out_caps: Option<&gst::Caps>,
+    n_connected_sessions: u32,
+) -> gst::Structure {
+    let codecs = out_caps
+        .iter()
+        .flat_map(|caps| caps.iter())
+        .filter_map(|s| s.get::<String>("encoding-name").ok())
+        .collect::<Vec<_>>();
+
+    let discovery = if out_caps.is_some() {
+        "done"
+    } else if in_caps.is_some() {
+        "discovering"
+    } else {
+        "waiting-for-caps"
+    };
+
+    gst::Structure::builder("webrtcsink-stream")
+        .field("name", name)
+        .field("serial", serial)
+        .field("is-video", is_video)
+        .field("in-caps", in_caps.cloned())
+        .field("codecs", gst::Array::new(&codecs))
+        .field("discovery", discovery)
+        .field("n-connected-sessions", n_connected_sessions)
+        .build()
+}
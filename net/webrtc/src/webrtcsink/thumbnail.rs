@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use anyhow::{Context, Error};
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst_utils::{ConsumptionLink, StreamProducer};
+
+use crate::utils::make_element;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-thumbnail",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink thumbnails"),
+    )
+});
+
+/// Encodes low rate JPEG thumbnails of a raw video input stream, which it
+/// consumes alongside the sessions
+#[derive(Debug)]
+pub struct ThumbnailGenerator {
+    pipeline: gst::Pipeline,
+    _link: ConsumptionLink,
+}
+
+impl ThumbnailGenerator {
+    /// `on_thumbnail` is called from a streaming thread with each encoded
+    /// thumbnail, `interval` is in milliseconds
+    pub fn new<F>(
+        producer: &StreamProducer,
+        stream_name: &str,
+        interval: u32,
+        width: u32,
+        on_thumbnail: F,
+    ) -> Result<Self, Error>
+    where
+        F: Fn(&gst::Sample) + Send + Sync + 'static,
+    {
+        let pipeline = gst::Pipeline::builder()
+            .name(format!("webrtcsink-thumbnails-{stream_name}"))
+            .build();
+
+        let appsrc = make_element("appsrc", None)?
+            .downcast::<gst_app::AppSrc>()
+            .unwrap();
+        StreamProducer::configure_consumer(&appsrc);
+
+        let videorate = make_element("videorate", None)?;
+        videorate.set_property("drop-only", true);
+
+        let capsfilter = make_element("capsfilter", None)?;
+        capsfilter.set_property(
+            "caps",
+            gst_video::VideoCapsBuilder::new()
+                .width(width as i32)
+                .pixel_aspect_ratio(gst::Fraction::new(1, 1))
+                .framerate(gst::Fraction::new(1000, interval as i32))
+                .build(),
+        );
+
+        let appsink = gst_app::AppSink::builder()
+            .sync(false)
+            .max_buffers(1)
+            .drop(true)
+            .callbacks(
+                gst_app::AppSinkCallbacks::builder()
+                    .new_sample(move |appsink| {
+                        let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                        on_thumbnail(&sample);
+                        Ok(gst::FlowSuccess::Ok)
+                    })
+                    .build(),
+            )
+            .build();
+
+        let elements = [
+            appsrc.upcast_ref::<gst::Element>(),
+            &make_element("videoconvert", None)?,
+            &make_element("videoscale", None)?,
+            &videorate,
+            &capsfilter,
+            &make_element("jpegenc", None)?,
+            appsink.upcast_ref::<gst::Element>(),
+        ];
+
+        pipeline.add_many(elements).unwrap();
+        gst::Element::link_many(elements)
+            .with_context(|| format!("Linking thumbnail pipeline for {stream_name}"))?;
+
+        let stream_name = stream_name.to_string();
+        pipeline
+            .bus()
+            .unwrap()
+            .set_sync_handler(move |_bus, msg| {
+                if let gst::MessageView::Error(err) = msg.view() {
+                    gst::warning!(
+                        CAT,
+                        "No thumbnails for stream {stream_name}, only raw video in system memory is supported: {}",
+                        err.error()
+                    );
+                }
+
+                gst::BusSyncReply::Drop
+            });
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .with_context(|| "Starting thumbnail pipeline")?;
+
+        let link = producer
+            .add_consumer(&appsrc)
+            .map_err(|err| anyhow::anyhow!("Could not link producer: {:?}", err))?;
+
+        Ok(Self {
+            pipeline,
+            _link: link,
+        })
+    }
+}
+
+impl Drop for ThumbnailGenerator {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+
+use crate::utils::set_turn_credentials;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtcsink-turn",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC sink TURN servers"),
+    )
+});
+
+/// Extracts the username and password from the structure returned by the
+/// `request-turn-credentials` signal
+pub fn credentials(
+    element: &super::BaseWebRTCSink,
+    s: Option<gst::Structure>,
+) -> Option<(String, String)> {
+    let s = s?;
+
+    match (s.get::<String>("username"), s.get::<String>("password")) {
+        (Ok(username), Ok(password)) => Some((username, password)),
+        _ => {
+            gst::warning!(
+                CAT,
+                obj: element,
+                "Ignoring TURN credentials without username and password: {s}"
+            );
+            None
+        }
+    }
+}
+
+/// Returns the configured `turn_servers`, with `credentials` set if any
+pub fn with_credentials(
+    element: &super::BaseWebRTCSink,
+    turn_servers: &gst::Array,
+    credentials: Option<&(String, String)>,
+) -> Vec<String> {
+    turn_servers
+        .iter()
+        .filter_map(|turn_server| turn_server.get::<String>().ok())
+        .map(|turn_server| match credentials {
+            Some((username, password)) => set_turn_credentials(&turn_server, username, password)
+                .unwrap_or_else(|| {
+                    gst::warning!(CAT, obj: element, "Invalid TURN server {turn_server}");
+                    turn_server
+                }),
+            None => turn_server,
+        })
+        .collect()
+}
+
+/// Hands `turn_servers` to `webrtcbin` in place of the `previous` ones
+///
+/// webrtcbin can't forget the servers added with its add-turn-server
+/// signal. The first server is thus set through its turn-server property,
+/// which replaces it, covering the common case of a single TURN server
+/// whose credentials expire.
+///
+/// Returns false if `webrtcbin` keeps using some of the `previous` servers
+pub fn set_servers(
+    element: &super::BaseWebRTCSink,
+    webrtcbin: &gst::Element,
+    turn_servers: &[String],
+    previous: &[String],
+) -> bool {
+    let (previous_first, previous_others) = match previous.split_first() {
+        Some((first, others)) => (Some(first), others),
+        None => (None, &[][..]),
+    };
+
+    let others = match turn_servers.split_first() {
+        Some((first, others)) => {
+            if previous_first != Some(first) {
+                webrtcbin.set_property("turn-server", first);
+            }
+            others
+        }
+        None => &[][..],
+    };
+
+    for turn_server in others {
+        if !previous_others.contains(turn_server)
+            && !webrtcbin.emit_by_name::<bool>("add-turn-server", &[turn_server])
+        {
+            gst::warning!(CAT, obj: element, "Failed to add TURN server {turn_server}");
+        }
+    }
+
+    let mut stale = previous_others
+        .iter()
+        .chain(previous_first.filter(|_| turn_servers.is_empty()))
+        .filter(|turn_server| !turn_servers.contains(turn_server))
+        .peekable();
+
+    if stale.peek().is_none() {
+        return true;
+    }
+
+    for turn_server in stale {
+        gst::warning!(
+            CAT,
+            obj: element,
+            "Can't remove TURN server {turn_server} from webrtcbin"
+        );
+    }
+
+    false
+}
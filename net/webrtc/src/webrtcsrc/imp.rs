@@ -2,14 +2,21 @@
 
 use gst::prelude::*;
 
-use crate::signaller::{prelude::*, Signallable, Signaller};
-use crate::utils::{Codec, Codecs, NavigationEvent, AUDIO_CAPS, RTP_CAPS, VIDEO_CAPS};
+use crate::grpc_signaller::GrpcSignaller;
+use crate::http_server_signaller::HttpServerSignaller;
+use crate::ipc_signaller::IpcSignaller;
+use crate::signaller::{prelude::*, Signallable, Signaller, WebRTCSignallerRole};
+use crate::sip_signaller::SipSignaller;
+use crate::utils::{
+    clock_ts_refclk, Codec, Codecs, NavigationEvent, AUDIO_CAPS, RTP_CAPS, VIDEO_CAPS,
+};
 use crate::webrtcsrc::WebRTCSrcPad;
+use crate::whep_signaller::WhepSignaller;
 use anyhow::{Context, Error};
 use gst::glib;
 use gst::glib::once_cell::sync::Lazy;
 use gst::subclass::prelude::*;
-use gst_webrtc::WebRTCDataChannel;
+use gst_webrtc::{WebRTCDataChannel, WebRTCICETransportPolicy};
 use std::borrow::BorrowMut;
 use std::collections::HashSet;
 use std::str::FromStr;
@@ -20,8 +27,11 @@ use url::Url;
 
 const DEFAULT_STUN_SERVER: Option<&str> = Some("stun://stun.l.google.com:19302");
 const DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION: bool = false;
+const DEFAULT_ICE_TRANSPORT_POLICY: WebRTCICETransportPolicy = WebRTCICETransportPolicy::All;
+const DEFAULT_CONTINUOUS_TIMELINE: bool = false;
+const DEFAULT_ENABLE_PRECISE_SYNC: bool = false;
 
-static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+pub(super) static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
         "webrtcsrc",
         gst::DebugColorFlags::empty(),
@@ -34,11 +44,19 @@ const RTP_TWCC_URI: &str =
 
 struct Settings {
     stun_server: Option<String>,
+    turn_servers: gst::Array,
+    ice_transport_policy: WebRTCICETransportPolicy,
     signaller: Signallable,
     meta: Option<gst::Structure>,
     video_codecs: Vec<Codec>,
     audio_codecs: Vec<Codec>,
     enable_data_channel_navigation: bool,
+    continuous_timeline: bool,
+    enable_precise_sync: bool,
+    /// Producers we may select, any of them if empty
+    producer_peer_ids: Vec<String>,
+    /// Metadata the producers we select must match
+    producer_meta_filter: Option<gst::Structure>,
 }
 
 #[derive(Default)]
@@ -57,6 +75,14 @@ impl ObjectSubclass for WebRTCSrc {
     type Interfaces = (gst::URIHandler, gst::ChildProxy);
 }
 
+unsafe impl<T: WebRTCSrcImpl> IsSubclassable<T> for super::WebRTCSrc {
+    fn class_init(class: &mut glib::Class<Self>) {
+        Self::parent_class_init::<T>(class);
+    }
+}
+
+pub(crate) trait WebRTCSrcImpl: BinImpl {}
+
 impl ObjectImpl for WebRTCSrc {
     fn properties() -> &'static [glib::ParamSpec] {
         static PROPS: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
@@ -65,6 +91,21 @@ impl ObjectImpl for WebRTCSrc {
                     .flags(glib::ParamFlags::READWRITE)
                     .default_value(DEFAULT_STUN_SERVER)
                     .build(),
+                gst::ParamSpecArray::builder("turn-servers")
+                    .flags(glib::ParamFlags::READWRITE | gst::PARAM_FLAG_MUTABLE_READY)
+                    .nick("List of TURN Servers to user")
+                    .blurb("The TURN servers of the form <\"turn(s)://username:password@host:port\", \"turn(s)://username1:password1@host1:port1\">, TURN REST API credentials can be passed as username and password")
+                    .element_spec(&glib::ParamSpecString::builder("turn-server")
+                        .nick("TURN Server")
+                        .blurb("The TURN server of the form turn(s)://username:password@host:port.")
+                        .build()
+                    )
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("ice-transport-policy", DEFAULT_ICE_TRANSPORT_POLICY)
+                    .nick("ICE Transport Policy")
+                    .blurb("The policy to apply for ICE transport")
+                    .mutable_ready()
+                    .build(),
                 glib::ParamSpecObject::builder::<Signallable>("signaller")
                     .flags(glib::ParamFlags::READWRITE  | gst::PARAM_FLAG_MUTABLE_READY)
                     .blurb("The Signallable object to use to handle WebRTC Signalling")
@@ -93,6 +134,87 @@ impl ObjectImpl for WebRTCSrc {
                     .default_value(DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION)
                     .mutable_ready()
                     .build(),
+                /**
+                 * GstWebRTCSrc:continuous-timeline:
+                 *
+                 * Keep the output of requested pads, which are reused by the next
+                 * sessions, continuous when the element is restarted to reconnect to
+                 * a producer. The timestamps of a new session then follow the ones of
+                 * the previous session without gap, RTP output additionally keeps its
+                 * SSRC and continues its sequence numbers and RTP timestamps, and no
+                 * EOS is sent on those pads when a session ends.
+                 *
+                 * This is meant for downstream muxers and recorders, live sinks
+                 * synchronizing on the clock will consider the buffers following a
+                 * reconnection late.
+                 */
+                glib::ParamSpecBoolean::builder("continuous-timeline")
+                    .nick("Continuous timeline")
+                    .blurb("Keep the timestamps, RTP sequence numbers and RTP timestamps of requested pads continuous across sessions")
+                    .default_value(DEFAULT_CONTINUOUS_TIMELINE)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSrc:enable-precise-sync:
+                 *
+                 * Synchronize the streams against the clock signalled by the
+                 * producer, as done by webrtcsink with its `do-clock-signalling`
+                 * property: the RTP timestamps are mapped to the RFC 7273 reference clock
+                 * advertised in the SDP and to the NTP times of the RTCP sender
+                 * reports, and buffers are given a #GstReferenceTimestampMeta with
+                 * their capture time.
+                 *
+                 * Several webrtcsrc instances receiving from producers sharing a
+                 * clock, for instance a #GstPtpClock, then output aligned running
+                 * times provided their pipelines use that same clock. The producer
+                 * sends its clock over the "clock" data channel, which is posted as
+                 * a `webrtcsrc-clock-info` element message for the application to
+                 * select the matching pipeline clock.
+                 */
+                glib::ParamSpecBoolean::builder("enable-precise-sync")
+                    .nick("Enable precise sync")
+                    .blurb("Synchronize the streams against the clock signalled by the producer")
+                    .default_value(DEFAULT_ENABLE_PRECISE_SYNC)
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSrc:producer-peer-ids:
+                 *
+                 * When no producer to consume from is set on the signaller, the
+                 * element connects to the first producer the signaller announces
+                 * which is in this list, any of them if empty, and matches
+                 * #GstWebRTCSrc:producer-meta-filter.
+                 *
+                 * This requires a signaller announcing producers to consumers,
+                 * such as the default one.
+                 */
+                gst::ParamSpecArray::builder("producer-peer-ids")
+                    .nick("Producer peer IDs")
+                    .blurb("IDs of the producers to select from when none is set on the signaller, any of them if empty")
+                    .element_spec(&glib::ParamSpecString::builder("producer-peer-id")
+                        .nick("Producer peer ID")
+                        .blurb("ID of a producer")
+                        .build()
+                    )
+                    .mutable_ready()
+                    .build(),
+                /**
+                 * GstWebRTCSrc:producer-meta-filter:
+                 *
+                 * When no producer to consume from is set on the signaller, the
+                 * element only connects to a producer whose metadata is a subset
+                 * of this structure, regardless of the structure names. For
+                 * instance `meta, room=(string)lobby, kind=(string){ camera, screen }`
+                 * selects producers in the "lobby" room that announce either a
+                 * camera or a screen.
+                 *
+                 * Producers announced without metadata never match.
+                 */
+                glib::ParamSpecBoxed::builder::<gst::Structure>("producer-meta-filter")
+                    .nick("Producer meta filter")
+                    .blurb("Metadata the producers to select from must match when none is set on the signaller")
+                    .mutable_ready()
+                    .build(),
             ]
         });
 
@@ -132,6 +254,15 @@ impl ObjectImpl for WebRTCSrc {
                     .get::<Option<String>>()
                     .expect("type checked upstream")
             }
+            "turn-servers" => {
+                self.settings.lock().unwrap().turn_servers =
+                    value.get::<gst::Array>().expect("type checked upstream")
+            }
+            "ice-transport-policy" => {
+                self.settings.lock().unwrap().ice_transport_policy = value
+                    .get::<WebRTCICETransportPolicy>()
+                    .expect("type checked upstream")
+            }
             "meta" => {
                 self.settings.lock().unwrap().meta = value
                     .get::<Option<gst::Structure>>()
@@ -141,6 +272,29 @@ impl ObjectImpl for WebRTCSrc {
                 let mut settings = self.settings.lock().unwrap();
                 settings.enable_data_channel_navigation = value.get::<bool>().unwrap();
             }
+            "continuous-timeline" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.continuous_timeline = value.get::<bool>().unwrap();
+            }
+            "enable-precise-sync" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.enable_precise_sync = value.get::<bool>().unwrap();
+            }
+            "producer-peer-ids" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.producer_peer_ids = value
+                    .get::<gst::ArrayRef>()
+                    .expect("type checked upstream")
+                    .iter()
+                    .filter_map(|id| id.get::<String>().ok())
+                    .collect();
+            }
+            "producer-meta-filter" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.producer_meta_filter = value
+                    .get::<Option<gst::Structure>>()
+                    .expect("type checked upstream");
+            }
             _ => unimplemented!(),
         }
     }
@@ -167,11 +321,32 @@ impl ObjectImpl for WebRTCSrc {
             )
             .to_value(),
             "stun-server" => self.settings.lock().unwrap().stun_server.to_value(),
+            "turn-servers" => self.settings.lock().unwrap().turn_servers.to_value(),
+            "ice-transport-policy" => {
+                let settings = self.settings.lock().unwrap();
+                settings.ice_transport_policy.to_value()
+            }
             "meta" => self.settings.lock().unwrap().meta.to_value(),
             "enable-data-channel-navigation" => {
                 let settings = self.settings.lock().unwrap();
                 settings.enable_data_channel_navigation.to_value()
             }
+            "continuous-timeline" => {
+                let settings = self.settings.lock().unwrap();
+                settings.continuous_timeline.to_value()
+            }
+            "enable-precise-sync" => {
+                let settings = self.settings.lock().unwrap();
+                settings.enable_precise_sync.to_value()
+            }
+            "producer-peer-ids" => {
+                let settings = self.settings.lock().unwrap();
+                gst::Array::new(&settings.producer_peer_ids).to_value()
+            }
+            "producer-meta-filter" => {
+                let settings = self.settings.lock().unwrap();
+                settings.producer_meta_filter.to_value()
+            }
             name => panic!("{} getter not implemented", name),
         }
     }
@@ -202,6 +377,28 @@ impl ObjectImpl for WebRTCSrc {
                     ])
                     .return_type::<gst::Element>()
                     .build(),
+                /**
+                 * WebRTCSrc::request-decoder:
+                 * @pad_name: The name of the output pad
+                 * @caps: The caps of the parsed encoded stream
+                 *
+                 * This signal can be used to provide the decoder of a
+                 * stream that needs decoding, for instance a proprietary
+                 * low latency decoder. The element must expose static
+                 * `sink` and `src` pads, its source pad becomes the target
+                 * of the output pad.
+                 *
+                 * Emitted once the stream is depayloaded and parsed, after
+                 * the filter requested with #WebRTCSrc::request-encoded-filter
+                 * if any.
+                 *
+                 * Returns: the decoder to use, or %NULL to let decodebin3
+                 * pick one.
+                 */
+                glib::subclass::Signal::builder("request-decoder")
+                    .param_types([String::static_type(), gst::Caps::static_type()])
+                    .return_type::<gst::Element>()
+                    .build(),
             ]
         });
 
@@ -227,6 +424,8 @@ impl Default for Settings {
 
         Self {
             stun_server: DEFAULT_STUN_SERVER.map(|v| v.to_string()),
+            turn_servers: gst::Array::new(Vec::new() as Vec<glib::SendValue>),
+            ice_transport_policy: DEFAULT_ICE_TRANSPORT_POLICY,
             signaller: signaller.upcast(),
             meta: Default::default(),
             audio_codecs: Codecs::audio_codecs()
@@ -238,6 +437,10 @@ impl Default for Settings {
                 .filter(|codec| codec.has_decoder())
                 .collect(),
             enable_data_channel_navigation: DEFAULT_ENABLE_DATA_CHANNEL_NAVIGATION,
+            continuous_timeline: DEFAULT_CONTINUOUS_TIMELINE,
+            enable_precise_sync: DEFAULT_ENABLE_PRECISE_SYNC,
+            producer_peer_ids: Vec::new(),
+            producer_meta_filter: None,
         }
     }
 }
@@ -247,7 +450,9 @@ struct SignallerSignals {
     error: glib::SignalHandlerId,
     session_started: glib::SignalHandlerId,
     session_ended: glib::SignalHandlerId,
+    session_requested: glib::SignalHandlerId,
     request_meta: glib::SignalHandlerId,
+    producer_added: glib::SignalHandlerId,
     session_description: glib::SignalHandlerId,
     handle_ice: glib::SignalHandlerId,
 }
@@ -279,7 +484,7 @@ impl WebRTCSrc {
         .and_then(|stream_id| {
             self.obj().iterate_src_pads().into_iter().find_map(|s| {
                 let pad = s.ok()?.downcast::<WebRTCSrcPad>().unwrap();
-                if pad.imp().stream_id() == stream_id {
+                if pad.imp().matches_stream_id(&stream_id) {
                     Some(pad)
                 } else {
                     None
@@ -306,6 +511,91 @@ impl WebRTCSrc {
         }
     }
 
+    /// Links a parsed stream, through the encoded filter if any, to the
+    /// decoder the application provides for it, or to decodebin3
+    fn decode_parsed_stream(
+        &self,
+        srcpad: &WebRTCSrcPad,
+        pad: &gst::Pad,
+        encoded_filter: Option<gst::Element>,
+    ) {
+        let pad = match encoded_filter {
+            Some(encoded_filter) => {
+                self.obj().add(&encoded_filter).unwrap();
+                pad.link(
+                    &encoded_filter
+                        .static_pad("sink")
+                        .expect("encoded filter must expose a static sink pad"),
+                )
+                .expect("parsebin ! encoded_filter linking failed");
+                encoded_filter.sync_state_with_parent().unwrap();
+
+                encoded_filter
+                    .static_pad("src")
+                    .expect("encoded filter must expose a static src pad")
+            }
+            None => pad.clone(),
+        };
+
+        let caps = pad.current_caps().unwrap_or_else(|| pad.query_caps(None));
+        let decoder = self
+            .obj()
+            .emit_by_name::<Option<gst::Element>>("request-decoder", &[&srcpad.name(), &caps]);
+
+        if let Some(decoder) = decoder {
+            gst::debug!(
+                CAT,
+                imp: self,
+                "Decoding {} with {} provided by the application",
+                srcpad.imp().stream_id(),
+                decoder.name()
+            );
+
+            self.obj().add(&decoder).unwrap();
+            pad.link(
+                &decoder
+                    .static_pad("sink")
+                    .expect("decoder must expose a static sink pad"),
+            )
+            .expect("parsebin ! decoder linking failed");
+            srcpad
+                .set_target(Some(
+                    &decoder
+                        .static_pad("src")
+                        .expect("decoder must expose a static src pad"),
+                ))
+                .unwrap();
+            decoder.sync_state_with_parent().unwrap();
+
+            return;
+        }
+
+        let decodebin = gst::ElementFactory::make("decodebin3")
+            .build()
+            .expect("decodebin3 needs to be present!");
+        // Stop decoding at the caps the pad was requested with, this
+        // is how applications get parsed encoded streams
+        if let Some(caps) = srcpad.imp().requested_caps() {
+            decodebin.set_property("caps", caps);
+        }
+        self.obj().add(&decodebin).unwrap();
+        decodebin.connect_pad_added(glib::clone!(@weak srcpad => move |_decodebin, pad| {
+            if pad.direction() == gst::PadDirection::Sink {
+                return;
+            }
+
+            srcpad.set_target(Some(pad)).unwrap();
+        }));
+
+        pad.link(
+            &decodebin
+                .static_pad("sink")
+                .expect("decodebin has a sink pad"),
+        )
+        .expect("parsebin ! decodebin3 linking failed");
+        decodebin.sync_state_with_parent().unwrap();
+    }
+
     fn handle_webrtc_src_pad(&self, bin: &gst::Bin, pad: &gst::Pad) {
         let srcpad = self.get_src_pad_from_webrtcbin_pad(pad);
         if let Some(ref srcpad) = srcpad {
@@ -319,6 +609,10 @@ impl WebRTCSrc {
 
             gst::debug!(CAT, imp: self, "Storing id {stream_id} on {pad:?}");
             pad.store_sticky_event(&builder.build()).ok();
+
+            srcpad
+                .imp()
+                .start_session(self.settings.lock().unwrap().continuous_timeline);
         }
 
         let ghostpad = gst::GhostPad::builder(gst::PadDirection::Src)
@@ -368,63 +662,38 @@ impl WebRTCSrc {
             .expect("Adding ghostpad to the bin should always work");
 
         if let Some(srcpad) = srcpad {
-            let producer_id = self.signaller().property::<String>("producer-peer-id");
+            let signaller = self.signaller();
+            let producer_id = if signaller.find_property("producer-peer-id").is_some() {
+                signaller.property::<String>("producer-peer-id")
+            } else {
+                String::new()
+            };
             let encoded_filter = self.obj().emit_by_name::<Option<gst::Element>>(
                 "request-encoded-filter",
                 &[&producer_id, &srcpad.name(), &srcpad.allowed_caps()],
             );
 
             if srcpad.imp().needs_decoding() {
-                let decodebin = gst::ElementFactory::make("decodebin3")
+                gst::debug!(CAT, imp: self, "Decoding for {}", srcpad.imp().stream_id());
+
+                // Depayload and parse first, so that the application can
+                // pick a decoder for the actual encoded caps
+                let parsebin = gst::ElementFactory::make("parsebin")
                     .build()
-                    .expect("decodebin3 needs to be present!");
-                self.obj().add(&decodebin).unwrap();
-                decodebin.sync_state_with_parent().unwrap();
-                decodebin.connect_pad_added(
-                    glib::clone!(@weak self as this, @weak srcpad => move |_webrtcbin, pad| {
-                        if pad.direction() == gst::PadDirection::Sink {
-                            return;
-                        }
+                    .expect("parsebin needs to be present!");
+                self.obj().add(&parsebin).unwrap();
 
-                        srcpad.set_target(Some(pad)).unwrap();
+                parsebin.connect_pad_added(
+                    glib::clone!(@weak self as this, @weak srcpad => move |_, pad| {
+                        this.decode_parsed_stream(&srcpad, pad, encoded_filter.clone());
                     }),
                 );
 
-                gst::debug!(CAT, imp: self, "Decoding for {}", srcpad.imp().stream_id());
+                ghostpad
+                    .link(&parsebin.static_pad("sink").unwrap())
+                    .expect("webrtcbin ! parsebin linking failed");
 
-                if let Some(encoded_filter) = encoded_filter {
-                    let filter_sink_pad = encoded_filter
-                        .static_pad("sink")
-                        .expect("encoded filter must expose a static sink pad");
-
-                    let parsebin = gst::ElementFactory::make("parsebin")
-                        .build()
-                        .expect("parsebin needs to be present!");
-                    self.obj().add_many([&parsebin, &encoded_filter]).unwrap();
-
-                    parsebin.connect_pad_added(move |_, pad| {
-                        pad.link(&filter_sink_pad)
-                            .expect("parsebin ! encoded_filter linking failed");
-                        encoded_filter
-                            .link(&decodebin)
-                            .expect("encoded_filter ! decodebin3 linking failed");
-
-                        encoded_filter.sync_state_with_parent().unwrap();
-                    });
-
-                    ghostpad
-                        .link(&parsebin.static_pad("sink").unwrap())
-                        .expect("webrtcbin ! parsebin linking failed");
-
-                    parsebin.sync_state_with_parent().unwrap();
-                } else {
-                    let sinkpad = decodebin
-                        .static_pad("sink")
-                        .expect("decodebin has a sink pad");
-                    ghostpad
-                        .link(&sinkpad)
-                        .expect("webrtcbin ! decodebin3 linking failed");
-                }
+                parsebin.sync_state_with_parent().unwrap();
             } else {
                 gst::debug!(
                     CAT,
@@ -464,8 +733,33 @@ impl WebRTCSrc {
             .build()
             .with_context(|| "Failed to make element webrtcbin".to_string())?;
 
-        if let Some(stun_server) = self.settings.lock().unwrap().stun_server.as_ref() {
-            webrtcbin.set_property("stun-server", stun_server);
+        {
+            let settings = self.settings.lock().unwrap();
+
+            webrtcbin.set_property("ice-transport-policy", settings.ice_transport_policy);
+
+            if let Some(stun_server) = settings.stun_server.as_ref() {
+                webrtcbin.set_property("stun-server", stun_server);
+            }
+
+            for turn_server in settings.turn_servers.iter() {
+                webrtcbin.emit_by_name::<bool>("add-turn-server", &[&turn_server]);
+            }
+
+            if settings.enable_precise_sync {
+                let rtpbin = webrtcbin
+                    .dynamic_cast_ref::<gst::ChildProxy>()
+                    .unwrap()
+                    .child_by_name("rtpbin")
+                    .unwrap();
+
+                // Map the RTP timestamps to the reference clock of the producer,
+                // both through the RFC 7273 SDP attributes and the NTP times of
+                // its sender reports
+                rtpbin.set_property("rfc7273-sync", true);
+                rtpbin.set_property("ntp-sync", true);
+                rtpbin.set_property("add-reference-timestamp-meta", true);
+            }
         }
 
         let bin = gst::Bin::new();
@@ -524,12 +818,13 @@ impl WebRTCSrc {
 
         // Same logic as gst_pad_create_stream_id and friends, making a hash of
         // the URI and adding `:<some-id>`, here the ID is the mline of the
-        // stream in the SDP.
+        // stream in the SDP. Signallers without a URI, such as the WHEP one,
+        // get IDs specific to the element instead.
         mline.map(|mline| {
             let mut cs = glib::Checksum::new(glib::ChecksumType::Sha256).unwrap();
             cs.update(
                 self.uri()
-                    .expect("get_stream_id should never be called if no URI has been set")
+                    .unwrap_or_else(|| self.obj().path_string().to_string())
                     .as_bytes(),
             );
 
@@ -544,6 +839,14 @@ impl WebRTCSrc {
         self.maybe_stop_signaller();
         self.state.lock().unwrap().session_id = None;
         for pad in obj.src_pads() {
+            // Requested pads are kept around for the next session
+            if is_requested_pad(&pad) {
+                let pad = pad.downcast_ref::<WebRTCSrcPad>().unwrap();
+                pad.imp().clear_stream_id();
+                pad.set_target(None::<&gst::Pad>).unwrap();
+                continue;
+            }
+
             obj.remove_pad(&pad)
                 .map_err(|err| anyhow::anyhow!("Couldn't remove pad? {err:?}"))?;
         }
@@ -592,8 +895,14 @@ impl WebRTCSrc {
                 false,
                 glib::closure!(@to-owned self as this => move |_signaler: glib::Object, _session_id: &str|{
                     this.state.lock().unwrap().session_id = None;
+                    // Requested pads continue with the next session
+                    let continuous = this.settings.lock().unwrap().continuous_timeline;
                     this.obj().iterate_src_pads().into_iter().for_each(|pad|
-                        { if let Err(e) = pad.map(|pad| pad.push_event(gst::event::Eos::new())) {
+                        { if let Err(e) = pad.map(|pad| {
+                            if !(continuous && is_requested_pad(&pad)) {
+                                pad.push_event(gst::event::Eos::new());
+                            }
+                        }) {
                             gst::error!(CAT, "Could not send EOS: {e:?}");
                         }}
                     );
@@ -602,6 +911,34 @@ impl WebRTCSrc {
                 }),
             ),
 
+            session_requested: signaller.connect_closure(
+                "session-requested",
+                false,
+                glib::closure!(@to-owned self as this => move |
+                        signaller: glib::Object,
+                        session_id: &str,
+                        peer_id: &str,
+                        offer: Option<&gst_webrtc::WebRTCSessionDescription>,
+                        _capabilities: Option<&gst::Structure>| {
+                    gst::info!(CAT, imp: this, "Session requested: {session_id}");
+                    this.state.lock().unwrap().session_id =
+                        Some(session_id.to_string());
+
+                    match offer {
+                        Some(offer) => {
+                            // Lets servers answering with all the candidates
+                            // know when gathering is complete
+                            signaller.emit_by_name::<()>(
+                                "consumer-added",
+                                &[&peer_id, this.webrtcbin().upcast_ref::<gst::Element>()],
+                            );
+                            this.handle_offer(offer)
+                        }
+                        None => this.create_offer(&signaller, peer_id),
+                    }
+                }),
+            ),
+
             request_meta: signaller.connect_closure(
                 "request-meta",
                 false,
@@ -613,6 +950,18 @@ impl WebRTCSrc {
                 }),
             ),
 
+            producer_added: signaller.connect_closure(
+                "producer-added",
+                false,
+                glib::closure!(@to-owned self as this => move |
+                        signaller: glib::Object,
+                        producer_id: &str,
+                        meta: Option<gst::Structure>,
+                        _new_connection: bool| {
+                    this.maybe_select_producer(&signaller, producer_id, meta.as_ref());
+                }),
+            ),
+
             session_description: signaller.connect_closure(
                 "session-description",
                 false,
@@ -620,9 +969,11 @@ impl WebRTCSrc {
                         _signaller: glib::Object,
                         _peer_id: &str,
                         desc: &gst_webrtc::WebRTCSessionDescription| {
-                    assert_eq!(desc.type_(), gst_webrtc::WebRTCSDPType::Offer);
-
-                    this.handle_offer(desc);
+                    match desc.type_() {
+                        gst_webrtc::WebRTCSDPType::Offer => this.handle_offer(desc),
+                        gst_webrtc::WebRTCSDPType::Answer => this.handle_answer(desc),
+                        type_ => gst::warning!(CAT, imp: this, "Ignoring unexpected {type_:?}"),
+                    }
                 }),
             ),
 
@@ -698,12 +1049,76 @@ impl WebRTCSrc {
         true
     }
 
+    // Assigns the stream to the first free pad requested by the application
+    // for its media type, returning the caps to negotiate for it
+    fn assign_requested_src_pad(
+        &self,
+        requested_pads: &[WebRTCSrcPad],
+        caps: &gst::Caps,
+        stream_id: &str,
+    ) -> Option<gst::Caps> {
+        let media_type = caps.structure(0)?.get::<&str>("media").ok()?;
+
+        for pad in requested_pads {
+            if pad.imp().has_stream_id()
+                || !pad
+                    .pad_template()
+                    .map_or(false, |templ| templ.name().starts_with(media_type))
+            {
+                continue;
+            }
+
+            let requested_caps = pad.imp().requested_caps();
+            let caps = match requested_caps {
+                Some(ref requested_caps) => filter_offered_caps(caps, requested_caps),
+                None => caps.clone(),
+            };
+
+            if caps.is_empty() {
+                continue;
+            }
+
+            gst::debug!(CAT, imp: self, "Assigning {stream_id} to {}", pad.name());
+
+            pad.imp().set_stream_id(stream_id);
+            match requested_caps {
+                Some(ref requested_caps) => pad.imp().set_needs_decoding(
+                    !requested_caps
+                        .iter()
+                        .all(|s| s.name() == RTP_CAPS.structure(0).unwrap().name()),
+                ),
+                None => {
+                    let raw_caps = if media_type == "video" {
+                        VIDEO_CAPS.to_owned()
+                    } else {
+                        AUDIO_CAPS.to_owned()
+                    };
+                    let caps_with_raw = [caps.clone(), raw_caps.clone()]
+                        .into_iter()
+                        .collect::<gst::Caps>();
+                    let downstream_caps = pad.peer_query_caps(Some(&caps_with_raw));
+                    if let Some(first_struct) = downstream_caps.structure(0) {
+                        if first_struct.has_name(raw_caps.structure(0).unwrap().name()) {
+                            pad.imp().set_needs_decoding(true)
+                        }
+                    }
+                }
+            }
+
+            return Some(caps);
+        }
+
+        None
+    }
+
     fn handle_offer(&self, offer: &gst_webrtc::WebRTCSessionDescription) {
         gst::log!(CAT, imp: self, "Got offer {}", offer.sdp().to_string());
 
         let sdp = offer.sdp();
-        let direction = gst_webrtc::WebRTCRTPTransceiverDirection::Recvonly;
         let webrtcbin = self.webrtcbin();
+        // When the application requested pads, only the matching medias are
+        // received, and no other pad is exposed
+        let requested_pads = self.requested_src_pads();
         for (i, media) in sdp.medias().enumerate() {
             let codec_names = {
                 let settings = self.settings.lock().unwrap();
@@ -766,19 +1181,15 @@ impl WebRTCSrc {
 
             if !caps.is_empty() {
                 let stream_id = self.get_stream_id(None, Some(i as u32)).unwrap();
-                if self.create_and_probe_src_pad(&caps, &stream_id) {
-                    gst::info!(
-                        CAT,
-                        imp: self,
-                        "Adding transceiver for {stream_id} with caps: {caps:#?}"
-                    );
-                    let transceiver = webrtcbin.emit_by_name::<gst_webrtc::WebRTCRTPTransceiver>(
-                        "add-transceiver",
-                        &[&direction, &caps],
-                    );
+                let caps = if requested_pads.is_empty() {
+                    self.create_and_probe_src_pad(&caps, &stream_id)
+                        .then_some(caps)
+                } else {
+                    self.assign_requested_src_pad(&requested_pads, &caps, &stream_id)
+                };
 
-                    transceiver.set_property("do_nack", true);
-                    transceiver.set_property("fec-type", gst_webrtc::WebRTCFECType::UlpRed);
+                if let Some(caps) = caps {
+                    self.add_transceiver(&webrtcbin, &stream_id, &caps);
                 }
             } else {
                 gst::info!(
@@ -795,31 +1206,162 @@ impl WebRTCSrc {
 
         let promise =
             gst::Promise::with_change_func(glib::clone!(@weak self as this => move |reply| {
-                    this.on_answer_created(reply);
+                    this.on_description_created(reply, "answer");
                 }
             ));
 
         webrtcbin.emit_by_name::<()>("create-answer", &[&None::<gst::Structure>, &promise]);
     }
 
-    fn on_answer_created(&self, reply: Result<Option<&gst::StructureRef>, gst::PromiseError>) {
+    /// Offers to receive the configured codecs when the signaller requests
+    /// a session without an offer, as with WHEP where the consumer
+    /// initiates the negotiation
+    fn create_offer(&self, signaller: &glib::Object, peer_id: &str) {
+        let webrtcbin = self.webrtcbin();
+
+        // Lets the signaller watch ICE gathering before it starts
+        signaller.emit_by_name::<()>(
+            "consumer-added",
+            &[&peer_id, webrtcbin.upcast_ref::<gst::Element>()],
+        );
+
+        let (video_caps, audio_caps) = {
+            let settings = self.settings.lock().unwrap();
+            let mut payload = 96;
+
+            (
+                offered_caps(&settings.video_codecs, &mut payload),
+                offered_caps(&settings.audio_codecs, &mut payload),
+            )
+        };
+
+        // One media per requested pad, or one video and one audio media,
+        // the mline of each is known in advance
+        let requested_pads = self.requested_src_pads();
+        let medias = if requested_pads.is_empty() {
+            vec![video_caps.clone(), audio_caps.clone()]
+        } else {
+            requested_pads
+                .iter()
+                .map(|pad| {
+                    if pad.name().starts_with("video_") {
+                        video_caps.clone()
+                    } else {
+                        audio_caps.clone()
+                    }
+                })
+                .collect()
+        };
+
+        let mut mline = 0;
+        for caps in medias {
+            if caps.is_empty() {
+                continue;
+            }
+
+            let stream_id = self.get_stream_id(None, Some(mline)).unwrap();
+            let caps = if requested_pads.is_empty() {
+                self.create_and_probe_src_pad(&caps, &stream_id)
+                    .then_some(caps)
+            } else {
+                self.assign_requested_src_pad(&requested_pads, &caps, &stream_id)
+            };
+
+            if let Some(caps) = caps {
+                self.add_transceiver(&webrtcbin, &stream_id, &caps);
+                mline += 1;
+            }
+        }
+
+        self.obj().no_more_pads();
+
+        let promise =
+            gst::Promise::with_change_func(glib::clone!(@weak self as this => move |reply| {
+                    this.on_description_created(reply, "offer");
+                }
+            ));
+
+        webrtcbin.emit_by_name::<()>("create-offer", &[&None::<gst::Structure>, &promise]);
+    }
+
+    fn handle_answer(&self, answer: &gst_webrtc::WebRTCSessionDescription) {
+        gst::log!(CAT, imp: self, "Got answer {}", answer.sdp().to_string());
+
+        // The medias the producer rejected will never get any data
+        let obj = self.obj();
+        for (i, media) in answer.sdp().medias().enumerate() {
+            if media.port() != 0 {
+                continue;
+            }
+
+            let Some(stream_id) = self.get_stream_id(None, Some(i as u32)) else {
+                continue;
+            };
+
+            for pad in obj.src_pads() {
+                if is_requested_pad(&pad) {
+                    continue;
+                }
+
+                let pad = pad.downcast::<WebRTCSrcPad>().unwrap();
+                if pad.imp().matches_stream_id(&stream_id) {
+                    gst::info!(CAT, imp: self, "Media {i} rejected, removing {}", pad.name());
+                    let _ = obj.remove_pad(&pad);
+                }
+            }
+        }
+
+        self.webrtcbin()
+            .emit_by_name::<()>("set-remote-description", &[answer, &None::<gst::Promise>]);
+    }
+
+    fn requested_src_pads(&self) -> Vec<WebRTCSrcPad> {
+        self.obj()
+            .src_pads()
+            .into_iter()
+            .filter(is_requested_pad)
+            .map(|pad| pad.downcast::<WebRTCSrcPad>().unwrap())
+            .collect()
+    }
+
+    fn add_transceiver(&self, webrtcbin: &gst::Bin, stream_id: &str, caps: &gst::Caps) {
+        gst::info!(
+            CAT,
+            imp: self,
+            "Adding transceiver for {stream_id} with caps: {caps:#?}"
+        );
+        let transceiver = webrtcbin.emit_by_name::<gst_webrtc::WebRTCRTPTransceiver>(
+            "add-transceiver",
+            &[&gst_webrtc::WebRTCRTPTransceiverDirection::Recvonly, caps],
+        );
+
+        transceiver.set_property("do_nack", true);
+        transceiver.set_property("fec-type", gst_webrtc::WebRTCFECType::UlpRed);
+    }
+
+    /// Sets our offer or answer, `kind`, as local description and sends it
+    /// to the peer
+    fn on_description_created(
+        &self,
+        reply: Result<Option<&gst::StructureRef>, gst::PromiseError>,
+        kind: &str,
+    ) {
         let reply = match reply {
             Ok(Some(reply)) => {
-                if !reply.has_field_with_type(
-                    "answer",
-                    gst_webrtc::WebRTCSessionDescription::static_type(),
-                ) {
+                if !reply
+                    .has_field_with_type(kind, gst_webrtc::WebRTCSessionDescription::static_type())
+                {
                     gst::element_error!(
                         self.obj(),
                         gst::StreamError::Failed,
-                        ["create-answer::Promise returned with no reply"]
+                        ["create-{kind}::Promise returned with no reply"]
                     );
                     return;
                 } else if reply.has_field_with_type("error", glib::Error::static_type()) {
                     gst::element_error!(
                         self.obj(),
                         gst::LibraryError::Failed,
-                        ["create-offer::Promise returned with error: {:?}", reply]
+                        ["create-{kind}::Promise returned with error: {:?}", reply]
                     );
                     return;
                 }
@@ -830,7 +1372,7 @@ impl WebRTCSrc {
                 gst::element_error!(
                     self.obj(),
                     gst::StreamError::Failed,
-                    ["create-answer::Promise returned with no reply"]
+                    ["create-{kind}::Promise returned with no reply"]
                 );
 
                 return;
@@ -839,21 +1381,21 @@ impl WebRTCSrc {
                 gst::element_error!(
                     self.obj(),
                     gst::LibraryError::Failed,
-                    ["create-answer::Promise returned with error {:?}", err]
+                    ["create-{kind}::Promise returned with error {:?}", err]
                 );
 
                 return;
             }
         };
 
-        let answer = reply
-            .value("answer")
+        let desc = reply
+            .value(kind)
             .unwrap()
             .get::<gst_webrtc::WebRTCSessionDescription>()
             .expect("Invalid argument");
 
         self.webrtcbin()
-            .emit_by_name::<()>("set-local-description", &[&answer, &None::<gst::Promise>]);
+            .emit_by_name::<()>("set-local-description", &[&desc, &None::<gst::Promise>]);
 
         let session_id = {
             let state = self.state.lock().unwrap();
@@ -871,15 +1413,77 @@ impl WebRTCSrc {
             }
         };
 
-        gst::log!(CAT, imp: self, "Sending SDP, {}", answer.sdp().to_string());
+        gst::log!(CAT, imp: self, "Sending SDP, {}", desc.sdp().to_string());
         let signaller = self.signaller();
-        signaller.send_sdp(&session_id, &answer);
+        signaller.send_sdp(&session_id, &desc);
     }
 
     fn on_data_channel(&self, data_channel: glib::Object) {
         gst::info!(CAT, imp: self, "Received data channel {data_channel:?}");
+        let Ok(data_channel) = data_channel.dynamic_cast::<WebRTCDataChannel>() else {
+            return;
+        };
+
+        if data_channel.label().as_deref() == Some("clock") {
+            if self.settings.lock().unwrap().enable_precise_sync {
+                data_channel.connect_on_message_string(
+                    glib::clone!(@weak self as this => move |_channel, msg| {
+                        if let Some(msg) = msg {
+                            this.handle_clock_info(msg);
+                        }
+                    }),
+                );
+            }
+
+            return;
+        }
+
         let mut state = self.state.lock().unwrap();
-        state.data_channel = data_channel.dynamic_cast::<WebRTCDataChannel>().ok();
+        state.data_channel = Some(data_channel);
+    }
+
+    /// Handles the clock the producer signalled, for the application to
+    /// select it as the pipeline clock when it isn't already
+    fn handle_clock_info(&self, msg: &str) {
+        let info = match serde_json::from_str::<serde_json::Value>(msg) {
+            Ok(serde_json::Value::Object(info)) => info,
+            _ => {
+                gst::warning!(CAT, imp: self, "Ignoring invalid clock info {msg}");
+                return;
+            }
+        };
+
+        let Some(ts_refclk) = info.get("ts-refclk").and_then(|v| v.as_str()) else {
+            gst::warning!(CAT, imp: self, "Clock info without ts-refclk: {msg}");
+            return;
+        };
+
+        let obj = self.obj();
+        let local_ts_refclk = obj.clock().map(|clock| clock_ts_refclk(&clock));
+        let is_synced = ts_refclk != "local" && local_ts_refclk.as_deref() == Some(ts_refclk);
+
+        if is_synced {
+            gst::info!(CAT, imp: self, "Producer shares our clock {ts_refclk}");
+        } else {
+            gst::warning!(
+                CAT,
+                imp: self,
+                "Producer clock {ts_refclk} differs from the pipeline clock {local_ts_refclk:?}, streams won't be aligned with other producers"
+            );
+        }
+
+        let base_time = info.get("base-time").and_then(|v| v.as_u64());
+        let _ = obj.post_message(
+            gst::message::Element::builder(
+                gst::Structure::builder("webrtcsrc-clock-info")
+                    .field("ts-refclk", ts_refclk)
+                    .field("base-time", base_time.map(gst::ClockTime::from_nseconds))
+                    .field("synced", is_synced)
+                    .build(),
+            )
+            .src(&*obj)
+            .build(),
+        );
     }
 
     fn on_ice_candidate(&self, sdp_m_line_index: u32, candidate: String) {
@@ -940,9 +1544,129 @@ impl WebRTCSrc {
             state.signaller_state = SignallerState::Stopped;
             gst::info!(CAT, imp: self, "Stopped signaller");
         }
+
+        // Select a producer again when restarted
+        if std::mem::take(&mut state.selected_producer) {
+            self.signaller()
+                .set_property("producer-peer-id", None::<String>);
+        }
+    }
+
+    /// Requests a session with the first announced producer passing our
+    /// filters, unless the producer to consume from is set on the signaller
+    fn maybe_select_producer(
+        &self,
+        signaller: &glib::Object,
+        producer_id: &str,
+        meta: Option<&gst::Structure>,
+    ) {
+        if signaller.find_property("producer-peer-id").is_none()
+            || signaller
+                .property::<Option<String>>("producer-peer-id")
+                .is_some()
+        {
+            return;
+        }
+
+        {
+            let settings = self.settings.lock().unwrap();
+
+            if !settings.producer_peer_ids.is_empty()
+                && !settings
+                    .producer_peer_ids
+                    .iter()
+                    .any(|id| id == producer_id)
+            {
+                gst::debug!(CAT, imp: self, "Producer {producer_id} isn't allowed");
+                return;
+            }
+
+            if let Some(filter) = settings.producer_meta_filter.as_ref() {
+                if !meta.map_or(false, |meta| meta_matches(meta, filter)) {
+                    gst::debug!(
+                        CAT,
+                        imp: self,
+                        "Meta {meta:?} of producer {producer_id} doesn't match {filter}"
+                    );
+                    return;
+                }
+            }
+        }
+
+        gst::info!(CAT, imp: self, "Selected producer {producer_id}");
+        self.state.lock().unwrap().selected_producer = true;
+        signaller.set_property("producer-peer-id", producer_id);
     }
 }
 
+/// Whether the fields of `meta` are subsets of the fields of `filter`,
+/// whatever the names of the structures
+fn meta_matches(meta: &gst::Structure, filter: &gst::Structure) -> bool {
+    let mut meta = meta.clone();
+    meta.set_name(filter.name());
+
+    meta.is_subset(filter)
+}
+
+fn is_requested_pad(pad: &gst::Pad) -> bool {
+    pad.pad_template()
+        .map_or(false, |templ| templ.presence() == gst::PadPresence::Request)
+}
+
+/// Keeps the offered RTP formats matching the caps a pad was requested with,
+/// which can be RTP, encoded or raw caps
+fn filter_offered_caps(caps: &gst::Caps, requested_caps: &gst::Caps) -> gst::Caps {
+    caps.iter()
+        .filter(|s| {
+            requested_caps
+                .iter()
+                .any(|requested| match requested.name().as_str() {
+                    "video/x-raw" | "audio/x-raw" => true,
+                    "application/x-rtp" => s.can_intersect(requested),
+                    _ => s
+                        .get::<&str>("encoding-name")
+                        .ok()
+                        .and_then(Codecs::find)
+                        .map_or(false, |codec| {
+                            codec.caps.iter().any(|c| c.can_intersect(requested))
+                        }),
+                })
+        })
+        .map(|s| s.to_owned())
+        .collect()
+}
+
+/// RTP caps offering to receive `codecs`, with dynamic payload types
+/// allocated from `payload`
+fn offered_caps(codecs: &[Codec], payload: &mut i32) -> gst::Caps {
+    codecs
+        .iter()
+        .map(|codec| {
+            let (media, clock_rate) = if codec.is_video() {
+                ("video", 90000i32)
+            } else {
+                ("audio", 48000i32)
+            };
+
+            let mut s = gst::Structure::builder("application/x-rtp")
+                .field("media", media)
+                .field("encoding-name", codec.name.as_str())
+                .field("payload", *payload)
+                .field("clock-rate", clock_rate);
+
+            match codec.name.as_str() {
+                "OPUS" => s = s.field("encoding-params", "2"),
+                "H264" => s = s.field("packetization-mode", "1"),
+                _ => (),
+            }
+
+            *payload += 1;
+
+            s.build()
+        })
+        .collect()
+}
+
 impl ElementImpl for WebRTCSrc {
     fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
         static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
@@ -994,12 +1718,69 @@ impl ElementImpl for WebRTCSrc {
                     WebRTCSrcPad::static_type(),
                 )
                 .unwrap(),
+                gst::PadTemplate::with_gtype(
+                    "video_req_%u",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Request,
+                    &video_caps_builder.build(),
+                    WebRTCSrcPad::static_type(),
+                )
+                .unwrap(),
+                gst::PadTemplate::with_gtype(
+                    "audio_req_%u",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Request,
+                    &audio_caps_builder.build(),
+                    WebRTCSrcPad::static_type(),
+                )
+                .unwrap(),
             ]
         });
 
         PAD_TEMPLATES.as_ref()
     }
 
+    fn request_new_pad(
+        &self,
+        templ: &gst::PadTemplate,
+        _name: Option<&str>,
+        caps: Option<&gst::Caps>,
+    ) -> Option<gst::Pad> {
+        let obj = self.obj();
+        if obj.current_state() > gst::State::Ready {
+            gst::error!(CAT, imp: self, "pads can only be requested before starting");
+            return None;
+        }
+
+        let name = if templ.name().starts_with("video_") {
+            format!(
+                "video_req_{}",
+                self.n_video_pads.fetch_add(1, Ordering::SeqCst)
+            )
+        } else {
+            format!(
+                "audio_req_{}",
+                self.n_audio_pads.fetch_add(1, Ordering::SeqCst)
+            )
+        };
+
+        let pad = gst::GhostPad::builder_from_template(templ)
+            .name(name)
+            .build()
+            .downcast::<WebRTCSrcPad>()
+            .unwrap();
+        pad.imp().set_requested_caps(caps.cloned());
+        obj.add_pad(&pad)
+            .expect("Adding ghost pad should never fail");
+
+        Some(pad.upcast())
+    }
+
+    fn release_pad(&self, pad: &gst::Pad) {
+        let _ = pad.set_active(false);
+        let _ = self.obj().remove_pad(pad);
+    }
+
     fn change_state(
         &self,
         transition: gst::StateChange,
@@ -1091,7 +1872,11 @@ impl URIHandlerImpl for WebRTCSrc {
     }
 
     fn uri(&self) -> Option<String> {
-        self.signaller().property::<Option<String>>("uri")
+        let signaller = self.signaller();
+
+        signaller
+            .find_property("uri")
+            .and_then(|_| signaller.property::<Option<String>>("uri"))
     }
 
     fn set_uri(&self, uri: &str) -> Result<(), glib::Error> {
@@ -1127,6 +1912,8 @@ enum SignallerState {
 
 struct State {
     session_id: Option<String>,
+    /// Whether we set the producer to consume from on the signaller
+    selected_producer: bool,
     signaller_state: SignallerState,
     webrtcbin: Option<gst::Element>,
     flow_combiner: gst_base::UniqueFlowCombiner,
@@ -1139,6 +1926,7 @@ impl Default for State {
         Self {
             signaller_state: SignallerState::Stopped,
             session_id: None,
+            selected_producer: false,
             webrtcbin: None,
             flow_combiner: Default::default(),
             signaller_signals: Default::default(),
@@ -1146,3 +1934,221 @@ impl Default for State {
         }
     }
 }
+
+#[derive(Default)]
+pub struct WhepWebRTCSrc {}
+
+impl ObjectImpl for WhepWebRTCSrc {
+    fn constructed(&self) {
+        let element = self.obj();
+        let ws = element.upcast_ref::<super::WebRTCSrc>().imp();
+
+        // Before the parent connects to it
+        ws.settings.lock().unwrap().signaller = WhepSignaller::default().upcast();
+
+        self.parent_constructed();
+    }
+}
+
+impl GstObjectImpl for WhepWebRTCSrc {}
+
+impl ElementImpl for WhepWebRTCSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "WhepWebRTCSrc",
+                "Source/Network/WebRTC",
+                "WebRTC src with WHEP client signaller",
+                "Thibault Saunier <tsaunier@igalia.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl BinImpl for WhepWebRTCSrc {}
+
+impl WebRTCSrcImpl for WhepWebRTCSrc {}
+
+#[glib::object_subclass]
+impl ObjectSubclass for WhepWebRTCSrc {
+    const NAME: &'static str = "GstWhepWebRTCSrc";
+    type Type = super::WhepWebRTCSrc;
+    type ParentType = super::WebRTCSrc;
+}
+
+#[derive(Default)]
+pub struct SipWebRTCSrc {}
+
+impl ObjectImpl for SipWebRTCSrc {
+    fn constructed(&self) {
+        let element = self.obj();
+        let ws = element.upcast_ref::<super::WebRTCSrc>().imp();
+
+        // Before the parent connects to it
+        ws.settings.lock().unwrap().signaller = SipSignaller::default().upcast();
+
+        self.parent_constructed();
+    }
+}
+
+impl GstObjectImpl for SipWebRTCSrc {}
+
+impl ElementImpl for SipWebRTCSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "SipWebRTCSrc",
+                "Source/Network/WebRTC",
+                "WebRTC src with SIP signaller",
+                "Thibault Saunier <tsaunier@igalia.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl BinImpl for SipWebRTCSrc {}
+
+impl WebRTCSrcImpl for SipWebRTCSrc {}
+
+#[glib::object_subclass]
+impl ObjectSubclass for SipWebRTCSrc {
+    const NAME: &'static str = "GstSipWebRTCSrc";
+    type Type = super::SipWebRTCSrc;
+    type ParentType = super::WebRTCSrc;
+}
+
+#[derive(Default)]
+pub struct GrpcWebRTCSrc {}
+
+impl ObjectImpl for GrpcWebRTCSrc {
+    fn constructed(&self) {
+        let element = self.obj();
+        let ws = element.upcast_ref::<super::WebRTCSrc>().imp();
+
+        // Before the parent connects to it
+        ws.settings.lock().unwrap().signaller =
+            GrpcSignaller::new(WebRTCSignallerRole::Consumer).upcast();
+
+        self.parent_constructed();
+    }
+}
+
+impl GstObjectImpl for GrpcWebRTCSrc {}
+
+impl ElementImpl for GrpcWebRTCSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "GrpcWebRTCSrc",
+                "Source/Network/WebRTC",
+                "WebRTC src with gRPC signaller",
+                "Thibault Saunier <tsaunier@igalia.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl BinImpl for GrpcWebRTCSrc {}
+
+impl WebRTCSrcImpl for GrpcWebRTCSrc {}
+
+#[glib::object_subclass]
+impl ObjectSubclass for GrpcWebRTCSrc {
+    const NAME: &'static str = "GstGrpcWebRTCSrc";
+    type Type = super::GrpcWebRTCSrc;
+    type ParentType = super::WebRTCSrc;
+}
+
+#[derive(Default)]
+pub struct WhipServerSrc {}
+
+impl ObjectImpl for WhipServerSrc {
+    fn constructed(&self) {
+        let element = self.obj();
+        let ws = element.upcast_ref::<super::WebRTCSrc>().imp();
+
+        // Before the parent connects to it, a source can only receive one
+        // stream at a time
+        ws.settings.lock().unwrap().signaller = HttpServerSignaller::with_max_sessions(1).upcast();
+
+        self.parent_constructed();
+    }
+}
+
+impl GstObjectImpl for WhipServerSrc {}
+
+impl ElementImpl for WhipServerSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "WhipServerSrc",
+                "Source/Network/WebRTC",
+                "WebRTC src with WHIP server signaller",
+                "Thibault Saunier <tsaunier@igalia.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl BinImpl for WhipServerSrc {}
+
+impl WebRTCSrcImpl for WhipServerSrc {}
+
+#[glib::object_subclass]
+impl ObjectSubclass for WhipServerSrc {
+    const NAME: &'static str = "GstWhipServerSrc";
+    type Type = super::WhipServerSrc;
+    type ParentType = super::WebRTCSrc;
+}
+
+#[derive(Default)]
+pub struct IpcWebRTCSrc {}
+
+impl ObjectImpl for IpcWebRTCSrc {
+    fn constructed(&self) {
+        let element = self.obj();
+        let ws = element.upcast_ref::<super::WebRTCSrc>().imp();
+
+        // Before the parent connects to it
+        ws.settings.lock().unwrap().signaller =
+            IpcSignaller::new(WebRTCSignallerRole::Consumer).upcast();
+
+        self.parent_constructed();
+    }
+}
+
+impl GstObjectImpl for IpcWebRTCSrc {}
+
+impl ElementImpl for IpcWebRTCSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "IpcWebRTCSrc",
+                "Source/Network/WebRTC",
+                "WebRTC src with external process signaller",
+                "Thibault Saunier <tsaunier@igalia.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+}
+
+impl BinImpl for IpcWebRTCSrc {}
+
+impl WebRTCSrcImpl for IpcWebRTCSrc {}
+
+#[glib::object_subclass]
+impl ObjectSubclass for IpcWebRTCSrc {
+    const NAME: &'static str = "GstIpcWebRTCSrc";
+    type Type = super::IpcWebRTCSrc;
+    type ParentType = super::WebRTCSrc;
+}
@@ -31,13 +31,108 @@
  * in `decodebinX` but for the case where a `videoconvert` is placed after a `video_XX` pad,
  * decoding will happen inside `webrtcsrc`.
  *
+ * ## Requesting pads
+ *
+ * By default, one pad is exposed for each stream offered by the producer. Applications
+ * only interested in some of them can instead request `video_req_%u` and `audio_req_%u`
+ * pads before starting, in which case only the offered medias matching a requested pad
+ * are received and no other pad is exposed.
+ *
+ * The caps a pad is requested with restrict the codecs accepted for it: RTP caps are
+ * exposed as is, encoded caps such as `video/x-h264` are depayloaded and parsed, for
+ * instance to record the stream without transcoding, and raw caps are decoded.
+ *
  * Since: 0.10
  */
+
+/**
+ * SECTION:element-whepwebrtcsrc
+ *
+ * `whepwebrtcsrc` is a #webrtcsrc pulling streams from WHEP egress endpoints, such
+ * as the ones of Cloudflare Stream or MediaMTX, it is the counterpart of
+ * `whipclientsink`:
+ *
+ * ``` bash
+ * gst-launch-1.0 whepwebrtcsrc signaller::whep-endpoint="http://127.0.0.1:8889/mystream/whep" ! videoconvert ! autovideosink
+ * ```
+ *
+ * As the WHEP client, the element creates the offer, receiving one video and one audio
+ * stream, or one stream per requested pad. The offer is posted once ICE gathering is
+ * complete and the pads of the medias the endpoint rejects are removed.
+ */
+
+/**
+ * SECTION:element-sipwebrtcsrc
+ *
+ * `sipwebrtcsrc` is a #webrtcsrc calling a SIP endpoint that supports ICE and
+ * DTLS-SRTP, such as a WebRTC gateway or a PBX, and receiving its media:
+ *
+ * ``` bash
+ * gst-launch-1.0 sipwebrtcsrc signaller::target-uri="sip:1000@pbx.example.com" \
+ *     signaller::registrar-uri="sip:pbx.example.com" signaller::username=gst \
+ *     signaller::password=secret ! videoconvert ! autovideosink
+ * ```
+ *
+ * The offer is sent in an INVITE once ICE gathering is complete, the call is
+ * hung up when the element stops. Incoming calls are not supported.
+ */
+
+/**
+ * SECTION:element-grpcwebrtcsrc
+ *
+ * `grpcwebrtcsrc` is a #webrtcsrc consuming a producer through a signalling
+ * service implementing the `gstreamer.webrtc.signalling.v1.Signalling` gRPC
+ * service, see `signalling.proto` in the sources of the plugin:
+ *
+ * ``` bash
+ * gst-launch-1.0 grpcwebrtcsrc signaller::uri="http://127.0.0.1:50051" \
+ *     signaller::producer-peer-id=<producer-peer-id> ! videoconvert ! autovideosink
+ * ```
+ *
+ * The messages are those of the WebSocket protocol of the default signaller,
+ * for backend services that would rather generate a client from the schema.
+ */
+/**
+ * SECTION:element-whipserversrc
+ *
+ * `whipserversrc` is a #webrtcsrc acting as a WHIP endpoint, receiving the stream
+ * of a WHIP client such as OBS or `whipclientsink`:
+ *
+ * ``` bash
+ * gst-launch-1.0 whipserversrc signaller::host-addr=http://0.0.0.0:8190 ! videoconvert ! autovideosink
+ * ```
+ *
+ * Clients POST their offer to `signaller::endpoint-path`, `/endpoint` by default,
+ * and end the session with a DELETE request on the URL of the Location header of
+ * the answer. The answer is sent once ICE gathering is complete, with all the
+ * candidates.
+ *
+ * Only one client is accepted at a time, as the source receives the streams of a
+ * single peer. The signaller itself terminates any number of concurrent ingests,
+ * each at its own `<endpoint-path>/<session id>` resource, and emits
+ * `client-connected` for each of them.
+ */
+
+/**
+ * SECTION:element-ipcwebrtcsrc
+ *
+ * `ipcwebrtcsrc` is a #webrtcsrc whose signalling is implemented by an external
+ * process, for instance a Python or Node.js script, exchanging newline-delimited
+ * JSON messages with the element over its standard input and output, or over a
+ * unix socket:
+ *
+ * ``` bash
+ * gst-launch-1.0 ipcwebrtcsrc signaller::command="python3 signaller.py" \
+ *     signaller::producer-peer-id=<producer-peer-id> ! videoconvert ! autovideosink
+ * ```
+ *
+ * See the documentation of the plugin for the messages.
+ */
 mod imp;
 mod pad;
 
 use crate::signaller::Signallable;
-use crate::signaller::WebRTCSignallerRole;
+use crate::signaller::{WebRTCSignallerRole, WebRTCSignallerState};
 use gst::prelude::*;
 use gst::{glib, prelude::StaticType};
 
@@ -45,12 +140,33 @@ glib::wrapper! {
     pub struct WebRTCSrc(ObjectSubclass<imp::WebRTCSrc>) @extends gst::Bin, gst::Element, gst::Object, @implements gst::URIHandler, gst::ChildProxy;
 }
 
+glib::wrapper! {
+    pub struct WhepWebRTCSrc(ObjectSubclass<imp::WhepWebRTCSrc>) @extends WebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::URIHandler, gst::ChildProxy;
+}
+
+glib::wrapper! {
+    pub struct SipWebRTCSrc(ObjectSubclass<imp::SipWebRTCSrc>) @extends WebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::URIHandler, gst::ChildProxy;
+}
+
+glib::wrapper! {
+    pub struct GrpcWebRTCSrc(ObjectSubclass<imp::GrpcWebRTCSrc>) @extends WebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::URIHandler, gst::ChildProxy;
+}
+
+glib::wrapper! {
+    pub struct WhipServerSrc(ObjectSubclass<imp::WhipServerSrc>) @extends WebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::URIHandler, gst::ChildProxy;
+}
+
+glib::wrapper! {
+    pub struct IpcWebRTCSrc(ObjectSubclass<imp::IpcWebRTCSrc>) @extends WebRTCSrc, gst::Bin, gst::Element, gst::Object, @implements gst::URIHandler, gst::ChildProxy;
+}
+
 glib::wrapper! {
     pub struct WebRTCSrcPad(ObjectSubclass<pad::WebRTCSrcPad>) @extends gst::GhostPad, gst::ProxyPad, gst::Pad, gst::Object;
 }
 
 pub fn register(plugin: Option<&gst::Plugin>) -> Result<(), glib::BoolError> {
     WebRTCSignallerRole::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
+    WebRTCSignallerState::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     WebRTCSrcPad::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     Signallable::static_type().mark_as_plugin_api(gst::PluginAPIFlags::empty());
     gst::Element::register(
@@ -58,5 +174,35 @@ pub fn register(plugin: Option<&gst::Plugin>) -> Result<(), glib::BoolError> {
         "webrtcsrc",
         gst::Rank::Primary,
         WebRTCSrc::static_type(),
+    )?;
+    gst::Element::register(
+        plugin,
+        "whepwebrtcsrc",
+        gst::Rank::None,
+        WhepWebRTCSrc::static_type(),
+    )?;
+    gst::Element::register(
+        plugin,
+        "sipwebrtcsrc",
+        gst::Rank::None,
+        SipWebRTCSrc::static_type(),
+    )?;
+    gst::Element::register(
+        plugin,
+        "grpcwebrtcsrc",
+        gst::Rank::None,
+        GrpcWebRTCSrc::static_type(),
+    )?;
+    gst::Element::register(
+        plugin,
+        "whipserversrc",
+        gst::Rank::None,
+        WhipServerSrc::static_type(),
+    )?;
+    gst::Element::register(
+        plugin,
+        "ipcwebrtcsrc",
+        gst::Rank::None,
+        IpcWebRTCSrc::static_type(),
     )
 }
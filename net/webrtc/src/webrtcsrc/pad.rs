@@ -1,15 +1,43 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use gst::glib;
+use gst::prelude::*;
 use gst::subclass::prelude::*;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Mutex;
 
+/// The RTP header fields of the last packet that was output, and the
+/// offsets applied to the packets of the current session
+#[derive(Default)]
+struct RtpTimeline {
+    ssrc: u32,
+    seqnum: u16,
+    rtptime: u32,
+    pts: Option<gst::ClockTime>,
+    seqnum_offset: u16,
+    rtptime_offset: u32,
+}
+
+/// Keeps the output of the pad continuous across the sessions feeding it
+#[derive(Default)]
+struct Timeline {
+    enabled: bool,
+    /// Set when a session starts feeding the pad, until its first buffer
+    rebase: bool,
+    /// In nanoseconds, added to the timestamps of the current session
+    offset: i64,
+    /// End of the last buffer that was output
+    end: Option<gst::ClockTime>,
+    rtp: Option<RtpTimeline>,
+}
+
 #[derive(Default)]
 pub struct WebRTCSrcPad {
     needs_raw: AtomicBool,
     stream_id: Mutex<Option<String>>,
+    requested_caps: Mutex<Option<gst::Caps>>,
+    timeline: Mutex<Timeline>,
 }
 
 impl WebRTCSrcPad {
@@ -29,6 +57,137 @@ impl WebRTCSrcPad {
         let stream_id = self.stream_id.lock().unwrap();
         stream_id.as_ref().unwrap().clone()
     }
+
+    pub fn clear_stream_id(&self) {
+        *self.stream_id.lock().unwrap() = None;
+    }
+
+    /// Whether the pad was assigned to a stream, requested pads are not until
+    /// an offer with a matching media has been received
+    pub fn has_stream_id(&self) -> bool {
+        self.stream_id.lock().unwrap().is_some()
+    }
+
+    pub fn matches_stream_id(&self, stream_id: &str) -> bool {
+        self.stream_id.lock().unwrap().as_deref() == Some(stream_id)
+    }
+
+    pub fn set_requested_caps(&self, caps: Option<gst::Caps>) {
+        *self.requested_caps.lock().unwrap() = caps;
+    }
+
+    /// The caps the application requested the pad with, if any
+    pub fn requested_caps(&self) -> Option<gst::Caps> {
+        self.requested_caps.lock().unwrap().clone()
+    }
+
+    /// Called when a session starts feeding the pad, its output then follows
+    /// the one of the previous session if `continuous`
+    pub fn start_session(&self, continuous: bool) {
+        let mut timeline = self.timeline.lock().unwrap();
+
+        if !continuous {
+            *timeline = Timeline::default();
+            return;
+        }
+
+        timeline.enabled = true;
+        timeline.rebase = true;
+    }
+
+    /// Offsets the timestamps of the current session so that they follow the
+    /// ones of the previous session without gap, as well as the sequence
+    /// numbers and RTP timestamps of RTP output, which also keeps its SSRC
+    fn make_continuous(&self, buffer: &mut gst::Buffer) {
+        let clock_rate = self.obj().current_caps().and_then(|caps| {
+            let s = caps.structure(0)?;
+            if s.name() != "application/x-rtp" {
+                return None;
+            }
+            s.get::<i32>("clock-rate").ok().map(|rate| rate as u32)
+        });
+
+        let mut timeline = self.timeline.lock().unwrap();
+        if !timeline.enabled {
+            return;
+        }
+
+        let rebase = timeline.rebase;
+        if rebase {
+            timeline.offset = match (timeline.end, buffer.pts()) {
+                (Some(end), Some(pts)) => end.nseconds() as i64 - pts.nseconds() as i64,
+                _ => 0,
+            };
+            timeline.rebase = false;
+
+            gst::debug!(
+                super::imp::CAT,
+                obj: self.obj(),
+                "New session, offsetting timestamps by {}ns",
+                timeline.offset
+            );
+        }
+
+        let offset = timeline.offset;
+        let shift = |ts: Option<gst::ClockTime>| {
+            ts.map(
+                |ts| gst::ClockTime::from_nseconds((ts.nseconds() as i64 + offset).max(0) as u64),
+            )
+        };
+
+        let buffer = buffer.make_mut();
+        let pts = shift(buffer.pts());
+        buffer.set_pts(pts);
+        buffer.set_dts(shift(buffer.dts()));
+
+        if let Some(pts) = pts {
+            let end = pts + buffer.duration().unwrap_or(gst::ClockTime::ZERO);
+            timeline.end = Some(timeline.end.map_or(end, |last| last.max(end)));
+        }
+
+        let Some(clock_rate) = clock_rate else {
+            return;
+        };
+
+        let Ok(mut rtp) = gst_rtp::RTPBuffer::from_buffer_writable(buffer) else {
+            return;
+        };
+
+        // The first session is output as is
+        let state = timeline.rtp.get_or_insert_with(|| RtpTimeline {
+            ssrc: rtp.ssrc(),
+            seqnum: rtp.seq().wrapping_sub(1),
+            rtptime: rtp.timestamp(),
+            pts,
+            ..Default::default()
+        });
+
+        if rebase {
+            // Continue from the last packet, the RTP time advancing as much as
+            // the timestamps
+            let elapsed = pts.zip(state.pts).map_or(0, |(pts, last)| {
+                (pts.saturating_sub(last).nseconds() as u128 * clock_rate as u128 / 1_000_000_000)
+                    as u32
+            });
+
+            state.seqnum_offset = state.seqnum.wrapping_add(1).wrapping_sub(rtp.seq());
+            state.rtptime_offset = state
+                .rtptime
+                .wrapping_add(elapsed)
+                .wrapping_sub(rtp.timestamp());
+        }
+
+        let seqnum = rtp.seq().wrapping_add(state.seqnum_offset);
+        let rtptime = rtp.timestamp().wrapping_add(state.rtptime_offset);
+
+        rtp.set_ssrc(state.ssrc);
+        rtp.set_seq(seqnum);
+        rtp.set_timestamp(rtptime);
+
+        state.seqnum = seqnum;
+        state.rtptime = rtptime;
+        state.pts = pts;
+    }
 }
 
 #[glib::object_subclass]
@@ -38,7 +197,21 @@ impl ObjectSubclass for WebRTCSrcPad {
     type ParentType = gst::GhostPad;
 }
 
-impl ObjectImpl for WebRTCSrcPad {}
+impl ObjectImpl for WebRTCSrcPad {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.obj()
+            .add_probe(gst::PadProbeType::BUFFER, |pad, info| {
+                if let Some(gst::PadProbeData::Buffer(ref mut buffer)) = info.data {
+                    pad.imp().make_continuous(buffer);
+                }
+
+                gst::PadProbeReturn::Ok
+            });
+    }
+}
+
 impl GstObjectImpl for WebRTCSrcPad {}
 impl PadImpl for WebRTCSrcPad {}
 impl ProxyPadImpl for WebRTCSrcPad {}
@@ -0,0 +1,557 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::{Signallable, SignallableImpl};
+use crate::utils::{
+    build_reqwest_client, extra_headers, is_valid_proxy, parse_redirect_location, set_ice_servers,
+    wait, wait_async, TlsSettings, WaitError,
+};
+use crate::RUNTIME;
+use async_recursion::async_recursion;
+use gst::glib;
+use gst::glib::once_cell::sync::Lazy;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_webrtc::{WebRTCICEGatheringState, WebRTCSessionDescription};
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderValue;
+use reqwest::StatusCode;
+use std::sync::Mutex;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "webrtc-whep-signaller",
+        gst::DebugColorFlags::empty(),
+        Some("WebRTC WHEP signaller"),
+    )
+});
+
+const MAX_REDIRECTS: u8 = 10;
+const DEFAULT_TIMEOUT: u32 = 15;
+const SESSION_ID: &str = "unique";
+
+#[derive(Debug)]
+enum State {
+    Stopped,
+    Post { redirects: u8 },
+    Running { whep_resource_url: String },
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::Stopped
+    }
+}
+
+#[derive(Clone)]
+struct Settings {
+    whep_endpoint: Option<String>,
+    use_link_headers: bool,
+    auth_token: Option<String>,
+    timeout: u32,
+    /// Proxy to send the requests through
+    proxy: Option<String>,
+    tls: TlsSettings,
+    /// Headers to add to every request, one string field per header
+    extra_headers: Option<gst::Structure>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            whep_endpoint: None,
+            use_link_headers: false,
+            auth_token: None,
+            timeout: DEFAULT_TIMEOUT,
+            proxy: None,
+            tls: Default::default(),
+            extra_headers: None,
+        }
+    }
+}
+
+/// Signaller of a WHEP client: the offer of the consumer is posted to the
+/// endpoint, which returns the answer of the producer
+#[derive(Default)]
+pub struct Signaller {
+    state: Mutex<State>,
+    settings: Mutex<Settings>,
+    canceller: Mutex<Option<futures::future::AbortHandle>>,
+}
+
+impl Signaller {
+    fn raise_error(&self, msg: String) {
+        self.obj()
+            .emit_by_name::<()>("error", &[&format!("Error: {msg}")]);
+    }
+
+    /// The extra headers, completed with the authorization header if we
+    /// have a token
+    fn headers(&self) -> HeaderMap {
+        let settings = self.settings.lock().unwrap();
+        let mut headermap = extra_headers(settings.extra_headers.as_ref());
+
+        if let Some(token) = settings.auth_token.as_ref() {
+            let bearer_token = "Bearer ".to_owned() + token;
+            headermap.insert(
+                reqwest::header::AUTHORIZATION,
+                HeaderValue::from_str(bearer_token.as_str())
+                    .expect("Failed to set auth token to header"),
+            );
+        }
+
+        headermap
+    }
+
+    fn http_client(&self, pol: reqwest::redirect::Policy) -> reqwest::Client {
+        let (proxy, tls) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.proxy.clone(), settings.tls.clone())
+        };
+
+        let tls_connector = tls.connector().unwrap_or_else(|err| {
+            self.raise_error(format!("Invalid TLS configuration: {err:?}"));
+            None
+        });
+
+        build_reqwest_client(pol, proxy.as_deref(), tls_connector)
+    }
+
+    fn handle_future_error(&self, err: WaitError) {
+        match err {
+            WaitError::FutureAborted => {
+                gst::warning!(CAT, imp: self, "Future aborted")
+            }
+            WaitError::FutureError(err) => self.raise_error(err.to_string()),
+        };
+    }
+
+    async fn send_offer(&self, webrtcbin: &gst::Element) {
+        let Some(offer) =
+            webrtcbin.property::<Option<WebRTCSessionDescription>>("local-description")
+        else {
+            self.raise_error("Local description is not set".to_string());
+            return;
+        };
+
+        gst::debug!(
+            CAT,
+            imp: self,
+            "Sending offer SDP: {:?}",
+            offer.sdp().as_text()
+        );
+
+        let (timeout, endpoint) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.timeout, settings.whep_endpoint.clone())
+        };
+
+        let endpoint = match reqwest::Url::parse(endpoint.as_deref().unwrap_or_default()) {
+            Ok(endpoint) => endpoint,
+            Err(err) => {
+                self.raise_error(format!("Invalid WHEP endpoint URL: {err}"));
+                return;
+            }
+        };
+
+        *self.state.lock().unwrap() = State::Post { redirects: 0 };
+
+        if let Err(err) = wait_async(
+            &self.canceller,
+            self.do_post(offer, webrtcbin, endpoint),
+            timeout,
+        )
+        .await
+        {
+            self.handle_future_error(err);
+        }
+    }
+
+    #[async_recursion]
+    async fn do_post(
+        &self,
+        offer: WebRTCSessionDescription,
+        webrtcbin: &gst::Element,
+        endpoint: reqwest::Url,
+    ) {
+        let redirects = match *self.state.lock().unwrap() {
+            State::Post { redirects } => redirects,
+            _ => {
+                self.raise_error("Trying to do POST in unexpected state".to_string());
+                return;
+            }
+        };
+
+        // Default policy for redirect does not share the auth token to new location
+        // So disable inbuilt redirecting and do a recursive call upon 3xx response code
+        let client = self.http_client(reqwest::redirect::Policy::none());
+
+        let body = offer.sdp().as_text().unwrap();
+
+        gst::debug!(CAT, imp: self, "Using endpoint {}", endpoint.as_str());
+        let mut headermap = self.headers();
+        headermap.insert(
+            reqwest::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/sdp"),
+        );
+
+        let res = client
+            .request(reqwest::Method::POST, endpoint.clone())
+            .headers(headermap)
+            .body(body)
+            .send()
+            .await;
+
+        match res {
+            Ok(resp) => {
+                self.parse_endpoint_response(offer, resp, redirects, webrtcbin, endpoint)
+                    .await
+            }
+            Err(err) => self.raise_error(err.to_string()),
+        }
+    }
+
+    async fn parse_endpoint_response(
+        &self,
+        offer: WebRTCSessionDescription,
+        resp: reqwest::Response,
+        redirects: u8,
+        webrtcbin: &gst::Element,
+        endpoint: reqwest::Url,
+    ) {
+        gst::debug!(CAT, imp: self, "Response status: {}", resp.status());
+
+        match resp.status() {
+            StatusCode::OK | StatusCode::CREATED => {
+                if self.settings.lock().unwrap().use_link_headers {
+                    if let Err(e) = set_ice_servers(webrtcbin, resp.headers()) {
+                        self.raise_error(e.to_string());
+                        return;
+                    };
+                }
+
+                // The resource URL is usually relative to the endpoint
+                let url = match parse_redirect_location(resp.headers(), &endpoint) {
+                    Ok(url) => url,
+                    Err(e) => {
+                        self.raise_error(e.to_string());
+                        return;
+                    }
+                };
+
+                gst::debug!(CAT, imp: self, "WHEP resource: {}", url);
+
+                {
+                    let mut state = self.state.lock().unwrap();
+                    if !matches!(*state, State::Post { .. }) {
+                        drop(state);
+                        self.raise_error("Expected to be in POST state".to_string());
+                        return;
+                    }
+
+                    *state = State::Running {
+                        whep_resource_url: url.to_string(),
+                    };
+                }
+
+                match resp.bytes().await {
+                    Ok(ans_bytes) => match gst_sdp::SDPMessage::parse_buffer(&ans_bytes) {
+                        Ok(ans_sdp) => {
+                            let answer = WebRTCSessionDescription::new(
+                                gst_webrtc::WebRTCSDPType::Answer,
+                                ans_sdp,
+                            );
+                            self.obj()
+                                .emit_by_name::<()>("session-description", &[&SESSION_ID, &answer]);
+                        }
+                        Err(err) => {
+                            self.raise_error(format!("Could not parse answer SDP: {err}"));
+                        }
+                    },
+                    Err(err) => self.raise_error(err.to_string()),
+                }
+            }
+
+            s if s.is_redirection() => {
+                if redirects >= MAX_REDIRECTS {
+                    self.raise_error("Too many redirects. Unable to connect.".to_string());
+                    return;
+                }
+
+                match parse_redirect_location(resp.headers(), &endpoint) {
+                    Ok(redirect_url) => {
+                        *self.state.lock().unwrap() = State::Post {
+                            redirects: redirects + 1,
+                        };
+
+                        gst::debug!(
+                            CAT,
+                            imp: self,
+                            "Redirecting endpoint to {}",
+                            redirect_url.as_str()
+                        );
+
+                        self.do_post(offer, webrtcbin, redirect_url).await
+                    }
+                    Err(e) => self.raise_error(e.to_string()),
+                }
+            }
+
+            s => match resp.bytes().await {
+                Ok(r) => {
+                    let res = r.escape_ascii().to_string();
+                    self.raise_error(format!("Unexpected response: {} - {}", s.as_str(), res));
+                }
+                Err(err) => self.raise_error(err.to_string()),
+            },
+        }
+    }
+
+    /// Releases the server-side resources of the session, if any
+    fn terminate_session(&self) {
+        let resource_url = match std::mem::take(&mut *self.state.lock().unwrap()) {
+            State::Running { whep_resource_url } => whep_resource_url,
+            _ => return,
+        };
+
+        let timeout = self.settings.lock().unwrap().timeout;
+        let headermap = self.headers();
+
+        gst::debug!(CAT, imp: self, "DELETE request on {}", resource_url);
+        let client = self.http_client(reqwest::redirect::Policy::default());
+        let future = async {
+            client
+                .delete(resource_url.clone())
+                .headers(headermap)
+                .send()
+                .await
+                .map_err(|err| {
+                    gst::error_msg!(
+                        gst::ResourceError::Failed,
+                        ["DELETE request failed {}: {:?}", resource_url, err]
+                    )
+                })
+        };
+
+        match wait(&self.canceller, future, timeout) {
+            Ok(r) => {
+                gst::debug!(CAT, imp: self, "Response to DELETE : {}", r.status());
+            }
+            Err(WaitError::FutureAborted) => {
+                gst::warning!(CAT, imp: self, "DELETE request aborted")
+            }
+            Err(WaitError::FutureError(e)) => {
+                gst::error!(CAT, imp: self, "Error on DELETE request : {}", e)
+            }
+        };
+    }
+
+    fn cancel(&self) {
+        // Interrupt requests in progress, if any
+        if let Some(canceller) = &*self.canceller.lock().unwrap() {
+            canceller.abort();
+        }
+    }
+}
+
+impl SignallableImpl for Signaller {
+    fn start(&self) {
+        if self.settings.lock().unwrap().whep_endpoint.is_none() {
+            self.raise_error("WHEP endpoint URL must be set".to_string());
+            return;
+        }
+
+        // The source creates the offer, which we post once ICE gathering
+        // is complete
+        self.obj().emit_by_name::<()>(
+            "session-requested",
+            &[
+                &SESSION_ID,
+                &SESSION_ID,
+                &None::<WebRTCSessionDescription>,
+                &None::<gst::Structure>,
+            ],
+        );
+    }
+
+    fn stop(&self) {
+        self.cancel();
+        self.terminate_session();
+    }
+
+    fn end_session(&self, session_id: &str) {
+        assert_eq!(session_id, SESSION_ID);
+
+        self.cancel();
+        self.terminate_session();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Signaller {
+    const NAME: &'static str = "GstWHEPWebRTCSrcSignaller";
+    type Type = super::WhepSignaller;
+    type ParentType = glib::Object;
+    type Interfaces = (Signallable,);
+}
+
+impl ObjectImpl for Signaller {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.obj().connect_closure(
+            "consumer-added",
+            false,
+            glib::closure!(|signaller: &super::WhepSignaller,
+                            _peer_id: &str,
+                            webrtcbin: &gst::Element| {
+                let obj_weak = signaller.downgrade();
+                webrtcbin.connect_notify(Some("ice-gathering-state"), move |webrtcbin, _pspec| {
+                    let Some(obj) = obj_weak.upgrade() else {
+                        return;
+                    };
+
+                    let state =
+                        webrtcbin.property::<WebRTCICEGatheringState>("ice-gathering-state");
+
+                    match state {
+                        WebRTCICEGatheringState::Gathering => {
+                            gst::info!(CAT, obj: obj, "ICE gathering started");
+                        }
+                        WebRTCICEGatheringState::Complete => {
+                            gst::info!(CAT, obj: obj, "ICE gathering complete");
+
+                            let webrtcbin = webrtcbin.clone();
+
+                            RUNTIME.spawn(async move { obj.imp().send_offer(&webrtcbin).await });
+                        }
+                        _ => (),
+                    }
+                });
+            }),
+        );
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![glib::ParamSpecString::builder("whep-endpoint")
+                    .nick("WHEP Endpoint")
+                    .blurb("The WHEP server endpoint to POST SDP offer to.
+                        e.g.: https://example.com/whep/endpoint/room1234")
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecBoolean::builder("use-link-headers")
+                    .nick("Use Link Headers")
+                    .blurb("Use link headers to configure ice-servers from the WHEP server response to the POST request.
+                        If set to TRUE and the WHEP server returns valid ice-servers,
+                        this property overrides the ice-servers values set using the stun-server and turn-servers properties.")
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecString::builder("auth-token")
+                    .nick("Authorization Token")
+                    .blurb("Authentication token to use, will be sent in the HTTP Header as 'Bearer <auth-token>'")
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecUInt::builder("timeout")
+                    .nick("Timeout")
+                    .blurb("Value in seconds to timeout WHEP endpoint requests (0 = No timeout).")
+                    .maximum(3600)
+                    .default_value(DEFAULT_TIMEOUT)
+                    .build(),
+
+                glib::ParamSpecString::builder("proxy")
+                    .nick("Proxy")
+                    .blurb("URL of the HTTP, HTTPS or SOCKS5 proxy to send requests through, e.g.: socks5://proxy.example.com:1080.
+                        Overrides the proxy set in the environment.")
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecBoxed::builder::<gst::Structure>("extra-headers")
+                    .nick("Extra Headers")
+                    .blurb("Headers to add to every request, as the string fields of a structure,
+                        e.g.: headers,X-Tenant=acme")
+                    .mutable_ready()
+                    .build(),
+            ]
+            .into_iter()
+            .chain(TlsSettings::properties())
+            .collect()
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        match pspec.name() {
+            "whep-endpoint" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.whep_endpoint = value.get().unwrap();
+            }
+            "use-link-headers" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.use_link_headers = value.get().unwrap();
+            }
+            "auth-token" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.auth_token = value.get().unwrap();
+            }
+            "timeout" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.timeout = value.get().unwrap();
+            }
+            "proxy" => {
+                let proxy = value.get::<Option<String>>().unwrap();
+                if proxy.as_deref().map_or(true, is_valid_proxy) {
+                    self.settings.lock().unwrap().proxy = proxy;
+                } else {
+                    gst::error!(CAT, imp: self, "Invalid proxy URL {proxy:?}");
+                }
+            }
+            "extra-headers" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.extra_headers = value.get().unwrap();
+            }
+            name if TlsSettings::PROPERTIES.contains(&name) => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.tls.set_property(name, value);
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "whep-endpoint" => {
+                let settings = self.settings.lock().unwrap();
+                settings.whep_endpoint.to_value()
+            }
+            "use-link-headers" => {
+                let settings = self.settings.lock().unwrap();
+                settings.use_link_headers.to_value()
+            }
+            "auth-token" => {
+                let settings = self.settings.lock().unwrap();
+                settings.auth_token.to_value()
+            }
+            "timeout" => {
+                let settings = self.settings.lock().unwrap();
+                settings.timeout.to_value()
+            }
+            "proxy" => {
+                let settings = self.settings.lock().unwrap();
+                settings.proxy.to_value()
+            }
+            "extra-headers" => {
+                let settings = self.settings.lock().unwrap();
+                settings.extra_headers.to_value()
+            }
+            name if TlsSettings::PROPERTIES.contains(&name) => {
+                let settings = self.settings.lock().unwrap();
+                settings.tls.property(name)
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::signaller::Signallable;
+use gst::glib;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct WhepSignaller(ObjectSubclass<imp::Signaller>) @implements Signallable;
+}
+
+unsafe impl Send for WhepSignaller {}
+unsafe impl Sync for WhepSignaller {}
+
+impl Default for WhepSignaller {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
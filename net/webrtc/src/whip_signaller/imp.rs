@@ -2,7 +2,8 @@
 
 use crate::signaller::{Signallable, SignallableImpl};
 use crate::utils::{
-    build_reqwest_client, parse_redirect_location, set_ice_servers, wait, wait_async, WaitError,
+    build_reqwest_client, extra_headers, is_valid_proxy, parse_redirect_location, set_ice_servers,
+    wait, wait_async, TlsSettings, WaitError,
 };
 use crate::RUNTIME;
 use async_recursion::async_recursion;
@@ -15,6 +16,7 @@ use reqwest::header::HeaderMap;
 use reqwest::header::HeaderValue;
 use reqwest::StatusCode;
 use std::sync::Mutex;
+use std::time::Duration;
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
@@ -26,6 +28,17 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 
 const MAX_REDIRECTS: u8 = 10;
 const DEFAULT_TIMEOUT: u32 = 15;
+const DEFAULT_MAX_RETRIES: u32 = 0;
+const DEFAULT_RETRY_INITIAL_DELAY: u32 = 500;
+const DEFAULT_RETRY_MAX_DELAY: u32 = 10000;
+
+/// Failure of the POST request that may not happen again if retried
+#[derive(Debug)]
+struct Retryable {
+    reason: String,
+    /// As requested by the endpoint with a Retry-After header
+    retry_after: Option<Duration>,
+}
 
 #[derive(Debug)]
 enum State {
@@ -46,6 +59,16 @@ struct Settings {
     use_link_headers: bool,
     auth_token: Option<String>,
     timeout: u32,
+    max_retries: u32,
+    /// In milliseconds
+    retry_initial_delay: u32,
+    /// In milliseconds
+    retry_max_delay: u32,
+    /// Proxy to send the requests through
+    proxy: Option<String>,
+    tls: TlsSettings,
+    /// Headers to add to every request, one string field per header
+    extra_headers: Option<gst::Structure>,
 }
 
 impl Default for Settings {
@@ -55,6 +78,12 @@ impl Default for Settings {
             use_link_headers: false,
             auth_token: None,
             timeout: DEFAULT_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_initial_delay: DEFAULT_RETRY_INITIAL_DELAY,
+            retry_max_delay: DEFAULT_RETRY_MAX_DELAY,
+            proxy: None,
+            tls: Default::default(),
+            extra_headers: None,
         }
     }
 }
@@ -72,6 +101,53 @@ impl Signaller {
             .emit_by_name::<()>("error", &[&format!("Error: {msg}")]);
     }
 
+    /// Returns the token to authenticate our requests with, as provided
+    /// by the application or the auth-token property. When `refresh` is
+    /// set, the previous token was rejected and only the application can
+    /// provide a new one
+    fn auth_token(&self, refresh: bool) -> Option<String> {
+        let token = self
+            .obj()
+            .emit_by_name::<Option<String>>("request-auth-token", &[&refresh]);
+
+        match token {
+            Some(token) => Some(token),
+            None if refresh => None,
+            None => self.settings.lock().unwrap().auth_token.clone(),
+        }
+    }
+
+    /// The extra headers, completed with the authorization header if we
+    /// have a token
+    fn headers(&self, auth_token: Option<&str>) -> HeaderMap {
+        let mut headermap = extra_headers(self.settings.lock().unwrap().extra_headers.as_ref());
+
+        if let Some(token) = auth_token {
+            let bearer_token = "Bearer ".to_owned() + token;
+            headermap.insert(
+                reqwest::header::AUTHORIZATION,
+                HeaderValue::from_str(bearer_token.as_str())
+                    .expect("Failed to set auth token to header"),
+            );
+        }
+
+        headermap
+    }
+
+    fn http_client(&self, pol: reqwest::redirect::Policy) -> reqwest::Client {
+        let (proxy, tls) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.proxy.clone(), settings.tls.clone())
+        };
+
+        let tls_connector = tls.connector().unwrap_or_else(|err| {
+            self.raise_error(format!("Invalid TLS configuration: {err:?}"));
+            None
+        });
+
+        build_reqwest_client(pol, proxy.as_deref(), tls_connector)
+    }
+
     fn handle_future_error(&self, err: WaitError) {
         match err {
             WaitError::FutureAborted => {
@@ -81,13 +157,66 @@ impl Signaller {
         };
     }
 
-    async fn send_offer(&self, webrtcbin: &gst::Element) {
-        {
-            let mut state = self.state.lock().unwrap();
-            *state = State::Post { redirects: 0 };
-            drop(state);
+    /// Configures `webrtcbin` with the ICE servers the endpoint advertises
+    /// in response to an OPTIONS request, before it starts gathering:
+    /// the ones returned along with the answer come too late for that
+    fn request_ice_servers(&self, webrtcbin: &gst::Element) {
+        let (timeout, endpoint) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.timeout, settings.whip_endpoint.clone())
+        };
+
+        let Some(endpoint) = endpoint.and_then(|endpoint| reqwest::Url::parse(&endpoint).ok())
+        else {
+            return;
+        };
+
+        gst::debug!(CAT, imp: self, "OPTIONS request on {}", endpoint);
+
+        let headermap = self.headers(self.auth_token(false).as_deref());
+        let client = self.http_client(reqwest::redirect::Policy::default());
+        let future = async {
+            client
+                .request(reqwest::Method::OPTIONS, endpoint.clone())
+                .headers(headermap)
+                .send()
+                .await
+                .map_err(|err| {
+                    gst::error_msg!(
+                        gst::ResourceError::Failed,
+                        ["OPTIONS request failed {}: {:?}", endpoint, err]
+                    )
+                })
+        };
+
+        // Endpoints aren't required to support OPTIONS, we'll still get
+        // the ICE servers in the response to the POST request
+        match wait(&self.canceller, future, timeout) {
+            Ok(resp) if resp.status().is_success() => {
+                if let Err(err) = set_ice_servers(webrtcbin, resp.headers()) {
+                    gst::warning!(CAT, imp: self, "Failed to set ICE servers: {}", err);
+                }
+            }
+            Ok(resp) => {
+                gst::debug!(CAT, imp: self, "Response to OPTIONS : {}", resp.status());
+            }
+            Err(err) => {
+                gst::warning!(CAT, imp: self, "Could not request ICE servers: {:?}", err);
+            }
         }
+    }
 
+    /// Exponential backoff with jitter, between half and all of the delay
+    /// doubled at each `attempt`, starting from 1
+    fn retry_delay(attempt: u32, initial_delay: u32, max_delay: u32) -> Duration {
+        let delay = (initial_delay as u64)
+            .saturating_mul(1 << (attempt - 1).min(31))
+            .min(max_delay as u64);
+
+        Duration::from_millis(fastrand::u64(delay / 2..=delay))
+    }
+
+    async fn send_offer(&self, webrtcbin: &gst::Element) {
         let local_desc =
             webrtcbin.property::<Option<WebRTCSessionDescription>>("local-description");
 
@@ -106,33 +235,93 @@ impl Signaller {
             offer_sdp.sdp().as_text()
         );
 
-        let timeout;
-        {
-            let settings = self.settings.lock().unwrap();
-            timeout = settings.timeout;
-            drop(settings);
-        }
+        let settings = self.settings.lock().unwrap().clone();
+        let endpoint =
+            reqwest::Url::parse(settings.whip_endpoint.as_ref().unwrap().as_str()).unwrap();
 
-        if let Err(e) =
-            wait_async(&self.canceller, self.do_post(offer_sdp, webrtcbin), timeout).await
-        {
-            self.handle_future_error(e);
-        }
-    }
+        let mut attempt = 0;
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                *state = State::Post { redirects: 0 };
+                drop(state);
+            }
 
-    #[async_recursion]
-    async fn do_post(&self, offer: gst_webrtc::WebRTCSessionDescription, webrtcbin: &gst::Element) {
-        let auth_token;
-        let endpoint;
+            let auth_token = self.auth_token(false);
+
+            let res = wait_async(
+                &self.canceller,
+                self.do_post(
+                    offer_sdp.clone(),
+                    webrtcbin,
+                    endpoint.clone(),
+                    auth_token,
+                    false,
+                ),
+                settings.timeout,
+            )
+            .await;
 
-        {
-            let settings = self.settings.lock().unwrap();
-            endpoint =
-                reqwest::Url::parse(settings.whip_endpoint.as_ref().unwrap().as_str()).unwrap();
-            auth_token = settings.auth_token.clone();
-            drop(settings);
+            let retryable = match res {
+                Ok(Ok(())) => return,
+                Ok(Err(retryable)) => retryable,
+                // Timed out
+                Err(WaitError::FutureError(err)) => Retryable {
+                    reason: err.to_string(),
+                    retry_after: None,
+                },
+                Err(e) => {
+                    self.handle_future_error(e);
+                    return;
+                }
+            };
+
+            if attempt >= settings.max_retries {
+                self.raise_error(retryable.reason);
+                return;
+            }
+
+            attempt += 1;
+            let delay = Self::retry_delay(
+                attempt,
+                settings.retry_initial_delay,
+                settings.retry_max_delay,
+            )
+            .max(retryable.retry_after.unwrap_or_default());
+
+            gst::warning!(
+                CAT,
+                imp: self,
+                "POST failed: {}, retrying in {:?} ({}/{})",
+                retryable.reason,
+                delay,
+                attempt,
+                settings.max_retries
+            );
+
+            self.obj().emit_by_name::<()>(
+                "retrying",
+                &[&attempt, &(delay.as_millis() as u64), &retryable.reason],
+            );
+
+            if let Err(e) = wait_async(&self.canceller, tokio::time::sleep(delay), 0).await {
+                self.handle_future_error(e);
+                return;
+            }
         }
+    }
 
+    /// `auth_refreshed` is set once the application provided a new token
+    /// after the endpoint rejected the previous one
+    #[async_recursion]
+    async fn do_post(
+        &self,
+        offer: gst_webrtc::WebRTCSessionDescription,
+        webrtcbin: &gst::Element,
+        endpoint: reqwest::Url,
+        auth_token: Option<String>,
+        auth_refreshed: bool,
+    ) -> Result<(), Retryable> {
         #[allow(unused_mut)]
         let mut redirects;
 
@@ -142,7 +331,7 @@ impl Signaller {
                 State::Post { redirects } => redirects,
                 _ => {
                     self.raise_error("Trying to do POST in unexpected state".to_string());
-                    return;
+                    return Ok(());
                 }
             };
             drop(state);
@@ -151,27 +340,18 @@ impl Signaller {
         // Default policy for redirect does not share the auth token to new location
         // So disable inbuilt redirecting and do a recursive call upon 3xx response code
         let pol = reqwest::redirect::Policy::none();
-        let client = build_reqwest_client(pol);
+        let client = self.http_client(pol);
 
         let sdp = offer.sdp();
         let body = sdp.as_text().unwrap();
 
         gst::debug!(CAT, imp: self, "Using endpoint {}", endpoint.as_str());
-        let mut headermap = HeaderMap::new();
+        let mut headermap = self.headers(auth_token.as_deref());
         headermap.insert(
             reqwest::header::CONTENT_TYPE,
             HeaderValue::from_static("application/sdp"),
         );
 
-        if let Some(token) = auth_token.as_ref() {
-            let bearer_token = "Bearer ".to_owned() + token;
-            headermap.insert(
-                reqwest::header::AUTHORIZATION,
-                HeaderValue::from_str(bearer_token.as_str())
-                    .expect("Failed to set auth token to header"),
-            );
-        }
-
         let res = client
             .request(reqwest::Method::POST, endpoint.clone())
             .headers(headermap)
@@ -181,29 +361,45 @@ impl Signaller {
 
         match res {
             Ok(resp) => {
-                self.parse_endpoint_response(offer, resp, redirects, webrtcbin)
-                    .await
+                self.parse_endpoint_response(
+                    offer,
+                    resp,
+                    redirects,
+                    webrtcbin,
+                    endpoint,
+                    auth_token,
+                    auth_refreshed,
+                )
+                .await
+            }
+            Err(err) if err.is_connect() || err.is_timeout() => Err(Retryable {
+                reason: err.to_string(),
+                retry_after: None,
+            }),
+            Err(err) => {
+                self.raise_error(err.to_string());
+                Ok(())
             }
-            Err(err) => self.raise_error(err.to_string()),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn parse_endpoint_response(
         &self,
         offer: gst_webrtc::WebRTCSessionDescription,
         resp: reqwest::Response,
         redirects: u8,
         webrtcbin: &gst::Element,
-    ) {
+        endpoint: reqwest::Url,
+        auth_token: Option<String>,
+        auth_refreshed: bool,
+    ) -> Result<(), Retryable> {
         gst::debug!(CAT, imp: self, "Parsing endpoint response");
 
-        let endpoint;
         let use_link_headers;
 
         {
             let settings = self.settings.lock().unwrap();
-            endpoint =
-                reqwest::Url::parse(settings.whip_endpoint.as_ref().unwrap().as_str()).unwrap();
             use_link_headers = settings.use_link_headers;
             drop(settings);
         }
@@ -215,7 +411,7 @@ impl Signaller {
                 if use_link_headers {
                     if let Err(e) = set_ice_servers(webrtcbin, resp.headers()) {
                         self.raise_error(e.to_string());
-                        return;
+                        return Ok(());
                     };
                 }
 
@@ -232,7 +428,7 @@ impl Signaller {
                             "Location header field should be present for WHIP resource URL"
                                 .to_string(),
                         );
-                        return;
+                        return Ok(());
                     }
                 };
 
@@ -240,7 +436,7 @@ impl Signaller {
                     Ok(loc) => loc,
                     Err(e) => {
                         self.raise_error(format!("Failed to convert location to string: {e}"));
-                        return;
+                        return Ok(());
                     }
                 };
 
@@ -252,7 +448,7 @@ impl Signaller {
                     Ok(joined_url) => joined_url,
                     Err(err) => {
                         self.raise_error(format!("URL join operation failed: {err:?}"));
-                        return;
+                        return Ok(());
                     }
                 };
 
@@ -264,7 +460,7 @@ impl Signaller {
                         },
                         _ => {
                             self.raise_error("Expected to be in POST state".to_string());
-                            return;
+                            return Ok(());
                         }
                     };
                     drop(state);
@@ -310,7 +506,7 @@ impl Signaller {
                                         self.raise_error(
                                             "Unexpected redirection in RUNNING state".to_string(),
                                         );
-                                        return;
+                                        return Ok(());
                                     }
                                     State::Stopped => unreachable!(),
                                 };
@@ -324,7 +520,9 @@ impl Signaller {
                                 redirect_url.as_str()
                             );
 
-                            self.do_post(offer, webrtcbin).await
+                            return self
+                                .do_post(offer, webrtcbin, redirect_url, auth_token, auth_refreshed)
+                                .await;
                         }
                         Err(e) => self.raise_error(e.to_string()),
                     }
@@ -333,24 +531,54 @@ impl Signaller {
                 }
             }
 
+            StatusCode::UNAUTHORIZED if !auth_refreshed => {
+                gst::debug!(CAT, imp: self, "Unauthorized, requesting a new token");
+
+                match self.auth_token(true) {
+                    Some(auth_token) => {
+                        return self
+                            .do_post(offer, webrtcbin, endpoint, Some(auth_token), true)
+                            .await;
+                    }
+                    None => {
+                        self.raise_error("Unauthorized and no new token was provided".to_string())
+                    }
+                }
+            }
+
             s => {
+                let retry_after = resp
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+
                 match resp.bytes().await {
                     Ok(r) => {
                         let res = r.escape_ascii().to_string();
+                        let reason = format!("Unexpected response: {} - {}", s.as_str(), res);
 
-                        // FIXME: Check and handle 'Retry-After' header in case of server error
-                        self.raise_error(format!("Unexpected response: {} - {}", s.as_str(), res));
+                        if s.is_server_error() {
+                            return Err(Retryable {
+                                reason,
+                                retry_after,
+                            });
+                        }
+
+                        self.raise_error(reason);
                     }
                     Err(err) => self.raise_error(err.to_string()),
                 }
             }
         }
+
+        Ok(())
     }
 
     fn terminate_session(&self) {
-        let settings = self.settings.lock().unwrap();
+        let timeout = self.settings.lock().unwrap().timeout;
         let state = self.state.lock().unwrap();
-        let timeout = settings.timeout;
 
         let resource_url = match *state {
             State::Running {
@@ -364,33 +592,41 @@ impl Signaller {
 
         drop(state);
 
-        let mut headermap = HeaderMap::new();
-        if let Some(token) = &settings.auth_token {
-            let bearer_token = "Bearer ".to_owned() + token.as_str();
-            headermap.insert(
-                reqwest::header::AUTHORIZATION,
-                HeaderValue::from_str(bearer_token.as_str())
-                    .expect("Failed to set auth token to header"),
-            );
-        }
+        let mut auth_token = self.auth_token(false);
+        let mut auth_refreshed = false;
 
         gst::debug!(CAT, imp: self, "DELETE request on {}", resource_url);
-        let client = build_reqwest_client(reqwest::redirect::Policy::default());
-        let future = async {
-            client
-                .delete(resource_url.clone())
-                .headers(headermap)
-                .send()
-                .await
-                .map_err(|err| {
-                    gst::error_msg!(
-                        gst::ResourceError::Failed,
-                        ["DELETE request failed {}: {:?}", resource_url, err]
-                    )
-                })
+        let client = self.http_client(reqwest::redirect::Policy::default());
+
+        let res = loop {
+            let future = async {
+                client
+                    .delete(resource_url.clone())
+                    .headers(self.headers(auth_token.as_deref()))
+                    .send()
+                    .await
+                    .map_err(|err| {
+                        gst::error_msg!(
+                            gst::ResourceError::Failed,
+                            ["DELETE request failed {}: {:?}", resource_url, err]
+                        )
+                    })
+            };
+
+            match wait(&self.canceller, future, timeout) {
+                Ok(r) if r.status() == StatusCode::UNAUTHORIZED && !auth_refreshed => {
+                    gst::debug!(CAT, imp: self, "Unauthorized, requesting a new token");
+
+                    auth_refreshed = true;
+                    match self.auth_token(true) {
+                        Some(token) => auth_token = Some(token),
+                        None => break Ok(r),
+                    }
+                }
+                res => break res,
+            }
         };
 
-        let res = wait(&self.canceller, future, timeout);
         match res {
             Ok(r) => {
                 gst::debug!(CAT, imp: self, "Response to DELETE : {}", r.status());
@@ -420,6 +656,10 @@ impl SignallableImpl for Signaller {
             glib::closure!(|signaller: &super::WhipSignaller,
                             _consumer_identifier: &str,
                             webrtcbin: &gst::Element| {
+                if signaller.imp().settings.lock().unwrap().use_link_headers {
+                    signaller.imp().request_ice_servers(webrtcbin);
+                }
+
                 let obj_weak = signaller.downgrade();
                 webrtcbin.connect_notify(Some("ice-gathering-state"), move |webrtcbin, _pspec| {
                     let obj = match obj_weak.upgrade() {
@@ -456,6 +696,7 @@ impl SignallableImpl for Signaller {
                 &"unique",
                 &"unique",
                 &None::<gst_webrtc::WebRTCSessionDescription>,
+                &None::<gst::Structure>,
             ],
         );
     }
@@ -499,6 +740,51 @@ impl ObjectSubclass for Signaller {
 }
 
 impl ObjectImpl for Signaller {
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+            vec![
+                /**
+                 * GstWHIPWebRTCSinkSignaller::request-auth-token:
+                 * @refresh: %TRUE if the endpoint rejected the previous token
+                 *
+                 * Emitted before each request to the WHIP endpoint, for applications
+                 * to provide short-lived tokens, for instance from an OAuth2 refresh
+                 * token flow, rather than a static #GstWHIPWebRTCSinkSignaller:auth-token.
+                 *
+                 * When the endpoint answers with 401 Unauthorized, the signal is
+                 * emitted again with @refresh set, and the request is retried once
+                 * with the new token.
+                 *
+                 * Returns: (nullable): the token to send as 'Bearer <token>', or %NULL
+                 * to use #GstWHIPWebRTCSinkSignaller:auth-token
+                 */
+                glib::subclass::Signal::builder("request-auth-token")
+                    .param_types([bool::static_type()])
+                    .return_type::<Option<String>>()
+                    .build(),
+                /**
+                 * GstWHIPWebRTCSinkSignaller::retrying:
+                 * @attempt: the number of the upcoming retry, starting from 1
+                 * @delay: milliseconds until the offer is posted again
+                 * @reason: why the previous attempt failed
+                 *
+                 * Emitted when posting the offer failed in a way that may not happen
+                 * again, such as a refused connection, a timeout or a server error,
+                 * and #GstWHIPWebRTCSinkSignaller:max-retries isn't reached yet.
+                 */
+                glib::subclass::Signal::builder("retrying")
+                    .param_types([
+                        u32::static_type(),
+                        u64::static_type(),
+                        String::static_type(),
+                    ])
+                    .build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
     fn properties() -> &'static [glib::ParamSpec] {
         static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
             vec![glib::ParamSpecString::builder("whip-endpoint")
@@ -510,7 +796,7 @@ impl ObjectImpl for Signaller {
 
                 glib::ParamSpecBoolean::builder("use-link-headers")
                     .nick("Use Link Headers")
-                    .blurb("Use link headers to configure ice-servers from the WHIP server response to the POST request.
+                    .blurb("Use link headers to configure ice-servers from the WHIP server response to the OPTIONS and POST requests.
                         If set to TRUE and the WHIP server returns valid ice-servers,
                         this property overrides the ice-servers values set using the stun-server and turn-server properties.")
                     .mutable_ready()
@@ -528,7 +814,45 @@ impl ObjectImpl for Signaller {
                     .maximum(3600)
                     .default_value(DEFAULT_TIMEOUT)
                     .build(),
+
+                glib::ParamSpecUInt::builder("max-retries")
+                    .nick("Maximum Retries")
+                    .blurb("How many times to post the offer again after a refused connection, a timeout or a server error (0 = No retry).")
+                    .default_value(DEFAULT_MAX_RETRIES)
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecUInt::builder("retry-initial-delay")
+                    .nick("Retry Initial Delay")
+                    .blurb("Delay in milliseconds before the first retry, doubled for each of the next ones, with jitter.")
+                    .default_value(DEFAULT_RETRY_INITIAL_DELAY)
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecUInt::builder("retry-max-delay")
+                    .nick("Retry Maximum Delay")
+                    .blurb("Maximum delay in milliseconds between retries, unless the server asks for more with a Retry-After header.")
+                    .default_value(DEFAULT_RETRY_MAX_DELAY)
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecString::builder("proxy")
+                    .nick("Proxy")
+                    .blurb("URL of the HTTP, HTTPS or SOCKS5 proxy to send requests through, e.g.: socks5://proxy.example.com:1080.
+                        Overrides the proxy set in the environment.")
+                    .mutable_ready()
+                    .build(),
+
+                glib::ParamSpecBoxed::builder::<gst::Structure>("extra-headers")
+                    .nick("Extra Headers")
+                    .blurb("Headers to add to every request, as the string fields of a structure,
+                        e.g.: headers,X-Tenant=acme")
+                    .mutable_ready()
+                    .build(),
             ]
+            .into_iter()
+            .chain(TlsSettings::properties())
+            .collect()
         });
 
         PROPERTIES.as_ref()
@@ -552,6 +876,34 @@ impl ObjectImpl for Signaller {
                 let mut settings = self.settings.lock().unwrap();
                 settings.timeout = value.get().unwrap();
             }
+            "max-retries" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.max_retries = value.get().unwrap();
+            }
+            "retry-initial-delay" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.retry_initial_delay = value.get().unwrap();
+            }
+            "retry-max-delay" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.retry_max_delay = value.get().unwrap();
+            }
+            "proxy" => {
+                let proxy = value.get::<Option<String>>().unwrap();
+                if proxy.as_deref().map_or(true, is_valid_proxy) {
+                    self.settings.lock().unwrap().proxy = proxy;
+                } else {
+                    gst::error!(CAT, imp: self, "Invalid proxy URL {proxy:?}");
+                }
+            }
+            "extra-headers" => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.extra_headers = value.get().unwrap();
+            }
+            name if TlsSettings::PROPERTIES.contains(&name) => {
+                let mut settings = self.settings.lock().unwrap();
+                settings.tls.set_property(name, value);
+            }
             _ => unimplemented!(),
         }
     }
@@ -574,6 +926,30 @@ impl ObjectImpl for Signaller {
                 let settings = self.settings.lock().unwrap();
                 settings.timeout.to_value()
             }
+            "max-retries" => {
+                let settings = self.settings.lock().unwrap();
+                settings.max_retries.to_value()
+            }
+            "retry-initial-delay" => {
+                let settings = self.settings.lock().unwrap();
+                settings.retry_initial_delay.to_value()
+            }
+            "retry-max-delay" => {
+                let settings = self.settings.lock().unwrap();
+                settings.retry_max_delay.to_value()
+            }
+            "proxy" => {
+                let settings = self.settings.lock().unwrap();
+                settings.proxy.to_value()
+            }
+            "extra-headers" => {
+                let settings = self.settings.lock().unwrap();
+                settings.extra_headers.to_value()
+            }
+            name if TlsSettings::PROPERTIES.contains(&name) => {
+                let settings = self.settings.lock().unwrap();
+                settings.tls.property(name)
+            }
             _ => unimplemented!(),
         }
     }
@@ -0,0 +1,319 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// Not all the test crates use all the helpers
+#![allow(dead_code)]
+
+use gst::glib;
+use gst::prelude::*;
+use gstrswebrtc::signaller::{Signallable, SignallableExt};
+
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        // clear this environment because it affects the default settings
+        std::env::remove_var("http_proxy");
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtc signaller tests");
+    });
+}
+
+/// Messages sent from our test harnesses
+#[derive(Debug)]
+pub enum Message {
+    SessionRequested(String, Option<gst_webrtc::WebRTCSessionDescription>),
+    SessionDescription(gst_webrtc::WebRTCSessionDescription),
+    SessionEnded(String),
+    Error(String),
+}
+
+/// Forwards the signals the element would handle to the returned receiver
+pub fn connect_messages(signaller: &Signallable) -> mpsc::Receiver<Message> {
+    let (sender, receiver) = mpsc::channel();
+
+    let sender_clone = Mutex::new(sender.clone());
+    signaller.connect("session-requested", false, move |args| {
+        let session_id = args[1].get::<String>().unwrap();
+        let offer = args[3]
+            .get::<Option<gst_webrtc::WebRTCSessionDescription>>()
+            .unwrap();
+        let _ = sender_clone
+            .lock()
+            .unwrap()
+            .send(Message::SessionRequested(session_id, offer));
+        None
+    });
+
+    let sender_clone = Mutex::new(sender.clone());
+    signaller.connect("session-description", false, move |args| {
+        let desc = args[2]
+            .get::<gst_webrtc::WebRTCSessionDescription>()
+            .unwrap();
+        let _ = sender_clone
+            .lock()
+            .unwrap()
+            .send(Message::SessionDescription(desc));
+        None
+    });
+
+    let sender_clone = Mutex::new(sender.clone());
+    signaller.connect("session-ended", false, move |args| {
+        let session_id = args[1].get::<String>().unwrap();
+        let _ = sender_clone
+            .lock()
+            .unwrap()
+            .send(Message::SessionEnded(session_id));
+        Some(false.to_value())
+    });
+
+    let sender_clone = Mutex::new(sender);
+    signaller.connect("error", false, move |args| {
+        let error = args[1].get::<String>().unwrap();
+        let _ = sender_clone.lock().unwrap().send(Message::Error(error));
+        None
+    });
+
+    receiver
+}
+
+/// Command line of an external signaller requesting a single session,
+/// `session-1` from `consumer-1`, as soon as it is started
+pub const SESSION_REQUESTING_SIGNALLER: &str = r#"sh -c 'read start; echo "{\"type\":\"sessionRequested\",\"sessionId\":\"session-1\",\"peerId\":\"consumer-1\"}"; cat > /dev/null'"#;
+
+/// Creates a webrtcsink whose session is requested by a local external
+/// signaller, without any STUN server: nothing leaves the host
+pub fn local_webrtcsink() -> gst::Element {
+    init();
+
+    let sink = gst::ElementFactory::make("webrtcsink")
+        .property_from_str("signaller-type", "ipc")
+        .property("stun-server", None::<String>)
+        .build()
+        .unwrap();
+    sink.property::<Signallable>("signaller")
+        .set_property("command", SESSION_REQUESTING_SIGNALLER);
+
+    sink
+}
+
+/// Forwards the descriptions the element sends through `signaller` to the
+/// returned receiver, instead of the signaller sending them to the peer
+pub fn connect_sent_descriptions(
+    signaller: &Signallable,
+) -> mpsc::Receiver<gst_webrtc::WebRTCSessionDescription> {
+    let (sender, receiver) = mpsc::channel();
+
+    let sender = Mutex::new(sender);
+    signaller.connect("send-session-description", false, move |args| {
+        let desc = args[2]
+            .get::<gst_webrtc::WebRTCSessionDescription>()
+            .unwrap();
+        let _ = sender.lock().unwrap().send(desc);
+        Some(true.to_value())
+    });
+
+    receiver
+}
+
+/// A request received by the mock HTTP endpoint
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: hyper::Method,
+    pub path: String,
+    pub headers: hyper::HeaderMap,
+    pub body: String,
+}
+
+/// Our custom test harness around a WHIP or WHEP client signaller and a
+/// mock endpoint, the test plays the part of the element driving the
+/// signaller
+pub struct Harness {
+    pub signaller: Signallable,
+    pub webrtcbin: gst::Element,
+    requests: Arc<Mutex<Vec<Request>>>,
+    receiver: mpsc::Receiver<Message>,
+    pipeline: gst::Pipeline,
+    direction: gst_webrtc::WebRTCRTPTransceiverDirection,
+    _rt: tokio::runtime::Runtime,
+}
+
+impl Harness {
+    /// Creates the signaller of `factory_name` and a mock endpoint at
+    /// `endpoint_path` answering its requests with `http_func`
+    ///
+    /// The offers are created for a single video stream in `direction`.
+    pub fn new<F>(
+        factory_name: &str,
+        endpoint_property: &str,
+        endpoint_path: &str,
+        direction: gst_webrtc::WebRTCRTPTransceiverDirection,
+        http_func: F,
+    ) -> Harness
+    where
+        F: Fn(&Request) -> hyper::Response<hyper::Body> + Send + Sync + 'static,
+    {
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::Server;
+
+        init();
+
+        // Get hold of a signaller that isn't driven by an element
+        let element = gst::ElementFactory::make(factory_name).build().unwrap();
+        let signaller =
+            glib::Object::with_type(element.property::<glib::Object>("signaller").type_())
+                .downcast::<Signallable>()
+                .unwrap();
+
+        let receiver = connect_messages(&signaller);
+
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // Create an HTTP sever that listens on localhost on some random, free port
+        let addr = ([127, 0, 0, 1], 0).into();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let http_func = Arc::new(http_func);
+        let make_service = make_service_fn({
+            let requests = requests.clone();
+            move |_ctx| {
+                let requests = requests.clone();
+                let http_func = http_func.clone();
+                async move {
+                    Ok::<_, hyper::Error>(service_fn(move |req: hyper::Request<hyper::Body>| {
+                        let requests = requests.clone();
+                        let http_func = http_func.clone();
+                        async move {
+                            let (parts, body) = req.into_parts();
+                            let body = hyper::body::to_bytes(body).await?;
+
+                            let request = Request {
+                                method: parts.method,
+                                path: parts.uri.path().to_string(),
+                                headers: parts.headers,
+                                body: String::from_utf8_lossy(&body).to_string(),
+                            };
+
+                            let response = http_func(&request);
+                            requests.lock().unwrap().push(request);
+
+                            Ok::<_, hyper::Error>(response)
+                        }
+                    }))
+                }
+            }
+        });
+
+        let (local_addr_sender, local_addr_receiver) = futures::channel::oneshot::channel();
+
+        rt.spawn(async move {
+            let server = Server::bind(&addr).serve(make_service);
+            local_addr_sender.send(server.local_addr()).unwrap();
+
+            server.await.unwrap();
+        });
+
+        let local_addr = futures::executor::block_on(local_addr_receiver).unwrap();
+        signaller.set_property(
+            endpoint_property,
+            format!("http://{local_addr}{endpoint_path}"),
+        );
+
+        let pipeline = gst::Pipeline::new();
+        let webrtcbin = gst::ElementFactory::make("webrtcbin")
+            .property_from_str("bundle-policy", "max-bundle")
+            .build()
+            .unwrap();
+        pipeline.add(&webrtcbin).unwrap();
+
+        Harness {
+            signaller,
+            webrtcbin,
+            requests,
+            receiver,
+            pipeline,
+            direction,
+            _rt: rt,
+        }
+    }
+
+    /// Starts the signaller and, once it requested the session, hands it a
+    /// local offer as webrtcsink and webrtcsrc do
+    pub fn send_offer(&self) {
+        self.signaller.start();
+
+        match self.wait_for_message() {
+            Message::SessionRequested(_, None) => (),
+            msg => panic!("Expected a session request without offer, got {msg:?}"),
+        }
+
+        self.signaller
+            .emit_by_name::<()>("consumer-added", &[&"unique", &self.webrtcbin]);
+
+        self.pipeline.set_state(gst::State::Playing).unwrap();
+
+        self.webrtcbin
+            .emit_by_name::<gst_webrtc::WebRTCRTPTransceiver>(
+                "add-transceiver",
+                &[
+                    &self.direction,
+                    &gst::Caps::builder("application/x-rtp")
+                        .field("media", "video")
+                        .field("encoding-name", "VP8")
+                        .field("payload", 96i32)
+                        .field("clock-rate", 90000i32)
+                        .build(),
+                ],
+            );
+
+        let promise = gst::Promise::new();
+        self.webrtcbin
+            .emit_by_name::<()>("create-offer", &[&None::<gst::Structure>, &promise]);
+        promise.wait();
+
+        let offer = promise
+            .get_reply()
+            .unwrap()
+            .get::<gst_webrtc::WebRTCSessionDescription>("offer")
+            .unwrap();
+
+        self.webrtcbin
+            .emit_by_name::<()>("set-local-description", &[&offer, &None::<gst::Promise>]);
+    }
+
+    pub fn wait_for_message(&self) -> Message {
+        self.receiver.recv_timeout(TIMEOUT).unwrap()
+    }
+
+    pub fn wait_for_answer(&self) -> gst_webrtc::WebRTCSessionDescription {
+        match self.wait_for_message() {
+            Message::SessionDescription(desc) => desc,
+            msg => panic!("Expected an answer, got {msg:?}"),
+        }
+    }
+
+    pub fn wait_for_error(&self) -> String {
+        match self.wait_for_message() {
+            Message::Error(err) => err,
+            msg => panic!("Expected an error, got {msg:?}"),
+        }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: MPL-2.0
+
+mod common;
+
+use common::{connect_messages, init, Message, TIMEOUT};
+use gst::glib;
+use gst::prelude::*;
+use gstrswebrtc::signaller::{Signallable, SignallableExt};
+
+use std::sync::mpsc;
+
+const OFFER: &str = "v=0\r\n\
+    o=- 0 0 IN IP4 127.0.0.1\r\n\
+    s=-\r\n\
+    t=0 0\r\n\
+    m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+    c=IN IP4 0.0.0.0\r\n\
+    a=rtpmap:96 VP8/90000\r\n\
+    a=recvonly\r\n";
+
+/// Our custom test harness around a started WHEP server signaller, the
+/// test plays the part of webrtcsink
+struct Harness {
+    signaller: Signallable,
+    receiver: mpsc::Receiver<Message>,
+    base_url: String,
+    client: reqwest::Client,
+    rt: tokio::runtime::Runtime,
+}
+
+impl Harness {
+    fn new(port: u16, properties: &[(&str, &dyn ToValue)]) -> Harness {
+        init();
+
+        let sink = gst::ElementFactory::make("whepserversink").build().unwrap();
+        let signaller = glib::Object::with_type(sink.property::<glib::Object>("signaller").type_())
+            .downcast::<Signallable>()
+            .unwrap();
+
+        let base_url = format!("http://127.0.0.1:{port}");
+        signaller.set_property("host-addr", &base_url);
+        signaller.set_property("answer-timeout", 1u32);
+        for (name, value) in properties {
+            signaller.set_property_from_value(name, &value.to_value());
+        }
+
+        let receiver = connect_messages(&signaller);
+
+        signaller.start();
+
+        Harness {
+            signaller,
+            receiver,
+            base_url,
+            client: reqwest::Client::new(),
+            rt: tokio::runtime::Runtime::new().unwrap(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str, body: &str) -> reqwest::Response {
+        let request = self
+            .client
+            .request(method, format!("{}{path}", self.base_url))
+            .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+            .body(body.to_string());
+
+        self.rt.block_on(request.send()).unwrap()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.signaller.stop();
+    }
+}
+
+#[test]
+fn test_cors_preflight() {
+    let harness = Harness::new(18190, &[]);
+
+    let response = harness.request(reqwest::Method::OPTIONS, "/endpoint", "");
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers()["Access-Control-Expose-Headers"],
+        "Location"
+    );
+}
+
+#[test]
+fn test_invalid_offer_is_rejected() {
+    let harness = Harness::new(18191, &[]);
+
+    let response = harness.request(reqwest::Method::POST, "/endpoint", "not an offer");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert!(harness.receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_unanswered_offer_ends_session() {
+    let harness = Harness::new(18192, &[]);
+
+    let response = harness.request(reqwest::Method::POST, "/endpoint", OFFER);
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+
+    let Message::SessionRequested(session_id, offer) =
+        harness.receiver.recv_timeout(TIMEOUT).unwrap()
+    else {
+        panic!("Expected a session request");
+    };
+    assert_eq!(
+        offer.unwrap().sdp().as_text().unwrap(),
+        gst_sdp::SDPMessage::parse_buffer(OFFER.as_bytes())
+            .unwrap()
+            .as_text()
+            .unwrap()
+    );
+
+    let Message::SessionEnded(ended_session_id) = harness.receiver.recv_timeout(TIMEOUT).unwrap()
+    else {
+        panic!("Expected the session to end");
+    };
+    assert_eq!(ended_session_id, session_id);
+}
+
+#[test]
+fn test_unknown_session_delete() {
+    let harness = Harness::new(18193, &[]);
+
+    let response = harness.request(reqwest::Method::DELETE, "/endpoint/unknown", "");
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let response = harness.request(reqwest::Method::DELETE, "/elsewhere", "");
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn test_max_sessions() {
+    let harness = Harness::new(18194, &[("max-sessions", &1u32), ("answer-timeout", &5u32)]);
+
+    // The first session is pending until it times out
+    let client = harness.client.clone();
+    let url = format!("{}/endpoint", harness.base_url);
+    let pending = harness.rt.spawn(async move {
+        client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+            .body(OFFER)
+            .send()
+            .await
+            .unwrap()
+            .status()
+    });
+
+    let Message::SessionRequested(..) = harness.receiver.recv_timeout(TIMEOUT).unwrap() else {
+        panic!("Expected a session request");
+    };
+
+    let response = harness.request(reqwest::Method::POST, "/endpoint", OFFER);
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+    assert_eq!(
+        harness.rt.block_on(pending).unwrap(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[test]
+fn test_token_validation() {
+    let harness = Harness::new(18195, &[]);
+    harness.signaller.connect_closure(
+        "validate-token",
+        false,
+        glib::closure!(|_signaller: &glib::Object, token: Option<&str>| {
+            token == Some("secret")
+        }),
+    );
+
+    let response = harness.request(reqwest::Method::POST, "/endpoint", OFFER);
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["WWW-Authenticate"], "Bearer");
+
+    let request = harness
+        .client
+        .post(format!("{}/endpoint", harness.base_url))
+        .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+        .bearer_auth("wrong")
+        .body(OFFER);
+    let response = harness.rt.block_on(request.send()).unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert!(harness.receiver.try_recv().is_err());
+
+    let request = harness
+        .client
+        .post(format!("{}/endpoint", harness.base_url))
+        .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+        .bearer_auth("secret")
+        .body(OFFER);
+    let response = harness.rt.block_on(request.send()).unwrap();
+    // Nobody answers in this test
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+
+    let Message::SessionRequested(..) = harness.receiver.recv_timeout(TIMEOUT).unwrap() else {
+        panic!("Expected a session request");
+    };
+}
+
+#[test]
+fn test_accepted_tokens() {
+    let harness = Harness::new(
+        18196,
+        &[("accepted-tokens", &gst::Array::new(["secret", "other"]))],
+    );
+
+    let response = harness.request(reqwest::Method::POST, "/endpoint", OFFER);
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let request = harness
+        .client
+        .post(format!("{}/endpoint", harness.base_url))
+        .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+        .bearer_auth("wrong")
+        .body(OFFER);
+    let response = harness.rt.block_on(request.send()).unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert!(harness.receiver.try_recv().is_err());
+
+    let request = harness
+        .client
+        .post(format!("{}/endpoint", harness.base_url))
+        .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+        .bearer_auth("other")
+        .body(OFFER);
+    let response = harness.rt.block_on(request.send()).unwrap();
+    // Nobody answers in this test
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+
+    let Message::SessionRequested(..) = harness.receiver.recv_timeout(TIMEOUT).unwrap() else {
+        panic!("Expected a session request");
+    };
+}
+
+#[test]
+fn test_concurrent_clients() {
+    let harness = Harness::new(18197, &[("answer-timeout", &2u32)]);
+
+    let (sender, receiver) = mpsc::channel();
+    let sender = std::sync::Mutex::new(sender);
+    harness.signaller.connect_closure(
+        "client-connected",
+        false,
+        glib::closure!(
+            move |_signaller: &glib::Object, session_id: &str, resource_url: &str| {
+                let _ = sender
+                    .lock()
+                    .unwrap()
+                    .send((session_id.to_string(), resource_url.to_string()));
+            }
+        ),
+    );
+
+    // Both sessions are pending at once until they time out
+    let pending = (0..2)
+        .map(|_| {
+            let client = harness.client.clone();
+            let url = format!("{}/endpoint", harness.base_url);
+            harness.rt.spawn(async move {
+                client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+                    .body(OFFER)
+                    .send()
+                    .await
+                    .unwrap()
+                    .status()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut clients = (0..2)
+        .map(|_| receiver.recv_timeout(TIMEOUT).unwrap())
+        .collect::<Vec<_>>();
+    for (session_id, resource_url) in &clients {
+        assert_eq!(resource_url, &format!("/endpoint/{session_id}"));
+    }
+    clients.dedup_by(|a, b| a.0 == b.0);
+    assert_eq!(clients.len(), 2);
+
+    for _ in 0..2 {
+        let Message::SessionRequested(session_id, _) =
+            harness.receiver.recv_timeout(TIMEOUT).unwrap()
+        else {
+            panic!("Expected a session request");
+        };
+        assert!(clients.iter().any(|(id, _)| *id == session_id));
+    }
+
+    for pending in pending {
+        assert_eq!(
+            harness.rt.block_on(pending).unwrap(),
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
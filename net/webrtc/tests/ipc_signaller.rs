@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+use gstrswebrtc::signaller::{
+    Signallable, SignallableExt, WebRTCSignallerRole, WebRTCSignallerState,
+};
+
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("ipc signaller tests");
+    });
+}
+
+/// A producer signaller spawning `command`, not driven by any element
+fn producer_signaller(command: &str) -> Signallable {
+    init();
+
+    let src = gst::ElementFactory::make("ipcwebrtcsrc").build().unwrap();
+    let signaller = glib::Object::with_type(src.property::<glib::Object>("signaller").type_())
+        .downcast::<Signallable>()
+        .unwrap();
+    signaller.set_property("role", WebRTCSignallerRole::Producer);
+    signaller.set_property("command", command);
+
+    signaller
+}
+
+/// Waits for the signaller to reach `expected`
+fn wait_for_state(signaller: &Signallable, expected: WebRTCSignallerState) {
+    let deadline = std::time::Instant::now() + TIMEOUT;
+
+    while signaller
+        .connection_stats()
+        .get::<WebRTCSignallerState>("state")
+        .unwrap()
+        != expected
+    {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "Timed out waiting for {expected:?}"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_incoming_messages() {
+    let signaller = producer_signaller(
+        r#"sh -c 'read start; echo "{\"type\":\"sessionRequested\",\"sessionId\":\"session-1\",\"peerId\":\"consumer-1\"}"; cat > /dev/null'"#,
+    );
+
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    signaller.connect_closure(
+        "session-requested",
+        false,
+        glib::closure!(|_signaller: &glib::Object,
+                        session_id: &str,
+                        peer_id: &str,
+                        offer: Option<&gst_webrtc::WebRTCSessionDescription>,
+                        _capabilities: Option<&gst::Structure>| {
+            let _ = sender.lock().unwrap().send((
+                session_id.to_string(),
+                peer_id.to_string(),
+                offer.is_some(),
+            ));
+        }),
+    );
+
+    signaller.start();
+
+    assert_eq!(
+        receiver.recv_timeout(TIMEOUT).unwrap(),
+        ("session-1".to_string(), "consumer-1".to_string(), false)
+    );
+
+    signaller.stop();
+    wait_for_state(&signaller, WebRTCSignallerState::Disconnected);
+}
+
+#[test]
+fn test_outgoing_messages() {
+    let path = std::env::temp_dir().join(format!("ipc-signaller-{}.jsonl", std::process::id()));
+    let signaller = producer_signaller(&format!("sh -c 'cat > {}'", path.display()));
+
+    signaller.start();
+    wait_for_state(&signaller, WebRTCSignallerState::Connected);
+
+    let sdp =
+        gst_sdp::SDPMessage::parse_buffer(b"v=0\r\no=- 0 0 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n")
+            .unwrap();
+    signaller.send_sdp(
+        "session-1",
+        &gst_webrtc::WebRTCSessionDescription::new(gst_webrtc::WebRTCSDPType::Offer, sdp),
+    );
+    signaller.add_ice(
+        "session-1",
+        "candidate:1 1 UDP 1 127.0.0.1 9 typ host",
+        0,
+        None,
+    );
+    signaller.end_session("session-1");
+    signaller.stop();
+
+    let messages = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let _ = std::fs::remove_file(&path);
+
+    let types = messages
+        .iter()
+        .map(|msg| msg["type"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(types, ["start", "sdp", "ice", "endSession", "stop"]);
+
+    assert_eq!(messages[0]["role"], "producer");
+    assert_eq!(messages[1]["sessionId"], "session-1");
+    assert_eq!(messages[1]["sdpType"], "offer");
+    assert_eq!(messages[2]["sdpMLineIndex"], 0);
+    assert_eq!(
+        messages[2]["candidate"],
+        "candidate:1 1 UDP 1 127.0.0.1 9 typ host"
+    );
+}
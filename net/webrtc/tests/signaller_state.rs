@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+use gstrswebrtc::signaller::{
+    Signallable, SignallableExt, Signaller, WebRTCSignallerRole, WebRTCSignallerState,
+};
+
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("signaller state tests");
+    });
+}
+
+/// Forwards the states the signaller goes through
+fn watch_states(signaller: &Signallable) -> mpsc::Receiver<(WebRTCSignallerState, Option<String>)> {
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+
+    signaller.connect("state-changed", false, move |args| {
+        let state = args[1].get::<WebRTCSignallerState>().unwrap();
+        let message = args[2].get::<Option<String>>().unwrap();
+        let _ = sender.lock().unwrap().send((state, message));
+
+        None
+    });
+
+    receiver
+}
+
+#[test]
+fn test_connection_failure() {
+    init();
+
+    let signaller: Signallable = Signaller::new(WebRTCSignallerRole::Producer).upcast();
+    // Nothing listens on the discard port
+    signaller.set_property("uri", "ws://127.0.0.1:9");
+    let states = watch_states(&signaller);
+
+    signaller.start();
+
+    let (state, _) = states.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(state, WebRTCSignallerState::Connecting);
+    let (state, message) = states.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(state, WebRTCSignallerState::Error);
+    assert!(message.is_some());
+
+    let stats = signaller.connection_stats();
+    assert_eq!(
+        stats.get::<WebRTCSignallerState>("state").unwrap(),
+        WebRTCSignallerState::Error
+    );
+    assert_eq!(stats.get::<u32>("connections").unwrap(), 0);
+    assert_eq!(stats.get::<u32>("errors").unwrap(), 1);
+    assert_eq!(stats.get::<Option<String>>("last-error").unwrap(), message);
+
+    signaller.stop();
+
+    let (state, _) = states.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(state, WebRTCSignallerState::Disconnected);
+    assert_eq!(
+        signaller
+            .connection_stats()
+            .get::<WebRTCSignallerState>("state")
+            .unwrap(),
+        WebRTCSignallerState::Disconnected
+    );
+}
+
+#[test]
+fn test_state_changes_are_counted() {
+    init();
+
+    let signaller: Signallable = Signaller::new(WebRTCSignallerRole::Producer).upcast();
+
+    // As done by signallers implemented outside of the plugin
+    for state in [
+        WebRTCSignallerState::Connecting,
+        WebRTCSignallerState::Connected,
+        WebRTCSignallerState::Reconnecting,
+        WebRTCSignallerState::Connected,
+        WebRTCSignallerState::Reconnecting,
+    ] {
+        signaller.emit_by_name::<()>("state-changed", &[&state, &None::<String>]);
+    }
+
+    let stats = signaller.connection_stats();
+    assert_eq!(
+        stats.get::<WebRTCSignallerState>("state").unwrap(),
+        WebRTCSignallerState::Reconnecting
+    );
+    assert_eq!(stats.get::<u32>("connections").unwrap(), 2);
+    assert_eq!(stats.get::<u32>("reconnections").unwrap(), 2);
+    assert_eq!(stats.get::<u32>("errors").unwrap(), 0);
+    assert_eq!(stats.get::<Option<String>>("last-error").unwrap(), None);
+}
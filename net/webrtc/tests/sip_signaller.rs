@@ -0,0 +1,412 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+use gstrswebrtc::signaller::{Signallable, SignallableExt};
+
+use std::net::UdpSocket;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const ANSWER: &str = "v=0\r\n\
+    o=- 0 0 IN IP4 127.0.0.1\r\n\
+    s=-\r\n\
+    t=0 0\r\n\
+    m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+    c=IN IP4 0.0.0.0\r\n\
+    a=rtpmap:96 VP8/90000\r\n\
+    a=sendonly\r\n";
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("sip signaller tests");
+    });
+}
+
+/// A request received by the mock SIP server
+#[derive(Debug, Clone)]
+struct Request {
+    method: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    fn parse(data: &str) -> Request {
+        let (head, body) = data.split_once("\r\n\r\n").unwrap();
+        let mut lines = head.split("\r\n");
+        let method = lines.next().unwrap().split(' ').next().unwrap().to_string();
+        let headers = lines
+            .map(|line| {
+                let (name, value) = line.split_once(':').unwrap();
+                (name.trim().to_string(), value.trim().to_string())
+            })
+            .collect();
+
+        Request {
+            method,
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Builds a response with `status_line`, such as `200 OK`
+    fn response(&self, status_line: &str, extra_headers: &[(&str, &str)], body: &str) -> String {
+        let mut response = format!("SIP/2.0 {status_line}\r\n");
+        for name in ["Via", "From", "Call-ID", "CSeq"] {
+            response.push_str(&format!("{name}: {}\r\n", self.header(name).unwrap()));
+        }
+        response.push_str(&format!(
+            "To: {};tag=server\r\n",
+            self.header("To").unwrap()
+        ));
+        for (name, value) in extra_headers {
+            response.push_str(&format!("{name}: {value}\r\n"));
+        }
+        response.push_str(&format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+
+        response
+    }
+}
+
+/// Messages sent from our test harness
+#[derive(Debug)]
+enum Message {
+    SessionRequested(Option<gst_webrtc::WebRTCSessionDescription>),
+    SessionDescription(gst_webrtc::WebRTCSessionDescription),
+    Error(String),
+}
+
+/// Our custom test harness around the SIP signaller and a mock SIP server
+/// listening on UDP
+struct Harness {
+    signaller: Signallable,
+    requests: Arc<Mutex<Vec<Request>>>,
+    receiver: mpsc::Receiver<Message>,
+    pipeline: gst::Pipeline,
+    webrtcbin: gst::Element,
+}
+
+impl Harness {
+    /// Creates a new SIP signaller and a mock server answering its
+    /// requests with `sip_func`, which returns no response for ACKs
+    fn new<F>(sip_func: F) -> Harness
+    where
+        F: Fn(&Request, usize) -> Option<String> + Send + 'static,
+    {
+        init();
+
+        // Get hold of a signaller that isn't driven by a source, the test
+        // plays the part of webrtcsrc
+        let src = gst::ElementFactory::make("sipwebrtcsrc").build().unwrap();
+        let signaller = glib::Object::with_type(src.property::<glib::Object>("signaller").type_())
+            .downcast::<Signallable>()
+            .unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+
+        let sender_clone = Mutex::new(sender.clone());
+        signaller.connect("session-requested", false, move |args| {
+            let offer = args[3]
+                .get::<Option<gst_webrtc::WebRTCSessionDescription>>()
+                .unwrap();
+            let _ = sender_clone
+                .lock()
+                .unwrap()
+                .send(Message::SessionRequested(offer));
+            None
+        });
+
+        let sender_clone = Mutex::new(sender.clone());
+        signaller.connect("session-description", false, move |args| {
+            let desc = args[2]
+                .get::<gst_webrtc::WebRTCSessionDescription>()
+                .unwrap();
+            let _ = sender_clone
+                .lock()
+                .unwrap()
+                .send(Message::SessionDescription(desc));
+            None
+        });
+
+        let sender_clone = Mutex::new(sender);
+        signaller.connect("error", false, move |args| {
+            let error = args[1].get::<String>().unwrap();
+            let _ = sender_clone.lock().unwrap().send(Message::Error(error));
+            None
+        });
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let local_addr = socket.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let requests_clone = requests.clone();
+        std::thread::spawn(move || {
+            let mut buf = vec![0u8; 65535];
+            loop {
+                let Ok((len, peer)) = socket.recv_from(&mut buf) else {
+                    break;
+                };
+
+                let request = Request::parse(std::str::from_utf8(&buf[..len]).unwrap());
+                let response = {
+                    let mut requests = requests_clone.lock().unwrap();
+                    let response = sip_func(&request, requests.len());
+                    requests.push(request);
+                    response
+                };
+
+                if let Some(response) = response {
+                    socket.send_to(response.as_bytes(), peer).unwrap();
+                }
+            }
+        });
+
+        signaller.set_property("target-uri", format!("sip:1000@{local_addr}"));
+
+        let pipeline = gst::Pipeline::new();
+        let webrtcbin = gst::ElementFactory::make("webrtcbin")
+            .property_from_str("bundle-policy", "max-bundle")
+            .build()
+            .unwrap();
+        pipeline.add(&webrtcbin).unwrap();
+
+        Harness {
+            signaller,
+            requests,
+            receiver,
+            pipeline,
+            webrtcbin,
+        }
+    }
+
+    /// Starts the signaller and, once it requested the session, creates
+    /// a local offer to receive video as webrtcsrc does
+    fn send_offer(&self) {
+        self.signaller.start();
+
+        match self.wait_for_message() {
+            Message::SessionRequested(None) => (),
+            msg => panic!("Expected a session request without offer, got {msg:?}"),
+        }
+
+        self.signaller
+            .emit_by_name::<()>("consumer-added", &[&"unique", &self.webrtcbin]);
+
+        self.pipeline.set_state(gst::State::Playing).unwrap();
+
+        self.webrtcbin
+            .emit_by_name::<gst_webrtc::WebRTCRTPTransceiver>(
+                "add-transceiver",
+                &[
+                    &gst_webrtc::WebRTCRTPTransceiverDirection::Recvonly,
+                    &gst::Caps::builder("application/x-rtp")
+                        .field("media", "video")
+                        .field("encoding-name", "VP8")
+                        .field("payload", 96i32)
+                        .field("clock-rate", 90000i32)
+                        .build(),
+                ],
+            );
+
+        let promise = gst::Promise::new();
+        self.webrtcbin
+            .emit_by_name::<()>("create-offer", &[&None::<gst::Structure>, &promise]);
+        promise.wait();
+
+        let offer = promise
+            .get_reply()
+            .unwrap()
+            .get::<gst_webrtc::WebRTCSessionDescription>("offer")
+            .unwrap();
+
+        self.webrtcbin
+            .emit_by_name::<()>("set-local-description", &[&offer, &None::<gst::Promise>]);
+    }
+
+    fn wait_for_message(&self) -> Message {
+        self.receiver.recv_timeout(TIMEOUT).unwrap()
+    }
+
+    fn wait_for_answer(&self) -> gst_webrtc::WebRTCSessionDescription {
+        match self.wait_for_message() {
+            Message::SessionDescription(desc) => desc,
+            msg => panic!("Expected an answer, got {msg:?}"),
+        }
+    }
+
+    fn wait_for_error(&self) -> String {
+        match self.wait_for_message() {
+            Message::Error(err) => err,
+            msg => panic!("Expected an error, got {msg:?}"),
+        }
+    }
+
+    /// Waits for the server to have received `n` requests
+    fn wait_for_requests(&self, n: usize) -> Vec<Request> {
+        let start = Instant::now();
+        loop {
+            let requests = self.requests.lock().unwrap().clone();
+            if requests.len() >= n || start.elapsed() > TIMEOUT {
+                return requests;
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
+
+/// Answers INVITEs and BYEs with 200 OK
+fn answer(request: &Request) -> Option<String> {
+    match request.method.as_str() {
+        "INVITE" => Some(request.response(
+            "200 OK",
+            &[
+                ("Contact", "<sip:1000@127.0.0.1>"),
+                ("Content-Type", "application/sdp"),
+            ],
+            ANSWER,
+        )),
+        "BYE" => Some(request.response("200 OK", &[], "")),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_invite() {
+    let h = Harness::new(|req, _| answer(req));
+
+    h.send_offer();
+
+    let answer = h.wait_for_answer();
+    assert_eq!(answer.type_(), gst_webrtc::WebRTCSDPType::Answer);
+    assert_eq!(answer.sdp().medias_len(), 1);
+
+    let requests = h.wait_for_requests(2);
+    assert_eq!(requests.len(), 2);
+
+    let invite = &requests[0];
+    assert_eq!(invite.method, "INVITE");
+    assert_eq!(invite.header("Content-Type").unwrap(), "application/sdp");
+    assert!(invite.header("Via").unwrap().contains(";branch=z9hG4bK"));
+
+    // The offer is only sent once all candidates are in it
+    let offer = gst_sdp::SDPMessage::parse_buffer(invite.body.as_bytes()).unwrap();
+    let media = offer.media(0).unwrap();
+    assert!(media.attributes().any(|attr| attr.key() == "candidate"));
+    assert!(media.attribute_val("recvonly").is_some());
+
+    // The answer is acknowledged within the dialog
+    let ack = &requests[1];
+    assert_eq!(ack.method, "ACK");
+    assert_eq!(ack.header("Call-ID"), invite.header("Call-ID"));
+    assert!(ack.header("To").unwrap().ends_with(";tag=server"));
+    assert!(ack.header("CSeq").unwrap().ends_with(" ACK"));
+}
+
+#[test]
+fn test_bye_on_stop() {
+    let h = Harness::new(|req, _| answer(req));
+
+    h.send_offer();
+    h.wait_for_answer();
+    h.wait_for_requests(2);
+
+    h.signaller.stop();
+
+    let requests = h.wait_for_requests(3);
+    assert_eq!(requests.len(), 3);
+
+    let bye = &requests[2];
+    assert_eq!(bye.method, "BYE");
+    assert_eq!(bye.header("Call-ID"), requests[0].header("Call-ID"));
+    assert!(bye.header("To").unwrap().ends_with(";tag=server"));
+
+    // Only once
+    h.signaller.stop();
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(h.requests.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_digest_auth() {
+    let h = Harness::new(|req, n| match (req.method.as_str(), n) {
+        ("INVITE", 0) => Some(req.response(
+            "401 Unauthorized",
+            &[(
+                "WWW-Authenticate",
+                "Digest realm=\"example.com\", nonce=\"abcdef\", qop=\"auth\"",
+            )],
+            "",
+        )),
+        _ => answer(req),
+    });
+    h.signaller.set_property("username", "gst");
+    h.signaller.set_property("password", "secret");
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    let requests = h.wait_for_requests(4);
+    assert_eq!(
+        requests
+            .iter()
+            .map(|req| req.method.as_str())
+            .collect::<Vec<_>>(),
+        ["INVITE", "ACK", "INVITE", "ACK"]
+    );
+
+    // The challenge is acknowledged within its transaction
+    assert_eq!(requests[1].header("Via"), requests[0].header("Via"));
+
+    let authorization = requests[2].header("Authorization").unwrap();
+    assert!(authorization.starts_with("Digest username=\"gst\""));
+    assert!(authorization.contains("realm=\"example.com\""));
+    assert!(authorization.contains("qop=auth"));
+    assert_ne!(requests[2].header("CSeq"), requests[0].header("CSeq"));
+    assert_eq!(requests[2].header("Call-ID"), requests[0].header("Call-ID"));
+}
+
+#[test]
+fn test_call_rejected() {
+    let h = Harness::new(|req, _| match req.method.as_str() {
+        "INVITE" => Some(req.response("486 Busy Here", &[], "")),
+        _ => None,
+    });
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Call failed: SIP/2.0 486 Busy Here"), "{err}");
+}
+
+#[test]
+fn test_no_target() {
+    let h = Harness::new(|req, _| answer(req));
+    h.signaller.set_property("target-uri", None::<String>);
+
+    h.signaller.start();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Target URI must be set"), "{err}");
+    assert!(h.requests.lock().unwrap().is_empty());
+}
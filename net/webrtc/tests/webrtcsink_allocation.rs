@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsink allocation tests");
+    });
+}
+
+/// A webrtcsink with a video input pad, ready to answer queries
+fn setup() -> (gst::Element, gst::Pad) {
+    let sink = gst::ElementFactory::make("webrtcsink").build().unwrap();
+    let pad = sink.request_pad_simple("video_%u").unwrap();
+
+    pad.set_active(true).unwrap();
+
+    (sink, pad)
+}
+
+fn video_caps() -> gst::Caps {
+    gst_video::VideoCapsBuilder::new()
+        .format(gst_video::VideoFormat::I420)
+        .width(320)
+        .height(240)
+        .framerate(gst::Fraction::new(30, 1))
+        .build()
+}
+
+#[test]
+fn test_raw_video_proposes_pool() {
+    init();
+
+    let (_sink, pad) = setup();
+    let caps = video_caps();
+    let info = gst_video::VideoInfo::from_caps(&caps).unwrap();
+
+    let mut query = gst::query::Allocation::new(Some(&caps), true);
+    assert!(pad.query(&mut query));
+
+    assert!(query
+        .find_allocation_meta::<gst_video::VideoMeta>()
+        .is_some());
+
+    let pools = query.allocation_pools();
+    assert_eq!(pools.len(), 1);
+
+    let (pool, size, _, _) = &pools[0];
+    let pool = pool.as_ref().expect("proposed pool");
+    assert!(*size as usize >= info.size());
+
+    let config = pool.config();
+    assert!(config.has_option(&gst_video::BUFFER_POOL_OPTION_VIDEO_META));
+    assert_eq!(config.params().unwrap().1, *size);
+
+    // The pool must be usable by upstream as is
+    pool.set_active(true).unwrap();
+    let buffer = pool.acquire_buffer(None).unwrap();
+    assert!(buffer.size() >= info.size());
+    pool.set_active(false).unwrap();
+}
+
+#[test]
+fn test_raw_video_without_pool() {
+    init();
+
+    let (_sink, pad) = setup();
+
+    let mut query = gst::query::Allocation::new(Some(&video_caps()), false);
+    assert!(pad.query(&mut query));
+
+    assert!(query
+        .find_allocation_meta::<gst_video::VideoMeta>()
+        .is_some());
+    assert!(query.allocation_pools().is_empty());
+    assert_eq!(query.allocation_params().len(), 1);
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+
+mod common;
+
+use common::{local_webrtcsink, TIMEOUT};
+use gst::prelude::*;
+use gstrswebrtc::signaller::Signallable;
+
+use std::sync::{mpsc, Mutex};
+
+#[test]
+fn test_drain_notifies_end_of_stream() {
+    let sink = local_webrtcsink();
+    sink.set_property("drain-timeout", 10000u32);
+
+    let pipeline = gst::Pipeline::new();
+    let src = gst::ElementFactory::make("videotestsrc")
+        .property("is-live", true)
+        .property("num-buffers", 60i32)
+        .build()
+        .unwrap();
+    pipeline.add_many([&src, &sink]).unwrap();
+    src.link(&sink).unwrap();
+
+    let signaller = sink.property::<Signallable>("signaller");
+
+    let (eos_sender, eos_receiver) = mpsc::channel();
+    let eos_sender = Mutex::new(eos_sender);
+    signaller.connect("end-of-stream", false, move |args| {
+        let session_id = args[1].get::<String>().unwrap();
+        let _ = eos_sender.lock().unwrap().send(session_id);
+        None
+    });
+
+    let (removed_sender, removed_receiver) = mpsc::channel();
+    let removed_sender = Mutex::new(removed_sender);
+    sink.connect("consumer-removed", false, move |args| {
+        let peer_id = args[1].get::<String>().unwrap();
+        let _ = removed_sender.lock().unwrap().send(peer_id);
+        None
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    let bus = pipeline.bus().unwrap();
+    let eos = bus
+        .iter_timed(gst::ClockTime::try_from(TIMEOUT).unwrap())
+        .any(|msg| matches!(msg.view(), gst::MessageView::Eos(..)));
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    assert!(eos, "No EOS");
+
+    // The element only posts EOS once the session was notified and ended
+    assert_eq!(eos_receiver.try_recv().unwrap(), "session-1");
+    assert_eq!(removed_receiver.try_recv().unwrap(), "consumer-1");
+}
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+const RTP_TWCC_URI: &str =
+    "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01";
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsink interop tests");
+    });
+}
+
+/// Runs a sink with one audio stream and the given interop rules, and
+/// returns the offer it makes to its first consumer
+fn offer_with_rules(rules: &[&str]) -> String {
+    init();
+
+    let pipeline = gst::Pipeline::new();
+
+    // The WHIP signaller starts a session on its own, without advertising
+    // a user agent, the offer is caught before it tries to reach the
+    // endpoint
+    let sink = gst::ElementFactory::make("whipclientsink").build().unwrap();
+    sink.property::<glib::Object>("signaller")
+        .set_property("whip-endpoint", "http://127.0.0.1:9/whip/endpoint");
+    sink.set_property(
+        "interop-rules",
+        gst::Array::new(
+            rules
+                .iter()
+                .map(|rule| rule.parse::<gst::Structure>().unwrap()),
+        ),
+    );
+    pipeline.add(&sink).unwrap();
+
+    let src = gst::ElementFactory::make("audiotestsrc")
+        .property("is-live", true)
+        .build()
+        .unwrap();
+    pipeline.add(&src).unwrap();
+    src.link(&sink).unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    sink.connect("munge-sdp", false, move |args| {
+        let local = args[2].get::<bool>().unwrap();
+        let description = args[3]
+            .get::<gst_webrtc::WebRTCSessionDescription>()
+            .unwrap();
+
+        if local {
+            let _ = sender
+                .lock()
+                .unwrap()
+                .send(description.sdp().as_text().unwrap());
+        }
+
+        Some(args[3].clone())
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let offer = receiver.recv_timeout(TIMEOUT).unwrap();
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    offer
+}
+
+#[test]
+fn test_twcc_offered_by_default() {
+    let offer = offer_with_rules(&[]);
+
+    assert!(offer.contains(RTP_TWCC_URI), "{offer}");
+}
+
+#[test]
+fn test_rule_disables_twcc() {
+    let offer = offer_with_rules(&[r#"rule, user-agent=(string)"^$", disable-twcc=(boolean)true"#]);
+
+    assert!(!offer.contains(RTP_TWCC_URI), "{offer}");
+    assert!(!offer.contains("transport-cc"), "{offer}");
+}
+
+#[test]
+fn test_unmatched_rule_is_ignored() {
+    let offer = offer_with_rules(&["rule, user-agent=(string)SDPARTA, disable-twcc=(boolean)true"]);
+
+    assert!(offer.contains(RTP_TWCC_URI), "{offer}");
+}
+
+#[test]
+fn test_invalid_rules_are_dropped() {
+    init();
+
+    let sink = gst::ElementFactory::make("whipclientsink").build().unwrap();
+    sink.set_property(
+        "interop-rules",
+        gst::Array::new([
+            "rule, disable-twcc=(boolean)true"
+                .parse::<gst::Structure>()
+                .unwrap(),
+            r#"rule, user-agent=(string)"(", disable-twcc=(boolean)true"#
+                .parse::<gst::Structure>()
+                .unwrap(),
+            "rule, user-agent=(string)Chrome, h264-packetization-mode=(int)1"
+                .parse::<gst::Structure>()
+                .unwrap(),
+        ]),
+    );
+
+    let rules = sink.property::<gst::Array>("interop-rules");
+    assert_eq!(rules.len(), 1);
+    assert_eq!(
+        rules.as_slice()[0]
+            .get::<gst::Structure>()
+            .unwrap()
+            .get::<&str>("user-agent")
+            .unwrap(),
+        "Chrome"
+    );
+}
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsink msid tests");
+    });
+}
+
+/// MSID of a media, as written by webrtcbin either in its own attribute or
+/// in the attributes of its SSRC
+fn media_msid(media: &gst_sdp::SDPMediaRef) -> Option<String> {
+    media
+        .attribute_val("msid")
+        .or_else(|| {
+            media
+                .attributes()
+                .filter(|attr| attr.key() == "ssrc")
+                .filter_map(|attr| attr.value())
+                .find_map(|value| value.split_once(" msid:").map(|(_, msid)| msid))
+        })
+        .and_then(|msid| msid.split_whitespace().next())
+        .map(str::to_string)
+}
+
+/// Runs a sink with one audio stream per track name, and returns the offer
+/// it makes to its first consumer
+fn offer_for_audio_tracks(track_names: &[Option<&str>], msids: &[Option<&str>]) -> String {
+    init();
+
+    let pipeline = gst::Pipeline::new();
+
+    // The WHIP signaller starts a session on its own, the offer is
+    // caught before it tries to reach the endpoint
+    let sink = gst::ElementFactory::make("whipclientsink").build().unwrap();
+    sink.property::<glib::Object>("signaller")
+        .set_property("whip-endpoint", "http://127.0.0.1:9/whip/endpoint");
+    pipeline.add(&sink).unwrap();
+
+    for (track_name, msid) in track_names.iter().zip(msids) {
+        let src = gst::ElementFactory::make("audiotestsrc")
+            .property("is-live", true)
+            .build()
+            .unwrap();
+        pipeline.add(&src).unwrap();
+
+        let sinkpad = sink.request_pad_simple("audio_%u").unwrap();
+        sinkpad.set_property("track-name", *track_name);
+        sinkpad.set_property("msid", *msid);
+        src.static_pad("src").unwrap().link(&sinkpad).unwrap();
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    sink.connect("munge-sdp", false, move |args| {
+        let local = args[2].get::<bool>().unwrap();
+        let description = args[3]
+            .get::<gst_webrtc::WebRTCSessionDescription>()
+            .unwrap();
+
+        if local {
+            let _ = sender
+                .lock()
+                .unwrap()
+                .send(description.sdp().as_text().unwrap());
+        }
+
+        Some(args[3].clone())
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let offer = receiver.recv_timeout(TIMEOUT).unwrap();
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    offer
+}
+
+fn audio_msids(offer: &str) -> Vec<Option<String>> {
+    let sdp = gst_sdp::SDPMessage::parse_buffer(offer.as_bytes()).unwrap();
+
+    sdp.medias()
+        .filter(|media| media.media() == Some("audio"))
+        .map(media_msid)
+        .collect()
+}
+
+#[test]
+fn test_audio_tracks_get_distinct_msids() {
+    let offer = offer_for_audio_tracks(&[None, Some("Commentary")], &[None, None]);
+    let msids = audio_msids(&offer);
+
+    assert_eq!(msids.len(), 2, "{offer}");
+    assert_eq!(msids[0].as_deref(), Some("audio_0"), "{offer}");
+    assert_eq!(msids[1].as_deref(), Some("Commentary-audio_1"), "{offer}");
+
+    // The track names are written as labels
+    assert!(offer.contains("a=label:Commentary"), "{offer}");
+}
+
+#[test]
+fn test_audio_track_names_are_sanitized() {
+    let offer = offer_for_audio_tracks(&[Some("Main mix"), Some("Voix off")], &[None, None]);
+    let msids = audio_msids(&offer);
+
+    assert_eq!(
+        msids,
+        vec![
+            Some("Main-mix-audio_0".to_string()),
+            Some("Voix-off-audio_1".to_string())
+        ],
+        "{offer}"
+    );
+}
+
+#[test]
+fn test_explicit_msid_is_kept() {
+    let offer = offer_for_audio_tracks(&[None, None], &[Some("program"), Some("program")]);
+    let msids = audio_msids(&offer);
+
+    assert_eq!(
+        msids,
+        vec![Some("program".to_string()), Some("program".to_string())],
+        "{offer}"
+    );
+}
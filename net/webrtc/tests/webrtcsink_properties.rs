@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsink properties tests");
+    });
+}
+
+fn loss_thresholds(sink: &gst::Element) -> (f64, f64) {
+    (
+        sink.property::<f64>("homegrown-loss-threshold-low"),
+        sink.property::<f64>("homegrown-loss-threshold-high"),
+    )
+}
+
+#[test]
+fn test_loss_thresholds_in_any_order() {
+    init();
+
+    // Raising both thresholds above the defaults, low first
+    let sink = gst::ElementFactory::make("webrtcsink").build().unwrap();
+    sink.set_property("homegrown-loss-threshold-low", 15f64);
+    sink.set_property("homegrown-loss-threshold-high", 30f64);
+    assert_eq!(loss_thresholds(&sink), (15., 30.));
+
+    // High first
+    let sink = gst::ElementFactory::make("webrtcsink").build().unwrap();
+    sink.set_property("homegrown-loss-threshold-high", 30f64);
+    sink.set_property("homegrown-loss-threshold-low", 15f64);
+    assert_eq!(loss_thresholds(&sink), (15., 30.));
+
+    // Lowering both thresholds below the defaults, low first
+    let sink = gst::ElementFactory::make("webrtcsink").build().unwrap();
+    sink.set_property("homegrown-loss-threshold-low", 0.5f64);
+    sink.set_property("homegrown-loss-threshold-high", 1f64);
+    assert_eq!(loss_thresholds(&sink), (0.5, 1.));
+
+    // High first
+    let sink = gst::ElementFactory::make("webrtcsink").build().unwrap();
+    sink.set_property("homegrown-loss-threshold-high", 1f64);
+    sink.set_property("homegrown-loss-threshold-low", 0.5f64);
+    assert_eq!(loss_thresholds(&sink), (0.5, 1.));
+}
+
+#[test]
+fn test_audio_bitrate_range() {
+    init();
+
+    let sink = gst::ElementFactory::make("webrtcsink").build().unwrap();
+
+    // The ceiling is left to the bitrate of the encoders by default
+    assert_eq!(sink.property::<u32>("audio-min-bitrate"), 16000);
+    assert_eq!(sink.property::<u32>("audio-max-bitrate"), 650000);
+
+    sink.set_property("audio-min-bitrate", 24000u32);
+    sink.set_property("audio-max-bitrate", 32000u32);
+    assert_eq!(sink.property::<u32>("audio-min-bitrate"), 24000);
+    assert_eq!(sink.property::<u32>("audio-max-bitrate"), 32000);
+}
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MPL-2.0
+
+mod common;
+
+use common::{connect_sent_descriptions, local_webrtcsink, TIMEOUT};
+use gst::prelude::*;
+use gstrswebrtc::signaller::Signallable;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Mutex};
+
+/// A sink with a single session, the test plays the part of the consumer
+struct Harness {
+    pipeline: gst::Pipeline,
+    sink: gst::Element,
+    webrtcbin: gst::Element,
+    offers: mpsc::Receiver<gst_webrtc::WebRTCSessionDescription>,
+}
+
+impl Harness {
+    fn new(configure: impl FnOnce(&gst::Element)) -> Harness {
+        let sink = local_webrtcsink();
+        configure(&sink);
+
+        let pipeline = gst::Pipeline::new();
+        let src = gst::ElementFactory::make("videotestsrc")
+            .property("is-live", true)
+            .build()
+            .unwrap();
+        pipeline.add_many([&src, &sink]).unwrap();
+        src.link(&sink).unwrap();
+
+        let offers = connect_sent_descriptions(&sink.property::<Signallable>("signaller"));
+
+        let (webrtcbin_sender, webrtcbin_receiver) = mpsc::channel();
+        let webrtcbin_sender = Mutex::new(webrtcbin_sender);
+        sink.connect("consumer-added", false, move |args| {
+            let webrtcbin = args[2].get::<gst::Element>().unwrap();
+            let _ = webrtcbin_sender.lock().unwrap().send(webrtcbin);
+            None
+        });
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        let webrtcbin = webrtcbin_receiver.recv_timeout(TIMEOUT).unwrap();
+
+        Harness {
+            pipeline,
+            sink,
+            webrtcbin,
+            offers,
+        }
+    }
+
+    /// Waits for the next offer of the session and returns its ICE ufrag
+    fn wait_for_offer(&self) -> String {
+        let offer = self.offers.recv_timeout(TIMEOUT).unwrap();
+        assert_eq!(offer.type_(), gst_webrtc::WebRTCSDPType::Offer);
+
+        offer
+            .sdp()
+            .media(0)
+            .and_then(|media| media.attribute_val("ice-ufrag"))
+            .map(String::from)
+            .unwrap()
+    }
+
+    fn restart_ice(&self) -> bool {
+        self.sink
+            .emit_by_name::<bool>("restart-ice", &[&None::<String>])
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
+
+#[test]
+fn test_restart_ice_replaces_turn_server() {
+    let h = Harness::new(|sink| {
+        sink.set_property("turn-servers", gst::Array::new(["turn://127.0.0.1:3478"]));
+
+        // Fresh credentials each time they are requested
+        let n_credentials = AtomicU32::new(0);
+        sink.connect("request-turn-credentials", false, move |_args| {
+            let n = n_credentials.fetch_add(1, Ordering::SeqCst) + 1;
+            Some(
+                Some(
+                    gst::Structure::builder("credentials")
+                        .field("username", format!("user{n}"))
+                        .field("password", "password")
+                        .build(),
+                )
+                .to_value(),
+            )
+        });
+    });
+
+    let ufrag = h.wait_for_offer();
+
+    let turn_server = h
+        .webrtcbin
+        .property::<Option<String>>("turn-server")
+        .unwrap();
+    assert!(turn_server.contains("user1:"), "{turn_server}");
+
+    assert!(h.restart_ice());
+
+    // The offer is made with new ICE credentials and the stale TURN server
+    // is replaced
+    assert_ne!(h.wait_for_offer(), ufrag);
+
+    let turn_server = h
+        .webrtcbin
+        .property::<Option<String>>("turn-server")
+        .unwrap();
+    assert!(turn_server.contains("user2:"), "{turn_server}");
+}
+
+#[test]
+fn test_restart_ice_reports_stale_turn_servers() {
+    let h = Harness::new(|sink| {
+        sink.set_property(
+            "turn-servers",
+            gst::Array::new(["turn://127.0.0.1:3478", "turn://127.0.0.1:3479"]),
+        );
+    });
+
+    let ufrag = h.wait_for_offer();
+
+    // Only the first TURN server can be replaced in webrtcbin, the session
+    // is restarted nonetheless
+    h.sink.set_property(
+        "turn-servers",
+        gst::Array::new(["turn://127.0.0.1:3478", "turn://127.0.0.1:3480"]),
+    );
+    assert!(!h.restart_ice());
+    assert_ne!(h.wait_for_offer(), ufrag);
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsink self-test tests");
+    });
+}
+
+#[test]
+fn test_self_test_report() {
+    init();
+
+    let pipeline = gst::parse_launch(
+        "videotestsrc is-live=true ! video/x-raw,width=320,height=240 ! \
+         webrtcsink name=sink self-test=true video-caps=video/x-vp8",
+    )
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    let sink = pipeline.by_name("sink").unwrap();
+    assert!(sink.property::<bool>("self-test"));
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+
+    let bus = pipeline.bus().unwrap();
+    let mut report = None;
+
+    // The report is posted after ten seconds of media, then the sink
+    // posts EOS
+    for msg in bus.iter_timed(gst::ClockTime::from_seconds(60)) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Element(element) => {
+                let Some(s) = element.structure() else {
+                    continue;
+                };
+
+                if s.name() == "webrtcsink-self-test" {
+                    assert_eq!(msg.src(), Some(sink.upcast_ref::<gst::Object>()));
+                    report = Some(s.to_owned());
+                }
+            }
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => panic!("Unexpected error: {err:?}"),
+            _ => (),
+        }
+    }
+
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let report = report.expect("No self-test report");
+    assert!(report.get::<bool>("success").unwrap(), "{report}");
+    assert!(report.get::<u64>("bitrate").unwrap() > 0, "{report}");
+
+    let codecs = report
+        .get::<gst::Array>("codecs")
+        .unwrap()
+        .iter()
+        .map(|codec| codec.get::<String>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(codecs, vec!["VP8".to_string()], "{report}");
+}
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsink shared pipeline tests");
+    });
+}
+
+/// A sink in shared-pipeline mode with a single session
+struct Harness {
+    pipeline: gst::Pipeline,
+    /// The bin of the session in the shared pipeline
+    session_bin: gst::Bin,
+    removed: mpsc::Receiver<String>,
+}
+
+impl Harness {
+    fn new() -> Harness {
+        init();
+
+        let pipeline = gst::Pipeline::new();
+
+        // The WHIP signaller starts a session on its own, the endpoint is
+        // never reached in these tests
+        let sink = gst::ElementFactory::make("whipclientsink")
+            .property("shared-pipeline", true)
+            .build()
+            .unwrap();
+        sink.property::<glib::Object>("signaller")
+            .set_property("whip-endpoint", "http://127.0.0.1:9/whip/endpoint");
+
+        let src = gst::ElementFactory::make("videotestsrc")
+            .property("is-live", true)
+            .build()
+            .unwrap();
+        pipeline.add_many([&src, &sink]).unwrap();
+        src.link(&sink).unwrap();
+
+        let (created_sender, created) = mpsc::channel();
+        let created_sender = Mutex::new(created_sender);
+        sink.connect("consumer-pipeline-created", false, move |args| {
+            let bin = args[2].get::<gst::Bin>().unwrap();
+            let _ = created_sender.lock().unwrap().send(bin);
+            None
+        });
+
+        let (removed_sender, removed) = mpsc::channel();
+        let removed_sender = Mutex::new(removed_sender);
+        sink.connect("consumer-removed", false, move |args| {
+            let peer_id = args[1].get::<String>().unwrap();
+            let _ = removed_sender.lock().unwrap().send(peer_id);
+            None
+        });
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        let session_bin = created.recv_timeout(TIMEOUT).unwrap();
+        assert!(session_bin.downcast_ref::<gst::Pipeline>().is_none());
+
+        // Messages only reach the shared pipeline once the bin is in it
+        let deadline = Instant::now() + TIMEOUT;
+        let shared_pipeline = loop {
+            if let Some(parent) = session_bin.parent() {
+                break parent;
+            }
+            assert!(Instant::now() < deadline, "Session bin never added");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert!(shared_pipeline.is::<gst::Pipeline>());
+        assert_ne!(&shared_pipeline, pipeline.upcast_ref::<gst::Object>());
+
+        Harness {
+            pipeline,
+            session_bin,
+            removed,
+        }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
+
+#[test]
+fn test_session_error_removes_session() {
+    let h = Harness::new();
+
+    let _ = h.session_bin.post_message(
+        gst::message::Error::builder(gst::CoreError::Failed, "Test error")
+            .src(&h.session_bin)
+            .build(),
+    );
+
+    assert_eq!(h.removed.recv_timeout(TIMEOUT).unwrap(), "unique");
+}
+
+#[test]
+fn test_session_eos_removes_session() {
+    let h = Harness::new();
+
+    // The shared pipeline itself doesn't post EOS while other sessions
+    // are running, the session is removed nonetheless
+    let _ = h
+        .session_bin
+        .post_message(gst::message::Eos::builder().src(&h.session_bin).build());
+
+    assert_eq!(h.removed.recv_timeout(TIMEOUT).unwrap(), "unique");
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsink streams tests");
+    });
+}
+
+#[test]
+fn test_get_streams() {
+    init();
+
+    let sink = gst::ElementFactory::make("webrtcsink").build().unwrap();
+    let video_pad = sink.request_pad_simple("video_%u").unwrap();
+    let audio_pad = sink.request_pad_simple("audio_%u").unwrap();
+
+    let streams = sink.emit_by_name::<gst::Array>("get-streams", &[]);
+    assert_eq!(streams.len(), 2);
+
+    let streams = streams
+        .iter()
+        .map(|stream| stream.get::<gst::Structure>().unwrap())
+        .collect::<Vec<_>>();
+
+    // Sorted by name
+    assert_eq!(
+        streams[0].get::<String>("name").unwrap(),
+        audio_pad.name().as_str()
+    );
+    assert!(!streams[0].get::<bool>("is-video").unwrap());
+    assert_eq!(
+        streams[1].get::<String>("name").unwrap(),
+        video_pad.name().as_str()
+    );
+    assert!(streams[1].get::<bool>("is-video").unwrap());
+
+    for stream in &streams {
+        assert_eq!(stream.get::<&str>("discovery").unwrap(), "waiting-for-caps");
+        assert!(stream
+            .get::<Option<gst::Caps>>("in-caps")
+            .unwrap()
+            .is_none());
+        assert!(stream.get::<gst::Array>("codecs").unwrap().is_empty());
+        assert_eq!(stream.get::<u32>("n-connected-sessions").unwrap(), 0);
+    }
+
+    sink.release_request_pad(&audio_pad);
+
+    let streams = sink.emit_by_name::<gst::Array>("get-streams", &[]);
+    assert_eq!(streams.len(), 1);
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gst::glib;
+use gst::prelude::*;
+
+fn init() {
+    use std::sync::Once;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstrswebrtc::plugin_register_static().expect("webrtcsrc producer selection tests");
+    });
+}
+
+/// Announces producers through the signaller of a webrtcsrc configured
+/// with `properties`, and returns the one it selected
+fn select_producer(
+    properties: &[(&str, &dyn ToValue)],
+    producers: &[(&str, Option<&str>)],
+) -> Option<String> {
+    init();
+
+    let src = gst::ElementFactory::make("webrtcsrc").build().unwrap();
+    for (name, value) in properties {
+        src.set_property_from_value(name, &value.to_value());
+    }
+
+    // The signaller isn't started, announcing producers doesn't start
+    // sessions
+    let signaller = src.property::<glib::Object>("signaller");
+    for (producer_id, meta) in producers {
+        let meta = meta.map(|meta| meta.parse::<gst::Structure>().unwrap());
+        signaller.emit_by_name::<()>("producer-added", &[producer_id, &meta, &true]);
+    }
+
+    signaller.property::<Option<String>>("producer-peer-id")
+}
+
+#[test]
+fn test_first_producer_selected() {
+    assert_eq!(
+        select_producer(&[], &[("p1", None), ("p2", None)]).as_deref(),
+        Some("p1")
+    );
+}
+
+#[test]
+fn test_allow_list() {
+    assert_eq!(
+        select_producer(
+            &[("producer-peer-ids", &gst::Array::new(["p2", "p3"]))],
+            &[("p1", None), ("p2", None), ("p3", None)]
+        )
+        .as_deref(),
+        Some("p2")
+    );
+
+    assert_eq!(
+        select_producer(
+            &[("producer-peer-ids", &gst::Array::new(["p3"]))],
+            &[("p1", None), ("p2", None)]
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_meta_filter() {
+    let filter = "meta, room=(string)lobby, kind=(string){ camera, screen }"
+        .parse::<gst::Structure>()
+        .unwrap();
+
+    assert_eq!(
+        select_producer(
+            &[("producer-meta-filter", &filter)],
+            &[
+                ("p1", None),
+                ("p2", Some("meta, room=(string)lobby, kind=(string)audio")),
+                ("p3", Some("meta, room=(string)stage, kind=(string)camera")),
+                (
+                    "p4",
+                    Some("meta, room=(string)lobby, kind=(string)screen, name=(string)Bob")
+                ),
+            ]
+        )
+        .as_deref(),
+        Some("p4")
+    );
+}
+
+#[test]
+fn test_explicit_producer_kept() {
+    init();
+
+    let src = gst::ElementFactory::make("webrtcsrc").build().unwrap();
+    let signaller = src.property::<glib::Object>("signaller");
+    signaller.set_property("producer-peer-id", "p2");
+
+    signaller.emit_by_name::<()>("producer-added", &[&"p1", &None::<gst::Structure>, &true]);
+
+    assert_eq!(
+        signaller
+            .property::<Option<String>>("producer-peer-id")
+            .as_deref(),
+        Some("p2")
+    );
+}
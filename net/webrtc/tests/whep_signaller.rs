@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: MPL-2.0
+
+mod common;
+
+use common::{Harness, Request};
+use gst::prelude::*;
+use gstrswebrtc::signaller::SignallableExt;
+
+const ANSWER: &str = "v=0\r\n\
+    o=- 0 0 IN IP4 127.0.0.1\r\n\
+    s=-\r\n\
+    t=0 0\r\n\
+    m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+    c=IN IP4 0.0.0.0\r\n\
+    a=rtpmap:96 VP8/90000\r\n\
+    a=sendonly\r\n";
+
+/// A WHEP signaller handing the offers of webrtcsrc to a mock WHEP
+/// endpoint answering with `http_func`
+fn harness<F>(http_func: F) -> Harness
+where
+    F: Fn(&Request) -> hyper::Response<hyper::Body> + Send + Sync + 'static,
+{
+    Harness::new(
+        "whepwebrtcsrc",
+        "whep-endpoint",
+        "/whep/endpoint",
+        gst_webrtc::WebRTCRTPTransceiverDirection::Recvonly,
+        http_func,
+    )
+}
+
+fn created_response() -> hyper::Response<hyper::Body> {
+    hyper::Response::builder()
+        .status(201)
+        .header("Content-Type", "application/sdp")
+        .header("Location", "/whep/resource/1")
+        .body(ANSWER.into())
+        .unwrap()
+}
+
+#[test]
+fn test_post_offer() {
+    let h = harness(|_req| created_response());
+    h.signaller.set_property("auth-token", "secret");
+
+    h.send_offer();
+
+    let answer = h.wait_for_answer();
+    assert_eq!(answer.type_(), gst_webrtc::WebRTCSDPType::Answer);
+    assert_eq!(answer.sdp().medias_len(), 1);
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 1);
+
+    let post = &requests[0];
+    assert_eq!(post.method, hyper::Method::POST);
+    assert_eq!(post.path, "/whep/endpoint");
+    assert_eq!(post.headers.get("Content-Type").unwrap(), "application/sdp");
+    assert_eq!(post.headers.get("Authorization").unwrap(), "Bearer secret");
+
+    // The offer is only sent once all candidates are in it
+    let offer = gst_sdp::SDPMessage::parse_buffer(post.body.as_bytes()).unwrap();
+    let media = offer.media(0).unwrap();
+    assert!(media.attributes().any(|attr| attr.key() == "candidate"));
+    assert!(media.attribute_val("recvonly").is_some());
+}
+
+#[test]
+fn test_delete_on_stop() {
+    let h = harness(|_req| created_response());
+    h.signaller.set_property("auth-token", "secret");
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    h.signaller.stop();
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 2);
+
+    // The resource URL is relative to the endpoint
+    let delete = &requests[1];
+    assert_eq!(delete.method, hyper::Method::DELETE);
+    assert_eq!(delete.path, "/whep/resource/1");
+    assert_eq!(
+        delete.headers.get("Authorization").unwrap(),
+        "Bearer secret"
+    );
+
+    // Only once
+    h.signaller.stop();
+    assert_eq!(h.requests().len(), 2);
+}
+
+#[test]
+fn test_redirect() {
+    let h = harness(|req| match req.path.as_str() {
+        "/whep/endpoint" => hyper::Response::builder()
+            .status(307)
+            .header("Location", "/whep/redirected")
+            .body(hyper::Body::empty())
+            .unwrap(),
+        _ => created_response(),
+    });
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].method, hyper::Method::POST);
+    assert_eq!(requests[1].path, "/whep/redirected");
+    assert_eq!(requests[1].body, requests[0].body);
+}
+
+#[test]
+fn test_server_error() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(503)
+            .body("unavailable".into())
+            .unwrap()
+    });
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Unexpected response: 503"), "{err}");
+}
+
+#[test]
+fn test_no_endpoint() {
+    let h = harness(|_req| created_response());
+    h.signaller.set_property("whep-endpoint", None::<String>);
+
+    h.signaller.start();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("WHEP endpoint URL must be set"), "{err}");
+    assert!(h.requests().is_empty());
+}
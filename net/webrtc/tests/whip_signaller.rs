@@ -0,0 +1,535 @@
+// SPDX-License-Identifier: MPL-2.0
+
+mod common;
+
+use common::{Harness, Request};
+use gst::prelude::*;
+use gstrswebrtc::signaller::SignallableExt;
+
+use std::sync::{Arc, Mutex};
+
+const ANSWER: &str = "v=0\r\n\
+    o=- 0 0 IN IP4 127.0.0.1\r\n\
+    s=-\r\n\
+    t=0 0\r\n\
+    m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+    c=IN IP4 0.0.0.0\r\n\
+    a=rtpmap:96 VP8/90000\r\n\
+    a=recvonly\r\n";
+
+/// A WHIP signaller handing the offers of webrtcsink to a mock WHIP
+/// endpoint answering with `http_func`
+fn harness<F>(http_func: F) -> Harness
+where
+    F: Fn(&Request) -> hyper::Response<hyper::Body> + Send + Sync + 'static,
+{
+    Harness::new(
+        "whipclientsink",
+        "whip-endpoint",
+        "/whip/endpoint",
+        gst_webrtc::WebRTCRTPTransceiverDirection::Sendonly,
+        http_func,
+    )
+}
+
+fn created_response() -> hyper::Response<hyper::Body> {
+    hyper::Response::builder()
+        .status(201)
+        .header("Content-Type", "application/sdp")
+        .header("Location", "/whip/resource/1")
+        .body(ANSWER.into())
+        .unwrap()
+}
+
+#[test]
+fn test_post_offer() {
+    let h = harness(|_req| created_response());
+    h.signaller.set_property("auth-token", "secret");
+
+    h.send_offer();
+
+    let answer = h.wait_for_answer();
+    assert_eq!(answer.type_(), gst_webrtc::WebRTCSDPType::Answer);
+    assert_eq!(answer.sdp().medias_len(), 1);
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 1);
+
+    let post = &requests[0];
+    assert_eq!(post.method, hyper::Method::POST);
+    assert_eq!(post.path, "/whip/endpoint");
+    assert_eq!(post.headers.get("Content-Type").unwrap(), "application/sdp");
+    assert_eq!(post.headers.get("Authorization").unwrap(), "Bearer secret");
+
+    // The offer is only sent once all candidates are in it
+    let offer = gst_sdp::SDPMessage::parse_buffer(post.body.as_bytes()).unwrap();
+    let media = offer.media(0).unwrap();
+    assert!(media.attributes().any(|attr| attr.key() == "candidate"));
+}
+
+#[test]
+fn test_extra_headers() {
+    let h = harness(|_req| created_response());
+    h.signaller.set_property("auth-token", "secret");
+    h.signaller.set_property(
+        "extra-headers",
+        gst::Structure::builder("headers")
+            .field("X-Tenant", "acme")
+            .field("Authorization", "ignored")
+            .build(),
+    );
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    h.signaller.end_session("unique");
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 2);
+
+    for request in &requests {
+        assert_eq!(request.headers.get("X-Tenant").unwrap(), "acme");
+        // Our token wins over the extra headers
+        assert_eq!(
+            request.headers.get("Authorization").unwrap(),
+            "Bearer secret"
+        );
+    }
+}
+
+#[test]
+fn test_proxy() {
+    let h = harness(|_req| created_response());
+
+    // The mock endpoint plays the proxy, requests for the unresolvable
+    // endpoint can only reach it through the proxy
+    let endpoint = h.signaller.property::<String>("whip-endpoint");
+    let proxy = endpoint.trim_end_matches("/whip/endpoint");
+    h.signaller.set_property("proxy", proxy);
+    h.signaller
+        .set_property("whip-endpoint", "http://whip.invalid/whip/endpoint");
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/whip/endpoint");
+    assert_eq!(requests[0].headers.get("Host").unwrap(), "whip.invalid");
+}
+
+#[test]
+fn test_invalid_proxy() {
+    let h = harness(|_req| created_response());
+
+    h.signaller.set_property("proxy", "ftp://proxy.example.com");
+    assert!(h.signaller.property::<Option<String>>("proxy").is_none());
+}
+
+#[test]
+fn test_no_auth_token() {
+    let h = harness(|_req| created_response());
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].headers.get("Authorization").is_none());
+}
+
+#[test]
+fn test_delete_on_end_session() {
+    let h = harness(|_req| created_response());
+    h.signaller.set_property("auth-token", "secret");
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    h.signaller.end_session("unique");
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 2);
+
+    // The resource URL is relative to the endpoint
+    let delete = &requests[1];
+    assert_eq!(delete.method, hyper::Method::DELETE);
+    assert_eq!(delete.path, "/whip/resource/1");
+    assert_eq!(
+        delete.headers.get("Authorization").unwrap(),
+        "Bearer secret"
+    );
+}
+
+#[test]
+fn test_link_headers() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(201)
+            .header("Location", "/whip/resource/1")
+            .header("Link", r#"<stun:stun.example.com:3478>; rel="ice-server""#)
+            .header(
+                "Link",
+                r#"<turn:turn.example.com:3478?transport=udp>; rel="ice-server"; username="user"; credential="pass"; credential-type="password""#,
+            )
+            .body(ANSWER.into())
+            .unwrap()
+    });
+    h.signaller.set_property("use-link-headers", true);
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    assert_eq!(
+        h.webrtcbin
+            .property::<Option<String>>("stun-server")
+            .as_deref(),
+        Some("stun://stun.example.com:3478")
+    );
+}
+
+#[test]
+fn test_link_headers_before_gathering() {
+    let h = harness(|req| {
+        if req.method == hyper::Method::OPTIONS {
+            hyper::Response::builder()
+                .status(204)
+                .header("Link", r#"<stun:stun.example.com:3478>; rel="ice-server""#)
+                .body(hyper::Body::empty())
+                .unwrap()
+        } else {
+            created_response()
+        }
+    });
+    h.signaller.set_property("use-link-headers", true);
+    h.signaller.set_property("auth-token", "secret");
+
+    h.signaller.start();
+    h.signaller
+        .emit_by_name::<()>("consumer-added", &[&"unique", &h.webrtcbin]);
+
+    // The ICE servers are configured by the time webrtcsink negotiates
+    assert_eq!(
+        h.webrtcbin
+            .property::<Option<String>>("stun-server")
+            .as_deref(),
+        Some("stun://stun.example.com:3478")
+    );
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, hyper::Method::OPTIONS);
+    assert_eq!(requests[0].path, "/whip/endpoint");
+    assert_eq!(
+        requests[0].headers.get("Authorization").unwrap(),
+        "Bearer secret"
+    );
+}
+
+#[test]
+fn test_link_headers_options_unsupported() {
+    let h = harness(|req| {
+        if req.method == hyper::Method::OPTIONS {
+            hyper::Response::builder()
+                .status(405)
+                .body(hyper::Body::empty())
+                .unwrap()
+        } else {
+            created_response()
+        }
+    });
+    h.signaller.set_property("use-link-headers", true);
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].method, hyper::Method::POST);
+}
+
+#[test]
+fn test_link_headers_ignored() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(201)
+            .header("Location", "/whip/resource/1")
+            .header("Link", r#"<stun:stun.example.com:3478>; rel="ice-server""#)
+            .body(ANSWER.into())
+            .unwrap()
+    });
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    assert_eq!(h.webrtcbin.property::<Option<String>>("stun-server"), None);
+}
+
+#[test]
+fn test_redirect() {
+    let h = harness(|req| match req.path.as_str() {
+        "/whip/endpoint" => hyper::Response::builder()
+            .status(307)
+            .header("Location", "/whip/redirected")
+            .body(hyper::Body::empty())
+            .unwrap(),
+        _ => created_response(),
+    });
+    h.signaller.set_property("auth-token", "secret");
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "/whip/endpoint");
+
+    // The offer and the token follow the redirection
+    assert_eq!(requests[1].method, hyper::Method::POST);
+    assert_eq!(requests[1].path, "/whip/redirected");
+    assert_eq!(requests[1].body, requests[0].body);
+    assert_eq!(
+        requests[1].headers.get("Authorization").unwrap(),
+        "Bearer secret"
+    );
+
+    h.signaller.end_session("unique");
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].method, hyper::Method::DELETE);
+    assert_eq!(requests[2].path, "/whip/resource/1");
+}
+
+#[test]
+fn test_too_many_redirects() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(307)
+            .header("Location", "/whip/endpoint")
+            .body(hyper::Body::empty())
+            .unwrap()
+    });
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Too many redirects"), "{err}");
+
+    // The initial request and all the redirections
+    assert_eq!(h.requests().len(), 11);
+}
+
+#[test]
+fn test_missing_location() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(201)
+            .body(ANSWER.into())
+            .unwrap()
+    });
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Location header"), "{err}");
+}
+
+#[test]
+fn test_invalid_answer() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(201)
+            .header("Location", "/whip/resource/1")
+            .body("not an sdp".into())
+            .unwrap()
+    });
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Could not parse answer SDP"), "{err}");
+}
+
+#[test]
+fn test_server_error() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(503)
+            .header("Retry-After", "1")
+            .body("overloaded".into())
+            .unwrap()
+    });
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(
+        err.contains("Unexpected response: 503 - overloaded"),
+        "{err}"
+    );
+
+    // No retry
+    assert_eq!(h.requests().len(), 1);
+}
+
+fn unauthorized_unless(token: &'static str) -> impl Fn(&Request) -> hyper::Response<hyper::Body> {
+    move |req| {
+        if req.headers.get("Authorization").unwrap() == token {
+            created_response()
+        } else {
+            hyper::Response::builder()
+                .status(401)
+                .body("expired".into())
+                .unwrap()
+        }
+    }
+}
+
+#[test]
+fn test_request_auth_token() {
+    let h = harness(unauthorized_unless("Bearer requested"));
+    h.signaller.set_property("auth-token", "secret");
+    h.signaller.connect("request-auth-token", false, |_| {
+        Some("requested".to_value())
+    });
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    // The token of the application takes precedence over the property
+    assert_eq!(h.requests().len(), 1);
+}
+
+#[test]
+fn test_refresh_auth_token() {
+    let h = harness(unauthorized_unless("Bearer fresh"));
+    h.signaller.set_property("auth-token", "stale");
+    h.signaller.connect("request-auth-token", false, |args| {
+        let refresh = args[1].get::<bool>().unwrap();
+        Some(refresh.then_some("fresh").to_value())
+    });
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    let requests = h.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[0].headers.get("Authorization").unwrap(),
+        "Bearer stale"
+    );
+    assert_eq!(
+        requests[1].headers.get("Authorization").unwrap(),
+        "Bearer fresh"
+    );
+}
+
+#[test]
+fn test_unauthorized_without_refresh() {
+    let h = harness(unauthorized_unless("Bearer fresh"));
+    h.signaller.set_property("auth-token", "stale");
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Unauthorized"), "{err}");
+
+    // Retried at most once
+    assert_eq!(h.requests().len(), 1);
+}
+
+#[test]
+fn test_retry_server_error() {
+    let attempts = Arc::new(Mutex::new(0));
+    let h = harness({
+        let attempts = attempts.clone();
+        move |_req| {
+            let mut attempts = attempts.lock().unwrap();
+            *attempts += 1;
+
+            if *attempts < 3 {
+                hyper::Response::builder()
+                    .status(503)
+                    .body("overloaded".into())
+                    .unwrap()
+            } else {
+                created_response()
+            }
+        }
+    });
+    h.signaller.set_property("max-retries", 5u32);
+    h.signaller.set_property("retry-initial-delay", 10u32);
+
+    let retries = Arc::new(Mutex::new(Vec::new()));
+    h.signaller.connect("retrying", false, {
+        let retries = retries.clone();
+        move |args| {
+            let attempt = args[1].get::<u32>().unwrap();
+            let delay = args[2].get::<u64>().unwrap();
+            let reason = args[3].get::<String>().unwrap();
+            retries.lock().unwrap().push((attempt, delay, reason));
+            None
+        }
+    });
+
+    h.send_offer();
+    h.wait_for_answer();
+
+    assert_eq!(h.requests().len(), 3);
+
+    let retries = retries.lock().unwrap();
+    assert_eq!(retries.len(), 2);
+    assert_eq!(retries[0].0, 1);
+    assert_eq!(retries[1].0, 2);
+    assert!((5..=10).contains(&retries[0].1), "{:?}", retries[0]);
+    assert!((10..=20).contains(&retries[1].1), "{:?}", retries[1]);
+    assert!(retries[0].2.contains("503"), "{:?}", retries[0]);
+}
+
+#[test]
+fn test_retries_exhausted() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(500)
+            .body("broken".into())
+            .unwrap()
+    });
+    h.signaller.set_property("max-retries", 2u32);
+    h.signaller.set_property("retry-initial-delay", 10u32);
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Unexpected response: 500 - broken"), "{err}");
+    assert_eq!(h.requests().len(), 3);
+}
+
+#[test]
+fn test_no_retry_client_error() {
+    let h = harness(|_req| {
+        hyper::Response::builder()
+            .status(400)
+            .body("bad offer".into())
+            .unwrap()
+    });
+    h.signaller.set_property("max-retries", 2u32);
+
+    h.send_offer();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("Unexpected response: 400"), "{err}");
+    assert_eq!(h.requests().len(), 1);
+}
+
+#[test]
+fn test_no_endpoint() {
+    let h = harness(|_req| created_response());
+    h.signaller.set_property("whip-endpoint", None::<String>);
+
+    h.signaller.start();
+
+    let err = h.wait_for_error();
+    assert!(err.contains("WHIP endpoint URL must be set"), "{err}");
+    assert!(h.requests().is_empty());
+}